@@ -0,0 +1,208 @@
+//! Sign-in activity per user (logins, PIN logins, logouts and failed attempts) and the
+//! day-by-day sales work behind the "who did what" view.
+
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use std::collections::BTreeMap;
+
+pub const EVENT_LOGIN: &str = "login";
+pub const EVENT_PIN_LOGIN: &str = "pin_login";
+pub const EVENT_LOGOUT: &str = "logout";
+pub const EVENT_LOGIN_FAILED: &str = "login_failed";
+
+/// Roles that may look at other users' activity
+pub const ACTIVITY_VIEWER_ROLES: &[&str] = &["Admin", "Manager"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UserActivity {
+    pub id: i64,
+    /// Missing for failed attempts against an unknown username
+    pub user_id: Option<i64>,
+    /// As typed at sign-in
+    pub username: String,
+    pub event_type: String,
+    pub location_id: Option<i64>,
+    pub created_at: String,
+}
+
+/// A user's sales work on one day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailyActivitySummary {
+    pub date: String,
+    /// Sales rung up and not voided
+    pub sales_count: i64,
+    pub sales_value: Money,
+    /// Sales this user voided
+    pub voids: i64,
+}
+
+/// Record a sign-in event. Best-effort: a failure here never blocks signing in or out.
+pub async fn record_activity(
+    pool: &SqlitePool,
+    user_id: Option<i64>,
+    username: &str,
+    event_type: &str,
+    location_id: Option<i64>,
+) {
+    let result = sqlx::query(
+        "INSERT INTO user_activity (user_id, username, event_type, location_id)
+         VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(user_id)
+    .bind(username.trim())
+    .bind(event_type)
+    .bind(location_id)
+    .execute(pool)
+    .await;
+    if let Err(e) = result {
+        eprintln!("Failed to record {} activity for {}: {}", event_type, username, e);
+    }
+}
+
+/// Fail unless a user with `caller_role` may see the activity of `user_id`: their own
+/// always, anyone else's only as Admin or Manager
+pub fn require_activity_access(caller_id: i64, caller_role: &str, user_id: i64) -> AppResult<()> {
+    if caller_id == user_id || ACTIVITY_VIEWER_ROLES.contains(&caller_role) {
+        Ok(())
+    } else {
+        Err(AppError::PermissionDenied {
+            message: "Only an admin or manager can view another user's activity".to_string(),
+        })
+    }
+}
+
+/// Sign-in events of a user between two dates (inclusive), newest first
+pub async fn user_activity(
+    pool: &SqlitePool,
+    user_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> AppResult<Vec<UserActivity>> {
+    Ok(sqlx::query_as::<_, UserActivity>(
+        "SELECT id, user_id, username, event_type, location_id, created_at
+         FROM user_activity
+         WHERE user_id = ?1 AND DATE(created_at) BETWEEN ?2 AND ?3
+         ORDER BY created_at DESC, id DESC",
+    )
+    .bind(user_id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Per-day sales, sales value and voids of a user between two dates (inclusive), oldest
+/// day first. Days without any of them are left out.
+pub async fn daily_summary(
+    pool: &SqlitePool,
+    user_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> AppResult<Vec<DailyActivitySummary>> {
+    let sales: Vec<(String, i64, Money)> = sqlx::query_as(
+        "SELECT DATE(created_at), COUNT(*), COALESCE(SUM(total_amount), 0.0)
+         FROM sales
+         WHERE cashier_id = ?1 AND is_voided = 0 AND DATE(created_at) BETWEEN ?2 AND ?3
+         GROUP BY DATE(created_at)",
+    )
+    .bind(user_id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+    let voids: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT DATE(voided_at), COUNT(*)
+         FROM sales
+         WHERE voided_by = ?1 AND is_voided = 1 AND DATE(voided_at) BETWEEN ?2 AND ?3
+         GROUP BY DATE(voided_at)",
+    )
+    .bind(user_id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+
+    let mut days: BTreeMap<String, DailyActivitySummary> = BTreeMap::new();
+    for (date, count, value) in sales {
+        let entry = days.entry(date.clone()).or_insert_with(|| DailyActivitySummary {
+            date,
+            ..Default::default()
+        });
+        entry.sales_count = count;
+        entry.sales_value = value;
+    }
+    for (date, count) in voids {
+        days.entry(date.clone())
+            .or_insert_with(|| DailyActivitySummary {
+                date,
+                ..Default::default()
+            })
+            .voids = count;
+    }
+    Ok(days.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_user, test_pool};
+
+    async fn sale(pool: &SqlitePool, number: &str, cashier: i64, total: f64, created_at: &str) -> i64 {
+        sqlx::query_scalar(
+            "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id, created_at)
+             VALUES (?1, ?2, ?2, 'cash', ?3, ?4) RETURNING id",
+        )
+        .bind(number)
+        .bind(total)
+        .bind(cashier)
+        .bind(created_at)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_daily_summary_counts_sales_value_and_voids_per_day() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier2").await;
+        let manager = insert_test_user(&pool, "manager").await;
+        sale(&pool, "S-1", cashier, 10.25, "2026-03-02 07:58:00").await;
+        sale(&pool, "S-2", cashier, 4.75, "2026-03-02 12:00:00").await;
+        let voided = sale(&pool, "S-3", cashier, 99.0, "2026-03-02 13:00:00").await;
+        sale(&pool, "S-4", cashier, 3.0, "2026-03-03 09:00:00").await;
+        sale(&pool, "S-5", cashier, 50.0, "2026-03-05 09:00:00").await;
+        sqlx::query("UPDATE sales SET is_voided = 1, voided_by = ?1, voided_at = '2026-03-03 08:00:00' WHERE id = ?2")
+            .bind(manager)
+            .bind(voided)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let days = daily_summary(&pool, cashier, "2026-03-01", "2026-03-04").await.unwrap();
+        let totals: Vec<_> = days
+            .iter()
+            .map(|d| (d.date.as_str(), d.sales_count, d.sales_value, d.voids))
+            .collect();
+        assert_eq!(
+            totals,
+            vec![
+                ("2026-03-02", 2, Money::from_major(15.0), 0),
+                ("2026-03-03", 1, Money::from_major(3.0), 0),
+            ]
+        );
+        let days = daily_summary(&pool, manager, "2026-03-01", "2026-03-04").await.unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!((days[0].date.as_str(), days[0].sales_count, days[0].voids), ("2026-03-03", 0, 1));
+    }
+
+    #[test]
+    fn test_only_admins_and_managers_see_other_users_activity() {
+        assert!(require_activity_access(7, "Cashier", 7).is_ok());
+        assert!(require_activity_access(1, "Manager", 7).is_ok());
+        assert!(require_activity_access(1, "Admin", 7).is_ok());
+        let err = require_activity_access(8, "Cashier", 7).unwrap_err();
+        assert_eq!(err.code(), "PERMISSION_DENIED");
+    }
+}
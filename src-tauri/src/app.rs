@@ -1,10 +1,10 @@
 // src-tauri/src/app.rs
 
-use crate::{commands, database, seeder_building_materials as seeder};
+use crate::{backup, commands, database, rest_api, webhooks};
 use bcrypt::{hash, verify, DEFAULT_COST};
-use database::get_migrations;
+use database::{apply_migrations, check_schema_version, connect_options};
 use log::LevelFilter;
-use sqlx::{sqlite::SqlitePoolOptions, Executor, Row, SqlitePool};
+use sqlx::{sqlite::SqlitePoolOptions, Row, SqlitePool};
 use std::fs::OpenOptions;
 use std::io::Write;
 use tauri::Manager;
@@ -36,72 +36,184 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
                     }
                 }
             });
+
+            // Background notification checks so alerts appear without the frontend polling
+            let pool = app.state::<SqlitePool>().inner().clone();
+            let scheduler = commands::notifications::spawn_notification_runner(
+                pool,
+                notification_check_interval(),
+            );
+            app.manage(scheduler);
+            app.manage(commands::dashboard::DashboardCache::default());
+            app.manage(commands::customer_display::CustomerDisplay::default());
+
+            // Automatic backups into the app data dir on the store's schedule, oldest
+            // rotated out
+            match app.path().app_data_dir() {
+                Ok(app_data_dir) => backup::spawn_backup_runner(
+                    app.state::<SqlitePool>().inner().clone(),
+                    backup::backup_dir(&app_data_dir),
+                    backups_to_keep(),
+                ),
+                Err(e) => eprintln!("⚠️  Warning: automatic backups disabled: {}", e),
+            }
+
+            // Post queued webhook deliveries in the background, retrying failures with backoff
+            webhooks::spawn_delivery_worker(app.state::<SqlitePool>().inner().clone());
+
+            // Opt-in LAN API so other tills can read stock and post sales through this one
+            if let Some(port) = rest_api_port() {
+                let pool = app.state::<SqlitePool>().inner().clone();
+                let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
+                match tauri::async_runtime::block_on(rest_api::start(pool, addr)) {
+                    Ok(server) => {
+                        println!("✅ DEBUG(main): REST API listening on {}", server.local_addr());
+                        app.manage(server);
+                    }
+                    Err(e) => eprintln!("⚠️  Warning: REST API disabled: {}", e),
+                }
+            }
+
             Ok(())
         })
         // Command handlers
         .invoke_handler(tauri::generate_handler![
             commands::auth::login_user,
+            commands::auth::login_with_pin,
+            commands::auth::reset_password_with_code,
             commands::auth::register_user,
             commands::auth::verify_session,
             commands::auth::logout_user,
+            commands::auth::switch_location,
+            commands::auth::switch_organization,
+            commands::members::invite_user_to_organization,
+            commands::members::accept_invitation,
+            commands::members::update_member_role,
+            commands::members::remove_member,
+            commands::members::get_organization_members,
+            commands::members::get_organization_invitations,
             commands::auth::get_session_user,
+            commands::backup::backup_database,
+            commands::backup::restore_database,
+            commands::backup::list_backups,
+            commands::backup::export_data_archive,
+            commands::backup::import_data_archive,
+            commands::backup::database_integrity_report,
+            commands::backup::system_diagnostics,
+            commands::backup::seed_demo_data,
+            commands::backup::reset_demo_data,
             commands::users::get_users,
             commands::users::create_user,
             commands::users::update_user,
             commands::users::delete_user,
             commands::users::update_user_profile,
             commands::users::change_user_password,
+            commands::users::unlock_user,
+            commands::users::get_user_activity,
+            commands::users::get_active_sessions,
+            commands::users::get_audit_log,
+            commands::users::get_fraud_indicators,
+            commands::users::set_user_pin,
+            commands::users::generate_password_reset_code,
             commands::products::get_products,
             commands::products::get_products_with_stock,
             commands::products::get_product_by_id,
+            commands::products::get_products_by_ids,
+            commands::products::get_cost_history,
+            commands::products::recalculate_average_costs,
+            commands::labels::generate_barcode_image,
+            commands::labels::assign_product_barcode,
+            commands::labels::generate_labels_pdf,
             commands::products::create_product,
             commands::products::update_product,
+            commands::products::bulk_update_prices,
             commands::products::delete_product,
-            commands::products::reactivate_product,
+            commands::products::archive_product,
+            commands::products::restore_product,
             commands::products::search_products,
             commands::products::get_product_by_barcode,
             commands::inventory::sync_inventory,
             commands::inventory::get_inventory,
             commands::inventory::update_stock,
             commands::inventory::get_inventory_movements,
+            commands::inventory::get_movement_summary,
             commands::inventory::create_stock_adjustment,
             commands::inventory::get_low_stock_items,
+            commands::inventory::get_product_lots,
+            commands::inventory::get_expiring_lots,
+            commands::inventory::set_product_lot_tracking,
             commands::sales::create_sale,
+            commands::sales::validate_sale_margins,
+            commands::sales::create_sale_offline,
+            commands::sales::process_pending_sales,
             commands::sales::get_sales,
+            commands::search::global_search,
             commands::sales::get_sales_with_details,
             commands::sales::get_sales_stats,
             commands::sales::void_sale,
             commands::sales::get_sale_details,
             commands::sales::search_sales,
+            commands::parked_sales::park_sale,
+            commands::parked_sales::list_parked_sales,
+            commands::parked_sales::resume_parked_sale,
+            commands::price_overrides::get_price_override_report,
             commands::returns::create_return,
             commands::returns::get_returns,
             commands::returns::get_return_by_id,
             commands::returns::get_return_items,
             commands::returns::get_sale_for_return,
+            commands::returns::get_returns_analytics,
+            commands::returns::export_return_lines_csv,
             commands::returns::get_returns_count,
+            commands::returns::get_return_risk_report,
             commands::store::get_store_config,
             commands::store::update_store_config,
+            commands::store::get_currencies,
+            commands::tax_rules::get_tax_rules,
+            commands::tax_rules::create_tax_rule,
+            commands::tax_rules::update_tax_rule,
+            commands::tax_rules::set_tax_rule_active,
+            commands::tax_rules::get_tax_report_by_rule,
+            commands::tax_rules::get_tax_report,
+            commands::exchange_rates::get_exchange_rates,
+            commands::exchange_rates::create_exchange_rate,
+            commands::exchange_rates::update_exchange_rate,
+            commands::exchange_rates::delete_exchange_rate,
+            commands::exchange_rates::calculate_change_due,
+            commands::exchange_rates::get_sale_payments,
+            commands::store::format_money,
+            commands::store::get_document_sequences,
+            commands::store::update_document_sequence,
             commands::store::upload_store_logo,
             commands::store::remove_store_logo,
             commands::shifts::create_shift,
             commands::shifts::close_shift,
             commands::shifts::get_current_shift,
             commands::shifts::get_shift_history,
+            commands::z_reports::get_z_report,
+            commands::z_reports::render_z_report,
             commands::cash_drawer::create_transaction,
             commands::cash_drawer::get_transactions,
             commands::cash_drawer::get_cash_drawer_balance,
+            commands::cash_drawer::open_cash_drawer,
             commands::customers::get_customers,
             commands::customers::get_customer,
             commands::customers::create_customer,
             commands::customers::update_customer,
+            commands::customers::archive_customer,
+            commands::customers::restore_customer,
             commands::customers::delete_customer,
             commands::customers::search_customers,
             commands::suppliers::get_suppliers,
             commands::suppliers::get_supplier,
             commands::suppliers::create_supplier,
             commands::suppliers::update_supplier,
+            commands::suppliers::archive_supplier,
+            commands::suppliers::restore_supplier,
             commands::suppliers::delete_supplier,
             commands::suppliers::search_suppliers,
+            commands::supplier_credits::get_open_supplier_credits,
+            commands::supplier_credits::apply_supplier_credit,
             commands::purchase_orders::get_purchase_orders,
             commands::purchase_orders::get_purchase_order,
             commands::purchase_orders::get_purchase_order_items,
@@ -114,19 +226,44 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             commands::expenses::create_expense,
             commands::expenses::update_expense,
             commands::expenses::delete_expense,
-            commands::receipts::get_templates,
-            commands::receipts::create_template,
-            commands::receipts::update_template,
-            commands::receipts::delete_template,
-            commands::receipts::get_default_template,
+            commands::receipt_footers::get_receipt_footer_rules,
+            commands::receipt_footers::create_receipt_footer_rule,
+            commands::receipt_footers::update_receipt_footer_rule,
+            commands::receipt_footers::set_receipt_footer_rule_active,
+            commands::receipt_footers::delete_receipt_footer_rule,
+            commands::receipt_footers::get_issued_coupons,
+            commands::receipt_templates::get_templates,
+            commands::receipt_templates::create_template,
+            commands::receipt_templates::update_template,
+            commands::receipt_templates::set_default_template,
+            commands::receipt_templates::delete_template,
+            commands::receipt_templates::get_default_template,
+            commands::receipt_templates::validate_receipt_template,
+            commands::receipt_templates::preview_receipt_template,
+            commands::receipts::render_receipt,
+            commands::receipts::print_receipt,
+            commands::receipts::render_pick_ticket,
+            commands::reorder::get_reorder_report,
+            commands::reorder::export_reorder_report_csv,
+            commands::reorder::set_reorder_policy,
+            commands::reorder::compute_suggested_reorder_points,
+            commands::reorder::get_stock_coverage,
             commands::dashboard::get_stats,
+            commands::dashboard::get_dashboard_stats,
             commands::dashboard::get_recent_activity,
             commands::reports::get_sales_report,
             commands::reports::get_product_performance,
             commands::reports::get_daily_sales,
+            commands::reports::get_inventory_valuation,
+            commands::reports::get_dead_stock,
             commands::reports::get_category_performance,
             commands::reports::get_financial_metrics,
             commands::reports::get_cash_flow_summary,
+            commands::reports::get_receivables_aging,
+            commands::reports::get_payables_aging,
+            commands::reports::get_employee_sales_performance,
+            commands::reports::get_commission_report,
+            commands::reports::get_void_analytics,
             commands::notifications::get_notifications,
             commands::notifications::get_notification_stats,
             commands::notifications::mark_notification_read,
@@ -137,7 +274,14 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             commands::notifications::check_pending_invoices,
             commands::notifications::check_outstanding_debts,
             commands::notifications::refresh_notifications,
+            commands::notifications::check_expiring_promotions,
+            commands::notifications::check_expiring_lots,
+            commands::notifications::check_todays_appointments,
+            commands::notifications::run_all_notification_checks,
+            commands::notifications::trigger_notification_checks,
             commands::notifications::delete_notification,
+            commands::notifications::delete_read_notifications,
+            commands::notifications::archive_read_notifications,
             commands::master_data::get_categories,
             commands::master_data::get_all_categories,
             commands::master_data::create_category,
@@ -155,9 +299,27 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             commands::master_data::delete_unit,
             commands::stock::receive_stock,
             commands::stock::adjust_stock,
+            commands::stock::approve_stock_adjustment,
+            commands::stock::reject_stock_adjustment,
+            commands::stock::get_stock_adjustments,
+            commands::stock::get_adjustment_reasons,
+            commands::stock::get_stock_by_location,
+            commands::stock::set_location_reorder_levels,
+            commands::stock::save_adjustment_reason,
             commands::stock::reserve_stock,
             commands::stock::release_reserved_stock,
             commands::stock::stock_take,
+            commands::writeoffs::record_stock_writeoff,
+            commands::writeoffs::approve_stock_writeoff,
+            commands::writeoffs::reject_stock_writeoff,
+            commands::writeoffs::get_stock_writeoffs,
+            commands::writeoffs::get_writeoff_report,
+            commands::transfers::create_transfer,
+            commands::transfers::ship_transfer,
+            commands::transfers::receive_transfer,
+            commands::transfers::get_transfers,
+            commands::transfers::get_transfer_items,
+            commands::transfers::get_in_transit_stock,
             commands::variants::get_all_variant_types,
             commands::variants::get_variant_type,
             commands::variants::create_variant_type,
@@ -191,6 +353,14 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             commands::organization::get_organization,
             commands::organization::create_organization,
             commands::organization::update_organization,
+            commands::organization::get_plan_usage,
+            commands::organization::get_organization_settings,
+            commands::organization::update_organization_settings,
+            commands::settings::get_settings,
+            commands::settings::update_settings,
+            commands::settings::get_demo_mode,
+            commands::settings::set_demo_mode,
+            commands::settings::purge_demo_data,
             commands::organization::get_locations,
             commands::organization::create_location,
             commands::organization::update_location,
@@ -202,6 +372,20 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             commands::promotions::update_promotion,
             commands::promotions::delete_promotion,
             commands::promotions::validate_promotion,
+            commands::promotions::redeem_promotion,
+            commands::promotions::evaluate_promotions,
+            commands::campaigns::get_campaign_performance,
+            commands::campaigns::get_campaign_roi_report,
+            commands::discounts::get_discount_rules,
+            commands::discounts::create_discount_rule,
+            commands::discounts::update_discount_rule,
+            commands::discounts::set_discount_rule_active,
+            commands::discounts::compute_auto_discounts,
+            commands::customer_display::cart_updated,
+            commands::customer_display::payment_started,
+            commands::customer_display::sale_completed,
+            commands::customer_display::get_customer_display_state,
+            commands::customer_display::open_customer_display_window,
             commands::appointments::get_appointments,
             commands::appointments::get_appointment,
             commands::appointments::create_appointment,
@@ -213,16 +397,32 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             commands::sync::check_sync_status,
             commands::sync::sync_single_record,
             commands::sync::delete_local_record,
+            commands::sync::get_pending_sync_batch,
+            commands::sync::mark_synced,
+            commands::sync::mark_sync_error,
+            commands::sync::get_sync_conflicts,
+            commands::sync::resolve_sync_conflict,
             commands::integrations::get_integrations,
             commands::integrations::get_integration,
             commands::integrations::create_integration,
             commands::integrations::update_integration,
             commands::integrations::delete_integration,
             commands::integrations::test_integration,
+            commands::integrations::get_webhooks,
+            commands::integrations::create_webhook,
+            commands::integrations::update_webhook,
+            commands::integrations::delete_webhook,
+            commands::integrations::test_webhook,
+            commands::integrations::get_webhook_deliveries,
+            commands::integrations::get_api_keys,
+            commands::integrations::create_api_key,
+            commands::integrations::revoke_api_key,
             commands::returns::create_return,
             commands::returns::get_returns,
             commands::returns::get_return_items,
             commands::returns::approve_return,
+            commands::returns::process_return,
+            commands::returns::void_return,
             commands::returns::complete_return,
             commands::returns::create_return_offline,
             commands::returns::sync_return_from_supabase,
@@ -230,39 +430,14 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             commands::returns::mark_return_as_synced,
             commands::returns::mark_return_as_error,
         ])
-        .run(tauri::generate_context!())?;
-    Ok(())
-}
-
-/// Apply migrations (runs all migration SQL statements)
-async fn apply_migrations(pool: &SqlitePool) -> Result<(), String> {
-    let migrations = get_migrations();
-    println!("DEBUG(main): applying {} migration(s)", migrations.len());
-
-    for mig in migrations {
-        println!(
-            "DEBUG(main): applying migration version {}: {}",
-            mig.version, mig.description
-        );
-
-        for stmt in mig.sql.split(';') {
-            let s = stmt.trim();
-            if s.is_empty() {
-                continue;
+        .build(tauri::generate_context!())?
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Some(server) = app_handle.try_state::<rest_api::RestApiServer>() {
+                    tauri::async_runtime::block_on(server.shutdown());
+                }
             }
-            let preview = if s.len() > 80 { &s[..80] } else { s };
-            println!("DEBUG(main): executing statement (preview): {}", preview);
-
-            pool.execute(s).await.map_err(|e| {
-                format!(
-                    "Migration failed (v{}): {} -- stmt: {}",
-                    mig.version, e, preview
-                )
-            })?;
-        }
-    }
-
-    println!("✅ DEBUG(main): migrations applied successfully");
+        });
     Ok(())
 }
 
@@ -322,6 +497,35 @@ async fn ensure_admin(pool: &SqlitePool) -> Result<(), String> {
     }
 }
 
+/// Interval for background notification checks.
+/// Override with POS_NOTIFICATION_INTERVAL_MINUTES (whole minutes, at least 1).
+fn notification_check_interval() -> std::time::Duration {
+    std::env::var("POS_NOTIFICATION_INTERVAL_MINUTES")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .map(|minutes| std::time::Duration::from_secs(minutes * 60))
+        .unwrap_or(commands::notifications::DEFAULT_NOTIFICATION_CHECK_INTERVAL)
+}
+
+/// Automatic backups kept before the oldest are deleted.
+/// Override with POS_BACKUPS_TO_KEEP (at least 1).
+/// Port for the LAN REST API from `POS_REST_API_PORT`; unset keeps the API off
+fn rest_api_port() -> Option<u16> {
+    std::env::var("POS_REST_API_PORT")
+        .ok()
+        .and_then(|v| v.trim().parse::<u16>().ok())
+        .filter(|port| *port > 0)
+}
+
+fn backups_to_keep() -> usize {
+    std::env::var("POS_BACKUPS_TO_KEEP")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|keep| *keep > 0)
+        .unwrap_or(backup::DEFAULT_BACKUPS_TO_KEEP)
+}
+
 /// Initialize database with proper cross-platform path handling
 async fn initialize_database(
     app_handle: &tauri::AppHandle,
@@ -344,7 +548,7 @@ async fn initialize_database(
         )
     })?;
 
-    let db_path = app_data_dir.join("pos.db");
+    let db_path = backup::database_path(&app_data_dir);
 
     // Create database file if it doesn't exist
     if !db_path.exists() {
@@ -389,12 +593,15 @@ async fn initialize_database(
         .acquire_timeout(std::time::Duration::from_secs(10))
         .idle_timeout(std::time::Duration::from_secs(300))
         .max_lifetime(std::time::Duration::from_secs(1800))
-        .connect(&conn_str)
+        .connect_with(connect_options(&conn_str)?)
         .await
         .map_err(|e| format!("Failed to create SqlitePool for '{}': {}", conn_str, e))?;
 
     println!("✅ DEBUG(main): Database connection pool created");
 
+    // Refuse a schema written by a newer build before migrating anything
+    check_schema_version(&pool).await?;
+
     // Apply migrations
     apply_migrations(&pool)
         .await
@@ -405,13 +612,7 @@ async fn initialize_database(
         .await
         .map_err(|e| format!("Failed to ensure admin user: {}", e))?;
 
-    // Seed database with initial data (DISABLED)
-    // println!("DEBUG(main): Starting database seeding...");
-    // if let Err(e) = seeder::seed_database(&pool).await {
-    //     eprintln!("⚠️  Warning: Failed to seed database: {}", e);
-    // } else {
-    //     println!("✅ DEBUG(main): Database seeded successfully");
-    // }
+    // Demo data is never seeded at startup; see the seed_demo_data command
 
     println!("✅ DEBUG(main): Database initialization complete");
     Ok(pool)
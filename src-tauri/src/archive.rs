@@ -0,0 +1,823 @@
+use crate::database::{apply_migrations, schema_version};
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::query::Query;
+use sqlx::sqlite::{Sqlite, SqliteArguments, SqliteConnection};
+use sqlx::{Connection, SqlitePool};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+pub const ARCHIVE_FORMAT: &str = "qorbooks-archive";
+const ARCHIVE_ENTRY: &str = "archive.json";
+
+/// Business tables carried in an archive, parents before children
+const ARCHIVE_TABLES: &[&str] = &[
+    "locations",
+    "users",
+    "categories",
+    "brands",
+    "units",
+    "exchange_rates",
+    "tax_rules",
+    "suppliers",
+    "customers",
+    "products",
+    "inventory",
+    "product_lots",
+    "shifts",
+    "sales",
+    "sale_items",
+    "sale_item_lots",
+    "sale_payments",
+    "sale_item_taxes",
+    "inventory_movements",
+    "cash_drawer_transactions",
+    "returns",
+    "return_items",
+    "comprehensive_returns",
+    "comprehensive_return_items",
+    "purchase_orders",
+    "purchase_order_items",
+    "expense_categories",
+    "expenses",
+    "promotions",
+];
+
+/// Rows of `table` that belong to organization `?1`. Tables without a filter are shared by
+/// every organization, as they are in the rest of the app, and travel whole.
+fn organization_filter(table: &str) -> Option<&'static str> {
+    Some(match table {
+        "locations" | "suppliers" | "customers" | "products" | "inventory" | "sales"
+        | "comprehensive_returns" | "purchase_orders" => "organization_id = ?1",
+        // Members, plus the cashiers of its sales who may predate memberships
+        "users" => {
+            "id IN (SELECT user_id FROM organization_users WHERE organization_id = ?1)
+             OR id IN (SELECT cashier_id FROM sales WHERE organization_id = ?1)"
+        }
+        "product_lots" | "inventory_movements" => {
+            "product_id IN (SELECT id FROM products WHERE organization_id = ?1)"
+        }
+        "sale_items" | "sale_payments" => {
+            "sale_id IN (SELECT id FROM sales WHERE organization_id = ?1)"
+        }
+        "sale_item_lots" | "sale_item_taxes" => {
+            "sale_item_id IN (SELECT si.id FROM sale_items si
+                              JOIN sales s ON s.id = si.sale_id
+                              WHERE s.organization_id = ?1)"
+        }
+        "returns" => "original_sale_id IN (SELECT id FROM sales WHERE organization_id = ?1)",
+        "return_items" => {
+            "return_id IN (SELECT r.id FROM returns r
+                           JOIN sales s ON s.id = r.original_sale_id
+                           WHERE s.organization_id = ?1)"
+        }
+        "comprehensive_return_items" => {
+            "return_id IN (SELECT id FROM comprehensive_returns WHERE organization_id = ?1)"
+        }
+        "purchase_order_items" => {
+            "purchase_order_id IN (SELECT id FROM purchase_orders WHERE organization_id = ?1)"
+        }
+        _ => return None,
+    })
+}
+
+/// Tables whose rows an import replaces for the organization. Users can belong to several
+/// organizations, so like the shared tables they are only ever added to.
+fn owned_by_organization(table: &str) -> bool {
+    table != "users" && organization_filter(table).is_some()
+}
+
+/// How a merge import matches archived rows to local ones
+struct MergeTable {
+    table: &'static str,
+    /// Natural keys; a row matching any of them is already present locally
+    keys: &'static [&'static str],
+    /// Child rows are only imported along with a newly imported parent
+    owner: Option<(&'static str, &'static str)>,
+    /// Foreign keys rewritten to the local ids of already merged tables
+    refs: &'static [(&'static str, &'static str)],
+    /// Foreign keys into tables that are not merged
+    cleared: &'static [&'static str],
+}
+
+const MERGE_TABLES: &[MergeTable] = &[
+    MergeTable {
+        table: "users",
+        keys: &["username", "email"],
+        owner: None,
+        refs: &[],
+        cleared: &[],
+    },
+    MergeTable {
+        table: "customers",
+        keys: &["customer_number", "email"],
+        owner: None,
+        refs: &[("created_by", "users")],
+        cleared: &[],
+    },
+    MergeTable {
+        table: "products",
+        keys: &["sku", "barcode"],
+        owner: None,
+        refs: &[],
+        cleared: &[],
+    },
+    MergeTable {
+        table: "inventory",
+        keys: &[],
+        owner: Some(("product_id", "products")),
+        refs: &[],
+        cleared: &[],
+    },
+    MergeTable {
+        table: "sales",
+        keys: &["sale_number"],
+        owner: None,
+        refs: &[("cashier_id", "users"), ("voided_by", "users")],
+        cleared: &["shift_id"],
+    },
+    MergeTable {
+        table: "sale_items",
+        keys: &[],
+        owner: Some(("sale_id", "sales")),
+        refs: &[("product_id", "products")],
+        cleared: &[],
+    },
+];
+
+/// Portable copy of the business data, stored as `archive.json` inside a zip file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DataArchive {
+    pub format: String,
+    pub schema_version: i64,
+    pub exported_at: String,
+    pub tables: BTreeMap<String, Vec<Map<String, Value>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Wipe the archived tables and load the archive as-is, ids included
+    Replace,
+    /// Add archived records missing locally, matched by natural keys (SKU, customer number, sale number)
+    Merge,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveExport {
+    pub path: String,
+    pub schema_version: i64,
+    pub rows: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportSummary {
+    pub archive_schema_version: i64,
+    pub inserted: BTreeMap<String, u64>,
+    pub skipped: BTreeMap<String, u64>,
+    pub conflicts: Vec<String>,
+}
+
+fn archive_error(e: impl std::fmt::Display) -> AppError {
+    AppError::validation("path", &format!("Unreadable data archive: {}", e))
+}
+
+pub(crate) async fn table_columns(conn: &mut SqliteConnection, table: &str) -> AppResult<Vec<String>> {
+    let columns = sqlx::query_scalar("SELECT name FROM pragma_table_info(?1) ORDER BY cid")
+        .bind(table)
+        .fetch_all(&mut *conn)
+        .await?;
+    Ok(columns)
+}
+
+/// Read the organization's rows of every archived table, letting SQLite serialize each row
+/// so types survive as-is
+pub async fn build_archive(pool: &SqlitePool, organization_id: i64) -> AppResult<DataArchive> {
+    let mut conn = pool.acquire().await?;
+    let mut tables = BTreeMap::new();
+
+    for table in ARCHIVE_TABLES {
+        let columns = table_columns(&mut conn, table).await?;
+        if columns.is_empty() {
+            continue;
+        }
+        let pairs: Vec<String> = columns
+            .iter()
+            .map(|column| format!("'{}', \"{}\"", column, column))
+            .collect();
+        let filter = organization_filter(table);
+        let query = format!(
+            "SELECT json_object({}) FROM {}{} ORDER BY rowid",
+            pairs.join(", "),
+            table,
+            filter.map(|filter| format!(" WHERE {}", filter)).unwrap_or_default()
+        );
+        let mut rows_query = sqlx::query_scalar(&query);
+        if filter.is_some() {
+            rows_query = rows_query.bind(organization_id);
+        }
+        let rows: Vec<String> = rows_query.fetch_all(&mut *conn).await?;
+        let rows = rows
+            .iter()
+            .map(|row| serde_json::from_str(row).map_err(|e| AppError::from(e.to_string())))
+            .collect::<AppResult<Vec<Map<String, Value>>>>()?;
+        tables.insert(table.to_string(), rows);
+    }
+
+    Ok(DataArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        schema_version: schema_version(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        tables,
+    })
+}
+
+pub async fn export_archive(
+    pool: &SqlitePool,
+    organization_id: i64,
+    path: &Path,
+) -> AppResult<ArchiveExport> {
+    let archive = build_archive(pool, organization_id).await?;
+
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(ARCHIVE_ENTRY, options)
+        .map_err(|e| AppError::from(e.to_string()))?;
+    let json = serde_json::to_vec(&archive).map_err(|e| AppError::from(e.to_string()))?;
+    zip.write_all(&json)?;
+    zip.finish().map_err(|e| AppError::from(e.to_string()))?;
+
+    Ok(ArchiveExport {
+        path: path.to_string_lossy().to_string(),
+        schema_version: archive.schema_version,
+        rows: archive
+            .tables
+            .iter()
+            .map(|(table, rows)| (table.clone(), rows.len()))
+            .collect(),
+    })
+}
+
+pub fn read_archive(path: &Path) -> AppResult<DataArchive> {
+    if !path.is_file() {
+        return Err(AppError::not_found("Data archive"));
+    }
+    let mut zip = ZipArchive::new(File::open(path)?).map_err(archive_error)?;
+    let entry = zip.by_name(ARCHIVE_ENTRY).map_err(archive_error)?;
+    serde_json::from_reader(entry).map_err(archive_error)
+}
+
+/// Check the whole archive before anything is written
+pub fn validate_archive(archive: &DataArchive, mode: ImportMode) -> AppResult<()> {
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(AppError::validation("path", "Not a QorBooks data archive"));
+    }
+    if archive.schema_version > schema_version() {
+        return Err(AppError::validation(
+            "path",
+            &format!(
+                "Archive was exported by a newer version (schema v{}, this app is v{}). Update the app first",
+                archive.schema_version,
+                schema_version()
+            ),
+        ));
+    }
+    if let Some(table) = archive
+        .tables
+        .keys()
+        .find(|table| !ARCHIVE_TABLES.contains(&table.as_str()))
+    {
+        return Err(AppError::validation(
+            "path",
+            &format!("Archive contains unknown table '{}'", table),
+        ));
+    }
+
+    if mode == ImportMode::Merge {
+        for merge in MERGE_TABLES {
+            let rows = archive
+                .tables
+                .get(merge.table)
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+            let mut seen = HashSet::new();
+            for row in rows {
+                if row.get("id").and_then(Value::as_i64).is_none() {
+                    return Err(AppError::validation(
+                        "path",
+                        &format!("Archived {} row without an id", merge.table),
+                    ));
+                }
+                let Some(key) = merge.keys.first() else {
+                    continue;
+                };
+                match row.get(*key).and_then(Value::as_str).map(str::trim) {
+                    Some(value) if !value.is_empty() => {
+                        if !seen.insert(value.to_string()) {
+                            return Err(AppError::validation(
+                                "path",
+                                &format!("Archive repeats {} {} '{}'", merge.table, key, value),
+                            ));
+                        }
+                    }
+                    _ => {
+                        return Err(AppError::validation(
+                            "path",
+                            &format!("Archived {} row is missing its {}", merge.table, key),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn bind_json<'q>(
+    query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    value: &Value,
+) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+    match value {
+        Value::Null => query.bind(None::<String>),
+        Value::Bool(b) => query.bind(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => query.bind(i),
+            None => query.bind(n.as_f64()),
+        },
+        Value::String(s) => query.bind(s.clone()),
+        other => query.bind(other.to_string()),
+    }
+}
+
+/// Insert the columns of `row` that still exist locally. Columns added since the archive
+/// was made take their defaults and columns since dropped are ignored.
+pub(crate) async fn insert_row(
+    conn: &mut SqliteConnection,
+    table: &str,
+    columns: &HashSet<String>,
+    row: &Map<String, Value>,
+) -> AppResult<i64> {
+    let names: Vec<&String> = row.keys().filter(|name| columns.contains(*name)).collect();
+    let quoted: Vec<String> = names.iter().map(|name| format!("\"{}\"", name)).collect();
+    let placeholders: Vec<String> = (1..=names.len()).map(|i| format!("?{}", i)).collect();
+    let sql = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table,
+        quoted.join(", "),
+        placeholders.join(", ")
+    );
+
+    let mut query = sqlx::query(&sql);
+    for name in names {
+        query = bind_json(query, &row[name]);
+    }
+    Ok(query.execute(&mut *conn).await?.last_insert_rowid())
+}
+
+async fn row_exists(
+    conn: &mut SqliteConnection,
+    table: &str,
+    row: &Map<String, Value>,
+) -> AppResult<bool> {
+    let Some(id) = row.get("id").and_then(Value::as_i64) else {
+        return Ok(false);
+    };
+    let sql = format!("SELECT 1 FROM {} WHERE id = ?1", table);
+    let found: Option<i64> = sqlx::query_scalar(&sql)
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(found.is_some())
+}
+
+/// Make an imported user a member of the organization so they can sign in to it
+async fn join_organization(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    user_id: i64,
+) -> AppResult<()> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO organization_users (organization_id, user_id, role)
+         VALUES (?1, ?2, 'User')",
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+async fn replace_tables(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    archive: &DataArchive,
+    summary: &mut ImportSummary,
+) -> AppResult<()> {
+    let mut tx = conn.begin().await?;
+
+    // Only the organization's rows go; tables missing from an older archive keep theirs
+    for table in ARCHIVE_TABLES.iter().rev() {
+        if !archive.tables.contains_key(*table) || !owned_by_organization(table) {
+            continue;
+        }
+        if let Some(filter) = organization_filter(table) {
+            sqlx::query(&format!("DELETE FROM {} WHERE {}", table, filter))
+                .bind(organization_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    for table in ARCHIVE_TABLES {
+        let Some(rows) = archive.tables.get(*table) else {
+            continue;
+        };
+        let columns: HashSet<String> = table_columns(&mut tx, table).await?.into_iter().collect();
+        let owned = owned_by_organization(table);
+        let mut inserted = 0;
+        let mut skipped = 0;
+        for row in rows {
+            if row_exists(&mut tx, table, row).await? {
+                // Shared rows already present are kept; an owned id still taken after the
+                // delete belongs to another organization
+                if owned {
+                    return Err(AppError::validation(
+                        "path",
+                        &format!(
+                            "Archived {} row {} is taken by another organization here. \
+                             Import in merge mode instead",
+                            table,
+                            row.get("id").cloned().unwrap_or_default()
+                        ),
+                    ));
+                }
+                skipped += 1;
+                continue;
+            }
+            let mut row = row.clone();
+            if owned && columns.contains("organization_id") {
+                row.insert("organization_id".to_string(), Value::from(organization_id));
+            }
+            let id = insert_row(&mut tx, table, &columns, &row).await?;
+            if *table == "users" {
+                join_organization(&mut tx, organization_id, id).await?;
+            }
+            inserted += 1;
+        }
+        summary.inserted.insert(table.to_string(), inserted);
+        summary.skipped.insert(table.to_string(), skipped);
+    }
+
+    for table in archive.tables.keys() {
+        let broken: Vec<(String,)> = sqlx::query_as(&format!(
+            "SELECT \"table\" FROM pragma_foreign_key_check('{}')",
+            table
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+        if !broken.is_empty() {
+            return Err(AppError::validation(
+                "path",
+                &format!(
+                    "Archive has {} row(s) in {} pointing at missing records",
+                    broken.len(),
+                    table
+                ),
+            ));
+        }
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn import_replace(
+    pool: &SqlitePool,
+    organization_id: i64,
+    archive: &DataArchive,
+    summary: &mut ImportSummary,
+) -> AppResult<()> {
+    let mut conn = pool.acquire().await?;
+
+    // Rows reference each other by id, so load everything first and check references at the end
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await?;
+    let result = replace_tables(&mut conn, organization_id, archive, summary).await;
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *conn)
+        .await?;
+    result
+}
+
+/// Local row matching a natural key of `row`. Owned tables only match the organization's
+/// own rows; users are matched across organizations since a login is global.
+async fn find_existing(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    merge: &MergeTable,
+    row: &Map<String, Value>,
+) -> AppResult<Option<(i64, String)>> {
+    let scope = if owned_by_organization(merge.table) {
+        " AND organization_id = ?2"
+    } else {
+        ""
+    };
+    for key in merge.keys {
+        let value = match row.get(*key) {
+            Some(Value::String(s)) if !s.trim().is_empty() => s.trim().to_string(),
+            _ => continue,
+        };
+        let sql = format!("SELECT id FROM {} WHERE {} = ?1{}", merge.table, key, scope);
+        let mut query = sqlx::query_scalar(&sql).bind(&value);
+        if !scope.is_empty() {
+            query = query.bind(organization_id);
+        }
+        let id: Option<i64> = query.fetch_optional(&mut *conn).await?;
+        if let Some(id) = id {
+            return Ok(Some((id, format!("{} '{}'", key, value))));
+        }
+    }
+    Ok(None)
+}
+
+async fn import_merge(
+    pool: &SqlitePool,
+    organization_id: i64,
+    archive: &DataArchive,
+    summary: &mut ImportSummary,
+) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+    // Archived id -> local id for every merged row, and the archived ids that were newly added
+    let mut local_ids: HashMap<&str, HashMap<i64, i64>> = HashMap::new();
+    let mut added: HashMap<&str, HashSet<i64>> = HashMap::new();
+
+    for merge in MERGE_TABLES {
+        let Some(rows) = archive.tables.get(merge.table) else {
+            continue;
+        };
+        let columns: HashSet<String> = table_columns(&mut tx, merge.table)
+            .await?
+            .into_iter()
+            .collect();
+        let mut inserted = 0;
+        let mut skipped = 0;
+
+        for row in rows {
+            let archived_id = row.get("id").and_then(Value::as_i64).unwrap_or_default();
+            let mut row = row.clone();
+
+            if let Some((column, parent)) = merge.owner {
+                let parent_id = row.get(column).and_then(Value::as_i64).unwrap_or_default();
+                if !added
+                    .get(parent)
+                    .is_some_and(|ids| ids.contains(&parent_id))
+                {
+                    skipped += 1;
+                    continue;
+                }
+                row.insert(
+                    column.to_string(),
+                    Value::from(local_ids[parent][&parent_id]),
+                );
+            }
+
+            if let Some((local_id, key)) =
+                find_existing(&mut tx, organization_id, merge, &row).await?
+            {
+                local_ids
+                    .entry(merge.table)
+                    .or_default()
+                    .insert(archived_id, local_id);
+                summary.conflicts.push(format!(
+                    "{}: {} already exists, kept the local record",
+                    merge.table, key
+                ));
+                skipped += 1;
+                continue;
+            }
+
+            for (column, parent) in merge.refs {
+                let mapped = row
+                    .get(*column)
+                    .and_then(Value::as_i64)
+                    .and_then(|id| local_ids.get(parent).and_then(|ids| ids.get(&id)).copied());
+                if let Some(local_id) = mapped {
+                    row.insert(column.to_string(), Value::from(local_id));
+                }
+            }
+            for column in merge.cleared {
+                row.insert(column.to_string(), Value::Null);
+            }
+            row.remove("id");
+            if owned_by_organization(merge.table) && columns.contains("organization_id") {
+                row.insert("organization_id".to_string(), Value::from(organization_id));
+            }
+
+            let local_id = insert_row(&mut tx, merge.table, &columns, &row).await?;
+            if merge.table == "users" {
+                join_organization(&mut tx, organization_id, local_id).await?;
+            }
+            local_ids
+                .entry(merge.table)
+                .or_default()
+                .insert(archived_id, local_id);
+            added.entry(merge.table).or_default().insert(archived_id);
+            inserted += 1;
+        }
+
+        summary.inserted.insert(merge.table.to_string(), inserted);
+        summary.skipped.insert(merge.table.to_string(), skipped);
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Import an archive into the organization inside a single transaction after validating it
+pub async fn import_archive(
+    pool: &SqlitePool,
+    organization_id: i64,
+    path: &Path,
+    mode: ImportMode,
+) -> AppResult<ImportSummary> {
+    let archive = read_archive(path)?;
+    validate_archive(&archive, mode)?;
+
+    let mut summary = ImportSummary {
+        archive_schema_version: archive.schema_version,
+        ..ImportSummary::default()
+    };
+    match mode {
+        ImportMode::Replace => {
+            import_replace(pool, organization_id, &archive, &mut summary).await?
+        }
+        ImportMode::Merge => import_merge(pool, organization_id, &archive, &mut summary).await?,
+    }
+
+    // Re-run the idempotent migrations so data fixups (e.g. money rounding) reach older archives
+    apply_migrations(pool).await?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+    use std::path::PathBuf;
+
+    fn temp_archive() -> PathBuf {
+        std::env::temp_dir().join(format!("qorbooks-archive-{}.zip", uuid::Uuid::new_v4()))
+    }
+
+    async fn insert_sale(pool: &SqlitePool, sale_number: &str, cashier: i64, product: i64) {
+        let sale_id = sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id)
+             VALUES (?1, 20.0, 20.0, 'cash', ?2)",
+        )
+        .bind(sale_number)
+        .bind(cashier)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        sqlx::query(
+            "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, line_total)
+             VALUES (?1, ?2, 2, 10.0, 20.0)",
+        )
+        .bind(sale_id)
+        .bind(product)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn count(pool: &SqlitePool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn move_to_other_organization(pool: &SqlitePool, product: i64) {
+        for sql in [
+            "UPDATE products SET organization_id = 2 WHERE id = ?1",
+            "UPDATE inventory SET organization_id = 2 WHERE product_id = ?1",
+        ] {
+            sqlx::query(sql).bind(product).execute(pool).await.unwrap();
+        }
+    }
+
+    async fn product_price(pool: &SqlitePool, sku: &str) -> f64 {
+        sqlx::query_scalar("SELECT selling_price FROM products WHERE sku = ?1")
+            .bind(sku)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_archive_round_trip_replaces_data() {
+        let source = test_pool().await;
+        let cashier = insert_test_user(&source, "cashier1").await;
+        let widget = insert_test_product(&source, "WIDGET", 10.0, 7).await;
+        insert_sale(&source, "SALE-1", cashier, widget).await;
+        // Another organization's product stays out of the archive
+        let hidden = insert_test_product(&source, "HIDDEN", 3.0, 1).await;
+        move_to_other_organization(&source, hidden).await;
+
+        let path = temp_archive();
+        let export = export_archive(&source, DEFAULT_ORGANIZATION_ID, &path).await.unwrap();
+        assert_eq!(export.rows["products"], 1);
+        assert_eq!(export.rows["inventory"], 1);
+        assert_eq!(export.schema_version, schema_version());
+
+        let target = test_pool().await;
+        insert_test_product(&target, "STALE", 1.0, 1).await;
+        // ...and another organization's product survives the replace
+        let other = insert_test_product(&target, "OTHER", 4.0, 2).await;
+        move_to_other_organization(&target, other).await;
+        let summary = import_archive(&target, DEFAULT_ORGANIZATION_ID, &path, ImportMode::Replace)
+            .await
+            .unwrap();
+        assert_eq!(summary.inserted["sales"], 1);
+
+        assert_eq!(count(&target, "products").await, 2);
+        assert_eq!(product_price(&target, "OTHER").await, 4.0);
+        assert_eq!(current_stock(&target, other).await, 2);
+        assert_eq!(product_price(&target, "WIDGET").await, 10.0);
+        assert_eq!(current_stock(&target, widget).await, 7);
+        assert_eq!(count(&target, "sale_items").await, 1);
+        assert_eq!(count(&target, "users").await, count(&source, "users").await);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_merge_keeps_local_records_on_conflicting_sku() {
+        let source = test_pool().await;
+        let cashier = insert_test_user(&source, "cashier1").await;
+        let widget = insert_test_product(&source, "WIDGET", 99.0, 3).await;
+        let gadget = insert_test_product(&source, "GADGET", 5.0, 4).await;
+        insert_sale(&source, "SALE-1", cashier, widget).await;
+        insert_sale(&source, "SALE-2", cashier, gadget).await;
+        let path = temp_archive();
+        export_archive(&source, DEFAULT_ORGANIZATION_ID, &path).await.unwrap();
+
+        let target = test_pool().await;
+        insert_test_user(&target, "someone_else").await;
+        let local_cashier = insert_test_user(&target, "cashier1").await;
+        let local_widget = insert_test_product(&target, "WIDGET", 10.0, 20).await;
+        insert_sale(&target, "SALE-1", local_cashier, local_widget).await;
+
+        let summary = import_archive(&target, DEFAULT_ORGANIZATION_ID, &path, ImportMode::Merge)
+            .await
+            .unwrap();
+
+        // The local WIDGET and SALE-1 win, GADGET and SALE-2 are added
+        assert_eq!(product_price(&target, "WIDGET").await, 10.0);
+        assert_eq!(current_stock(&target, local_widget).await, 20);
+        assert_eq!(product_price(&target, "GADGET").await, 5.0);
+        assert_eq!(summary.inserted["products"], 1);
+        assert_eq!(summary.skipped["products"], 1);
+        assert_eq!(summary.inserted["sales"], 1);
+        assert!(summary
+            .conflicts
+            .iter()
+            .any(|c| c == "products: sku 'WIDGET' already exists, kept the local record"));
+
+        // SALE-2 points at the local cashier and the newly added GADGET
+        let (sale_cashier, item_sku): (i64, String) = sqlx::query_as(
+            "SELECT s.cashier_id, p.sku
+             FROM sales s
+             JOIN sale_items si ON si.sale_id = s.id
+             JOIN products p ON p.id = si.product_id
+             WHERE s.sale_number = 'SALE-2'",
+        )
+        .fetch_one(&target)
+        .await
+        .unwrap();
+        assert_eq!(sale_cashier, local_cashier);
+        assert_eq!(item_sku, "GADGET");
+        assert_eq!(count(&target, "sale_items").await, 2);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_rejects_archive_from_newer_schema() {
+        let archive = DataArchive {
+            format: ARCHIVE_FORMAT.to_string(),
+            schema_version: schema_version() + 1,
+            exported_at: String::new(),
+            tables: BTreeMap::new(),
+        };
+        let err = validate_archive(&archive, ImportMode::Replace).unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+}
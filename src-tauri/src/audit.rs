@@ -0,0 +1,141 @@
+use crate::error::AppResult;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{FromRow, SqlitePool};
+
+/// Most entries `get_audit_log` returns when no limit is given
+const DEFAULT_AUDIT_LIMIT: i64 = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub user_id: Option<i64>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<i64>,
+    /// JSON of the changed fields before and after the change
+    pub before_json: Option<String>,
+    pub after_json: Option<String>,
+    pub created_at: String,
+}
+
+/// Which audit entries to list; every filter left out matches all entries
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditLogFilter {
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub action: Option<String>,
+    /// Inclusive `YYYY-MM-DD` dates
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Record a sensitive change in the organization it was made in. Call it on the
+/// transaction making the change so the entry and the change commit or roll back together.
+pub async fn log_audit(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    user_id: Option<i64>,
+    action: &str,
+    entity_type: &str,
+    entity_id: Option<i64>,
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+) -> AppResult<i64> {
+    let id = sqlx::query(
+        "INSERT INTO audit_log (user_id, action, entity_type, entity_id, before_json, after_json,
+                                organization_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )
+    .bind(user_id)
+    .bind(action)
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(before.map(|value| value.to_string()))
+    .bind(after.map(|value| value.to_string()))
+    .bind(organization_id)
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+    Ok(id)
+}
+
+/// The organization's audit entries matching the filter, newest first
+pub async fn get_audit_log(
+    pool: &SqlitePool,
+    organization_id: i64,
+    filter: &AuditLogFilter,
+) -> AppResult<Vec<AuditEntry>> {
+    Ok(sqlx::query_as::<_, AuditEntry>(
+        "SELECT id, user_id, action, entity_type, entity_id, before_json, after_json,
+                COALESCE(created_at, '') as created_at
+         FROM audit_log
+         WHERE organization_id = ?8
+           AND (?1 IS NULL OR entity_type = ?1)
+           AND (?2 IS NULL OR entity_id = ?2)
+           AND (?3 IS NULL OR user_id = ?3)
+           AND (?4 IS NULL OR action = ?4)
+           AND (?5 IS NULL OR DATE(created_at) >= ?5)
+           AND (?6 IS NULL OR DATE(created_at) <= ?6)
+         ORDER BY created_at DESC, id DESC
+         LIMIT ?7",
+    )
+    .bind(&filter.entity_type)
+    .bind(filter.entity_id)
+    .bind(filter.user_id)
+    .bind(&filter.action)
+    .bind(&filter.start_date)
+    .bind(&filter.end_date)
+    .bind(filter.limit.unwrap_or(DEFAULT_AUDIT_LIMIT).max(1))
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{insert_test_user, test_pool};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_entries_roll_back_with_their_transaction() {
+        let pool = test_pool().await;
+        let user = insert_test_user(&pool, "auditor").await;
+
+        let org = DEFAULT_ORGANIZATION_ID;
+        let mut tx = pool.begin().await.unwrap();
+        let after = json!({ "is_voided": true });
+        log_audit(&mut tx, org, Some(user), "void_sale", "sale", Some(9), None, Some(&after))
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+        assert!(get_audit_log(&pool, org, &AuditLogFilter::default()).await.unwrap().is_empty());
+
+        let mut tx = pool.begin().await.unwrap();
+        log_audit(&mut tx, org, Some(user), "void_sale", "sale", Some(9), None, None).await.unwrap();
+        log_audit(&mut tx, org, Some(user), "delete_product", "product", Some(3), None, None).await.unwrap();
+        log_audit(&mut tx, org, None, "delete_product", "product", Some(4), None, None).await.unwrap();
+        log_audit(&mut tx, org + 1, Some(user), "delete_product", "product", Some(5), None, None)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let filter = AuditLogFilter {
+            entity_type: Some("product".to_string()),
+            user_id: Some(user),
+            ..Default::default()
+        };
+        let entries = get_audit_log(&pool, org, &filter).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].action.as_str(), entries[0].entity_id), ("delete_product", Some(3)));
+        assert_eq!(get_audit_log(&pool, org, &AuditLogFilter::default()).await.unwrap().len(), 3);
+        // Another organization's entries stay with it
+        let other = get_audit_log(&pool, org + 1, &AuditLogFilter::default()).await.unwrap();
+        assert_eq!(other.len(), 1);
+        assert_eq!(other[0].entity_id, Some(5));
+    }
+}
@@ -0,0 +1,424 @@
+use crate::error::{AppError, AppResult};
+use crate::settings::{self, STORE_SETTINGS};
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Database file name inside the app data directory
+pub const DATABASE_FILE: &str = "pos.db";
+/// Backup directory name inside the app data directory
+pub const BACKUP_DIR: &str = "backups";
+/// Automatic backups to keep before the oldest are rotated out
+pub const DEFAULT_BACKUPS_TO_KEEP: usize = 7;
+/// How often automatic backups are taken
+pub const DEFAULT_BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// How often the runner checks whether a backup is due (the app may sleep or restart)
+const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// `off`, `daily` or `weekly`; automatic backups run daily when it is not set
+pub const BACKUP_SCHEDULE_SETTING: &str = "backup_schedule";
+
+const AUTO_PREFIX: &str = "auto";
+const MANUAL_PREFIX: &str = "backup";
+const PRE_RESTORE_PREFIX: &str = "pre-restore";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: String,
+}
+
+impl BackupInfo {
+    fn from_path(path: &Path) -> AppResult<Self> {
+        let metadata = fs::metadata(path)?;
+        let created_at: chrono::DateTime<chrono::Local> = metadata.modified()?.into();
+        Ok(BackupInfo {
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.to_string_lossy().to_string(),
+            size_bytes: metadata.len(),
+            created_at: created_at.to_rfc3339(),
+        })
+    }
+}
+
+pub fn database_path(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(DATABASE_FILE)
+}
+
+pub fn backup_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(BACKUP_DIR)
+}
+
+/// Where a backup named by the caller goes. Only a plain `.db` file name inside `dir` is
+/// accepted, so a backup can never write the database somewhere else on disk.
+pub fn backup_target(dir: &Path, name: &str) -> AppResult<PathBuf> {
+    let name = name.trim();
+    let plain_name = !name.is_empty()
+        && Path::new(name).file_name().is_some_and(|file_name| file_name == name)
+        && Path::new(name).extension().is_some_and(|ext| ext == "db");
+    if !plain_name {
+        return Err(AppError::validation(
+            "target_path",
+            "Backups are saved in the backups directory under a name ending in .db",
+        ));
+    }
+    Ok(dir.join(name))
+}
+
+/// Copy the live database to `target` with `VACUUM INTO`, which is safe while other
+/// connections keep reading and writing
+pub async fn backup_to(pool: &SqlitePool, target: &Path) -> AppResult<BackupInfo> {
+    if target.exists() {
+        return Err(AppError::validation(
+            "target_path",
+            &format!("{} already exists", target.display()),
+        ));
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    sqlx::query("VACUUM INTO ?1")
+        .bind(target.to_string_lossy().to_string())
+        .execute(pool)
+        .await?;
+
+    BackupInfo::from_path(target)
+}
+
+/// Back up into `dir` under a timestamped name such as `auto-20240101-020000-123.db`
+async fn backup_into(pool: &SqlitePool, dir: &Path, prefix: &str) -> AppResult<BackupInfo> {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f");
+    backup_to(pool, &dir.join(format!("{}-{}.db", prefix, stamp))).await
+}
+
+pub async fn create_backup(pool: &SqlitePool, dir: &Path) -> AppResult<BackupInfo> {
+    backup_into(pool, dir, MANUAL_PREFIX).await
+}
+
+/// Backups in `dir`, newest first
+pub fn list_backups(dir: &Path) -> AppResult<Vec<BackupInfo>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "db") {
+            let modified = fs::metadata(&path)?.modified()?;
+            backups.push((modified, BackupInfo::from_path(&path)?));
+        }
+    }
+    backups.sort_by(|(a_time, a), (b_time, b)| {
+        b_time.cmp(a_time).then(b.file_name.cmp(&a.file_name))
+    });
+    Ok(backups.into_iter().map(|(_, backup)| backup).collect())
+}
+
+/// Delete all but the newest `keep` automatic backups. Manual and pre-restore copies are kept.
+pub fn rotate_backups(dir: &Path, keep: usize) -> AppResult<usize> {
+    let auto_backups: Vec<BackupInfo> = list_backups(dir)?
+        .into_iter()
+        .filter(|backup| backup.file_name.starts_with(AUTO_PREFIX))
+        .collect();
+
+    let mut removed = 0;
+    for backup in auto_backups.iter().skip(keep) {
+        fs::remove_file(&backup.path)?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Run `PRAGMA integrity_check` on a database file without touching the live pool
+pub async fn check_integrity(path: &Path) -> AppResult<()> {
+    if !path.is_file() {
+        return Err(AppError::not_found("Backup file"));
+    }
+
+    let options = SqliteConnectOptions::new().filename(path).read_only(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await?;
+    let result: Result<String, sqlx::Error> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_one(&pool)
+        .await;
+    pool.close().await;
+
+    match result {
+        Ok(status) if status == "ok" => Ok(()),
+        Ok(status) => Err(AppError::validation(
+            "source_path",
+            &format!("Backup failed integrity check: {}", status),
+        )),
+        Err(e) => Err(AppError::validation(
+            "source_path",
+            &format!("Backup is not a readable database: {}", e),
+        )),
+    }
+}
+
+/// Restore `source` over the database at `db_path`.
+///
+/// The source is integrity-checked first, then a safety copy of the current database is
+/// written to `backup_dir`. The pool is closed before the files are swapped, so the app must
+/// restart afterwards. Returns the safety copy.
+pub async fn restore(
+    pool: &SqlitePool,
+    source: &Path,
+    db_path: &Path,
+    backup_dir: &Path,
+) -> AppResult<BackupInfo> {
+    check_integrity(source).await?;
+    let safety_copy = backup_into(pool, backup_dir, PRE_RESTORE_PREFIX).await?;
+    pool.close().await;
+
+    // Copy next to the target first so the swap itself is a single rename
+    let staging = db_path.with_extension("db.restoring");
+    fs::copy(source, &staging)?;
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let sidecar = PathBuf::from(format!("{}{}", db_path.display(), suffix));
+        if sidecar.exists() {
+            fs::remove_file(sidecar)?;
+        }
+    }
+    fs::rename(&staging, db_path)?;
+
+    Ok(safety_copy)
+}
+
+/// Take an automatic backup if the newest one is older than `interval`, then rotate.
+/// Returns the new backup, or `None` when one was not due yet.
+pub async fn run_scheduled_backup(
+    pool: &SqlitePool,
+    dir: &Path,
+    interval: Duration,
+    keep: usize,
+) -> AppResult<Option<BackupInfo>> {
+    let newest_auto = list_backups(dir)?
+        .into_iter()
+        .find(|backup| backup.file_name.starts_with(AUTO_PREFIX));
+    if let Some(newest) = newest_auto {
+        let modified = fs::metadata(&newest.path)?.modified()?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        if age < interval {
+            return Ok(None);
+        }
+    }
+
+    let backup = backup_into(pool, dir, AUTO_PREFIX).await?;
+    rotate_backups(dir, keep)?;
+    Ok(Some(backup))
+}
+
+/// How often automatic backups are taken under the store's `backup_schedule`, `None` when
+/// they are turned off
+pub async fn backup_interval(pool: &SqlitePool) -> AppResult<Option<Duration>> {
+    let schedule: Option<String> =
+        settings::get_setting(pool, BACKUP_SCHEDULE_SETTING, STORE_SETTINGS).await?;
+    Ok(match schedule.as_deref() {
+        Some("off") => None,
+        Some("weekly") => Some(DEFAULT_BACKUP_INTERVAL * 7),
+        _ => Some(DEFAULT_BACKUP_INTERVAL),
+    })
+}
+
+/// Spawn a task that takes automatic backups on the store's schedule, checking hourly
+/// whether one is due so a changed schedule applies without a restart. Failures are logged
+/// and retried on the next check.
+pub fn spawn_backup_runner(pool: SqlitePool, dir: PathBuf, keep: usize) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if pool.is_closed() {
+                break;
+            }
+            let interval = match backup_interval(&pool).await {
+                Ok(interval) => interval,
+                Err(e) => {
+                    log::error!("Could not read the backup schedule: {}", e.message());
+                    Some(DEFAULT_BACKUP_INTERVAL)
+                }
+            };
+            if let Some(interval) = interval {
+                match run_scheduled_backup(&pool, &dir, interval, keep).await {
+                    Ok(Some(backup)) => log::info!(
+                        "Automatic backup written to {} ({} bytes)",
+                        backup.path,
+                        backup.size_bytes
+                    ),
+                    Ok(None) => {}
+                    Err(e) => log::error!("Automatic backup failed: {}", e.message()),
+                }
+            }
+            tokio::time::sleep(BACKUP_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::apply_migrations;
+    use crate::test_utils::insert_test_user;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("qorbooks-backup-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn file_pool(path: &Path) -> SqlitePool {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .connect_with(options)
+            .await
+            .unwrap();
+        apply_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    async fn user_count(pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM users")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_backup_during_writes_restores_into_fresh_pool() {
+        let dir = temp_dir();
+        let pool = file_pool(&database_path(&dir)).await;
+        let before = user_count(&pool).await;
+
+        let writer_pool = pool.clone();
+        let writer = tokio::spawn(async move {
+            for i in 0..50 {
+                insert_test_user(&writer_pool, &format!("writer{}", i)).await;
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let backup = create_backup(&pool, &backup_dir(&dir)).await.unwrap();
+        writer.await.unwrap();
+
+        assert!(backup.size_bytes > 0);
+        check_integrity(Path::new(&backup.path)).await.unwrap();
+
+        let restored = file_pool(Path::new(&backup.path)).await;
+        let restored_users = user_count(&restored).await;
+        assert!(restored_users >= before);
+        assert!(restored_users <= before + 50);
+        assert_eq!(user_count(&pool).await, before + 50);
+
+        let err = backup_to(&pool, Path::new(&backup.path)).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_restore_swaps_database_and_keeps_safety_copy() {
+        let dir = temp_dir();
+        let db_path = database_path(&dir);
+        let backups = backup_dir(&dir);
+        let pool = file_pool(&db_path).await;
+
+        insert_test_user(&pool, "before_backup").await;
+        let backup = create_backup(&pool, &backups).await.unwrap();
+        insert_test_user(&pool, "after_backup").await;
+        let live_users = user_count(&pool).await;
+
+        let safety = restore(&pool, Path::new(&backup.path), &db_path, &backups)
+            .await
+            .unwrap();
+        assert!(pool.is_closed());
+        assert!(safety.file_name.starts_with(PRE_RESTORE_PREFIX));
+
+        let reopened = file_pool(&db_path).await;
+        assert_eq!(user_count(&reopened).await, live_users - 1);
+        let safety_pool = file_pool(Path::new(&safety.path)).await;
+        assert_eq!(user_count(&safety_pool).await, live_users);
+
+        // A corrupt source is rejected before anything is touched
+        let junk = dir.join("junk.db");
+        fs::write(&junk, b"definitely not sqlite").unwrap();
+        let err = restore(&reopened, &junk, &db_path, &backups)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert!(!reopened.is_closed());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_backup_interval_follows_the_schedule() {
+        let pool = crate::test_utils::test_pool().await;
+        assert_eq!(backup_interval(&pool).await.unwrap(), Some(DEFAULT_BACKUP_INTERVAL));
+        settings::set_setting(&pool, BACKUP_SCHEDULE_SETTING, STORE_SETTINGS, &"weekly")
+            .await
+            .unwrap();
+        assert_eq!(backup_interval(&pool).await.unwrap(), Some(DEFAULT_BACKUP_INTERVAL * 7));
+        settings::set_setting(&pool, BACKUP_SCHEDULE_SETTING, STORE_SETTINGS, &"off")
+            .await
+            .unwrap();
+        assert_eq!(backup_interval(&pool).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_backup_target_stays_in_backup_dir() {
+        let dir = Path::new("/data/backups");
+        assert_eq!(
+            backup_target(dir, " month-end.db ").unwrap(),
+            dir.join("month-end.db")
+        );
+        for name in ["", "../pos.db", "/tmp/copy.db", "nested/copy.db", "copy.txt", ".."] {
+            assert!(backup_target(dir, name).is_err(), "{} was accepted", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_backups_are_rotated() {
+        let dir = temp_dir();
+        let pool = file_pool(&database_path(&dir)).await;
+        let backups = backup_dir(&dir);
+
+        let first = run_scheduled_backup(&pool, &backups, DEFAULT_BACKUP_INTERVAL, 2)
+            .await
+            .unwrap();
+        assert!(first.is_some());
+        // Not due again until the interval has passed
+        let skipped = run_scheduled_backup(&pool, &backups, DEFAULT_BACKUP_INTERVAL, 2)
+            .await
+            .unwrap();
+        assert!(skipped.is_none());
+
+        create_backup(&pool, &backups).await.unwrap();
+        for _ in 0..3 {
+            run_scheduled_backup(&pool, &backups, Duration::ZERO, 2)
+                .await
+                .unwrap();
+        }
+
+        let listed = list_backups(&backups).unwrap();
+        let auto = listed
+            .iter()
+            .filter(|b| b.file_name.starts_with(AUTO_PREFIX))
+            .count();
+        assert_eq!(auto, 2);
+        assert_eq!(listed.len(), 3);
+        assert!(listed.windows(2).all(|w| w[0].created_at >= w[1].created_at));
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,224 @@
+use crate::document_numbers::{next_sequence_value, DOCUMENT_NUMBER_ATTEMPTS};
+use crate::error::{AppError, AppResult};
+use barcoders::sym::code128::Code128;
+use barcoders::sym::ean13::EAN13;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+
+/// Counter in `document_sequences` behind auto-assigned barcodes; its prefix is the
+/// leading digits of every generated EAN-13 (20-29 is reserved for in-store use)
+pub const BARCODE_SEQUENCE: &str = "barcode";
+
+/// Quiet zone either side of the bars, in modules
+const QUIET_ZONE: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Symbology {
+    Code128,
+    Ean13,
+}
+
+impl Symbology {
+    /// EAN-13 for codes that are valid ones, Code128 for anything else
+    pub fn detect(code: &str) -> Self {
+        if code.len() == 13 && complete_ean13(code).is_ok() {
+            Symbology::Ean13
+        } else {
+            Symbology::Code128
+        }
+    }
+}
+
+/// Check digit of the first 12 digits of an EAN-13: weights 1 and 3 alternate from the left
+pub fn ean13_check_digit(digits: &str) -> AppResult<u32> {
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(AppError::validation("code", "EAN-13 needs 12 digits before the check digit"));
+    }
+    let sum: u32 = digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { d } else { d * 3 })
+        .sum();
+    Ok((10 - sum % 10) % 10)
+}
+
+/// A full 13-digit EAN: the check digit is appended to 12 digits and verified on 13
+pub fn complete_ean13(code: &str) -> AppResult<String> {
+    if !code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(AppError::validation("code", "EAN-13 codes contain digits only"));
+    }
+    match code.len() {
+        12 => Ok(format!("{}{}", code, ean13_check_digit(code)?)),
+        13 => {
+            let expected = ean13_check_digit(&code[..12])?;
+            if code[12..].parse::<u32>().ok() != Some(expected) {
+                return Err(AppError::validation(
+                    "code",
+                    &format!("Invalid EAN-13 check digit, expected {}", expected),
+                ));
+            }
+            Ok(code.to_string())
+        }
+        _ => Err(AppError::validation("code", "EAN-13 codes have 12 or 13 digits")),
+    }
+}
+
+/// Bars and spaces of a barcode, one entry per module, `true` for a bar
+pub fn encode(code: &str, symbology: Symbology) -> AppResult<Vec<bool>> {
+    let modules = match symbology {
+        Symbology::Ean13 => {
+            let code = complete_ean13(code)?;
+            EAN13::new(&code[..12])
+                .map_err(|e| AppError::validation("code", &e.to_string()))?
+                .encode()
+        }
+        Symbology::Code128 => {
+            if code.is_empty() || !code.chars().all(|c| (' '..='~').contains(&c)) {
+                return Err(AppError::validation(
+                    "code",
+                    "Code128 codes must be printable ASCII characters",
+                ));
+            }
+            // Character set C packs digit pairs; set B covers the rest of printable ASCII
+            let set = if code.len().is_multiple_of(2) && code.chars().all(|c| c.is_ascii_digit()) {
+                '\u{0106}'
+            } else {
+                '\u{0181}'
+            };
+            Code128::new(format!("{}{}", set, code))
+                .map_err(|e| AppError::validation("code", &e.to_string()))?
+                .encode()
+        }
+    };
+    Ok(modules.into_iter().map(|m| m == 1).collect())
+}
+
+/// Render a barcode as a black-on-white greyscale PNG
+pub fn render_png(
+    code: &str,
+    symbology: Symbology,
+    module_width: u32,
+    height: u32,
+) -> AppResult<Vec<u8>> {
+    let modules = encode(code, symbology)?;
+    let module_width = module_width.max(1) as usize;
+    let width = (modules.len() + 2 * QUIET_ZONE) * module_width;
+
+    let mut row = vec![255u8; width];
+    for (i, _) in modules.iter().enumerate().filter(|(_, bar)| **bar) {
+        let start = (QUIET_ZONE + i) * module_width;
+        row[start..start + module_width].fill(0);
+    }
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, width as u32, height.max(1));
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| AppError::Internal { message: format!("Failed to encode barcode: {}", e) })?;
+        let image: Vec<u8> = row.iter().copied().cycle().take(width * height.max(1) as usize).collect();
+        writer
+            .write_image_data(&image)
+            .map_err(|e| AppError::Internal { message: format!("Failed to encode barcode: {}", e) })?;
+    }
+    Ok(bytes)
+}
+
+/// Give a product without a barcode the next EAN-13 from the barcode sequence.
+/// Numbers already on another product are skipped. Returns the product's barcode.
+pub async fn assign_barcode(conn: &mut SqliteConnection, product_id: i64) -> AppResult<String> {
+    let existing: Option<String> = sqlx::query_scalar("SELECT barcode FROM products WHERE id = ?1")
+        .bind(product_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Product"))?;
+    if let Some(barcode) = existing.filter(|b| !b.trim().is_empty()) {
+        return Ok(barcode);
+    }
+
+    for _ in 0..DOCUMENT_NUMBER_ATTEMPTS {
+        let (prefix, value) = next_sequence_value(&mut *conn, BARCODE_SEQUENCE).await?;
+        let digits = format!("{}{:0width$}", prefix, value, width = 12 - prefix.len().min(12));
+        if digits.len() != 12 {
+            return Err(AppError::Conflict {
+                message: format!("Barcode sequence {} has run out of numbers", prefix),
+            });
+        }
+        let barcode = complete_ean13(&digits)?;
+
+        let taken: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM products WHERE barcode = ?1)")
+            .bind(&barcode)
+            .fetch_one(&mut *conn)
+            .await?;
+        if taken {
+            continue;
+        }
+        sqlx::query("UPDATE products SET barcode = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2")
+            .bind(&barcode)
+            .bind(product_id)
+            .execute(&mut *conn)
+            .await?;
+        return Ok(barcode);
+    }
+    Err(AppError::Conflict {
+        message: "Could not find a free barcode; check the barcode sequence".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_product, test_pool};
+
+    #[test]
+    fn test_ean13_check_digits() {
+        for code in ["4006381333931", "5901234123457", "9780306406157", "0012345678905"] {
+            assert_eq!(complete_ean13(&code[..12]).unwrap(), code);
+            assert_eq!(complete_ean13(code).unwrap(), code);
+        }
+        assert_eq!(complete_ean13("4006381333932").unwrap_err().code(), "VALIDATION_ERROR");
+        assert!(complete_ean13("40063813339").is_err());
+        assert!(complete_ean13("40063813339A").is_err());
+
+        assert_eq!(Symbology::detect("5901234123457"), Symbology::Ean13);
+        assert_eq!(Symbology::detect("5901234123458"), Symbology::Code128);
+        assert_eq!(encode("5901234123457", Symbology::Ean13).unwrap().len(), 95);
+        assert!(encode("CEM-50KG", Symbology::Code128).is_ok());
+        assert!(encode("caf\u{e9}", Symbology::Code128).is_err());
+
+        let png = render_png("5901234123457", Symbology::Ean13, 2, 40).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+
+    #[tokio::test]
+    async fn test_barcodes_are_assigned_from_the_sequence() {
+        let pool = test_pool().await;
+        let first = insert_test_product(&pool, "BRICK", 1.0, 0).await;
+        let second = insert_test_product(&pool, "BLOCK", 1.0, 0).await;
+        let third = insert_test_product(&pool, "SLAB", 1.0, 0).await;
+        let fourth = insert_test_product(&pool, "PAVER", 1.0, 0).await;
+        // The second number is already printed on another product
+        sqlx::query("UPDATE products SET barcode = '2000000000022' WHERE id = ?1")
+            .bind(third)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+
+        assert_eq!(assign_barcode(&mut conn, first).await.unwrap(), "2000000000015");
+        // Assigning again keeps the barcode
+        assert_eq!(assign_barcode(&mut conn, first).await.unwrap(), "2000000000015");
+        assert_eq!(assign_barcode(&mut conn, second).await.unwrap(), "2000000000039");
+        assert_eq!(assign_barcode(&mut conn, third).await.unwrap(), "2000000000022");
+
+        sqlx::query("UPDATE document_sequences SET prefix = '29', next_value = 7 WHERE doc_type = 'barcode'")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(assign_barcode(&mut conn, fourth).await.unwrap(), "2900000000070");
+    }
+}
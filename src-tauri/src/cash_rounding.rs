@@ -0,0 +1,92 @@
+//! Cash rounding for markets without the smallest coins: the cash part of a sale is rounded
+//! to the store's increment (0.05) while card payments stay exact. The difference is kept
+//! on the sale so shift and tax totals reconcile with the cash actually collected.
+
+use crate::error::AppResult;
+use crate::money::Money;
+use crate::organization_settings;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingDirection {
+    /// Halves round up
+    Nearest,
+    Up,
+    Down,
+}
+
+/// The store's rule, e.g. `{"increment": 0.05, "direction": "nearest"}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CashRounding {
+    pub increment: Money,
+    pub direction: RoundingDirection,
+}
+
+impl CashRounding {
+    pub fn is_valid(&self) -> bool {
+        self.increment > Money::ZERO
+    }
+
+    /// What rounding `cash` adds to the amount due: negative when rounded down
+    pub fn adjustment(&self, cash: Money) -> Money {
+        let step = self.increment.minor();
+        let remainder = cash.minor().rem_euclid(step);
+        if remainder == 0 {
+            return Money::ZERO;
+        }
+        let round_up = match self.direction {
+            RoundingDirection::Nearest => remainder * 2 >= step,
+            RoundingDirection::Up => true,
+            RoundingDirection::Down => false,
+        };
+        if round_up {
+            Money::from_minor(step - remainder)
+        } else {
+            Money::from_minor(-remainder)
+        }
+    }
+}
+
+/// The organization's rule, or `None` when cash is taken to the cent
+pub async fn cash_rounding_rule(
+    pool: &SqlitePool,
+    organization_id: i64,
+) -> AppResult<Option<CashRounding>> {
+    let mut conn = pool.acquire().await?;
+    let settings = organization_settings::load(&mut conn, organization_id).await?;
+    let rule = CashRounding {
+        increment: settings.cash_rounding,
+        direction: settings.cash_rounding_direction,
+    };
+    Ok(rule.is_valid().then_some(rule))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(direction: RoundingDirection) -> CashRounding {
+        CashRounding {
+            increment: Money::from_major(0.05),
+            direction,
+        }
+    }
+
+    #[test]
+    fn test_rounds_to_the_increment_in_each_direction() {
+        let nearest = rule(RoundingDirection::Nearest);
+        assert_eq!(nearest.adjustment(Money::from_major(10.02)), Money::from_major(-0.02));
+        assert_eq!(nearest.adjustment(Money::from_major(10.03)), Money::from_major(0.02));
+        assert_eq!(nearest.adjustment(Money::from_major(10.05)), Money::ZERO);
+        let up = rule(RoundingDirection::Up);
+        assert_eq!(up.adjustment(Money::from_major(10.01)), Money::from_major(0.04));
+        let down = rule(RoundingDirection::Down);
+        assert_eq!(down.adjustment(Money::from_major(10.04)), Money::from_major(-0.04));
+
+        let setting: CashRounding =
+            serde_json::from_value(serde_json::json!({"increment": 0.1, "direction": "up"})).unwrap();
+        assert_eq!(setting.adjustment(Money::from_major(3.21)), Money::from_major(0.09));
+    }
+}
@@ -1,5 +1,6 @@
 // src-tauri/src/commands/appointments.rs
 use crate::models::*;
+use crate::plans;
 use sqlx::SqlitePool;
 use tauri::State;
 
@@ -9,7 +10,9 @@ pub async fn get_appointments(
     status: Option<String>,
     employee_id: Option<i64>,
     customer_id: Option<i64>,
+    session_token: String,
 ) -> Result<Vec<Appointment>, String> {
+    plans::crm_organization(pool.inner(), &session_token).await?;
     let mut query = "SELECT * FROM appointments WHERE 1=1".to_string();
 
     if status.is_some() {
@@ -47,7 +50,9 @@ pub async fn get_appointments(
 pub async fn get_appointment(
     pool: State<'_, SqlitePool>,
     appointment_id: i64,
+    session_token: String,
 ) -> Result<Appointment, String> {
+    plans::crm_organization(pool.inner(), &session_token).await?;
     let appointment = sqlx::query_as::<_, Appointment>("SELECT * FROM appointments WHERE id = ?")
         .bind(appointment_id)
         .fetch_one(pool.inner())
@@ -62,7 +67,9 @@ pub async fn create_appointment(
     pool: State<'_, SqlitePool>,
     request: CreateAppointmentRequest,
     user_id: i64,
+    session_token: String,
 ) -> Result<Appointment, String> {
+    plans::writable_crm_organization(pool.inner(), &session_token).await?;
     // Generate appointment number
     let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM appointments")
         .fetch_one(pool.inner())
@@ -95,7 +102,7 @@ pub async fn create_appointment(
     .map_err(|e| format!("Failed to create appointment: {}", e))?;
 
     let appointment_id = result.last_insert_rowid();
-    get_appointment(pool, appointment_id).await
+    get_appointment(pool, appointment_id, session_token).await
 }
 
 #[tauri::command]
@@ -103,7 +110,9 @@ pub async fn update_appointment(
     pool: State<'_, SqlitePool>,
     appointment_id: i64,
     request: UpdateAppointmentRequest,
+    session_token: String,
 ) -> Result<Appointment, String> {
+    plans::writable_crm_organization(pool.inner(), &session_token).await?;
     sqlx::query(
         "UPDATE appointments SET
             customer_id = COALESCE(?, customer_id),
@@ -136,14 +145,16 @@ pub async fn update_appointment(
     .await
     .map_err(|e| format!("Failed to update appointment: {}", e))?;
 
-    get_appointment(pool, appointment_id).await
+    get_appointment(pool, appointment_id, session_token).await
 }
 
 #[tauri::command]
 pub async fn delete_appointment(
     pool: State<'_, SqlitePool>,
     appointment_id: i64,
+    session_token: String,
 ) -> Result<(), String> {
+    plans::writable_crm_organization(pool.inner(), &session_token).await?;
     sqlx::query("DELETE FROM appointments WHERE id = ?")
         .bind(appointment_id)
         .execute(pool.inner())
@@ -157,7 +168,9 @@ pub async fn delete_appointment(
 pub async fn cancel_appointment(
     pool: State<'_, SqlitePool>,
     appointment_id: i64,
+    session_token: String,
 ) -> Result<Appointment, String> {
+    plans::writable_crm_organization(pool.inner(), &session_token).await?;
     sqlx::query(
         "UPDATE appointments SET status = 'Cancelled', updated_at = CURRENT_TIMESTAMP WHERE id = ?",
     )
@@ -166,5 +179,5 @@ pub async fn cancel_appointment(
     .await
     .map_err(|e| format!("Failed to cancel appointment: {}", e))?;
 
-    get_appointment(pool, appointment_id).await
+    get_appointment(pool, appointment_id, session_token).await
 }
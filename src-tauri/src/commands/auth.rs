@@ -1,12 +1,20 @@
+use crate::activity;
+use crate::location_stock;
+use crate::lockout::{self, LockoutPolicy, LoginAttemptStatus};
+use crate::margins::DEFAULT_LOCATION_ID;
 use crate::models::{CreateUserRequest, LoginRequest, LoginResponse, User};
+use crate::password_reset;
+use crate::pin;
 use crate::session::SESSION_MANAGER;
+use crate::tenancy;
 use crate::validation;
 use bcrypt::{hash, verify, DEFAULT_COST};
+use chrono::Utc;
 use sqlx::{Row, SqlitePool};
 use tauri::{command, State};
 
 const GENERIC_AUTH_ERROR: &str = "Invalid username or password";
-const ACCOUNT_LOCKED_ERROR: &str = "Account temporarily locked due to multiple failed login attempts. Please try again later.";
+const ACCOUNT_LOCKED_ERROR: &str = "Account temporarily locked due to multiple failed login attempts.";
 
 #[command]
 pub async fn login_user(
@@ -15,15 +23,22 @@ pub async fn login_user(
 ) -> Result<LoginResponse, String> {
     // Validate input
     validation::validate_required(&request.username, "username")
-        .map_err(|e| e.message)?;
+        .map_err(|e| e.message())?;
     validation::validate_required(&request.password, "password")
-        .map_err(|e| e.message)?;
+        .map_err(|e| e.message())?;
 
     let pool_ref = pool.inner();
+    let policy = LockoutPolicy::load(pool_ref).await.map_err(|e| e.message())?;
+    let attempt_key = lockout::attempt_key(pool_ref, &request.username)
+        .await
+        .map_err(|e| e.message())?;
 
-    // Check if user is rate-limited
-    if SESSION_MANAGER.is_locked(&request.username) {
-        return Err(ACCOUNT_LOCKED_ERROR.to_string());
+    // Check if the account is locked out
+    let attempt_status = lockout::check_login_allowed(pool_ref, &attempt_key, &policy, Utc::now())
+        .await
+        .map_err(|e| e.message())?;
+    if let LoginAttemptStatus::Locked { .. } = attempt_status {
+        return Err(login_error_message(&attempt_status));
     }
 
     // Fetch user by username or email
@@ -42,8 +57,9 @@ pub async fn login_user(
     let row = match row {
         Some(r) => r,
         None => {
-            SESSION_MANAGER.record_failed_attempt(&request.username);
-            return Err(GENERIC_AUTH_ERROR.to_string());
+            return Err(
+                record_failed_login(pool_ref, &request.username, None, &attempt_key, &policy).await,
+            );
         }
     };
 
@@ -56,17 +72,32 @@ pub async fn login_user(
         .map_err(|e| format!("Password verification error: {}", e))?;
 
     if !password_valid {
-        SESSION_MANAGER.record_failed_attempt(&request.username);
-        return Err(GENERIC_AUTH_ERROR.to_string());
+        let id: Option<i64> = row.try_get("id").ok();
+        let message =
+            record_failed_login(pool_ref, &request.username, id, &attempt_key, &policy).await;
+        return Err(message);
     }
 
+    // Check the location the user is signing in at
+    let location_id = request.location_id.unwrap_or(DEFAULT_LOCATION_ID);
+    let mut conn = pool_ref.acquire().await.map_err(|e| e.to_string())?;
+    location_stock::require_active_location(&mut conn, location_id)
+        .await
+        .map_err(|e| e.message())?;
+
     // Extract user data
     let id: i64 = row.try_get("id").map_err(|e| e.to_string())?;
+    let organization_id = tenancy::home_organization(&mut conn, id)
+        .await
+        .map_err(|e| e.message())?;
+    drop(conn);
     let username: String = row.try_get("username").map_err(|e| e.to_string())?;
     let role: String = row.try_get("role").map_err(|e| e.to_string())?;
 
     // Clear failed attempts on successful login
-    SESSION_MANAGER.clear_failed_attempts(&username);
+    lockout::clear_failed_logins(pool_ref, &attempt_key)
+        .await
+        .map_err(|e| e.message())?;
 
     // Update last_login timestamp (best-effort, non-fatal)
     let _ = sqlx::query("UPDATE users SET last_login = CURRENT_TIMESTAMP WHERE id = ?1")
@@ -91,10 +122,107 @@ pub async fn login_user(
 
     // Create session
     let session_token = SESSION_MANAGER.create_session(id, username, role);
+    SESSION_MANAGER
+        .set_location(&session_token, location_id)
+        .map_err(|e| e.message())?;
+    SESSION_MANAGER
+        .set_organization(&session_token, organization_id)
+        .map_err(|e| e.message())?;
+    activity::record_activity(pool_ref, Some(id), &user.username, activity::EVENT_LOGIN, Some(location_id))
+        .await;
+
+    Ok(LoginResponse {
+        user,
+        session_token,
+        location_id,
+        organization_id,
+    })
+}
+
+/// Fast user switching at the register. PIN sessions are short-lived and
+/// cannot be used for administrative actions.
+#[command]
+pub async fn login_with_pin(
+    pool: State<'_, SqlitePool>,
+    username_or_badge: String,
+    pin: String,
+) -> Result<LoginResponse, String> {
+    validation::validate_required(&username_or_badge, "username_or_badge")
+        .map_err(|e| e.message())?;
+    validation::validate_required(&pin, "pin")
+        .map_err(|e| e.message())?;
+
+    let pool_ref = pool.inner();
+    let policy = LockoutPolicy::load(pool_ref).await.map_err(|e| e.message())?;
+    let attempt_key = lockout::attempt_key(pool_ref, &username_or_badge)
+        .await
+        .map_err(|e| e.message())?;
+
+    // PIN failures count towards the same lockout as password failures, per user whether
+    // they typed their username or their badge number
+    let attempt_status = lockout::check_login_allowed(pool_ref, &attempt_key, &policy, Utc::now())
+        .await
+        .map_err(|e| e.message())?;
+    if let LoginAttemptStatus::Locked { .. } = attempt_status {
+        return Err(login_error_message(&attempt_status));
+    }
+
+    let user_id = match pin::verify_user_pin(pool_ref, &username_or_badge, &pin)
+        .await
+        .map_err(|e| e.message())?
+    {
+        Some(id) => id,
+        None => {
+            let message = record_failed_login(
+                pool_ref,
+                &username_or_badge,
+                None,
+                &attempt_key,
+                &policy,
+            )
+            .await;
+            return Err(message);
+        }
+    };
+
+    lockout::clear_failed_logins(pool_ref, &attempt_key)
+        .await
+        .map_err(|e| e.message())?;
+
+    let _ = sqlx::query("UPDATE users SET last_login = CURRENT_TIMESTAMP WHERE id = ?1")
+        .bind(user_id)
+        .execute(pool_ref)
+        .await;
+
+    let row = sqlx::query(
+        "SELECT id, username, email, first_name, last_name, role, is_active,
+                profile_image_url, last_login, created_at, updated_at
+         FROM users
+         WHERE id = ?1",
+    )
+    .bind(user_id)
+    .fetch_one(pool_ref)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    let user = build_user_from_row(row)?;
+    let mut conn = pool_ref.acquire().await.map_err(|e| e.to_string())?;
+    let organization_id = tenancy::home_organization(&mut conn, user.id)
+        .await
+        .map_err(|e| e.message())?;
+    let session_token =
+        SESSION_MANAGER.create_pin_session(user.id, user.username.clone(), user.role.clone());
+    SESSION_MANAGER
+        .set_organization(&session_token, organization_id)
+        .map_err(|e| e.message())?;
+    activity::record_activity(pool_ref, Some(user.id), &user.username, activity::EVENT_PIN_LOGIN, None)
+        .await;
 
     Ok(LoginResponse {
         user,
         session_token,
+        location_id: DEFAULT_LOCATION_ID,
+        organization_id,
     })
 }
 
@@ -105,15 +233,15 @@ pub async fn register_user(
 ) -> Result<User, String> {
     // Validate all inputs
     validation::validate_username(&request.username)
-        .map_err(|e| e.message)?;
+        .map_err(|e| e.message())?;
     validation::validate_email(&request.email)
-        .map_err(|e| e.message)?;
+        .map_err(|e| e.message())?;
     validation::validate_password_strength(&request.password)
-        .map_err(|e| e.message)?;
+        .map_err(|e| e.message())?;
     validation::validate_required(&request.first_name, "first_name")
-        .map_err(|e| e.message)?;
+        .map_err(|e| e.message())?;
     validation::validate_required(&request.last_name, "last_name")
-        .map_err(|e| e.message)?;
+        .map_err(|e| e.message())?;
 
     let pool_ref = pool.inner();
 
@@ -163,6 +291,27 @@ pub async fn register_user(
     build_user_from_row(row)
 }
 
+#[command]
+pub async fn reset_password_with_code(
+    pool: State<'_, SqlitePool>,
+    username: String,
+    code: String,
+    new_password: String,
+) -> Result<bool, String> {
+    password_reset::reset_password_with_code(
+        pool.inner(),
+        &SESSION_MANAGER,
+        &username,
+        &code,
+        &new_password,
+        Utc::now(),
+    )
+    .await
+    .map_err(|e| e.message())?;
+
+    Ok(true)
+}
+
 #[command]
 pub async fn verify_session(session_token: String) -> Result<bool, String> {
     #[cfg(debug_assertions)]
@@ -179,8 +328,18 @@ pub async fn verify_session(session_token: String) -> Result<bool, String> {
 }
 
 #[command]
-pub async fn logout_user(session_token: String) -> Result<(), String> {
+pub async fn logout_user(pool: State<'_, SqlitePool>, session_token: String) -> Result<(), String> {
     if !session_token.is_empty() {
+        if let Ok(session) = SESSION_MANAGER.validate_session(&session_token) {
+            activity::record_activity(
+                pool.inner(),
+                Some(session.user_id),
+                &session.username,
+                activity::EVENT_LOGOUT,
+                Some(session.location_id),
+            )
+            .await;
+        }
         SESSION_MANAGER.remove_session(&session_token);
     }
 
@@ -204,8 +363,79 @@ pub async fn get_session_user(
     Ok(SESSION_MANAGER.get_session(&session_token))
 }
 
+/// Move a signed-in session to another store or branch
+#[command]
+pub async fn switch_location(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    location_id: i64,
+) -> Result<i64, String> {
+    SESSION_MANAGER
+        .validate_session(&session_token)
+        .map_err(|e| e.message())?;
+    let mut conn = pool.inner().acquire().await.map_err(|e| e.to_string())?;
+    location_stock::require_active_location(&mut conn, location_id)
+        .await
+        .map_err(|e| e.message())?;
+    let session = SESSION_MANAGER
+        .set_location(&session_token, location_id)
+        .map_err(|e| e.message())?;
+    Ok(session.location_id)
+}
+
+/// Move a signed-in session to another organization the user owns
+#[command]
+pub async fn switch_organization(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    organization_id: i64,
+) -> Result<i64, String> {
+    let session = SESSION_MANAGER
+        .validate_session(&session_token)
+        .map_err(|e| e.message())?;
+    let mut conn = pool.inner().acquire().await.map_err(|e| e.to_string())?;
+    tenancy::require_owner(&mut conn, organization_id, session.user_id)
+        .await
+        .map_err(|e| e.message())?;
+    let session = SESSION_MANAGER
+        .set_organization(&session_token, organization_id)
+        .map_err(|e| e.message())?;
+    Ok(session.organization_id)
+}
+
 // Helper functions
 
+/// Record a failed login under its lockout key and build the error shown to the user.
+/// The message is identical for unknown usernames and wrong passwords.
+async fn record_failed_login(
+    pool: &SqlitePool,
+    username: &str,
+    user_id: Option<i64>,
+    attempt_key: &str,
+    policy: &LockoutPolicy,
+) -> String {
+    activity::record_activity(pool, user_id, username, activity::EVENT_LOGIN_FAILED, None).await;
+    match lockout::record_failed_login(pool, attempt_key, policy, Utc::now()).await {
+        Ok(status) => login_error_message(&status),
+        Err(_) => GENERIC_AUTH_ERROR.to_string(),
+    }
+}
+
+/// Build the login error message including remaining attempts or lockout expiry
+fn login_error_message(status: &LoginAttemptStatus) -> String {
+    match status {
+        LoginAttemptStatus::Allowed { remaining_attempts } => format!(
+            "{}. {} attempt(s) remaining before the account is temporarily locked.",
+            GENERIC_AUTH_ERROR, remaining_attempts
+        ),
+        LoginAttemptStatus::Locked { locked_until } => format!(
+            "{} Try again after {} UTC.",
+            ACCOUNT_LOCKED_ERROR,
+            locked_until.format("%Y-%m-%d %H:%M:%S")
+        ),
+    }
+}
+
 /// Parse boolean field that might be stored as bool or integer
 fn parse_boolean_field(row: &sqlx::sqlite::SqliteRow, field_name: &str) -> Result<bool, String> {
     match row.try_get::<bool, _>(field_name) {
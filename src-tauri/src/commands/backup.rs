@@ -0,0 +1,151 @@
+use crate::archive::{self, ArchiveExport, ImportMode, ImportSummary};
+use crate::backup::{self, BackupInfo};
+use crate::database::{self, IntegrityReport};
+use crate::demo_mode;
+use crate::diagnostics::{self, SystemDiagnostics};
+use crate::seeder::{self, Industry, SeedSummary, TableRowCount};
+use crate::session::SESSION_MANAGER;
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Manager, State};
+
+pub(crate) fn app_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))
+}
+
+/// Back up the live database into the app's backup directory (admins only). `target_path`
+/// names the backup file there; without it the backup gets a timestamped name.
+#[command]
+pub async fn backup_database(
+    app: AppHandle,
+    pool: State<'_, SqlitePool>,
+    target_path: Option<String>,
+    session_token: String,
+) -> Result<BackupInfo, String> {
+    SESSION_MANAGER
+        .require_full_session(&session_token, &["Admin"])
+        .map_err(|e| e.message())?;
+    let pool_ref = pool.inner();
+    let dir = backup::backup_dir(&app_data_dir(&app)?);
+    let backup = match target_path.filter(|path| !path.trim().is_empty()) {
+        Some(name) => backup::backup_to(pool_ref, &backup::backup_target(&dir, &name)?).await?,
+        None => backup::create_backup(pool_ref, &dir).await?,
+    };
+    Ok(backup)
+}
+
+/// Restore a backup over the live database and restart the app.
+/// Returns the pre-restore safety copy of the replaced database.
+#[command]
+pub async fn restore_database(
+    app: AppHandle,
+    pool: State<'_, SqlitePool>,
+    source_path: String,
+    session_token: String,
+) -> Result<BackupInfo, String> {
+    SESSION_MANAGER
+        .require_full_session(&session_token, &["Admin"])
+        .map_err(|e| e.message())?;
+    demo_mode::require_live(pool.inner(), "Restoring a backup").await?;
+    let data_dir = app_data_dir(&app)?;
+    let safety_copy = backup::restore(
+        pool.inner(),
+        Path::new(source_path.trim()),
+        &backup::database_path(&data_dir),
+        &backup::backup_dir(&data_dir),
+    )
+    .await?;
+
+    // The pool is closed now; give the frontend a moment to receive the reply first
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        app.restart();
+    });
+
+    Ok(safety_copy)
+}
+
+#[command]
+pub async fn list_backups(
+    app: AppHandle,
+    session_token: String,
+) -> Result<Vec<BackupInfo>, String> {
+    SESSION_MANAGER
+        .require_full_session(&session_token, &["Admin"])
+        .map_err(|e| e.message())?;
+    Ok(backup::list_backups(&backup::backup_dir(&app_data_dir(
+        &app,
+    )?))?)
+}
+
+/// Export the session organization's business data to a zipped JSON archive for moving to
+/// another machine (admins only)
+#[command]
+pub async fn export_data_archive(
+    pool: State<'_, SqlitePool>,
+    path: String,
+    session_token: String,
+) -> Result<ArchiveExport, String> {
+    let session = SESSION_MANAGER
+        .require_full_session(&session_token, &["Admin"])
+        .map_err(|e| e.message())?;
+    let path = Path::new(path.trim());
+    Ok(archive::export_archive(pool.inner(), session.organization_id, path).await?)
+}
+
+/// Import a data archive into the session's organization, either replacing its archived
+/// records or merging by natural keys (admins only)
+#[command]
+pub async fn import_data_archive(
+    pool: State<'_, SqlitePool>,
+    path: String,
+    mode: ImportMode,
+    session_token: String,
+) -> Result<ImportSummary, String> {
+    let session = SESSION_MANAGER
+        .require_full_session(&session_token, &["Admin"])
+        .map_err(|e| e.message())?;
+    demo_mode::require_live(pool.inner(), "Importing a data archive").await?;
+    let path = Path::new(path.trim());
+    Ok(archive::import_archive(pool.inner(), session.organization_id, path, mode).await?)
+}
+
+/// Check the database for corruption and rows that break a foreign key, e.g. before an
+/// upgrade or after restoring an old backup. Reports only; nothing is changed.
+#[command]
+pub async fn database_integrity_report(pool: State<'_, SqlitePool>) -> Result<IntegrityReport, String> {
+    database::integrity_report(pool.inner()).await
+}
+
+/// App version, database location and size, schema version and sync backlog, for bug reports
+#[command]
+pub async fn system_diagnostics(pool: State<'_, SqlitePool>) -> Result<SystemDiagnostics, String> {
+    Ok(diagnostics::system_diagnostics(pool.inner()).await?)
+}
+
+/// Seed an industry's demo store (admins only). Refused once the store has real sales.
+#[command]
+pub async fn seed_demo_data(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    template: Industry,
+) -> Result<SeedSummary, String> {
+    SESSION_MANAGER
+        .require_full_session(&session_token, &["Admin"])
+        .map_err(|e| e.message())?;
+    Ok(seeder::seed_demo_data(pool.inner(), template).await?)
+}
+
+/// Remove everything the demo templates seeded, leaving the store's own data (admins only)
+#[command]
+pub async fn reset_demo_data(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<Vec<TableRowCount>, String> {
+    SESSION_MANAGER
+        .require_full_session(&session_token, &["Admin"])
+        .map_err(|e| e.message())?;
+    Ok(seeder::reset_demo_data(pool.inner()).await?)
+}
@@ -0,0 +1,350 @@
+// src-tauri/src/commands/campaigns.rs - Revenue and return of marketing campaigns
+use crate::cost_history::SALE_ITEM_COST_SQL;
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use crate::plans;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use tauri::{command, State};
+
+/// What a campaign brought in through its promotions. A sale counts once however many
+/// of the campaign's promotions it used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignPerformance {
+    pub campaign_id: i64,
+    pub name: String,
+    pub status: Option<String>,
+    pub budget: Money,
+    pub actual_cost: Money,
+    pub promotion_count: i64,
+    /// Times the campaign's promotions were used
+    pub uses: i64,
+    pub discounted_amount: Money,
+    /// Sales made with the campaign's promotions
+    pub attributed_sales: i64,
+    pub attributed_revenue: Money,
+    /// Line totals of those sales less their cost
+    pub gross_profit: Money,
+    /// Actual cost per attributed sale; none without sales
+    pub cost_per_acquisition: Option<Money>,
+    /// Gross profit less actual cost, as a percent of actual cost; none for a campaign
+    /// that cost nothing
+    pub roi_percent: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignRoiReport {
+    pub start_date: String,
+    pub end_date: String,
+    /// Best return first; campaigns without a cost come last
+    pub campaigns: Vec<CampaignPerformance>,
+}
+
+#[derive(FromRow)]
+struct CampaignRow {
+    id: i64,
+    name: String,
+    status: Option<String>,
+    budget: Option<Money>,
+    actual_cost: Option<Money>,
+}
+
+/// Non-voided, non-demo sales that used one of campaign `?1`'s promotions, between the
+/// optional dates `?2` and `?3`
+const ATTRIBUTED_SALES_SQL: &str = "SELECT DISTINCT pu.sale_id FROM promotion_usage pu
+    JOIN promotions p ON p.id = pu.promotion_id
+    JOIN sales s ON s.id = pu.sale_id
+    WHERE p.campaign_id = ?1 AND s.is_voided = 0 AND s.is_demo = 0
+      AND (?2 IS NULL OR DATE(s.created_at) >= ?2)
+      AND (?3 IS NULL OR DATE(s.created_at) <= ?3)";
+
+async fn campaign_performance(
+    pool: &SqlitePool,
+    campaign: CampaignRow,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+) -> AppResult<CampaignPerformance> {
+    let promotion_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM promotions WHERE campaign_id = ?1")
+            .bind(campaign.id)
+            .fetch_one(pool)
+            .await?;
+
+    let (uses, discounted_amount): (i64, Money) = sqlx::query_as(
+        "SELECT COUNT(*), COALESCE(SUM(pu.discount_amount), 0.0)
+         FROM promotion_usage pu
+         JOIN promotions p ON p.id = pu.promotion_id
+         JOIN sales s ON s.id = pu.sale_id
+         WHERE p.campaign_id = ?1 AND s.is_voided = 0 AND s.is_demo = 0
+           AND (?2 IS NULL OR DATE(s.created_at) >= ?2)
+           AND (?3 IS NULL OR DATE(s.created_at) <= ?3)",
+    )
+    .bind(campaign.id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(pool)
+    .await?;
+
+    let (attributed_sales, attributed_revenue): (i64, Money) = sqlx::query_as(&format!(
+        "SELECT COUNT(*), COALESCE(SUM(total_amount), 0.0) FROM sales WHERE id IN ({})",
+        ATTRIBUTED_SALES_SQL
+    ))
+    .bind(campaign.id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(pool)
+    .await?;
+
+    let gross_profit: Money = sqlx::query_scalar(&format!(
+        "SELECT COALESCE(SUM(si.line_total - {cost} * si.quantity), 0.0)
+         FROM sale_items si JOIN sales s ON s.id = si.sale_id
+         WHERE s.id IN ({attributed})",
+        cost = SALE_ITEM_COST_SQL,
+        attributed = ATTRIBUTED_SALES_SQL
+    ))
+    .bind(campaign.id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(pool)
+    .await?;
+
+    let actual_cost = campaign.actual_cost.unwrap_or_default();
+    let cost_per_acquisition =
+        (attributed_sales > 0).then(|| Money::from_minor(actual_cost.minor() / attributed_sales));
+    let roi_percent = (actual_cost > Money::ZERO)
+        .then(|| (gross_profit - actual_cost).to_major() / actual_cost.to_major() * 100.0);
+
+    Ok(CampaignPerformance {
+        campaign_id: campaign.id,
+        name: campaign.name,
+        status: campaign.status,
+        budget: campaign.budget.unwrap_or_default(),
+        actual_cost,
+        promotion_count,
+        uses,
+        discounted_amount,
+        attributed_sales,
+        attributed_revenue,
+        gross_profit,
+        cost_per_acquisition,
+        roi_percent,
+    })
+}
+
+pub(crate) async fn get_campaign_performance_internal(
+    pool: &SqlitePool,
+    campaign_id: i64,
+) -> AppResult<CampaignPerformance> {
+    let campaign = sqlx::query_as::<_, CampaignRow>(
+        "SELECT id, name, status, budget, actual_cost FROM campaigns WHERE id = ?1",
+    )
+    .bind(campaign_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Campaign"))?;
+    campaign_performance(pool, campaign, None, None).await
+}
+
+/// Uses, discounts, attributed revenue and profit of a campaign over its whole life
+#[command]
+pub async fn get_campaign_performance(
+    pool: State<'_, SqlitePool>,
+    campaign_id: i64,
+    session_token: String,
+) -> Result<CampaignPerformance, AppError> {
+    plans::crm_organization(pool.inner(), &session_token).await?;
+    get_campaign_performance_internal(pool.inner(), campaign_id).await
+}
+
+/// Campaigns running at some point between the dates, ranked by return on their cost.
+/// Only sales made between the dates are attributed.
+pub(crate) async fn get_campaign_roi_report_internal(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+) -> AppResult<CampaignRoiReport> {
+    let rows = sqlx::query_as::<_, CampaignRow>(
+        "SELECT id, name, status, budget, actual_cost FROM campaigns
+         WHERE start_date <= ?2 AND (end_date IS NULL OR end_date >= ?1)
+         ORDER BY id",
+    )
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+
+    let mut campaigns = Vec::with_capacity(rows.len());
+    for row in rows {
+        campaigns.push(campaign_performance(pool, row, Some(start_date), Some(end_date)).await?);
+    }
+    campaigns.sort_by(|a, b| match (a.roi_percent, b.roi_percent) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+
+    Ok(CampaignRoiReport {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        campaigns,
+    })
+}
+
+#[command]
+pub async fn get_campaign_roi_report(
+    pool: State<'_, SqlitePool>,
+    start_date: String,
+    end_date: String,
+    session_token: String,
+) -> Result<CampaignRoiReport, AppError> {
+    plans::crm_organization(pool.inner(), &session_token).await?;
+    get_campaign_roi_report_internal(pool.inner(), &start_date, &end_date).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::promotions::redeem_promotion_internal;
+    use crate::commands::sales::create_sale_internal;
+    use crate::models::{CreateSaleRequest, SaleItemRequest};
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    fn sale(product_id: i64, quantity: i32) -> CreateSaleRequest {
+        let line_total = Money::from_major(10.0).times(quantity);
+        CreateSaleRequest {
+            items: vec![SaleItemRequest {
+                product_id,
+                quantity,
+                unit_price: Money::from_major(10.0),
+                discount_amount: Money::ZERO,
+                line_total,
+                measured_quantity: None,
+                price_override: None,
+                override_reason: None,
+            }],
+            subtotal: line_total,
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: line_total,
+            payment_method: "cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        }
+    }
+
+    async fn insert_campaign(pool: &SqlitePool, name: &str, actual_cost: f64) -> i64 {
+        sqlx::query(
+            "INSERT INTO campaigns (name, campaign_type, start_date, budget, actual_cost)
+             VALUES (?1, 'Email', '2000-01-01', 100, ?2)",
+        )
+        .bind(name)
+        .bind(actual_cost)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+    }
+
+    async fn insert_promotion(
+        pool: &SqlitePool,
+        code: &str,
+        discount_type: &str,
+        value: f64,
+        campaign_id: Option<i64>,
+    ) {
+        sqlx::query(
+            "INSERT INTO promotions (code, name, discount_type, discount_value, start_date, campaign_id)
+             VALUES (?1, ?1, ?2, ?3, '2000-01-01', ?4)",
+        )
+        .bind(code)
+        .bind(discount_type)
+        .bind(value)
+        .bind(campaign_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_campaign_performance_attributes_sales_of_its_promotions() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        // Sells at 10.00 and costs 5.00
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 50).await;
+        let spring = insert_campaign(&pool, "Spring", 6.0).await;
+        let flyer = insert_campaign(&pool, "Flyer", 40.0).await;
+        let free = insert_campaign(&pool, "Word of mouth", 0.0).await;
+        insert_promotion(&pool, "SPRING10", "Percentage", 10.0, Some(spring)).await;
+        insert_promotion(&pool, "SPRING5", "Fixed Amount", 5.0, Some(spring)).await;
+        insert_promotion(&pool, "FLYER", "Fixed Amount", 1.0, Some(flyer)).await;
+        insert_promotion(&pool, "WALKIN", "Fixed Amount", 1.0, None).await;
+
+        for (quantity, code) in [(2, "SPRING10"), (3, "SPRING5"), (4, "FLYER"), (1, "WALKIN")] {
+            let sold = create_sale_internal(&pool, sale(widget, quantity), cashier, None)
+                .await
+                .unwrap();
+            redeem_promotion_internal(&pool, code, sold.id, None)
+                .await
+                .unwrap();
+        }
+
+        let performance = get_campaign_performance_internal(&pool, spring)
+            .await
+            .unwrap();
+        assert_eq!(performance.promotion_count, 2);
+        assert_eq!(performance.uses, 2);
+        // 10% of 20.00 and 5.00 off 30.00
+        assert_eq!(performance.discounted_amount, Money::from_major(7.0));
+        assert_eq!(performance.attributed_sales, 2);
+        assert_eq!(performance.attributed_revenue, Money::from_major(50.0));
+        assert_eq!(performance.gross_profit, Money::from_major(25.0));
+        assert_eq!(
+            performance.cost_per_acquisition,
+            Some(Money::from_major(3.0))
+        );
+        let roi = performance.roi_percent.unwrap();
+        assert!((roi - 316.67).abs() < 0.01, "{}", roi);
+
+        let err = get_campaign_performance_internal(&pool, 9999)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+
+        // Flyer: 20.00 profit on a 40.00 cost; the campaign that cost nothing ranks last
+        let report = get_campaign_roi_report_internal(&pool, "2000-01-01", "2999-12-31")
+            .await
+            .unwrap();
+        let ranked: Vec<(i64, Option<f64>)> = report
+            .campaigns
+            .iter()
+            .map(|campaign| {
+                (
+                    campaign.campaign_id,
+                    campaign.roi_percent.map(|roi| roi.round()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![(spring, Some(317.0)), (flyer, Some(-50.0)), (free, None)]
+        );
+
+        // Nothing was sold in 2001
+        let report = get_campaign_roi_report_internal(&pool, "2001-01-01", "2001-12-31")
+            .await
+            .unwrap();
+        assert!(report
+            .campaigns
+            .iter()
+            .all(|campaign| campaign.attributed_sales == 0));
+    }
+}
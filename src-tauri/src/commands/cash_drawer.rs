@@ -1,13 +1,22 @@
 use tauri::{command, State};
 use sqlx::{SqlitePool, Row};
+use crate::error::{AppError, AppResult};
+use crate::margins::DEFAULT_LOCATION_ID;
 use crate::models::{CashDrawerTransaction, CreateCashDrawerTransactionRequest};
+use crate::money::Money;
+use crate::plans;
+use crate::printer;
+use crate::validation::validate_required;
+use serde::{Deserialize, Serialize};
 
 #[command]
 pub async fn create_transaction(
     pool: State<'_, SqlitePool>,
     user_id: i64,
     request: CreateCashDrawerTransactionRequest,
+    session_token: String,
 ) -> Result<CashDrawerTransaction, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
     
     // Verify shift exists and is open
@@ -116,29 +125,21 @@ pub async fn get_transactions(
     Ok(transactions)
 }
 
-#[command]
-pub async fn get_cash_drawer_balance(
-    pool: State<'_, SqlitePool>,
-    shift_id: i64,
-) -> Result<f64, String> {
-    let pool_ref = pool.inner();
-    
+/// Cash that should be in the drawer for a shift
+pub(crate) async fn cash_drawer_balance(pool: &SqlitePool, shift_id: i64) -> AppResult<Money> {
     // Get shift opening amount
-    let shift = sqlx::query(
-        "SELECT opening_amount FROM shifts WHERE id = ?1"
-    )
-    .bind(shift_id)
-    .fetch_one(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to fetch shift: {}", e))?;
+    let opening_amount: Money =
+        sqlx::query_scalar("SELECT opening_amount FROM shifts WHERE id = ?1")
+            .bind(shift_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("Shift"))?;
 
-    let opening_amount: f64 = shift.try_get("opening_amount").map_err(|e| e.to_string())?;
-    
-    // Calculate net cash flow from transactions
-    let cash_flow = sqlx::query(
+    // Calculate net cash flow from transactions; refunds are already stored negative
+    let net_flow: Money = sqlx::query_scalar(
         "SELECT COALESCE(SUM(
             CASE 
-                WHEN transaction_type IN ('opening', 'deposit') THEN amount
+                WHEN transaction_type IN ('opening', 'deposit', 'refund') THEN amount
                 WHEN transaction_type IN ('withdrawal', 'adjustment') THEN -amount
                 ELSE 0
             END
@@ -146,38 +147,208 @@ pub async fn get_cash_drawer_balance(
          FROM cash_drawer_transactions WHERE shift_id = ?1"
     )
     .bind(shift_id)
-    .fetch_one(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to calculate cash flow: {}", e))?;
+    .fetch_one(pool)
+    .await?;
 
-    let net_flow: f64 = cash_flow.try_get("net_flow").map_err(|e| e.to_string())?;
-    
     // Calculate cash sales
-    let cash_sales = sqlx::query(
+    let total_cash_sales: Money = sqlx::query_scalar(
         "SELECT COALESCE(SUM(total_amount), 0) as total_cash_sales
          FROM sales WHERE shift_id = ?1 AND payment_method = 'Cash' AND is_voided = 0"
     )
     .bind(shift_id)
-    .fetch_one(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to calculate cash sales: {}", e))?;
+    .fetch_one(pool)
+    .await?;
 
-    let total_cash_sales: f64 = cash_sales.try_get("total_cash_sales").map_err(|e| e.to_string())?;
-    
-    // Calculate cash returns/refunds
-    let cash_returns = sqlx::query(
+    // Cash rounding is always taken in cash, whatever else the sale was paid with
+    let cash_rounding: Money = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(cash_rounding), 0) FROM sales WHERE shift_id = ?1 AND is_voided = 0",
+    )
+    .bind(shift_id)
+    .fetch_one(pool)
+    .await?;
+
+    // Calculate cash refunds from the legacy returns table, which has no drawer rows
+    let total_cash_returns: Money = sqlx::query_scalar(
         "SELECT COALESCE(SUM(total_amount), 0) as total_cash_returns
          FROM returns WHERE shift_id = ?1 AND refund_method = 'Cash'"
     )
     .bind(shift_id)
-    .fetch_one(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to calculate cash returns: {}", e))?;
+    .fetch_one(pool)
+    .await?;
 
-    let total_cash_returns: f64 = cash_returns.try_get("total_cash_returns").map_err(|e| e.to_string())?;
-    
-    // Final balance = opening + net flow + cash sales - cash returns
-    let balance = opening_amount + net_flow + total_cash_sales - total_cash_returns;
-    
-    Ok(balance)
+    // Final balance = opening + net flow + cash sales + rounding - cash returns
+    Ok(opening_amount + net_flow + total_cash_sales + cash_rounding - total_cash_returns)
+}
+
+#[command]
+pub async fn get_cash_drawer_balance(
+    pool: State<'_, SqlitePool>,
+    shift_id: i64,
+) -> Result<Money, String> {
+    Ok(cash_drawer_balance(pool.inner(), shift_id).await?)
+}
+
+/// A drawer opening without a sale, and how many the shift has had
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoSaleEvent {
+    pub transaction: CashDrawerTransaction,
+    pub shift_no_sale_count: i64,
+    /// The shift has gone over the store's no-sale threshold
+    pub threshold_exceeded: bool,
+}
+
+/// Log a no-sale drawer opening against the user's open shift. The first time a shift goes
+/// over the threshold, managers get a warning notification.
+pub(crate) async fn record_no_sale(
+    pool: &SqlitePool,
+    user_id: i64,
+    reason: &str,
+) -> AppResult<NoSaleEvent> {
+    validate_required(reason, "reason")?;
+
+    let mut tx = pool.begin().await?;
+    let shift_id: i64 =
+        sqlx::query_scalar("SELECT id FROM shifts WHERE user_id = ?1 AND status = 'open'")
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| {
+                AppError::validation("shift_id", "Open a shift before opening the cash drawer")
+            })?;
+
+    let transaction_id = sqlx::query(
+        "INSERT INTO cash_drawer_transactions (shift_id, transaction_type, amount, reason, user_id)
+         VALUES (?1, 'no_sale', 0, ?2, ?3)",
+    )
+    .bind(shift_id)
+    .bind(reason.trim())
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    let shift_no_sale_count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM cash_drawer_transactions WHERE shift_id = ?1 AND transaction_type = 'no_sale'",
+    )
+    .bind(shift_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    let threshold: i64 =
+        sqlx::query_scalar("SELECT no_sale_alert_threshold FROM locations WHERE id = ?1")
+            .bind(DEFAULT_LOCATION_ID)
+            .fetch_optional(&mut *tx)
+            .await?
+            .unwrap_or(5);
+    let threshold_exceeded = shift_no_sale_count > threshold;
+
+    if threshold_exceeded {
+        sqlx::query(
+            "INSERT INTO notifications (notification_type, title, message, severity, reference_id, reference_type)
+             SELECT 'no_sale', 'Frequent No-Sales',
+                    COALESCE(u.first_name || ' ' || u.last_name, 'A cashier') || ' has opened the drawer without a sale '
+                        || ?2 || ' times this shift',
+                    'warning', ?1, 'shift'
+             FROM shifts s
+             LEFT JOIN users u ON u.id = s.user_id
+             WHERE s.id = ?1
+             AND NOT EXISTS (
+                SELECT 1 FROM notifications n
+                WHERE n.notification_type = 'no_sale' AND n.reference_id = ?1 AND n.reference_type = 'shift'
+             )",
+        )
+        .bind(shift_id)
+        .bind(shift_no_sale_count)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let row = sqlx::query(
+        "SELECT id, shift_id, transaction_type, amount, reason, user_id, created_at
+         FROM cash_drawer_transactions WHERE id = ?1",
+    )
+    .bind(transaction_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    let transaction = CashDrawerTransaction {
+        id: row.try_get("id")?,
+        shift_id: row.try_get("shift_id")?,
+        transaction_type: row.try_get("transaction_type")?,
+        amount: row.try_get("amount")?,
+        reason: row.try_get("reason")?,
+        user_id: row.try_get("user_id")?,
+        created_at: row.try_get("created_at")?,
+    };
+    tx.commit().await?;
+
+    Ok(NoSaleEvent {
+        transaction,
+        shift_no_sale_count,
+        threshold_exceeded,
+    })
+}
+
+/// Pop the cash drawer without a sale and log who did it and why
+#[command]
+pub async fn open_cash_drawer(
+    pool: State<'_, SqlitePool>,
+    user_id: i64,
+    reason: String,
+    session_token: String,
+) -> Result<NoSaleEvent, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    validate_required(&reason, "reason")?;
+    let receipt_printer = printer::receipt_printer(pool.inner()).await?;
+    printer::send_raw(&receipt_printer, &printer::DRAWER_KICK).await?;
+    record_no_sale(pool.inner(), user_id, &reason).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_user, test_pool};
+
+    #[tokio::test]
+    async fn test_no_sales_are_logged_and_alert_once_over_threshold() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+
+        let err = record_no_sale(&pool, cashier, "Change for the bus").await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+
+        sqlx::query("UPDATE locations SET no_sale_alert_threshold = 2 WHERE id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let shift_id = sqlx::query(
+            "INSERT INTO shifts (user_id, start_time, opening_amount, status)
+             VALUES (?1, CURRENT_TIMESTAMP, 100, 'open')",
+        )
+        .bind(cashier)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+
+        let first = record_no_sale(&pool, cashier, "Change for a 50").await.unwrap();
+        assert_eq!(first.transaction.transaction_type, "no_sale");
+        assert_eq!(first.transaction.shift_id, shift_id);
+        assert_eq!(first.transaction.user_id, cashier);
+        assert_eq!(first.transaction.reason.as_deref(), Some("Change for a 50"));
+        assert!(!first.threshold_exceeded);
+
+        record_no_sale(&pool, cashier, "Check float").await.unwrap();
+        let third = record_no_sale(&pool, cashier, "Customer query").await.unwrap();
+        assert_eq!(third.shift_no_sale_count, 3);
+        assert!(third.threshold_exceeded);
+        record_no_sale(&pool, cashier, "Change").await.unwrap();
+
+        let alerts: Vec<String> = sqlx::query_scalar(
+            "SELECT message FROM notifications WHERE notification_type = 'no_sale' AND reference_id = ?1",
+        )
+        .bind(shift_id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(alerts, vec!["Test User has opened the drawer without a sale 3 times this shift"]);
+    }
 }
@@ -0,0 +1,387 @@
+// src-tauri/src/commands/customer_display.rs - Live cart feed for the customer-facing screen
+use crate::error::{AppError, AppResult};
+use crate::margins::DEFAULT_LOCATION_ID;
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter, Manager, State, WebviewUrl, WebviewWindowBuilder};
+
+/// Event the customer display window listens on
+pub const CUSTOMER_DISPLAY_EVENT: &str = "customer-display://update";
+/// Label of the secondary webview window
+pub const CUSTOMER_DISPLAY_WINDOW: &str = "customer-display";
+/// Cart edits closer together than this are coalesced into one update
+pub const CART_UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What the customer screen is showing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum DisplayPhase {
+    /// No customer at the till: branding only
+    Idle,
+    Cart,
+    Payment,
+    Completed { sale_id: i64 },
+}
+
+/// A cart line as the customer sees it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplayLine {
+    pub name: String,
+    pub quantity: f64,
+    pub unit_price: Money,
+    #[serde(default)]
+    pub discount_amount: Money,
+    pub line_total: Money,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DisplayTotals {
+    pub subtotal: Money,
+    pub discount_amount: Money,
+    pub tax_amount: Money,
+    pub total_amount: Money,
+}
+
+/// Store name and contact details shown around the cart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct StoreBranding {
+    pub name: String,
+    pub address: Option<String>,
+    pub city: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub currency: Option<String>,
+}
+
+/// Payload of every customer display event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomerDisplayUpdate {
+    #[serde(flatten)]
+    pub phase: DisplayPhase,
+    pub items: Vec<DisplayLine>,
+    pub totals: DisplayTotals,
+    pub branding: Option<StoreBranding>,
+    /// Increases with every change so the window can drop stale events
+    pub sequence: u64,
+}
+
+/// When a state change should reach the window
+#[derive(Debug, PartialEq)]
+pub enum Emission {
+    Now(Box<CustomerDisplayUpdate>),
+    /// Throttled: emit the latest state once this much time has passed
+    After(Duration),
+    /// Throttled and a delayed emission is already scheduled
+    Scheduled,
+}
+
+#[derive(Debug)]
+struct DisplayState {
+    phase: DisplayPhase,
+    items: Vec<DisplayLine>,
+    totals: DisplayTotals,
+    branding: Option<StoreBranding>,
+    sequence: u64,
+    last_emitted: Option<Instant>,
+    pending: bool,
+}
+
+impl DisplayState {
+    fn snapshot(&self) -> CustomerDisplayUpdate {
+        CustomerDisplayUpdate {
+            phase: self.phase.clone(),
+            items: self.items.clone(),
+            totals: self.totals.clone(),
+            branding: self.branding.clone(),
+            sequence: self.sequence,
+        }
+    }
+
+    fn emit_now(&mut self, now: Instant) -> CustomerDisplayUpdate {
+        self.last_emitted = Some(now);
+        self.pending = false;
+        self.snapshot()
+    }
+}
+
+/// State machine the POS drives; managed as Tauri state
+#[derive(Debug)]
+pub struct CustomerDisplay {
+    state: Mutex<DisplayState>,
+}
+
+impl Default for CustomerDisplay {
+    fn default() -> Self {
+        CustomerDisplay {
+            state: Mutex::new(DisplayState {
+                phase: DisplayPhase::Idle,
+                items: Vec::new(),
+                totals: DisplayTotals::default(),
+                branding: None,
+                sequence: 0,
+                last_emitted: None,
+                pending: false,
+            }),
+        }
+    }
+}
+
+impl CustomerDisplay {
+    pub fn current(&self) -> CustomerDisplayUpdate {
+        self.state.lock().unwrap().snapshot()
+    }
+
+    pub fn set_branding(&self, branding: Option<StoreBranding>) {
+        self.state.lock().unwrap().branding = branding;
+    }
+
+    /// The cart changed; an empty cart returns the screen to idle.
+    /// Updates are throttled to one per `CART_UPDATE_INTERVAL`, the last one always delivered.
+    pub fn cart_updated(&self, items: Vec<DisplayLine>, totals: DisplayTotals, now: Instant) -> Emission {
+        let mut state = self.state.lock().unwrap();
+        state.phase = if items.is_empty() {
+            DisplayPhase::Idle
+        } else {
+            DisplayPhase::Cart
+        };
+        state.items = items;
+        state.totals = totals;
+        state.sequence += 1;
+
+        let since_last = state.last_emitted.map(|at| now.saturating_duration_since(at));
+        match since_last {
+            Some(elapsed) if elapsed < CART_UPDATE_INTERVAL => {
+                if state.pending {
+                    Emission::Scheduled
+                } else {
+                    state.pending = true;
+                    Emission::After(CART_UPDATE_INTERVAL - elapsed)
+                }
+            }
+            _ => Emission::Now(Box::new(state.emit_now(now))),
+        }
+    }
+
+    /// The cashier moved to payment; only a cart with items can be paid
+    pub fn payment_started(&self, now: Instant) -> AppResult<CustomerDisplayUpdate> {
+        let mut state = self.state.lock().unwrap();
+        if state.phase != DisplayPhase::Cart || state.items.is_empty() {
+            return Err(AppError::Conflict {
+                message: "Payment can only start on a cart with items".to_string(),
+            });
+        }
+        state.phase = DisplayPhase::Payment;
+        state.sequence += 1;
+        Ok(state.emit_now(now))
+    }
+
+    /// The sale went through; the screen thanks the customer until the next cart starts
+    pub fn sale_completed(&self, sale_id: i64, now: Instant) -> AppResult<CustomerDisplayUpdate> {
+        let mut state = self.state.lock().unwrap();
+        if !matches!(state.phase, DisplayPhase::Cart | DisplayPhase::Payment) {
+            return Err(AppError::Conflict {
+                message: "There is no sale in progress on the customer display".to_string(),
+            });
+        }
+        state.phase = DisplayPhase::Completed { sale_id };
+        state.sequence += 1;
+        Ok(state.emit_now(now))
+    }
+
+    /// The throttled cart update, if one is still waiting to go out
+    pub fn take_pending(&self, now: Instant) -> Option<CustomerDisplayUpdate> {
+        let mut state = self.state.lock().unwrap();
+        if state.pending {
+            Some(state.emit_now(now))
+        } else {
+            None
+        }
+    }
+}
+
+async fn store_branding(pool: &SqlitePool) -> AppResult<Option<StoreBranding>> {
+    Ok(sqlx::query_as::<_, StoreBranding>(
+        "SELECT name, address, city, phone, email, currency FROM locations WHERE id = ?1",
+    )
+    .bind(DEFAULT_LOCATION_ID)
+    .fetch_optional(pool)
+    .await?)
+}
+
+fn send(app: &AppHandle, update: &CustomerDisplayUpdate) -> AppResult<()> {
+    app.emit_to(CUSTOMER_DISPLAY_WINDOW, CUSTOMER_DISPLAY_EVENT, update.clone())
+        .map_err(|e| AppError::Internal {
+            message: format!("Failed to update customer display: {}", e),
+        })
+}
+
+#[command]
+pub async fn cart_updated(
+    app: AppHandle,
+    pool: State<'_, SqlitePool>,
+    display: State<'_, CustomerDisplay>,
+    items: Vec<DisplayLine>,
+    totals: DisplayTotals,
+) -> Result<CustomerDisplayUpdate, AppError> {
+    display.set_branding(store_branding(pool.inner()).await?);
+    match display.cart_updated(items, totals, Instant::now()) {
+        Emission::Now(update) => send(&app, &update)?,
+        Emission::After(delay) => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(delay).await;
+                if let Some(update) = app.state::<CustomerDisplay>().take_pending(Instant::now()) {
+                    if let Err(e) = send(&app, &update) {
+                        eprintln!("⚠️  Warning: {}", e.message());
+                    }
+                }
+            });
+        }
+        Emission::Scheduled => {}
+    }
+    Ok(display.current())
+}
+
+#[command]
+pub async fn payment_started(
+    app: AppHandle,
+    display: State<'_, CustomerDisplay>,
+) -> Result<CustomerDisplayUpdate, AppError> {
+    let update = display.payment_started(Instant::now())?;
+    send(&app, &update)?;
+    Ok(update)
+}
+
+#[command]
+pub async fn sale_completed(
+    app: AppHandle,
+    display: State<'_, CustomerDisplay>,
+    sale_id: i64,
+) -> Result<CustomerDisplayUpdate, AppError> {
+    let update = display.sale_completed(sale_id, Instant::now())?;
+    send(&app, &update)?;
+    Ok(update)
+}
+
+/// Current screen state, for a display window that has just opened
+#[command]
+pub async fn get_customer_display_state(
+    pool: State<'_, SqlitePool>,
+    display: State<'_, CustomerDisplay>,
+) -> Result<CustomerDisplayUpdate, AppError> {
+    display.set_branding(store_branding(pool.inner()).await?);
+    Ok(display.current())
+}
+
+/// Open the customer-facing window, or bring it forward if it is already open
+#[command]
+pub async fn open_customer_display_window(app: AppHandle) -> Result<(), AppError> {
+    let to_internal = |e: tauri::Error| AppError::Internal {
+        message: format!("Failed to open customer display: {}", e),
+    };
+    if let Some(window) = app.get_webview_window(CUSTOMER_DISPLAY_WINDOW) {
+        window.show().map_err(to_internal)?;
+        return window.set_focus().map_err(to_internal);
+    }
+    WebviewWindowBuilder::new(
+        &app,
+        CUSTOMER_DISPLAY_WINDOW,
+        WebviewUrl::App("index.html#/customer-display".into()),
+    )
+    .title("Customer Display")
+    .inner_size(1024.0, 768.0)
+    .build()
+    .map_err(to_internal)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(name: &str, quantity: f64, unit_price: i64) -> DisplayLine {
+        DisplayLine {
+            name: name.to_string(),
+            quantity,
+            unit_price: Money::from_minor(unit_price),
+            discount_amount: Money::ZERO,
+            line_total: Money::from_minor(unit_price).times_decimal(quantity),
+        }
+    }
+
+    #[test]
+    fn test_display_state_transitions() {
+        let display = CustomerDisplay::default();
+        let start = Instant::now();
+
+        assert_eq!(display.payment_started(start).unwrap_err().code(), "CONFLICT");
+        assert_eq!(display.sale_completed(1, start).unwrap_err().code(), "CONFLICT");
+
+        let cart = vec![line("Cement", 2.0, 900)];
+        let Emission::Now(update) = display.cart_updated(cart, DisplayTotals::default(), start) else {
+            panic!("first cart update should go out immediately");
+        };
+        assert_eq!(update.phase, DisplayPhase::Cart);
+
+        let paying = display.payment_started(start).unwrap();
+        assert_eq!(paying.phase, DisplayPhase::Payment);
+        let done = display.sale_completed(42, start).unwrap();
+        assert_eq!(done.phase, DisplayPhase::Completed { sale_id: 42 });
+        assert_eq!(display.payment_started(start).unwrap_err().code(), "CONFLICT");
+
+        // The next customer's first scan replaces the thank-you screen; clearing the cart idles it
+        let later = start + CART_UPDATE_INTERVAL;
+        assert!(matches!(
+            display.cart_updated(vec![line("Sand", 1.0, 200)], DisplayTotals::default(), later),
+            Emission::Now(_)
+        ));
+        let cleared_at = later + CART_UPDATE_INTERVAL;
+        let Emission::Now(cleared) = display.cart_updated(Vec::new(), DisplayTotals::default(), cleared_at) else {
+            panic!("update after the interval should go out immediately");
+        };
+        assert_eq!(cleared.phase, DisplayPhase::Idle);
+        assert_eq!(display.payment_started(later).unwrap_err().code(), "CONFLICT");
+    }
+
+    #[test]
+    fn test_rapid_cart_edits_are_coalesced() {
+        let display = CustomerDisplay::default();
+        let start = Instant::now();
+        let totals = DisplayTotals::default();
+
+        assert!(matches!(
+            display.cart_updated(vec![line("Nails", 1.0, 10)], totals.clone(), start),
+            Emission::Now(_)
+        ));
+        let soon = start + Duration::from_millis(100);
+        assert_eq!(
+            display.cart_updated(vec![line("Nails", 2.0, 10)], totals.clone(), soon),
+            Emission::After(CART_UPDATE_INTERVAL - Duration::from_millis(100))
+        );
+        assert_eq!(
+            display.cart_updated(vec![line("Nails", 3.0, 10)], totals.clone(), soon),
+            Emission::Scheduled
+        );
+
+        // The delayed emission carries the latest cart, and only once
+        let pending = display.take_pending(start + CART_UPDATE_INTERVAL).unwrap();
+        assert_eq!(pending.items[0].quantity, 3.0);
+        assert_eq!(pending.sequence, 3);
+        assert!(display.take_pending(start + CART_UPDATE_INTERVAL).is_none());
+
+        // Phase changes are never throttled
+        assert_eq!(display.payment_started(start + CART_UPDATE_INTERVAL).unwrap().sequence, 4);
+
+        let json = serde_json::to_value(&pending).unwrap();
+        assert_eq!(json["phase"], "cart");
+        assert_eq!(json["items"][0]["line_total"], 0.3);
+        assert_eq!(json["totals"]["total_amount"], 0.0);
+        assert!(json["branding"].is_null());
+        let done = serde_json::to_value(display.sale_completed(7, start).unwrap()).unwrap();
+        assert_eq!(done["phase"], "completed");
+        assert_eq!(done["sale_id"], 7);
+    }
+}
@@ -1,5 +1,11 @@
 use tauri::{command, State};
 use crate::models::{Customer, CreateCustomerRequest, UpdateCustomerRequest};
+use crate::db_utils::{ensure_email_available, require_unreferenced};
+use crate::error::AppError;
+use crate::plans;
+use crate::sync_outbox::{enqueue_change, SyncOperation};
+use crate::tenancy;
+use crate::validation::Validate;
 use sqlx::{SqlitePool, Row};
 
 // Generate unique customer number
@@ -18,11 +24,18 @@ pub async fn get_customers(
     status: Option<String>,
     customer_type: Option<String>,
     limit: Option<i64>,
+    session_token: String,
+    include_archived: Option<bool>,
 ) -> Result<Vec<Customer>, String> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
 
     // Build query with parameterized conditions
     let mut query = String::from("SELECT * FROM customers WHERE 1=1");
+    query.push_str(&tenancy::organization_scope("organization_id", organization_id));
+    if !include_archived.unwrap_or(false) {
+        query.push_str(" AND archived_at IS NULL");
+    }
     let mut conditions = Vec::new();
 
     if status.is_some() {
@@ -90,11 +103,14 @@ pub async fn get_customers(
 pub async fn get_customer(
     pool: State<'_, SqlitePool>,
     customer_id: i64,
+    session_token: String,
 ) -> Result<Customer, String> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
 
-    let row = sqlx::query("SELECT * FROM customers WHERE id = ?1")
+    let row = sqlx::query("SELECT * FROM customers WHERE id = ?1 AND organization_id = ?2")
         .bind(customer_id)
+        .bind(organization_id)
         .fetch_optional(pool_ref)
         .await
         .map_err(|e| {
@@ -140,41 +156,54 @@ pub async fn create_customer(
     pool: State<'_, SqlitePool>,
     request: CreateCustomerRequest,
     user_id: i64,
+    session_token: String,
 ) -> Result<Customer, String> {
-    let pool_ref = pool.inner();
+    let organization_id = plans::crm_organization(pool.inner(), &session_token).await?;
+    let customer_id =
+        create_customer_internal(pool.inner(), &request, user_id, organization_id).await?;
 
-    // Check if email already exists (if provided)
-    if let Some(ref email) = request.email {
-        let exists = sqlx::query("SELECT id FROM customers WHERE email = ?1")
-            .bind(email)
-            .fetch_optional(pool_ref)
-            .await
-            .map_err(|e| {
-                format!("Database error: {}", e)
-            })?;
+    // Fetch and return the created customer
+    get_customer(pool, customer_id, session_token).await
+}
 
-        if exists.is_some() {
-            return Err("Email already exists".to_string());
-        }
+async fn create_customer_internal(
+    pool_ref: &SqlitePool,
+    request: &CreateCustomerRequest,
+    user_id: i64,
+    organization_id: i64,
+) -> Result<i64, String> {
+    request.validate()?;
+
+    // Blank emails are stored as NULL so they never collide on the UNIQUE index
+    let email = request
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|email| !email.is_empty());
+    if let Some(email) = email {
+        ensure_email_available(pool_ref, "customers", email, None).await?;
     }
 
     // Generate customer number
     let customer_number = generate_customer_number(pool_ref).await?;
 
     // Set default customer type if not provided
-    let customer_type = request.customer_type.unwrap_or_else(|| "Retail".to_string());
+    let customer_type = request.customer_type.as_deref().unwrap_or("Retail");
+
+    let mut tx = pool_ref.begin().await.map_err(AppError::from)?;
+    plans::require_writable(&mut tx, organization_id).await?;
 
     let result = sqlx::query(
         "INSERT INTO customers (
             customer_number, first_name, last_name, email, phone, company,
             address, city, state, zip_code, country, date_of_birth,
-            customer_type, notes, tags, created_by
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)"
+            customer_type, notes, tags, created_by, organization_id
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)"
     )
         .bind(&customer_number)
         .bind(&request.first_name)
         .bind(&request.last_name)
-        .bind(&request.email)
+        .bind(email)
         .bind(&request.phone)
         .bind(&request.company)
         .bind(&request.address)
@@ -183,20 +212,20 @@ pub async fn create_customer(
         .bind(&request.zip_code)
         .bind(&request.country)
         .bind(&request.date_of_birth)
-        .bind(&customer_type)
+        .bind(customer_type)
         .bind(&request.notes)
         .bind(&request.tags)
         .bind(user_id)
-        .execute(pool_ref)
+        .bind(organization_id)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| {
-            format!("Database error: {}", e)
-        })?;
+        .map_err(AppError::from)?;
 
     let customer_id = result.last_insert_rowid();
+    enqueue_change(&mut tx, "customer", customer_id, SyncOperation::Create).await?;
+    tx.commit().await.map_err(AppError::from)?;
 
-    // Fetch and return the created customer
-    get_customer(pool, customer_id).await
+    Ok(customer_id)
 }
 
 #[command]
@@ -204,12 +233,17 @@ pub async fn update_customer(
     pool: State<'_, SqlitePool>,
     customer_id: i64,
     request: UpdateCustomerRequest,
+    session_token: String,
 ) -> Result<Customer, String> {
+    request.validate()?;
+
     let pool_ref = pool.inner();
+    let organization_id = plans::writable_crm_organization(pool.inner(), &session_token).await?;
 
     // Check if customer exists
-    let exists = sqlx::query("SELECT id FROM customers WHERE id = ?1")
+    let exists = sqlx::query("SELECT id FROM customers WHERE id = ?1 AND organization_id = ?2")
         .bind(customer_id)
+        .bind(organization_id)
         .fetch_optional(pool_ref)
         .await
         .map_err(|e| {
@@ -221,17 +255,8 @@ pub async fn update_customer(
     }
 
     // Check email uniqueness if being updated
-    if let Some(ref email) = request.email {
-        let email_exists = sqlx::query("SELECT id FROM customers WHERE email = ?1 AND id != ?2")
-            .bind(email)
-            .bind(customer_id)
-            .fetch_optional(pool_ref)
-            .await
-            .map_err(|e| format!("Database error: {}", e))?;
-
-        if email_exists.is_some() {
-            return Err("Email already exists".to_string());
-        }
+    if let Some(email) = request.email.as_deref().map(str::trim).filter(|e| !e.is_empty()) {
+        ensure_email_available(pool_ref, "customers", email, Some(customer_id)).await?;
     }
 
     // Build dynamic update query
@@ -316,27 +341,101 @@ pub async fn update_customer(
     }
     q = q.bind(customer_id);
 
-    q.execute(pool_ref)
+    let mut tx = pool_ref.begin().await.map_err(AppError::from)?;
+    q.execute(&mut *tx)
         .await
         .map_err(|e| {
             format!("Database error: {}", e)
         })?;
+    enqueue_change(&mut tx, "customer", customer_id, SyncOperation::Update).await?;
+    tx.commit().await.map_err(AppError::from)?;
 
 
     // Fetch and return the updated customer
-    get_customer(pool, customer_id).await
+    get_customer(pool, customer_id, session_token).await
 }
 
+/// Tables whose rows keep a customer in the history; while any exist it can only be archived
+const CUSTOMER_REFERENCES: &[(&str, &str)] = &[
+    ("loyalty_transactions", "customer_id"),
+    ("customer_interactions", "customer_id"),
+    ("appointments", "customer_id"),
+    ("promotion_usage", "customer_id"),
+];
+
+/// Take a customer out of search and the default lists while keeping their history
+#[command]
+pub async fn archive_customer(
+    pool: State<'_, SqlitePool>,
+    customer_id: i64,
+    session_token: String,
+) -> Result<String, String> {
+    plans::writable_crm_organization(pool.inner(), &session_token).await?;
+    let mut tx = pool.inner().begin().await.map_err(AppError::from)?;
+    let result = sqlx::query(
+        "UPDATE customers SET status = 'Inactive', archived_at = COALESCE(archived_at, CURRENT_TIMESTAMP),
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1",
+    )
+    .bind(customer_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    if result.rows_affected() == 0 {
+        return Err("Customer not found".to_string());
+    }
+    enqueue_change(&mut tx, "customer", customer_id, SyncOperation::Update).await?;
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok("Customer archived successfully".to_string())
+}
+
+#[command]
+pub async fn restore_customer(
+    pool: State<'_, SqlitePool>,
+    customer_id: i64,
+    session_token: String,
+) -> Result<String, String> {
+    plans::writable_crm_organization(pool.inner(), &session_token).await?;
+    let mut tx = pool.inner().begin().await.map_err(AppError::from)?;
+    let result = sqlx::query(
+        "UPDATE customers SET status = 'Active', archived_at = NULL, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1",
+    )
+    .bind(customer_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    if result.rows_affected() == 0 {
+        return Err("Customer not found".to_string());
+    }
+    enqueue_change(&mut tx, "customer", customer_id, SyncOperation::Update).await?;
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok("Customer restored successfully".to_string())
+}
+
+/// Delete a customer for good. Only possible while nothing references them; customers
+/// with history are archived instead.
 #[command]
 pub async fn delete_customer(
     pool: State<'_, SqlitePool>,
     customer_id: i64,
+    session_token: String,
 ) -> Result<String, String> {
+    plans::writable_crm_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
+    let mut tx = pool_ref.begin().await.map_err(AppError::from)?;
+    require_unreferenced(&mut tx, "Customer", CUSTOMER_REFERENCES, customer_id).await?;
+
+    // Queued first so the entry still carries the customer's details
+    enqueue_change(&mut tx, "customer", customer_id, SyncOperation::Delete).await?;
     let result = sqlx::query("DELETE FROM customers WHERE id = ?1")
         .bind(customer_id)
-        .execute(pool_ref)
+        .execute(&mut *tx)
         .await
         .map_err(|e| {
             format!("Database error: {}", e)
@@ -345,6 +444,7 @@ pub async fn delete_customer(
     if result.rows_affected() == 0 {
         return Err("Customer not found".to_string());
     }
+    tx.commit().await.map_err(AppError::from)?;
 
     Ok("Customer deleted successfully".to_string())
 }
@@ -353,23 +453,28 @@ pub async fn delete_customer(
 pub async fn search_customers(
     pool: State<'_, SqlitePool>,
     query: String,
+    session_token: String,
 ) -> Result<Vec<Customer>, String> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
 
     let search_pattern = format!("%{}%", query);
 
     let rows = sqlx::query(
         "SELECT * FROM customers
-         WHERE first_name LIKE ?1
+         WHERE (first_name LIKE ?1
             OR last_name LIKE ?1
             OR email LIKE ?1
             OR phone LIKE ?1
             OR company LIKE ?1
-            OR customer_number LIKE ?1
+            OR customer_number LIKE ?1)
+           AND organization_id = ?2
+           AND archived_at IS NULL
          ORDER BY created_at DESC
          LIMIT 50"
     )
         .bind(&search_pattern)
+        .bind(organization_id)
         .fetch_all(pool_ref)
         .await
         .map_err(|e| {
@@ -409,3 +514,67 @@ pub async fn search_customers(
 
     Ok(customers)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{insert_test_user, test_pool};
+
+    fn customer_request(email: Option<&str>) -> CreateCustomerRequest {
+        CreateCustomerRequest {
+            first_name: "Ada".to_string(),
+            last_name: "Lovelace".to_string(),
+            email: email.map(str::to_string),
+            phone: None,
+            company: None,
+            address: None,
+            city: None,
+            state: None,
+            zip_code: None,
+            country: None,
+            date_of_birth: None,
+            customer_type: None,
+            notes: None,
+            tags: None,
+        }
+    }
+
+    fn error_of(err: &str) -> serde_json::Value {
+        serde_json::from_str(err).expect("error should be a structured AppError")
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_and_malformed_customer_emails_are_rejected() {
+        let pool = test_pool().await;
+        let user_id = insert_test_user(&pool, "clerk").await;
+
+        let ada = customer_request(Some("ada@example.com"));
+        create_customer_internal(&pool, &ada, user_id, DEFAULT_ORGANIZATION_ID)
+            .await
+            .unwrap();
+
+        let duplicate = customer_request(Some(" ADA@example.com "));
+        let err = create_customer_internal(&pool, &duplicate, user_id, DEFAULT_ORGANIZATION_ID)
+            .await
+            .unwrap_err();
+        let err = error_of(&err);
+        assert_eq!(err["code"], "CONFLICT");
+        assert_eq!(err["message"], "Email already in use");
+
+        let malformed = customer_request(Some("ada@"));
+        let err = create_customer_internal(&pool, &malformed, user_id, DEFAULT_ORGANIZATION_ID)
+            .await
+            .unwrap_err();
+        let err = error_of(&err);
+        assert_eq!(err["code"], "VALIDATION_ERROR");
+        assert_eq!(err["message"], "Invalid email format");
+
+        // Customers without an email never collide
+        for email in [None, Some(""), Some("  ")] {
+            create_customer_internal(&pool, &customer_request(email), user_id, DEFAULT_ORGANIZATION_ID)
+                .await
+                .unwrap();
+        }
+    }
+}
@@ -1,8 +1,20 @@
+use crate::error::AppResult;
 use crate::models::{DashboardStats, InventoryItem, Product, Sale};
+use crate::money::Money;
+use crate::tenancy::{active_organization, organization_scope};
+use chrono::{Datelike, Duration as DateDuration, NaiveDate};
 use serde::{Deserialize, Serialize};
 use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tauri::{command, State};
 
+/// How long a computed overview is served before the queries run again
+pub const DASHBOARD_CACHE_TTL: Duration = Duration::from_secs(30);
+const DASHBOARD_TOP_PRODUCTS: i64 = 5;
+const DASHBOARD_RECENT_SALES: i32 = 5;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RecentActivity {
     pub sales: Vec<Sale>,
@@ -10,17 +22,111 @@ pub struct RecentActivity {
     pub recent_products: Vec<Product>,
 }
 
+/// A sales figure for the current period next to the same span of the previous one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodComparison {
+    pub current: Money,
+    pub previous: Money,
+    /// Percentage change, `None` when the previous period had no sales
+    pub change_percent: Option<f64>,
+}
+
+impl PeriodComparison {
+    fn new(current: Money, previous: Money) -> Self {
+        let change_percent = (previous != Money::ZERO).then(|| {
+            let previous = previous.to_major();
+            (current.to_major() - previous) / previous * 100.0
+        });
+        PeriodComparison {
+            current,
+            previous,
+            change_percent,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardTopProduct {
+    pub product_id: i64,
+    pub sku: String,
+    pub name: String,
+    pub quantity_sold: i64,
+    pub revenue: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenShiftSummary {
+    pub shift_id: i64,
+    pub start_time: String,
+    pub opening_amount: Money,
+    pub sales_count: i64,
+    pub sales_total: Money,
+    pub cash_sales: Money,
+}
+
+/// Everything the dashboard shows, computed in one call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardOverview {
+    pub today_sales: Money,
+    pub today_transactions: i64,
+    pub average_transaction_value: Money,
+    /// Last 7 days against the 7 days before
+    pub week: PeriodComparison,
+    /// Month to date against the same days of the previous month
+    pub month: PeriodComparison,
+    pub low_stock_items: i64,
+    /// Best sellers by revenue over the last 7 days
+    pub top_products: Vec<DashboardTopProduct>,
+    /// The requesting user's open shift, if any
+    pub open_shift: Option<OpenShiftSummary>,
+    pub recent_sales: Vec<Sale>,
+    pub generated_at: String,
+}
+
+/// Recently computed overviews per organization and user, kept in managed state
+#[derive(Default)]
+pub struct DashboardCache {
+    entries: Mutex<HashMap<(i64, Option<i64>), (Instant, DashboardOverview)>>,
+}
+
+impl DashboardCache {
+    fn get(
+        &self,
+        organization_id: i64,
+        user_id: Option<i64>,
+        ttl: Duration,
+    ) -> Option<DashboardOverview> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&(organization_id, user_id))
+            .filter(|(computed_at, _)| computed_at.elapsed() < ttl)
+            .map(|(_, overview)| overview.clone())
+    }
+
+    fn put(&self, organization_id: i64, user_id: Option<i64>, overview: DashboardOverview) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (computed_at, _)| computed_at.elapsed() < DASHBOARD_CACHE_TTL);
+        entries.insert((organization_id, user_id), (Instant::now(), overview));
+    }
+}
+
 #[command]
-pub async fn get_stats(pool: State<'_, SqlitePool>) -> Result<DashboardStats, String> {
+pub async fn get_stats(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<DashboardStats, String> {
     let pool_ref = pool.inner();
+    let organization_id = active_organization(&session_token)?;
+    let sales_scope = organization_scope("organization_id", organization_id);
 
     // Fetch today's sales
-    let today_sales_row = sqlx::query(
+    let today_sales_row = sqlx::query(&format!(
         "SELECT COALESCE(CAST(SUM(total_amount) AS REAL), 0.0) as total_sales,
                 CAST(COUNT(*) AS INTEGER) as transaction_count
          FROM sales 
-         WHERE DATE(created_at) = DATE('now') AND is_voided = 0",
-    )
+         WHERE DATE(created_at) = DATE('now') AND is_voided = 0{}",
+        sales_scope
+    ))
     .fetch_one(pool_ref)
     .await
     .map_err(|e| format!("Failed to get today's sales: {}", e))?;
@@ -36,11 +142,12 @@ pub async fn get_stats(pool: State<'_, SqlitePool>) -> Result<DashboardStats, St
     })?;
 
     // Fetch week sales
-    let week_sales_row = sqlx::query(
+    let week_sales_row = sqlx::query(&format!(
         "SELECT COALESCE(CAST(SUM(total_amount) AS REAL), 0.0) as week_total
          FROM sales 
-         WHERE DATE(created_at) >= DATE('now', '-6 days') AND is_voided = 0",
-    )
+         WHERE DATE(created_at) >= DATE('now', '-6 days') AND is_voided = 0{}",
+        sales_scope
+    ))
     .fetch_one(pool_ref)
     .await
     .map_err(|e| format!("Failed to get week sales: {}", e))?;
@@ -51,11 +158,12 @@ pub async fn get_stats(pool: State<'_, SqlitePool>) -> Result<DashboardStats, St
     })?;
 
     // Fetch month sales
-    let month_sales_row = sqlx::query(
+    let month_sales_row = sqlx::query(&format!(
         "SELECT COALESCE(CAST(SUM(total_amount) AS REAL), 0.0) as month_total
          FROM sales 
-         WHERE DATE(created_at) >= DATE('now', 'start of month') AND is_voided = 0",
-    )
+         WHERE DATE(created_at) >= DATE('now', 'start of month') AND is_voided = 0{}",
+        sales_scope
+    ))
     .fetch_one(pool_ref)
     .await
     .map_err(|e| format!("Failed to get month sales: {}", e))?;
@@ -66,11 +174,12 @@ pub async fn get_stats(pool: State<'_, SqlitePool>) -> Result<DashboardStats, St
     })?;
 
     // Fetch total products
-    let total_products_row = sqlx::query(
+    let total_products_row = sqlx::query(&format!(
         "SELECT CAST(COUNT(*) AS INTEGER) as product_count 
          FROM products 
-         WHERE is_active = 1",
-    )
+         WHERE is_active = 1{}",
+        organization_scope("organization_id", organization_id)
+    ))
     .fetch_one(pool_ref)
     .await
     .map_err(|e| format!("Failed to get product count: {}", e))?;
@@ -81,12 +190,13 @@ pub async fn get_stats(pool: State<'_, SqlitePool>) -> Result<DashboardStats, St
     })?;
 
     // Fetch low stock items count
-    let low_stock_items_row = sqlx::query(
+    let low_stock_items_row = sqlx::query(&format!(
         "SELECT CAST(COUNT(*) AS INTEGER) as low_stock_count
          FROM inventory i
          JOIN products p ON i.product_id = p.id
-         WHERE i.current_stock <= i.minimum_stock AND p.is_active = 1",
-    )
+         WHERE i.current_stock <= i.minimum_stock AND p.is_active = 1{}",
+        organization_scope("p.organization_id", organization_id)
+    ))
     .fetch_one(pool_ref)
     .await
     .map_err(|e| format!("Failed to get low stock count: {}", e))?;
@@ -126,18 +236,20 @@ pub async fn get_stats(pool: State<'_, SqlitePool>) -> Result<DashboardStats, St
 pub async fn get_recent_activity(
     pool: State<'_, SqlitePool>,
     limit: Option<i32>,
+    session_token: String,
 ) -> Result<RecentActivity, String> {
     let pool_ref = pool.inner();
     let limit = limit.unwrap_or(10);
+    let organization_id = active_organization(&session_token)?;
 
     // Fetch recent sales
-    let sales = fetch_recent_sales(pool_ref, limit).await?;
+    let sales = fetch_recent_sales(pool_ref, organization_id, limit).await?;
 
     // Fetch low stock items
-    let low_stock_items = fetch_low_stock_items(pool_ref, limit).await?;
+    let low_stock_items = fetch_low_stock_items(pool_ref, organization_id, limit).await?;
 
     // Fetch recent products
-    let recent_products = fetch_recent_products(pool_ref, limit).await?;
+    let recent_products = fetch_recent_products(pool_ref, organization_id, limit).await?;
 
     Ok(RecentActivity {
         sales,
@@ -146,6 +258,170 @@ pub async fn get_recent_activity(
     })
 }
 
+/// Dashboard figures in one round-trip, cached for `DASHBOARD_CACHE_TTL`
+#[command]
+pub async fn get_dashboard_stats(
+    pool: State<'_, SqlitePool>,
+    cache: State<'_, DashboardCache>,
+    user_id: Option<i64>,
+    session_token: String,
+) -> Result<DashboardOverview, String> {
+    let organization_id = active_organization(&session_token)?;
+    if let Some(overview) = cache.get(organization_id, user_id, DASHBOARD_CACHE_TTL) {
+        return Ok(overview);
+    }
+    let today = chrono::Utc::now().date_naive();
+    let overview =
+        get_dashboard_overview_internal(pool.inner(), organization_id, user_id, today).await?;
+    cache.put(organization_id, user_id, overview.clone());
+    Ok(overview)
+}
+
+/// The same day one month earlier, clamped to the end of shorter months
+fn one_month_before(date: NaiveDate) -> NaiveDate {
+    date.checked_sub_months(chrono::Months::new(1))
+        .unwrap_or(date - DateDuration::days(30))
+}
+
+fn iso(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+pub(crate) async fn get_dashboard_overview_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    user_id: Option<i64>,
+    today: NaiveDate,
+) -> AppResult<DashboardOverview> {
+    let week_start = today - DateDuration::days(6);
+    let previous_week_start = today - DateDuration::days(13);
+    let month_start = today.with_day(1).unwrap_or(today);
+    let previous_month_start = one_month_before(month_start);
+    let previous_month_end = one_month_before(today);
+
+    // Every period total in a single pass over the last two months of sales
+    let totals = sqlx::query(&format!(
+        "SELECT
+            COALESCE(SUM(CASE WHEN d = ?1 THEN total_amount END), 0.0) as today_sales,
+            COUNT(CASE WHEN d = ?1 THEN 1 END) as today_transactions,
+            COALESCE(SUM(CASE WHEN d >= ?2 AND d <= ?1 THEN total_amount END), 0.0) as week_sales,
+            COALESCE(SUM(CASE WHEN d >= ?3 AND d < ?2 THEN total_amount END), 0.0) as previous_week_sales,
+            COALESCE(SUM(CASE WHEN d >= ?4 AND d <= ?1 THEN total_amount END), 0.0) as month_sales,
+            COALESCE(SUM(CASE WHEN d >= ?5 AND d <= ?6 THEN total_amount END), 0.0) as previous_month_sales
+         FROM (SELECT DATE(created_at) as d, total_amount FROM sales
+               WHERE is_voided = 0 AND DATE(created_at) >= MIN(?3, ?5){})",
+        organization_scope("organization_id", organization_id)
+    ))
+    .bind(iso(today))
+    .bind(iso(week_start))
+    .bind(iso(previous_week_start))
+    .bind(iso(month_start))
+    .bind(iso(previous_month_start))
+    .bind(iso(previous_month_end))
+    .fetch_one(pool)
+    .await?;
+
+    let today_sales: Money = totals.try_get("today_sales")?;
+    let today_transactions: i64 = totals.try_get("today_transactions")?;
+    let average_transaction_value = if today_transactions > 0 {
+        Money::from_major(today_sales.to_major() / today_transactions as f64)
+    } else {
+        Money::ZERO
+    };
+
+    let low_stock_items: i64 = sqlx::query_scalar(&format!(
+        "SELECT COUNT(*)
+         FROM inventory i
+         JOIN products p ON i.product_id = p.id
+         WHERE i.current_stock <= i.minimum_stock AND p.is_active = 1{}",
+        organization_scope("p.organization_id", organization_id)
+    ))
+    .fetch_one(pool)
+    .await?;
+
+    let top_rows = sqlx::query(&format!(
+        "SELECT p.id as product_id, p.sku, p.name,
+                SUM(si.quantity) as quantity_sold,
+                COALESCE(SUM(si.line_total), 0.0) as revenue
+         FROM sale_items si
+         JOIN sales s ON si.sale_id = s.id
+         JOIN products p ON si.product_id = p.id
+         WHERE s.is_voided = 0 AND DATE(s.created_at) >= ?1 AND DATE(s.created_at) <= ?2{}
+         GROUP BY p.id
+         ORDER BY revenue DESC, quantity_sold DESC
+         LIMIT ?3",
+        organization_scope("s.organization_id", organization_id)
+    ))
+    .bind(iso(week_start))
+    .bind(iso(today))
+    .bind(DASHBOARD_TOP_PRODUCTS)
+    .fetch_all(pool)
+    .await?;
+    let mut top_products = Vec::with_capacity(top_rows.len());
+    for row in top_rows {
+        top_products.push(DashboardTopProduct {
+            product_id: row.try_get("product_id")?,
+            sku: row.try_get("sku")?,
+            name: row.try_get("name")?,
+            quantity_sold: row.try_get("quantity_sold")?,
+            revenue: row.try_get("revenue")?,
+        });
+    }
+
+    let open_shift = match user_id {
+        Some(user_id) => {
+            let row = sqlx::query(
+                "SELECT sh.id, sh.start_time, sh.opening_amount,
+                        COUNT(s.id) as sales_count,
+                        COALESCE(SUM(s.total_amount), 0.0) as sales_total,
+                        COALESCE(SUM(CASE WHEN s.payment_method = 'cash' THEN s.total_amount END), 0.0) as cash_sales
+                 FROM shifts sh
+                 LEFT JOIN sales s ON s.shift_id = sh.id AND s.is_voided = 0
+                 WHERE sh.user_id = ?1 AND sh.status = 'open'
+                 GROUP BY sh.id
+                 ORDER BY sh.start_time DESC
+                 LIMIT 1",
+            )
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+            match row {
+                Some(row) => Some(OpenShiftSummary {
+                    shift_id: row.try_get("id")?,
+                    start_time: row.try_get("start_time")?,
+                    opening_amount: row.try_get("opening_amount")?,
+                    sales_count: row.try_get("sales_count")?,
+                    sales_total: row.try_get("sales_total")?,
+                    cash_sales: row.try_get("cash_sales")?,
+                }),
+                None => None,
+            }
+        }
+        None => None,
+    };
+
+    let recent_sales = fetch_recent_sales(pool, organization_id, DASHBOARD_RECENT_SALES).await?;
+
+    Ok(DashboardOverview {
+        today_sales,
+        today_transactions,
+        average_transaction_value,
+        week: PeriodComparison::new(
+            totals.try_get("week_sales")?,
+            totals.try_get("previous_week_sales")?,
+        ),
+        month: PeriodComparison::new(
+            totals.try_get("month_sales")?,
+            totals.try_get("previous_month_sales")?,
+        ),
+        low_stock_items,
+        top_products,
+        open_shift,
+        recent_sales,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
 // Helper functions
 
 /// Log error in debug mode
@@ -158,19 +434,24 @@ fn log_error<E: std::fmt::Display>(field: &str, expected_type: &str, error: &E)
 }
 
 /// Fetch recent sales with cashier information
-async fn fetch_recent_sales(pool: &SqlitePool, limit: i32) -> Result<Vec<Sale>, String> {
-    let sales_rows = sqlx::query(
+async fn fetch_recent_sales(
+    pool: &SqlitePool,
+    organization_id: i64,
+    limit: i32,
+) -> Result<Vec<Sale>, String> {
+    let sales_rows = sqlx::query(&format!(
         "SELECT s.id, s.sale_number, s.subtotal, s.tax_amount, s.discount_amount, s.total_amount,
                 s.payment_method, s.payment_status, s.cashier_id, s.customer_name, s.customer_phone,
                 s.customer_email, s.notes, s.is_voided, s.voided_by, s.voided_at, s.void_reason,
-                s.shift_id, s.created_at,
+                s.shift_id, s.created_at, s.cash_rounding,
                 u.first_name, u.last_name
          FROM sales s
          JOIN users u ON s.cashier_id = u.id
-         WHERE s.is_voided = 0
+         WHERE s.is_voided = 0{}
          ORDER BY s.created_at DESC
          LIMIT ?1",
-    )
+        organization_scope("s.organization_id", organization_id)
+    ))
     .bind(limit)
     .fetch_all(pool)
     .await
@@ -185,9 +466,10 @@ async fn fetch_recent_sales(pool: &SqlitePool, limit: i32) -> Result<Vec<Sale>,
 /// Fetch low stock items with product details
 async fn fetch_low_stock_items(
     pool: &SqlitePool,
+    organization_id: i64,
     limit: i32,
 ) -> Result<Vec<InventoryItem>, String> {
-    let low_stock_rows = sqlx::query(
+    let low_stock_rows = sqlx::query(&format!(
         "SELECT i.id, i.product_id, i.current_stock, i.minimum_stock, i.maximum_stock,
                 i.reserved_stock, i.available_stock, i.last_updated, i.last_stock_take,
                 i.stock_take_count,
@@ -197,10 +479,11 @@ async fn fetch_low_stock_items(
                 p.created_at as product_created_at, p.updated_at as product_updated_at
          FROM inventory i
          JOIN products p ON i.product_id = p.id
-         WHERE i.current_stock <= i.minimum_stock AND p.is_active = 1
+         WHERE i.current_stock <= i.minimum_stock AND p.is_active = 1{}
          ORDER BY (i.minimum_stock - i.current_stock) DESC
          LIMIT ?1",
-    )
+        organization_scope("p.organization_id", organization_id)
+    ))
     .bind(limit)
     .fetch_all(pool)
     .await
@@ -213,17 +496,22 @@ async fn fetch_low_stock_items(
 }
 
 /// Fetch recent products
-async fn fetch_recent_products(pool: &SqlitePool, limit: i32) -> Result<Vec<Product>, String> {
-    let recent_products_rows = sqlx::query(
+async fn fetch_recent_products(
+    pool: &SqlitePool,
+    organization_id: i64,
+    limit: i32,
+) -> Result<Vec<Product>, String> {
+    let recent_products_rows = sqlx::query(&format!(
         "SELECT id, sku, barcode, name, description, category, subcategory, brand,
                 unit_of_measure, cost_price, selling_price, wholesale_price, tax_rate,
                 is_active, is_taxable, weight, dimensions, supplier_info, reorder_point,
                 created_at, updated_at
          FROM products
-         WHERE is_active = 1
+         WHERE is_active = 1{}
          ORDER BY created_at DESC
          LIMIT ?1",
-    )
+        organization_scope("organization_id", organization_id)
+    ))
     .bind(limit)
     .fetch_all(pool)
     .await
@@ -257,6 +545,8 @@ fn build_sale_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Sale, String> {
         void_reason: row.try_get("void_reason").ok().flatten(),
         shift_id: row.try_get("shift_id").ok().flatten(),
         created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
+        reprint_count: 0,
+        cash_rounding: row.try_get("cash_rounding").map_err(|e| e.to_string())?,
     })
 }
 
@@ -309,3 +599,143 @@ fn build_inventory_item_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Invent
         product: Some(product),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    async fn insert_sale(
+        pool: &SqlitePool,
+        cashier_id: i64,
+        shift_id: Option<i64>,
+        created_at: &str,
+        lines: &[(i64, i32, f64)],
+        voided: bool,
+    ) {
+        let total: f64 = lines.iter().map(|(_, qty, price)| *qty as f64 * price).sum();
+        let sale_id = sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id,
+                                shift_id, is_voided, created_at)
+             VALUES ('S-' || ?4, ?1, ?1, 'cash', ?2, ?3, ?5, ?4)",
+        )
+        .bind(total)
+        .bind(cashier_id)
+        .bind(shift_id)
+        .bind(created_at)
+        .bind(voided)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        for (product_id, quantity, price) in lines {
+            sqlx::query(
+                "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, line_total)
+                 VALUES (?1, ?2, ?3, ?4, ?3 * ?4)",
+            )
+            .bind(sale_id)
+            .bind(product_id)
+            .bind(quantity)
+            .bind(price)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_overview_figures() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "dash").await;
+        let sand = insert_test_product(&pool, "SAND", 10.0, 50).await;
+        let gravel = insert_test_product(&pool, "GRAVEL", 10.0, 50).await;
+        insert_test_product(&pool, "LIME", 10.0, 0).await;
+        let shift_id = sqlx::query(
+            "INSERT INTO shifts (user_id, start_time, opening_amount, status)
+             VALUES (?1, '2024-06-15 08:00:00', 100.0, 'open')",
+        )
+        .bind(cashier)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+
+        insert_sale(&pool, cashier, Some(shift_id), "2024-06-15 09:00:00", &[(sand, 3, 10.0)], false).await;
+        insert_sale(&pool, cashier, Some(shift_id), "2024-06-15 10:00:00", &[(gravel, 2, 10.0)], false).await;
+        insert_sale(&pool, cashier, Some(shift_id), "2024-06-15 11:00:00", &[(sand, 10, 10.0)], true).await;
+        insert_sale(&pool, cashier, None, "2024-06-10 10:00:00", &[(gravel, 4, 10.0)], false).await;
+        insert_sale(&pool, cashier, None, "2024-06-05 10:00:00", &[(sand, 2, 12.5)], false).await;
+        insert_sale(&pool, cashier, None, "2024-05-10 10:00:00", &[(sand, 6, 10.0)], false).await;
+        insert_sale(&pool, cashier, None, "2024-05-20 10:00:00", &[(sand, 50, 10.0)], false).await;
+
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let overview =
+            get_dashboard_overview_internal(&pool, DEFAULT_ORGANIZATION_ID, Some(cashier), today)
+                .await
+                .unwrap();
+
+        assert_eq!(overview.today_sales, Money::from_major(50.0));
+        assert_eq!(overview.today_transactions, 2);
+        assert_eq!(overview.average_transaction_value, Money::from_major(25.0));
+
+        assert_eq!(overview.week.current, Money::from_major(90.0));
+        assert_eq!(overview.week.previous, Money::from_major(25.0));
+        assert_eq!(overview.week.change_percent, Some(260.0));
+        assert_eq!(overview.month.current, Money::from_major(115.0));
+        assert_eq!(overview.month.previous, Money::from_major(60.0));
+
+        assert_eq!(overview.low_stock_items, 1);
+
+        let top: Vec<(&str, i64)> = overview
+            .top_products
+            .iter()
+            .map(|p| (p.sku.as_str(), p.quantity_sold))
+            .collect();
+        assert_eq!(top, vec![("GRAVEL", 6), ("SAND", 3)]);
+        assert_eq!(overview.top_products[0].revenue, Money::from_major(60.0));
+
+        let shift = overview.open_shift.unwrap();
+        assert_eq!(shift.shift_id, shift_id);
+        assert_eq!(shift.opening_amount, Money::from_major(100.0));
+        assert_eq!(shift.sales_count, 2);
+        assert_eq!(shift.sales_total, Money::from_major(50.0));
+
+        assert_eq!(overview.recent_sales.len(), 5);
+        assert_eq!(overview.recent_sales[0].created_at, "2024-06-15 10:00:00");
+        assert!(overview.recent_sales.iter().all(|sale| !sale.is_voided));
+
+        let anonymous =
+            get_dashboard_overview_internal(&pool, DEFAULT_ORGANIZATION_ID, None, today)
+                .await
+                .unwrap();
+        assert!(anonymous.open_shift.is_none());
+
+        let other =
+            get_dashboard_overview_internal(&pool, DEFAULT_ORGANIZATION_ID + 1, None, today)
+                .await
+                .unwrap();
+        assert_eq!(other.today_sales, Money::ZERO);
+        assert_eq!(other.low_stock_items, 0);
+        assert!(other.top_products.is_empty());
+        assert!(other.recent_sales.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_cache_expires() {
+        let pool = test_pool().await;
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let overview = get_dashboard_overview_internal(&pool, DEFAULT_ORGANIZATION_ID, None, today)
+            .await
+            .unwrap();
+        assert_eq!(overview.week.change_percent, None);
+
+        let cache = DashboardCache::default();
+        let org = DEFAULT_ORGANIZATION_ID;
+        cache.put(org, None, overview);
+        assert!(cache.get(org, None, DASHBOARD_CACHE_TTL).is_some());
+        assert!(cache.get(org, Some(1), DASHBOARD_CACHE_TTL).is_none());
+        assert!(cache.get(org + 1, None, DASHBOARD_CACHE_TTL).is_none());
+        assert!(cache.get(org, None, Duration::ZERO).is_none());
+    }
+}
@@ -0,0 +1,419 @@
+// src-tauri/src/commands/discounts.rs - Automatic discount rules ("Buy 10+ get 5% off")
+use crate::currency::{self, Currency};
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use crate::plans;
+use crate::validation::{validate_enum, validate_positive, validate_quantity, validate_required};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Row, SqlitePool};
+use tauri::{command, State};
+
+pub const RULE_SCOPES: [&str; 3] = ["product", "category", "all"];
+pub const RULE_TYPES: [&str; 2] = ["percent", "fixed"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DiscountRule {
+    pub id: i64,
+    pub name: String,
+    /// `product`, `category` or `all`
+    pub scope: String,
+    pub product_id: Option<i64>,
+    pub category: Option<String>,
+    /// `percent` of the line, or a `fixed` amount off each unit
+    pub discount_type: String,
+    pub value: f64,
+    /// Units on the line before the rule applies
+    pub min_quantity: i32,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub is_active: bool,
+    pub created_by: Option<i64>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscountRuleRequest {
+    pub name: String,
+    pub scope: String,
+    pub product_id: Option<i64>,
+    pub category: Option<String>,
+    pub discount_type: String,
+    pub value: f64,
+    #[serde(default)]
+    pub min_quantity: Option<i32>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+}
+
+/// A line of the cart being priced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CartLine {
+    pub product_id: i64,
+    pub quantity: i32,
+    pub unit_price: Money,
+    /// Decimal quantity of a weighed or measured line
+    #[serde(default)]
+    pub measured_quantity: Option<f64>,
+}
+
+/// The best rule for a cart line, in cart order; lines no rule covers get no discount
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoDiscount {
+    pub product_id: i64,
+    pub rule_id: Option<i64>,
+    pub rule_name: Option<String>,
+    /// Shopper-facing wording, e.g. "Buy 10+ get 5% off"
+    pub description: Option<String>,
+    pub discount_amount: Money,
+    pub line_total: Money,
+}
+
+const RULE_COLUMNS: &str = "id, name, scope, product_id, category, discount_type, value, min_quantity,
+    start_date, end_date, is_active, created_by, COALESCE(created_at, '') as created_at";
+
+impl CartLine {
+    fn gross(&self) -> Money {
+        match self.measured_quantity {
+            Some(measured) => self.unit_price.times_decimal(measured),
+            None => self.unit_price.times(self.quantity),
+        }
+    }
+
+    fn units(&self) -> f64 {
+        self.measured_quantity.unwrap_or(self.quantity as f64)
+    }
+}
+
+impl DiscountRule {
+    fn covers(&self, product_id: i64, category: Option<&str>) -> bool {
+        match self.scope.as_str() {
+            "product" => self.product_id == Some(product_id),
+            "category" => self.category.is_some() && self.category.as_deref() == category,
+            _ => true,
+        }
+    }
+
+    /// Discount this rule gives a line, never more than the line itself
+    fn discount_for(&self, line: &CartLine) -> Money {
+        if line.units() < self.min_quantity as f64 {
+            return Money::ZERO;
+        }
+        let gross = line.gross();
+        let discount = match self.discount_type.as_str() {
+            "percent" => gross.percent(self.value),
+            _ => Money::from_major(self.value).times_decimal(line.units()),
+        };
+        discount.min(gross)
+    }
+
+    fn describe(&self, currency: &Currency) -> String {
+        let off = match self.discount_type.as_str() {
+            "percent" if self.value.fract() == 0.0 => format!("{:.0}% off", self.value),
+            "percent" => format!("{}% off", self.value),
+            _ => format!("{} off each", currency.format(Money::from_major(self.value))),
+        };
+        if self.min_quantity > 1 {
+            format!("Buy {}+ get {}", self.min_quantity, off)
+        } else {
+            off
+        }
+    }
+}
+
+fn validate_rule(request: &DiscountRuleRequest) -> AppResult<()> {
+    validate_required(&request.name, "name")?;
+    validate_enum(&request.scope.as_str(), &RULE_SCOPES, "scope")?;
+    validate_enum(&request.discount_type.as_str(), &RULE_TYPES, "discount_type")?;
+    validate_positive(request.value, "value")?;
+    if request.discount_type == "percent" && request.value > 100.0 {
+        return Err(AppError::validation("value", "A percentage discount cannot exceed 100"));
+    }
+    validate_quantity(request.min_quantity.unwrap_or(1), "min_quantity")?;
+    if request.scope == "product" && request.product_id.is_none() {
+        return Err(AppError::validation("product_id", "Product rules need a product"));
+    }
+    if request.scope == "category" && request.category.as_deref().is_none_or(|c| c.trim().is_empty()) {
+        return Err(AppError::validation("category", "Category rules need a category"));
+    }
+    if let (Some(start), Some(end)) = (&request.start_date, &request.end_date) {
+        if end < start {
+            return Err(AppError::validation("end_date", "The rule ends before it starts"));
+        }
+    }
+    Ok(())
+}
+
+async fn fetch_rule(pool: &SqlitePool, rule_id: i64) -> AppResult<DiscountRule> {
+    let query = format!("SELECT {} FROM discount_rules WHERE id = ?1", RULE_COLUMNS);
+    sqlx::query_as::<_, DiscountRule>(&query)
+        .bind(rule_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("Discount rule"))
+}
+
+/// Best active rule for each line; where rules overlap the largest discount wins
+pub async fn compute_auto_discounts_internal(
+    pool: &SqlitePool,
+    cart_items: &[CartLine],
+) -> AppResult<Vec<AutoDiscount>> {
+    let query = format!(
+        "SELECT {} FROM discount_rules
+         WHERE is_active = 1
+           AND (start_date IS NULL OR start_date <= ?1)
+           AND (end_date IS NULL OR end_date >= ?1)
+         ORDER BY id",
+        RULE_COLUMNS
+    );
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let rules = sqlx::query_as::<_, DiscountRule>(&query)
+        .bind(&today)
+        .fetch_all(pool)
+        .await?;
+    let currency = currency::store_currency(pool).await?;
+
+    let mut discounts = Vec::with_capacity(cart_items.len());
+    for line in cart_items {
+        let category: Option<String> = sqlx::query("SELECT category FROM products WHERE id = ?1")
+            .bind(line.product_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::not_found(&format!("product {}", line.product_id)))?
+            .try_get("category")?;
+
+        let mut best: Option<(&DiscountRule, Money)> = None;
+        for rule in rules.iter().filter(|r| r.covers(line.product_id, category.as_deref())) {
+            let discount = rule.discount_for(line);
+            if discount > best.map_or(Money::ZERO, |(_, amount)| amount) {
+                best = Some((rule, discount));
+            }
+        }
+
+        let discount_amount = best.map_or(Money::ZERO, |(_, amount)| amount);
+        discounts.push(AutoDiscount {
+            product_id: line.product_id,
+            rule_id: best.map(|(rule, _)| rule.id),
+            rule_name: best.map(|(rule, _)| rule.name.clone()),
+            description: best.map(|(rule, _)| rule.describe(&currency)),
+            discount_amount,
+            line_total: line.gross() - discount_amount,
+        });
+    }
+    Ok(discounts)
+}
+
+#[command]
+pub async fn compute_auto_discounts(
+    pool: State<'_, SqlitePool>,
+    cart_items: Vec<CartLine>,
+) -> Result<Vec<AutoDiscount>, AppError> {
+    compute_auto_discounts_internal(pool.inner(), &cart_items).await
+}
+
+#[command]
+pub async fn get_discount_rules(
+    pool: State<'_, SqlitePool>,
+    active_only: Option<bool>,
+) -> Result<Vec<DiscountRule>, AppError> {
+    let query = format!(
+        "SELECT {} FROM discount_rules WHERE (?1 = 0 OR is_active = 1) ORDER BY name",
+        RULE_COLUMNS
+    );
+    Ok(sqlx::query_as::<_, DiscountRule>(&query)
+        .bind(active_only.unwrap_or(false))
+        .fetch_all(pool.inner())
+        .await?)
+}
+
+#[command]
+pub async fn create_discount_rule(
+    pool: State<'_, SqlitePool>,
+    request: DiscountRuleRequest,
+    user_id: i64,
+    session_token: String,
+) -> Result<DiscountRule, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    validate_rule(&request)?;
+    let rule_id = sqlx::query(
+        "INSERT INTO discount_rules (name, scope, product_id, category, discount_type, value,
+                                     min_quantity, start_date, end_date, created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+    )
+    .bind(request.name.trim())
+    .bind(&request.scope)
+    .bind(request.product_id.filter(|_| request.scope == "product"))
+    .bind(request.category.as_deref().filter(|_| request.scope == "category"))
+    .bind(&request.discount_type)
+    .bind(request.value)
+    .bind(request.min_quantity.unwrap_or(1))
+    .bind(&request.start_date)
+    .bind(&request.end_date)
+    .bind(user_id)
+    .execute(pool.inner())
+    .await?
+    .last_insert_rowid();
+
+    fetch_rule(pool.inner(), rule_id).await
+}
+
+#[command]
+pub async fn update_discount_rule(
+    pool: State<'_, SqlitePool>,
+    rule_id: i64,
+    request: DiscountRuleRequest,
+    session_token: String,
+) -> Result<DiscountRule, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    validate_rule(&request)?;
+    let updated = sqlx::query(
+        "UPDATE discount_rules SET
+            name = ?1, scope = ?2, product_id = ?3, category = ?4, discount_type = ?5, value = ?6,
+            min_quantity = ?7, start_date = ?8, end_date = ?9, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?10",
+    )
+    .bind(request.name.trim())
+    .bind(&request.scope)
+    .bind(request.product_id.filter(|_| request.scope == "product"))
+    .bind(request.category.as_deref().filter(|_| request.scope == "category"))
+    .bind(&request.discount_type)
+    .bind(request.value)
+    .bind(request.min_quantity.unwrap_or(1))
+    .bind(&request.start_date)
+    .bind(&request.end_date)
+    .bind(rule_id)
+    .execute(pool.inner())
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::not_found("Discount rule"));
+    }
+
+    fetch_rule(pool.inner(), rule_id).await
+}
+
+#[command]
+pub async fn set_discount_rule_active(
+    pool: State<'_, SqlitePool>,
+    rule_id: i64,
+    is_active: bool,
+    session_token: String,
+) -> Result<DiscountRule, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    let updated = sqlx::query(
+        "UPDATE discount_rules SET is_active = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+    )
+    .bind(is_active)
+    .bind(rule_id)
+    .execute(pool.inner())
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::not_found("Discount rule"));
+    }
+
+    fetch_rule(pool.inner(), rule_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_product, test_pool};
+
+    async fn insert_rule(
+        pool: &SqlitePool,
+        scope: &str,
+        target: (Option<i64>, Option<&str>),
+        discount_type: &str,
+        value: f64,
+        min_quantity: i32,
+    ) -> i64 {
+        sqlx::query(
+            "INSERT INTO discount_rules (name, scope, product_id, category, discount_type, value, min_quantity)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )
+        .bind(format!("{} {} {}", scope, discount_type, value))
+        .bind(scope)
+        .bind(target.0)
+        .bind(target.1)
+        .bind(discount_type)
+        .bind(value)
+        .bind(min_quantity)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+    }
+
+    fn line(product_id: i64, quantity: i32, unit_price: f64) -> CartLine {
+        CartLine {
+            product_id,
+            quantity,
+            unit_price: Money::from_major(unit_price),
+            measured_quantity: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_rules_pick_the_largest_discount() {
+        let pool = test_pool().await;
+        let cement = insert_test_product(&pool, "CEMENT", 10.0, 100).await;
+        let sand = insert_test_product(&pool, "SAND", 2.0, 100).await;
+        let nails = insert_test_product(&pool, "NAILS", 1.0, 100).await;
+        sqlx::query("UPDATE products SET category = 'Building' WHERE id IN (?1, ?2)")
+            .bind(cement)
+            .bind(sand)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let bulk = insert_rule(&pool, "product", (Some(cement), None), "percent", 5.0, 10).await;
+        let category = insert_rule(&pool, "category", (None, Some("Building")), "fixed", 0.25, 1).await;
+        insert_rule(&pool, "all", (None, None), "percent", 2.0, 1).await;
+        // An expired rule would otherwise be the best deal
+        sqlx::query(
+            "INSERT INTO discount_rules (name, scope, discount_type, value, min_quantity, end_date)
+             VALUES ('Old sale', 'all', 'percent', 50, 1, '2000-01-01')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let discounts = compute_auto_discounts_internal(
+            &pool,
+            &[line(cement, 12, 10.0), line(cement, 2, 10.0), line(sand, 4, 2.0), line(nails, 3, 1.0)],
+        )
+        .await
+        .unwrap();
+
+        // 12 bags: 5% of 120.00 beats 0.25 off each (3.00) and 2% (2.40)
+        assert_eq!(discounts[0].rule_id, Some(bulk));
+        assert_eq!(discounts[0].discount_amount, Money::from_minor(600));
+        assert_eq!(discounts[0].line_total, Money::from_minor(11400));
+        assert_eq!(discounts[0].description.as_deref(), Some("Buy 10+ get 5% off"));
+        // 2 bags miss the bulk rule: 0.50 fixed beats 2% (0.40)
+        assert_eq!(discounts[1].rule_id, Some(category));
+        assert_eq!(discounts[1].discount_amount, Money::from_minor(50));
+        assert_eq!(discounts[1].description.as_deref(), Some("$0.25 off each"));
+        // 4 x 2.00: 1.00 fixed beats 2% (0.16)
+        assert_eq!(discounts[2].discount_amount, Money::from_minor(100));
+        // Nails only match the store-wide 2%
+        assert_eq!(discounts[3].discount_amount, Money::from_minor(6));
+        assert_eq!(discounts[3].description.as_deref(), Some("2% off"));
+    }
+
+    #[tokio::test]
+    async fn test_fixed_discount_never_exceeds_the_line() {
+        let pool = test_pool().await;
+        let washer = insert_test_product(&pool, "WASHER", 0.10, 100).await;
+        insert_rule(&pool, "product", (Some(washer), None), "fixed", 0.5, 1).await;
+
+        let discounts = compute_auto_discounts_internal(&pool, &[line(washer, 3, 0.10)])
+            .await
+            .unwrap();
+        assert_eq!(discounts[0].discount_amount, Money::from_minor(30));
+        assert_eq!(discounts[0].line_total, Money::ZERO);
+
+        let err = compute_auto_discounts_internal(&pool, &[line(999, 1, 1.0)])
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+}
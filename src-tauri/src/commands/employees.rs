@@ -1,5 +1,6 @@
 // src-tauri/src/commands/employees.rs
 use crate::models::*;
+use crate::plans;
 use sqlx::SqlitePool;
 use tauri::State;
 
@@ -56,7 +57,9 @@ pub async fn get_employee(
 pub async fn create_employee(
     pool: State<'_, SqlitePool>,
     request: CreateEmployeeRequest,
+    session_token: String,
 ) -> Result<Employee, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     // Generate employee number
     let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM employees")
         .fetch_one(pool.inner())
@@ -101,7 +104,9 @@ pub async fn update_employee(
     pool: State<'_, SqlitePool>,
     employee_id: i64,
     request: UpdateEmployeeRequest,
+    session_token: String,
 ) -> Result<Employee, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     sqlx::query(
         "UPDATE employees SET
             department = COALESCE(?, department),
@@ -140,7 +145,12 @@ pub async fn update_employee(
 }
 
 #[tauri::command]
-pub async fn delete_employee(pool: State<'_, SqlitePool>, employee_id: i64) -> Result<(), String> {
+pub async fn delete_employee(
+    pool: State<'_, SqlitePool>,
+    employee_id: i64,
+    session_token: String,
+) -> Result<(), String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     sqlx::query("DELETE FROM employees WHERE id = ?")
         .bind(employee_id)
         .execute(pool.inner())
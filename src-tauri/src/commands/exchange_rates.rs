@@ -0,0 +1,191 @@
+// src-tauri/src/commands/exchange_rates.rs - Exchange rates for foreign-currency tenders
+use crate::currency::{self, ChangeDue};
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use crate::validation::validate_positive;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use tauri::{command, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExchangeRate {
+    pub id: i64,
+    pub currency: String,
+    /// Store-currency units one unit of `currency` buys
+    pub rate_to_base: f64,
+    pub effective_date: String,
+    pub created_by: Option<i64>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRateRequest {
+    pub currency: String,
+    pub rate_to_base: f64,
+    pub effective_date: String,
+}
+
+/// A sale's tender as handed over, with its store-currency value
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SalePayment {
+    pub id: i64,
+    pub sale_id: i64,
+    pub payment_method: String,
+    pub currency: String,
+    pub amount: Money,
+    pub exchange_rate: f64,
+    pub base_amount: Money,
+    pub change_base: Money,
+    pub change_amount: Money,
+    pub created_at: String,
+}
+
+const RATE_COLUMNS: &str =
+    "id, currency, rate_to_base, effective_date, created_by, COALESCE(created_at, '') as created_at";
+
+async fn validate_rate(pool: &SqlitePool, request: &ExchangeRateRequest) -> AppResult<String> {
+    let code = request.currency.trim().to_uppercase();
+    currency::validate_currency_code(pool, &code).await?;
+    if currency::store_currency(pool).await?.code == code {
+        return Err(AppError::validation(
+            "currency",
+            "The store currency always converts at 1",
+        ));
+    }
+    validate_positive(request.rate_to_base, "rate_to_base")?;
+    if chrono::NaiveDate::parse_from_str(&request.effective_date, "%Y-%m-%d").is_err() {
+        return Err(AppError::validation(
+            "effective_date",
+            "Effective date must be YYYY-MM-DD",
+        ));
+    }
+    Ok(code)
+}
+
+async fn fetch_rate(pool: &SqlitePool, rate_id: i64) -> AppResult<ExchangeRate> {
+    let query = format!("SELECT {} FROM exchange_rates WHERE id = ?1", RATE_COLUMNS);
+    sqlx::query_as::<_, ExchangeRate>(&query)
+        .bind(rate_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("Exchange rate"))
+}
+
+fn duplicate_rate(e: sqlx::Error) -> AppError {
+    match &e {
+        sqlx::Error::Database(db) if db.message().contains("UNIQUE") => AppError::Conflict {
+            message: "A rate for that currency already takes effect on that date".to_string(),
+        },
+        _ => e.into(),
+    }
+}
+
+#[command]
+pub async fn get_exchange_rates(
+    pool: State<'_, SqlitePool>,
+    currency: Option<String>,
+) -> Result<Vec<ExchangeRate>, AppError> {
+    let query = format!(
+        "SELECT {} FROM exchange_rates
+         WHERE (?1 IS NULL OR currency = ?1)
+         ORDER BY currency, effective_date DESC",
+        RATE_COLUMNS
+    );
+    Ok(sqlx::query_as::<_, ExchangeRate>(&query)
+        .bind(currency.map(|c| c.trim().to_uppercase()))
+        .fetch_all(pool.inner())
+        .await?)
+}
+
+#[command]
+pub async fn create_exchange_rate(
+    pool: State<'_, SqlitePool>,
+    request: ExchangeRateRequest,
+    user_id: i64,
+) -> Result<ExchangeRate, AppError> {
+    let code = validate_rate(pool.inner(), &request).await?;
+    let rate_id = sqlx::query(
+        "INSERT INTO exchange_rates (currency, rate_to_base, effective_date, created_by)
+         VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(&code)
+    .bind(request.rate_to_base)
+    .bind(&request.effective_date)
+    .bind(user_id)
+    .execute(pool.inner())
+    .await
+    .map_err(duplicate_rate)?
+    .last_insert_rowid();
+
+    fetch_rate(pool.inner(), rate_id).await
+}
+
+#[command]
+pub async fn update_exchange_rate(
+    pool: State<'_, SqlitePool>,
+    rate_id: i64,
+    request: ExchangeRateRequest,
+) -> Result<ExchangeRate, AppError> {
+    let code = validate_rate(pool.inner(), &request).await?;
+    let updated = sqlx::query(
+        "UPDATE exchange_rates SET currency = ?1, rate_to_base = ?2, effective_date = ?3 WHERE id = ?4",
+    )
+    .bind(&code)
+    .bind(request.rate_to_base)
+    .bind(&request.effective_date)
+    .bind(rate_id)
+    .execute(pool.inner())
+    .await
+    .map_err(duplicate_rate)?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::not_found("Exchange rate"));
+    }
+
+    fetch_rate(pool.inner(), rate_id).await
+}
+
+#[command]
+pub async fn delete_exchange_rate(
+    pool: State<'_, SqlitePool>,
+    rate_id: i64,
+) -> Result<(), AppError> {
+    let deleted = sqlx::query("DELETE FROM exchange_rates WHERE id = ?1")
+        .bind(rate_id)
+        .execute(pool.inner())
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::not_found("Exchange rate"));
+    }
+    Ok(())
+}
+
+/// Change owed on `amount_due` (store currency) for a tender in any currency, given in both
+#[command]
+pub async fn calculate_change_due(
+    pool: State<'_, SqlitePool>,
+    amount_due: Money,
+    tender_currency: String,
+    tender_amount: Money,
+) -> Result<ChangeDue, AppError> {
+    let base = currency::store_currency(pool.inner()).await?;
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let mut conn = pool.acquire().await?;
+    currency::change_due(&mut conn, &base, &tender_currency, tender_amount, amount_due, &today).await
+}
+
+#[command]
+pub async fn get_sale_payments(
+    pool: State<'_, SqlitePool>,
+    sale_id: i64,
+) -> Result<Vec<SalePayment>, AppError> {
+    Ok(sqlx::query_as::<_, SalePayment>(
+        "SELECT id, sale_id, payment_method, currency, amount, exchange_rate, base_amount,
+                change_base, change_amount, COALESCE(created_at, '') as created_at
+         FROM sale_payments
+         WHERE sale_id = ?1
+         ORDER BY id",
+    )
+    .bind(sale_id)
+    .fetch_all(pool.inner())
+    .await?)
+}
@@ -1,16 +1,11 @@
+use crate::demo_mode;
+use crate::document_numbers::next_document_number;
 use crate::models::{CreateExpenseRequest, Expense, UpdateExpenseRequest};
+use crate::plans;
+use crate::validation::Validate;
 use sqlx::{Row, SqlitePool};
 use tauri::{command, State};
 
-// Generate unique expense number
-async fn generate_expense_number(pool: &SqlitePool) -> Result<String, String> {
-    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM expenses")
-        .fetch_one(pool)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
-    Ok(format!("EXP{:06}", count + 1))
-}
-
 #[command]
 pub async fn get_expenses(
     pool: State<'_, SqlitePool>,
@@ -65,6 +60,7 @@ pub async fn get_expenses(
             created_by: row.try_get("created_by").ok(),
             created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
             updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
+            is_demo: row.try_get("is_demo").unwrap_or(false),
         });
     }
     Ok(expenses)
@@ -75,14 +71,19 @@ pub async fn create_expense(
     pool: State<'_, SqlitePool>,
     request: CreateExpenseRequest,
     user_id: i64,
+    session_token: String,
 ) -> Result<Expense, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    request.validate()?;
+
     let pool_ref = pool.inner();
-    let expense_number = generate_expense_number(pool_ref).await?;
+    let is_demo = demo_mode::is_enabled(pool_ref).await?;
+    let expense_number = next_document_number(pool_ref, "expense").await?;
 
     let result = sqlx::query(
         "INSERT INTO expenses (expense_number, category_id, vendor, description, amount, expense_date,
-         payment_method, reference_number, is_recurring, recurring_frequency, tags, notes, created_by)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)"
+         payment_method, reference_number, is_recurring, recurring_frequency, tags, notes, created_by, is_demo)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)"
     )
         .bind(&expense_number)
         .bind(&request.category_id)
@@ -97,6 +98,7 @@ pub async fn create_expense(
         .bind(&request.tags)
         .bind(&request.notes)
         .bind(user_id)
+        .bind(is_demo)
         .execute(pool_ref).await
         .map_err(|e| format!("Database error: {}", e))?;
 
@@ -140,6 +142,7 @@ pub async fn get_expense(pool: State<'_, SqlitePool>, expense_id: i64) -> Result
         created_by: row.try_get("created_by").ok(),
         created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
         updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
+        is_demo: row.try_get("is_demo").unwrap_or(false),
     })
 }
 
@@ -148,7 +151,11 @@ pub async fn update_expense(
     pool: State<'_, SqlitePool>,
     expense_id: i64,
     request: UpdateExpenseRequest,
+    session_token: String,
 ) -> Result<Expense, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    request.validate()?;
+
     let pool_ref = pool.inner();
 
     let mut updates = Vec::new();
@@ -216,7 +223,9 @@ pub async fn update_expense(
 pub async fn delete_expense(
     pool: State<'_, SqlitePool>,
     expense_id: i64,
+    session_token: String,
 ) -> Result<String, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
     let result = sqlx::query("DELETE FROM expenses WHERE id = ?1")
         .bind(expense_id)
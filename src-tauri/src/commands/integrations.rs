@@ -1,5 +1,8 @@
 // src-tauri/src/commands/integrations.rs
 use crate::models::*;
+use crate::rest_api::{self, ApiKey, CreateApiKeyRequest, NewApiKey};
+use crate::session::SESSION_MANAGER;
+use crate::webhooks::{self, Webhook, WebhookDelivery, WebhookRequest};
 use sqlx::SqlitePool;
 use tauri::State;
 
@@ -112,3 +115,84 @@ pub async fn test_integration(
     // In a real implementation, you would test the connection to the integration
     Ok(())
 }
+
+#[tauri::command]
+pub async fn get_webhooks(pool: State<'_, SqlitePool>) -> Result<Vec<Webhook>, String> {
+    Ok(webhooks::get_webhooks(pool.inner()).await?)
+}
+
+#[tauri::command]
+pub async fn create_webhook(
+    pool: State<'_, SqlitePool>,
+    request: WebhookRequest,
+) -> Result<Webhook, String> {
+    Ok(webhooks::create_webhook(pool.inner(), &request).await?)
+}
+
+#[tauri::command]
+pub async fn update_webhook(
+    pool: State<'_, SqlitePool>,
+    webhook_id: i64,
+    request: WebhookRequest,
+) -> Result<Webhook, String> {
+    Ok(webhooks::update_webhook(pool.inner(), webhook_id, &request).await?)
+}
+
+#[tauri::command]
+pub async fn delete_webhook(pool: State<'_, SqlitePool>, webhook_id: i64) -> Result<(), String> {
+    Ok(webhooks::delete_webhook(pool.inner(), webhook_id).await?)
+}
+
+/// Post a test event to the webhook now and return how the delivery went
+#[tauri::command]
+pub async fn test_webhook(
+    pool: State<'_, SqlitePool>,
+    webhook_id: i64,
+) -> Result<WebhookDelivery, String> {
+    Ok(webhooks::test_webhook(pool.inner(), &webhooks::http_client(), webhook_id).await?)
+}
+
+/// Latest 100 deliveries for a webhook, newest first
+#[tauri::command]
+pub async fn get_webhook_deliveries(
+    pool: State<'_, SqlitePool>,
+    webhook_id: i64,
+) -> Result<Vec<WebhookDelivery>, String> {
+    Ok(webhooks::get_deliveries(pool.inner(), webhook_id, 100).await?)
+}
+
+#[tauri::command]
+pub async fn get_api_keys(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<Vec<ApiKey>, String> {
+    let session = SESSION_MANAGER.require_full_session(&session_token, &["Admin"])?;
+    Ok(rest_api::get_api_keys(pool.inner(), session.organization_id).await?)
+}
+
+/// Create a key for the LAN REST API; the returned plaintext key is not stored and cannot be shown again
+#[tauri::command]
+pub async fn create_api_key(
+    pool: State<'_, SqlitePool>,
+    request: CreateApiKeyRequest,
+    session_token: String,
+) -> Result<NewApiKey, String> {
+    let session = SESSION_MANAGER.require_full_session(&session_token, &["Admin"])?;
+    Ok(rest_api::create_api_key(
+        pool.inner(),
+        session.organization_id,
+        &request,
+        Some(session.user_id),
+    )
+    .await?)
+}
+
+#[tauri::command]
+pub async fn revoke_api_key(
+    pool: State<'_, SqlitePool>,
+    api_key_id: i64,
+    session_token: String,
+) -> Result<(), String> {
+    let session = SESSION_MANAGER.require_full_session(&session_token, &["Admin"])?;
+    Ok(rest_api::revoke_api_key(pool.inner(), session.organization_id, api_key_id).await?)
+}
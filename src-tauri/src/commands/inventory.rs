@@ -1,4 +1,11 @@
+use crate::commands::products::require_product_in_organization;
+use crate::error::AppError;
+use crate::location_stock::{location_levels_join, location_stock_sql};
+use crate::lots::{self, ExpiringLot, ProductLot};
 use crate::models::{InventoryItem, StockUpdateRequest};
+use crate::plans;
+use crate::tenancy::{self, active_organization, organization_scope};
+use crate::validation::Validate;
 use serde::{Deserialize, Serialize};
 use sqlx::{Row, SqlitePool};
 use tauri::{command, State};
@@ -17,35 +24,46 @@ pub struct InventoryMovement {
     pub user_id: Option<i64>,
     pub created_at: String,
     pub product_name: Option<String>,
+    pub product_sku: Option<String>,
     pub user_name: Option<String>,
+    /// Stock on hand right after this movement
+    pub running_balance: i32,
 }
 
 #[command]
-pub async fn sync_inventory(pool: State<'_, SqlitePool>) -> Result<i32, String> {
+pub async fn sync_inventory(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<i32, AppError> {
     let pool_ref = pool.inner();
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
     
     // Find products without inventory records and create them
-    let result = sqlx::query(
-        "INSERT INTO inventory (product_id, current_stock, available_stock, minimum_stock, maximum_stock, reserved_stock, stock_take_count)
-         SELECT p.id, 0, 0, p.reorder_point, 1000, 0, 0
+    let result = sqlx::query(&format!(
+        "INSERT INTO inventory (product_id, current_stock, available_stock, minimum_stock, maximum_stock, reserved_stock, stock_take_count, organization_id)
+         SELECT p.id, 0, 0, p.reorder_point, 1000, 0, 0, p.organization_id
          FROM products p
-         WHERE NOT EXISTS (SELECT 1 FROM inventory i WHERE i.product_id = p.id)"
-    )
+         WHERE NOT EXISTS (SELECT 1 FROM inventory i WHERE i.product_id = p.id){}",
+        organization_scope("p.organization_id", organization_id)
+    ))
     .execute(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to sync inventory: {}", e))?;
+    .await?;
     
     Ok(result.rows_affected() as i32)
 }
 
 #[command]
-pub async fn get_inventory(pool: State<'_, SqlitePool>) -> Result<Vec<InventoryItem>, String> {
+pub async fn get_inventory(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<Vec<InventoryItem>, AppError> {
     let pool_ref = pool.inner();
+    let organization_id = active_organization(&session_token)?;
 
     // Note: sync_inventory should be called explicitly when needed (e.g., after creating products)
     // Calling it on every get_inventory request causes unnecessary database operations
 
-    let rows = sqlx::query(
+    let rows = sqlx::query(&format!(
         "SELECT 
                 i.id, 
                 p.id as product_id, 
@@ -63,49 +81,50 @@ pub async fn get_inventory(pool: State<'_, SqlitePool>) -> Result<Vec<InventoryI
                 p.created_at, p.updated_at
          FROM products p
          LEFT JOIN inventory i ON p.id = i.product_id
+         WHERE 1 = 1{}
          ORDER BY p.name ASC",
-    )
+        organization_scope("p.organization_id", organization_id)
+    ))
     .fetch_all(pool_ref)
-    .await
-    .map_err(|e| format!("Database error: {}", e))?;
+    .await?;
 
     let mut inventory_items = Vec::new();
     for row in rows {
         let product = crate::models::Product {
-            id: row.try_get("product_id").map_err(|e| e.to_string())?,
-            sku: row.try_get("sku").map_err(|e| e.to_string())?,
+            id: row.try_get("product_id")?,
+            sku: row.try_get("sku")?,
             barcode: row.try_get("barcode").ok().flatten(),
-            name: row.try_get("name").map_err(|e| e.to_string())?,
+            name: row.try_get("name")?,
             description: row.try_get("description").ok().flatten(),
             category: row.try_get("category").ok().flatten(),
             subcategory: row.try_get("subcategory").ok().flatten(),
             brand: row.try_get("brand").ok().flatten(),
-            unit_of_measure: row.try_get("unit_of_measure").map_err(|e| e.to_string())?,
-            cost_price: row.try_get("cost_price").map_err(|e| e.to_string())?,
-            selling_price: row.try_get("selling_price").map_err(|e| e.to_string())?,
-            wholesale_price: row.try_get("wholesale_price").map_err(|e| e.to_string())?,
-            tax_rate: row.try_get("tax_rate").map_err(|e| e.to_string())?,
-            is_active: row.try_get("is_active").map_err(|e| e.to_string())?,
-            is_taxable: row.try_get("is_taxable").map_err(|e| e.to_string())?,
-            weight: row.try_get("weight").map_err(|e| e.to_string())?,
+            unit_of_measure: row.try_get("unit_of_measure")?,
+            cost_price: row.try_get("cost_price")?,
+            selling_price: row.try_get("selling_price")?,
+            wholesale_price: row.try_get("wholesale_price")?,
+            tax_rate: row.try_get("tax_rate")?,
+            is_active: row.try_get("is_active")?,
+            is_taxable: row.try_get("is_taxable")?,
+            weight: row.try_get("weight")?,
             dimensions: row.try_get("dimensions").ok().flatten(),
             supplier_info: row.try_get("supplier_info").ok().flatten(),
-            reorder_point: row.try_get("reorder_point").map_err(|e| e.to_string())?,
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-            updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
+            reorder_point: row.try_get("reorder_point")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
         };
 
         let inventory_item = InventoryItem {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            product_id: row.try_get("product_id").map_err(|e| e.to_string())?,
-            current_stock: row.try_get("current_stock").map_err(|e| e.to_string())?,
-            minimum_stock: row.try_get("minimum_stock").map_err(|e| e.to_string())?,
-            maximum_stock: row.try_get("maximum_stock").map_err(|e| e.to_string())?,
-            reserved_stock: row.try_get("reserved_stock").map_err(|e| e.to_string())?,
-            available_stock: row.try_get("available_stock").map_err(|e| e.to_string())?,
-            last_updated: row.try_get("last_updated").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            product_id: row.try_get("product_id")?,
+            current_stock: row.try_get("current_stock")?,
+            minimum_stock: row.try_get("minimum_stock")?,
+            maximum_stock: row.try_get("maximum_stock")?,
+            reserved_stock: row.try_get("reserved_stock")?,
+            available_stock: row.try_get("available_stock")?,
+            last_updated: row.try_get("last_updated")?,
             last_stock_take: row.try_get("last_stock_take").ok().flatten(),
-            stock_take_count: row.try_get("stock_take_count").map_err(|e| e.to_string())?,
+            stock_take_count: row.try_get("stock_take_count")?,
             product: Some(product),
         };
 
@@ -119,35 +138,38 @@ pub async fn get_inventory(pool: State<'_, SqlitePool>) -> Result<Vec<InventoryI
 pub async fn update_stock(
     pool: State<'_, SqlitePool>,
     request: StockUpdateRequest,
-) -> Result<bool, String> {
+    session_token: String,
+) -> Result<bool, AppError> {
+    request.validate()?;
+
     let pool_ref = pool.inner();
+    let organization_id = active_organization(&session_token)?;
 
     // Start transaction to ensure atomicity
-    let mut tx = pool_ref
-        .begin()
-        .await
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut tx = pool_ref.begin().await?;
+    tenancy::require_in_organization(&mut tx, "products", request.product_id, organization_id, "Product")
+        .await?;
+    plans::require_writable(&mut tx, organization_id).await?;
 
     // Get current stock
     let current_stock =
         sqlx::query("SELECT current_stock, reserved_stock FROM inventory WHERE product_id = ?1")
             .bind(request.product_id)
-            .fetch_one(&mut *tx)
-            .await
-            .map_err(|e| format!("Failed to get current stock: {}", e))?;
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::inventory_not_found(request.product_id))?;
 
-    let current_stock_value: i32 = current_stock
-        .try_get("current_stock")
-        .map_err(|e| e.to_string())?;
-    let reserved_stock_value: i32 = current_stock
-        .try_get("reserved_stock")
-        .map_err(|e| e.to_string())?;
+    let current_stock_value: i32 = current_stock.try_get("current_stock")?;
+    let reserved_stock_value: i32 = current_stock.try_get("reserved_stock")?;
 
     let new_stock = current_stock_value + request.quantity_change;
     let new_available_stock = new_stock - reserved_stock_value;
 
     if new_stock < 0 {
-        return Err("Stock cannot go below zero".to_string());
+        return Err(AppError::validation(
+            "quantity_change",
+            "Stock cannot go below zero",
+        ));
     }
 
     // Update inventory
@@ -162,8 +184,7 @@ pub async fn update_stock(
     .bind(new_available_stock)
     .bind(request.product_id)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| format!("Failed to update inventory: {}", e))?;
+    .await?;
 
     // Record movement
     sqlx::query(
@@ -181,84 +202,143 @@ pub async fn update_stock(
     .bind(&request.notes)
     .bind(request.user_id)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| format!("Failed to record inventory movement: {}", e))?;
+    .await?;
 
     // Commit transaction
-    tx.commit()
-        .await
-        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    tx.commit().await?;
 
     Ok(true)
 }
 
+/// Movements newest first, with filters. Pass the id of the last movement already shown as
+/// `before_id` to fetch the next page; unlike `offset`, that stays stable while new movements arrive.
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn get_inventory_movements(
     pool: State<'_, SqlitePool>,
     product_id: Option<i64>,
+    movement_type: Option<String>,
+    user_id: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    before_id: Option<i64>,
     limit: Option<i32>,
     offset: Option<i32>,
-) -> Result<Vec<InventoryMovement>, String> {
-    let pool_ref = pool.inner();
+    session_token: String,
+) -> Result<Vec<InventoryMovement>, AppError> {
+    let organization_id = active_organization(&session_token)?;
+    let filter = MovementFilter {
+        product_id,
+        movement_type,
+        user_id,
+        start_date,
+        end_date,
+        before_id,
+    };
+    get_inventory_movements_internal(pool.inner(), organization_id, &filter, limit, offset).await
+}
 
-    let limit = limit.unwrap_or(100);
-    let offset = offset.unwrap_or(0);
+#[derive(Debug, Default)]
+pub struct MovementFilter {
+    pub product_id: Option<i64>,
+    pub movement_type: Option<String>,
+    pub user_id: Option<i64>,
+    /// Inclusive `YYYY-MM-DD` bounds on the movement date
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub before_id: Option<i64>,
+}
 
-    let query = if let Some(_pid) = product_id {
-        "SELECT im.id, im.product_id, im.movement_type, im.quantity_change, im.previous_stock,
-                im.new_stock, im.reference_id, im.reference_type, im.notes, im.user_id, im.created_at,
-                p.name as product_name,
-                u.first_name || ' ' || u.last_name as user_name
-         FROM inventory_movements im
-         JOIN products p ON im.product_id = p.id
-         LEFT JOIN users u ON im.user_id = u.id
-         WHERE im.product_id = ?1
-         ORDER BY im.created_at DESC
-         LIMIT ?2 OFFSET ?3"
-    } else {
-        "SELECT im.id, im.product_id, im.movement_type, im.quantity_change, im.previous_stock,
+pub(crate) async fn get_inventory_movements_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    filter: &MovementFilter,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<Vec<InventoryMovement>, AppError> {
+    let limit = limit.unwrap_or(100).clamp(1, 1000);
+    // A cursor replaces the offset, the two would skip rows twice
+    let offset = if filter.before_id.is_some() { 0 } else { offset.unwrap_or(0).max(0) };
+
+    // The balance is anchored on today's stock and walks back through every later movement,
+    // so it is computed before filtering and always ends at current_stock.
+    let mut query = String::from(
+        "WITH balances AS (
+            SELECT im.id,
+                   COALESCE(i.current_stock, 0) - COALESCE(SUM(im.quantity_change) OVER (
+                       PARTITION BY im.product_id ORDER BY im.id DESC
+                       ROWS BETWEEN UNBOUNDED PRECEDING AND 1 PRECEDING), 0) as running_balance
+            FROM inventory_movements im
+            LEFT JOIN inventory i ON i.product_id = im.product_id",
+    );
+    let mut params: Vec<String> = Vec::new();
+    if let Some(product_id) = filter.product_id {
+        query.push_str(" WHERE im.product_id = ?");
+        params.push(product_id.to_string());
+    }
+    query.push_str(
+        "
+         )
+         SELECT im.id, im.product_id, im.movement_type, im.quantity_change, im.previous_stock,
                 im.new_stock, im.reference_id, im.reference_type, im.notes, im.user_id, im.created_at,
-                p.name as product_name,
-                u.first_name || ' ' || u.last_name as user_name
+                p.name as product_name, p.sku as product_sku,
+                u.first_name || ' ' || u.last_name as user_name,
+                b.running_balance
          FROM inventory_movements im
+         JOIN balances b ON b.id = im.id
          JOIN products p ON im.product_id = p.id
          LEFT JOIN users u ON im.user_id = u.id
-         ORDER BY im.created_at DESC
-         LIMIT ?1 OFFSET ?2"
-    };
+         WHERE 1=1",
+    );
+    query.push_str(&organization_scope("p.organization_id", organization_id));
+    if let Some(movement_type) = filter.movement_type.as_deref().filter(|t| !t.is_empty()) {
+        query.push_str(" AND im.movement_type = ?");
+        params.push(movement_type.to_string());
+    }
+    if let Some(user_id) = filter.user_id {
+        query.push_str(" AND im.user_id = ?");
+        params.push(user_id.to_string());
+    }
+    if let Some(start) = filter.start_date.as_deref().filter(|d| !d.is_empty()) {
+        query.push_str(" AND DATE(im.created_at) >= ?");
+        params.push(start.to_string());
+    }
+    if let Some(end) = filter.end_date.as_deref().filter(|d| !d.is_empty()) {
+        query.push_str(" AND DATE(im.created_at) <= ?");
+        params.push(end.to_string());
+    }
+    if let Some(before_id) = filter.before_id {
+        query.push_str(" AND im.id < ?");
+        params.push(before_id.to_string());
+    }
+    query.push_str(" ORDER BY im.id DESC LIMIT ? OFFSET ?");
+    params.push(limit.to_string());
+    params.push(offset.to_string());
 
-    let rows = if let Some(pid) = product_id {
-        sqlx::query(query)
-            .bind(pid)
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(pool_ref)
-            .await
-    } else {
-        sqlx::query(query)
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(pool_ref)
-            .await
+    let mut sql_query = sqlx::query(&query);
+    for param in &params {
+        sql_query = sql_query.bind(param);
     }
-    .map_err(|e| format!("Database error: {}", e))?;
+    let rows = sql_query.fetch_all(pool).await?;
 
     let mut movements = Vec::new();
     for row in rows {
         let movement = InventoryMovement {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            product_id: row.try_get("product_id").map_err(|e| e.to_string())?,
-            movement_type: row.try_get("movement_type").map_err(|e| e.to_string())?,
-            quantity_change: row.try_get("quantity_change").map_err(|e| e.to_string())?,
-            previous_stock: row.try_get("previous_stock").map_err(|e| e.to_string())?,
-            new_stock: row.try_get("new_stock").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            product_id: row.try_get("product_id")?,
+            movement_type: row.try_get("movement_type")?,
+            quantity_change: row.try_get("quantity_change")?,
+            previous_stock: row.try_get("previous_stock")?,
+            new_stock: row.try_get("new_stock")?,
             reference_id: row.try_get("reference_id").ok().flatten(),
             reference_type: row.try_get("reference_type").ok().flatten(),
             notes: row.try_get("notes").ok().flatten(),
             user_id: row.try_get("user_id").ok().flatten(),
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
+            created_at: row.try_get("created_at")?,
             product_name: row.try_get("product_name").ok().flatten(),
+            product_sku: row.try_get("product_sku").ok().flatten(),
             user_name: row.try_get("user_name").ok().flatten(),
+            running_balance: row.try_get("running_balance")?,
         };
         movements.push(movement);
     }
@@ -266,6 +346,76 @@ pub async fn get_inventory_movements(
     Ok(movements)
 }
 
+/// Units moved in and out for one movement type on one day
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MovementSummary {
+    pub date: String,
+    pub movement_type: String,
+    pub quantity_in: i64,
+    pub quantity_out: i64,
+    pub movement_count: i64,
+}
+
+fn period_days(period: Option<&str>) -> Result<i64, AppError> {
+    match period.unwrap_or("month") {
+        "week" => Ok(7),
+        "month" => Ok(30),
+        "quarter" => Ok(90),
+        "year" => Ok(365),
+        other => Err(AppError::validation(
+            "period",
+            &format!("Unknown period '{}', expected week, month, quarter or year", other),
+        )),
+    }
+}
+
+/// Daily in/out totals by movement type for a product over the last week, month, quarter or year
+#[command]
+pub async fn get_movement_summary(
+    pool: State<'_, SqlitePool>,
+    product_id: i64,
+    period: Option<String>,
+    session_token: String,
+) -> Result<Vec<MovementSummary>, AppError> {
+    let organization_id = active_organization(&session_token)?;
+    require_product_in_organization(pool.inner(), product_id, organization_id).await?;
+    get_movement_summary_internal(pool.inner(), product_id, period.as_deref()).await
+}
+
+pub(crate) async fn get_movement_summary_internal(
+    pool: &SqlitePool,
+    product_id: i64,
+    period: Option<&str>,
+) -> Result<Vec<MovementSummary>, AppError> {
+    let days = period_days(period)?;
+    let rows = sqlx::query(
+        "SELECT DATE(created_at) as date, movement_type,
+                COALESCE(SUM(CASE WHEN quantity_change > 0 THEN quantity_change END), 0) as quantity_in,
+                COALESCE(SUM(CASE WHEN quantity_change < 0 THEN -quantity_change END), 0) as quantity_out,
+                COUNT(*) as movement_count
+         FROM inventory_movements
+         WHERE product_id = ?1 AND DATE(created_at) > DATE('now', ?2)
+         GROUP BY DATE(created_at), movement_type
+         ORDER BY date, movement_type",
+    )
+    .bind(product_id)
+    .bind(format!("-{} days", days))
+    .fetch_all(pool)
+    .await?;
+
+    let mut summary = Vec::with_capacity(rows.len());
+    for row in rows {
+        summary.push(MovementSummary {
+            date: row.try_get("date")?,
+            movement_type: row.try_get("movement_type")?,
+            quantity_in: row.try_get("quantity_in")?,
+            quantity_out: row.try_get("quantity_out")?,
+            movement_count: row.try_get("movement_count")?,
+        });
+    }
+    Ok(summary)
+}
+
 #[command]
 pub async fn create_stock_adjustment(
     pool: State<'_, SqlitePool>,
@@ -273,7 +423,8 @@ pub async fn create_stock_adjustment(
     quantity_change: i32,
     reason: String,
     user_id: i64,
-) -> Result<bool, String> {
+    session_token: String,
+) -> Result<bool, AppError> {
     let _pool_ref = pool.inner();
 
     let request = StockUpdateRequest {
@@ -284,76 +435,110 @@ pub async fn create_stock_adjustment(
         reference_id: None,
         reference_type: None,
         user_id: Some(user_id),
+        reason_code: None,
     };
 
-    update_stock(pool, request).await
+    update_stock(pool, request, session_token).await
 }
 
 #[command]
 pub async fn get_low_stock_items(
     pool: State<'_, SqlitePool>,
     limit: Option<i32>,
-) -> Result<Vec<InventoryItem>, String> {
-    let pool_ref = pool.inner();
-
-    let limit = limit.unwrap_or(50);
+    location_id: Option<i64>,
+    session_token: String,
+) -> Result<Vec<InventoryItem>, AppError> {
+    let organization_id = active_organization(&session_token)?;
+    get_low_stock_items_internal(pool.inner(), organization_id, limit.unwrap_or(50), location_id).await
+}
 
-    let rows = sqlx::query(
-        "SELECT i.id, i.product_id, i.current_stock, i.minimum_stock, i.maximum_stock,
-                i.reserved_stock, i.available_stock, i.last_updated, i.last_stock_take,
-                i.stock_take_count,
-                p.sku, p.barcode, p.name, p.description, p.category, p.subcategory, p.brand,
-                p.unit_of_measure, p.cost_price, p.selling_price, p.wholesale_price, p.tax_rate,
-                p.is_active, p.is_taxable, p.weight, p.dimensions, p.supplier_info, p.reorder_point,
-                p.created_at, p.updated_at
-         FROM inventory i
-         JOIN products p ON i.product_id = p.id
-         WHERE i.current_stock <= i.minimum_stock AND p.is_active = 1
-         ORDER BY (i.minimum_stock - i.current_stock) DESC
+/// Products of the organization at or under their minimum stock, across the business or,
+/// given a location, at that location against the levels set there
+pub(crate) async fn get_low_stock_items_internal(
+    pool_ref: &SqlitePool,
+    organization_id: i64,
+    limit: i32,
+    location_id: Option<i64>,
+) -> Result<Vec<InventoryItem>, AppError> {
+    let (stock, levels_join) = match location_id {
+        Some(_) => (location_stock_sql("i.product_id", "?2"), location_levels_join("?2")),
+        None => ("i.current_stock".to_string(), String::new()),
+    };
+    let (minimum, maximum) = match location_id {
+        Some(_) => (
+            "COALESCE(lv.minimum_stock, i.minimum_stock)",
+            "COALESCE(lv.maximum_stock, i.maximum_stock)",
+        ),
+        None => ("i.minimum_stock", "i.maximum_stock"),
+    };
+    let query = format!(
+        "SELECT * FROM (
+             SELECT i.id, i.product_id, {stock} as current_stock, {minimum} as minimum_stock,
+                    {maximum} as maximum_stock,
+                    i.reserved_stock, i.available_stock, i.last_updated, i.last_stock_take,
+                    i.stock_take_count,
+                    p.sku, p.barcode, p.name, p.description, p.category, p.subcategory, p.brand,
+                    p.unit_of_measure, p.cost_price, p.selling_price, p.wholesale_price, p.tax_rate,
+                    p.is_active, p.is_taxable, p.weight, p.dimensions, p.supplier_info, p.reorder_point,
+                    p.created_at, p.updated_at
+             FROM inventory i
+             JOIN products p ON i.product_id = p.id
+             {levels_join}
+             WHERE p.is_active = 1{scope}
+         )
+         WHERE current_stock <= minimum_stock
+         ORDER BY (minimum_stock - current_stock) DESC
          LIMIT ?1",
-    )
-    .bind(limit)
-    .fetch_all(pool_ref)
-    .await
-    .map_err(|e| format!("Database error: {}", e))?;
+        stock = stock,
+        minimum = minimum,
+        maximum = maximum,
+        levels_join = levels_join,
+        scope = organization_scope("i.organization_id", organization_id)
+    );
+
+    let rows = sqlx::query(&query)
+        .bind(limit)
+        .bind(location_id)
+        .fetch_all(pool_ref)
+        .await?;
 
     let mut low_stock_items = Vec::new();
     for row in rows {
         let product = crate::models::Product {
-            id: row.try_get("product_id").map_err(|e| e.to_string())?,
-            sku: row.try_get("sku").map_err(|e| e.to_string())?,
+            id: row.try_get("product_id")?,
+            sku: row.try_get("sku")?,
             barcode: row.try_get("barcode").ok().flatten(),
-            name: row.try_get("name").map_err(|e| e.to_string())?,
+            name: row.try_get("name")?,
             description: row.try_get("description").ok().flatten(),
             category: row.try_get("category").ok().flatten(),
             subcategory: row.try_get("subcategory").ok().flatten(),
             brand: row.try_get("brand").ok().flatten(),
-            unit_of_measure: row.try_get("unit_of_measure").map_err(|e| e.to_string())?,
-            cost_price: row.try_get("cost_price").map_err(|e| e.to_string())?,
-            selling_price: row.try_get("selling_price").map_err(|e| e.to_string())?,
-            wholesale_price: row.try_get("wholesale_price").map_err(|e| e.to_string())?,
-            tax_rate: row.try_get("tax_rate").map_err(|e| e.to_string())?,
-            is_active: row.try_get("is_active").map_err(|e| e.to_string())?,
-            is_taxable: row.try_get("is_taxable").map_err(|e| e.to_string())?,
-            weight: row.try_get("weight").map_err(|e| e.to_string())?,
+            unit_of_measure: row.try_get("unit_of_measure")?,
+            cost_price: row.try_get("cost_price")?,
+            selling_price: row.try_get("selling_price")?,
+            wholesale_price: row.try_get("wholesale_price")?,
+            tax_rate: row.try_get("tax_rate")?,
+            is_active: row.try_get("is_active")?,
+            is_taxable: row.try_get("is_taxable")?,
+            weight: row.try_get("weight")?,
             dimensions: row.try_get("dimensions").ok().flatten(),
             supplier_info: row.try_get("supplier_info").ok().flatten(),
-            reorder_point: row.try_get("reorder_point").map_err(|e| e.to_string())?,
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-            updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
+            reorder_point: row.try_get("reorder_point")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
         };
 
         let inventory_item = InventoryItem {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            product_id: row.try_get("product_id").map_err(|e| e.to_string())?,
-            current_stock: row.try_get("current_stock").map_err(|e| e.to_string())?,
-            minimum_stock: row.try_get("minimum_stock").map_err(|e| e.to_string())?,
-            maximum_stock: row.try_get("maximum_stock").map_err(|e| e.to_string())?,
-            reserved_stock: row.try_get("reserved_stock").map_err(|e| e.to_string())?,
-            available_stock: row.try_get("available_stock").map_err(|e| e.to_string())?,
-            last_updated: row.try_get("last_updated").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            product_id: row.try_get("product_id")?,
+            current_stock: row.try_get("current_stock")?,
+            minimum_stock: row.try_get("minimum_stock")?,
+            maximum_stock: row.try_get("maximum_stock")?,
+            reserved_stock: row.try_get("reserved_stock")?,
+            available_stock: row.try_get("available_stock")?,
+            last_updated: row.try_get("last_updated")?,
             last_stock_take: row.try_get("last_stock_take").ok().flatten(),
-            stock_take_count: row.try_get("stock_take_count").map_err(|e| e.to_string())?,
+            stock_take_count: row.try_get("stock_take_count")?,
             product: Some(product),
         };
 
@@ -362,3 +547,194 @@ pub async fn get_low_stock_items(
 
     Ok(low_stock_items)
 }
+
+/// Lots of a product, earliest expiry first; empty lots only when asked for
+#[command]
+pub async fn get_product_lots(
+    pool: State<'_, SqlitePool>,
+    product_id: i64,
+    include_empty: Option<bool>,
+    session_token: String,
+) -> Result<Vec<ProductLot>, AppError> {
+    let organization_id = active_organization(&session_token)?;
+    require_product_in_organization(pool.inner(), product_id, organization_id).await?;
+    lots::get_product_lots(pool.inner(), product_id, include_empty.unwrap_or(false)).await
+}
+
+/// Lots expiring within `days` (default 30) and ones already expired, for waste prevention
+#[command]
+pub async fn get_expiring_lots(
+    pool: State<'_, SqlitePool>,
+    days: Option<i64>,
+    session_token: String,
+) -> Result<Vec<ExpiringLot>, AppError> {
+    let organization_id = active_organization(&session_token)?;
+    let days = days.unwrap_or(lots::DEFAULT_EXPIRY_WINDOW_DAYS);
+    lots::get_expiring_lots(pool.inner(), organization_id, days).await
+}
+
+#[command]
+pub async fn set_product_lot_tracking(
+    pool: State<'_, SqlitePool>,
+    product_id: i64,
+    lot_tracked: bool,
+    session_token: String,
+) -> Result<bool, AppError> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    require_product_in_organization(pool.inner(), product_id, organization_id).await?;
+    lots::set_lot_tracking(pool.inner(), product_id, lot_tracked).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+
+    /// Apply a stock change the way the stock commands do: inventory and movement together
+    async fn move_stock(pool: &SqlitePool, product_id: i64, change: i32, movement_type: &str, user_id: i64) {
+        let previous = current_stock(pool, product_id).await;
+        sqlx::query("UPDATE inventory SET current_stock = current_stock + ?1 WHERE product_id = ?2")
+            .bind(change)
+            .bind(product_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO inventory_movements
+                (product_id, movement_type, quantity_change, previous_stock, new_stock, user_id)
+             VALUES (?1, ?2, ?3, ?4, ?4 + ?3, ?5)",
+        )
+        .bind(product_id)
+        .bind(movement_type)
+        .bind(change)
+        .bind(previous)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_running_balance_ends_at_current_stock() {
+        let pool = test_pool().await;
+        let clerk = insert_test_user(&pool, "clerk").await;
+        let manager = insert_test_user(&pool, "manager").await;
+        let sand = insert_test_product(&pool, "SAND", 10.0, 20).await;
+        let gravel = insert_test_product(&pool, "GRAVEL", 10.0, 5).await;
+
+        move_stock(&pool, sand, 30, "receipt", manager).await;
+        move_stock(&pool, gravel, -2, "sale", clerk).await;
+        move_stock(&pool, sand, -4, "sale", clerk).await;
+        move_stock(&pool, sand, -1, "damage", manager).await;
+
+        let filter = MovementFilter {
+            product_id: Some(sand),
+            ..Default::default()
+        };
+        let movements = get_inventory_movements_internal(&pool, DEFAULT_ORGANIZATION_ID, &filter, None, None)
+            .await
+            .unwrap();
+        assert_eq!(movements.len(), 3);
+        assert_eq!(movements[0].running_balance, current_stock(&pool, sand).await);
+        assert_eq!(movements[0].running_balance, 45);
+        assert!(movements.iter().all(|m| m.running_balance == m.new_stock));
+        assert_eq!(movements[0].product_sku.as_deref(), Some("SAND"));
+        assert_eq!(movements[0].user_name.as_deref(), Some("Test User"));
+
+        // Filters narrow the rows without changing each row's balance
+        let filter = MovementFilter {
+            movement_type: Some("sale".to_string()),
+            user_id: Some(clerk),
+            ..Default::default()
+        };
+        let sales = get_inventory_movements_internal(&pool, DEFAULT_ORGANIZATION_ID, &filter, None, None)
+            .await
+            .unwrap();
+        let balances: Vec<(i64, i32)> = sales.iter().map(|m| (m.product_id, m.running_balance)).collect();
+        assert_eq!(balances, vec![(sand, 46), (gravel, 3)]);
+
+        let summary = get_movement_summary_internal(&pool, sand, Some("week")).await.unwrap();
+        let by_type: Vec<(&str, i64, i64)> = summary
+            .iter()
+            .map(|s| (s.movement_type.as_str(), s.quantity_in, s.quantity_out))
+            .collect();
+        assert_eq!(by_type, vec![("damage", 0, 1), ("receipt", 30, 0), ("sale", 0, 4)]);
+        let err = get_movement_summary_internal(&pool, sand, Some("decade")).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+
+    #[tokio::test]
+    async fn test_keyset_pages_stay_stable_when_movements_arrive() {
+        let pool = test_pool().await;
+        let clerk = insert_test_user(&pool, "clerk").await;
+        let sand = insert_test_product(&pool, "SAND", 10.0, 100).await;
+        for _ in 0..5 {
+            move_stock(&pool, sand, -1, "sale", clerk).await;
+        }
+
+        let first = get_inventory_movements_internal(&pool, DEFAULT_ORGANIZATION_ID, &MovementFilter::default(), Some(2), None)
+            .await
+            .unwrap();
+        move_stock(&pool, sand, -1, "sale", clerk).await;
+
+        let mut seen: Vec<i64> = first.iter().map(|m| m.id).collect();
+        let mut cursor = first.last().map(|m| m.id);
+        while let Some(before_id) = cursor {
+            let filter = MovementFilter {
+                before_id: Some(before_id),
+                ..Default::default()
+            };
+            let page = get_inventory_movements_internal(&pool, DEFAULT_ORGANIZATION_ID, &filter, Some(2), None)
+                .await
+                .unwrap();
+            seen.extend(page.iter().map(|m| m.id));
+            cursor = page.last().map(|m| m.id);
+        }
+
+        assert_eq!(seen.len(), 5);
+        let mut unique = seen.clone();
+        unique.dedup();
+        assert_eq!(unique, seen);
+    }
+
+    #[tokio::test]
+    async fn test_low_stock_lists_only_the_organizations_inventory() {
+        let pool = test_pool().await;
+        let ours = insert_test_product(&pool, "OURS", 10.0, 0).await;
+        let theirs = insert_test_product(&pool, "THEIRS", 10.0, 0).await;
+        let other: i64 = sqlx::query_scalar(
+            "INSERT INTO organizations (name, slug) VALUES ('Other Shop', 'other-shop') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        for table in ["products", "inventory"] {
+            let column = if table == "products" { "id" } else { "product_id" };
+            sqlx::query(&format!("UPDATE {} SET organization_id = ?1 WHERE {} = ?2", table, column))
+                .bind(other)
+                .bind(theirs)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let low = get_low_stock_items_internal(&pool, 1, 50, None).await.unwrap();
+        assert_eq!(low.iter().map(|item| item.product_id).collect::<Vec<_>>(), vec![ours]);
+        let low = get_low_stock_items_internal(&pool, other, 50, None).await.unwrap();
+        assert_eq!(low.iter().map(|item| item.product_id).collect::<Vec<_>>(), vec![theirs]);
+
+        let clerk = insert_test_user(&pool, "clerk").await;
+        move_stock(&pool, theirs, 5, "receipt", clerk).await;
+        let filter = MovementFilter::default();
+        let movements = get_inventory_movements_internal(&pool, DEFAULT_ORGANIZATION_ID, &filter, None, None)
+            .await
+            .unwrap();
+        assert!(movements.is_empty());
+        let movements = get_inventory_movements_internal(&pool, other, &filter, None, None)
+            .await
+            .unwrap();
+        assert_eq!(movements.len(), 1);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::barcode::{self, Symbology};
+use crate::commands::backup::app_data_dir;
+use crate::labels::{self, LabelTemplate};
+use crate::plans;
+use sqlx::SqlitePool;
+use tauri::{command, AppHandle, State};
+
+/// Directory inside the app data directory that label PDFs are written to
+const LABELS_DIR: &str = "labels";
+
+/// PNG of a barcode; without a symbology, valid EAN-13 codes use EAN-13 and others Code128
+#[command]
+pub async fn generate_barcode_image(
+    code: String,
+    symbology: Option<Symbology>,
+    module_width: Option<u32>,
+    height: Option<u32>,
+) -> Result<Vec<u8>, String> {
+    let code = code.trim();
+    let symbology = symbology.unwrap_or_else(|| Symbology::detect(code));
+    Ok(barcode::render_png(
+        code,
+        symbology,
+        module_width.unwrap_or(2),
+        height.unwrap_or(80),
+    )?)
+}
+
+/// Give a product without a barcode the next one from the barcode sequence
+#[command]
+pub async fn assign_product_barcode(
+    pool: State<'_, SqlitePool>,
+    product_id: i64,
+    session_token: String,
+) -> Result<String, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    let mut tx = pool.inner().begin().await.map_err(|e| e.to_string())?;
+    let barcode = barcode::assign_barcode(&mut tx, product_id).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(barcode)
+}
+
+/// Shelf labels for the products as a PDF; returns the file path
+#[command]
+pub async fn generate_labels_pdf(
+    app: AppHandle,
+    pool: State<'_, SqlitePool>,
+    product_ids: Vec<i64>,
+    template: LabelTemplate,
+) -> Result<String, String> {
+    let dir = app_data_dir(&app)?.join(LABELS_DIR);
+    let path = labels::generate_labels_pdf(pool.inner(), &product_ids, template, &dir).await?;
+    Ok(path.to_string_lossy().to_string())
+}
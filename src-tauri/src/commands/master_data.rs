@@ -1,4 +1,8 @@
 // src-tauri/src/commands/master_data.rs - Master Data Management Commands
+use crate::error::{AppError, AppResult};
+use crate::plans;
+use crate::validation::validate_non_negative;
+use sqlx::sqlite::SqliteConnection;
 use sqlx::{FromRow, SqlitePool};
 use tauri::State;
 
@@ -7,6 +11,10 @@ pub struct Category {
     pub id: i64,
     pub name: String,
     pub description: Option<String>,
+    /// Overrides the store's minimum margin for products in this category
+    pub min_margin_percent: Option<f64>,
+    /// Category this one sits under; top-level categories have none
+    pub parent_id: Option<i64>,
     pub is_active: bool,
     pub created_at: String,
     pub updated_at: String,
@@ -37,6 +45,10 @@ pub struct Unit {
 pub struct CategoryRequest {
     pub name: String,
     pub description: Option<String>,
+    #[serde(default)]
+    pub min_margin_percent: Option<f64>,
+    #[serde(default)]
+    pub parent_id: Option<i64>,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -78,63 +90,164 @@ pub async fn get_all_categories(pool: State<'_, SqlitePool>) -> Result<Vec<Categ
     Ok(categories)
 }
 
-#[tauri::command]
-pub async fn create_category(
-    pool: State<'_, SqlitePool>,
-    request: CategoryRequest,
-) -> Result<Category, String> {
-    let result = sqlx::query(
-        "INSERT INTO categories (name, description) VALUES (?, ?)"
+/// Fail unless `parent_id` exists and putting category `id` under it keeps the tree
+/// free of cycles. `id` is `None` for a category still being created.
+pub(crate) async fn check_category_parent(
+    conn: &mut SqliteConnection,
+    id: Option<i64>,
+    parent_id: i64,
+) -> AppResult<()> {
+    let exists: Option<i64> = sqlx::query_scalar("SELECT id FROM categories WHERE id = ?1")
+        .bind(parent_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if exists.is_none() {
+        return Err(AppError::not_found("Parent category"));
+    }
+    let Some(id) = id else {
+        return Ok(());
+    };
+    let is_descendant: bool = sqlx::query_scalar(
+        "WITH RECURSIVE ancestors(id) AS (
+            SELECT ?1
+            UNION
+            SELECT c.parent_id FROM categories c JOIN ancestors a ON c.id = a.id
+            WHERE c.parent_id IS NOT NULL
+         )
+         SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = ?2)",
     )
-    .bind(&request.name)
-    .bind(&request.description)
-    .execute(pool.inner())
-    .await
-    .map_err(|e| format!("Failed to create category: {}", e))?;
+    .bind(parent_id)
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if is_descendant {
+        return Err(AppError::validation(
+            "parent_id",
+            "A category cannot be placed under itself or one of its subcategories",
+        ));
+    }
+    Ok(())
+}
+
+/// The category row and name a product is saved with. An id wins over the legacy name;
+/// a name without a row gets one, so every categorized product maps onto the tree.
+pub(crate) async fn resolve_product_category(
+    conn: &mut SqliteConnection,
+    category_id: Option<i64>,
+    category: Option<&str>,
+) -> AppResult<(Option<i64>, Option<String>)> {
+    if let Some(category_id) = category_id {
+        let name: Option<String> = sqlx::query_scalar("SELECT name FROM categories WHERE id = ?1")
+            .bind(category_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        let name = name.ok_or_else(|| AppError::not_found("Category"))?;
+        return Ok((Some(category_id), Some(name)));
+    }
+    let Some(name) = category.map(str::trim).filter(|name| !name.is_empty()) else {
+        return Ok((None, None));
+    };
+    sqlx::query("INSERT OR IGNORE INTO categories (name) VALUES (?1)")
+        .bind(name)
+        .execute(&mut *conn)
+        .await?;
+    let id: i64 = sqlx::query_scalar("SELECT id FROM categories WHERE name = ?1")
+        .bind(name)
+        .fetch_one(&mut *conn)
+        .await?;
+    Ok((Some(id), Some(name.to_string())))
+}
 
+pub(crate) async fn create_category_internal(pool: &SqlitePool, request: &CategoryRequest) -> AppResult<Category> {
+    if let Some(min_margin) = request.min_margin_percent {
+        validate_non_negative(min_margin, "min_margin_percent")?;
+    }
+
+    let mut tx = pool.begin().await?;
+    if let Some(parent_id) = request.parent_id {
+        check_category_parent(&mut tx, None, parent_id).await?;
+    }
     let category = sqlx::query_as::<_, Category>(
-        "SELECT * FROM categories WHERE id = ?"
+        "INSERT INTO categories (name, description, min_margin_percent, parent_id) VALUES (?, ?, ?, ?)
+         RETURNING *"
     )
-    .bind(result.last_insert_rowid())
-    .fetch_one(pool.inner())
-    .await
-    .map_err(|e| format!("Failed to fetch created category: {}", e))?;
+    .bind(&request.name)
+    .bind(&request.description)
+    .bind(request.min_margin_percent)
+    .bind(request.parent_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
 
     Ok(category)
 }
 
 #[tauri::command]
-pub async fn update_category(
+pub async fn create_category(
     pool: State<'_, SqlitePool>,
-    id: i64,
     request: CategoryRequest,
+    session_token: String,
 ) -> Result<Category, String> {
-    sqlx::query(
-        "UPDATE categories SET name = ?, description = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?"
-    )
-    .bind(&request.name)
-    .bind(&request.description)
-    .bind(id)
-    .execute(pool.inner())
-    .await
-    .map_err(|e| format!("Failed to update category: {}", e))?;
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    Ok(create_category_internal(pool.inner(), &request).await?)
+}
 
+pub(crate) async fn update_category_internal(
+    pool: &SqlitePool,
+    id: i64,
+    request: &CategoryRequest,
+) -> AppResult<Category> {
+    if let Some(min_margin) = request.min_margin_percent {
+        validate_non_negative(min_margin, "min_margin_percent")?;
+    }
+
+    let mut tx = pool.begin().await?;
+    if let Some(parent_id) = request.parent_id {
+        check_category_parent(&mut tx, Some(id), parent_id).await?;
+    }
     let category = sqlx::query_as::<_, Category>(
-        "SELECT * FROM categories WHERE id = ?"
+        "UPDATE categories SET name = ?, description = ?, min_margin_percent = ?, parent_id = ?,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?
+         RETURNING *"
     )
+    .bind(&request.name)
+    .bind(&request.description)
+    .bind(request.min_margin_percent)
+    .bind(request.parent_id)
     .bind(id)
-    .fetch_one(pool.inner())
-    .await
-    .map_err(|e| format!("Failed to fetch updated category: {}", e))?;
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::not_found("Category"))?;
+    // Keep the legacy name on products in step with a rename
+    sqlx::query("UPDATE products SET category = ?1 WHERE category_id = ?2")
+        .bind(&category.name)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
 
     Ok(category)
 }
 
+#[tauri::command]
+pub async fn update_category(
+    pool: State<'_, SqlitePool>,
+    id: i64,
+    request: CategoryRequest,
+    session_token: String,
+) -> Result<Category, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    Ok(update_category_internal(pool.inner(), id, &request).await?)
+}
+
 #[tauri::command]
 pub async fn delete_category(
     pool: State<'_, SqlitePool>,
     id: i64,
+    session_token: String,
 ) -> Result<(), String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     sqlx::query("UPDATE categories SET is_active = 0 WHERE id = ?")
         .bind(id)
         .execute(pool.inner())
@@ -174,7 +287,9 @@ pub async fn get_all_brands(pool: State<'_, SqlitePool>) -> Result<Vec<Brand>, S
 pub async fn create_brand(
     pool: State<'_, SqlitePool>,
     request: BrandRequest,
+    session_token: String,
 ) -> Result<Brand, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let result = sqlx::query(
         "INSERT INTO brands (name, description) VALUES (?, ?)"
     )
@@ -200,7 +315,9 @@ pub async fn update_brand(
     pool: State<'_, SqlitePool>,
     id: i64,
     request: BrandRequest,
+    session_token: String,
 ) -> Result<Brand, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     sqlx::query(
         "UPDATE brands SET name = ?, description = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?"
     )
@@ -226,7 +343,9 @@ pub async fn update_brand(
 pub async fn delete_brand(
     pool: State<'_, SqlitePool>,
     id: i64,
+    session_token: String,
 ) -> Result<(), String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     sqlx::query("UPDATE brands SET is_active = 0 WHERE id = ?")
         .bind(id)
         .execute(pool.inner())
@@ -266,7 +385,9 @@ pub async fn get_all_units(pool: State<'_, SqlitePool>) -> Result<Vec<Unit>, Str
 pub async fn create_unit(
     pool: State<'_, SqlitePool>,
     request: UnitRequest,
+    session_token: String,
 ) -> Result<Unit, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let result = sqlx::query(
         "INSERT INTO units (name, abbreviation, description) VALUES (?, ?, ?)"
     )
@@ -293,7 +414,9 @@ pub async fn update_unit(
     pool: State<'_, SqlitePool>,
     id: i64,
     request: UnitRequest,
+    session_token: String,
 ) -> Result<Unit, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     sqlx::query(
         "UPDATE units SET name = ?, abbreviation = ?, description = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?"
     )
@@ -320,7 +443,9 @@ pub async fn update_unit(
 pub async fn delete_unit(
     pool: State<'_, SqlitePool>,
     id: i64,
+    session_token: String,
 ) -> Result<(), String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     sqlx::query("UPDATE units SET is_active = 0 WHERE id = ?")
         .bind(id)
         .execute(pool.inner())
@@ -329,3 +454,43 @@ pub async fn delete_unit(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_pool;
+
+    fn request(name: &str, parent_id: Option<i64>) -> CategoryRequest {
+        CategoryRequest {
+            name: name.to_string(),
+            description: None,
+            min_margin_percent: None,
+            parent_id,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_category_cannot_move_under_itself_or_a_descendant() {
+        let pool = test_pool().await;
+        let food = create_category_internal(&pool, &request("Food", None)).await.unwrap();
+        let fruit = create_category_internal(&pool, &request("Fruit", Some(food.id))).await.unwrap();
+        let apples = create_category_internal(&pool, &request("Apples", Some(fruit.id))).await.unwrap();
+        assert_eq!(apples.parent_id, Some(fruit.id));
+
+        for parent in [food.id, fruit.id, apples.id] {
+            let err = update_category_internal(&pool, food.id, &request("Food", Some(parent)))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), "VALIDATION_ERROR");
+        }
+        let err = create_category_internal(&pool, &request("Pears", Some(999))).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+
+        // Moving a subtree elsewhere is fine
+        let produce = create_category_internal(&pool, &request("Produce", None)).await.unwrap();
+        let fruit = update_category_internal(&pool, fruit.id, &request("Fruit", Some(produce.id)))
+            .await
+            .unwrap();
+        assert_eq!(fruit.parent_id, Some(produce.id));
+    }
+}
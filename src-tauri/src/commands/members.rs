@@ -0,0 +1,519 @@
+// src-tauri/src/commands/members.rs - Organization members and invitations
+use crate::audit;
+use crate::error::{AppError, AppResult};
+use crate::session::SESSION_MANAGER;
+use crate::tenancy::{self, Membership};
+use chrono::{Duration, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{FromRow, SqlitePool};
+use tauri::{command, State};
+
+/// Permission that lets a member below Admin manage memberships
+pub const MANAGE_MEMBERS: &str = "members.manage";
+
+/// How long an invitation code can be accepted
+const INVITATION_TTL_DAYS: i64 = 7;
+
+const INVITE_CODE_LENGTH: usize = 10;
+// No 0/O or 1/I so codes can be read out loud or copied from paper
+const INVITE_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// A member of the organization with their user details
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OrganizationMember {
+    pub user_id: i64,
+    pub username: String,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub role: String,
+    /// JSON array of permissions granted on top of the role
+    pub permissions: Option<String>,
+    pub is_active: bool,
+    pub joined_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OrganizationInvitation {
+    pub id: i64,
+    pub organization_id: i64,
+    pub email: String,
+    pub role: String,
+    pub permissions: Option<String>,
+    pub invite_code: String,
+    /// `pending`, `accepted` or `revoked`
+    pub status: String,
+    pub invited_by: Option<i64>,
+    pub expires_at: String,
+    pub created_at: String,
+}
+
+/// Roles in rank order; a member can only grant or manage roles below their own,
+/// except owners, who manage everyone
+fn role_rank(role: &str) -> AppResult<u8> {
+    match role {
+        "User" => Ok(0),
+        "Manager" => Ok(1),
+        "Admin" => Ok(2),
+        "Owner" => Ok(3),
+        other => Err(AppError::validation(
+            "role",
+            &format!("Unknown role '{}', expected Owner, Admin, Manager or User", other),
+        )),
+    }
+}
+
+/// Fail unless `caller` may give a member `role`
+fn check_can_grant(caller: &Membership, role: &str) -> AppResult<()> {
+    let rank = role_rank(role)?;
+    if caller.role == "Owner" || rank < role_rank(&caller.role)? {
+        Ok(())
+    } else {
+        Err(AppError::PermissionDenied {
+            message: format!("A {} cannot grant the {} role", caller.role, role),
+        })
+    }
+}
+
+/// Normalize a permissions list to the JSON array stored on the membership
+fn permissions_json(permissions: Option<Vec<String>>) -> AppResult<Option<String>> {
+    permissions
+        .map(|permissions| {
+            serde_json::to_string(&permissions)
+                .map_err(|e| AppError::validation("permissions", &e.to_string()))
+        })
+        .transpose()
+}
+
+fn generate_invite_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..INVITE_CODE_LENGTH)
+        .map(|_| INVITE_CODE_ALPHABET[rng.gen_range(0..INVITE_CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+/// The membership of the member being changed, which the caller must outrank
+async fn managed_member(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    caller: &Membership,
+    user_id: i64,
+) -> AppResult<Membership> {
+    let member = tenancy::membership(conn, organization_id, user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Member"))?;
+    if caller.role != "Owner" && role_rank(&member.role)? >= role_rank(&caller.role)? {
+        return Err(AppError::PermissionDenied {
+            message: format!("A {} cannot change a {}", caller.role, member.role),
+        });
+    }
+    Ok(member)
+}
+
+/// Fail if the member is the organization's only active owner
+async fn check_not_last_owner(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    member: &Membership,
+) -> AppResult<()> {
+    if member.role != "Owner" {
+        return Ok(());
+    }
+    let owners: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM organization_users
+         WHERE organization_id = ?1 AND role = 'Owner' AND is_active = 1",
+    )
+    .bind(organization_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if owners <= 1 {
+        return Err(AppError::Conflict {
+            message: "An organization must keep at least one owner".to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub(crate) async fn invite_user_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    caller_id: i64,
+    email: &str,
+    role: &str,
+    permissions: Option<Vec<String>>,
+) -> AppResult<OrganizationInvitation> {
+    let email = email.trim().to_lowercase();
+    crate::validation::validate_email(&email)?;
+    let permissions = permissions_json(permissions)?;
+
+    let mut tx = pool.begin().await?;
+    let caller = tenancy::require_permission(&mut tx, organization_id, caller_id, MANAGE_MEMBERS).await?;
+    check_can_grant(&caller, role)?;
+
+    let already_member: Option<i64> = sqlx::query_scalar(
+        "SELECT ou.id FROM organization_users ou JOIN users u ON u.id = ou.user_id
+         WHERE ou.organization_id = ?1 AND ou.is_active = 1 AND LOWER(u.email) = ?2",
+    )
+    .bind(organization_id)
+    .bind(&email)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if already_member.is_some() {
+        return Err(AppError::Conflict {
+            message: format!("{} is already a member", email),
+        });
+    }
+
+    let expires_at = (Utc::now() + Duration::days(INVITATION_TTL_DAYS))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    let invitation = sqlx::query_as::<_, OrganizationInvitation>(
+        "INSERT INTO organization_invitations
+            (organization_id, email, role, permissions, invite_code, invited_by, expires_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         RETURNING id, organization_id, email, role, permissions, invite_code, status, invited_by,
+                   expires_at, COALESCE(created_at, '') as created_at",
+    )
+    .bind(organization_id)
+    .bind(&email)
+    .bind(role)
+    .bind(&permissions)
+    .bind(generate_invite_code())
+    .bind(caller_id)
+    .bind(&expires_at)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(invitation)
+}
+
+/// Invite someone by email to the session's organization with a role. Returns the code
+/// they accept the invitation with.
+#[command]
+pub async fn invite_user_to_organization(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    email: String,
+    role: String,
+    permissions: Option<Vec<String>>,
+) -> Result<OrganizationInvitation, AppError> {
+    let session = SESSION_MANAGER.require_password_session(&session_token)?;
+    invite_user_internal(
+        pool.inner(),
+        session.organization_id,
+        session.user_id,
+        &email,
+        &role,
+        permissions,
+    )
+    .await
+}
+
+pub(crate) async fn accept_invitation_internal(
+    pool: &SqlitePool,
+    user_id: i64,
+    code: &str,
+) -> AppResult<i64> {
+    let mut tx = pool.begin().await?;
+    let invitation: Option<(i64, i64, String, String, Option<String>, bool)> = sqlx::query_as(
+        "SELECT id, organization_id, email, role, permissions, expires_at < ?2
+         FROM organization_invitations
+         WHERE invite_code = ?1 AND status = 'pending'",
+    )
+    .bind(code.trim().to_uppercase())
+    .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+    .fetch_optional(&mut *tx)
+    .await?;
+    let (invitation_id, organization_id, email, role, permissions, expired) =
+        invitation.ok_or_else(|| AppError::not_found("Invitation"))?;
+    if expired {
+        return Err(AppError::validation("code", "This invitation has expired"));
+    }
+
+    let user_email: String = sqlx::query_scalar("SELECT email FROM users WHERE id = ?1")
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("User"))?;
+    if !user_email.trim().eq_ignore_ascii_case(&email) {
+        return Err(AppError::PermissionDenied {
+            message: "This invitation was sent to another email address".to_string(),
+        });
+    }
+
+    sqlx::query(
+        "INSERT INTO organization_users (organization_id, user_id, role, permissions)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(organization_id, user_id) DO UPDATE SET
+            role = excluded.role, permissions = excluded.permissions, is_active = 1",
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .bind(&role)
+    .bind(&permissions)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "UPDATE organization_invitations
+         SET status = 'accepted', accepted_by = ?1, accepted_at = CURRENT_TIMESTAMP
+         WHERE id = ?2",
+    )
+    .bind(user_id)
+    .bind(invitation_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(organization_id)
+}
+
+/// Join the organization an invitation code was sent for. Returns the organization id.
+#[command]
+pub async fn accept_invitation(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    code: String,
+) -> Result<i64, AppError> {
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    accept_invitation_internal(pool.inner(), session.user_id, &code).await
+}
+
+pub(crate) async fn update_member_role_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    caller_id: i64,
+    user_id: i64,
+    role: &str,
+    permissions: Option<Vec<String>>,
+) -> AppResult<()> {
+    let permissions = permissions_json(permissions)?;
+    let mut tx = pool.begin().await?;
+    let caller = tenancy::require_permission(&mut tx, organization_id, caller_id, MANAGE_MEMBERS).await?;
+    check_can_grant(&caller, role)?;
+    let member = managed_member(&mut tx, organization_id, &caller, user_id).await?;
+    if role != "Owner" {
+        check_not_last_owner(&mut tx, organization_id, &member).await?;
+    }
+
+    audit::log_audit(
+        &mut tx,
+        organization_id,
+        Some(caller_id),
+        "change_member_role",
+        "organization_user",
+        Some(user_id),
+        Some(&serde_json::json!({ "organization_id": organization_id, "role": member.role })),
+        Some(&serde_json::json!({ "organization_id": organization_id, "role": role })),
+    )
+    .await?;
+    sqlx::query(
+        "UPDATE organization_users SET role = ?1, permissions = COALESCE(?2, permissions)
+         WHERE organization_id = ?3 AND user_id = ?4",
+    )
+    .bind(role)
+    .bind(&permissions)
+    .bind(organization_id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Change a member's role, and their extra permissions when given
+#[command]
+pub async fn update_member_role(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    user_id: i64,
+    role: String,
+    permissions: Option<Vec<String>>,
+) -> Result<(), AppError> {
+    let session = SESSION_MANAGER.require_password_session(&session_token)?;
+    update_member_role_internal(
+        pool.inner(),
+        session.organization_id,
+        session.user_id,
+        user_id,
+        &role,
+        permissions,
+    )
+    .await
+}
+
+pub(crate) async fn remove_member_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    caller_id: i64,
+    user_id: i64,
+) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+    let caller = tenancy::require_permission(&mut tx, organization_id, caller_id, MANAGE_MEMBERS).await?;
+    let member = managed_member(&mut tx, organization_id, &caller, user_id).await?;
+    check_not_last_owner(&mut tx, organization_id, &member).await?;
+
+    audit::log_audit(
+        &mut tx,
+        organization_id,
+        Some(caller_id),
+        "remove_member",
+        "organization_user",
+        Some(user_id),
+        Some(&serde_json::json!({ "organization_id": organization_id, "role": member.role, "is_active": true })),
+        Some(&serde_json::json!({ "organization_id": organization_id, "is_active": false })),
+    )
+    .await?;
+    sqlx::query("UPDATE organization_users SET is_active = 0 WHERE organization_id = ?1 AND user_id = ?2")
+        .bind(organization_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Take a member out of the session's organization
+#[command]
+pub async fn remove_member(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    user_id: i64,
+) -> Result<(), AppError> {
+    let session = SESSION_MANAGER.require_password_session(&session_token)?;
+    remove_member_internal(pool.inner(), session.organization_id, session.user_id, user_id).await
+}
+
+#[command]
+pub async fn get_organization_members(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    include_inactive: Option<bool>,
+) -> Result<Vec<OrganizationMember>, AppError> {
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    let members = sqlx::query_as::<_, OrganizationMember>(
+        "SELECT u.id as user_id, u.username, u.email, u.first_name, u.last_name,
+                ou.role, ou.permissions, ou.is_active, ou.joined_at
+         FROM organization_users ou
+         JOIN users u ON u.id = ou.user_id
+         WHERE ou.organization_id = ?1 AND (ou.is_active = 1 OR ?2)
+         ORDER BY CASE ou.role WHEN 'Owner' THEN 0 WHEN 'Admin' THEN 1 WHEN 'Manager' THEN 2 ELSE 3 END,
+                  u.last_name, u.first_name",
+    )
+    .bind(session.organization_id)
+    .bind(include_inactive.unwrap_or(false))
+    .fetch_all(pool.inner())
+    .await?;
+    Ok(members)
+}
+
+#[command]
+pub async fn get_organization_invitations(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    status: Option<String>,
+) -> Result<Vec<OrganizationInvitation>, AppError> {
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    let mut conn = pool.inner().acquire().await?;
+    tenancy::require_permission(&mut conn, session.organization_id, session.user_id, MANAGE_MEMBERS)
+        .await?;
+    let invitations = sqlx::query_as::<_, OrganizationInvitation>(
+        "SELECT id, organization_id, email, role, permissions, invite_code, status, invited_by,
+                expires_at, COALESCE(created_at, '') as created_at
+         FROM organization_invitations
+         WHERE organization_id = ?1 AND (?2 IS NULL OR status = ?2)
+         ORDER BY created_at DESC, id DESC",
+    )
+    .bind(session.organization_id)
+    .bind(status)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(invitations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_user, test_pool};
+
+    async fn add_member(pool: &SqlitePool, username: &str, role: &str, permissions: Option<&str>) -> i64 {
+        let user_id = insert_test_user(pool, username).await;
+        sqlx::query(
+            "INSERT INTO organization_users (organization_id, user_id, role, permissions)
+             VALUES (1, ?1, ?2, ?3)",
+        )
+        .bind(user_id)
+        .bind(role)
+        .bind(permissions)
+        .execute(pool)
+        .await
+        .unwrap();
+        user_id
+    }
+
+    #[tokio::test]
+    async fn test_last_owner_cannot_be_removed_or_demoted() {
+        let pool = test_pool().await;
+        let owner = add_member(&pool, "owner", "Owner", None).await;
+
+        let err = remove_member_internal(&pool, 1, owner, owner).await.unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+        let err = update_member_role_internal(&pool, 1, owner, owner, "Admin", None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+
+        // With a second owner either can step down
+        let partner = add_member(&pool, "partner", "Admin", None).await;
+        update_member_role_internal(&pool, 1, owner, partner, "Owner", None).await.unwrap();
+        update_member_role_internal(&pool, 1, partner, owner, "Admin", None).await.unwrap();
+        let err = remove_member_internal(&pool, 1, partner, partner).await.unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+    }
+
+    #[tokio::test]
+    async fn test_members_cannot_grant_or_manage_roles_at_or_above_their_own() {
+        let pool = test_pool().await;
+        let owner = add_member(&pool, "owner", "Owner", None).await;
+        let admin = add_member(&pool, "admin", "Admin", None).await;
+        let lead = add_member(&pool, "lead", "Manager", Some(r#"["members.manage"]"#)).await;
+        let clerk = add_member(&pool, "clerk", "User", None).await;
+
+        for (caller, target, role) in [
+            (admin, clerk, "Owner"),
+            (admin, clerk, "Admin"),
+            (admin, owner, "User"),
+            (lead, clerk, "Manager"),
+        ] {
+            let err = update_member_role_internal(&pool, 1, caller, target, role, None)
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), "PERMISSION_DENIED", "{} -> {}", target, role);
+        }
+        let err = invite_user_internal(&pool, 1, clerk, "new@example.com", "User", None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "PERMISSION_DENIED");
+
+        // The granted permission lets a manager invite users, who join on accepting
+        let invitation = invite_user_internal(&pool, 1, lead, "New@Example.com", "User", None)
+            .await
+            .unwrap();
+        let newcomer = insert_test_user(&pool, "new").await;
+        sqlx::query("UPDATE users SET email = 'new@example.com' WHERE id = ?1")
+            .bind(newcomer)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let err = accept_invitation_internal(&pool, clerk, &invitation.invite_code)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "PERMISSION_DENIED");
+        accept_invitation_internal(&pool, newcomer, &invitation.invite_code.to_lowercase())
+            .await
+            .unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+        let joined = tenancy::membership(&mut conn, 1, newcomer).await.unwrap().unwrap();
+        assert_eq!(joined.role, "User");
+    }
+}
@@ -1,27 +1,46 @@
 pub mod appointments;
 pub mod auth;
+pub mod backup;
+pub mod campaigns;
 pub mod cash_drawer;
+pub mod customer_display;
 pub mod customers;
 pub mod dashboard;
+pub mod discounts;
 pub mod employees;
+pub mod exchange_rates;
 pub mod expenses;
 pub mod integrations;
 pub mod inventory;
+pub mod labels;
 pub mod master_data;
+pub mod members;
 pub mod notifications;
 pub mod organization;
+pub mod parked_sales;
+pub mod price_overrides;
 pub mod products;
 pub mod promotions;
 pub mod purchase_orders;
+pub mod receipt_footers;
+pub mod receipt_templates;
 pub mod receipts;
+pub mod reorder;
 pub mod reports;
 pub mod returns;
 pub mod sales;
+pub mod search;
+pub mod settings;
 pub mod shifts;
 pub mod stock;
 pub mod store;
+pub mod supplier_credits;
 pub mod suppliers;
 pub mod sync;
+pub mod tax_rules;
 pub mod time_tracking;
+pub mod transfers;
 pub mod users;
 pub mod variants;
+pub mod writeoffs;
+pub mod z_reports;
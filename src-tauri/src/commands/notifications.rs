@@ -1,7 +1,13 @@
 // src-tauri/src/commands/notifications.rs
+use crate::db_utils::Pagination;
+use crate::lots::DEFAULT_EXPIRY_WINDOW_DAYS;
+use crate::models::{Listing, Page, PageCursor};
 use serde::{Deserialize, Serialize};
-use sqlx::{Row, SqlitePool};
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
+use std::sync::Arc;
+use std::time::Duration;
 use tauri::{command, State};
+use tokio::sync::Notify;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Notification {
@@ -11,6 +17,7 @@ pub struct Notification {
     pub message: String,
     pub severity: String, // info, warning, error, success
     pub is_read: bool,
+    pub is_archived: bool,
     pub user_id: Option<i64>,
     pub reference_id: Option<i64>,
     pub reference_type: Option<String>,
@@ -25,63 +32,126 @@ pub struct NotificationStats {
     pub system: i32,
     pub invoice: i32,
     pub debt: i32,
+    pub error: i32,
+    pub warning: i32,
+    pub info: i32,
+    pub success: i32,
+    /// Unread error-severity notifications (drives the red badge)
+    pub unread_error: i32,
 }
 
-#[command]
-pub async fn get_notifications(
-    pool: State<'_, SqlitePool>,
-    user_id: Option<i64>,
-    is_read: Option<bool>,
-    notification_type: Option<String>,
-    limit: Option<i32>,
-) -> Result<Vec<Notification>, String> {
-    let pool_ref = pool.inner();
-
-    let limit = limit.unwrap_or(50);
+/// Number of notifications created by each check in `run_all_notification_checks`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotificationCheckCounts {
+    pub low_stock: i32,
+    pub invoice: i32,
+    pub debt: i32,
+    pub promotion: i32,
+    pub appointment: i32,
+    pub lot_expiry: i32,
+    pub total: i32,
+}
 
-    let mut query = String::from(
-        "SELECT id, notification_type, title, message, severity, is_read, 
-                user_id, reference_id, reference_type, created_at
-         FROM notifications
-         WHERE 1=1",
-    );
+/// How far ahead to warn about promotions that are about to end
+const DEFAULT_PROMOTION_EXPIRY_DAYS: i64 = 7;
 
-    let mut params: Vec<String> = Vec::new();
-    let mut param_count = 0;
+/// Default interval between background notification checks
+pub const DEFAULT_NOTIFICATION_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
 
-    if let Some(uid) = user_id {
-        param_count += 1;
-        query.push_str(&format!(
-            " AND (user_id = ?{} OR user_id IS NULL)",
-            param_count
-        ));
-        params.push(uid.to_string());
-    }
+/// Handle to the background notification runner, kept in managed state
+pub struct NotificationScheduler {
+    wake: Arc<Notify>,
+}
 
-    if let Some(read) = is_read {
-        param_count += 1;
-        query.push_str(&format!(" AND is_read = ?{}", param_count));
-        params.push(if read { "1" } else { "0" }.to_string());
+impl NotificationScheduler {
+    /// Ask the runner to check now instead of waiting for the next interval
+    pub fn trigger(&self) {
+        self.wake.notify_one();
     }
+}
 
-    if let Some(ref ntype) = notification_type {
-        if !ntype.is_empty() && ntype != "all" {
-            param_count += 1;
-            query.push_str(&format!(" AND notification_type = ?{}", param_count));
-            params.push(ntype.clone());
+/// Spawn a task that runs every notification check on startup and then on each interval.
+/// Failures are logged and retried on the next run; they never take the app down.
+pub fn spawn_notification_runner(pool: SqlitePool, interval: Duration) -> NotificationScheduler {
+    let wake = Arc::new(Notify::new());
+    let runner_wake = wake.clone();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let result =
+                run_all_notification_checks_internal(&pool, DEFAULT_PROMOTION_EXPIRY_DAYS).await;
+            match result {
+                Ok(counts) if counts.total > 0 => {
+                    log::info!(
+                        "Notification checks created {} notification(s): {:?}",
+                        counts.total,
+                        counts
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("Background notification checks failed: {}", e),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = runner_wake.notified() => {}
+            }
         }
-    }
+    });
 
-    query.push_str(" ORDER BY created_at DESC");
-    query.push_str(&format!(" LIMIT ?{}", param_count + 1));
-    params.push(limit.to_string());
+    NotificationScheduler { wake }
+}
 
-    let mut sql_query = sqlx::query(&query);
-    for param in &params {
-        sql_query = sql_query.bind(param);
+/// Filters of the notification list, shared by the page query and its count
+#[derive(Debug, Default)]
+pub(crate) struct NotificationFilter<'a> {
+    pub user_id: Option<i64>,
+    pub is_read: Option<bool>,
+    pub notification_type: Option<&'a str>,
+    pub severity: Option<&'a str>,
+    pub include_archived: bool,
+}
+
+impl<'a> NotificationFilter<'a> {
+    fn push_conditions(&self, query: &mut QueryBuilder<'a, Sqlite>) {
+        if !self.include_archived {
+            query.push(" AND COALESCE(is_archived, 0) = 0");
+        }
+        if let Some(uid) = self.user_id {
+            query.push(" AND (user_id = ").push_bind(uid).push(" OR user_id IS NULL)");
+        }
+        if let Some(read) = self.is_read {
+            query.push(" AND is_read = ").push_bind(read);
+        }
+        if let Some(ntype) = self.notification_type.filter(|ntype| !ntype.is_empty() && *ntype != "all") {
+            query.push(" AND notification_type = ").push_bind(ntype);
+        }
+        if let Some(sev) = self.severity.filter(|sev| !sev.is_empty() && *sev != "all") {
+            query.push(" AND severity = ").push_bind(sev);
+        }
     }
+}
+
+/// One page of notifications, newest first. The total is only counted when `with_total` is set.
+pub(crate) async fn get_notifications_internal(
+    pool_ref: &SqlitePool,
+    filter: &NotificationFilter<'_>,
+    pagination: &Pagination,
+    with_total: bool,
+) -> Result<Page<Notification>, String> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, notification_type, title, message, severity, is_read, is_archived,
+                user_id, reference_id, reference_type, created_at
+         FROM notifications
+         WHERE 1=1",
+    );
+    filter.push_conditions(&mut query);
+    pagination.push_after(&mut query, "created_at", "id");
+    query.push(" ORDER BY created_at DESC, id DESC");
+    pagination.push_limit(&mut query);
 
-    let rows = sql_query
+    let rows = query
+        .build()
         .fetch_all(pool_ref)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
@@ -97,6 +167,7 @@ pub async fn get_notifications(
             message: row.try_get("message").map_err(|e| e.to_string())?,
             severity: row.try_get("severity").map_err(|e| e.to_string())?,
             is_read: row.try_get("is_read").map_err(|e| e.to_string())?,
+            is_archived: row.try_get("is_archived").unwrap_or(false),
             user_id: row.try_get("user_id").ok(),
             reference_id: row.try_get("reference_id").ok(),
             reference_type: row.try_get("reference_type").ok(),
@@ -104,7 +175,51 @@ pub async fn get_notifications(
         });
     }
 
-    Ok(notifications)
+    let total_count = if with_total {
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM notifications WHERE 1=1");
+        filter.push_conditions(&mut count);
+        count
+            .build_query_scalar::<i64>()
+            .fetch_one(pool_ref)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?
+    } else {
+        0
+    };
+    Ok(pagination.into_page(notifications, total_count, |notification| PageCursor {
+        created_at: notification.created_at.clone(),
+        id: notification.id,
+    }))
+}
+
+/// Notifications newest first, 50 at a time unless `limit` says otherwise. Pass the previous
+/// page's `next_cursor` as `after_id`/`after_created_at` to page by keyset; with `envelope`
+/// the reply is a `Page` carrying the total count and that cursor.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_notifications(
+    pool: State<'_, SqlitePool>,
+    user_id: Option<i64>,
+    is_read: Option<bool>,
+    notification_type: Option<String>,
+    severity: Option<String>,
+    include_archived: Option<bool>,
+    limit: Option<i32>,
+    offset: Option<i32>,
+    after_id: Option<i64>,
+    after_created_at: Option<String>,
+    envelope: Option<bool>,
+) -> Result<Listing<Notification>, String> {
+    let filter = NotificationFilter {
+        user_id,
+        is_read,
+        notification_type: notification_type.as_deref(),
+        severity: severity.as_deref(),
+        include_archived: include_archived.unwrap_or(false),
+    };
+    let pagination = Pagination::from_params(i64::from(limit.unwrap_or(50)), offset, after_id, after_created_at)?;
+    let page = get_notifications_internal(pool.inner(), &filter, &pagination, envelope.unwrap_or(false)).await?;
+    Ok(Listing::new(page, envelope))
 }
 
 #[command]
@@ -121,9 +236,14 @@ pub async fn get_notification_stats(
             SUM(CASE WHEN notification_type = 'low_stock' THEN 1 ELSE 0 END) as low_stock,
             SUM(CASE WHEN notification_type = 'system' THEN 1 ELSE 0 END) as system,
             SUM(CASE WHEN notification_type = 'invoice' THEN 1 ELSE 0 END) as invoice,
-            SUM(CASE WHEN notification_type = 'debt' THEN 1 ELSE 0 END) as debt
+            SUM(CASE WHEN notification_type = 'debt' THEN 1 ELSE 0 END) as debt,
+            SUM(CASE WHEN severity = 'error' THEN 1 ELSE 0 END) as error,
+            SUM(CASE WHEN severity = 'warning' THEN 1 ELSE 0 END) as warning,
+            SUM(CASE WHEN severity = 'info' THEN 1 ELSE 0 END) as info,
+            SUM(CASE WHEN severity = 'success' THEN 1 ELSE 0 END) as success,
+            SUM(CASE WHEN severity = 'error' AND is_read = 0 THEN 1 ELSE 0 END) as unread_error
          FROM notifications
-         WHERE 1=1",
+         WHERE COALESCE(is_archived, 0) = 0",
     );
 
     if user_id.is_some() {
@@ -148,6 +268,11 @@ pub async fn get_notification_stats(
         system: row.try_get("system").unwrap_or(0),
         invoice: row.try_get("invoice").unwrap_or(0),
         debt: row.try_get("debt").unwrap_or(0),
+        error: row.try_get("error").unwrap_or(0),
+        warning: row.try_get("warning").unwrap_or(0),
+        info: row.try_get("info").unwrap_or(0),
+        success: row.try_get("success").unwrap_or(0),
+        unread_error: row.try_get("unread_error").unwrap_or(0),
     })
 }
 
@@ -328,6 +453,126 @@ async fn check_outstanding_debts_internal(pool: &SqlitePool) -> Result<i32, Stri
     Ok(result.rows_affected() as i32)
 }
 
+// Promotions and appointments are one-off events, so any earlier notification for the same
+// record (read or not) counts as a duplicate.
+async fn check_expiring_promotions_internal(pool: &SqlitePool, days_ahead: i64) -> Result<i32, String> {
+    let result = sqlx::query(
+        "INSERT INTO notifications (notification_type, title, message, severity, reference_id, reference_type)
+         SELECT
+            'promotion',
+            'Promotion Ending Soon',
+            'Promotion ' || p.name || ' (' || p.code || ') ends on ' || DATE(p.end_date),
+            'warning',
+            p.id,
+            'promotion'
+         FROM promotions p
+         WHERE p.is_active = 1
+         AND p.end_date IS NOT NULL
+         AND DATE(p.start_date) <= DATE('now', 'localtime')
+         AND DATE(p.end_date) >= DATE('now', 'localtime')
+         AND DATE(p.end_date) <= DATE('now', 'localtime', '+' || ?1 || ' days')
+         AND (p.usage_limit IS NULL OR p.usage_count < p.usage_limit)
+         AND NOT EXISTS (
+            SELECT 1 FROM notifications n
+            WHERE n.notification_type = 'promotion'
+            AND n.reference_id = p.id
+            AND n.reference_type = 'promotion'
+         )"
+    )
+    .bind(days_ahead)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(result.rows_affected() as i32)
+}
+
+async fn check_todays_appointments_internal(pool: &SqlitePool) -> Result<i32, String> {
+    let result = sqlx::query(
+        "INSERT INTO notifications (notification_type, title, message, severity, reference_id, reference_type)
+         SELECT
+            'appointment',
+            'Appointment Today',
+            'Appointment ' || a.appointment_number || ' at ' || a.start_time
+                || COALESCE(' with ' || c.first_name || ' ' || c.last_name, '')
+                || COALESCE(' for ' || sv.name, ''),
+            'info',
+            a.id,
+            'appointment'
+         FROM appointments a
+         LEFT JOIN customers c ON a.customer_id = c.id
+         LEFT JOIN services sv ON a.service_id = sv.id
+         WHERE a.status IN ('Scheduled', 'Confirmed')
+         AND DATE(a.appointment_date) = DATE('now', 'localtime')
+         AND NOT EXISTS (
+            SELECT 1 FROM notifications n
+            WHERE n.notification_type = 'appointment'
+            AND n.reference_id = a.id
+            AND n.reference_type = 'appointment'
+         )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(result.rows_affected() as i32)
+}
+
+// A lot expires once, so like promotions any earlier notification for it is a duplicate
+async fn check_expiring_lots_internal(pool: &SqlitePool, days_ahead: i64) -> Result<i32, String> {
+    let result = sqlx::query(
+        "INSERT INTO notifications (notification_type, title, message, severity, reference_id, reference_type)
+         SELECT
+            'lot_expiry',
+            CASE WHEN DATE(l.expiry_date) < DATE('now', 'localtime')
+                 THEN 'Lot Expired' ELSE 'Lot Expiring Soon' END,
+            'Lot ' || l.lot_number || ' of ' || p.name || ' (' || p.sku || '), '
+                || l.quantity || ' units, expires on ' || DATE(l.expiry_date),
+            CASE WHEN DATE(l.expiry_date) < DATE('now', 'localtime') THEN 'error' ELSE 'warning' END,
+            l.id,
+            'product_lot'
+         FROM product_lots l
+         JOIN products p ON l.product_id = p.id
+         WHERE l.quantity > 0
+         AND l.expiry_date IS NOT NULL
+         AND DATE(l.expiry_date) <= DATE('now', 'localtime', '+' || ?1 || ' days')
+         AND NOT EXISTS (
+            SELECT 1 FROM notifications n
+            WHERE n.notification_type = 'lot_expiry'
+            AND n.reference_id = l.id
+            AND n.reference_type = 'product_lot'
+         )"
+    )
+    .bind(days_ahead)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(result.rows_affected() as i32)
+}
+
+async fn run_all_notification_checks_internal(
+    pool: &SqlitePool,
+    promotion_days_ahead: i64,
+) -> Result<NotificationCheckCounts, String> {
+    let low_stock = check_low_stock_internal(pool).await?;
+    let invoice = check_pending_invoices_internal(pool).await?;
+    let debt = check_outstanding_debts_internal(pool).await?;
+    let promotion = check_expiring_promotions_internal(pool, promotion_days_ahead).await?;
+    let appointment = check_todays_appointments_internal(pool).await?;
+    let lot_expiry = check_expiring_lots_internal(pool, DEFAULT_EXPIRY_WINDOW_DAYS).await?;
+
+    Ok(NotificationCheckCounts {
+        low_stock,
+        invoice,
+        debt,
+        promotion,
+        appointment,
+        lot_expiry,
+        total: low_stock + invoice + debt + promotion + appointment + lot_expiry,
+    })
+}
+
 #[command]
 pub async fn check_low_stock_alerts(pool: State<'_, SqlitePool>) -> Result<i32, String> {
     check_low_stock_internal(pool.inner()).await
@@ -349,6 +594,83 @@ pub async fn delete_notification(
     Ok(true)
 }
 
+/// Delete read notifications older than the retention window. Returns the number deleted.
+#[command]
+pub async fn delete_read_notifications(
+    pool: State<'_, SqlitePool>,
+    user_id: Option<i64>,
+    older_than_days: i64,
+) -> Result<u64, String> {
+    delete_read_notifications_internal(pool.inner(), user_id, older_than_days).await
+}
+
+/// Archive read notifications older than the retention window instead of deleting them.
+/// Returns the number archived.
+#[command]
+pub async fn archive_read_notifications(
+    pool: State<'_, SqlitePool>,
+    user_id: Option<i64>,
+    older_than_days: i64,
+) -> Result<u64, String> {
+    archive_read_notifications_internal(pool.inner(), user_id, older_than_days).await
+}
+
+async fn delete_read_notifications_internal(
+    pool: &SqlitePool,
+    user_id: Option<i64>,
+    older_than_days: i64,
+) -> Result<u64, String> {
+    purge_read_notifications(pool, "DELETE FROM notifications", user_id, older_than_days)
+        .await
+        .map_err(|e| format!("Failed to delete notifications: {}", e))
+}
+
+async fn archive_read_notifications_internal(
+    pool: &SqlitePool,
+    user_id: Option<i64>,
+    older_than_days: i64,
+) -> Result<u64, String> {
+    purge_read_notifications(
+        pool,
+        "UPDATE notifications SET is_archived = 1",
+        user_id,
+        older_than_days,
+    )
+    .await
+    .map_err(|e| format!("Failed to archive notifications: {}", e))
+}
+
+/// Run `statement` against read, unarchived notifications created more than
+/// `older_than_days` days ago
+async fn purge_read_notifications(
+    pool: &SqlitePool,
+    statement: &str,
+    user_id: Option<i64>,
+    older_than_days: i64,
+) -> Result<u64, String> {
+    if older_than_days < 0 {
+        return Err("older_than_days cannot be negative".to_string());
+    }
+
+    let mut query = format!(
+        "{} WHERE is_read = 1
+           AND COALESCE(is_archived, 0) = 0
+           AND created_at < DATETIME('now', ?1)",
+        statement
+    );
+    if user_id.is_some() {
+        query.push_str(" AND (user_id = ?2 OR user_id IS NULL)");
+    }
+
+    let mut sql_query = sqlx::query(&query).bind(format!("-{} days", older_than_days));
+    if let Some(uid) = user_id {
+        sql_query = sql_query.bind(uid);
+    }
+
+    let result = sql_query.execute(pool).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
+
 #[command]
 pub async fn check_pending_invoices(pool: State<'_, SqlitePool>) -> Result<i32, String> {
     check_pending_invoices_internal(pool.inner()).await
@@ -370,3 +692,149 @@ pub async fn refresh_notifications(pool: State<'_, SqlitePool>) -> Result<(i32,
 
     Ok((low_stock_count, invoice_count, debt_count))
 }
+
+#[command]
+pub async fn check_expiring_promotions(
+    pool: State<'_, SqlitePool>,
+    days_ahead: Option<i64>,
+) -> Result<i32, String> {
+    let days_ahead = days_ahead.unwrap_or(DEFAULT_PROMOTION_EXPIRY_DAYS);
+    check_expiring_promotions_internal(pool.inner(), days_ahead).await
+}
+
+#[command]
+pub async fn check_expiring_lots(
+    pool: State<'_, SqlitePool>,
+    days_ahead: Option<i64>,
+) -> Result<i32, String> {
+    check_expiring_lots_internal(pool.inner(), days_ahead.unwrap_or(DEFAULT_EXPIRY_WINDOW_DAYS)).await
+}
+
+#[command]
+pub async fn check_todays_appointments(pool: State<'_, SqlitePool>) -> Result<i32, String> {
+    check_todays_appointments_internal(pool.inner()).await
+}
+
+#[command]
+pub async fn run_all_notification_checks(
+    pool: State<'_, SqlitePool>,
+    promotion_days_ahead: Option<i64>,
+) -> Result<NotificationCheckCounts, String> {
+    run_all_notification_checks_internal(
+        pool.inner(),
+        promotion_days_ahead.unwrap_or(DEFAULT_PROMOTION_EXPIRY_DAYS),
+    )
+    .await
+}
+
+/// Wake the background runner so checks run immediately
+#[command]
+pub async fn trigger_notification_checks(
+    scheduler: State<'_, NotificationScheduler>,
+) -> Result<bool, String> {
+    scheduler.trigger();
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_pool;
+
+    async fn insert_promotion(pool: &SqlitePool, code: &str, end_offset_days: i64) {
+        sqlx::query(
+            "INSERT INTO promotions (code, name, discount_type, discount_value, start_date, end_date)
+             VALUES (?1, ?1, 'Percentage', 10.0, DATE('now', 'localtime', '-1 days'),
+                     DATE('now', 'localtime', ?2 || ' days'))",
+        )
+        .bind(code)
+        .bind(format!("{:+}", end_offset_days))
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn insert_appointment(pool: &SqlitePool, number: &str, day_offset: i64, status: &str) {
+        sqlx::query(
+            "INSERT INTO appointments (appointment_number, appointment_date, start_time, end_time,
+                                       duration_minutes, status, price)
+             VALUES (?1, DATE('now', 'localtime', ?2 || ' days'), '10:00', '10:30', 30, ?3, 25.0)",
+        )
+        .bind(number)
+        .bind(format!("{:+}", day_offset))
+        .bind(status)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expiring_promotions_are_deduplicated() {
+        let pool = test_pool().await;
+        insert_promotion(&pool, "SOON", 3).await;
+        insert_promotion(&pool, "LATER", 30).await;
+        insert_promotion(&pool, "ENDED", -2).await;
+
+        assert_eq!(check_expiring_promotions_internal(&pool, 7).await.unwrap(), 1);
+
+        sqlx::query("UPDATE notifications SET is_read = 1").execute(&pool).await.unwrap();
+        assert_eq!(check_expiring_promotions_internal(&pool, 7).await.unwrap(), 0);
+    }
+
+    async fn insert_notification(pool: &SqlitePool, is_read: bool, age_days: i64) {
+        sqlx::query(
+            "INSERT INTO notifications (notification_type, title, message, severity, is_read, created_at)
+             VALUES ('system', 'Test', 'Test', 'info', ?1, DATETIME('now', ?2 || ' days'))",
+        )
+        .bind(is_read)
+        .bind(format!("-{}", age_days))
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_purge_only_touches_old_read_notifications() {
+        let pool = test_pool().await;
+        insert_notification(&pool, true, 40).await;
+        insert_notification(&pool, true, 35).await;
+        insert_notification(&pool, true, 1).await;
+        insert_notification(&pool, false, 40).await;
+
+        assert_eq!(archive_read_notifications_internal(&pool, None, 30).await.unwrap(), 2);
+        let visible: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE is_archived = 0")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(visible, 2);
+
+        // Archived rows are left alone; only the remaining old read row is deleted
+        insert_notification(&pool, true, 60).await;
+        assert_eq!(delete_read_notifications_internal(&pool, None, 30).await.unwrap(), 1);
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notifications")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(total, 4);
+    }
+
+    #[tokio::test]
+    async fn test_run_all_checks_reports_counts_per_type() {
+        let pool = test_pool().await;
+        insert_appointment(&pool, "APT-1", 0, "Scheduled").await;
+        insert_appointment(&pool, "APT-2", 0, "Confirmed").await;
+        insert_appointment(&pool, "APT-3", 0, "Cancelled").await;
+        insert_appointment(&pool, "APT-4", 1, "Scheduled").await;
+        insert_promotion(&pool, "SOON", 1).await;
+
+        let counts = run_all_notification_checks_internal(&pool, 7).await.unwrap();
+        assert_eq!(counts.appointment, 2);
+        assert_eq!(counts.promotion, 1);
+        assert_eq!(counts.total, counts.low_stock + counts.invoice + counts.debt + 3);
+
+        let again = run_all_notification_checks_internal(&pool, 7).await.unwrap();
+        assert_eq!(again.appointment, 0);
+        assert_eq!(again.promotion, 0);
+    }
+}
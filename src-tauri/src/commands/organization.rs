@@ -1,5 +1,12 @@
 // src-tauri/src/commands/organization.rs
+use crate::error::AppError;
+use crate::margins::BelowCostPolicy;
 use crate::models::*;
+use crate::organization_settings::{self, OrganizationSettings};
+use crate::plans::{self, PlanResource, PlanUsage};
+use crate::session::SESSION_MANAGER;
+use crate::tenancy;
+use crate::validation::validate_non_negative;
 use sqlx::SqlitePool;
 use tauri::State;
 
@@ -155,14 +162,18 @@ pub async fn get_locations(pool: State<'_, SqlitePool>) -> Result<Vec<Location>,
 pub async fn create_location(
     pool: State<'_, SqlitePool>,
     request: CreateLocationRequest,
+    session_token: String,
 ) -> Result<Location, String> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let mut conn = pool.acquire().await.map_err(AppError::from)?;
+    plans::check_plan_limit(&mut conn, organization_id, PlanResource::Locations).await?;
     let tax_rate = request.tax_rate.unwrap_or(0.0);
     let currency = request.currency.unwrap_or_else(|| "USD".to_string());
     let country = request.country.unwrap_or_else(|| "US".to_string());
 
     let result = sqlx::query(
-        "INSERT INTO locations (name, address, city, state, zip_code, country, phone, email, tax_rate, currency, logo_url)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO locations (name, address, city, state, zip_code, country, phone, email, tax_rate, currency, logo_url, organization_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&request.name)
     .bind(&request.address)
@@ -175,7 +186,8 @@ pub async fn create_location(
     .bind(tax_rate)
     .bind(&currency)
     .bind(&request.logo_url)
-    .execute(pool.inner())
+    .bind(organization_id)
+    .execute(&mut *conn)
     .await
     .map_err(|e| format!("Failed to create location: {}", e))?;
 
@@ -183,7 +195,7 @@ pub async fn create_location(
 
     sqlx::query_as::<_, Location>("SELECT * FROM locations WHERE id = ?")
         .bind(location_id)
-        .fetch_one(pool.inner())
+        .fetch_one(&mut *conn)
         .await
         .map_err(|e| format!("Failed to fetch created location: {}", e))
 }
@@ -194,6 +206,22 @@ pub async fn update_location(
     location_id: i64,
     request: UpdateLocationRequest,
 ) -> Result<Location, String> {
+    if let Some(policy) = &request.below_cost_policy {
+        BelowCostPolicy::parse(policy)?;
+    }
+    if let Some(min_margin) = request.min_margin_percent {
+        validate_non_negative(min_margin, "min_margin_percent")?;
+    }
+    if let Some(threshold) = request.no_sale_alert_threshold {
+        validate_non_negative(threshold, "no_sale_alert_threshold")?;
+    }
+    if let Some(threshold) = request.return_alert_count {
+        validate_non_negative(threshold, "return_alert_count")?;
+    }
+    if let Some(threshold) = request.return_alert_value {
+        validate_non_negative(threshold, "return_alert_value")?;
+    }
+
     sqlx::query(
         "UPDATE locations SET
             name = COALESCE(?, name),
@@ -208,6 +236,14 @@ pub async fn update_location(
             currency = COALESCE(?, currency),
             logo_url = COALESCE(?, logo_url),
             is_active = COALESCE(?, is_active),
+            below_cost_policy = COALESCE(?, below_cost_policy),
+            min_margin_percent = COALESCE(?, min_margin_percent),
+            receipt_printer = COALESCE(?, receipt_printer),
+            no_sale_alert_threshold = COALESCE(?, no_sale_alert_threshold),
+            adjustment_approval_threshold = COALESCE(?, adjustment_approval_threshold),
+            return_alert_count = COALESCE(?, return_alert_count),
+            return_alert_value = COALESCE(?, return_alert_value),
+            receipt_footer = COALESCE(?, receipt_footer),
             updated_at = CURRENT_TIMESTAMP
          WHERE id = ?",
     )
@@ -223,6 +259,14 @@ pub async fn update_location(
     .bind(&request.currency)
     .bind(&request.logo_url)
     .bind(&request.is_active)
+    .bind(&request.below_cost_policy)
+    .bind(request.min_margin_percent)
+    .bind(&request.receipt_printer)
+    .bind(request.no_sale_alert_threshold)
+    .bind(request.adjustment_approval_threshold)
+    .bind(request.return_alert_count)
+    .bind(request.return_alert_value)
+    .bind(&request.receipt_footer)
     .bind(location_id)
     .execute(pool.inner())
     .await
@@ -248,3 +292,40 @@ pub async fn delete_location(
 
     Ok(())
 }
+
+/// The session organization's plan, its limits and how much of them is used
+#[tauri::command]
+pub async fn get_plan_usage(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<PlanUsage, String> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let mut conn = pool.acquire().await.map_err(AppError::from)?;
+    Ok(plans::plan_usage(&mut conn, organization_id).await?)
+}
+
+#[tauri::command]
+pub async fn get_organization_settings(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<OrganizationSettings, String> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let mut conn = pool.acquire().await.map_err(AppError::from)?;
+    Ok(organization_settings::load(&mut conn, organization_id).await?)
+}
+
+/// Replace the session organization's settings after validating them. Needs an owner or
+/// admin of the organization signed in with their password.
+#[tauri::command]
+pub async fn update_organization_settings(
+    pool: State<'_, SqlitePool>,
+    settings: OrganizationSettings,
+    session_token: String,
+) -> Result<OrganizationSettings, String> {
+    let session = SESSION_MANAGER.require_password_session(&session_token)?;
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    let mut conn = pool.acquire().await.map_err(AppError::from)?;
+    tenancy::require_admin(&mut conn, organization_id, session.user_id).await?;
+    organization_settings::save(&mut conn, organization_id, &settings).await?;
+    Ok(settings)
+}
@@ -0,0 +1,146 @@
+// src-tauri/src/commands/parked_sales.rs - Carts put on hold to serve another customer
+use crate::error::{AppError, AppResult};
+use crate::plans;
+use crate::validation::validate_required;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tauri::{command, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParkedSale {
+    pub id: i64,
+    pub cashier_id: i64,
+    pub label: String,
+    /// The cart exactly as the frontend parked it
+    pub cart: serde_json::Value,
+    pub created_at: String,
+}
+
+fn parked_from_row(row: &sqlx::sqlite::SqliteRow) -> AppResult<ParkedSale> {
+    let cart_json: String = row.try_get("cart_json")?;
+    Ok(ParkedSale {
+        id: row.try_get("id")?,
+        cashier_id: row.try_get("cashier_id")?,
+        label: row.try_get("label")?,
+        cart: serde_json::from_str(&cart_json).map_err(|e| AppError::Internal {
+            message: format!("Parked cart is not valid JSON: {}", e),
+        })?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+pub(crate) async fn park_sale_internal(
+    pool: &SqlitePool,
+    cashier_id: i64,
+    label: &str,
+    cart_json: &serde_json::Value,
+) -> AppResult<ParkedSale> {
+    validate_required(label, "label")?;
+    if cart_json.is_null() {
+        return Err(AppError::validation("cart_json", "There is no cart to park"));
+    }
+    let row = sqlx::query(
+        "INSERT INTO parked_sales (cashier_id, label, cart_json) VALUES (?1, ?2, ?3)
+         RETURNING id, cashier_id, label, cart_json, COALESCE(created_at, '') as created_at",
+    )
+    .bind(cashier_id)
+    .bind(label.trim())
+    .bind(cart_json.to_string())
+    .fetch_one(pool)
+    .await?;
+    parked_from_row(&row)
+}
+
+pub(crate) async fn list_parked_sales_internal(
+    pool: &SqlitePool,
+    cashier_id: i64,
+) -> AppResult<Vec<ParkedSale>> {
+    let rows = sqlx::query(
+        "SELECT id, cashier_id, label, cart_json, COALESCE(created_at, '') as created_at
+         FROM parked_sales
+         WHERE cashier_id = ?1
+         ORDER BY created_at, id",
+    )
+    .bind(cashier_id)
+    .fetch_all(pool)
+    .await?;
+    rows.iter().map(parked_from_row).collect()
+}
+
+/// Hand the parked cart back and remove it, so it can only be resumed once
+pub(crate) async fn resume_parked_sale_internal(
+    pool: &SqlitePool,
+    parked_id: i64,
+) -> AppResult<ParkedSale> {
+    let row = sqlx::query(
+        "DELETE FROM parked_sales WHERE id = ?1
+         RETURNING id, cashier_id, label, cart_json, COALESCE(created_at, '') as created_at",
+    )
+    .bind(parked_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Parked sale"))?;
+    parked_from_row(&row)
+}
+
+#[command]
+pub async fn park_sale(
+    pool: State<'_, SqlitePool>,
+    cashier_id: i64,
+    label: String,
+    cart_json: serde_json::Value,
+    session_token: String,
+) -> Result<ParkedSale, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    park_sale_internal(pool.inner(), cashier_id, &label, &cart_json).await
+}
+
+#[command]
+pub async fn list_parked_sales(
+    pool: State<'_, SqlitePool>,
+    cashier_id: i64,
+) -> Result<Vec<ParkedSale>, AppError> {
+    list_parked_sales_internal(pool.inner(), cashier_id).await
+}
+
+#[command]
+pub async fn resume_parked_sale(
+    pool: State<'_, SqlitePool>,
+    parked_id: i64,
+    session_token: String,
+) -> Result<ParkedSale, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    resume_parked_sale_internal(pool.inner(), parked_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_user, test_pool};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_parked_cart_resumes_once() {
+        let pool = test_pool().await;
+        let alice = insert_test_user(&pool, "alice").await;
+        let bob = insert_test_user(&pool, "bob").await;
+        let cart = json!({"items": [{"product_id": 3, "quantity": 2}], "customer_name": "Ngono"});
+
+        let parked = park_sale_internal(&pool, alice, " Blue truck ", &cart).await.unwrap();
+        assert_eq!(parked.label, "Blue truck");
+        park_sale_internal(&pool, bob, "Walk-in", &json!({"items": []})).await.unwrap();
+
+        let alices = list_parked_sales_internal(&pool, alice).await.unwrap();
+        assert_eq!(alices.len(), 1);
+        assert_eq!(alices[0].cart, cart);
+
+        let resumed = resume_parked_sale_internal(&pool, parked.id).await.unwrap();
+        assert_eq!(resumed.cart, cart);
+        assert!(list_parked_sales_internal(&pool, alice).await.unwrap().is_empty());
+        let err = resume_parked_sale_internal(&pool, parked.id).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+
+        let err = park_sale_internal(&pool, alice, "  ", &cart).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+}
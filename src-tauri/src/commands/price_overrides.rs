@@ -0,0 +1,247 @@
+// src-tauri/src/commands/price_overrides.rs - Approval and reporting for register price overrides
+use crate::db_utils::{is_manager, require_manager};
+use crate::error::{AppError, AppResult};
+use crate::models::CreateSaleRequest;
+use crate::money::Money;
+use crate::pin;
+use crate::tenancy;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use tauri::{command, State};
+
+/// Overrides rung up by one cashier over a period
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CashierOverrideSummary {
+    pub cashier_id: i64,
+    pub cashier_name: String,
+    pub override_count: i64,
+    pub quantity: f64,
+    /// List price minus charged price, summed over the units sold
+    pub value_given_away: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceOverrideReport {
+    pub start_date: String,
+    pub end_date: String,
+    pub cashiers: Vec<CashierOverrideSummary>,
+    pub total_given_away: Money,
+}
+
+/// Who approved the sale's price overrides, or `None` if it has none. Managers approve
+/// their own; a cashier needs a manager's PIN captured with the request.
+pub(crate) async fn authorize_price_overrides(
+    pool: &SqlitePool,
+    request: &CreateSaleRequest,
+    cashier_id: i64,
+) -> AppResult<Option<i64>> {
+    if request.items.iter().all(|item| item.price_override.is_none()) {
+        return Ok(None);
+    }
+
+    let mut conn = pool.acquire().await?;
+    if is_manager(&mut conn, cashier_id).await? {
+        return Ok(Some(cashier_id));
+    }
+    drop(conn);
+
+    let approval = request
+        .override_approval
+        .as_ref()
+        .ok_or_else(|| AppError::PermissionDenied {
+            message: "A manager must approve price overrides".to_string(),
+        })?;
+    let approver = pin::verify_user_pin(pool, &approval.username_or_badge, &approval.pin)
+        .await?
+        .ok_or_else(|| AppError::PermissionDenied {
+            message: "Manager PIN not recognised".to_string(),
+        })?;
+
+    let mut conn = pool.acquire().await?;
+    require_manager(&mut conn, approver, "approve a price override").await?;
+    Ok(Some(approver))
+}
+
+pub(crate) async fn get_price_override_report_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> AppResult<PriceOverrideReport> {
+    let cashiers = sqlx::query_as::<_, CashierOverrideSummary>(&format!(
+        "SELECT s.cashier_id,
+                COALESCE(u.first_name || ' ' || u.last_name, 'Unknown') as cashier_name,
+                COUNT(*) as override_count,
+                CAST(COALESCE(SUM(COALESCE(si.measured_quantity, si.quantity)), 0) AS REAL) as quantity,
+                COALESCE(SUM((si.original_unit_price - si.unit_price)
+                             * COALESCE(si.measured_quantity, si.quantity)), 0.0) as value_given_away
+         FROM sale_items si
+         JOIN sales s ON s.id = si.sale_id
+         LEFT JOIN users u ON u.id = s.cashier_id
+         WHERE si.original_unit_price IS NOT NULL
+           AND s.is_voided = 0
+           AND DATE(s.created_at) BETWEEN ?1 AND ?2{}
+         GROUP BY s.cashier_id
+         ORDER BY value_given_away DESC",
+        tenancy::organization_scope("s.organization_id", organization_id)
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(PriceOverrideReport {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        total_given_away: cashiers.iter().map(|cashier| cashier.value_given_away).sum(),
+        cashiers,
+    })
+}
+
+#[command]
+pub async fn get_price_override_report(
+    pool: State<'_, SqlitePool>,
+    start_date: String,
+    end_date: String,
+    session_token: String,
+) -> Result<PriceOverrideReport, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_price_override_report_internal(pool.inner(), organization_id, &start_date, &end_date).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::sales::create_sale_internal;
+    use crate::models::{ManagerApproval, SaleItemRequest};
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    async fn give_pin(pool: &SqlitePool, user_id: i64, pin: &str) {
+        sqlx::query("INSERT INTO user_pins (user_id, pin_hash) VALUES (?1, ?2)")
+            .bind(user_id)
+            .bind(bcrypt::hash(pin, 4).unwrap())
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    fn overridden_sale(product_id: i64, approval: Option<(&str, &str)>) -> CreateSaleRequest {
+        // Listed at 10.00, sold 2 at 7.50
+        let line_total = Money::from_minor(1500);
+        CreateSaleRequest {
+            items: vec![SaleItemRequest {
+                product_id,
+                quantity: 2,
+                unit_price: Money::from_major(10.0),
+                discount_amount: Money::ZERO,
+                line_total,
+                measured_quantity: None,
+                price_override: Some(Money::from_minor(750)),
+                override_reason: Some("Damaged box".to_string()),
+            }],
+            subtotal: line_total,
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: line_total,
+            payment_method: "cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: approval.map(|(username, pin)| ManagerApproval {
+                username_or_badge: username.to_string(),
+                pin: pin.to_string(),
+            }),
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_price_override_is_rejected() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let other_cashier = insert_test_user(&pool, "cashier2").await;
+        let manager = insert_test_user(&pool, "manager1").await;
+        sqlx::query("UPDATE users SET role = 'Manager' WHERE id = ?1")
+            .bind(manager)
+            .execute(&pool)
+            .await
+            .unwrap();
+        give_pin(&pool, other_cashier, "2222").await;
+        give_pin(&pool, manager, "9999").await;
+        let product = insert_test_product(&pool, "OVR-1", 10.0, 10).await;
+
+        for approval in [None, Some(("cashier2", "2222")), Some(("manager1", "0000"))] {
+            let err = create_sale_internal(&pool, overridden_sale(product, approval), cashier, None)
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), "PERMISSION_DENIED");
+        }
+        let items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sale_items")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(items, 0);
+    }
+
+    #[tokio::test]
+    async fn test_approved_override_is_recorded_and_reported() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let manager = insert_test_user(&pool, "manager1").await;
+        sqlx::query("UPDATE users SET role = 'Manager' WHERE id = ?1")
+            .bind(manager)
+            .execute(&pool)
+            .await
+            .unwrap();
+        give_pin(&pool, manager, "9999").await;
+        let product = insert_test_product(&pool, "OVR-1", 10.0, 10).await;
+
+        let request = overridden_sale(product, Some(("manager1", "9999")));
+        let sale = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+        // A manager ringing up their own sale needs no PIN
+        create_sale_internal(&pool, overridden_sale(product, None), manager, None)
+            .await
+            .unwrap();
+
+        let (unit_price, original, approved_by): (Money, Money, i64) = sqlx::query_as(
+            "SELECT unit_price, original_unit_price, override_approved_by
+             FROM sale_items WHERE sale_id = ?1",
+        )
+        .bind(sale.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(unit_price, Money::from_minor(750));
+        assert_eq!(original, Money::from_major(10.0));
+        assert_eq!(approved_by, manager);
+
+        let audited: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM audit_log WHERE action = 'price_override'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(audited, 2);
+
+        let report =
+            get_price_override_report_internal(&pool, DEFAULT_ORGANIZATION_ID, "2000-01-01", "2999-12-31")
+                .await
+                .unwrap();
+        assert_eq!(report.cashiers.len(), 2);
+        assert_eq!(report.cashiers[0].override_count, 1);
+        assert_eq!(report.cashiers[0].value_given_away, Money::from_minor(500));
+        assert_eq!(report.total_given_away, Money::from_minor(1000));
+
+        let other = get_price_override_report_internal(&pool, 2, "2000-01-01", "2999-12-31")
+            .await
+            .unwrap();
+        assert!(other.cashiers.is_empty());
+    }
+}
@@ -1,6 +1,27 @@
-use crate::models::{CreateProductRequest, Product, ProductSearchRequest};
+use crate::audit;
+use crate::commands::master_data::resolve_product_category;
+use crate::cost_history::{
+    self, record_initial_cost, set_cost_price, CostHistoryEntry, CostSource, RecalculatedCost,
+};
+use crate::db_utils::{is_manager, require_manager, require_unreferenced, Pagination};
+use crate::error::{AppError, AppResult};
+use crate::margins::{
+    check_selling_price, describe_below_cost, location_min_margin, BelowCostItem, UncostedItem,
+};
+use crate::models::{
+    CreateProductRequest, Listing, ManagerApproval, Page, PageCursor, Product, ProductSearchRequest,
+};
+use crate::money::Money;
+use crate::pin;
+use crate::plans::{self, PlanResource};
+use crate::session::SESSION_MANAGER;
+use crate::sync_outbox::{enqueue_change, SyncOperation};
+use crate::tenancy;
+use crate::validation::{for_product, validate_amount, Validate};
 use serde::{Deserialize, Serialize};
-use sqlx::{Row, SqlitePool};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
+use std::collections::HashMap;
 use tauri::State;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -32,78 +53,151 @@ pub struct ProductWithStock {
     pub updated_at: String,
 }
 
-#[tauri::command]
-pub async fn get_products(pool: State<'_, SqlitePool>) -> Result<Vec<Product>, String> {
-    let rows = sqlx::query("SELECT * FROM products ORDER BY is_active DESC, name ASC")
-        .fetch_all(pool.inner())
-        .await
-        .map_err(|e| e.to_string())?;
+/// Products of an organization. Unpaged they are all listed, active ones first by name;
+/// paged they are walked newest first so a cursor can pick up where a page ended.
+pub(crate) async fn get_products_internal(
+    pool_ref: &SqlitePool,
+    organization_id: i64,
+    include_archived: bool,
+    pagination: Option<&Pagination>,
+    with_total: bool,
+) -> AppResult<Page<Product>> {
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM products WHERE organization_id = ");
+    query.push_bind(organization_id);
+    if !include_archived {
+        query.push(" AND archived_at IS NULL");
+    }
+    match pagination {
+        Some(pagination) => {
+            pagination.push_after(&mut query, "created_at", "id");
+            query.push(" ORDER BY created_at DESC, id DESC");
+            pagination.push_limit(&mut query);
+        }
+        None => {
+            query.push(" ORDER BY is_active DESC, name ASC");
+        }
+    }
+    let rows = query.build().fetch_all(pool_ref).await?;
 
     let mut products = Vec::new();
     for row in rows {
         let product = Product {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            sku: row.try_get("sku").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            sku: row.try_get("sku")?,
             barcode: row.try_get("barcode").ok().flatten(),
-            name: row.try_get("name").map_err(|e| e.to_string())?,
+            name: row.try_get("name")?,
             description: row.try_get("description").ok().flatten(),
             category: row.try_get("category").ok().flatten(),
             subcategory: row.try_get("subcategory").ok().flatten(),
             brand: row.try_get("brand").ok().flatten(),
-            unit_of_measure: row.try_get("unit_of_measure").map_err(|e| e.to_string())?,
-            cost_price: row.try_get("cost_price").map_err(|e| e.to_string())?,
-            selling_price: row.try_get("selling_price").map_err(|e| e.to_string())?,
-            wholesale_price: row.try_get("wholesale_price").map_err(|e| e.to_string())?,
-            tax_rate: row.try_get("tax_rate").map_err(|e| e.to_string())?,
-            is_active: row.try_get("is_active").map_err(|e| e.to_string())?,
-            is_taxable: row.try_get("is_taxable").map_err(|e| e.to_string())?,
-            weight: row.try_get("weight").map_err(|e| e.to_string())?,
+            unit_of_measure: row.try_get("unit_of_measure")?,
+            cost_price: row.try_get("cost_price")?,
+            selling_price: row.try_get("selling_price")?,
+            wholesale_price: row.try_get("wholesale_price")?,
+            tax_rate: row.try_get("tax_rate")?,
+            is_active: row.try_get("is_active")?,
+            is_taxable: row.try_get("is_taxable")?,
+            weight: row.try_get("weight")?,
             dimensions: row.try_get("dimensions").ok().flatten(),
             supplier_info: row.try_get("supplier_info").ok().flatten(),
-            reorder_point: row.try_get("reorder_point").map_err(|e| e.to_string())?,
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-            updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
+            reorder_point: row.try_get("reorder_point")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
         };
         products.push(product);
     }
 
-    Ok(products)
+    let total_count = if with_total {
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM products WHERE organization_id = ");
+        count.push_bind(organization_id);
+        if !include_archived {
+            count.push(" AND archived_at IS NULL");
+        }
+        count.build_query_scalar::<i64>().fetch_one(pool_ref).await?
+    } else {
+        products.len() as i64
+    };
+    Ok(match pagination {
+        Some(pagination) => pagination.into_page(products, total_count, |product| PageCursor {
+            created_at: product.created_at.clone(),
+            id: product.id,
+        }),
+        None => Page::new(products, total_count, 0),
+    })
+}
+
+/// Products of the active organization. Without `limit`, a cursor or `envelope` every
+/// product is returned as before; otherwise pages of `limit` (100 by default) newest first.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_products(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    include_archived: Option<bool>,
+    limit: Option<i32>,
+    offset: Option<i32>,
+    after_id: Option<i64>,
+    after_created_at: Option<String>,
+    envelope: Option<bool>,
+) -> Result<Listing<Product>, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let paged = limit.is_some()
+        || offset.is_some()
+        || after_id.is_some()
+        || after_created_at.is_some()
+        || envelope.unwrap_or(false);
+    let pagination = if paged {
+        Some(Pagination::from_params(i64::from(limit.unwrap_or(100)), offset, after_id, after_created_at)?)
+    } else {
+        None
+    };
+    let page = get_products_internal(
+        pool.inner(),
+        organization_id,
+        include_archived.unwrap_or(false),
+        pagination.as_ref(),
+        envelope.unwrap_or(false),
+    )
+    .await?;
+    Ok(Listing::new(page, envelope))
 }
 
 #[tauri::command]
 pub async fn get_product_by_id(
     pool: State<'_, SqlitePool>,
     product_id: i64,
-) -> Result<Option<Product>, String> {
-    let row = sqlx::query("SELECT * FROM products WHERE id = ? AND is_active = 1")
+    session_token: String,
+) -> Result<Option<Product>, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let row = sqlx::query("SELECT * FROM products WHERE id = ? AND is_active = 1 AND organization_id = ?")
         .bind(product_id)
+        .bind(organization_id)
         .fetch_optional(pool.inner())
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
     if let Some(row) = row {
         let product = Product {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            sku: row.try_get("sku").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            sku: row.try_get("sku")?,
             barcode: row.try_get("barcode").ok().flatten(),
-            name: row.try_get("name").map_err(|e| e.to_string())?,
+            name: row.try_get("name")?,
             description: row.try_get("description").ok().flatten(),
             category: row.try_get("category").ok().flatten(),
             subcategory: row.try_get("subcategory").ok().flatten(),
             brand: row.try_get("brand").ok().flatten(),
-            unit_of_measure: row.try_get("unit_of_measure").map_err(|e| e.to_string())?,
-            cost_price: row.try_get("cost_price").map_err(|e| e.to_string())?,
-            selling_price: row.try_get("selling_price").map_err(|e| e.to_string())?,
-            wholesale_price: row.try_get("wholesale_price").map_err(|e| e.to_string())?,
-            tax_rate: row.try_get("tax_rate").map_err(|e| e.to_string())?,
-            is_active: row.try_get("is_active").map_err(|e| e.to_string())?,
-            is_taxable: row.try_get("is_taxable").map_err(|e| e.to_string())?,
-            weight: row.try_get("weight").map_err(|e| e.to_string())?,
+            unit_of_measure: row.try_get("unit_of_measure")?,
+            cost_price: row.try_get("cost_price")?,
+            selling_price: row.try_get("selling_price")?,
+            wholesale_price: row.try_get("wholesale_price")?,
+            tax_rate: row.try_get("tax_rate")?,
+            is_active: row.try_get("is_active")?,
+            is_taxable: row.try_get("is_taxable")?,
+            weight: row.try_get("weight")?,
             dimensions: row.try_get("dimensions").ok().flatten(),
             supplier_info: row.try_get("supplier_info").ok().flatten(),
-            reorder_point: row.try_get("reorder_point").map_err(|e| e.to_string())?,
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-            updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
+            reorder_point: row.try_get("reorder_point")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
         };
         Ok(Some(product))
     } else {
@@ -115,7 +209,19 @@ pub async fn get_product_by_id(
 pub async fn create_product(
     pool: State<'_, SqlitePool>,
     request: CreateProductRequest,
-) -> Result<Product, String> {
+    session_token: String,
+) -> Result<Product, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    create_product_internal(pool.inner(), request, organization_id).await
+}
+
+async fn create_product_internal(
+    pool: &SqlitePool,
+    request: CreateProductRequest,
+    organization_id: i64,
+) -> AppResult<Product> {
+    request.validate()?;
+
     // Convert empty strings to None for optional fields to avoid UNIQUE constraint issues
     let barcode = request.barcode.as_ref().and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) });
     let description = request.description.as_ref().and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) });
@@ -125,17 +231,21 @@ pub async fn create_product(
     let dimensions = request.dimensions.as_ref().and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) });
     let supplier_info = request.supplier_info.as_ref().and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) });
 
+    let mut tx = pool.begin().await?;
+    plans::check_plan_limit(&mut tx, organization_id, PlanResource::Products).await?;
+    let (category_id, category) = resolve_product_category(&mut tx, request.category_id, category).await?;
+
     let product_id = sqlx::query(
         "INSERT INTO products (sku, barcode, name, description, category, subcategory, brand, 
          unit_of_measure, cost_price, selling_price, wholesale_price, tax_rate, is_taxable, 
-         weight, dimensions, supplier_info, reorder_point, is_active) 
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1)",
+         weight, dimensions, supplier_info, reorder_point, is_active, organization_id, category_id) 
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1, ?, ?)",
     )
     .bind(&request.sku)
     .bind(barcode)
     .bind(&request.name)
     .bind(description)
-    .bind(category)
+    .bind(&category)
     .bind(subcategory)
     .bind(brand)
     .bind(&request.unit_of_measure)
@@ -148,22 +258,27 @@ pub async fn create_product(
     .bind(dimensions)
     .bind(supplier_info)
     .bind(request.reorder_point)
-    .execute(pool.inner())
-    .await
-    .map_err(|e| e.to_string())?
+    .bind(organization_id)
+    .bind(category_id)
+    .execute(&mut *tx)
+    .await?
     .last_insert_rowid();
 
     // CRITICAL: Auto-create inventory record for this product
     sqlx::query(
         "INSERT INTO inventory (product_id, current_stock, minimum_stock, maximum_stock, 
-         reserved_stock, available_stock, last_updated) 
-         VALUES (?, 0, ?, 1000, 0, 0, CURRENT_TIMESTAMP)",
+         reserved_stock, available_stock, last_updated, organization_id) 
+         VALUES (?, 0, ?, 1000, 0, 0, CURRENT_TIMESTAMP, ?)",
     )
     .bind(product_id)
     .bind(request.reorder_point)
-    .execute(pool.inner())
-    .await
-    .map_err(|e| format!("Failed to create inventory record: {}", e))?;
+    .bind(organization_id)
+    .execute(&mut *tx)
+    .await?;
+
+    record_initial_cost(&mut tx, product_id, request.cost_price, None).await?;
+    enqueue_change(&mut tx, "product", product_id, SyncOperation::Create).await?;
+    tx.commit().await?;
 
     let product = Product {
         id: product_id,
@@ -171,7 +286,7 @@ pub async fn create_product(
         barcode: request.barcode,
         name: request.name,
         description: request.description,
-        category: request.category,
+        category,
         subcategory: request.subcategory,
         brand: request.brand,
         unit_of_measure: request.unit_of_measure,
@@ -197,7 +312,11 @@ pub async fn update_product(
     pool: State<'_, SqlitePool>,
     product_id: i64,
     request: CreateProductRequest,
-) -> Result<Product, String> {
+    session_token: String,
+) -> Result<Product, AppError> {
+    request.validate()?;
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+
     // Convert empty strings to None for optional fields to avoid UNIQUE constraint issues
     let barcode = request.barcode.as_ref().and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) });
     let description = request.description.as_ref().and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) });
@@ -207,21 +326,27 @@ pub async fn update_product(
     let dimensions = request.dimensions.as_ref().and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) });
     let supplier_info = request.supplier_info.as_ref().and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) });
 
+    let mut tx = pool.begin().await?;
+    tenancy::require_in_organization(&mut tx, "products", product_id, organization_id, "Product")
+        .await?;
+
+    set_cost_price(&mut tx, product_id, request.cost_price, CostSource::Manual, None, None).await?;
+    let (category_id, category) = resolve_product_category(&mut tx, request.category_id, category).await?;
+
     sqlx::query(
         "UPDATE products SET sku = ?, barcode = ?, name = ?, description = ?, category = ?, 
-         subcategory = ?, brand = ?, unit_of_measure = ?, cost_price = ?, selling_price = ?, 
+         subcategory = ?, brand = ?, unit_of_measure = ?, selling_price = ?, 
          wholesale_price = ?, tax_rate = ?, is_taxable = ?, weight = ?, dimensions = ?, 
-         supplier_info = ?, reorder_point = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+         supplier_info = ?, reorder_point = ?, category_id = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
     )
     .bind(&request.sku)
     .bind(barcode)
     .bind(&request.name)
     .bind(description)
-    .bind(category)
+    .bind(&category)
     .bind(subcategory)
     .bind(brand)
     .bind(&request.unit_of_measure)
-    .bind(request.cost_price)
     .bind(request.selling_price)
     .bind(request.wholesale_price)
     .bind(request.tax_rate)
@@ -230,10 +355,13 @@ pub async fn update_product(
     .bind(dimensions)
     .bind(supplier_info)
     .bind(request.reorder_point)
+    .bind(category_id)
     .bind(product_id)
-    .execute(pool.inner())
-    .await
-    .map_err(|e| e.to_string())?;
+    .execute(&mut *tx)
+    .await?;
+
+    enqueue_change(&mut tx, "product", product_id, SyncOperation::Update).await?;
+    tx.commit().await?;
 
     let product = Product {
         id: product_id,
@@ -241,7 +369,7 @@ pub async fn update_product(
         barcode: request.barcode,
         name: request.name,
         description: request.description,
-        category: request.category,
+        category,
         subcategory: request.subcategory,
         brand: request.brand,
         unit_of_measure: request.unit_of_measure,
@@ -262,34 +390,202 @@ pub async fn update_product(
     Ok(product)
 }
 
-#[tauri::command]
-pub async fn delete_product(pool: State<'_, SqlitePool>, product_id: i64) -> Result<bool, String> {
-    let result = sqlx::query("UPDATE products SET is_active = 0 WHERE id = ?")
-        .bind(product_id)
-        .execute(pool.inner())
+/// Tables whose rows keep a product in the history; while any exist it can only be archived
+const PRODUCT_REFERENCES: &[(&str, &str)] = &[
+    ("sale_items", "product_id"),
+    ("return_items", "product_id"),
+    ("comprehensive_return_items", "product_id"),
+    ("purchase_order_items", "product_id"),
+    ("inventory_movements", "product_id"),
+    ("stock_adjustments", "product_id"),
+    ("stock_writeoffs", "product_id"),
+    ("stock_transfer_items", "product_id"),
+    ("product_lots", "product_id"),
+    ("discount_rules", "product_id"),
+    ("tax_rules", "product_id"),
+];
+
+/// Fail with NOT_FOUND unless the product belongs to the organization
+pub(crate) async fn require_product_in_organization(
+    pool: &SqlitePool,
+    product_id: i64,
+    organization_id: i64,
+) -> AppResult<()> {
+    let mut conn = pool.acquire().await?;
+    tenancy::require_in_organization(&mut conn, "products", product_id, organization_id, "Product")
         .await
-        .map_err(|e| e.to_string())?;
+}
+
+pub(crate) async fn archive_product_internal(
+    pool: &SqlitePool,
+    product_id: i64,
+    force: bool,
+    user_id: Option<i64>,
+) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+    let product: Option<(i64, i64)> = sqlx::query_as(
+        "SELECT COALESCE((SELECT SUM(current_stock) FROM inventory WHERE product_id = p.id), 0),
+                p.organization_id
+         FROM products p WHERE p.id = ?1",
+    )
+    .bind(product_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let (stock, organization_id) = product.ok_or_else(|| AppError::not_found("Product"))?;
+    if stock != 0 && !force {
+        return Err(AppError::Conflict {
+            message: format!(
+                "Product still has {} in stock; write it off first or archive with force",
+                stock
+            ),
+        });
+    }
+
+    sqlx::query(
+        "UPDATE products SET is_active = 0, archived_at = COALESCE(archived_at, CURRENT_TIMESTAMP),
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1",
+    )
+    .bind(product_id)
+    .execute(&mut *tx)
+    .await?;
+    audit::log_audit(
+        &mut tx,
+        organization_id,
+        user_id,
+        "archive_product",
+        "product",
+        Some(product_id),
+        Some(&serde_json::json!({ "is_active": true })),
+        Some(&serde_json::json!({ "is_active": false, "current_stock": stock })),
+    )
+    .await?;
+    enqueue_change(&mut tx, "product", product_id, SyncOperation::Update).await?;
+    tx.commit().await?;
+    Ok(())
+}
 
-    Ok(result.rows_affected() > 0)
+/// Take a product out of search and the default lists while keeping its history.
+/// Products with stock on hand need `force`.
+#[tauri::command]
+pub async fn archive_product(
+    pool: State<'_, SqlitePool>,
+    product_id: i64,
+    force: Option<bool>,
+    session_token: String,
+) -> Result<(), AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    require_product_in_organization(pool.inner(), product_id, session.organization_id).await?;
+    archive_product_internal(pool.inner(), product_id, force.unwrap_or(false), Some(session.user_id))
+        .await
 }
 
 #[tauri::command]
-pub async fn reactivate_product(pool: State<'_, SqlitePool>, product_id: i64) -> Result<bool, String> {
-    let result = sqlx::query("UPDATE products SET is_active = 1 WHERE id = ?")
+pub async fn restore_product(
+    pool: State<'_, SqlitePool>,
+    product_id: i64,
+    session_token: String,
+) -> Result<bool, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    let mut tx = pool.begin().await?;
+    tenancy::require_in_organization(&mut tx, "products", product_id, session.organization_id, "Product")
+        .await?;
+    let result = sqlx::query(
+        "UPDATE products SET is_active = 1, archived_at = NULL, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(product_id)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    audit::log_audit(
+        &mut tx,
+        session.organization_id,
+        Some(session.user_id),
+        "restore_product",
+        "product",
+        Some(product_id),
+        Some(&serde_json::json!({ "is_active": false })),
+        Some(&serde_json::json!({ "is_active": true })),
+    )
+    .await?;
+    enqueue_change(&mut tx, "product", product_id, SyncOperation::Update).await?;
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+pub(crate) async fn delete_product_internal(
+    pool: &SqlitePool,
+    product_id: i64,
+    user_id: Option<i64>,
+) -> AppResult<bool> {
+    let mut tx = pool.begin().await?;
+    let product: Option<(String, String, i64)> =
+        sqlx::query_as("SELECT sku, name, organization_id FROM products WHERE id = ?1")
+            .bind(product_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let Some((sku, name, organization_id)) = product else {
+        return Ok(false);
+    };
+    require_unreferenced(&mut tx, "Product", PRODUCT_REFERENCES, product_id).await?;
+
+    audit::log_audit(
+        &mut tx,
+        organization_id,
+        user_id,
+        "delete_product",
+        "product",
+        Some(product_id),
+        Some(&serde_json::json!({ "sku": sku, "name": name })),
+        None,
+    )
+    .await?;
+    // Queued first so the entry still carries the product's details
+    enqueue_change(&mut tx, "product", product_id, SyncOperation::Delete).await?;
+    for owned in ["inventory", "location_inventory", "product_variants", "product_cost_history"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE product_id = ?1", owned))
+            .bind(product_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("DELETE FROM products WHERE id = ?1")
         .bind(product_id)
-        .execute(pool.inner())
-        .await
-        .map_err(|e| e.to_string())?;
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
 
-    Ok(result.rows_affected() > 0)
+    Ok(true)
+}
+
+/// Delete a product for good. Only possible while nothing references it; products with
+/// history are archived instead.
+#[tauri::command]
+pub async fn delete_product(
+    pool: State<'_, SqlitePool>,
+    product_id: i64,
+    session_token: String,
+) -> Result<bool, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    require_product_in_organization(pool.inner(), product_id, session.organization_id).await?;
+    delete_product_internal(pool.inner(), product_id, Some(session.user_id)).await
 }
 
 #[tauri::command]
 pub async fn search_products(
     pool: State<'_, SqlitePool>,
     request: ProductSearchRequest,
-) -> Result<Vec<Product>, String> {
-    let mut query = String::from("SELECT * FROM products WHERE is_active = 1");
+    session_token: String,
+) -> Result<Vec<Product>, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let mut query = String::from("SELECT * FROM products WHERE is_active = 1 AND archived_at IS NULL");
+    query.push_str(&tenancy::organization_scope("organization_id", organization_id));
     let mut params: Vec<String> = Vec::new();
 
     if let Some(search_term) = &request.search_term {
@@ -340,33 +636,32 @@ pub async fn search_products(
 
     let rows = sql_query
         .fetch_all(pool.inner())
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
     let mut products = Vec::new();
     for row in rows {
         let product = Product {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            sku: row.try_get("sku").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            sku: row.try_get("sku")?,
             barcode: row.try_get("barcode").ok().flatten(),
-            name: row.try_get("name").map_err(|e| e.to_string())?,
+            name: row.try_get("name")?,
             description: row.try_get("description").ok().flatten(),
             category: row.try_get("category").ok().flatten(),
             subcategory: row.try_get("subcategory").ok().flatten(),
             brand: row.try_get("brand").ok().flatten(),
-            unit_of_measure: row.try_get("unit_of_measure").map_err(|e| e.to_string())?,
-            cost_price: row.try_get("cost_price").map_err(|e| e.to_string())?,
-            selling_price: row.try_get("selling_price").map_err(|e| e.to_string())?,
-            wholesale_price: row.try_get("wholesale_price").map_err(|e| e.to_string())?,
-            tax_rate: row.try_get("tax_rate").map_err(|e| e.to_string())?,
-            is_active: row.try_get("is_active").map_err(|e| e.to_string())?,
-            is_taxable: row.try_get("is_taxable").map_err(|e| e.to_string())?,
-            weight: row.try_get("weight").map_err(|e| e.to_string())?,
+            unit_of_measure: row.try_get("unit_of_measure")?,
+            cost_price: row.try_get("cost_price")?,
+            selling_price: row.try_get("selling_price")?,
+            wholesale_price: row.try_get("wholesale_price")?,
+            tax_rate: row.try_get("tax_rate")?,
+            is_active: row.try_get("is_active")?,
+            is_taxable: row.try_get("is_taxable")?,
+            weight: row.try_get("weight")?,
             dimensions: row.try_get("dimensions").ok().flatten(),
             supplier_info: row.try_get("supplier_info").ok().flatten(),
-            reorder_point: row.try_get("reorder_point").map_err(|e| e.to_string())?,
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-            updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
+            reorder_point: row.try_get("reorder_point")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
         };
         products.push(product);
     }
@@ -375,7 +670,18 @@ pub async fn search_products(
 }
 
 #[tauri::command]
-pub async fn get_products_with_stock(pool: State<'_, SqlitePool>) -> Result<Vec<ProductWithStock>, String> {
+pub async fn get_products_with_stock(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<Vec<ProductWithStock>, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_products_with_stock_internal(pool.inner(), organization_id).await
+}
+
+pub(crate) async fn get_products_with_stock_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+) -> AppResult<Vec<ProductWithStock>> {
     let rows = sqlx::query(
         "SELECT p.*, 
                 COALESCE(i.current_stock, 0) as current_stock,
@@ -384,85 +690,484 @@ pub async fn get_products_with_stock(pool: State<'_, SqlitePool>) -> Result<Vec<
                 COALESCE(i.reserved_stock, 0) as reserved_stock
          FROM products p
          LEFT JOIN inventory i ON p.id = i.product_id
-         WHERE p.is_active = 1
+         WHERE p.is_active = 1 AND p.organization_id = ?1
          ORDER BY p.name"
     )
-    .fetch_all(pool.inner())
-    .await
-    .map_err(|e| e.to_string())?;
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
 
-    let mut products = Vec::new();
-    for row in rows {
-        let product = ProductWithStock {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            sku: row.try_get("sku").map_err(|e| e.to_string())?,
-            barcode: row.try_get("barcode").ok().flatten(),
-            name: row.try_get("name").map_err(|e| e.to_string())?,
-            description: row.try_get("description").ok().flatten(),
-            category: row.try_get("category").ok().flatten(),
-            subcategory: row.try_get("subcategory").ok().flatten(),
-            brand: row.try_get("brand").ok().flatten(),
-            unit_of_measure: row.try_get("unit_of_measure").map_err(|e| e.to_string())?,
-            cost_price: row.try_get("cost_price").map_err(|e| e.to_string())?,
-            selling_price: row.try_get("selling_price").map_err(|e| e.to_string())?,
-            wholesale_price: row.try_get("wholesale_price").map_err(|e| e.to_string())?,
-            tax_rate: row.try_get("tax_rate").map_err(|e| e.to_string())?,
-            is_active: row.try_get("is_active").map_err(|e| e.to_string())?,
-            is_taxable: row.try_get("is_taxable").map_err(|e| e.to_string())?,
-            weight: row.try_get("weight").map_err(|e| e.to_string())?,
-            dimensions: row.try_get("dimensions").ok().flatten(),
-            supplier_info: row.try_get("supplier_info").ok().flatten(),
-            reorder_point: row.try_get("reorder_point").map_err(|e| e.to_string())?,
-            current_stock: row.try_get("current_stock").map_err(|e| e.to_string())?,
-            minimum_stock: row.try_get("minimum_stock").map_err(|e| e.to_string())?,
-            available_stock: row.try_get("available_stock").map_err(|e| e.to_string())?,
-            reserved_stock: row.try_get("reserved_stock").map_err(|e| e.to_string())?,
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-            updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
-        };
-        products.push(product);
+    rows.iter().map(product_with_stock_from_row).collect()
+}
+
+fn product_with_stock_from_row(row: &SqliteRow) -> AppResult<ProductWithStock> {
+    Ok(ProductWithStock {
+        id: row.try_get("id")?,
+        sku: row.try_get("sku")?,
+        barcode: row.try_get("barcode").ok().flatten(),
+        name: row.try_get("name")?,
+        description: row.try_get("description").ok().flatten(),
+        category: row.try_get("category").ok().flatten(),
+        subcategory: row.try_get("subcategory").ok().flatten(),
+        brand: row.try_get("brand").ok().flatten(),
+        unit_of_measure: row.try_get("unit_of_measure")?,
+        cost_price: row.try_get("cost_price")?,
+        selling_price: row.try_get("selling_price")?,
+        wholesale_price: row.try_get("wholesale_price")?,
+        tax_rate: row.try_get("tax_rate")?,
+        is_active: row.try_get("is_active")?,
+        is_taxable: row.try_get("is_taxable")?,
+        weight: row.try_get("weight")?,
+        dimensions: row.try_get("dimensions").ok().flatten(),
+        supplier_info: row.try_get("supplier_info").ok().flatten(),
+        reorder_point: row.try_get("reorder_point")?,
+        current_stock: row.try_get("current_stock")?,
+        minimum_stock: row.try_get("minimum_stock")?,
+        available_stock: row.try_get("available_stock")?,
+        reserved_stock: row.try_get("reserved_stock")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+/// Fetch several products with their stock in one query, in the order the ids were given.
+/// Unknown or inactive ids are skipped.
+pub(crate) async fn get_products_by_ids_internal(
+    pool: &SqlitePool,
+    ids: &[i64],
+    organization_id: i64,
+) -> AppResult<Vec<ProductWithStock>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
     }
 
-    Ok(products)
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let query = format!(
+        "SELECT p.*,
+                COALESCE(i.current_stock, 0) as current_stock,
+                COALESCE(i.minimum_stock, 0) as minimum_stock,
+                COALESCE(i.available_stock, 0) as available_stock,
+                COALESCE(i.reserved_stock, 0) as reserved_stock
+         FROM products p
+         LEFT JOIN inventory i ON p.id = i.product_id
+         WHERE p.is_active = 1 AND p.id IN ({}){}",
+        placeholders,
+        tenancy::organization_scope("p.organization_id", organization_id)
+    );
+    let mut q = sqlx::query(&query);
+    for id in ids {
+        q = q.bind(id);
+    }
+    let rows = q.fetch_all(pool).await?;
+
+    let mut found: HashMap<i64, ProductWithStock> = HashMap::with_capacity(rows.len());
+    for row in &rows {
+        let product = product_with_stock_from_row(row)?;
+        found.insert(product.id, product);
+    }
+    // Repeated ids come back once, at their first position
+    Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+}
+
+#[tauri::command]
+pub async fn get_products_by_ids(
+    pool: State<'_, SqlitePool>,
+    ids: Vec<i64>,
+    session_token: String,
+) -> Result<Vec<ProductWithStock>, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_products_by_ids_internal(pool.inner(), &ids, organization_id).await
+}
+
+/// Every cost price the product has had, newest first
+#[tauri::command]
+pub async fn get_cost_history(
+    pool: State<'_, SqlitePool>,
+    product_id: i64,
+    session_token: String,
+) -> Result<Vec<CostHistoryEntry>, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    require_product_in_organization(pool.inner(), product_id, organization_id).await?;
+    cost_history::get_cost_history(pool.inner(), product_id).await
+}
+
+/// Rebuild moving-average costs from stock movements, for a manager or admin.
+/// Returns the products whose cost was corrected.
+#[tauri::command]
+pub async fn recalculate_average_costs(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<Vec<RecalculatedCost>, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    let mut conn = pool.acquire().await?;
+    require_manager(&mut conn, session.user_id, "recalculate average costs").await?;
+    drop(conn);
+    cost_history::recalculate_average_costs(pool.inner(), session.organization_id, Some(session.user_id))
+        .await
+}
+
+/// A new selling price for one product
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceUpdate {
+    pub product_id: i64,
+    pub selling_price: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkPriceUpdateRequest {
+    pub updates: Vec<PriceUpdate>,
+    /// Manager PIN letting prices under the minimum margin through when the caller is
+    /// not a manager
+    pub approval: Option<ManagerApproval>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkPriceUpdateResult {
+    pub updated: usize,
+    /// Prices applied under the minimum margin with a manager's approval
+    pub below_margin: Vec<BelowCostItem>,
+    /// Products with no cost price, so their margin could not be checked
+    pub uncosted: Vec<UncostedItem>,
+}
+
+/// Who approved prices under the minimum margin: the user if they are a manager, else
+/// the manager whose PIN came with the request
+async fn authorize_below_margin(
+    pool: &SqlitePool,
+    approval: Option<&ManagerApproval>,
+    user_id: i64,
+    below_margin: &[BelowCostItem],
+) -> AppResult<i64> {
+    let mut conn = pool.acquire().await?;
+    if is_manager(&mut conn, user_id).await? {
+        return Ok(user_id);
+    }
+    drop(conn);
+
+    let approval = approval.ok_or_else(|| AppError::Validation {
+        field: Some("approval".to_string()),
+        message: format!(
+            "Manager approval required: {} priced below the minimum margin",
+            describe_below_cost(below_margin)
+        ),
+    })?;
+    let approver = pin::verify_user_pin(pool, &approval.username_or_badge, &approval.pin)
+        .await?
+        .ok_or_else(|| AppError::PermissionDenied {
+            message: "Manager PIN not recognised".to_string(),
+        })?;
+    let mut conn = pool.acquire().await?;
+    require_manager(&mut conn, approver, "approve prices below the minimum margin").await?;
+    Ok(approver)
+}
+
+pub(crate) async fn bulk_update_prices_internal(
+    pool: &SqlitePool,
+    request: BulkPriceUpdateRequest,
+    user_id: i64,
+    organization_id: i64,
+) -> AppResult<BulkPriceUpdateResult> {
+    for update in &request.updates {
+        for_product(update.product_id, validate_amount(update.selling_price, "selling_price"))?;
+    }
+
+    let mut conn = pool.acquire().await?;
+    for update in &request.updates {
+        tenancy::require_in_organization(
+            &mut conn,
+            "products",
+            update.product_id,
+            organization_id,
+            "Product",
+        )
+        .await?;
+    }
+    let store_min_margin = location_min_margin(&mut conn, None).await?;
+    let mut below_margin = Vec::new();
+    let mut uncosted = Vec::new();
+    for update in &request.updates {
+        let (below, no_cost) =
+            check_selling_price(&mut conn, update.product_id, update.selling_price, store_min_margin)
+                .await?;
+        below_margin.extend(below);
+        uncosted.extend(no_cost);
+    }
+    drop(conn);
+
+    let approved_by = if below_margin.is_empty() {
+        None
+    } else {
+        Some(authorize_below_margin(pool, request.approval.as_ref(), user_id, &below_margin).await?)
+    };
+
+    let mut tx = pool.begin().await?;
+
+    for update in &request.updates {
+        let previous_price: Option<Money> =
+            sqlx::query_scalar("SELECT selling_price FROM products WHERE id = ?1")
+                .bind(update.product_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        audit::log_audit(
+            &mut tx,
+            organization_id,
+            Some(user_id),
+            "bulk_update_prices",
+            "product",
+            Some(update.product_id),
+            Some(&serde_json::json!({ "selling_price": previous_price })),
+            Some(&serde_json::json!({
+                "selling_price": update.selling_price,
+                "approved_by": approved_by,
+            })),
+        )
+        .await?;
+        sqlx::query(
+            "UPDATE products SET selling_price = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        )
+        .bind(update.selling_price)
+        .bind(update.product_id)
+        .execute(&mut *tx)
+        .await?;
+        enqueue_change(&mut tx, "product", update.product_id, SyncOperation::Update).await?;
+    }
+    tx.commit().await?;
+
+    Ok(BulkPriceUpdateResult {
+        updated: request.updates.len(),
+        below_margin,
+        uncosted,
+    })
+}
+
+/// Reprice several products at once. Prices under cost plus the minimum margin need a
+/// manager's approval whatever the store's below-cost policy.
+#[tauri::command]
+pub async fn bulk_update_prices(
+    pool: State<'_, SqlitePool>,
+    request: BulkPriceUpdateRequest,
+    session_token: String,
+) -> Result<BulkPriceUpdateResult, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    bulk_update_prices_internal(pool.inner(), request, session.user_id, session.organization_id)
+        .await
 }
 
 #[tauri::command]
 pub async fn get_product_by_barcode(
     pool: State<'_, SqlitePool>,
     barcode: String,
-) -> Result<Option<Product>, String> {
-    let row = sqlx::query("SELECT * FROM products WHERE barcode = ? AND is_active = 1")
-        .bind(barcode)
-        .fetch_optional(pool.inner())
-        .await
-        .map_err(|e| e.to_string())?;
+    session_token: String,
+) -> Result<Option<Product>, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let row = sqlx::query(&format!(
+        "SELECT * FROM products WHERE barcode = ? AND is_active = 1{}",
+        tenancy::organization_scope("organization_id", organization_id)
+    ))
+    .bind(barcode)
+    .fetch_optional(pool.inner())
+    .await?;
 
     if let Some(row) = row {
         let product = Product {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            sku: row.try_get("sku").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            sku: row.try_get("sku")?,
             barcode: row.try_get("barcode").ok().flatten(),
-            name: row.try_get("name").map_err(|e| e.to_string())?,
+            name: row.try_get("name")?,
             description: row.try_get("description").ok().flatten(),
             category: row.try_get("category").ok().flatten(),
             subcategory: row.try_get("subcategory").ok().flatten(),
             brand: row.try_get("brand").ok().flatten(),
-            unit_of_measure: row.try_get("unit_of_measure").map_err(|e| e.to_string())?,
-            cost_price: row.try_get("cost_price").map_err(|e| e.to_string())?,
-            selling_price: row.try_get("selling_price").map_err(|e| e.to_string())?,
-            wholesale_price: row.try_get("wholesale_price").map_err(|e| e.to_string())?,
-            tax_rate: row.try_get("tax_rate").map_err(|e| e.to_string())?,
-            is_active: row.try_get("is_active").map_err(|e| e.to_string())?,
-            is_taxable: row.try_get("is_taxable").map_err(|e| e.to_string())?,
-            weight: row.try_get("weight").map_err(|e| e.to_string())?,
+            unit_of_measure: row.try_get("unit_of_measure")?,
+            cost_price: row.try_get("cost_price")?,
+            selling_price: row.try_get("selling_price")?,
+            wholesale_price: row.try_get("wholesale_price")?,
+            tax_rate: row.try_get("tax_rate")?,
+            is_active: row.try_get("is_active")?,
+            is_taxable: row.try_get("is_taxable")?,
+            weight: row.try_get("weight")?,
             dimensions: row.try_get("dimensions").ok().flatten(),
             supplier_info: row.try_get("supplier_info").ok().flatten(),
-            reorder_point: row.try_get("reorder_point").map_err(|e| e.to_string())?,
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-            updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
+            reorder_point: row.try_get("reorder_point")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
         };
         Ok(Some(product))
     } else {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+
+    fn product_request(sku: &str) -> CreateProductRequest {
+        CreateProductRequest {
+            sku: sku.to_string(),
+            barcode: None,
+            name: "Widget".to_string(),
+            description: None,
+            category: None,
+            subcategory: None,
+            brand: None,
+            unit_of_measure: "each".to_string(),
+            cost_price: 5.0,
+            selling_price: 10.0,
+            wholesale_price: 8.0,
+            tax_rate: 0.0,
+            is_taxable: false,
+            weight: 0.0,
+            dimensions: None,
+            supplier_info: None,
+            reorder_point: 5,
+            category_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_sku_is_conflict() {
+        let pool = test_pool().await;
+        create_product_internal(&pool, product_request("SKU-1"), DEFAULT_ORGANIZATION_ID)
+            .await
+            .unwrap();
+
+        let err = create_product_internal(&pool, product_request("SKU-1"), DEFAULT_ORGANIZATION_ID)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+    }
+
+    #[tokio::test]
+    async fn test_get_products_by_ids_keeps_input_order() {
+        let pool = test_pool().await;
+        let first = create_product_internal(&pool, product_request("SKU-1"), DEFAULT_ORGANIZATION_ID).await.unwrap();
+        let second = create_product_internal(&pool, product_request("SKU-2"), DEFAULT_ORGANIZATION_ID).await.unwrap();
+        sqlx::query("UPDATE inventory SET current_stock = 7 WHERE product_id = ?1")
+            .bind(second.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let products = get_products_by_ids_internal(&pool, &[second.id, 999, first.id, second.id], DEFAULT_ORGANIZATION_ID)
+            .await
+            .unwrap();
+        let ids: Vec<i64> = products.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![second.id, first.id]);
+        assert_eq!(products[0].current_stock, 7);
+
+        assert!(get_products_by_ids_internal(&pool, &[], DEFAULT_ORGANIZATION_ID).await.unwrap().is_empty());
+
+        let other_org: i64 = sqlx::query_scalar(
+            "INSERT INTO organizations (name, slug) VALUES ('Other Shop', 'other-shop') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(get_products_by_ids_internal(&pool, &[first.id], other_org).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_archiving_stocked_product_needs_force() {
+        let pool = test_pool().await;
+        let stocked = insert_test_product(&pool, "STOCKED", 10.0, 3).await;
+
+        let err = archive_product_internal(&pool, stocked, false, None).await.unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+        archive_product_internal(&pool, stocked, true, None).await.unwrap();
+        let (is_active, archived): (bool, bool) =
+            sqlx::query_as("SELECT is_active, archived_at IS NOT NULL FROM products WHERE id = ?1")
+                .bind(stocked)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!((is_active, archived), (false, true));
+
+        let err = archive_product_internal(&pool, 999, false, None).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_archived_product_keeps_its_sales_in_reports() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier").await;
+        let product = insert_test_product(&pool, "OLD", 10.0, 0).await;
+        let sale_id: i64 = sqlx::query_scalar(
+            "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id)
+             VALUES ('S-1', 20.0, 20.0, 'cash', ?1) RETURNING id",
+        )
+        .bind(cashier)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, line_total)
+             VALUES (?1, ?2, 2, 10.0, 20.0)",
+        )
+        .bind(sale_id)
+        .bind(product)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Sold once, so it can be archived but not deleted
+        let err = delete_product_internal(&pool, product, None).await.unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+        archive_product_internal(&pool, product, false, None).await.unwrap();
+
+        let report = crate::commands::reports::get_sales_report_internal(&pool, DEFAULT_ORGANIZATION_ID, None, None, None, false)
+            .await
+            .unwrap();
+        assert_eq!(report.total_sales, Money::from_minor(2000));
+        assert_eq!(report.total_transactions, 1);
+
+        let unsold = insert_test_product(&pool, "NEVER-SOLD", 5.0, 0).await;
+        assert!(delete_product_internal(&pool, unsold, None).await.unwrap());
+        assert!(!delete_product_internal(&pool, unsold, None).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_bulk_price_update_below_margin_needs_manager() {
+        let pool = test_pool().await;
+        // Cost 5.00 each, store minimum 20% so at least 6.00
+        let hammer = insert_test_product(&pool, "HAMMER", 10.0, 5).await;
+        let nails = insert_test_product(&pool, "NAILS", 10.0, 5).await;
+        let cashier = insert_test_user(&pool, "cashier").await;
+        let manager = insert_test_user(&pool, "manager").await;
+        sqlx::query("UPDATE locations SET min_margin_percent = 20 WHERE id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE users SET role = 'Manager' WHERE id = ?1")
+            .bind(manager)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let request = || BulkPriceUpdateRequest {
+            updates: vec![
+                PriceUpdate { product_id: hammer, selling_price: Money::from_major(7.0) },
+                PriceUpdate { product_id: nails, selling_price: Money::from_major(5.5) },
+            ],
+            approval: None,
+        };
+
+        let err = bulk_update_prices_internal(&pool, request(), cashier, DEFAULT_ORGANIZATION_ID).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert!(err.message().contains("NAILS at 5.50 (margin 10.0%, minimum 20%)"));
+        let unchanged: Money = sqlx::query_scalar("SELECT selling_price FROM products WHERE id = ?1")
+            .bind(hammer)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(unchanged, Money::from_major(10.0));
+
+        let result = bulk_update_prices_internal(&pool, request(), manager, DEFAULT_ORGANIZATION_ID).await.unwrap();
+        assert_eq!(result.updated, 2);
+        assert_eq!(result.below_margin.len(), 1);
+        assert_eq!(result.below_margin[0].minimum_unit_price, Money::from_major(6.0));
+    }
+}
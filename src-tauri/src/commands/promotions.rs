@@ -1,5 +1,8 @@
 // src-tauri/src/commands/promotions.rs
 use crate::models::*;
+use crate::money::Money;
+use crate::plans;
+use crate::promotions::{self, PromotionEvaluation, BUY_X_GET_Y};
 use sqlx::SqlitePool;
 use tauri::State;
 
@@ -39,35 +42,73 @@ pub async fn get_promotion(
     Ok(promotion)
 }
 
-#[tauri::command]
-pub async fn get_promotion_by_code(
-    pool: State<'_, SqlitePool>,
-    code: String,
+pub(crate) async fn get_promotion_by_code_internal(
+    pool: &SqlitePool,
+    code: &str,
 ) -> Result<Promotion, String> {
     let promotion = sqlx::query_as::<_, Promotion>(
         "SELECT * FROM promotions WHERE code = ? AND is_active = 1",
     )
-    .bind(&code)
-    .fetch_one(pool.inner())
+    .bind(code)
+    .fetch_one(pool)
     .await
     .map_err(|e| format!("Promotion not found: {}", e))?;
 
     Ok(promotion)
 }
 
+#[tauri::command]
+pub async fn get_promotion_by_code(
+    pool: State<'_, SqlitePool>,
+    code: String,
+) -> Result<Promotion, String> {
+    get_promotion_by_code_internal(pool.inner(), &code).await
+}
+
+/// Buy X Get Y parameters must be whole positive quantities and a percent in (0, 100]
+fn check_buy_x_get_y(
+    buy_quantity: Option<i32>,
+    get_quantity: Option<i32>,
+    get_discount_percent: Option<f64>,
+) -> Result<(), String> {
+    if buy_quantity.is_some_and(|quantity| quantity <= 0) {
+        return Err("Buy quantity must be greater than 0".to_string());
+    }
+    if get_quantity.is_some_and(|quantity| quantity <= 0) {
+        return Err("Get quantity must be greater than 0".to_string());
+    }
+    if get_discount_percent.is_some_and(|percent| !(percent > 0.0 && percent <= 100.0)) {
+        return Err("Get discount percent must be above 0 and at most 100".to_string());
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn create_promotion(
     pool: State<'_, SqlitePool>,
     request: CreatePromotionRequest,
     user_id: i64,
+    session_token: String,
 ) -> Result<Promotion, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    check_buy_x_get_y(
+        request.buy_quantity,
+        request.get_quantity,
+        request.get_discount_percent,
+    )?;
+    if request.discount_type == BUY_X_GET_Y
+        && (request.buy_quantity.is_none() || request.get_quantity.is_none())
+    {
+        return Err("Buy X Get Y promotions need a buy and a get quantity".to_string());
+    }
+
     let result = sqlx::query(
         "INSERT INTO promotions (
             code, name, description, discount_type, discount_value,
             min_purchase_amount, max_discount_amount, start_date, end_date,
             usage_limit, customer_type, applicable_products, applicable_categories,
-            created_by
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            created_by, buy_quantity, get_quantity, get_discount_percent, campaign_id
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&request.code)
     .bind(&request.name)
@@ -83,6 +124,10 @@ pub async fn create_promotion(
     .bind(&request.applicable_products)
     .bind(&request.applicable_categories)
     .bind(user_id)
+    .bind(request.buy_quantity)
+    .bind(request.get_quantity)
+    .bind(request.get_discount_percent)
+    .bind(request.campaign_id)
     .execute(pool.inner())
     .await
     .map_err(|e| format!("Failed to create promotion: {}", e))?;
@@ -96,7 +141,15 @@ pub async fn update_promotion(
     pool: State<'_, SqlitePool>,
     promotion_id: i64,
     request: UpdatePromotionRequest,
+    session_token: String,
 ) -> Result<Promotion, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    check_buy_x_get_y(
+        request.buy_quantity,
+        request.get_quantity,
+        request.get_discount_percent,
+    )?;
+
     sqlx::query(
         "UPDATE promotions SET
             code = COALESCE(?, code),
@@ -113,6 +166,10 @@ pub async fn update_promotion(
             applicable_products = COALESCE(?, applicable_products),
             applicable_categories = COALESCE(?, applicable_categories),
             is_active = COALESCE(?, is_active),
+            buy_quantity = COALESCE(?, buy_quantity),
+            get_quantity = COALESCE(?, get_quantity),
+            get_discount_percent = COALESCE(?, get_discount_percent),
+            campaign_id = COALESCE(?, campaign_id),
             updated_at = CURRENT_TIMESTAMP
          WHERE id = ?",
     )
@@ -130,6 +187,10 @@ pub async fn update_promotion(
     .bind(&request.applicable_products)
     .bind(&request.applicable_categories)
     .bind(&request.is_active)
+    .bind(request.buy_quantity)
+    .bind(request.get_quantity)
+    .bind(request.get_discount_percent)
+    .bind(request.campaign_id)
     .bind(promotion_id)
     .execute(pool.inner())
     .await
@@ -142,7 +203,9 @@ pub async fn update_promotion(
 pub async fn delete_promotion(
     pool: State<'_, SqlitePool>,
     promotion_id: i64,
+    session_token: String,
 ) -> Result<(), String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     sqlx::query("DELETE FROM promotions WHERE id = ?")
         .bind(promotion_id)
         .execute(pool.inner())
@@ -152,14 +215,13 @@ pub async fn delete_promotion(
     Ok(())
 }
 
-#[tauri::command]
-pub async fn validate_promotion(
-    pool: State<'_, SqlitePool>,
-    code: String,
+pub(crate) async fn validate_promotion_internal(
+    pool: &SqlitePool,
+    code: &str,
     purchase_amount: f64,
     customer_type: Option<String>,
 ) -> Result<Promotion, String> {
-    let promotion = get_promotion_by_code(pool.clone(), code).await?;
+    let promotion = get_promotion_by_code_internal(pool, code).await?;
 
     // Check if promotion is valid
     let now = chrono::Local::now().format("%Y-%m-%d").to_string();
@@ -204,3 +266,107 @@ pub async fn validate_promotion(
 
     Ok(promotion)
 }
+
+#[tauri::command]
+pub async fn validate_promotion(
+    pool: State<'_, SqlitePool>,
+    code: String,
+    purchase_amount: f64,
+    customer_type: Option<String>,
+) -> Result<Promotion, String> {
+    validate_promotion_internal(pool.inner(), &code, purchase_amount, customer_type).await
+}
+
+/// Discount a promotion gives on a purchase of `purchase_amount`
+fn promotion_discount(promotion: &Promotion, purchase_amount: f64) -> f64 {
+    let discount = match promotion.discount_type.as_str() {
+        "Percentage" => purchase_amount * promotion.discount_value / 100.0,
+        _ => promotion.discount_value,
+    };
+    let discount = match promotion.max_discount_amount {
+        Some(max) => discount.min(max),
+        None => discount,
+    };
+    Money::from_major(discount.min(purchase_amount)).to_major()
+}
+
+/// Record that `code` was used on `sale_id`, counting it against the promotion's usage
+/// limit. A coupon printed on a receipt cannot be used on the sale that issued it.
+pub(crate) async fn redeem_promotion_internal(
+    pool: &SqlitePool,
+    code: &str,
+    sale_id: i64,
+    customer_type: Option<String>,
+) -> Result<PromotionUsage, String> {
+    let total_amount: f64 = sqlx::query_scalar("SELECT total_amount FROM sales WHERE id = ?")
+        .bind(sale_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to fetch sale: {}", e))?
+        .ok_or_else(|| "Sale not found".to_string())?;
+
+    let promotion = validate_promotion_internal(pool, code, total_amount, customer_type).await?;
+    if promotion.source_sale_id == Some(sale_id) {
+        return Err("A coupon cannot be used on the sale that issued it".to_string());
+    }
+    let discount_amount = promotion_discount(&promotion, total_amount);
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    // Counted only while under the limit, so two tills cannot both use a single-use code
+    let counted = sqlx::query(
+        "UPDATE promotions SET usage_count = usage_count + 1, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ? AND (usage_limit IS NULL OR usage_count < usage_limit)",
+    )
+    .bind(promotion.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to redeem promotion: {}", e))?;
+    if counted.rows_affected() == 0 {
+        return Err("Promotion usage limit reached".to_string());
+    }
+    let usage_id = sqlx::query(
+        "INSERT INTO promotion_usage (promotion_id, sale_id, discount_amount) VALUES (?, ?, ?)",
+    )
+    .bind(promotion.id)
+    .bind(sale_id)
+    .bind(discount_amount)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to redeem promotion: {}", e))?
+    .last_insert_rowid();
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(PromotionUsage {
+        id: usage_id,
+        promotion_id: promotion.id,
+        code: promotion.code,
+        sale_id,
+        discount_amount,
+        source_sale_id: promotion.source_sale_id,
+    })
+}
+
+#[tauri::command]
+pub async fn redeem_promotion(
+    pool: State<'_, SqlitePool>,
+    code: String,
+    sale_id: i64,
+    customer_type: Option<String>,
+    session_token: String,
+) -> Result<PromotionUsage, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    redeem_promotion_internal(pool.inner(), &code, sale_id, customer_type).await
+}
+
+/// Discounts the entered promotion codes give on a cart, line by line, so the till can show
+/// them before the sale is completed
+#[tauri::command]
+pub async fn evaluate_promotions(
+    pool: State<'_, SqlitePool>,
+    codes: Vec<String>,
+    items: Vec<SaleItemRequest>,
+) -> Result<PromotionEvaluation, String> {
+    promotions::evaluate_cart(pool.inner(), &codes, &items)
+        .await
+        .map_err(|e| e.message())
+}
@@ -1,25 +1,27 @@
+use crate::commands::products::require_product_in_organization;
+use crate::cost_history::{receive_at_cost, CostSource};
+use crate::document_numbers::next_document_number;
+use crate::error::{AppError, AppResult};
 use crate::models::{
     CreatePurchaseOrderRequest, PurchaseOrder, PurchaseOrderItem, UpdatePurchaseOrderRequest,
 };
+use crate::plans;
+use crate::tenancy;
+use crate::validation::{for_product, validate_price, validate_quantity};
+use crate::webhooks::{self, EVENT_PURCHASE_ORDER_RECEIVED};
 use sqlx::{Row, SqlitePool};
 use tauri::{command, State};
 
-// Generate unique PO number
-async fn generate_po_number(pool: &SqlitePool) -> Result<String, String> {
-    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM purchase_orders")
-        .fetch_one(pool)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
-    Ok(format!("PO{:06}", count + 1))
-}
-
 #[command]
 pub async fn get_purchase_orders(
     pool: State<'_, SqlitePool>,
     status: Option<String>,
+    session_token: String,
 ) -> Result<Vec<PurchaseOrder>, String> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
     let mut query = String::from("SELECT * FROM purchase_orders WHERE 1=1");
+    query.push_str(&tenancy::organization_scope("organization_id", organization_id));
 
     if status.is_some() {
         query.push_str(" AND status = ?");
@@ -68,10 +70,13 @@ pub async fn get_purchase_orders(
 pub async fn get_purchase_order(
     pool: State<'_, SqlitePool>,
     po_id: i64,
+    session_token: String,
 ) -> Result<PurchaseOrder, String> {
     let pool_ref = pool.inner();
-    let row = sqlx::query("SELECT * FROM purchase_orders WHERE id = ?1")
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let row = sqlx::query("SELECT * FROM purchase_orders WHERE id = ?1 AND organization_id = ?2")
         .bind(po_id)
+        .bind(organization_id)
         .fetch_optional(pool_ref)
         .await
         .map_err(|e| format!("Database error: {}", e))?
@@ -102,8 +107,11 @@ pub async fn get_purchase_order(
 pub async fn get_purchase_order_items(
     pool: State<'_, SqlitePool>,
     po_id: i64,
+    session_token: String,
 ) -> Result<Vec<PurchaseOrderItem>, String> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
+    require_purchase_order_in_organization(pool_ref, po_id, organization_id).await?;
     let rows = sqlx::query("SELECT * FROM purchase_order_items WHERE purchase_order_id = ?1")
         .bind(po_id)
         .fetch_all(pool_ref)
@@ -131,14 +139,50 @@ pub async fn get_purchase_order_items(
     Ok(items)
 }
 
+/// Fail with NOT_FOUND unless the purchase order belongs to the organization
+async fn require_purchase_order_in_organization(
+    pool: &SqlitePool,
+    po_id: i64,
+    organization_id: i64,
+) -> AppResult<()> {
+    let mut conn = pool.acquire().await?;
+    tenancy::require_in_organization(
+        &mut conn,
+        "purchase_orders",
+        po_id,
+        organization_id,
+        "Purchase order",
+    )
+    .await
+}
+
+/// Fail with NOT_FOUND unless the supplier belongs to the organization
+async fn require_supplier_in_organization(
+    pool: &SqlitePool,
+    supplier_id: i64,
+    organization_id: i64,
+) -> AppResult<()> {
+    let mut conn = pool.acquire().await?;
+    tenancy::require_in_organization(&mut conn, "suppliers", supplier_id, organization_id, "Supplier")
+        .await
+}
+
 #[command]
 pub async fn create_purchase_order(
     pool: State<'_, SqlitePool>,
     request: CreatePurchaseOrderRequest,
     user_id: i64,
+    session_token: String,
 ) -> Result<PurchaseOrder, String> {
     let pool_ref = pool.inner();
-    let po_number = generate_po_number(pool_ref).await?;
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    require_supplier_in_organization(pool_ref, request.supplier_id, organization_id).await?;
+
+    for item in &request.items {
+        for_product(item.product_id, validate_quantity(item.quantity, "quantity"))?;
+        for_product(item.product_id, validate_price(item.unit_cost, "unit_cost"))?;
+        require_product_in_organization(pool_ref, item.product_id, organization_id).await?;
+    }
 
     // Calculate totals
     let subtotal: f64 = request
@@ -157,11 +201,14 @@ pub async fn create_purchase_order(
         .await
         .map_err(|e| format!("Transaction error: {}", e))?;
 
+    let po_number = next_document_number(&mut *tx, "purchase_order").await?;
+
     // Insert purchase order
     let result = sqlx::query(
         "INSERT INTO purchase_orders (po_number, supplier_id, order_date, expected_delivery_date,
-         subtotal, tax, shipping_cost, total_amount, payment_method, notes, created_by)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+         subtotal, tax, shipping_cost, total_amount, payment_method, notes, created_by,
+         organization_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
     )
     .bind(&po_number)
     .bind(request.supplier_id)
@@ -174,6 +221,7 @@ pub async fn create_purchase_order(
     .bind(&request.payment_method)
     .bind(&request.notes)
     .bind(user_id)
+    .bind(organization_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| format!("Database error: {}", e))?;
@@ -204,7 +252,7 @@ pub async fn create_purchase_order(
         .map_err(|e| format!("Transaction commit error: {}", e))?;
 
     // Fetch and return the created purchase order
-    get_purchase_order(pool, po_id).await
+    get_purchase_order(pool, po_id, session_token).await
 }
 
 #[command]
@@ -212,8 +260,14 @@ pub async fn update_purchase_order(
     pool: State<'_, SqlitePool>,
     po_id: i64,
     request: UpdatePurchaseOrderRequest,
+    session_token: String,
 ) -> Result<PurchaseOrder, String> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
+    require_purchase_order_in_organization(pool_ref, po_id, organization_id).await?;
+    if let Some(supplier_id) = request.supplier_id {
+        require_supplier_in_organization(pool_ref, supplier_id, organization_id).await?;
+    }
 
     let mut updates = Vec::new();
 
@@ -318,19 +372,22 @@ pub async fn update_purchase_order(
             .map_err(|e| format!("Database error: {}", e))?;
     }
 
-    get_purchase_order(pool, po_id).await
+    get_purchase_order(pool, po_id, session_token).await
 }
 
 #[command]
 pub async fn delete_purchase_order(
     pool: State<'_, SqlitePool>,
     po_id: i64,
+    session_token: String,
 ) -> Result<String, String> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     // Items will be deleted automatically due to ON DELETE CASCADE
-    let result = sqlx::query("DELETE FROM purchase_orders WHERE id = ?1")
+    let result = sqlx::query("DELETE FROM purchase_orders WHERE id = ?1 AND organization_id = ?2")
         .bind(po_id)
+        .bind(organization_id)
         .execute(pool_ref)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
@@ -347,8 +404,36 @@ pub async fn receive_purchase_order_item(
     pool: State<'_, SqlitePool>,
     item_id: i64,
     received_qty: i32,
+    session_token: String,
 ) -> Result<PurchaseOrderItem, String> {
-    let pool_ref = pool.inner();
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    receive_purchase_order_item_internal(pool.inner(), organization_id, item_id, received_qty).await
+}
+
+async fn receive_purchase_order_item_internal(
+    pool_ref: &SqlitePool,
+    organization_id: i64,
+    item_id: i64,
+    received_qty: i32,
+) -> Result<PurchaseOrderItem, String> {
+    // Items of another organization's orders look the same as missing ones
+    let product_id: i64 = sqlx::query_scalar(
+        "SELECT poi.product_id FROM purchase_order_items poi
+         JOIN purchase_orders po ON po.id = poi.purchase_order_id
+         WHERE poi.id = ?1 AND po.organization_id = ?2",
+    )
+    .bind(item_id)
+    .bind(organization_id)
+    .fetch_optional(pool_ref)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?
+    .ok_or("Purchase order item not found".to_string())?;
+    for_product(product_id, validate_quantity(received_qty, "received_qty"))?;
+
+    let mut tx = pool_ref
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
 
     // Update received quantity
     sqlx::query(
@@ -356,14 +441,14 @@ pub async fn receive_purchase_order_item(
     )
     .bind(received_qty)
     .bind(item_id)
-    .execute(pool_ref)
+    .execute(&mut *tx)
     .await
     .map_err(|e| format!("Database error: {}", e))?;
 
     // Get the updated item
     let row = sqlx::query("SELECT * FROM purchase_order_items WHERE id = ?1")
         .bind(item_id)
-        .fetch_optional(pool_ref)
+        .fetch_optional(&mut *tx)
         .await
         .map_err(|e| format!("Database error: {}", e))?
         .ok_or("Purchase order item not found".to_string())?;
@@ -383,6 +468,59 @@ pub async fn receive_purchase_order_item(
         notes: row.try_get("notes").ok(),
     };
 
+    // Receiving at a known unit cost moves the product's average cost before the stock
+    // lands; the old cost stays in its history
+    let unit_cost = (item.unit_cost > 0.0).then_some(item.unit_cost);
+    if let Some(unit_cost) = unit_cost {
+        receive_at_cost(
+            &mut tx,
+            item.product_id,
+            received_qty,
+            unit_cost,
+            CostSource::PurchaseOrder,
+            Some(item.purchase_order_id),
+            None,
+        )
+        .await?;
+    }
+
+    // Put the received units in stock with a receipt movement carrying their cost
+    let previous_stock: i32 =
+        sqlx::query_scalar("SELECT current_stock FROM inventory WHERE product_id = ?1")
+            .bind(item.product_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?
+            .ok_or_else(|| AppError::inventory_not_found(item.product_id))?;
+    sqlx::query(
+        "UPDATE inventory SET
+            current_stock = current_stock + ?1,
+            available_stock = available_stock + ?1,
+            last_updated = CURRENT_TIMESTAMP
+         WHERE product_id = ?2",
+    )
+    .bind(received_qty)
+    .bind(item.product_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update inventory: {}", e))?;
+    sqlx::query(
+        "INSERT INTO inventory_movements
+            (product_id, movement_type, quantity_change, previous_stock, new_stock,
+             reference_id, reference_type, notes, user_id, unit_cost)
+         VALUES (?1, 'receipt', ?2, ?3, ?4, ?5, 'purchase_order', ?6, NULL, ?7)",
+    )
+    .bind(item.product_id)
+    .bind(received_qty)
+    .bind(previous_stock)
+    .bind(previous_stock + received_qty)
+    .bind(item.purchase_order_id)
+    .bind(format!("Purchase order receipt: {} units @ ${:.2}", received_qty, item.unit_cost))
+    .bind(unit_cost)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create movement record: {}", e))?;
+
     // Check if all items are received and update PO status
     let po_id = item.purchase_order_id;
 
@@ -391,7 +529,7 @@ pub async fn receive_purchase_order_item(
         "SELECT quantity, received_quantity FROM purchase_order_items WHERE purchase_order_id = ?1",
     )
     .bind(po_id)
-    .fetch_all(pool_ref)
+    .fetch_all(&mut *tx)
     .await
     .map_err(|e| format!("Database error: {}", e))?;
 
@@ -408,16 +546,175 @@ pub async fn receive_purchase_order_item(
     if all_received {
         sqlx::query("UPDATE purchase_orders SET status = 'Received' WHERE id = ?1")
             .bind(po_id)
-            .execute(pool_ref)
+            .execute(&mut *tx)
             .await
             .map_err(|e| format!("Database error: {}", e))?;
     } else if partial_received {
         sqlx::query("UPDATE purchase_orders SET status = 'Partial' WHERE id = ?1")
             .bind(po_id)
-            .execute(pool_ref)
+            .execute(&mut *tx)
             .await
             .map_err(|e| format!("Database error: {}", e))?;
     }
 
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    webhooks::emit_event(
+        pool_ref,
+        EVENT_PURCHASE_ORDER_RECEIVED,
+        serde_json::json!({
+            "purchase_order_id": po_id,
+            "received_quantity": received_qty,
+            "all_received": all_received,
+            "item": &item,
+        }),
+    )
+    .await;
+
     Ok(item)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::sales::create_sale_internal;
+    use crate::models::{CreateSaleRequest, SaleItemRequest};
+    use crate::money::Money;
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+
+    async fn insert_po_item(pool: &SqlitePool, product_id: i64, quantity: i32) -> i64 {
+        let supplier_id = sqlx::query(
+            "INSERT INTO suppliers (supplier_number, company_name) VALUES ('SUP-1', 'Acme')",
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        let po_id = sqlx::query(
+            "INSERT INTO purchase_orders (po_number, supplier_id, order_date)
+             VALUES ('PO-1', ?1, '2024-01-01')",
+        )
+        .bind(supplier_id)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        sqlx::query(
+            "INSERT INTO purchase_order_items (purchase_order_id, product_id, quantity, unit_cost, total_cost)
+             VALUES (?1, ?2, ?3, 2.5, ?3 * 2.5)",
+        )
+        .bind(po_id)
+        .bind(product_id)
+        .bind(quantity)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+    }
+
+    #[tokio::test]
+    async fn test_receive_rejects_non_positive_quantities() {
+        let pool = test_pool().await;
+        let product_id = insert_test_product(&pool, "BOLT", 5.0, 0).await;
+        let item_id = insert_po_item(&pool, product_id, 10).await;
+
+        for received_qty in [0, -5] {
+            let err = receive_purchase_order_item_internal(
+                &pool,
+                DEFAULT_ORGANIZATION_ID,
+                item_id,
+                received_qty,
+            )
+            .await
+            .unwrap_err();
+            assert!(err.contains("VALIDATION_ERROR"));
+            assert!(err.contains(&format!("product {}", product_id)));
+        }
+
+        // Another organization cannot receive the order into its stock
+        let err = receive_purchase_order_item_internal(&pool, 2, item_id, 4).await.unwrap_err();
+        assert!(err.contains("not found"));
+
+        let item = receive_purchase_order_item_internal(&pool, DEFAULT_ORGANIZATION_ID, item_id, 4)
+            .await
+            .unwrap();
+        assert_eq!(item.received_quantity, 4);
+        assert_eq!(current_stock(&pool, product_id).await, 4);
+    }
+
+    #[tokio::test]
+    async fn test_receipts_average_the_cost_sales_are_costed_at() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let product_id = insert_test_product(&pool, "BOLT", 10.0, 0).await;
+        let first = insert_po_item(&pool, product_id, 10).await;
+        let second = sqlx::query(
+            "INSERT INTO purchase_order_items (purchase_order_id, product_id, quantity, unit_cost, total_cost)
+             SELECT purchase_order_id, product_id, 10, 7.0, 70.0 FROM purchase_order_items WHERE id = ?1",
+        )
+        .bind(first)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        sqlx::query("UPDATE purchase_order_items SET unit_cost = 5.0, total_cost = 50.0 WHERE id = ?1")
+            .bind(first)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        receive_purchase_order_item_internal(&pool, DEFAULT_ORGANIZATION_ID, first, 10).await.unwrap();
+        receive_purchase_order_item_internal(&pool, DEFAULT_ORGANIZATION_ID, second, 10).await.unwrap();
+        assert_eq!(current_stock(&pool, product_id).await, 20);
+
+        let unit_price = Money::from_major(10.0);
+        let request = CreateSaleRequest {
+            items: vec![SaleItemRequest {
+                product_id,
+                quantity: 4,
+                unit_price,
+                discount_amount: Money::ZERO,
+                line_total: unit_price.times(4),
+                measured_quantity: None,
+                price_override: None,
+                override_reason: None,
+            }],
+            subtotal: unit_price.times(4),
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: unit_price.times(4),
+            payment_method: "cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        };
+        let sale = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+        let line_cost: f64 = sqlx::query_scalar("SELECT cost_price FROM sale_items WHERE sale_id = ?1")
+            .bind(sale.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(line_cost, 6.0);
+
+        let costed: Vec<Option<f64>> = sqlx::query_scalar(
+            "SELECT unit_cost FROM inventory_movements WHERE product_id = ?1 AND movement_type = 'receipt' ORDER BY id",
+        )
+        .bind(product_id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(costed, vec![Some(5.0), Some(7.0)]);
+    }
+}
@@ -0,0 +1,344 @@
+// src-tauri/src/commands/receipt_footers.rs - Receipt footer rules and the coupons they issue
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use crate::receipt_footers::{
+    ReceiptFooterRule, COUPON_CODE_PLACEHOLDER, COUPON_DISCOUNT_TYPES, RECEIPT_FOOTER_RULE_COLUMNS,
+};
+use crate::validation::{validate_amount, validate_enum, validate_required};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use tauri::{command, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptFooterRuleRequest {
+    pub name: String,
+    pub message: String,
+    #[serde(default)]
+    pub min_amount: Money,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub coupon_discount_type: Option<String>,
+    pub coupon_discount_value: Option<f64>,
+    pub coupon_valid_days: Option<i32>,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// A coupon a footer rule printed, with the sale that issued it and the one it was used on
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct IssuedCoupon {
+    pub promotion_id: i64,
+    pub code: String,
+    pub footer_rule_id: i64,
+    pub issued_sale_id: Option<i64>,
+    pub issued_sale_number: Option<String>,
+    pub issued_at: String,
+    pub redeemed_sale_id: Option<i64>,
+    pub redeemed_sale_number: Option<String>,
+    pub redeemed_at: Option<String>,
+}
+
+fn validate_rule(request: &ReceiptFooterRuleRequest) -> AppResult<()> {
+    validate_required(&request.name, "name")?;
+    validate_required(&request.message, "message")?;
+    validate_amount(request.min_amount, "min_amount")?;
+    if let (Some(start), Some(end)) = (&request.start_date, &request.end_date) {
+        if start > end {
+            return Err(AppError::validation("end_date", "The end date cannot be before the start date"));
+        }
+    }
+    let Some(discount_type) = &request.coupon_discount_type else {
+        return Ok(());
+    };
+    validate_enum(&discount_type.as_str(), &COUPON_DISCOUNT_TYPES, "coupon_discount_type")?;
+    if !request.coupon_discount_value.is_some_and(|value| value > 0.0) {
+        return Err(AppError::validation("coupon_discount_value", "Coupons need a discount above zero"));
+    }
+    if request.coupon_valid_days.is_some_and(|days| days <= 0) {
+        return Err(AppError::validation("coupon_valid_days", "Coupons must be valid for at least a day"));
+    }
+    if !request.message.contains(COUPON_CODE_PLACEHOLDER) {
+        return Err(AppError::validation(
+            "message",
+            &format!("Coupon messages must include {} where the code goes", COUPON_CODE_PLACEHOLDER),
+        ));
+    }
+    Ok(())
+}
+
+async fn fetch_rule(pool: &SqlitePool, rule_id: i64) -> AppResult<ReceiptFooterRule> {
+    let query = format!("SELECT {} FROM receipt_footer_rules WHERE id = ?1", RECEIPT_FOOTER_RULE_COLUMNS);
+    sqlx::query_as::<_, ReceiptFooterRule>(&query)
+        .bind(rule_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("Receipt footer rule"))
+}
+
+#[command]
+pub async fn get_receipt_footer_rules(
+    pool: State<'_, SqlitePool>,
+    active_only: Option<bool>,
+) -> Result<Vec<ReceiptFooterRule>, AppError> {
+    let query = format!(
+        "SELECT {} FROM receipt_footer_rules WHERE (?1 = 0 OR is_active = 1) ORDER BY priority, id",
+        RECEIPT_FOOTER_RULE_COLUMNS
+    );
+    Ok(sqlx::query_as::<_, ReceiptFooterRule>(&query)
+        .bind(active_only.unwrap_or(false))
+        .fetch_all(pool.inner())
+        .await?)
+}
+
+pub(crate) async fn create_receipt_footer_rule_internal(
+    pool: &SqlitePool,
+    request: &ReceiptFooterRuleRequest,
+) -> AppResult<ReceiptFooterRule> {
+    validate_rule(request)?;
+    let coupon = request.coupon_discount_type.is_some();
+    let rule_id = sqlx::query(
+        "INSERT INTO receipt_footer_rules (name, message, min_amount, start_date, end_date,
+                                           coupon_discount_type, coupon_discount_value,
+                                           coupon_valid_days, priority)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )
+    .bind(request.name.trim())
+    .bind(request.message.trim())
+    .bind(request.min_amount)
+    .bind(&request.start_date)
+    .bind(&request.end_date)
+    .bind(&request.coupon_discount_type)
+    .bind(request.coupon_discount_value.filter(|_| coupon))
+    .bind(request.coupon_valid_days.filter(|_| coupon))
+    .bind(request.priority)
+    .execute(pool)
+    .await?
+    .last_insert_rowid();
+
+    fetch_rule(pool, rule_id).await
+}
+
+#[command]
+pub async fn create_receipt_footer_rule(
+    pool: State<'_, SqlitePool>,
+    request: ReceiptFooterRuleRequest,
+) -> Result<ReceiptFooterRule, AppError> {
+    create_receipt_footer_rule_internal(pool.inner(), &request).await
+}
+
+#[command]
+pub async fn update_receipt_footer_rule(
+    pool: State<'_, SqlitePool>,
+    rule_id: i64,
+    request: ReceiptFooterRuleRequest,
+) -> Result<ReceiptFooterRule, AppError> {
+    validate_rule(&request)?;
+    let coupon = request.coupon_discount_type.is_some();
+    let updated = sqlx::query(
+        "UPDATE receipt_footer_rules SET
+            name = ?1, message = ?2, min_amount = ?3, start_date = ?4, end_date = ?5,
+            coupon_discount_type = ?6, coupon_discount_value = ?7, coupon_valid_days = ?8,
+            priority = ?9, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?10",
+    )
+    .bind(request.name.trim())
+    .bind(request.message.trim())
+    .bind(request.min_amount)
+    .bind(&request.start_date)
+    .bind(&request.end_date)
+    .bind(&request.coupon_discount_type)
+    .bind(request.coupon_discount_value.filter(|_| coupon))
+    .bind(request.coupon_valid_days.filter(|_| coupon))
+    .bind(request.priority)
+    .bind(rule_id)
+    .execute(pool.inner())
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::not_found("Receipt footer rule"));
+    }
+
+    fetch_rule(pool.inner(), rule_id).await
+}
+
+#[command]
+pub async fn set_receipt_footer_rule_active(
+    pool: State<'_, SqlitePool>,
+    rule_id: i64,
+    is_active: bool,
+) -> Result<ReceiptFooterRule, AppError> {
+    let updated = sqlx::query(
+        "UPDATE receipt_footer_rules SET is_active = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+    )
+    .bind(is_active)
+    .bind(rule_id)
+    .execute(pool.inner())
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::not_found("Receipt footer rule"));
+    }
+
+    fetch_rule(pool.inner(), rule_id).await
+}
+
+/// Coupons already issued keep working; they just lose the link to the rule
+#[command]
+pub async fn delete_receipt_footer_rule(pool: State<'_, SqlitePool>, rule_id: i64) -> Result<(), AppError> {
+    let deleted = sqlx::query("DELETE FROM receipt_footer_rules WHERE id = ?1")
+        .bind(rule_id)
+        .execute(pool.inner())
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::not_found("Receipt footer rule"));
+    }
+    Ok(())
+}
+
+pub(crate) async fn get_issued_coupons_internal(
+    pool: &SqlitePool,
+    rule_id: Option<i64>,
+) -> AppResult<Vec<IssuedCoupon>> {
+    Ok(sqlx::query_as::<_, IssuedCoupon>(
+        "SELECT p.id as promotion_id, p.code, p.footer_rule_id,
+                p.source_sale_id as issued_sale_id, issued.sale_number as issued_sale_number,
+                p.created_at as issued_at,
+                u.sale_id as redeemed_sale_id, redeemed.sale_number as redeemed_sale_number,
+                u.used_at as redeemed_at
+         FROM promotions p
+         LEFT JOIN sales issued ON issued.id = p.source_sale_id
+         LEFT JOIN promotion_usage u ON u.id = (
+             SELECT MIN(pu.id) FROM promotion_usage pu WHERE pu.promotion_id = p.id
+         )
+         LEFT JOIN sales redeemed ON redeemed.id = u.sale_id
+         WHERE p.footer_rule_id IS NOT NULL AND (?1 IS NULL OR p.footer_rule_id = ?1)
+         ORDER BY p.id DESC",
+    )
+    .bind(rule_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Coupons printed on receipts, newest first, with where each came from and was used
+#[command]
+pub async fn get_issued_coupons(
+    pool: State<'_, SqlitePool>,
+    rule_id: Option<i64>,
+) -> Result<Vec<IssuedCoupon>, AppError> {
+    get_issued_coupons_internal(pool.inner(), rule_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::promotions::{redeem_promotion_internal, validate_promotion_internal};
+    use crate::commands::sales::create_sale_internal;
+    use crate::models::{CreateSaleRequest, SaleItemRequest};
+    use crate::receipt::render_sale_receipt;
+    use crate::receipt_footers::footer_messages;
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    fn sale(product_id: i64, quantity: i32) -> CreateSaleRequest {
+        let line_total = Money::from_major(10.0).times(quantity);
+        CreateSaleRequest {
+            items: vec![SaleItemRequest {
+                product_id,
+                quantity,
+                unit_price: Money::from_major(10.0),
+                discount_amount: Money::ZERO,
+                line_total,
+                measured_quantity: None,
+                price_override: None,
+                override_reason: None,
+            }],
+            subtotal: line_total,
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: line_total,
+            payment_method: "cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        }
+    }
+
+    fn rule(name: &str, message: &str, min_amount: f64) -> ReceiptFooterRuleRequest {
+        ReceiptFooterRuleRequest {
+            name: name.to_string(),
+            message: message.to_string(),
+            min_amount: Money::from_major(min_amount),
+            start_date: None,
+            end_date: None,
+            coupon_discount_type: None,
+            coupon_discount_value: None,
+            coupon_valid_days: None,
+            priority: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_footer_rules_print_by_threshold_and_issue_redeemable_coupons() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+
+        create_receipt_footer_rule_internal(&pool, &rule("Thanks", "Thanks for shopping", 0.0))
+            .await
+            .unwrap();
+        let mut expired = rule("Spring", "Spring sale on now", 0.0);
+        expired.end_date = Some("2000-03-31".to_string());
+        create_receipt_footer_rule_internal(&pool, &expired).await.unwrap();
+        let mut coupon = rule("Come back", "10% off your next purchase, code {code}", 50.0);
+        coupon.coupon_discount_type = Some("Percentage".to_string());
+        coupon.coupon_discount_value = Some(10.0);
+        coupon.coupon_valid_days = Some(30);
+        coupon.priority = 1;
+        let coupon_rule = create_receipt_footer_rule_internal(&pool, &coupon).await.unwrap();
+
+        // 10.00 is under the coupon's 50.00 threshold
+        let small = create_sale_internal(&pool, sale(widget, 1), cashier, None).await.unwrap();
+        assert_eq!(footer_messages(&pool, small.id).await.unwrap(), vec!["Thanks for shopping"]);
+
+        let big = create_sale_internal(&pool, sale(widget, 6), cashier, None).await.unwrap();
+        let messages = footer_messages(&pool, big.id).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        let code = messages[1].strip_prefix("10% off your next purchase, code ").unwrap().to_string();
+        assert!(code.starts_with("RC-"));
+
+        // Reprints show the same code rather than issuing another
+        let receipt = render_sale_receipt(&pool, big.id, None).await.unwrap();
+        assert!(receipt.contains(&code));
+        let issued: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM promotions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(issued, 1);
+
+        // The code works through the promotion engine, once, and not on its own sale
+        let promotion = validate_promotion_internal(&pool, &code, 30.0, None).await.unwrap();
+        assert_eq!((promotion.discount_type.as_str(), promotion.usage_limit), ("Percentage", Some(1)));
+        assert_eq!(promotion.source_sale_id, Some(big.id));
+        assert!(redeem_promotion_internal(&pool, &code, big.id, None).await.is_err());
+        let usage = redeem_promotion_internal(&pool, &code, small.id, None).await.unwrap();
+        assert_eq!(usage.discount_amount, 1.0);
+        assert_eq!(usage.source_sale_id, Some(big.id));
+        let err = redeem_promotion_internal(&pool, &code, small.id, None).await.unwrap_err();
+        assert_eq!(err, "Promotion usage limit reached");
+
+        let coupons = get_issued_coupons_internal(&pool, Some(coupon_rule.id)).await.unwrap();
+        assert_eq!(coupons.len(), 1);
+        assert_eq!(coupons[0].issued_sale_number.as_deref(), Some(big.sale_number.as_str()));
+        assert_eq!(coupons[0].redeemed_sale_id, Some(small.id));
+
+        coupon.message = "10% off your next purchase".to_string();
+        let err = create_receipt_footer_rule_internal(&pool, &coupon).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+}
@@ -0,0 +1,379 @@
+// src-tauri/src/commands/receipt_templates.rs - Receipt template management and preview
+use crate::currency;
+use crate::error::{AppError, AppResult};
+use crate::models::{CreateReceiptTemplateRequest, ReceiptTemplate};
+use crate::receipt;
+use sqlx::sqlite::SqliteConnection;
+use sqlx::SqlitePool;
+use tauri::{command, State};
+
+const PRINTER_TYPES: &[&str] = &["thermal", "inkjet", "laser"];
+
+const TEMPLATE_COLUMNS: &str = "id, name, template_type, printer_type, template_content, is_default,
+    paper_width, font_size, created_at, updated_at";
+
+fn validate_template(request: &CreateReceiptTemplateRequest) -> AppResult<()> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::validation("name", "Template name is required"));
+    }
+    if receipt::known_placeholders(&request.template_type).is_empty() {
+        return Err(AppError::validation(
+            "template_type",
+            &format!(
+                "Unknown template type '{}', expected sale, return, void or warehouse",
+                request.template_type
+            ),
+        ));
+    }
+    if !PRINTER_TYPES.contains(&request.printer_type.as_str()) {
+        return Err(AppError::validation(
+            "printer_type",
+            &format!(
+                "Unknown printer type '{}', expected thermal, inkjet or laser",
+                request.printer_type
+            ),
+        ));
+    }
+    let unknown = receipt::unknown_placeholders(&request.template_type, &request.template_content);
+    if !unknown.is_empty() {
+        let unknown: Vec<String> = unknown.iter().map(|name| format!("{{{{{}}}}}", name)).collect();
+        return Err(AppError::validation(
+            "template_content",
+            &format!("Unknown placeholders: {}", unknown.join(", ")),
+        ));
+    }
+    Ok(())
+}
+
+async fn fetch_template(conn: &mut SqliteConnection, template_id: i64) -> AppResult<ReceiptTemplate> {
+    sqlx::query_as::<_, ReceiptTemplate>(&format!(
+        "SELECT {} FROM receipt_templates WHERE id = ?1",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(template_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::not_found("Receipt template"))
+}
+
+/// Whether a template other than `exclude_id` is the default for the type and printer
+async fn has_other_default(
+    conn: &mut SqliteConnection,
+    template_type: &str,
+    printer_type: &str,
+    exclude_id: i64,
+) -> AppResult<bool> {
+    let existing: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM receipt_templates
+         WHERE template_type = ?1 AND printer_type = ?2 AND is_default = 1 AND id != ?3",
+    )
+    .bind(template_type)
+    .bind(printer_type)
+    .bind(exclude_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(existing.is_some())
+}
+
+/// Make the template the only default of its type and printer
+async fn make_default(conn: &mut SqliteConnection, template: &ReceiptTemplate) -> AppResult<()> {
+    sqlx::query(
+        "UPDATE receipt_templates SET is_default = 0
+         WHERE template_type = ?1 AND printer_type = ?2 AND id != ?3",
+    )
+    .bind(&template.template_type)
+    .bind(&template.printer_type)
+    .bind(template.id)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query("UPDATE receipt_templates SET is_default = 1, updated_at = CURRENT_TIMESTAMP WHERE id = ?1")
+        .bind(template.id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn create_template_internal(
+    pool: &SqlitePool,
+    request: &CreateReceiptTemplateRequest,
+) -> AppResult<ReceiptTemplate> {
+    validate_template(request)?;
+
+    let mut tx = pool.begin().await?;
+    let template_id: i64 = sqlx::query_scalar(
+        "INSERT INTO receipt_templates (name, template_type, printer_type, template_content, is_default, paper_width, font_size)
+         VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6)
+         RETURNING id",
+    )
+    .bind(request.name.trim())
+    .bind(&request.template_type)
+    .bind(&request.printer_type)
+    .bind(&request.template_content)
+    .bind(request.paper_width)
+    .bind(request.font_size)
+    .fetch_one(&mut *tx)
+    .await?;
+    let template = fetch_template(&mut tx, template_id).await?;
+    // The first template of a type and printer is its default
+    if request.is_default
+        || !has_other_default(&mut tx, &template.template_type, &template.printer_type, template_id).await?
+    {
+        make_default(&mut tx, &template).await?;
+    }
+    let template = fetch_template(&mut tx, template_id).await?;
+    tx.commit().await?;
+    Ok(template)
+}
+
+#[command]
+pub async fn create_template(
+    pool: State<'_, SqlitePool>,
+    request: CreateReceiptTemplateRequest,
+) -> Result<ReceiptTemplate, String> {
+    Ok(create_template_internal(pool.inner(), &request).await?)
+}
+
+pub(crate) async fn update_template_internal(
+    pool: &SqlitePool,
+    template_id: i64,
+    request: &CreateReceiptTemplateRequest,
+) -> AppResult<ReceiptTemplate> {
+    validate_template(request)?;
+
+    let mut tx = pool.begin().await?;
+    let current = fetch_template(&mut tx, template_id).await?;
+    let moves_slot =
+        current.template_type != request.template_type || current.printer_type != request.printer_type;
+    if current.is_default && (moves_slot || !request.is_default) {
+        return Err(AppError::validation(
+            "is_default",
+            "This is the default template; make another template the default first",
+        ));
+    }
+
+    sqlx::query(
+        "UPDATE receipt_templates SET
+            name = ?1, template_type = ?2, printer_type = ?3, template_content = ?4,
+            paper_width = ?5, font_size = ?6, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?7",
+    )
+    .bind(request.name.trim())
+    .bind(&request.template_type)
+    .bind(&request.printer_type)
+    .bind(&request.template_content)
+    .bind(request.paper_width)
+    .bind(request.font_size)
+    .bind(template_id)
+    .execute(&mut *tx)
+    .await?;
+    let template = fetch_template(&mut tx, template_id).await?;
+    if request.is_default
+        || !has_other_default(&mut tx, &template.template_type, &template.printer_type, template_id).await?
+    {
+        make_default(&mut tx, &template).await?;
+    }
+    let template = fetch_template(&mut tx, template_id).await?;
+    tx.commit().await?;
+    Ok(template)
+}
+
+#[command]
+pub async fn update_template(
+    pool: State<'_, SqlitePool>,
+    template_id: i64,
+    request: CreateReceiptTemplateRequest,
+) -> Result<ReceiptTemplate, String> {
+    Ok(update_template_internal(pool.inner(), template_id, &request).await?)
+}
+
+pub(crate) async fn set_default_template_internal(pool: &SqlitePool, template_id: i64) -> AppResult<ReceiptTemplate> {
+    let mut tx = pool.begin().await?;
+    let template = fetch_template(&mut tx, template_id).await?;
+    make_default(&mut tx, &template).await?;
+    let template = fetch_template(&mut tx, template_id).await?;
+    tx.commit().await?;
+    Ok(template)
+}
+
+/// Make a template the default of its type and printer, replacing the current default
+#[command]
+pub async fn set_default_template(
+    pool: State<'_, SqlitePool>,
+    template_id: i64,
+) -> Result<ReceiptTemplate, String> {
+    Ok(set_default_template_internal(pool.inner(), template_id).await?)
+}
+
+#[command]
+pub async fn delete_template(
+    pool: State<'_, SqlitePool>,
+    template_id: i64,
+) -> Result<bool, String> {
+    let mut conn = pool.inner().acquire().await.map_err(AppError::from)?;
+    let template = fetch_template(&mut conn, template_id).await?;
+    if template.is_default {
+        return Err("Cannot delete default template".to_string());
+    }
+
+    sqlx::query("DELETE FROM receipt_templates WHERE id = ?1")
+        .bind(template_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to delete template: {}", e))?;
+
+    Ok(true)
+}
+
+#[command]
+pub async fn get_templates(
+    pool: State<'_, SqlitePool>,
+    template_type: Option<String>,
+    printer_type: Option<String>,
+) -> Result<Vec<ReceiptTemplate>, String> {
+    let templates = sqlx::query_as::<_, ReceiptTemplate>(&format!(
+        "SELECT {} FROM receipt_templates
+         WHERE (?1 IS NULL OR template_type = ?1) AND (?2 IS NULL OR printer_type = ?2)
+         ORDER BY template_type, is_default DESC, name ASC",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(template_type)
+    .bind(printer_type)
+    .fetch_all(pool.inner())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(templates)
+}
+
+#[command]
+pub async fn get_default_template(
+    pool: State<'_, SqlitePool>,
+    template_type: String,
+    printer_type: String,
+) -> Result<Option<ReceiptTemplate>, String> {
+    let template = sqlx::query_as::<_, ReceiptTemplate>(&format!(
+        "SELECT {} FROM receipt_templates WHERE template_type = ?1 AND printer_type = ?2 AND is_default = 1",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(template_type)
+    .bind(printer_type)
+    .fetch_optional(pool.inner())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(template)
+}
+
+/// Placeholders in the content that templates of the type cannot fill; empty when it is valid
+#[command]
+pub fn validate_receipt_template(template_type: String, template_content: String) -> Result<Vec<String>, String> {
+    Ok(receipt::unknown_placeholders(&template_type, &template_content))
+}
+
+/// A saved template, or unsaved edits to one, rendered with sample data
+#[command]
+pub async fn preview_receipt_template(
+    pool: State<'_, SqlitePool>,
+    template_id: Option<i64>,
+    template: Option<CreateReceiptTemplateRequest>,
+) -> Result<String, AppError> {
+    let template = match (template, template_id) {
+        (Some(template), _) => template,
+        (None, Some(template_id)) => {
+            let mut conn = pool.inner().acquire().await?;
+            let saved = fetch_template(&mut conn, template_id).await?;
+            CreateReceiptTemplateRequest {
+                name: saved.name,
+                template_type: saved.template_type,
+                printer_type: saved.printer_type,
+                template_content: saved.template_content,
+                is_default: saved.is_default,
+                paper_width: saved.paper_width,
+                font_size: saved.font_size,
+            }
+        }
+        (None, None) => {
+            return Err(AppError::validation(
+                "template_id",
+                "Give a template id or template content to preview",
+            ))
+        }
+    };
+    let currency = currency::store_currency(pool.inner()).await?;
+    Ok(receipt::render_sample(
+        &template.template_type,
+        &template.template_content,
+        template.paper_width,
+        template.font_size,
+        &currency,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_pool;
+
+    fn request(name: &str, content: &str, is_default: bool) -> CreateReceiptTemplateRequest {
+        CreateReceiptTemplateRequest {
+            name: name.to_string(),
+            template_type: "sale".to_string(),
+            printer_type: "laser".to_string(),
+            template_content: content.to_string(),
+            is_default,
+            paper_width: 80,
+            font_size: 12,
+        }
+    }
+
+    async fn defaults(pool: &SqlitePool) -> Vec<String> {
+        sqlx::query_scalar(
+            "SELECT name FROM receipt_templates
+             WHERE template_type = 'sale' AND printer_type = 'laser' AND is_default = 1",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_type_and_printer_keep_exactly_one_default() {
+        let pool = test_pool().await;
+        let first = create_template_internal(&pool, &request("A4", "{{items}}", false)).await.unwrap();
+        assert!(first.is_default, "the first laser sale template becomes the default");
+        let second = create_template_internal(&pool, &request("Letter", "{{items}}", true)).await.unwrap();
+        assert_eq!(defaults(&pool).await, vec!["Letter".to_string()]);
+
+        let err = update_template_internal(&pool, second.id, &request("Letter", "{{total_amount}}", false))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+
+        set_default_template_internal(&pool, first.id).await.unwrap();
+        assert_eq!(defaults(&pool).await, vec!["A4".to_string()]);
+        // The database refuses a second default even behind the commands' back
+        assert!(sqlx::query("UPDATE receipt_templates SET is_default = 1 WHERE id = ?1")
+            .bind(second.id)
+            .execute(&pool)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_placeholders_are_reported() {
+        let pool = test_pool().await;
+        let content = "{{store_name}}\\n{{loyalty_points}} {{items}} {{return_number}} {{loyalty_points}}";
+        assert_eq!(
+            receipt::unknown_placeholders("sale", content),
+            vec!["loyalty_points".to_string(), "return_number".to_string()]
+        );
+        assert!(receipt::unknown_placeholders("return", "{{return_number}} {{user_name}}").is_empty());
+
+        let err = create_template_internal(&pool, &request("Bad", content, false)).await.unwrap_err();
+        assert!(err.message().contains("{{loyalty_points}}, {{return_number}}"));
+
+        let currency = currency::store_currency(&pool).await.unwrap();
+        let preview = receipt::render_sample("sale", "{{store_name}}\\n{{total_amount}}", 80, 12, &currency);
+        assert_eq!(preview, "Sample Hardware\n$293.68");
+    }
+}
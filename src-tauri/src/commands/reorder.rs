@@ -0,0 +1,944 @@
+use crate::csv_export;
+use crate::error::{AppError, AppResult};
+use crate::location_stock::{location_levels_join, location_stock_sql};
+use crate::money::Money;
+use crate::plans;
+use crate::tenancy;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use tauri::{command, State};
+
+/// Purchase order statuses whose outstanding lines are still expected to arrive
+const OPEN_PO_STATUSES: &str = "'Draft', 'Sent', 'Confirmed', 'Partial'";
+
+/// A product at or below its reorder point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorderItem {
+    pub product_id: i64,
+    pub sku: String,
+    pub name: String,
+    pub current_stock: i32,
+    pub reorder_point: i32,
+    pub maximum_stock: i32,
+    /// Quantity ordered on open purchase orders but not yet received
+    pub on_order_quantity: i32,
+    /// PO numbers of the open orders counted in `on_order_quantity`
+    pub open_po_numbers: Vec<String>,
+    /// An open purchase order already covers this product, so it should not be ordered again
+    pub covered_by_open_po: bool,
+    pub suggested_quantity: i32,
+    /// Unit cost on the latest purchase order line, or the product cost price
+    pub last_cost: Money,
+    pub estimated_cost: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorderSupplierGroup {
+    /// `None` for products never ordered from a supplier
+    pub supplier_id: Option<i64>,
+    pub supplier_name: String,
+    pub items: Vec<ReorderItem>,
+    pub estimated_spend: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorderReport {
+    pub groups: Vec<ReorderSupplierGroup>,
+    pub total_estimated_spend: Money,
+    pub generated_at: String,
+}
+
+/// Stock to order up to: the maximum stock when set above the reorder point,
+/// otherwise twice the reorder point
+fn target_stock(reorder_point: i32, maximum_stock: i32) -> i32 {
+    if maximum_stock > reorder_point {
+        maximum_stock
+    } else {
+        reorder_point * 2
+    }
+}
+
+/// Products at or below their reorder point, grouped by the supplier they were last
+/// ordered from. Quantity already on open purchase orders is deducted from the suggestion.
+/// Given a location, its stock is compared with the thresholds set there, falling back to
+/// the product-wide ones.
+pub async fn get_reorder_report_internal(
+    pool: &SqlitePool,
+    location_id: Option<i64>,
+) -> AppResult<ReorderReport> {
+    let (current_stock, reorder_point, maximum_stock, levels_join) = match location_id {
+        Some(_) => (
+            location_stock_sql("i.product_id", "?1"),
+            "COALESCE(lv.reorder_point, p.reorder_point, 0)",
+            "COALESCE(lv.maximum_stock, i.maximum_stock, 0)",
+            location_levels_join("?1"),
+        ),
+        None => (
+            "COALESCE(i.current_stock, 0)".to_string(),
+            "COALESCE(p.reorder_point, 0)",
+            "COALESCE(i.maximum_stock, 0)",
+            String::new(),
+        ),
+    };
+    let query = format!(
+        "WITH open_lines AS (
+             SELECT poi.product_id,
+                    SUM(poi.quantity - COALESCE(poi.received_quantity, 0)) as on_order,
+                    GROUP_CONCAT(po.po_number, ',') as po_numbers
+             FROM purchase_order_items poi
+             JOIN purchase_orders po ON poi.purchase_order_id = po.id
+             WHERE po.status IN ({open})
+               AND poi.quantity > COALESCE(poi.received_quantity, 0)
+             GROUP BY poi.product_id
+         ),
+         last_lines AS (
+             SELECT poi.product_id, po.supplier_id, poi.unit_cost,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY poi.product_id ORDER BY po.order_date DESC, poi.id DESC
+                    ) as rn
+             FROM purchase_order_items poi
+             JOIN purchase_orders po ON poi.purchase_order_id = po.id
+             WHERE po.status != 'Cancelled'
+         )
+         SELECT p.id, p.sku, p.name,
+                {reorder_point} as reorder_point,
+                COALESCE(p.cost_price, 0) as cost_price,
+                {current_stock} as current_stock,
+                {maximum_stock} as maximum_stock,
+                COALESCE(ol.on_order, 0) as on_order,
+                ol.po_numbers,
+                s.id as supplier_id, s.company_name as supplier_name,
+                ll.unit_cost as last_cost
+         FROM products p
+         JOIN inventory i ON i.product_id = p.id
+         {levels_join}
+         LEFT JOIN open_lines ol ON ol.product_id = p.id
+         LEFT JOIN last_lines ll ON ll.product_id = p.id AND ll.rn = 1
+         LEFT JOIN suppliers s ON s.id = ll.supplier_id
+         WHERE p.is_active = 1 AND {current_stock} <= {reorder_point}
+         ORDER BY s.company_name IS NULL, s.company_name, p.name",
+        open = OPEN_PO_STATUSES,
+        reorder_point = reorder_point,
+        current_stock = current_stock,
+        maximum_stock = maximum_stock,
+        levels_join = levels_join
+    );
+    let rows = sqlx::query(&query).bind(location_id).fetch_all(pool).await?;
+
+    let mut groups: Vec<ReorderSupplierGroup> = Vec::new();
+    for row in rows {
+        let current_stock: i32 = row.try_get("current_stock")?;
+        let reorder_point: i32 = row.try_get("reorder_point")?;
+        let maximum_stock: i32 = row.try_get("maximum_stock")?;
+        let on_order_quantity: i32 = row.try_get("on_order")?;
+        let last_cost: Option<Money> = row.try_get("last_cost")?;
+        let last_cost = last_cost.unwrap_or(row.try_get("cost_price")?);
+        let open_po_numbers: Option<String> = row.try_get("po_numbers")?;
+
+        let shortfall = target_stock(reorder_point, maximum_stock) - current_stock;
+        let suggested_quantity = (shortfall - on_order_quantity).max(0);
+        let item = ReorderItem {
+            product_id: row.try_get("id")?,
+            sku: row.try_get("sku")?,
+            name: row.try_get("name")?,
+            current_stock,
+            reorder_point,
+            maximum_stock,
+            on_order_quantity,
+            open_po_numbers: open_po_numbers
+                .map(|numbers| numbers.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            covered_by_open_po: on_order_quantity > 0,
+            suggested_quantity,
+            last_cost,
+            estimated_cost: last_cost.times(suggested_quantity),
+        };
+
+        let supplier_id: Option<i64> = row.try_get("supplier_id")?;
+        match groups.last_mut() {
+            Some(group) if group.supplier_id == supplier_id => {
+                group.estimated_spend += item.estimated_cost;
+                group.items.push(item);
+            }
+            _ => {
+                let supplier_name: Option<String> = row.try_get("supplier_name")?;
+                groups.push(ReorderSupplierGroup {
+                    supplier_id,
+                    supplier_name: supplier_name.unwrap_or_else(|| "Unassigned".to_string()),
+                    estimated_spend: item.estimated_cost,
+                    items: vec![item],
+                });
+            }
+        }
+    }
+
+    let total_estimated_spend = groups.iter().map(|group| group.estimated_spend).sum();
+    Ok(ReorderReport {
+        groups,
+        total_estimated_spend,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// The report as CSV, one row per product with a subtotal row after each supplier
+pub fn reorder_report_to_csv(report: &ReorderReport) -> String {
+    let headers = [
+        "Supplier",
+        "SKU",
+        "Product",
+        "Current Stock",
+        "Reorder Point",
+        "On Order",
+        "Open POs",
+        "Suggested Qty",
+        "Last Cost",
+        "Estimated Cost",
+    ];
+    let mut rows = Vec::new();
+    for group in &report.groups {
+        for item in &group.items {
+            rows.push(vec![
+                group.supplier_name.clone(),
+                item.sku.clone(),
+                item.name.clone(),
+                item.current_stock.to_string(),
+                item.reorder_point.to_string(),
+                item.on_order_quantity.to_string(),
+                item.open_po_numbers.join(" "),
+                item.suggested_quantity.to_string(),
+                item.last_cost.to_string(),
+                item.estimated_cost.to_string(),
+            ]);
+        }
+        let mut subtotal = vec![String::new(); headers.len()];
+        subtotal[0] = group.supplier_name.clone();
+        subtotal[2] = "Subtotal".to_string();
+        subtotal[9] = group.estimated_spend.to_string();
+        rows.push(subtotal);
+    }
+    let mut total = vec![String::new(); headers.len()];
+    total[2] = "Total".to_string();
+    total[9] = report.total_estimated_spend.to_string();
+    rows.push(total);
+    csv_export::to_csv(&headers, &rows)
+}
+
+/// Days of sales history a product's average daily sales are taken over
+const VELOCITY_WINDOW_DAYS: i32 = 90;
+/// Supplier lead time assumed for products without a reorder policy
+const DEFAULT_LEAD_TIME_DAYS: i32 = 7;
+
+/// How a product's minimum stock and reorder point are set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReorderPolicy {
+    /// Fixed thresholds
+    Absolute { minimum_stock: i32, reorder_point: i32 },
+    /// Reorder once stock falls to `percent` of the maximum stock
+    PercentOfMaximum { percent: f64 },
+    /// Enough stock to sell through the supplier's lead time plus `safety_days`, at the
+    /// average daily sales of the last 90 days. The minimum stock covers the safety days.
+    SalesVelocity {
+        lead_time_days: i32,
+        #[serde(default)]
+        safety_days: i32,
+    },
+}
+
+/// Thresholds a policy sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReorderLevels {
+    pub minimum_stock: i32,
+    pub reorder_point: i32,
+}
+
+/// A product's reorder point as recomputed from its sales
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestedReorderPoint {
+    pub product_id: i64,
+    pub sku: String,
+    pub name: String,
+    pub average_daily_sales: f64,
+    pub previous_reorder_point: i32,
+    pub minimum_stock: i32,
+    pub reorder_point: i32,
+    /// The product's own policy, or sales velocity at the default lead time
+    pub policy: ReorderPolicy,
+}
+
+impl ReorderPolicy {
+    fn validate(&self) -> AppResult<()> {
+        match *self {
+            ReorderPolicy::Absolute {
+                minimum_stock,
+                reorder_point,
+            } => {
+                if minimum_stock < 0 || reorder_point < 0 {
+                    return Err(AppError::validation("policy", "Stock levels cannot be negative"));
+                }
+                if minimum_stock > reorder_point {
+                    return Err(AppError::validation(
+                        "reorder_point",
+                        "The reorder point must be at least the minimum stock",
+                    ));
+                }
+            }
+            ReorderPolicy::PercentOfMaximum { percent } => {
+                if !(percent > 0.0 && percent <= 100.0) {
+                    return Err(AppError::validation("percent", "Percent must be above 0 and at most 100"));
+                }
+            }
+            ReorderPolicy::SalesVelocity {
+                lead_time_days,
+                safety_days,
+            } => {
+                if lead_time_days <= 0 {
+                    return Err(AppError::validation("lead_time_days", "Lead time must be at least one day"));
+                }
+                if safety_days < 0 {
+                    return Err(AppError::validation("safety_days", "Safety days cannot be negative"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Thresholds for a product holding at most `maximum_stock` that sells
+    /// `average_daily_sales` units a day
+    fn levels(&self, maximum_stock: i32, average_daily_sales: f64) -> ReorderLevels {
+        match *self {
+            ReorderPolicy::Absolute {
+                minimum_stock,
+                reorder_point,
+            } => ReorderLevels {
+                minimum_stock,
+                reorder_point,
+            },
+            ReorderPolicy::PercentOfMaximum { percent } => {
+                let level = (maximum_stock as f64 * percent / 100.0).ceil() as i32;
+                ReorderLevels {
+                    minimum_stock: level,
+                    reorder_point: level,
+                }
+            }
+            ReorderPolicy::SalesVelocity {
+                lead_time_days,
+                safety_days,
+            } => ReorderLevels {
+                minimum_stock: (average_daily_sales * safety_days as f64).ceil() as i32,
+                reorder_point: (average_daily_sales * (lead_time_days + safety_days) as f64).ceil() as i32,
+            },
+        }
+    }
+}
+
+/// Units of `product` sold on sales that were not voided in the velocity window
+fn sold_in_window_sql(product: &str) -> String {
+    format!(
+        "(SELECT COALESCE(SUM(si.quantity), 0) FROM sale_items si
+          JOIN sales s ON s.id = si.sale_id
+          WHERE si.product_id = {} AND s.is_voided = 0
+            AND s.created_at >= datetime('now', '-{} days'))",
+        product, VELOCITY_WINDOW_DAYS
+    )
+}
+
+async fn apply_reorder_levels(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    levels: ReorderLevels,
+) -> AppResult<()> {
+    sqlx::query("UPDATE products SET reorder_point = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2")
+        .bind(levels.reorder_point)
+        .bind(product_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("UPDATE inventory SET minimum_stock = ?1 WHERE product_id = ?2")
+        .bind(levels.minimum_stock)
+        .bind(product_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Keep `policy` for the product and set its thresholds from it now
+pub async fn set_reorder_policy_internal(
+    pool: &SqlitePool,
+    product_id: i64,
+    policy: &ReorderPolicy,
+) -> AppResult<ReorderLevels> {
+    policy.validate()?;
+    let mut tx = pool.begin().await?;
+    let (maximum_stock, sold): (i32, i64) = sqlx::query_as(&format!(
+        "SELECT COALESCE(i.maximum_stock, 0), {sold}
+         FROM products p JOIN inventory i ON i.product_id = p.id
+         WHERE p.id = ?1",
+        sold = sold_in_window_sql("p.id")
+    ))
+    .bind(product_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::not_found("Product"))?;
+
+    let policy_json = serde_json::to_string(policy)
+        .map_err(|e| AppError::validation("policy", &format!("Invalid policy: {}", e)))?;
+    sqlx::query(
+        "INSERT INTO reorder_policies (product_id, policy) VALUES (?1, ?2)
+         ON CONFLICT(product_id) DO UPDATE SET policy = excluded.policy, updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(product_id)
+    .bind(&policy_json)
+    .execute(&mut *tx)
+    .await?;
+
+    let levels = policy.levels(maximum_stock, sold as f64 / VELOCITY_WINDOW_DAYS as f64);
+    apply_reorder_levels(&mut tx, product_id, levels).await?;
+    tx.commit().await?;
+    Ok(levels)
+}
+
+/// Recompute the reorder point of every active product from its last 90 days of sales.
+/// Products follow their own policy; products without one use sales velocity at the
+/// default lead time, and are left alone when they sold nothing, as there is nothing to
+/// go on. Returns the products whose thresholds were set.
+pub async fn compute_suggested_reorder_points_internal(
+    pool: &SqlitePool,
+) -> AppResult<Vec<SuggestedReorderPoint>> {
+    let mut tx = pool.begin().await?;
+    let rows = sqlx::query(&format!(
+        "SELECT p.id, p.sku, p.name, COALESCE(p.reorder_point, 0) as reorder_point,
+                COALESCE(i.maximum_stock, 0) as maximum_stock, rp.policy, {sold} as sold
+         FROM products p
+         JOIN inventory i ON i.product_id = p.id
+         LEFT JOIN reorder_policies rp ON rp.product_id = p.id
+         WHERE p.is_active = 1 AND p.archived_at IS NULL
+         ORDER BY p.name",
+        sold = sold_in_window_sql("p.id")
+    ))
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut suggestions = Vec::new();
+    for row in rows {
+        let product_id: i64 = row.try_get("id")?;
+        let sold: i64 = row.try_get("sold")?;
+        let policy_json: Option<String> = row.try_get("policy")?;
+        let policy = match policy_json {
+            Some(json) => serde_json::from_str(&json).map_err(|e| {
+                AppError::validation("policy", &format!("Invalid reorder policy for product {}: {}", product_id, e))
+            })?,
+            None if sold == 0 => continue,
+            None => ReorderPolicy::SalesVelocity {
+                lead_time_days: DEFAULT_LEAD_TIME_DAYS,
+                safety_days: 0,
+            },
+        };
+        let average_daily_sales = sold as f64 / VELOCITY_WINDOW_DAYS as f64;
+        let levels = policy.levels(row.try_get("maximum_stock")?, average_daily_sales);
+        apply_reorder_levels(&mut tx, product_id, levels).await?;
+        suggestions.push(SuggestedReorderPoint {
+            product_id,
+            sku: row.try_get("sku")?,
+            name: row.try_get("name")?,
+            average_daily_sales,
+            previous_reorder_point: row.try_get("reorder_point")?,
+            minimum_stock: levels.minimum_stock,
+            reorder_point: levels.reorder_point,
+            policy,
+        });
+    }
+    tx.commit().await?;
+    Ok(suggestions)
+}
+
+/// Days a product's stock lasts at its recent rate of sale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockCoverageItem {
+    pub product_id: i64,
+    pub sku: String,
+    pub name: String,
+    pub current_stock: i32,
+    pub average_daily_sales: f64,
+    /// `None` when the product sold nothing in the window
+    pub days_of_stock_remaining: Option<f64>,
+    /// Stock runs out before an order placed today would arrive
+    pub stocks_out_before_lead_time: bool,
+}
+
+/// Days of stock left for every active product: stock on hand divided by the average
+/// daily units sold over the last `window_days`. Given a location, both stock and sales
+/// are that location's. Voided and demo sales do not count. Products that will run out
+/// within `lead_time_days` are flagged; the soonest to run out come first, and products
+/// with no recent sales come last.
+pub async fn get_stock_coverage_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    location_id: Option<i64>,
+    lead_time_days: i32,
+    window_days: i32,
+) -> AppResult<Vec<StockCoverageItem>> {
+    if lead_time_days < 0 {
+        return Err(AppError::validation("lead_time_days", "Lead time cannot be negative"));
+    }
+    if window_days <= 0 {
+        return Err(AppError::validation("window_days", "The window must be at least one day"));
+    }
+    let current_stock = match location_id {
+        Some(_) => location_stock_sql("i.product_id", "?1"),
+        None => "COALESCE(i.current_stock, 0)".to_string(),
+    };
+    let rows = sqlx::query(&format!(
+        "WITH sold AS (
+             SELECT si.product_id, SUM(si.quantity) as units
+             FROM sale_items si
+             JOIN sales s ON s.id = si.sale_id
+             WHERE s.is_voided = 0 AND s.is_demo = 0
+               AND s.created_at >= datetime('now', '-' || ?2 || ' days')
+               AND (?1 IS NULL OR s.location_id = ?1){sale_scope}
+             GROUP BY si.product_id
+         )
+         SELECT p.id, p.sku, p.name, {current_stock} as current_stock, COALESCE(sold.units, 0) as units
+         FROM products p
+         JOIN inventory i ON i.product_id = p.id
+         LEFT JOIN sold ON sold.product_id = p.id
+         WHERE p.is_active = 1 AND p.archived_at IS NULL{product_scope}
+         ORDER BY p.name",
+        current_stock = current_stock,
+        sale_scope = tenancy::organization_scope("s.organization_id", organization_id),
+        product_scope = tenancy::organization_scope("p.organization_id", organization_id)
+    ))
+    .bind(location_id)
+    .bind(window_days)
+    .fetch_all(pool)
+    .await?;
+
+    let mut items = Vec::with_capacity(rows.len());
+    for row in rows {
+        let current_stock: i32 = row.try_get("current_stock")?;
+        let units: i64 = row.try_get("units")?;
+        let average_daily_sales = units as f64 / window_days as f64;
+        let days_of_stock_remaining =
+            (units > 0).then(|| current_stock.max(0) as f64 / average_daily_sales);
+        items.push(StockCoverageItem {
+            product_id: row.try_get("id")?,
+            sku: row.try_get("sku")?,
+            name: row.try_get("name")?,
+            current_stock,
+            average_daily_sales,
+            days_of_stock_remaining,
+            stocks_out_before_lead_time: days_of_stock_remaining
+                .is_some_and(|days| days < lead_time_days as f64),
+        });
+    }
+    items.sort_by(|a, b| match (a.days_of_stock_remaining, b.days_of_stock_remaining) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    Ok(items)
+}
+
+#[command]
+pub async fn get_reorder_report(
+    pool: State<'_, SqlitePool>,
+    location_id: Option<i64>,
+) -> Result<ReorderReport, String> {
+    Ok(get_reorder_report_internal(pool.inner(), location_id).await?)
+}
+
+#[command]
+pub async fn export_reorder_report_csv(
+    pool: State<'_, SqlitePool>,
+    location_id: Option<i64>,
+) -> Result<String, String> {
+    let report = get_reorder_report_internal(pool.inner(), location_id).await?;
+    Ok(reorder_report_to_csv(&report))
+}
+
+#[command]
+pub async fn set_reorder_policy(
+    pool: State<'_, SqlitePool>,
+    product_id: i64,
+    policy: ReorderPolicy,
+    session_token: String,
+) -> Result<ReorderLevels, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    Ok(set_reorder_policy_internal(pool.inner(), product_id, &policy).await?)
+}
+
+#[command]
+pub async fn compute_suggested_reorder_points(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<Vec<SuggestedReorderPoint>, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    Ok(compute_suggested_reorder_points_internal(pool.inner()).await?)
+}
+
+/// Lead time defaults to the one assumed for products without a reorder policy, and the
+/// window to the 90 days reorder points are computed over
+#[command]
+pub async fn get_stock_coverage(
+    pool: State<'_, SqlitePool>,
+    location_id: Option<i64>,
+    lead_time_days: Option<i32>,
+    window_days: Option<i32>,
+    session_token: String,
+) -> Result<Vec<StockCoverageItem>, String> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    Ok(get_stock_coverage_internal(
+        pool.inner(),
+        organization_id,
+        location_id,
+        lead_time_days.unwrap_or(DEFAULT_LEAD_TIME_DAYS),
+        window_days.unwrap_or(VELOCITY_WINDOW_DAYS),
+    )
+    .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::inventory::get_low_stock_items_internal;
+    use crate::location_stock::{set_location_reorder_levels, LocationReorderLevels};
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{insert_test_product, test_pool};
+
+    async fn insert_supplier(pool: &SqlitePool, number: &str, name: &str) -> i64 {
+        sqlx::query("INSERT INTO suppliers (supplier_number, company_name) VALUES (?1, ?2)")
+            .bind(number)
+            .bind(name)
+            .execute(pool)
+            .await
+            .unwrap()
+            .last_insert_rowid()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_po_line(
+        pool: &SqlitePool,
+        po_number: &str,
+        supplier_id: i64,
+        status: &str,
+        product_id: i64,
+        quantity: i32,
+        received: i32,
+        unit_cost: f64,
+    ) {
+        let po_id = sqlx::query(
+            "INSERT INTO purchase_orders (po_number, supplier_id, order_date, status)
+             VALUES (?1, ?2, '2024-01-01', ?3)",
+        )
+        .bind(po_number)
+        .bind(supplier_id)
+        .bind(status)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        sqlx::query(
+            "INSERT INTO purchase_order_items
+                (purchase_order_id, product_id, quantity, received_quantity, unit_cost, total_cost)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?3 * ?5)",
+        )
+        .bind(po_id)
+        .bind(product_id)
+        .bind(quantity)
+        .bind(received)
+        .bind(unit_cost)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    fn find<'a>(report: &'a ReorderReport, sku: &str) -> &'a ReorderItem {
+        report
+            .groups
+            .iter()
+            .flat_map(|group| group.items.iter())
+            .find(|item| item.sku == sku)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_open_purchase_orders_suppress_suggestions() {
+        let pool = test_pool().await;
+        let acme = insert_supplier(&pool, "SUP-1", "Acme").await;
+        let covered = insert_test_product(&pool, "COVERED", 10.0, 2).await;
+        let partly = insert_test_product(&pool, "PARTLY", 10.0, 0).await;
+        let closed = insert_test_product(&pool, "CLOSED", 10.0, 4).await;
+        insert_test_product(&pool, "PLENTY", 10.0, 50).await;
+        // Fixtures use reorder point 5; order up to 20
+        sqlx::query("UPDATE inventory SET maximum_stock = 20").execute(&pool).await.unwrap();
+
+        insert_po_line(&pool, "PO-1", acme, "Sent", covered, 18, 0, 3.0).await;
+        insert_po_line(&pool, "PO-2", acme, "Partial", partly, 10, 4, 3.0).await;
+        insert_po_line(&pool, "PO-3", acme, "Received", closed, 10, 10, 3.0).await;
+        insert_po_line(&pool, "PO-4", acme, "Cancelled", closed, 30, 0, 9.0).await;
+
+        let report = get_reorder_report_internal(&pool, None).await.unwrap();
+        assert!(report
+            .groups
+            .iter()
+            .all(|group| group.items.iter().all(|item| item.sku != "PLENTY")));
+
+        let item = find(&report, "COVERED");
+        assert!(item.covered_by_open_po);
+        assert_eq!(item.on_order_quantity, 18);
+        assert_eq!(item.open_po_numbers, vec!["PO-1".to_string()]);
+        assert_eq!(item.suggested_quantity, 0);
+
+        // 20 - 0 on hand - 6 still outstanding
+        let item = find(&report, "PARTLY");
+        assert!(item.covered_by_open_po);
+        assert_eq!(item.on_order_quantity, 6);
+        assert_eq!(item.suggested_quantity, 14);
+
+        // Received and cancelled orders do not count; cost comes from the received one
+        let item = find(&report, "CLOSED");
+        assert!(!item.covered_by_open_po);
+        assert_eq!(item.on_order_quantity, 0);
+        assert_eq!(item.suggested_quantity, 16);
+        assert_eq!(item.last_cost, Money::from_major(3.0));
+    }
+
+    #[tokio::test]
+    async fn test_locations_reorder_against_their_own_levels() {
+        let pool = test_pool().await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 10).await;
+        let branch: i64 = sqlx::query_scalar("INSERT INTO locations (name) VALUES ('Branch') RETURNING id")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO location_inventory (product_id, location_id, current_stock) VALUES (?1, ?2, 4)")
+            .bind(widget)
+            .bind(branch)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let levels = LocationReorderLevels {
+            minimum_stock: Some(5),
+            maximum_stock: Some(12),
+            reorder_point: Some(6),
+        };
+        set_location_reorder_levels(&pool, widget, branch, &levels).await.unwrap();
+
+        // 10 on hand across the business is over the product's reorder point of 5
+        let business = get_reorder_report_internal(&pool, None).await.unwrap();
+        assert!(business.groups.is_empty());
+        // The main store's 6 is too, with no levels of its own
+        let main = get_reorder_report_internal(&pool, Some(1)).await.unwrap();
+        assert!(main.groups.is_empty());
+
+        let report = get_reorder_report_internal(&pool, Some(branch)).await.unwrap();
+        let item = find(&report, "WIDGET");
+        assert_eq!((item.current_stock, item.reorder_point), (4, 6));
+        assert_eq!(item.suggested_quantity, 8);
+        let low = get_low_stock_items_internal(&pool, 1, 50, Some(branch)).await.unwrap();
+        assert_eq!(low.len(), 1);
+        assert_eq!((low[0].current_stock, low[0].minimum_stock), (4, 5));
+
+        let inverted = LocationReorderLevels {
+            minimum_stock: Some(9),
+            maximum_stock: Some(3),
+            reorder_point: None,
+        };
+        let err = set_location_reorder_levels(&pool, widget, branch, &inverted)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+
+    #[tokio::test]
+    async fn test_groups_by_supplier_with_subtotals_and_exports_csv() {
+        let pool = test_pool().await;
+        let acme = insert_supplier(&pool, "SUP-1", "Acme, Inc").await;
+        let bolt = insert_test_product(&pool, "BOLT", 4.0, 1).await;
+        let nut = insert_test_product(&pool, "NUT", 2.0, 0).await;
+        // Never ordered: cost price 1.50, unassigned
+        insert_test_product(&pool, "WASHER", 3.0, 0).await;
+        sqlx::query("UPDATE inventory SET maximum_stock = 10").execute(&pool).await.unwrap();
+        insert_po_line(&pool, "PO-1", acme, "Received", bolt, 5, 5, 2.5).await;
+        insert_po_line(&pool, "PO-2", acme, "Received", nut, 5, 5, 1.0).await;
+
+        let report = get_reorder_report_internal(&pool, None).await.unwrap();
+        assert_eq!(report.groups.len(), 2);
+        let group = &report.groups[0];
+        assert_eq!(group.supplier_id, Some(acme));
+        assert_eq!(group.items.len(), 2);
+        // 9 x 2.50 + 10 x 1.00
+        assert_eq!(group.estimated_spend, Money::from_major(32.5));
+        let unassigned = &report.groups[1];
+        assert_eq!(unassigned.supplier_id, None);
+        assert_eq!(unassigned.supplier_name, "Unassigned");
+        assert_eq!(unassigned.estimated_spend, Money::from_major(15.0));
+        assert_eq!(report.total_estimated_spend, Money::from_major(47.5));
+
+        let csv = reorder_report_to_csv(&report);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].starts_with("Supplier,SKU,Product"));
+        assert!(lines[1].starts_with("\"Acme, Inc\",BOLT,"));
+        assert!(lines[3].starts_with("\"Acme, Inc\",,Subtotal,") && lines[3].ends_with(",32.50"));
+        assert!(lines[6].ends_with(",47.50"));
+    }
+
+    /// Record `quantity` units of `product_id` sold `days_ago`
+    async fn insert_sold(pool: &SqlitePool, product_id: i64, quantity: i32, days_ago: i32, voided: bool) {
+        let sale_id = sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id, is_voided, created_at)
+             VALUES (?1, 0, 0, 'cash', 1, ?2, datetime('now', '-' || ?3 || ' days'))",
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(voided)
+        .bind(days_ago)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        sqlx::query(
+            "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, line_total)
+             VALUES (?1, ?2, ?3, 1, ?3)",
+        )
+        .bind(sale_id)
+        .bind(product_id)
+        .bind(quantity)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn levels_of(pool: &SqlitePool, product_id: i64) -> (i32, i32) {
+        sqlx::query_as(
+            "SELECT i.minimum_stock, p.reorder_point FROM products p
+             JOIN inventory i ON i.product_id = p.id WHERE p.id = ?1",
+        )
+        .bind(product_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reorder_policies_set_thresholds() {
+        let pool = test_pool().await;
+        let product = insert_test_product(&pool, "CEMENT", 8.0, 40).await;
+        sqlx::query("UPDATE inventory SET maximum_stock = 90").execute(&pool).await.unwrap();
+
+        let absolute = ReorderPolicy::Absolute {
+            minimum_stock: 4,
+            reorder_point: 12,
+        };
+        set_reorder_policy_internal(&pool, product, &absolute).await.unwrap();
+        assert_eq!(levels_of(&pool, product).await, (4, 12));
+
+        let percent = ReorderPolicy::PercentOfMaximum { percent: 25.0 };
+        let levels = set_reorder_policy_internal(&pool, product, &percent).await.unwrap();
+        assert_eq!((levels.minimum_stock, levels.reorder_point), (23, 23));
+
+        // 90 units in the window, one sale before it and a voided one: 1 a day
+        insert_sold(&pool, product, 45, 10, false).await;
+        insert_sold(&pool, product, 45, 80, false).await;
+        insert_sold(&pool, product, 100, 120, false).await;
+        insert_sold(&pool, product, 30, 5, true).await;
+        let velocity = ReorderPolicy::SalesVelocity {
+            lead_time_days: 12,
+            safety_days: 3,
+        };
+        set_reorder_policy_internal(&pool, product, &velocity).await.unwrap();
+        assert_eq!(levels_of(&pool, product).await, (3, 15));
+
+        let err = set_reorder_policy_internal(&pool, product, &ReorderPolicy::PercentOfMaximum { percent: 0.0 })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        let err = set_reorder_policy_internal(&pool, 9999, &absolute).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+
+        let policy: ReorderPolicy =
+            serde_json::from_str(r#"{"type": "sales_velocity", "lead_time_days": 5}"#).unwrap();
+        assert_eq!(
+            policy,
+            ReorderPolicy::SalesVelocity {
+                lead_time_days: 5,
+                safety_days: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_suggested_reorder_points_follow_sales_velocity() {
+        let pool = test_pool().await;
+        let fast = insert_test_product(&pool, "FAST", 1.0, 10).await;
+        let fixed = insert_test_product(&pool, "FIXED", 1.0, 10).await;
+        let unsold = insert_test_product(&pool, "UNSOLD", 1.0, 10).await;
+        insert_sold(&pool, fast, 180, 30, false).await;
+        insert_sold(&pool, fixed, 900, 30, false).await;
+        let absolute = ReorderPolicy::Absolute {
+            minimum_stock: 1,
+            reorder_point: 3,
+        };
+        set_reorder_policy_internal(&pool, fixed, &absolute).await.unwrap();
+
+        let suggestions = compute_suggested_reorder_points_internal(&pool).await.unwrap();
+        assert_eq!(suggestions.len(), 2);
+        // 2 a day over the default 7-day lead time; fixtures start at reorder point 5
+        let suggestion = suggestions.iter().find(|s| s.product_id == fast).unwrap();
+        assert_eq!(suggestion.average_daily_sales, 2.0);
+        assert_eq!((suggestion.previous_reorder_point, suggestion.reorder_point), (5, 14));
+        assert_eq!(levels_of(&pool, fast).await, (0, 14));
+        // A product's own policy wins over its velocity
+        assert_eq!(levels_of(&pool, fixed).await, (1, 3));
+        // Nothing sold and no policy: left as it was
+        assert_eq!(levels_of(&pool, unsold).await, (0, 5));
+    }
+
+    #[tokio::test]
+    async fn test_stock_coverage_flags_products_running_out_within_lead_time() {
+        let pool = test_pool().await;
+        let fast = insert_test_product(&pool, "FAST", 1.0, 10).await;
+        let slow = insert_test_product(&pool, "SLOW", 1.0, 90).await;
+        let unsold = insert_test_product(&pool, "UNSOLD", 1.0, 10).await;
+        // 2 a day and 1 a day over 90 days; old and voided sales do not count
+        insert_sold(&pool, fast, 180, 20, false).await;
+        insert_sold(&pool, slow, 90, 40, false).await;
+        insert_sold(&pool, slow, 500, 120, false).await;
+        insert_sold(&pool, unsold, 50, 3, true).await;
+
+        let coverage = get_stock_coverage_internal(&pool, DEFAULT_ORGANIZATION_ID, None, 7, 90)
+            .await
+            .unwrap();
+        let skus: Vec<&str> = coverage.iter().map(|item| item.sku.as_str()).collect();
+        assert_eq!(skus, vec!["FAST", "SLOW", "UNSOLD"]);
+        assert_eq!(coverage[0].average_daily_sales, 2.0);
+        assert_eq!(coverage[0].days_of_stock_remaining, Some(5.0));
+        assert!(coverage[0].stocks_out_before_lead_time);
+        assert_eq!(coverage[1].days_of_stock_remaining, Some(90.0));
+        assert!(!coverage[1].stocks_out_before_lead_time);
+        assert_eq!(coverage[2].days_of_stock_remaining, None);
+        assert!(!coverage[2].stocks_out_before_lead_time);
+
+        // A shorter lead time clears the flag; a branch with no sales has no coverage
+        let coverage = get_stock_coverage_internal(&pool, DEFAULT_ORGANIZATION_ID, None, 5, 90)
+            .await
+            .unwrap();
+        assert!(coverage.iter().all(|item| !item.stocks_out_before_lead_time));
+        let branch: i64 = sqlx::query_scalar("INSERT INTO locations (name) VALUES ('Branch') RETURNING id")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let coverage = get_stock_coverage_internal(&pool, DEFAULT_ORGANIZATION_ID, Some(branch), 7, 90)
+            .await
+            .unwrap();
+        assert!(coverage.iter().all(|item| item.days_of_stock_remaining.is_none()));
+
+        let err = get_stock_coverage_internal(&pool, DEFAULT_ORGANIZATION_ID, None, 7, 0)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+
+        let other = get_stock_coverage_internal(&pool, 2, None, 7, 90).await.unwrap();
+        assert!(other.is_empty());
+    }
+}
@@ -1,43 +1,53 @@
+use crate::cost_history::SALE_ITEM_COST_SQL;
+use crate::error::AppResult;
+use crate::location_stock::location_stock_sql;
+use crate::money::Money;
+use crate::organization_settings;
+use crate::settings::{self, STORE_SETTINGS};
+use crate::tenancy;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
-use sqlx::{Row, SqlitePool};
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
 use tauri::{command, State};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SalesReport {
-    pub total_sales: f64,
+    pub total_sales: Money,
     pub total_transactions: i32,
-    pub average_transaction: f64,
-    pub total_profit: f64,
-    pub total_tax: f64,
-    pub total_discount: f64,
-    pub cash_sales: f64,
-    pub card_sales: f64,
-    pub mobile_sales: f64,
-    pub check_sales: f64,
+    pub average_transaction: Money,
+    pub total_profit: Money,
+    pub total_tax: Money,
+    pub total_discount: Money,
+    pub cash_sales: Money,
+    pub card_sales: Money,
+    pub mobile_sales: Money,
+    pub check_sales: Money,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FinancialMetrics {
-    pub gross_profit: f64,
+    pub gross_profit: Money,
     pub gross_profit_margin: f64,
-    pub net_profit: f64,
+    pub net_profit: Money,
     pub net_profit_margin: f64,
     pub revenue_growth_rate: f64,
     pub average_basket_size: f64,
     pub inventory_turnover_ratio: f64,
     pub return_on_investment: f64,
-    pub total_cogs: f64,
-    pub operating_expenses: f64,
+    pub total_cogs: Money,
+    pub operating_expenses: Money,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CashFlowSummary {
-    pub cash_inflow: f64,
-    pub cash_outflow: f64,
-    pub net_cash_flow: f64,
-    pub cash_from_operations: f64,
-    pub opening_balance: f64,
-    pub closing_balance: f64,
+    pub cash_inflow: Money,
+    pub cash_outflow: Money,
+    pub net_cash_flow: Money,
+    pub cash_from_operations: Money,
+    pub opening_balance: Money,
+    pub closing_balance: Money,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -47,37 +57,253 @@ pub struct ProductPerformance {
     pub sku: String,
     pub category: Option<String>,
     pub total_quantity_sold: i32,
-    pub total_revenue: f64,
-    pub total_profit: f64,
+    pub total_revenue: Money,
+    pub total_profit: Money,
     pub transaction_count: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DailySales {
     pub date: String,
-    pub total_sales: f64,
+    pub total_sales: Money,
     pub transaction_count: i32,
-    pub average_transaction: f64,
+    pub average_transaction: Money,
+}
+
+/// Stock on hand of one product at cost
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InventoryValuationLine {
+    pub product_id: i64,
+    pub product_name: String,
+    pub sku: String,
+    pub quantity: i32,
+    pub unit_cost: Money,
+    pub total_value: Money,
+}
+
+/// Stock on hand at cost, for one location or the whole business
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InventoryValuation {
+    pub location_id: Option<i64>,
+    pub total_units: i64,
+    pub total_value: Money,
+    pub lines: Vec<InventoryValuationLine>,
+}
+
+/// A product with stock on hand that has not sold within the report's window
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadStockLine {
+    pub product_id: i64,
+    pub product_name: String,
+    pub sku: String,
+    pub quantity: i32,
+    pub unit_cost: Money,
+    pub total_value: Money,
+    /// `None` when the product has never sold
+    pub last_sale_date: Option<String>,
+    pub is_active: bool,
+}
+
+/// Stock that has not moved in `days_without_sale` days, most capital tied up first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadStockReport {
+    pub days_without_sale: i32,
+    pub location_id: Option<i64>,
+    pub total_value: Money,
+    pub lines: Vec<DeadStockLine>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CategoryPerformance {
+    /// `None` for the uncategorized row
+    pub category_id: Option<i64>,
     pub category: String,
-    pub total_revenue: f64,
-    pub total_profit: f64,
+    pub total_revenue: Money,
+    pub total_profit: Money,
     pub total_items_sold: i32,
     pub product_count: i32,
+    /// Whether the row can be drilled into. A parent's row for its own products cannot.
+    pub has_subcategories: bool,
 }
 
-#[command]
-pub async fn get_sales_report(
-    pool: State<'_, SqlitePool>,
-    start_date: Option<String>,
-    end_date: Option<String>,
-) -> Result<SalesReport, String> {
-    let pool_ref = pool.inner();
+/// One cashier's sales over a period, for the leaderboard
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmployeeSalesPerformance {
+    pub cashier_id: i64,
+    pub cashier_name: String,
+    pub total_sales: Money,
+    pub transaction_count: i64,
+    pub total_profit: Money,
+    pub items_sold: i64,
+    /// Sales this cashier rang up that were later voided
+    pub voided_count: i64,
+}
 
-    let mut query = String::from(
+/// What the store owes one supplier, by age of the purchase order, less open credit memos
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SupplierAging {
+    pub supplier_id: i64,
+    pub supplier_name: String,
+    pub current: Money,
+    pub days_1_30: Money,
+    pub days_31_60: Money,
+    pub days_61_90: Money,
+    pub days_over_90: Money,
+    pub total_outstanding: Money,
+    pub open_orders: i32,
+    /// Unapplied balance of the supplier's credit memos
+    pub open_credits: Money,
+    pub net_payable: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PayablesAging {
+    pub as_of_date: String,
+    pub suppliers: Vec<SupplierAging>,
+    pub total_current: Money,
+    pub total_1_30: Money,
+    pub total_31_60: Money,
+    pub total_61_90: Money,
+    pub total_over_90: Money,
+    pub total_outstanding: Money,
+    pub total_open_credits: Money,
+    pub total_net_payable: Money,
+}
+
+/// Voids sharing a reason, after trimming, collapsing whitespace and lowercasing it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoidReasonSummary {
+    pub reason: String,
+    pub void_count: i64,
+    pub voided_amount: Money,
+}
+
+/// Voids by the user who voided the sale
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoidCashierSummary {
+    pub user_id: Option<i64>,
+    pub user_name: String,
+    pub void_count: i64,
+    pub voided_amount: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DailyVoids {
+    pub date: String,
+    pub void_count: i64,
+    pub voided_amount: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoidAnalytics {
+    pub start_date: String,
+    pub end_date: String,
+    pub total_voids: i64,
+    pub total_voided_amount: Money,
+    pub by_reason: Vec<VoidReasonSummary>,
+    pub by_cashier: Vec<VoidCashierSummary>,
+    pub daily: Vec<DailyVoids>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomerAging {
+    pub customer_name: String,
+    pub customer_phone: Option<String>,
+    pub current: Money,
+    pub days_1_30: Money,
+    pub days_31_60: Money,
+    pub days_61_90: Money,
+    pub days_over_90: Money,
+    pub total_outstanding: Money,
+    pub open_sales: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReceivablesAging {
+    pub as_of_date: String,
+    pub customers: Vec<CustomerAging>,
+    pub total_current: Money,
+    pub total_1_30: Money,
+    pub total_31_60: Money,
+    pub total_61_90: Money,
+    pub total_over_90: Money,
+    pub total_outstanding: Money,
+}
+
+/// Parse the `YYYY-MM-DD` part of a report date
+fn parse_report_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date.get(..10).unwrap_or(date), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date: {}", date))
+}
+
+/// `AND` bounds on a timestamp column for an inclusive date range. Ranging on the raw
+/// timestamp instead of `DATE(column)` lets SQLite use the column's index.
+fn created_at_range(
+    column: &str,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+) -> Result<(String, Vec<String>), String> {
+    let mut clause = String::new();
+    let mut params = Vec::new();
+
+    if let Some(start) = start_date.filter(|start| !start.is_empty()) {
+        clause.push_str(&format!(" AND {} >= ?", column));
+        params.push(parse_report_date(start)?.format("%Y-%m-%d").to_string());
+    }
+
+    if let Some(end) = end_date.filter(|end| !end.is_empty()) {
+        let day_after = parse_report_date(end)?
+            .succ_opt()
+            .ok_or_else(|| format!("Invalid date: {}", end))?;
+        clause.push_str(&format!(" AND {} < ?", column));
+        params.push(day_after.format("%Y-%m-%d").to_string());
+    }
+
+    Ok((clause, params))
+}
+
+/// `AND` condition on the `s` alias for `sales` leaving out sales rung up in demo mode,
+/// unless the report asks for them
+fn demo_sales_filter(include_demo: bool) -> &'static str {
+    if include_demo {
+        ""
+    } else {
+        " AND s.is_demo = 0"
+    }
+}
+
+/// `AND` condition on the `si` alias for `sale_items` leaving out lines of demo sales,
+/// unless the report asks for them. Lines missing from a left join are kept.
+fn demo_lines_filter(include_demo: bool) -> &'static str {
+    if include_demo {
+        ""
+    } else {
+        " AND NOT EXISTS (SELECT 1 FROM sales d WHERE d.id = si.sale_id AND d.is_demo = 1)"
+    }
+}
+
+/// `created_at_range` on `s.created_at` for one organization's sales, narrowed to one
+/// location's when given
+fn sales_scope(
+    organization_id: i64,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    location_id: Option<i64>,
+    include_demo: bool,
+) -> Result<(String, Vec<String>), String> {
+    let (mut clause, mut params) = created_at_range("s.created_at", start_date, end_date)?;
+    clause.push_str(&tenancy::organization_scope("s.organization_id", organization_id));
+    if let Some(location_id) = location_id {
+        clause.push_str(" AND s.location_id = ?");
+        params.push(location_id.to_string());
+    }
+    clause.push_str(demo_sales_filter(include_demo));
+    Ok((clause, params))
+}
+
+/// Sales totals for the sales report, filtered by `range` from `created_at_range`
+fn sales_totals_sql(range: &str) -> String {
+    format!(
         "SELECT 
             COALESCE(SUM(s.total_amount), 0.0) as total_sales,
             COUNT(*) as total_transactions,
@@ -89,25 +315,23 @@ pub async fn get_sales_report(
             COALESCE(SUM(CASE WHEN s.payment_method = 'mobile' THEN s.total_amount ELSE 0.0 END), 0.0) as mobile_sales,
             COALESCE(SUM(CASE WHEN s.payment_method = 'check' THEN s.total_amount ELSE 0.0 END), 0.0) as check_sales
          FROM sales s
-         WHERE s.is_voided = 0",
-    );
-
-    let mut params: Vec<String> = Vec::new();
-
-    if let Some(start) = &start_date {
-        if !start.is_empty() {
-            query.push_str(" AND DATE(s.created_at) >= ?");
-            params.push(start.clone());
-        }
-    }
+         WHERE s.is_voided = 0{}",
+        range
+    )
+}
 
-    if let Some(end) = &end_date {
-        if !end.is_empty() {
-            query.push_str(" AND DATE(s.created_at) <= ?");
-            params.push(end.clone());
-        }
-    }
+pub(crate) async fn get_sales_report_internal(
+    pool_ref: &SqlitePool,
+    organization_id: i64,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    location_id: Option<i64>,
+    include_demo: bool,
+) -> Result<SalesReport, String> {
+    let (range, params) =
+        sales_scope(organization_id, start_date, end_date, location_id, include_demo)?;
 
+    let query = sales_totals_sql(&range);
     let mut sql_query = sqlx::query(&query);
     for param in &params {
         sql_query = sql_query.bind(param);
@@ -119,31 +343,17 @@ pub async fn get_sales_report(
         .map_err(|e| format!("Database error: {}", e))?;
 
     // Calculate total profit
-    let mut profit_query = String::from(
-        "SELECT COALESCE(SUM((si.unit_price - si.cost_price) * si.quantity), 0.0) as total_profit
+    let profit_query = format!(
+        "SELECT COALESCE(SUM((si.unit_price - {cost}) * si.quantity), 0.0) as total_profit
          FROM sale_items si
          JOIN sales s ON si.sale_id = s.id
-         WHERE s.is_voided = 0",
+         WHERE s.is_voided = 0{range}",
+        cost = SALE_ITEM_COST_SQL,
+        range = range
     );
 
-    let mut profit_params: Vec<String> = Vec::new();
-
-    if let Some(start) = &start_date {
-        if !start.is_empty() {
-            profit_query.push_str(" AND DATE(s.created_at) >= ?");
-            profit_params.push(start.clone());
-        }
-    }
-
-    if let Some(end) = &end_date {
-        if !end.is_empty() {
-            profit_query.push_str(" AND DATE(s.created_at) <= ?");
-            profit_params.push(end.clone());
-        }
-    }
-
     let mut profit_sql_query = sqlx::query(&profit_query);
-    for param in &profit_params {
+    for param in &params {
         profit_sql_query = profit_sql_query.bind(param);
     }
 
@@ -166,18 +376,41 @@ pub async fn get_sales_report(
     })
 }
 
+#[command]
+pub async fn get_sales_report(
+    pool: State<'_, SqlitePool>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    location_id: Option<i64>,
+    include_demo: Option<bool>,
+    session_token: String,
+) -> Result<SalesReport, String> {
+    get_sales_report_internal(
+        pool.inner(),
+        tenancy::active_organization(&session_token)?,
+        start_date.as_deref(),
+        end_date.as_deref(),
+        location_id,
+        include_demo.unwrap_or(false),
+    )
+    .await
+}
+
 #[command]
 pub async fn get_product_performance(
     pool: State<'_, SqlitePool>,
     start_date: Option<String>,
     end_date: Option<String>,
     limit: Option<i32>,
+    include_demo: Option<bool>,
+    session_token: String,
 ) -> Result<Vec<ProductPerformance>, String> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
 
     let limit = limit.unwrap_or(20);
 
-    let mut query = String::from(
+    let mut query = QueryBuilder::<Sqlite>::new(format!(
         "SELECT 
             p.id as product_id,
             p.name as product_name,
@@ -185,43 +418,38 @@ pub async fn get_product_performance(
             p.category,
             COALESCE(SUM(si.quantity), 0) as total_quantity_sold,
             COALESCE(SUM(si.line_total), 0.0) as total_revenue,
-            COALESCE(SUM((si.unit_price - si.cost_price) * si.quantity), 0.0) as total_profit,
+            COALESCE(SUM((si.unit_price - {cost}) * si.quantity), 0.0) as total_profit,
             COUNT(DISTINCT s.id) as transaction_count
          FROM products p
          LEFT JOIN sale_items si ON p.id = si.product_id
          LEFT JOIN sales s ON si.sale_id = s.id AND s.is_voided = 0
-         WHERE 1=1",
-    );
-
-    let mut params: Vec<String> = Vec::new();
-
-    if let Some(start) = &start_date {
-        if !start.is_empty() {
-            query.push_str(" AND (s.created_at IS NULL OR DATE(s.created_at) >= ?)");
-            params.push(start.clone());
-        }
+         WHERE 1=1{demo}{scope}",
+        cost = SALE_ITEM_COST_SQL,
+        demo = demo_lines_filter(include_demo.unwrap_or(false)),
+        scope = tenancy::organization_scope("p.organization_id", organization_id)
+    ));
+
+    if let Some(start) = start_date.as_deref().filter(|start| !start.is_empty()) {
+        query
+            .push(" AND (s.created_at IS NULL OR DATE(s.created_at) >= ")
+            .push_bind(start)
+            .push(")");
     }
 
-    if let Some(end) = &end_date {
-        if !end.is_empty() {
-            query.push_str(" AND (s.created_at IS NULL OR DATE(s.created_at) <= ?)");
-            params.push(end.clone());
-        }
+    if let Some(end) = end_date.as_deref().filter(|end| !end.is_empty()) {
+        query
+            .push(" AND (s.created_at IS NULL OR DATE(s.created_at) <= ")
+            .push_bind(end)
+            .push(")");
     }
 
-    query.push_str(" GROUP BY p.id, p.name, p.sku, p.category");
-    query.push_str(" HAVING total_quantity_sold > 0");
-    query.push_str(" ORDER BY total_revenue DESC");
-    query.push_str(" LIMIT ?");
+    query.push(" GROUP BY p.id, p.name, p.sku, p.category");
+    query.push(" HAVING total_quantity_sold > 0");
+    query.push(" ORDER BY total_revenue DESC");
+    query.push(" LIMIT ").push_bind(limit);
 
-    params.push(limit.to_string());
-
-    let mut sql_query = sqlx::query(&query);
-    for param in &params {
-        sql_query = sql_query.bind(param);
-    }
-
-    let rows = sql_query
+    let rows = query
+        .build()
         .fetch_all(pool_ref)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
@@ -248,8 +476,12 @@ pub async fn get_daily_sales(
     pool: State<'_, SqlitePool>,
     start_date: Option<String>,
     end_date: Option<String>,
+    location_id: Option<i64>,
+    include_demo: Option<bool>,
+    session_token: String,
 ) -> Result<Vec<DailySales>, String> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
 
     let mut query = String::from(
         "SELECT 
@@ -261,23 +493,16 @@ pub async fn get_daily_sales(
          WHERE s.is_voided = 0",
     );
 
-    let mut params: Vec<String> = Vec::new();
-
-    if let Some(start) = &start_date {
-        if !start.is_empty() {
-            query.push_str(" AND DATE(created_at) >= ?");
-            params.push(start.clone());
-        }
-    }
-
-    if let Some(end) = &end_date {
-        if !end.is_empty() {
-            query.push_str(" AND DATE(created_at) <= ?");
-            params.push(end.clone());
-        }
-    }
+    let (range, params) = sales_scope(
+        organization_id,
+        start_date.as_deref(),
+        end_date.as_deref(),
+        location_id,
+        include_demo.unwrap_or(false),
+    )?;
+    query.push_str(&range);
 
-    query.push_str(" GROUP BY DATE(created_at)");
+    query.push_str(" GROUP BY DATE(s.created_at)");
     query.push_str(" ORDER BY date DESC");
 
     let mut sql_query = sqlx::query(&query);
@@ -303,111 +528,181 @@ pub async fn get_daily_sales(
     Ok(daily_sales)
 }
 
-#[command]
-pub async fn get_category_performance(
-    pool: State<'_, SqlitePool>,
-    start_date: Option<String>,
-    end_date: Option<String>,
-) -> Result<Vec<CategoryPerformance>, String> {
-    let pool_ref = pool.inner();
+/// The category of `category_id` a report at `level` rolls it into: the child of `level`
+/// on its way up the tree, the top-level category when `level` is `None`, or `level`
+/// itself for its own products. `None` when it sits outside `level`.
+fn rollup_category(
+    parents: &HashMap<i64, Option<i64>>,
+    category_id: i64,
+    level: Option<i64>,
+) -> Option<i64> {
+    if Some(category_id) == level {
+        return Some(category_id);
+    }
+    let mut current = category_id;
+    // Bounded by the number of categories so damaged data with a cycle cannot hang
+    for _ in 0..=parents.len() {
+        let parent = parents.get(&current).copied().flatten();
+        if parent == level {
+            return Some(current);
+        }
+        current = parent?;
+    }
+    None
+}
 
-    let mut query = String::from(
+/// Revenue by category at one level of the tree. Without `parent_id` that is the
+/// top-level categories plus uncategorized products, otherwise the subcategories of
+/// `parent_id` and a row for products filed directly under it. Every row includes the
+/// sales of all categories below it.
+pub(crate) async fn get_category_performance_internal(
+    pool_ref: &SqlitePool,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    parent_id: Option<i64>,
+    include_demo: bool,
+) -> Result<Vec<CategoryPerformance>, String> {
+    let mut query = QueryBuilder::<Sqlite>::new(format!(
         "SELECT 
-            COALESCE(p.category, 'Uncategorized') as category,
+            COALESCE(p.category_id, (SELECT c.id FROM categories c WHERE c.name = p.category)) as category_id,
             COALESCE(SUM(si.line_total), 0.0) as total_revenue,
-            COALESCE(SUM((si.unit_price - si.cost_price) * si.quantity), 0.0) as total_profit,
+            COALESCE(SUM((si.unit_price - {cost}) * si.quantity), 0.0) as total_profit,
             COALESCE(SUM(si.quantity), 0) as total_items_sold,
             COUNT(DISTINCT p.id) as product_count
          FROM products p
          LEFT JOIN sale_items si ON p.id = si.product_id
          LEFT JOIN sales s ON si.sale_id = s.id AND s.is_voided = 0
-         WHERE 1=1",
-    );
-
-    let mut params: Vec<String> = Vec::new();
-
-    if let Some(start) = &start_date {
-        if !start.is_empty() {
-            query.push_str(" AND (s.created_at IS NULL OR DATE(s.created_at) >= ?)");
-            params.push(start.clone());
-        }
+         WHERE 1=1{demo}",
+        cost = SALE_ITEM_COST_SQL,
+        demo = demo_lines_filter(include_demo)
+    ));
+
+    if let Some(start) = start_date.filter(|start| !start.is_empty()) {
+        query
+            .push(" AND (s.created_at IS NULL OR DATE(s.created_at) >= ")
+            .push_bind(start)
+            .push(")");
     }
 
-    if let Some(end) = &end_date {
-        if !end.is_empty() {
-            query.push_str(" AND (s.created_at IS NULL OR DATE(s.created_at) <= ?)");
-            params.push(end.clone());
-        }
+    if let Some(end) = end_date.filter(|end| !end.is_empty()) {
+        query
+            .push(" AND (s.created_at IS NULL OR DATE(s.created_at) <= ")
+            .push_bind(end)
+            .push(")");
     }
 
-    query.push_str(" GROUP BY p.category");
-    query.push_str(" ORDER BY total_revenue DESC");
-
-    let mut sql_query = sqlx::query(&query);
-    for param in &params {
-        sql_query = sql_query.bind(param);
-    }
+    query.push(" GROUP BY 1");
 
-    let rows = sql_query
+    let rows = query
+        .build()
         .fetch_all(pool_ref)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    let mut categories = Vec::new();
+    let tree: Vec<(i64, Option<i64>, String)> =
+        sqlx::query_as("SELECT id, parent_id, name FROM categories")
+            .fetch_all(pool_ref)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+    let parents: HashMap<i64, Option<i64>> = tree.iter().map(|(id, parent, _)| (*id, *parent)).collect();
+    let names: HashMap<i64, &str> = tree.iter().map(|(id, _, name)| (*id, name.as_str())).collect();
+
+    let mut totals: BTreeMap<Option<i64>, CategoryPerformance> = BTreeMap::new();
     for row in rows {
-        let total_revenue: f64 = row.try_get("total_revenue").map_err(|e| e.to_string())?;
-        if total_revenue > 0.0 {
-            categories.push(CategoryPerformance {
-                category: row.try_get("category").map_err(|e| e.to_string())?,
-                total_revenue,
-                total_profit: row.try_get("total_profit").map_err(|e| e.to_string())?,
-                total_items_sold: row.try_get("total_items_sold").map_err(|e| e.to_string())?,
-                product_count: row.try_get("product_count").map_err(|e| e.to_string())?,
-            });
-        }
+        let category_id: Option<i64> = row.try_get("category_id").map_err(|e| e.to_string())?;
+        let bucket = match category_id {
+            Some(id) => match rollup_category(&parents, id, parent_id) {
+                Some(bucket) => Some(bucket),
+                None => continue,
+            },
+            None if parent_id.is_none() => None,
+            None => continue,
+        };
+        let entry = totals.entry(bucket).or_insert_with(|| CategoryPerformance {
+            category_id: bucket,
+            category: bucket
+                .and_then(|id| names.get(&id).copied())
+                .unwrap_or("Uncategorized")
+                .to_string(),
+            total_revenue: Money::ZERO,
+            total_profit: Money::ZERO,
+            total_items_sold: 0,
+            product_count: 0,
+            has_subcategories: bucket.is_some_and(|id| {
+                Some(id) != parent_id && parents.values().any(|parent| *parent == Some(id))
+            }),
+        });
+        entry.total_revenue += row.try_get::<Money, _>("total_revenue").map_err(|e| e.to_string())?;
+        entry.total_profit += row.try_get::<Money, _>("total_profit").map_err(|e| e.to_string())?;
+        entry.total_items_sold += row.try_get::<i32, _>("total_items_sold").map_err(|e| e.to_string())?;
+        entry.product_count += row.try_get::<i32, _>("product_count").map_err(|e| e.to_string())?;
     }
 
+    let mut categories: Vec<CategoryPerformance> = totals
+        .into_values()
+        .filter(|category| category.total_revenue > Money::ZERO)
+        .collect();
+    categories.sort_by(|a, b| b.total_revenue.cmp(&a.total_revenue));
     Ok(categories)
 }
 
+#[command]
+pub async fn get_category_performance(
+    pool: State<'_, SqlitePool>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    parent_id: Option<i64>,
+    include_demo: Option<bool>,
+) -> Result<Vec<CategoryPerformance>, String> {
+    get_category_performance_internal(
+        pool.inner(),
+        start_date.as_deref(),
+        end_date.as_deref(),
+        parent_id,
+        include_demo.unwrap_or(false),
+    )
+    .await
+}
+
+/// Share of revenue the organization estimates for operating expenses
+async fn operating_expense_percent(pool: &SqlitePool, organization_id: i64) -> AppResult<f64> {
+    let mut conn = pool.acquire().await?;
+    Ok(organization_settings::load(&mut conn, organization_id)
+        .await?
+        .operating_expense_percent)
+}
+
 #[command]
 pub async fn get_financial_metrics(
     pool: State<'_, SqlitePool>,
     start_date: Option<String>,
     end_date: Option<String>,
+    session_token: String,
+    include_demo: Option<bool>,
 ) -> Result<FinancialMetrics, String> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let expense_percent = operating_expense_percent(pool_ref, organization_id).await?;
 
     // Build date filter
-    let mut date_filter = String::new();
-    let mut params: Vec<String> = Vec::new();
-
-    if let Some(start) = &start_date {
-        if !start.is_empty() {
-            date_filter.push_str(" AND DATE(s.created_at) >= ?");
-            params.push(start.clone());
-        }
-    }
-
-    if let Some(end) = &end_date {
-        if !end.is_empty() {
-            date_filter.push_str(" AND DATE(s.created_at) <= ?");
-            params.push(end.clone());
-        }
-    }
+    let (mut date_filter, params) =
+        created_at_range("s.created_at", start_date.as_deref(), end_date.as_deref())?;
+    date_filter.push_str(demo_sales_filter(include_demo.unwrap_or(false)));
+    date_filter.push_str(&tenancy::organization_scope("s.organization_id", organization_id));
 
     // Calculate revenue and COGS
     let revenue_query = format!(
         "SELECT 
             COALESCE(SUM(s.total_amount), 0.0) as total_revenue,
-            COALESCE(SUM(si.cost_price * si.quantity), 0.0) as total_cogs,
-            COALESCE(SUM((si.unit_price - si.cost_price) * si.quantity), 0.0) as gross_profit,
+            COALESCE(SUM({cost} * si.quantity), 0.0) as total_cogs,
+            COALESCE(SUM((si.unit_price - {cost}) * si.quantity), 0.0) as gross_profit,
             COUNT(DISTINCT s.id) as transaction_count,
             COALESCE(SUM(si.quantity), 0) as total_items
          FROM sales s
          JOIN sale_items si ON s.id = si.sale_id
          WHERE s.is_voided = 0{}",
-        date_filter
+        date_filter,
+        cost = SALE_ITEM_COST_SQL
     );
 
     let mut sql_query = sqlx::query(&revenue_query);
@@ -420,27 +715,27 @@ pub async fn get_financial_metrics(
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    let total_revenue: f64 = row.try_get("total_revenue").unwrap_or(0.0);
-    let total_cogs: f64 = row.try_get("total_cogs").unwrap_or(0.0);
-    let gross_profit: f64 = row.try_get("gross_profit").unwrap_or(0.0);
+    let total_revenue: Money = row.try_get("total_revenue").unwrap_or_default();
+    let total_cogs: Money = row.try_get("total_cogs").unwrap_or_default();
+    let gross_profit: Money = row.try_get("gross_profit").unwrap_or_default();
     let transaction_count: i32 = row.try_get("transaction_count").unwrap_or(0);
     let total_items: i32 = row.try_get("total_items").unwrap_or(0);
 
-    // Calculate operating expenses (simplified - typically 15-20% of revenue)
-    let operating_expenses = total_revenue * 0.15;
+    // Operating expenses are estimated as the organization's configured share of revenue
+    let operating_expenses = total_revenue.percent(expense_percent);
 
     // Calculate net profit
     let net_profit = gross_profit - operating_expenses;
 
     // Calculate profit margins
-    let gross_profit_margin = if total_revenue > 0.0 {
-        (gross_profit / total_revenue) * 100.0
+    let gross_profit_margin = if total_revenue > Money::ZERO {
+        (gross_profit.to_major() / total_revenue.to_major()) * 100.0
     } else {
         0.0
     };
 
-    let net_profit_margin = if total_revenue > 0.0 {
-        (net_profit / total_revenue) * 100.0
+    let net_profit_margin = if total_revenue > Money::ZERO {
+        (net_profit.to_major() / total_revenue.to_major()) * 100.0
     } else {
         0.0
     };
@@ -453,24 +748,28 @@ pub async fn get_financial_metrics(
     };
 
     // Calculate inventory turnover (COGS / Average Inventory)
-    let inventory_value_query = "SELECT COALESCE(SUM(i.current_stock * p.cost_price), 0.0) as inventory_value
-                                  FROM inventory i
-                                  JOIN products p ON i.product_id = p.id";
-    let inventory_row = sqlx::query(inventory_value_query)
+    let inventory_value_query = format!(
+        "SELECT COALESCE(SUM(i.current_stock * p.cost_price), 0.0) as inventory_value
+         FROM inventory i
+         JOIN products p ON i.product_id = p.id
+         WHERE 1=1{}",
+        tenancy::organization_scope("p.organization_id", organization_id)
+    );
+    let inventory_row = sqlx::query(&inventory_value_query)
         .fetch_one(pool_ref)
         .await
         .map_err(|e| format!("Failed to get inventory value: {}", e))?;
     
-    let inventory_value: f64 = inventory_row.try_get("inventory_value").unwrap_or(0.0);
-    let inventory_turnover_ratio = if inventory_value > 0.0 {
-        total_cogs / inventory_value
+    let inventory_value: Money = inventory_row.try_get("inventory_value").unwrap_or_default();
+    let inventory_turnover_ratio = if inventory_value > Money::ZERO {
+        total_cogs.to_major() / inventory_value.to_major()
     } else {
         0.0
     };
 
     // Calculate ROI
-    let return_on_investment = if total_cogs > 0.0 {
-        (gross_profit / total_cogs) * 100.0
+    let return_on_investment = if total_cogs > Money::ZERO {
+        (gross_profit.to_major() / total_cogs.to_major()) * 100.0
     } else {
         0.0
     };
@@ -497,79 +796,62 @@ pub async fn get_cash_flow_summary(
     pool: State<'_, SqlitePool>,
     start_date: Option<String>,
     end_date: Option<String>,
+    session_token: String,
+    include_demo: Option<bool>,
 ) -> Result<CashFlowSummary, String> {
     let pool_ref = pool.inner();
-
-    // Build date filter
-    let mut date_filter = String::new();
-    let mut params: Vec<String> = Vec::new();
-
-    if let Some(start) = &start_date {
-        if !start.is_empty() {
-            date_filter.push_str(" AND DATE(s.created_at) >= ?");
-            params.push(start.clone());
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let expense_percent = operating_expense_percent(pool_ref, organization_id).await?;
+
+    let start = start_date.as_deref().filter(|start| !start.is_empty());
+    let end = end_date.as_deref().filter(|end| !end.is_empty());
+    let demo_filter = demo_sales_filter(include_demo.unwrap_or(false));
+    let organization_filter = tenancy::organization_scope("s.organization_id", organization_id);
+    let push_date_filter = |query: &mut QueryBuilder<'_, Sqlite>| {
+        if let Some(start) = start {
+            query.push(" AND DATE(s.created_at) >= ").push_bind(start.to_string());
         }
-    }
-
-    if let Some(end) = &end_date {
-        if !end.is_empty() {
-            date_filter.push_str(" AND DATE(s.created_at) <= ?");
-            params.push(end.clone());
+        if let Some(end) = end {
+            query.push(" AND DATE(s.created_at) <= ").push_bind(end.to_string());
         }
-    }
+        query.push(demo_filter);
+        query.push(&organization_filter);
+    };
 
     // Calculate cash inflow from sales
-    let inflow_query = format!(
+    let mut inflow_query = QueryBuilder::<Sqlite>::new(
         "SELECT COALESCE(SUM(s.total_amount), 0.0) as cash_inflow
          FROM sales s
-         WHERE s.is_voided = 0{}",
-        date_filter
+         WHERE s.is_voided = 0",
     );
+    push_date_filter(&mut inflow_query);
 
-    let mut sql_query = sqlx::query(&inflow_query);
-    for param in &params {
-        sql_query = sql_query.bind(param);
-    }
-
-    let inflow_row = sql_query
+    let inflow_row = inflow_query
+        .build()
         .fetch_one(pool_ref)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    let cash_inflow: f64 = inflow_row.try_get("cash_inflow").unwrap_or(0.0);
+    let cash_inflow: Money = inflow_row.try_get("cash_inflow").unwrap_or_default();
 
     // Calculate cash outflow (COGS + operating expenses estimate)
-    let mut outflow_params: Vec<String> = Vec::new();
-    let outflow_query = format!(
-        "SELECT COALESCE(SUM(si.cost_price * si.quantity), 0.0) as cogs
+    let mut outflow_query = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT COALESCE(SUM({cost} * si.quantity), 0.0) as cogs
          FROM sale_items si
          JOIN sales s ON si.sale_id = s.id
-         WHERE s.is_voided = 0{}",
-        date_filter
-    );
-
-    let mut outflow_sql = sqlx::query(&outflow_query);
-    if let Some(start) = &start_date {
-        if !start.is_empty() {
-            outflow_params.push(start.clone());
-        }
-    }
-    if let Some(end) = &end_date {
-        if !end.is_empty() {
-            outflow_params.push(end.clone());
-        }
-    }
-    for param in &outflow_params {
-        outflow_sql = outflow_sql.bind(param);
-    }
+         WHERE s.is_voided = 0",
+        cost = SALE_ITEM_COST_SQL
+    ));
+    push_date_filter(&mut outflow_query);
 
-    let outflow_row = outflow_sql
+    let outflow_row = outflow_query
+        .build()
         .fetch_one(pool_ref)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    let cogs: f64 = outflow_row.try_get("cogs").unwrap_or(0.0);
-    let operating_expenses = cash_inflow * 0.15; // Estimate
+    let cogs: Money = outflow_row.try_get("cogs").unwrap_or_default();
+    let operating_expenses = cash_inflow.percent(expense_percent); // Estimate
     let cash_outflow = cogs + operating_expenses;
 
     // Calculate net cash flow
@@ -579,7 +861,7 @@ pub async fn get_cash_flow_summary(
     let cash_from_operations = net_cash_flow;
 
     // Balance calculations (simplified - would need actual cash drawer data)
-    let opening_balance = 1000.0; // Placeholder
+    let opening_balance = Money::from_major(1000.0); // Placeholder
     let closing_balance = opening_balance + net_cash_flow;
 
     Ok(CashFlowSummary {
@@ -591,3 +873,1099 @@ pub async fn get_cash_flow_summary(
         closing_balance,
     })
 }
+
+/// Sales, profit and items per cashier over a period, best seller first. Voided sales
+/// count only towards `voided_count`.
+pub async fn get_employee_sales_performance_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<EmployeeSalesPerformance>, String> {
+    let query = format!(
+        "WITH sale_lines AS (
+            SELECT si.sale_id,
+                   SUM((si.unit_price - {cost}) * si.quantity) as profit,
+                   SUM(si.quantity) as items_sold
+            FROM sale_items si
+            JOIN sales s ON s.id = si.sale_id
+            GROUP BY si.sale_id
+         )
+         SELECT
+            s.cashier_id,
+            COALESCE(u.first_name || ' ' || u.last_name, 'Unknown') as cashier_name,
+            COALESCE(SUM(CASE WHEN s.is_voided = 0 THEN s.total_amount END), 0.0) as total_sales,
+            COUNT(CASE WHEN s.is_voided = 0 THEN 1 END) as transaction_count,
+            COALESCE(SUM(CASE WHEN s.is_voided = 0 THEN l.profit END), 0.0) as total_profit,
+            COALESCE(SUM(CASE WHEN s.is_voided = 0 THEN l.items_sold END), 0) as items_sold,
+            COUNT(CASE WHEN s.is_voided = 1 THEN 1 END) as voided_count
+         FROM sales s
+         LEFT JOIN users u ON u.id = s.cashier_id
+         LEFT JOIN sale_lines l ON l.sale_id = s.id
+         WHERE DATE(s.created_at) BETWEEN ?1 AND ?2{scope}
+         GROUP BY s.cashier_id
+         ORDER BY total_sales DESC",
+        cost = SALE_ITEM_COST_SQL,
+        scope = tenancy::organization_scope("s.organization_id", organization_id)
+    );
+
+    let rows = sqlx::query(&query)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut performances = Vec::new();
+    for row in rows {
+        performances.push(EmployeeSalesPerformance {
+            cashier_id: row.try_get("cashier_id").map_err(|e| e.to_string())?,
+            cashier_name: row.try_get("cashier_name").map_err(|e| e.to_string())?,
+            total_sales: row.try_get("total_sales").map_err(|e| e.to_string())?,
+            transaction_count: row.try_get("transaction_count").map_err(|e| e.to_string())?,
+            total_profit: row.try_get("total_profit").map_err(|e| e.to_string())?,
+            items_sold: row.try_get("items_sold").map_err(|e| e.to_string())?,
+            voided_count: row.try_get("voided_count").map_err(|e| e.to_string())?,
+        });
+    }
+
+    Ok(performances)
+}
+
+#[command]
+pub async fn get_employee_sales_performance(
+    pool: State<'_, SqlitePool>,
+    start_date: String,
+    end_date: String,
+    session_token: String,
+) -> Result<Vec<EmployeeSalesPerformance>, String> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_employee_sales_performance_internal(pool.inner(), organization_id, &start_date, &end_date)
+        .await
+}
+
+pub const COMMISSION_BASIS_SETTING: &str = "commission_basis";
+
+/// What an employee's commission rate is applied to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommissionBasis {
+    /// Line totals after discounts
+    #[default]
+    Revenue,
+    /// Line totals less the cost of the goods sold
+    Profit,
+}
+
+/// Commission an employee earned on the sales they rang up over a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommissionReport {
+    pub employee_id: i64,
+    pub employee_name: String,
+    pub salary_type: String,
+    /// Percent of the commissionable amount
+    pub commission_rate: f64,
+    pub basis: CommissionBasis,
+    pub start_date: String,
+    pub end_date: String,
+    pub sale_count: i64,
+    pub line_count: i64,
+    pub revenue: Money,
+    pub profit: Money,
+    /// Revenue or profit, as `basis` says
+    pub commissionable_amount: Money,
+    pub commission: Money,
+}
+
+/// Commission on the lines of sales the employee was the cashier of. Voided and demo sales
+/// earn nothing. The basis is the one given, else the store's `commission_basis` setting.
+pub async fn get_commission_report_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    employee_id: i64,
+    start_date: &str,
+    end_date: &str,
+    basis: Option<CommissionBasis>,
+) -> Result<CommissionReport, String> {
+    let employee = sqlx::query(
+        "SELECT e.user_id, e.salary_type, COALESCE(e.commission_rate, 0.0) as commission_rate,
+                u.first_name || ' ' || u.last_name as employee_name
+         FROM employees e
+         JOIN users u ON u.id = e.user_id
+         WHERE e.id = ?1",
+    )
+    .bind(employee_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?
+    .ok_or_else(|| "Employee not found".to_string())?;
+    let user_id: i64 = employee.try_get("user_id").map_err(|e| e.to_string())?;
+    let commission_rate: f64 = employee.try_get("commission_rate").map_err(|e| e.to_string())?;
+
+    let basis = match basis {
+        Some(basis) => basis,
+        None => settings::get_setting(pool, COMMISSION_BASIS_SETTING, STORE_SETTINGS)
+            .await?
+            .unwrap_or_default(),
+    };
+
+    let query = format!(
+        "SELECT COUNT(DISTINCT s.id) as sale_count,
+                COUNT(si.id) as line_count,
+                COALESCE(SUM(si.line_total), 0.0) as revenue,
+                COALESCE(SUM(si.line_total - {cost} * si.quantity), 0.0) as profit
+         FROM sale_items si
+         JOIN sales s ON s.id = si.sale_id
+         WHERE s.cashier_id = ?1 AND s.is_voided = 0 AND s.is_demo = 0
+           AND DATE(s.created_at) BETWEEN ?2 AND ?3{scope}",
+        cost = SALE_ITEM_COST_SQL,
+        scope = tenancy::organization_scope("s.organization_id", organization_id)
+    );
+    let totals = sqlx::query(&query)
+        .bind(user_id)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+    let revenue: Money = totals.try_get("revenue").map_err(|e| e.to_string())?;
+    let profit: Money = totals.try_get("profit").map_err(|e| e.to_string())?;
+    let commissionable_amount = match basis {
+        CommissionBasis::Revenue => revenue,
+        CommissionBasis::Profit => profit.max(Money::ZERO),
+    };
+
+    Ok(CommissionReport {
+        employee_id,
+        employee_name: employee.try_get("employee_name").map_err(|e| e.to_string())?,
+        salary_type: employee.try_get("salary_type").map_err(|e| e.to_string())?,
+        commission_rate,
+        basis,
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        sale_count: totals.try_get("sale_count").map_err(|e| e.to_string())?,
+        line_count: totals.try_get("line_count").map_err(|e| e.to_string())?,
+        revenue,
+        profit,
+        commissionable_amount,
+        commission: commissionable_amount.percent(commission_rate),
+    })
+}
+
+#[command]
+pub async fn get_commission_report(
+    pool: State<'_, SqlitePool>,
+    employee_id: i64,
+    start_date: String,
+    end_date: String,
+    basis: Option<CommissionBasis>,
+    session_token: String,
+) -> Result<CommissionReport, String> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_commission_report_internal(
+        pool.inner(),
+        organization_id,
+        employee_id,
+        &start_date,
+        &end_date,
+        basis,
+    )
+    .await
+}
+
+/// Group a free-text void reason with others that differ only in spacing or case
+fn normalize_void_reason(reason: Option<&str>) -> String {
+    let reason = reason
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if reason.is_empty() {
+        "(no reason)".to_string()
+    } else {
+        reason
+    }
+}
+
+/// Sales voided over a period, by reason, by who voided them and by day. Dated by when the
+/// void happened rather than the sale.
+pub async fn get_void_analytics_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> Result<VoidAnalytics, String> {
+    let rows = sqlx::query(&format!(
+        "SELECT s.void_reason, s.voided_by, s.total_amount,
+                DATE(COALESCE(s.voided_at, s.created_at)) as void_date,
+                COALESCE(u.first_name || ' ' || u.last_name, 'Unknown') as user_name
+         FROM sales s
+         LEFT JOIN users u ON u.id = s.voided_by
+         WHERE s.is_voided = 1
+         AND DATE(COALESCE(s.voided_at, s.created_at)) BETWEEN ?1 AND ?2{}",
+        tenancy::organization_scope("s.organization_id", organization_id)
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut by_reason: HashMap<String, VoidReasonSummary> = HashMap::new();
+    let mut by_cashier: HashMap<Option<i64>, VoidCashierSummary> = HashMap::new();
+    let mut daily: BTreeMap<String, DailyVoids> = BTreeMap::new();
+    let mut total_voided_amount = Money::ZERO;
+
+    for row in &rows {
+        let amount: Money = row.try_get("total_amount").map_err(|e| e.to_string())?;
+        let reason: Option<String> = row.try_get("void_reason").map_err(|e| e.to_string())?;
+        let user_id: Option<i64> = row.try_get("voided_by").map_err(|e| e.to_string())?;
+        let date: String = row.try_get("void_date").map_err(|e| e.to_string())?;
+        total_voided_amount += amount;
+
+        let reason = normalize_void_reason(reason.as_deref());
+        let entry = by_reason.entry(reason.clone()).or_insert(VoidReasonSummary {
+            reason,
+            void_count: 0,
+            voided_amount: Money::ZERO,
+        });
+        entry.void_count += 1;
+        entry.voided_amount += amount;
+
+        let entry = match by_cashier.entry(user_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(VoidCashierSummary {
+                user_id,
+                user_name: row.try_get("user_name").map_err(|e| e.to_string())?,
+                void_count: 0,
+                voided_amount: Money::ZERO,
+            }),
+        };
+        entry.void_count += 1;
+        entry.voided_amount += amount;
+
+        let entry = daily.entry(date.clone()).or_insert(DailyVoids {
+            date,
+            void_count: 0,
+            voided_amount: Money::ZERO,
+        });
+        entry.void_count += 1;
+        entry.voided_amount += amount;
+    }
+
+    let mut by_reason: Vec<VoidReasonSummary> = by_reason.into_values().collect();
+    by_reason.sort_by(|a, b| {
+        b.void_count
+            .cmp(&a.void_count)
+            .then(b.voided_amount.cmp(&a.voided_amount))
+            .then(a.reason.cmp(&b.reason))
+    });
+    let mut by_cashier: Vec<VoidCashierSummary> = by_cashier.into_values().collect();
+    by_cashier.sort_by(|a, b| {
+        b.void_count
+            .cmp(&a.void_count)
+            .then(b.voided_amount.cmp(&a.voided_amount))
+            .then(a.user_id.cmp(&b.user_id))
+    });
+
+    Ok(VoidAnalytics {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        total_voids: rows.len() as i64,
+        total_voided_amount,
+        by_reason,
+        by_cashier,
+        daily: daily.into_values().collect(),
+    })
+}
+
+#[command]
+pub async fn get_void_analytics(
+    pool: State<'_, SqlitePool>,
+    start_date: String,
+    end_date: String,
+    session_token: String,
+) -> Result<VoidAnalytics, String> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_void_analytics_internal(pool.inner(), organization_id, &start_date, &end_date).await
+}
+
+/// Bucket unpaid and partially paid sales by age (days since the sale date), grouped by customer
+pub async fn get_receivables_aging_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    as_of_date: Option<String>,
+) -> Result<ReceivablesAging, String> {
+    let as_of_date = match as_of_date {
+        Some(d) if !d.is_empty() => d,
+        _ => chrono::Local::now().format("%Y-%m-%d").to_string(),
+    };
+
+    let rows = sqlx::query(&format!(
+        "WITH open_sales AS (
+            SELECT
+                COALESCE(s.customer_name, s.customer_phone, 'Walk-in Customer') as customer_name,
+                s.customer_phone,
+                s.total_amount,
+                CAST(julianday(DATE(?1)) - julianday(DATE(s.created_at)) AS INTEGER) as age_days
+            FROM sales s
+            WHERE s.payment_status IN ('Pending', 'Partial')
+            AND s.is_voided = 0
+            AND DATE(s.created_at) <= DATE(?1){}
+         )
+         SELECT
+            customer_name,
+            MAX(customer_phone) as customer_phone,
+            COALESCE(SUM(CASE WHEN age_days <= 0 THEN total_amount ELSE 0.0 END), 0.0) as current,
+            COALESCE(SUM(CASE WHEN age_days BETWEEN 1 AND 30 THEN total_amount ELSE 0.0 END), 0.0) as days_1_30,
+            COALESCE(SUM(CASE WHEN age_days BETWEEN 31 AND 60 THEN total_amount ELSE 0.0 END), 0.0) as days_31_60,
+            COALESCE(SUM(CASE WHEN age_days BETWEEN 61 AND 90 THEN total_amount ELSE 0.0 END), 0.0) as days_61_90,
+            COALESCE(SUM(CASE WHEN age_days > 90 THEN total_amount ELSE 0.0 END), 0.0) as days_over_90,
+            COALESCE(SUM(total_amount), 0.0) as total_outstanding,
+            COUNT(*) as open_sales
+         FROM open_sales
+         GROUP BY customer_name
+         ORDER BY total_outstanding DESC",
+        tenancy::organization_scope("s.organization_id", organization_id)
+    ))
+    .bind(&as_of_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut report = ReceivablesAging {
+        as_of_date,
+        customers: Vec::new(),
+        total_current: Money::ZERO,
+        total_1_30: Money::ZERO,
+        total_31_60: Money::ZERO,
+        total_61_90: Money::ZERO,
+        total_over_90: Money::ZERO,
+        total_outstanding: Money::ZERO,
+    };
+
+    for row in rows {
+        let customer = CustomerAging {
+            customer_name: row.try_get("customer_name").map_err(|e| e.to_string())?,
+            customer_phone: row.try_get("customer_phone").ok().flatten(),
+            current: row.try_get("current").unwrap_or_default(),
+            days_1_30: row.try_get("days_1_30").unwrap_or_default(),
+            days_31_60: row.try_get("days_31_60").unwrap_or_default(),
+            days_61_90: row.try_get("days_61_90").unwrap_or_default(),
+            days_over_90: row.try_get("days_over_90").unwrap_or_default(),
+            total_outstanding: row.try_get("total_outstanding").unwrap_or_default(),
+            open_sales: row.try_get("open_sales").unwrap_or(0),
+        };
+
+        report.total_current += customer.current;
+        report.total_1_30 += customer.days_1_30;
+        report.total_31_60 += customer.days_31_60;
+        report.total_61_90 += customer.days_61_90;
+        report.total_over_90 += customer.days_over_90;
+        report.total_outstanding += customer.total_outstanding;
+        report.customers.push(customer);
+    }
+
+    Ok(report)
+}
+
+#[command]
+pub async fn get_receivables_aging(
+    pool: State<'_, SqlitePool>,
+    as_of_date: Option<String>,
+    session_token: String,
+) -> Result<ReceivablesAging, String> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_receivables_aging_internal(pool.inner(), organization_id, as_of_date).await
+}
+
+/// Bucket unpaid purchase orders by age (days since the order date), grouped by supplier,
+/// and net off each supplier's open credit memos
+pub async fn get_payables_aging_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    as_of_date: Option<String>,
+) -> Result<PayablesAging, String> {
+    let as_of_date = match as_of_date {
+        Some(d) if !d.is_empty() => d,
+        _ => chrono::Local::now().format("%Y-%m-%d").to_string(),
+    };
+
+    let rows = sqlx::query(&format!(
+        "WITH open_orders AS (
+            SELECT
+                po.supplier_id,
+                po.total_amount - COALESCE((
+                    SELECT SUM(sp.amount) FROM supplier_payments sp
+                    WHERE sp.purchase_order_id = po.id AND DATE(sp.payment_date) <= DATE(?1)
+                ), 0.0) as outstanding,
+                CAST(julianday(DATE(?1)) - julianday(DATE(po.order_date)) AS INTEGER) as age_days
+            FROM purchase_orders po
+            WHERE po.status NOT IN ('Draft', 'Cancelled')
+            AND DATE(po.order_date) <= DATE(?1){}
+         )
+         SELECT
+            o.supplier_id,
+            s.company_name as supplier_name,
+            COALESCE(SUM(CASE WHEN age_days <= 0 THEN outstanding ELSE 0.0 END), 0.0) as current,
+            COALESCE(SUM(CASE WHEN age_days BETWEEN 1 AND 30 THEN outstanding ELSE 0.0 END), 0.0) as days_1_30,
+            COALESCE(SUM(CASE WHEN age_days BETWEEN 31 AND 60 THEN outstanding ELSE 0.0 END), 0.0) as days_31_60,
+            COALESCE(SUM(CASE WHEN age_days BETWEEN 61 AND 90 THEN outstanding ELSE 0.0 END), 0.0) as days_61_90,
+            COALESCE(SUM(CASE WHEN age_days > 90 THEN outstanding ELSE 0.0 END), 0.0) as days_over_90,
+            COALESCE(SUM(outstanding), 0.0) as total_outstanding,
+            COUNT(*) as open_orders
+         FROM open_orders o
+         JOIN suppliers s ON s.id = o.supplier_id
+         WHERE o.outstanding > 0.005
+         GROUP BY o.supplier_id",
+        tenancy::organization_scope("po.organization_id", organization_id)
+    ))
+    .bind(&as_of_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut suppliers: BTreeMap<i64, SupplierAging> = BTreeMap::new();
+    for row in rows {
+        let supplier = SupplierAging {
+            supplier_id: row.try_get("supplier_id").map_err(|e| e.to_string())?,
+            supplier_name: row.try_get("supplier_name").map_err(|e| e.to_string())?,
+            current: row.try_get("current").unwrap_or_default(),
+            days_1_30: row.try_get("days_1_30").unwrap_or_default(),
+            days_31_60: row.try_get("days_31_60").unwrap_or_default(),
+            days_61_90: row.try_get("days_61_90").unwrap_or_default(),
+            days_over_90: row.try_get("days_over_90").unwrap_or_default(),
+            total_outstanding: row.try_get("total_outstanding").unwrap_or_default(),
+            open_orders: row.try_get("open_orders").unwrap_or(0),
+            open_credits: Money::ZERO,
+            net_payable: Money::ZERO,
+        };
+        suppliers.insert(supplier.supplier_id, supplier);
+    }
+
+    // A memo belongs to the organization of the return it was issued for
+    let credits: Vec<(i64, String, Money)> = sqlx::query_as(&format!(
+        "SELECT m.supplier_id, s.company_name, SUM(m.amount - m.applied_amount)
+         FROM supplier_credit_memos m
+         JOIN suppliers s ON s.id = m.supplier_id
+         JOIN comprehensive_returns r ON r.id = m.return_id
+         WHERE m.status = 'Open'{}
+         GROUP BY m.supplier_id",
+        tenancy::organization_scope("r.organization_id", organization_id)
+    ))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+    for (supplier_id, supplier_name, open_credits) in credits {
+        suppliers
+            .entry(supplier_id)
+            .or_insert_with(|| SupplierAging {
+                supplier_id,
+                supplier_name,
+                current: Money::ZERO,
+                days_1_30: Money::ZERO,
+                days_31_60: Money::ZERO,
+                days_61_90: Money::ZERO,
+                days_over_90: Money::ZERO,
+                total_outstanding: Money::ZERO,
+                open_orders: 0,
+                open_credits: Money::ZERO,
+                net_payable: Money::ZERO,
+            })
+            .open_credits = open_credits;
+    }
+
+    let mut report = PayablesAging {
+        as_of_date,
+        suppliers: Vec::new(),
+        total_current: Money::ZERO,
+        total_1_30: Money::ZERO,
+        total_31_60: Money::ZERO,
+        total_61_90: Money::ZERO,
+        total_over_90: Money::ZERO,
+        total_outstanding: Money::ZERO,
+        total_open_credits: Money::ZERO,
+        total_net_payable: Money::ZERO,
+    };
+
+    for mut supplier in suppliers.into_values() {
+        supplier.net_payable = supplier.total_outstanding - supplier.open_credits;
+
+        report.total_current += supplier.current;
+        report.total_1_30 += supplier.days_1_30;
+        report.total_31_60 += supplier.days_31_60;
+        report.total_61_90 += supplier.days_61_90;
+        report.total_over_90 += supplier.days_over_90;
+        report.total_outstanding += supplier.total_outstanding;
+        report.total_open_credits += supplier.open_credits;
+        report.total_net_payable += supplier.net_payable;
+        report.suppliers.push(supplier);
+    }
+    report.suppliers.sort_by_key(|supplier| std::cmp::Reverse(supplier.net_payable));
+
+    Ok(report)
+}
+
+#[command]
+pub async fn get_payables_aging(
+    pool: State<'_, SqlitePool>,
+    as_of_date: Option<String>,
+    session_token: String,
+) -> Result<PayablesAging, String> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_payables_aging_internal(pool.inner(), organization_id, as_of_date).await
+}
+
+pub(crate) async fn get_inventory_valuation_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    location_id: Option<i64>,
+) -> Result<InventoryValuation, String> {
+    let stock = match location_id {
+        Some(_) => location_stock_sql("p.id", "?1"),
+        None => "i.current_stock".to_string(),
+    };
+    let query = format!(
+        "SELECT p.id, p.name, p.sku, p.cost_price, {} AS quantity
+         FROM products p
+         JOIN inventory i ON i.product_id = p.id
+         WHERE p.is_active = 1{}
+         ORDER BY p.name",
+        stock,
+        tenancy::organization_scope("p.organization_id", organization_id)
+    );
+    let rows = sqlx::query(&query)
+        .bind(location_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut valuation = InventoryValuation {
+        location_id,
+        total_units: 0,
+        total_value: Money::ZERO,
+        lines: Vec::new(),
+    };
+    for row in rows {
+        let quantity: i32 = row.try_get("quantity").map_err(|e| e.to_string())?;
+        if quantity <= 0 {
+            continue;
+        }
+        let unit_cost = Money::from_major(row.try_get("cost_price").map_err(|e| e.to_string())?);
+        let total_value = unit_cost.times(quantity);
+        valuation.total_units += i64::from(quantity);
+        valuation.total_value += total_value;
+        valuation.lines.push(InventoryValuationLine {
+            product_id: row.try_get("id").map_err(|e| e.to_string())?,
+            product_name: row.try_get("name").map_err(|e| e.to_string())?,
+            sku: row.try_get("sku").map_err(|e| e.to_string())?,
+            quantity,
+            unit_cost,
+            total_value,
+        });
+    }
+
+    Ok(valuation)
+}
+
+/// Stock value at cost for one location, or across all locations when none is given
+#[command]
+pub async fn get_inventory_valuation(
+    pool: State<'_, SqlitePool>,
+    location_id: Option<i64>,
+    session_token: String,
+) -> Result<InventoryValuation, String> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_inventory_valuation_internal(pool.inner(), organization_id, location_id).await
+}
+
+pub(crate) async fn get_dead_stock_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    days_without_sale: i32,
+    location_id: Option<i64>,
+    include_inactive: bool,
+) -> Result<DeadStockReport, String> {
+    if days_without_sale <= 0 {
+        return Err("days_without_sale must be at least 1".to_string());
+    }
+    let stock = match location_id {
+        Some(_) => location_stock_sql("p.id", "?1"),
+        None => "i.current_stock".to_string(),
+    };
+    // Voided and demo sales moved no stock; given a location, only its own sales count
+    let query = format!(
+        "WITH last_sales AS (
+             SELECT si.product_id, MAX(s.created_at) AS last_sale
+             FROM sale_items si
+             JOIN sales s ON s.id = si.sale_id
+             WHERE s.is_voided = 0 AND s.is_demo = 0 AND (?1 IS NULL OR s.location_id = ?1)
+             GROUP BY si.product_id
+         )
+         SELECT p.id, p.name, p.sku, p.cost_price, p.is_active, {} AS quantity, ls.last_sale
+         FROM products p
+         JOIN inventory i ON i.product_id = p.id
+         LEFT JOIN last_sales ls ON ls.product_id = p.id
+         WHERE (?3 = 1 OR p.is_active = 1)
+           AND (ls.last_sale IS NULL OR ls.last_sale < datetime('now', '-' || ?2 || ' days')){}",
+        stock,
+        tenancy::organization_scope("p.organization_id", organization_id)
+    );
+    let rows = sqlx::query(&query)
+        .bind(location_id)
+        .bind(days_without_sale)
+        .bind(include_inactive)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut lines = Vec::new();
+    for row in rows {
+        let quantity: i32 = row.try_get("quantity").map_err(|e| e.to_string())?;
+        if quantity <= 0 {
+            continue;
+        }
+        let unit_cost = Money::from_major(row.try_get("cost_price").map_err(|e| e.to_string())?);
+        lines.push(DeadStockLine {
+            product_id: row.try_get("id").map_err(|e| e.to_string())?,
+            product_name: row.try_get("name").map_err(|e| e.to_string())?,
+            sku: row.try_get("sku").map_err(|e| e.to_string())?,
+            quantity,
+            unit_cost,
+            total_value: unit_cost.times(quantity),
+            last_sale_date: row.try_get("last_sale").map_err(|e| e.to_string())?,
+            is_active: row.try_get("is_active").map_err(|e| e.to_string())?,
+        });
+    }
+    lines.sort_by(|a, b| b.total_value.cmp(&a.total_value).then_with(|| a.product_name.cmp(&b.product_name)));
+
+    Ok(DeadStockReport {
+        days_without_sale,
+        location_id,
+        total_value: lines.iter().map(|line| line.total_value).sum(),
+        lines,
+    })
+}
+
+/// Products with stock on hand and no sales in the last `days_without_sale` days, for one
+/// location or the whole business. Inactive products are left out unless asked for.
+#[command]
+pub async fn get_dead_stock(
+    pool: State<'_, SqlitePool>,
+    days_without_sale: i32,
+    location_id: Option<i64>,
+    include_inactive: Option<bool>,
+    session_token: String,
+) -> Result<DeadStockReport, String> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_dead_stock_internal(
+        pool.inner(),
+        organization_id,
+        days_without_sale,
+        location_id,
+        include_inactive.unwrap_or(false),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    async fn insert_sale(pool: &SqlitePool, number: &str, customer: &str, total: f64, status: &str, date: &str) {
+        sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, payment_status,
+                                cashier_id, customer_name, created_at)
+             VALUES (?1, ?2, ?2, 'cash', ?3, 1, ?4, ?5)",
+        )
+        .bind(number)
+        .bind(total)
+        .bind(status)
+        .bind(customer)
+        .bind(date)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_receivables_aging_buckets() {
+        let pool = test_pool().await;
+        insert_sale(&pool, "S-1", "Acme", 100.0, "Pending", "2024-06-30 10:00:00").await;
+        insert_sale(&pool, "S-2", "Acme", 50.0, "Partial", "2024-06-01 10:00:00").await;
+        insert_sale(&pool, "S-3", "Acme", 25.0, "Pending", "2024-03-01 10:00:00").await;
+        insert_sale(&pool, "S-4", "Bob", 40.0, "Pending", "2024-04-15 10:00:00").await;
+        insert_sale(&pool, "S-5", "Bob", 999.0, "Completed", "2024-05-01 10:00:00").await;
+
+        let report = get_receivables_aging_internal(&pool, DEFAULT_ORGANIZATION_ID, Some("2024-06-30".to_string()))
+            .await
+            .unwrap();
+
+        let acme = report.customers.iter().find(|c| c.customer_name == "Acme").unwrap();
+        assert_eq!(acme.current, Money::from_major(100.0));
+        assert_eq!(acme.days_1_30, Money::from_major(50.0));
+        assert_eq!(acme.days_over_90, Money::from_major(25.0));
+        assert_eq!(acme.open_sales, 3);
+
+        let bob = report.customers.iter().find(|c| c.customer_name == "Bob").unwrap();
+        assert_eq!(bob.days_31_60, Money::from_major(0.0));
+        assert_eq!(bob.days_61_90, Money::from_major(40.0));
+
+        assert_eq!(report.total_outstanding, Money::from_major(215.0));
+
+        // Another organization owes nothing on these sales
+        let other = get_receivables_aging_internal(&pool, 2, Some("2024-06-30".to_string()))
+            .await
+            .unwrap();
+        assert!(other.customers.is_empty());
+        assert_eq!(other.total_outstanding, Money::from_major(0.0));
+    }
+
+    #[tokio::test]
+    async fn test_employee_leaderboard_ranks_by_sales_and_counts_voids() {
+        let pool = test_pool().await;
+        let alice = insert_test_user(&pool, "alice").await;
+        let bob = insert_test_user(&pool, "bob").await;
+        let product = insert_test_product(&pool, "LB-1", 10.0, 10).await;
+        sqlx::query("UPDATE users SET first_name = 'Bob' WHERE id = ?1")
+            .bind(bob)
+            .execute(&pool)
+            .await
+            .unwrap();
+        // (cashier, quantity at 10.00 with cost 5.00, voided)
+        for (sale, (cashier, quantity, voided)) in
+            [(alice, 1, false), (bob, 2, false), (bob, 3, false), (bob, 1, true)].iter().enumerate()
+        {
+            let total = 10.0 * *quantity as f64;
+            let sale_id = sqlx::query(
+                "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id,
+                                    is_voided, created_at)
+                 VALUES (?1, ?2, ?2, 'cash', ?3, ?4, '2024-05-01 10:00:00')",
+            )
+            .bind(format!("LB-{}", sale))
+            .bind(total)
+            .bind(cashier)
+            .bind(voided)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+            sqlx::query(
+                "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, line_total, cost_price)
+                 VALUES (?1, ?2, ?3, 10, ?4, 5)",
+            )
+            .bind(sale_id)
+            .bind(product)
+            .bind(quantity)
+            .bind(total)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let board = get_employee_sales_performance_internal(&pool, DEFAULT_ORGANIZATION_ID, "2024-05-01", "2024-05-31")
+            .await
+            .unwrap();
+        assert_eq!(board.len(), 2);
+        let top = &board[0];
+        assert_eq!((top.cashier_id, top.cashier_name.as_str()), (bob, "Bob User"));
+        assert_eq!(top.total_sales, Money::from_major(50.0));
+        assert_eq!(top.total_profit, Money::from_major(25.0));
+        assert_eq!((top.transaction_count, top.items_sold, top.voided_count), (2, 5, 1));
+        assert_eq!((board[1].cashier_id, board[1].voided_count), (alice, 0));
+    }
+
+    #[tokio::test]
+    async fn test_void_analytics_groups_normalized_reasons() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let voids = [
+            ("S-1", 10.0, Some("  Customer   changed mind"), "2024-06-01 09:00:00"),
+            ("S-2", 15.0, Some("customer changed MIND"), "2024-06-01 12:00:00"),
+            ("S-3", 20.0, Some("Price error"), "2024-06-02 10:00:00"),
+            ("S-4", 5.0, None, "2024-06-02 11:00:00"),
+        ];
+        for (number, total, reason, voided_at) in voids {
+            sqlx::query(
+                "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id,
+                                    is_voided, voided_by, voided_at, void_reason, created_at)
+                 VALUES (?1, ?2, ?2, 'Cash', ?3, 1, ?3, ?4, ?5, ?4)",
+            )
+            .bind(number)
+            .bind(total)
+            .bind(cashier)
+            .bind(voided_at)
+            .bind(reason)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        insert_sale(&pool, "S-5", "Acme", 99.0, "Completed", "2024-06-01 10:00:00").await;
+
+        let analytics = get_void_analytics_internal(&pool, DEFAULT_ORGANIZATION_ID, "2024-06-01", "2024-06-30")
+            .await
+            .unwrap();
+
+        assert_eq!(analytics.total_voids, 4);
+        assert_eq!(analytics.total_voided_amount, Money::from_major(50.0));
+        assert_eq!(analytics.by_reason.len(), 3);
+        assert_eq!(analytics.by_reason[0].reason, "customer changed mind");
+        assert_eq!(analytics.by_reason[0].void_count, 2);
+        assert_eq!(analytics.by_reason[0].voided_amount, Money::from_major(25.0));
+        assert_eq!(analytics.by_cashier.len(), 1);
+        assert_eq!(analytics.by_cashier[0].user_name, "Test User");
+        assert_eq!(analytics.by_cashier[0].void_count, 4);
+        assert_eq!(analytics.daily.len(), 2);
+        assert_eq!(analytics.daily[1].date, "2024-06-02");
+        assert_eq!(analytics.daily[1].voided_amount, Money::from_major(25.0));
+    }
+
+    #[tokio::test]
+    async fn test_sales_date_range_uses_created_at_index() {
+        let pool = test_pool().await;
+        insert_sale(&pool, "S-1", "Acme", 10.0, "Completed", "2024-01-31 23:59:59").await;
+        insert_sale(&pool, "S-2", "Acme", 20.0, "Completed", "2024-02-01 00:00:00").await;
+
+        let (range, params) =
+            created_at_range("s.created_at", Some("2024-01-01"), Some("2024-01-31")).unwrap();
+        let sql = sales_totals_sql(&range);
+
+        let plan_sql = format!("EXPLAIN QUERY PLAN {}", sql);
+        let mut plan_query = sqlx::query(&plan_sql);
+        for param in &params {
+            plan_query = plan_query.bind(param);
+        }
+        let plan: Vec<String> = plan_query
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get("detail"))
+            .collect();
+        assert!(
+            plan.iter().any(|step| step.contains("USING COVERING INDEX idx_sales_created_report")),
+            "{:?}",
+            plan
+        );
+
+        let mut totals_query = sqlx::query(&sql);
+        for param in &params {
+            totals_query = totals_query.bind(param);
+        }
+        let total: Money = totals_query.fetch_one(&pool).await.unwrap().get("total_sales");
+        assert_eq!(total, Money::from_major(10.0));
+    }
+
+    #[tokio::test]
+    async fn test_category_performance_rolls_subcategories_into_parents() {
+        let pool = test_pool().await;
+        let category = |name: &str, parent: Option<i64>| {
+            let pool = pool.clone();
+            let name = name.to_string();
+            async move {
+                sqlx::query_scalar::<_, i64>("INSERT INTO categories (name, parent_id) VALUES (?1, ?2) RETURNING id")
+                    .bind(name)
+                    .bind(parent)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+            }
+        };
+        let drinks = category("Drinks", None).await;
+        let soda = category("Soda", Some(drinks)).await;
+        let cola = category("Cola", Some(soda)).await;
+        let juice = category("Juice", Some(drinks)).await;
+        insert_sale(&pool, "S-1", "Walk-in", 0.0, "completed", "2024-06-01 10:00:00").await;
+        let sale_id: i64 = sqlx::query_scalar("SELECT id FROM sales WHERE sale_number = 'S-1'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        for (sku, category_id, total) in [("COLA", Some(cola), 30.0), ("OJ", Some(juice), 20.0), ("WATER", Some(drinks), 5.0), ("MISC", None, 7.0)] {
+            let product = insert_test_product(&pool, sku, total, 0).await;
+            sqlx::query("UPDATE products SET category_id = ?1 WHERE id = ?2")
+                .bind(category_id)
+                .bind(product)
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query(
+                "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, line_total)
+                 VALUES (?1, ?2, 1, ?3, ?3)",
+            )
+            .bind(sale_id)
+            .bind(product)
+            .bind(total)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let summary = |rows: Vec<CategoryPerformance>| {
+            rows.into_iter()
+                .map(|row| (row.category, row.total_revenue.to_major(), row.has_subcategories))
+                .collect::<Vec<_>>()
+        };
+
+        let top = get_category_performance_internal(&pool, None, None, None, false).await.unwrap();
+        assert_eq!(
+            summary(top),
+            vec![("Drinks".to_string(), 55.0, true), ("Uncategorized".to_string(), 7.0, false)]
+        );
+        let drinks_level = get_category_performance_internal(&pool, None, None, Some(drinks), false).await.unwrap();
+        assert_eq!(
+            summary(drinks_level),
+            vec![
+                ("Soda".to_string(), 30.0, true),
+                ("Juice".to_string(), 20.0, false),
+                ("Drinks".to_string(), 5.0, false),
+            ]
+        );
+        let soda_level = get_category_performance_internal(&pool, None, None, Some(soda), false).await.unwrap();
+        assert_eq!(summary(soda_level), vec![("Cola".to_string(), 30.0, false)]);
+    }
+
+    async fn sell_on(pool: &SqlitePool, product_id: i64, quantity: i32, days_ago: i32, voided: bool) {
+        let sale_id = sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id, is_voided, created_at)
+             VALUES (?1, 0, 0, 'cash', 1, ?2, datetime('now', '-' || ?3 || ' days'))",
+        )
+        .bind(format!("S-{}-{}", product_id, days_ago))
+        .bind(voided)
+        .bind(days_ago)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        sqlx::query(
+            "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, line_total)
+             VALUES (?1, ?2, ?3, 1, ?3)",
+        )
+        .bind(sale_id)
+        .bind(product_id)
+        .bind(quantity)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dead_stock_lists_unsold_stock_by_capital_tied_up() {
+        let pool = test_pool().await;
+        insert_test_user(&pool, "cashier").await;
+        // Cost is half the price in the fixtures
+        let stale = insert_test_product(&pool, "STALE", 10.0, 4).await;
+        let never = insert_test_product(&pool, "NEVER", 20.0, 3).await;
+        let fresh = insert_test_product(&pool, "FRESH", 10.0, 8).await;
+        let voided = insert_test_product(&pool, "VOIDED", 2.0, 5).await;
+        insert_test_product(&pool, "EMPTY", 10.0, 0).await;
+        let retired = insert_test_product(&pool, "RETIRED", 100.0, 1).await;
+        sqlx::query("UPDATE products SET is_active = 0 WHERE id = ?1")
+            .bind(retired)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sell_on(&pool, stale, 1, 100, false).await;
+        sell_on(&pool, fresh, 1, 5, false).await;
+        sell_on(&pool, voided, 1, 5, true).await;
+
+        let report = get_dead_stock_internal(&pool, DEFAULT_ORGANIZATION_ID, 30, None, false).await.unwrap();
+        let lines: Vec<(&str, i32, Money, bool)> = report
+            .lines
+            .iter()
+            .map(|line| (line.sku.as_str(), line.quantity, line.total_value, line.last_sale_date.is_some()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("NEVER", 3, Money::from_major(30.0), false),
+                ("STALE", 4, Money::from_major(20.0), true),
+                ("VOIDED", 5, Money::from_major(5.0), false),
+            ]
+        );
+        assert_eq!(report.total_value, Money::from_major(55.0));
+
+        let with_inactive = get_dead_stock_internal(&pool, DEFAULT_ORGANIZATION_ID, 30, None, true).await.unwrap();
+        assert_eq!(with_inactive.lines[0].sku, "RETIRED");
+        assert!(!with_inactive.lines[0].is_active);
+        // A longer window than the stale sale's age clears it
+        let report = get_dead_stock_internal(&pool, DEFAULT_ORGANIZATION_ID, 120, None, false).await.unwrap();
+        assert!(report.lines.iter().all(|line| line.sku != "STALE"));
+        assert!(get_dead_stock_internal(&pool, DEFAULT_ORGANIZATION_ID, 0, None, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_commission_report_applies_the_employee_rate() {
+        let pool = test_pool().await;
+        let seller = insert_test_user(&pool, "seller").await;
+        let other = insert_test_user(&pool, "other").await;
+        let product = insert_test_product(&pool, "CM-1", 10.0, 10).await;
+        let employee_id = sqlx::query(
+            "INSERT INTO employees (user_id, employee_number, salary_type, commission_rate)
+             VALUES (?1, 'EMP00001', 'Commission', 5)",
+        )
+        .bind(seller)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        // (cashier, quantity at 10.00 with cost 5.00, voided, day)
+        let sales = [
+            (seller, 4, false, "2024-05-02"),
+            (seller, 6, false, "2024-05-20"),
+            (seller, 3, true, "2024-05-21"),
+            (seller, 9, false, "2024-06-01"),
+            (other, 7, false, "2024-05-10"),
+        ];
+        for (sale, (cashier, quantity, voided, day)) in sales.iter().enumerate() {
+            let total = 10.0 * *quantity as f64;
+            let sale_id = sqlx::query(
+                "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id,
+                                    is_voided, created_at)
+                 VALUES (?1, ?2, ?2, 'cash', ?3, ?4, ?5 || ' 10:00:00')",
+            )
+            .bind(format!("CM-{}", sale))
+            .bind(total)
+            .bind(cashier)
+            .bind(voided)
+            .bind(day)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+            sqlx::query(
+                "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, line_total, cost_price)
+                 VALUES (?1, ?2, ?3, 10, ?4, 5)",
+            )
+            .bind(sale_id)
+            .bind(product)
+            .bind(quantity)
+            .bind(total)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let report = get_commission_report_internal(&pool, DEFAULT_ORGANIZATION_ID, employee_id, "2024-05-01", "2024-05-31", None)
+            .await
+            .unwrap();
+        assert_eq!(report.basis, CommissionBasis::Revenue);
+        assert_eq!((report.sale_count, report.line_count), (2, 2));
+        assert_eq!(report.revenue, Money::from_major(100.0));
+        assert_eq!(report.profit, Money::from_major(50.0));
+        assert_eq!(report.commission, Money::from_major(5.0));
+
+        settings::set_setting(&pool, COMMISSION_BASIS_SETTING, STORE_SETTINGS, &serde_json::json!("profit"))
+            .await
+            .unwrap();
+        let report = get_commission_report_internal(&pool, DEFAULT_ORGANIZATION_ID, employee_id, "2024-05-01", "2024-05-31", None)
+            .await
+            .unwrap();
+        assert_eq!(report.commissionable_amount, Money::from_major(50.0));
+        assert_eq!(report.commission, Money::from_major(2.5));
+
+        let err = get_commission_report_internal(&pool, DEFAULT_ORGANIZATION_ID, 9999, "2024-05-01", "2024-05-31", None).await;
+        assert!(err.is_err());
+    }
+}
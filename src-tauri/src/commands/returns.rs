@@ -1,7 +1,27 @@
+use crate::audit;
+use crate::commands::supplier_credits;
+use crate::csv_export;
+use crate::currency;
+use crate::db_utils::{require_manager, Pagination};
+use crate::demo_mode;
+use crate::document_numbers::{is_duplicate_number, next_document_number, DOCUMENT_NUMBER_ATTEMPTS};
+use crate::error::{AppError, AppResult};
+use crate::location_stock::{post_transfer_leg, TransferLeg};
+use crate::margins::DEFAULT_LOCATION_ID;
+use crate::models::{Listing, Page, PageCursor};
+use crate::money::Money;
+use crate::plans;
+use crate::session::SESSION_MANAGER;
+use crate::sync_inbound;
+use crate::sync_outbox::{enqueue_change, SyncOperation};
+use crate::tenancy;
+use crate::validation::{for_product, validate_amount, validate_line_item, validate_required};
+use crate::webhooks::{self, EVENT_RETURN_CREATED};
 use serde::{Deserialize, Serialize};
-use sqlx::{Row, SqlitePool};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{QueryBuilder, Row, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
 use tauri::{command, State};
-use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ReturnType {
@@ -11,6 +31,24 @@ pub enum ReturnType {
     TransferReturn,  // Return items from another location
 }
 
+impl ReturnType {
+    /// Document sequence used for this type's return numbers (SR-, PR-, IR-, TR-)
+    pub fn doc_type(&self) -> &'static str {
+        match self {
+            ReturnType::SalesReturn => "sales_return",
+            ReturnType::PurchaseReturn => "purchase_return",
+            ReturnType::InventoryReturn => "inventory_return",
+            ReturnType::TransferReturn => "transfer_return",
+        }
+    }
+
+    /// Purchase, inventory and transfer returns wait for a manager's approval and move
+    /// stock when completed. Sales returns post as soon as they are rung up.
+    pub fn posts_on_completion(&self) -> bool {
+        !matches!(self, ReturnType::SalesReturn)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ReturnReason {
     Defective,
@@ -25,6 +63,25 @@ pub enum ReturnReason {
     Other,
 }
 
+impl ReturnReason {
+    /// The reason stored as `value`, if it is one this build knows
+    pub fn from_stored(value: &str) -> Option<Self> {
+        match value {
+            "Defective" => Some(ReturnReason::Defective),
+            "WrongItem" => Some(ReturnReason::WrongItem),
+            "Damaged" => Some(ReturnReason::Damaged),
+            "Expired" => Some(ReturnReason::Expired),
+            "Overstock" => Some(ReturnReason::Overstock),
+            "Recall" => Some(ReturnReason::Recall),
+            "CustomerDissatisfaction" => Some(ReturnReason::CustomerDissatisfaction),
+            "WrongShipment" => Some(ReturnReason::WrongShipment),
+            "QualityIssue" => Some(ReturnReason::QualityIssue),
+            "Other" => Some(ReturnReason::Other),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ReturnCondition {
     New,
@@ -35,6 +92,21 @@ pub enum ReturnCondition {
     Sealed,
 }
 
+impl ReturnCondition {
+    /// The condition stored as `value`, if it is one this build knows
+    pub fn from_stored(value: &str) -> Option<Self> {
+        match value {
+            "New" => Some(ReturnCondition::New),
+            "Opened" => Some(ReturnCondition::Opened),
+            "Used" => Some(ReturnCondition::Used),
+            "Damaged" => Some(ReturnCondition::Damaged),
+            "Defective" => Some(ReturnCondition::Defective),
+            "Sealed" => Some(ReturnCondition::Sealed),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum DispositionAction {
     Restock,           // Put back into inventory
@@ -45,12 +117,29 @@ pub enum DispositionAction {
     WriteOff,          // Financial write-off
 }
 
+impl DispositionAction {
+    /// The disposition stored as `value`, if it is one this build knows
+    pub fn from_stored(value: &str) -> Option<Self> {
+        match value {
+            "Restock" => Some(DispositionAction::Restock),
+            "Dispose" => Some(DispositionAction::Dispose),
+            "ReturnToSupplier" => Some(DispositionAction::ReturnToSupplier),
+            "Transfer" => Some(DispositionAction::Transfer),
+            "Repair" => Some(DispositionAction::Repair),
+            "WriteOff" => Some(DispositionAction::WriteOff),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ReturnItem {
     pub product_id: i64,
+    /// Set when the item was sold as a variant, so its stock goes back to that variant
+    pub product_variant_id: Option<i64>,
     pub quantity: i32,
-    pub unit_price: f64,
-    pub line_total: f64,
+    pub unit_price: Money,
+    pub line_total: Money,
     pub reason: ReturnReason,
     pub condition: ReturnCondition,
     pub disposition: DispositionAction,
@@ -73,9 +162,9 @@ pub struct ComprehensiveReturn {
     pub from_location_name: Option<String>,
     pub to_location_id: Option<i64>,
     pub to_location_name: Option<String>,
-    pub subtotal: f64,
-    pub tax_amount: f64,
-    pub total_amount: f64,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub total_amount: Money,
     pub refund_method: Option<String>,
     pub credit_method: Option<String>,
     pub expected_credit_date: Option<String>,
@@ -85,6 +174,7 @@ pub struct ComprehensiveReturn {
     pub approved_by: Option<i64>,
     pub approved_by_name: Option<String>,
     pub approved_at: Option<String>,
+    pub completed_by: Option<i64>,
     pub completed_at: Option<String>,
     pub reason: Option<String>,
     pub notes: Option<String>,
@@ -100,108 +190,574 @@ pub struct ComprehensiveReturnItem {
     pub product_id: i64,
     pub product_name: String,
     pub product_sku: String,
+    pub product_variant_id: Option<i64>,
+    pub variant_name: Option<String>,
     pub quantity: i32,
-    pub unit_price: f64,
-    pub line_total: f64,
+    pub unit_price: Money,
+    pub line_total: Money,
     pub reason: ReturnReason,
     pub condition: ReturnCondition,
     pub disposition: DispositionAction,
+    /// The stored reason, condition and disposition when they are values this build does not
+    /// know, e.g. from data imported from another system; the enums then hold the fallback
+    pub unrecognized_reason: Option<String>,
+    pub unrecognized_condition: Option<String>,
+    pub unrecognized_disposition: Option<String>,
     pub batch_number: Option<String>,
     pub expiry_date: Option<String>,
     pub notes: Option<String>,
     pub created_at: String,
 }
 
-#[command]
-pub async fn create_return(
-    pool: State<'_, SqlitePool>,
-    return_type: ReturnType,
-    reference_id: Option<i64>,
-    reference_number: Option<String>,
-    supplier_id: Option<i64>,
-    from_location_id: Option<i64>,
-    to_location_id: Option<i64>,
-    items: Vec<ReturnItem>,
-    subtotal: f64,
-    tax_amount: f64,
-    total_amount: f64,
-    refund_method: Option<String>,
-    credit_method: Option<String>,
-    expected_credit_date: Option<String>,
-    reason: Option<String>,
-    notes: Option<String>,
-    attachments: Option<Vec<String>>,
+/// Everything recorded for a new return, gathered from the `create_return` arguments
+#[derive(Debug)]
+pub(crate) struct NewReturn {
+    pub return_type: ReturnType,
+    pub reference_id: Option<i64>,
+    pub reference_number: Option<String>,
+    pub supplier_id: Option<i64>,
+    pub from_location_id: Option<i64>,
+    pub to_location_id: Option<i64>,
+    pub items: Vec<ReturnItem>,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub total_amount: Money,
+    pub refund_method: Option<String>,
+    pub credit_method: Option<String>,
+    pub expected_credit_date: Option<String>,
+    pub reason: Option<String>,
+    pub notes: Option<String>,
+    pub user_id: i64,
+    pub shift_id: Option<i64>,
+    pub organization_id: i64,
+}
+
+/// A return rung up while the till was offline, as the frontend queues it. The type, the
+/// amounts and who processed it are required, so a malformed payload is rejected instead
+/// of being saved as a zero-value sales return.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OfflineReturnPayload {
+    pub return_type: ReturnType,
+    pub reference_id: Option<i64>,
+    pub reference_number: Option<String>,
+    pub supplier_id: Option<i64>,
+    pub from_location_id: Option<i64>,
+    pub to_location_id: Option<i64>,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub total_amount: Money,
+    pub refund_method: Option<String>,
+    pub credit_method: Option<String>,
+    pub expected_credit_date: Option<String>,
+    /// `Pending` when not given
+    pub status: Option<String>,
+    pub processed_by: i64,
+    pub reason: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl OfflineReturnPayload {
+    pub fn from_json(return_data: serde_json::Value) -> AppResult<Self> {
+        serde_json::from_value(return_data)
+            .map_err(|e| AppError::validation("return_data", &format!("Invalid return payload: {}", e)))
+    }
+}
+
+/// A return pulled from the cloud: the offline payload plus the identity the cloud keeps.
+/// Only deserialized to check the record's shape; the merge works on the raw JSON.
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct RemoteReturnRecord {
+    id: i64,
+    return_number: String,
+    #[serde(flatten)]
+    payload: OfflineReturnPayload,
+}
+
+/// Units sold and already returned for one product on a sale
+#[derive(Debug, Clone, Copy, Default)]
+struct ReturnedQuantity {
+    sold: i64,
+    returned: i64,
+}
+
+impl ReturnedQuantity {
+    fn returnable(&self) -> i64 {
+        (self.sold - self.returned).max(0)
+    }
+}
+
+/// Sold and returned units per product on a sale. Returns count from both the
+/// comprehensive returns that reference the sale (unless rejected) and the legacy `returns` table.
+async fn returned_quantities(
+    conn: &mut SqliteConnection,
+    sale_id: i64,
+) -> AppResult<HashMap<i64, ReturnedQuantity>> {
+    let rows: Vec<(i64, i64, i64)> = sqlx::query_as(
+        r#"
+        WITH returned AS (
+            SELECT cri.product_id, cri.quantity
+            FROM comprehensive_return_items cri
+            JOIN comprehensive_returns cr ON cr.id = cri.return_id
+            WHERE cr.return_type = 'SalesReturn'
+              AND cr.reference_id = ?1
+              AND cr.status != 'Rejected'
+            UNION ALL
+            SELECT ri.product_id, ri.quantity
+            FROM return_items ri
+            JOIN returns r ON r.id = ri.return_id
+            WHERE r.original_sale_id = ?1
+        )
+        SELECT si.product_id,
+               SUM(si.quantity) as sold,
+               (SELECT COALESCE(SUM(quantity), 0) FROM returned
+                WHERE returned.product_id = si.product_id) as returned
+        FROM sale_items si
+        WHERE si.sale_id = ?1
+        GROUP BY si.product_id
+        "#,
+    )
+    .bind(sale_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(product_id, sold, returned)| (product_id, ReturnedQuantity { sold, returned }))
+        .collect())
+}
+
+/// Reject a sales return that takes back more of a product than is left on the original sale
+async fn check_returnable(
+    conn: &mut SqliteConnection,
+    sale_id: i64,
+    items: &[ReturnItem],
+) -> AppResult<()> {
+    let is_voided: bool = sqlx::query_scalar("SELECT is_voided FROM sales WHERE id = ?1")
+        .bind(sale_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(AppError::sale_not_found)?;
+    if is_voided {
+        return Err(AppError::validation(
+            "reference_id",
+            "A voided sale cannot be returned",
+        ));
+    }
+
+    let mut requested: HashMap<i64, i64> = HashMap::new();
+    for item in items {
+        *requested.entry(item.product_id).or_default() += i64::from(item.quantity);
+    }
+
+    let available = returned_quantities(conn, sale_id).await?;
+    for (product_id, quantity) in requested {
+        let remaining = available
+            .get(&product_id)
+            .map(ReturnedQuantity::returnable)
+            .unwrap_or(0);
+        if quantity > remaining {
+            return Err(AppError::validation(
+                "quantity",
+                &format!(
+                    "Only {} of product {} can still be returned on this sale, {} requested",
+                    remaining, product_id, quantity
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Stock a return line moves: its variant's when it was sold as one, otherwise the product's
+#[derive(Debug, Clone, Copy)]
+struct ReturnedStock {
+    product_id: i64,
+    product_variant_id: Option<i64>,
+}
+
+/// Move a returned line's stock and write its movement against the return
+async fn post_return_stock(
+    conn: &mut SqliteConnection,
+    return_id: i64,
+    stock: ReturnedStock,
+    movement_type: &str,
+    quantity_change: i32,
+    notes: &str,
     user_id: i64,
-    shift_id: Option<i64>,
-) -> Result<i64, String> {
-    let pool_ref = pool.inner();
+) -> AppResult<()> {
+    let mut notes = notes.to_string();
+    let previous_stock: i32 = match stock.product_variant_id {
+        Some(variant_id) => {
+            let previous_stock = sqlx::query_scalar(
+                "SELECT current_stock FROM variant_inventory WHERE product_variant_id = ?1",
+            )
+            .bind(variant_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::not_found("Variant inventory"))?;
+            sqlx::query(
+                "UPDATE variant_inventory SET
+                    current_stock = current_stock + ?1,
+                    available_stock = available_stock + ?1,
+                    last_updated = CURRENT_TIMESTAMP
+                 WHERE product_variant_id = ?2",
+            )
+            .bind(quantity_change)
+            .bind(variant_id)
+            .execute(&mut *conn)
+            .await?;
+            let sku: String = sqlx::query_scalar("SELECT sku FROM product_variants WHERE id = ?1")
+                .bind(variant_id)
+                .fetch_one(&mut *conn)
+                .await?;
+            notes.push_str(&format!(" (variant {})", sku));
+            previous_stock
+        }
+        None => {
+            let previous_stock =
+                sqlx::query_scalar("SELECT current_stock FROM inventory WHERE product_id = ?1")
+                    .bind(stock.product_id)
+                    .fetch_optional(&mut *conn)
+                    .await?
+                    .ok_or_else(|| AppError::inventory_not_found(stock.product_id))?;
+            sqlx::query(
+                "UPDATE inventory SET
+                    current_stock = current_stock + ?1,
+                    available_stock = available_stock + ?1,
+                    last_updated = CURRENT_TIMESTAMP
+                 WHERE product_id = ?2",
+            )
+            .bind(quantity_change)
+            .bind(stock.product_id)
+            .execute(&mut *conn)
+            .await?;
+            previous_stock
+        }
+    };
+
+    sqlx::query(
+        "INSERT INTO inventory_movements
+            (product_id, movement_type, quantity_change, previous_stock, new_stock,
+             reference_id, reference_type, notes, user_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'comprehensive_return', ?7, ?8)",
+    )
+    .bind(stock.product_id)
+    .bind(movement_type)
+    .bind(quantity_change)
+    .bind(previous_stock)
+    .bind(previous_stock + quantity_change)
+    .bind(return_id)
+    .bind(&notes)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Reject a variant that isn't one of the line's product
+async fn check_return_variant(conn: &mut SqliteConnection, item: &ReturnItem) -> AppResult<()> {
+    if let Some(variant_id) = item.product_variant_id {
+        let variant_product: Option<i64> =
+            sqlx::query_scalar("SELECT product_id FROM product_variants WHERE id = ?1")
+                .bind(variant_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+        if variant_product != Some(item.product_id) {
+            return Err(AppError::validation(
+                "product_variant_id",
+                &format!("Variant {} is not a variant of product {}", variant_id, item.product_id),
+            ));
+        }
+    }
+    Ok(())
+}
 
-    // Generate unique return number based on type
-    let prefix = match return_type {
-        ReturnType::SalesReturn => "SR",
-        ReturnType::PurchaseReturn => "PR",
-        ReturnType::InventoryReturn => "IR",
-        ReturnType::TransferReturn => "TR",
+/// A sales return's refund paid out during a shift
+struct RefundPayment<'a> {
+    return_id: i64,
+    return_number: &'a str,
+    shift_id: i64,
+    sale_id: Option<i64>,
+    method: &'a str,
+    amount: Money,
+    user_id: i64,
+}
+
+/// Pay a refund out of its shift. Cash leaves the drawer as a negative `refund` row, other
+/// methods are split out against the original sale, and the shift's returns total goes up.
+async fn pay_refund(
+    conn: &mut SqliteConnection,
+    refund: &RefundPayment<'_>,
+    currency: &str,
+) -> AppResult<()> {
+    if refund.method.eq_ignore_ascii_case("cash") {
+        sqlx::query(
+            "INSERT INTO cash_drawer_transactions (shift_id, transaction_type, amount, reason, user_id)
+             VALUES (?1, 'refund', ?2, ?3, ?4)",
+        )
+        .bind(refund.shift_id)
+        .bind(-refund.amount)
+        .bind(format!("Refund {}", refund.return_number))
+        .bind(refund.user_id)
+        .execute(&mut *conn)
+        .await?;
+    } else if let Some(sale_id) = refund.sale_id {
+        sqlx::query(
+            "INSERT INTO sale_payments (sale_id, payment_method, currency, amount, exchange_rate,
+                                        base_amount, return_id)
+             VALUES (?1, ?2, ?3, ?4, 1.0, ?4, ?5)",
+        )
+        .bind(sale_id)
+        .bind(refund.method)
+        .bind(currency)
+        .bind(-refund.amount)
+        .bind(refund.return_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query("UPDATE shifts SET total_returns = total_returns + ?1 WHERE id = ?2")
+        .bind(refund.amount)
+        .bind(refund.shift_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Undo `pay_refund` for a voided return with counter-entries, keeping the originals
+async fn reverse_refund(conn: &mut SqliteConnection, return_id: i64, user_id: i64) -> AppResult<()> {
+    let row = sqlx::query(
+        "SELECT return_number, return_type, shift_id, refund_method, total_amount
+         FROM comprehensive_returns WHERE id = ?1",
+    )
+    .bind(return_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let return_type: String = row.try_get("return_type")?;
+    let shift_id: Option<i64> = row.try_get("shift_id")?;
+    let method: Option<String> = row.try_get("refund_method")?;
+    let (Some(shift_id), Some(method)) = (shift_id, method) else {
+        return Ok(());
     };
-    
-    let uuid_str = Uuid::new_v4().to_string();
-    let return_number = format!(
-        "{}-{}",
-        prefix,
-        uuid_str.split('-').next().unwrap_or(&uuid_str[..8])
-    );
+    if return_type != "SalesReturn" {
+        return Ok(());
+    }
+    let return_number: String = row.try_get("return_number")?;
+    let amount: Money = row.try_get("total_amount")?;
 
-    // Start transaction
-    let mut tx = pool_ref
-        .begin()
-        .await
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    if method.eq_ignore_ascii_case("cash") {
+        sqlx::query(
+            "INSERT INTO cash_drawer_transactions (shift_id, transaction_type, amount, reason, user_id)
+             VALUES (?1, 'refund', ?2, ?3, ?4)",
+        )
+        .bind(shift_id)
+        .bind(amount)
+        .bind(format!("Refund {} voided", return_number))
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    } else {
+        sqlx::query(
+            "INSERT INTO sale_payments (sale_id, payment_method, currency, amount, exchange_rate,
+                                        base_amount, return_id)
+             SELECT sale_id, payment_method, currency, -amount, exchange_rate, -base_amount, return_id
+             FROM sale_payments WHERE return_id = ?1",
+        )
+        .bind(return_id)
+        .execute(&mut *conn)
+        .await?;
+    }
 
-    // Create comprehensive return record
-    let return_result = sqlx::query(
+    sqlx::query("UPDATE shifts SET total_returns = total_returns - ?1 WHERE id = ?2")
+        .bind(amount)
+        .bind(shift_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Move stock for every line of a return according to its disposition
+async fn post_return_items(conn: &mut SqliteConnection, return_id: i64, user_id: i64) -> AppResult<()> {
+    let rows = sqlx::query(
         r#"
-        INSERT INTO comprehensive_returns (
-            return_number, return_type, reference_id, reference_number, supplier_id,
-            from_location_id, to_location_id, subtotal, tax_amount, total_amount,
-            refund_method, credit_method, expected_credit_date, status, processed_by,
-            reason, notes, shift_id
-        )
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
+        SELECT cri.product_id, cri.product_variant_id, cri.quantity, cri.reason, cri.disposition,
+               cr.from_location_id, cr.to_location_id
+        FROM comprehensive_return_items cri
+        JOIN comprehensive_returns cr ON cr.id = cri.return_id
+        WHERE cri.return_id = ?1
+        ORDER BY cri.id
         "#
     )
-    .bind(&return_number)
-    .bind(format!("{:?}", return_type))
-    .bind(reference_id)
-    .bind(&reference_number)
-    .bind(supplier_id)
-    .bind(from_location_id)
-    .bind(to_location_id)
-    .bind(subtotal)
-    .bind(tax_amount)
-    .bind(total_amount)
-    .bind(&refund_method)
-    .bind(&credit_method)
-    .bind(&expected_credit_date)
-    .bind("Pending")
-    .bind(user_id)
-    .bind(&reason)
-    .bind(&notes)
-    .bind(shift_id)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| format!("Failed to create return: {}", e))?;
+    .bind(return_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for row in rows {
+        let quantity: i32 = row.try_get("quantity")?;
+        let reason: String = row.try_get("reason")?;
+        let disposition: String = row.try_get("disposition")?;
+        let from_location_id: Option<i64> = row.try_get("from_location_id")?;
+        let to_location_id: Option<i64> = row.try_get("to_location_id")?;
+
+        let posting = match disposition.as_str() {
+            "Restock" => Some(("return", quantity, format!("Return restocked: {}", reason))),
+            "Dispose" | "WriteOff" => {
+                Some(("damage", -quantity, format!("Item disposed: {}", disposition)))
+            }
+            "Transfer" => {
+                // Out of one location and into the other, so the stock is still on hand
+                if let (Some(from_loc), Some(to_loc)) = (from_location_id, to_location_id) {
+                    let notes = format!("Transfer from location {} to {}", from_loc, to_loc);
+                    for (location_id, quantity_change) in [(from_loc, -quantity), (to_loc, quantity)] {
+                        let leg = TransferLeg {
+                            product_id: row.try_get("product_id")?,
+                            location_id,
+                            quantity_change,
+                            reference_type: "comprehensive_return",
+                            reference_id: return_id,
+                            notes: &notes,
+                            user_id,
+                        };
+                        post_transfer_leg(conn, &leg).await?;
+                    }
+                }
+                None
+            }
+            "ReturnToSupplier" => {
+                Some(("return", -quantity, format!("Return to supplier: {}", reason)))
+            }
+            "Repair" => Some(("adjustment", -quantity, format!("Item sent for repair: {}", reason))),
+            _ => None,
+        };
+        if let Some((movement_type, quantity_change, notes)) = posting {
+            let stock = ReturnedStock {
+                product_id: row.try_get("product_id")?,
+                product_variant_id: row.try_get("product_variant_id")?,
+            };
+            post_return_stock(
+                conn,
+                return_id,
+                stock,
+                movement_type,
+                quantity_change,
+                &notes,
+                user_id,
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) async fn create_return_internal(pool: &SqlitePool, request: NewReturn) -> AppResult<i64> {
+    for item in &request.items {
+        validate_line_item(item.product_id, item.quantity, item.unit_price)?;
+        for_product(item.product_id, validate_amount(item.line_total, "line_total"))?;
+    }
+
+    let currency = currency::store_currency(pool).await?;
+    let is_demo = demo_mode::is_enabled(pool).await?;
+
+    // Start transaction
+    let mut tx = pool.begin().await?;
+
+    if matches!(request.return_type, ReturnType::SalesReturn) {
+        // Only the original sale bounds what can be taken back and refunded
+        let sale_id = request.reference_id.ok_or_else(|| {
+            AppError::validation("reference_id", "A sales return must reference the original sale")
+        })?;
+        tenancy::require_in_organization(
+            &mut tx,
+            "sales",
+            sale_id,
+            request.organization_id,
+            "Sale",
+        )
+        .await?;
+        check_returnable(&mut tx, sale_id, &request.items).await?;
+    }
+    if let Some(supplier_id) = request.supplier_id {
+        tenancy::require_in_organization(
+            &mut tx,
+            "suppliers",
+            supplier_id,
+            request.organization_id,
+            "Supplier",
+        )
+        .await?;
+    }
+    for item in &request.items {
+        tenancy::require_in_organization(
+            &mut tx,
+            "products",
+            item.product_id,
+            request.organization_id,
+            "Product",
+        )
+        .await?;
+        check_return_variant(&mut tx, item).await?;
+    }
 
-    let return_id = return_result.last_insert_rowid();
+    // Create comprehensive return record under the next number for its type,
+    // drawing again if that number is already taken
+    let mut attempts = 0;
+    let (return_id, return_number) = loop {
+        attempts += 1;
+        let return_number = next_document_number(&mut *tx, request.return_type.doc_type()).await?;
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO comprehensive_returns (
+                return_number, return_type, reference_id, reference_number, supplier_id,
+                from_location_id, to_location_id, subtotal, tax_amount, total_amount,
+                refund_method, credit_method, expected_credit_date, status, processed_by,
+                reason, notes, shift_id, is_demo, organization_id
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)
+            "#
+        )
+        .bind(&return_number)
+        .bind(format!("{:?}", request.return_type))
+        .bind(request.reference_id)
+        .bind(&request.reference_number)
+        .bind(request.supplier_id)
+        .bind(request.from_location_id)
+        .bind(request.to_location_id)
+        .bind(request.subtotal)
+        .bind(request.tax_amount)
+        .bind(request.total_amount)
+        .bind(&request.refund_method)
+        .bind(&request.credit_method)
+        .bind(&request.expected_credit_date)
+        .bind("Pending")
+        .bind(request.user_id)
+        .bind(&request.reason)
+        .bind(&request.notes)
+        .bind(request.shift_id)
+        .bind(is_demo)
+        .bind(request.organization_id)
+        .execute(&mut *tx)
+        .await;
+
+        match inserted {
+            Ok(result) => break (result.last_insert_rowid(), return_number),
+            Err(e)
+                if attempts < DOCUMENT_NUMBER_ATTEMPTS
+                    && is_duplicate_number(&e, "comprehensive_returns", "return_number") => {}
+            Err(e) => return Err(e.into()),
+        }
+    };
 
     // Create return items
-    for item in &items {
-        let item_result = sqlx::query(
+    for item in &request.items {
+        sqlx::query(
             r#"
             INSERT INTO comprehensive_return_items (
                 return_id, product_id, quantity, unit_price, line_total,
-                reason, condition, disposition, batch_number, expiry_date, notes
+                reason, condition, disposition, batch_number, expiry_date, notes,
+                product_variant_id
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
             "#
         )
         .bind(return_id)
@@ -215,189 +771,177 @@ pub async fn create_return(
         .bind(&item.batch_number)
         .bind(&item.expiry_date)
         .bind(&item.notes)
+        .bind(item.product_variant_id)
         .execute(&mut *tx)
-        .await
-        .map_err(|e| format!("Failed to create return item: {}", e))?;
-
-        // Update inventory based on disposition
-        match item.disposition {
-            DispositionAction::Restock => {
-                // Add back to inventory
-                sqlx::query(
-                    "UPDATE products SET current_stock = current_stock + ?1 WHERE id = ?2"
-                )
-                .bind(item.quantity)
-                .bind(item.product_id)
-                .execute(&mut *tx)
-                .await
-                .map_err(|e| format!("Failed to restock item: {}", e))?;
-
-                // Create inventory movement record
-                sqlx::query(
-                    r#"
-                    INSERT INTO inventory_movements (
-                        product_id, movement_type, quantity, reference_type, reference_id,
-                        notes, created_by
-                    )
-                    VALUES (?1, 'return', ?2, 'comprehensive_return', ?3, ?4, ?5)
-                    "#
-                )
-                .bind(item.product_id)
-                .bind(item.quantity)
-                .bind(return_id)
-                .bind(format!("Return restocked: {:?}", item.reason))
-                .bind(user_id)
-                .execute(&mut *tx)
-                .await
-                .map_err(|e| format!("Failed to create inventory movement: {}", e))?;
-            }
-            DispositionAction::Dispose | DispositionAction::WriteOff => {
-                // Remove from inventory
-                sqlx::query(
-                    "UPDATE products SET current_stock = current_stock - ?1 WHERE id = ?2"
-                )
-                .bind(item.quantity)
-                .bind(item.product_id)
-                .execute(&mut *tx)
-                .await
-                .map_err(|e| format!("Failed to remove disposed item: {}", e))?;
-
-                // Create inventory movement record
-                sqlx::query(
-                    r#"
-                    INSERT INTO inventory_movements (
-                        product_id, movement_type, quantity, reference_type, reference_id,
-                        notes, created_by
-                    )
-                    VALUES (?1, 'damage', ?2, 'comprehensive_return', ?3, ?4, ?5)
-                    "#
-                )
-                .bind(item.product_id)
-                .bind(-item.quantity)
-                .bind(return_id)
-                .bind(format!("Item disposed: {:?}", item.disposition))
-                .bind(user_id)
-                .execute(&mut *tx)
-                .await
-                .map_err(|e| format!("Failed to create inventory movement: {}", e))?;
-            }
-            DispositionAction::Transfer => {
-                // Handle transfer between locations
-                if let (Some(from_loc), Some(to_loc)) = (from_location_id, to_location_id) {
-                    // Remove from source location
-                    sqlx::query(
-                        "UPDATE products SET current_stock = current_stock - ?1 WHERE id = ?2"
-                    )
-                    .bind(item.quantity)
-                    .bind(item.product_id)
-                    .execute(&mut *tx)
-                    .await
-                    .map_err(|e| format!("Failed to remove from source location: {}", e))?;
-
-                    // Add to destination location (would need location-specific inventory)
-                    // For now, just create movement record
-                    sqlx::query(
-                        r#"
-                        INSERT INTO inventory_movements (
-                            product_id, movement_type, quantity, reference_type, reference_id,
-                            notes, created_by
-                        )
-                        VALUES (?1, 'transfer', ?2, 'comprehensive_return', ?3, ?4, ?5)
-                        "#
-                    )
-                    .bind(item.product_id)
-                    .bind(item.quantity)
-                    .bind(return_id)
-                    .bind(format!("Transfer from location {} to {}", from_loc, to_loc))
-                    .bind(user_id)
-                    .execute(&mut *tx)
-                    .await
-                    .map_err(|e| format!("Failed to create transfer movement: {}", e))?;
-                }
-            }
-            DispositionAction::ReturnToSupplier => {
-                // Create supplier return record or update purchase order
-                // For now, just create movement record
-                sqlx::query(
-                    r#"
-                    INSERT INTO inventory_movements (
-                        product_id, movement_type, quantity, reference_type, reference_id,
-                        notes, created_by
-                    )
-                    VALUES (?1, 'return', ?2, 'comprehensive_return', ?3, ?4, ?5)
-                    "#
-                )
-                .bind(item.product_id)
-                .bind(-item.quantity)
-                .bind(return_id)
-                .bind(format!("Return to supplier: {:?}", item.reason))
-                .bind(user_id)
-                .execute(&mut *tx)
-                .await
-                .map_err(|e| format!("Failed to create supplier return movement: {}", e))?;
-            }
-            DispositionAction::Repair => {
-                // Move to repair status
-                sqlx::query(
-                    "UPDATE products SET current_stock = current_stock - ?1 WHERE id = ?2"
-                )
-                .bind(item.quantity)
-                .bind(item.product_id)
-                .execute(&mut *tx)
-                .await
-                .map_err(|e| format!("Failed to move item to repair: {}", e))?;
-
-                sqlx::query(
-                    r#"
-                    INSERT INTO inventory_movements (
-                        product_id, movement_type, quantity, reference_type, reference_id,
-                        notes, created_by
-                    )
-                    VALUES (?1, 'adjustment', ?2, 'comprehensive_return', ?3, ?4, ?5)
-                    "#
-                )
-                .bind(item.product_id)
-                .bind(-item.quantity)
-                .bind(return_id)
-                .bind(format!("Item sent for repair: {:?}", item.reason))
-                .bind(user_id)
-                .execute(&mut *tx)
-                .await
-                .map_err(|e| format!("Failed to create repair movement: {}", e))?;
-            }
-        }
+        .await?;
+    }
+
+    // Returns that wait for approval move stock when they are completed instead
+    if !request.return_type.posts_on_completion() {
+        post_return_items(&mut tx, return_id, request.user_id).await?;
+    }
+
+    if let (ReturnType::SalesReturn, Some(shift_id), Some(method)) = (
+        &request.return_type,
+        request.shift_id,
+        request.refund_method.as_deref(),
+    ) {
+        let refund = RefundPayment {
+            return_id,
+            return_number: &return_number,
+            shift_id,
+            sale_id: request.reference_id,
+            method,
+            amount: request.total_amount,
+            user_id: request.user_id,
+        };
+        pay_refund(&mut tx, &refund, &currency.code).await?;
     }
 
+    enqueue_change(&mut tx, "return", return_id, SyncOperation::Create).await?;
+
     // Commit transaction
-    tx.commit()
-        .await
-        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    tx.commit().await?;
+    webhooks::emit_entity_event(pool, EVENT_RETURN_CREATED, "return", return_id).await;
 
     Ok(return_id)
 }
 
 #[command]
-pub async fn get_returns(
+pub async fn create_return(
     pool: State<'_, SqlitePool>,
-    return_type: Option<String>,
-    status: Option<String>,
-    start_date: Option<String>,
-    end_date: Option<String>,
-    limit: Option<i32>,
-    offset: Option<i32>,
-) -> Result<Vec<ComprehensiveReturn>, String> {
-    let pool_ref = pool.inner();
+    return_type: ReturnType,
+    reference_id: Option<i64>,
+    reference_number: Option<String>,
+    supplier_id: Option<i64>,
+    from_location_id: Option<i64>,
+    to_location_id: Option<i64>,
+    items: Vec<ReturnItem>,
+    subtotal: Money,
+    tax_amount: Money,
+    total_amount: Money,
+    refund_method: Option<String>,
+    credit_method: Option<String>,
+    expected_credit_date: Option<String>,
+    reason: Option<String>,
+    notes: Option<String>,
+    attachments: Option<Vec<String>>,
+    shift_id: Option<i64>,
+    session_token: String,
+) -> Result<i64, AppError> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    let user_id = SESSION_MANAGER.validate_session(&session_token)?.user_id;
+    let request = NewReturn {
+        return_type,
+        reference_id,
+        reference_number,
+        supplier_id,
+        from_location_id,
+        to_location_id,
+        items,
+        subtotal,
+        tax_amount,
+        total_amount,
+        refund_method,
+        credit_method,
+        expected_credit_date,
+        reason,
+        notes,
+        user_id,
+        shift_id,
+        organization_id,
+    };
+    create_return_internal(pool.inner(), request).await
+}
+
+/// Push the `AND ...` conditions for the returns list, shared by the page query and its count.
+/// Every value is bound, so each combination of filters prepares one cacheable statement.
+fn push_returns_filter<'a>(query: &mut QueryBuilder<'a, Sqlite>, filter: &ReturnsQuery<'a>) {
+    query.push(tenancy::organization_scope(
+        "cr.organization_id",
+        filter.organization_id,
+    ));
+
+    if let Some(rt) = filter.return_type.filter(|rt| !rt.is_empty()) {
+        query.push(" AND cr.return_type = ").push_bind(rt);
+    }
+
+    if let Some(st) = filter.status.filter(|st| !st.is_empty()) {
+        query.push(" AND cr.status = ").push_bind(st);
+    }
+
+    if let Some(start) = filter.start_date.filter(|start| !start.is_empty()) {
+        query.push(" AND DATE(cr.created_at) >= ").push_bind(start);
+    }
+
+    if let Some(end) = filter.end_date.filter(|end| !end.is_empty()) {
+        query.push(" AND DATE(cr.created_at) <= ").push_bind(end);
+    }
 
-    let limit = limit.unwrap_or(100);
-    let offset = offset.unwrap_or(0);
+    // Matches the return's own numbers, its supplier, the customer of the original
+    // sale, or any product it contains by name or SKU
+    if let Some(term) = filter.search.map(str::trim).filter(|term| !term.is_empty()) {
+        let pattern = format!("%{}%", term);
+        query
+            .push(" AND (cr.return_number LIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR cr.reference_number LIKE ")
+            .push_bind(pattern.clone())
+            .push(
+                " OR EXISTS (SELECT 1 FROM suppliers sup
+                              WHERE sup.id = cr.supplier_id AND sup.company_name LIKE ",
+            )
+            .push_bind(pattern.clone())
+            .push(
+                ") OR EXISTS (SELECT 1 FROM sales sa
+                              WHERE cr.return_type = 'SalesReturn' AND sa.id = cr.reference_id
+                                AND sa.customer_name LIKE ",
+            )
+            .push_bind(pattern.clone())
+            .push(
+                ") OR EXISTS (SELECT 1 FROM comprehensive_return_items sri
+                              JOIN products sp ON sp.id = sri.product_id
+                              WHERE sri.return_id = cr.id
+                                AND (sp.name LIKE ",
+            )
+            .push_bind(pattern.clone())
+            .push(" OR sp.sku LIKE ")
+            .push_bind(pattern)
+            .push(")))");
+    }
+}
+
+/// `ORDER BY` for the returns list: `date` (the default), `amount` or `status`,
+/// newest or largest first unless `descending` is false
+fn returns_order_by(sort_by: Option<&str>, descending: Option<bool>) -> AppResult<String> {
+    let column = match sort_by.filter(|sort| !sort.is_empty()).unwrap_or("date") {
+        "date" => "cr.created_at",
+        "amount" => "cr.total_amount",
+        "status" => "cr.status",
+        other => {
+            return Err(AppError::validation(
+                "sort_by",
+                &format!("Cannot sort returns by '{}'", other),
+            ))
+        }
+    };
+    let direction = if descending.unwrap_or(true) { "DESC" } else { "ASC" };
+    Ok(format!("{} {}, cr.id {}", column, direction, direction))
+}
 
-    let mut query = String::from(
+async fn fetch_returns(
+    pool_ref: &SqlitePool,
+    filter: &ReturnsQuery<'_>,
+    order_by: &str,
+    pagination: &Pagination,
+) -> AppResult<Vec<ComprehensiveReturn>> {
+    let mut query = QueryBuilder::<Sqlite>::new(
         r#"
         SELECT 
             cr.id, cr.return_number, cr.return_type, cr.reference_id, cr.reference_number,
             cr.supplier_id, cr.from_location_id, cr.to_location_id, cr.subtotal, cr.tax_amount,
             cr.total_amount, cr.refund_method, cr.credit_method, cr.expected_credit_date,
-            cr.status, cr.processed_by, cr.approved_by, cr.approved_at, cr.completed_at,
+            cr.status, cr.processed_by, cr.approved_by, cr.approved_at, cr.completed_by, cr.completed_at,
             cr.reason, cr.notes, cr.created_at, cr.updated_at,
             u.first_name || ' ' || u.last_name as processed_by_name,
             u2.first_name || ' ' || u2.last_name as approved_by_name,
@@ -411,66 +955,20 @@ pub async fn get_returns(
         LEFT JOIN suppliers s ON cr.supplier_id = s.id
         LEFT JOIN locations l1 ON cr.from_location_id = l1.id
         LEFT JOIN locations l2 ON cr.to_location_id = l2.id
-        WHERE 1=1
-        "#
+        WHERE 1=1"#,
     );
+    push_returns_filter(&mut query, filter);
+    pagination.push_after(&mut query, "cr.created_at", "cr.id");
+    query.push(format!(" ORDER BY {}", order_by));
+    pagination.push_limit(&mut query);
 
-    let mut params: Vec<String> = Vec::new();
-    let mut param_count = 0;
-
-    if let Some(rt) = return_type {
-        if !rt.is_empty() {
-            param_count += 1;
-            query.push_str(&format!(" AND cr.return_type = ?{}", param_count));
-            params.push(rt);
-        }
-    }
-
-    if let Some(st) = status {
-        if !st.is_empty() {
-            param_count += 1;
-            query.push_str(&format!(" AND cr.status = ?{}", param_count));
-            params.push(st);
-        }
-    }
-
-    if let Some(ref start) = start_date {
-        if !start.is_empty() {
-            param_count += 1;
-            query.push_str(&format!(" AND DATE(cr.created_at) >= ?{}", param_count));
-            params.push(start.clone());
-        }
-    }
-
-    if let Some(ref end) = end_date {
-        if !end.is_empty() {
-            param_count += 1;
-            query.push_str(&format!(" AND DATE(cr.created_at) <= ?{}", param_count));
-            params.push(end.clone());
-        }
-    }
-
-    query.push_str(" ORDER BY cr.created_at DESC");
-    query.push_str(&format!(" LIMIT ?{}", param_count + 1));
-    query.push_str(&format!(" OFFSET ?{}", param_count + 2));
-    params.push(limit.to_string());
-    params.push(offset.to_string());
-
-    let mut sql_query = sqlx::query(&query);
-    for param in &params {
-        sql_query = sql_query.bind(param);
-    }
-
-    let rows = sql_query
-        .fetch_all(pool_ref)
-        .await
-        .map_err(|e| format!("Failed to fetch returns: {}", e))?;
+    let rows = query.build().fetch_all(pool_ref).await?;
 
     let mut returns = Vec::new();
     for row in rows {
         let return_record = ComprehensiveReturn {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            return_number: row.try_get("return_number").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            return_number: row.try_get("return_number")?,
             return_type: row.try_get::<String, _>("return_type").unwrap_or_default(),
             reference_id: row.try_get("reference_id").ok(),
             reference_number: row.try_get("reference_number").ok(),
@@ -480,24 +978,25 @@ pub async fn get_returns(
             from_location_name: row.try_get("from_location_name").ok(),
             to_location_id: row.try_get("to_location_id").ok(),
             to_location_name: row.try_get("to_location_name").ok(),
-            subtotal: row.try_get("subtotal").map_err(|e| e.to_string())?,
-            tax_amount: row.try_get("tax_amount").map_err(|e| e.to_string())?,
-            total_amount: row.try_get("total_amount").map_err(|e| e.to_string())?,
+            subtotal: row.try_get("subtotal")?,
+            tax_amount: row.try_get("tax_amount")?,
+            total_amount: row.try_get("total_amount")?,
             refund_method: row.try_get("refund_method").ok(),
             credit_method: row.try_get("credit_method").ok(),
             expected_credit_date: row.try_get("expected_credit_date").ok(),
-            status: row.try_get("status").map_err(|e| e.to_string())?,
-            processed_by: row.try_get("processed_by").map_err(|e| e.to_string())?,
+            status: row.try_get("status")?,
+            processed_by: row.try_get("processed_by")?,
             processed_by_name: row.try_get("processed_by_name").ok(),
             approved_by: row.try_get("approved_by").ok(),
             approved_by_name: row.try_get("approved_by_name").ok(),
             approved_at: row.try_get("approved_at").ok(),
-            completed_at: row.try_get("completed_at").ok(),
+            completed_by: row.try_get("completed_by").ok().flatten(),
+        completed_at: row.try_get("completed_at").ok(),
             reason: row.try_get("reason").ok(),
             notes: row.try_get("notes").ok(),
-            items_count: row.try_get("items_count").map_err(|e| e.to_string())?,
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-            updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
+            items_count: row.try_get("items_count")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
         };
         returns.push(return_record);
     }
@@ -505,76 +1004,179 @@ pub async fn get_returns(
     Ok(returns)
 }
 
+async fn count_returns(pool_ref: &SqlitePool, filter: &ReturnsQuery<'_>) -> AppResult<i64> {
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM comprehensive_returns cr WHERE 1=1");
+    push_returns_filter(&mut query, filter);
+    Ok(query.build_query_scalar::<i64>().fetch_one(pool_ref).await?)
+}
+
+/// Returns, newest first unless sorted otherwise. When sorted by date, newest first, the
+/// previous page's `next_cursor` can be passed as `after_id`/`after_created_at` to page by
+/// keyset; with `envelope` the reply is a `Page` carrying the total count and that cursor.
 #[command]
-pub async fn get_return_items(
+#[allow(clippy::too_many_arguments)]
+pub async fn get_returns(
     pool: State<'_, SqlitePool>,
-    return_id: i64,
-) -> Result<Vec<ComprehensiveReturnItem>, String> {
-    let pool_ref = pool.inner();
+    return_type: Option<String>,
+    status: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    search: Option<String>,
+    sort_by: Option<String>,
+    sort_desc: Option<bool>,
+    limit: Option<i32>,
+    offset: Option<i32>,
+    after_id: Option<i64>,
+    after_created_at: Option<String>,
+    envelope: Option<bool>,
+    session_token: String,
+) -> Result<Listing<ComprehensiveReturn>, AppError> {
+    let filter = ReturnsQuery {
+        return_type: return_type.as_deref(),
+        status: status.as_deref(),
+        start_date: start_date.as_deref(),
+        end_date: end_date.as_deref(),
+        search: search.as_deref(),
+        sort_by: sort_by.as_deref(),
+        sort_desc,
+        organization_id: tenancy::active_organization(&session_token)?,
+    };
+    let pagination = Pagination::from_params(i64::from(limit.unwrap_or(100)), offset, after_id, after_created_at)?;
+    let page = get_returns_internal(pool.inner(), &filter, &pagination, envelope.unwrap_or(false)).await?;
+    Ok(Listing::new(page, envelope))
+}
+
+/// Filters, search and sort accepted by the returns list
+#[derive(Debug, Default)]
+pub(crate) struct ReturnsQuery<'a> {
+    pub return_type: Option<&'a str>,
+    pub status: Option<&'a str>,
+    pub start_date: Option<&'a str>,
+    pub end_date: Option<&'a str>,
+    pub search: Option<&'a str>,
+    pub sort_by: Option<&'a str>,
+    pub sort_desc: Option<bool>,
+    pub organization_id: i64,
+}
+
+/// One page of returns. The total is only counted when `with_total` is set.
+pub(crate) async fn get_returns_internal(
+    pool_ref: &SqlitePool,
+    query: &ReturnsQuery<'_>,
+    pagination: &Pagination,
+    with_total: bool,
+) -> AppResult<Page<ComprehensiveReturn>> {
+    let order_by = returns_order_by(query.sort_by, query.sort_desc)?;
+    // A cursor marks a place in the newest-first order only
+    let newest_first = query.sort_by.filter(|sort| !sort.is_empty()).unwrap_or("date") == "date"
+        && query.sort_desc.unwrap_or(true);
+    if pagination.is_keyset() && !newest_first {
+        return Err(AppError::validation(
+            "after_id",
+            "A cursor can only be used when sorting by date, newest first",
+        ));
+    }
+    let items = fetch_returns(pool_ref, query, &order_by, pagination).await?;
+    let total_count = if with_total { count_returns(pool_ref, query).await? } else { 0 };
+    Ok(pagination.into_page(items, total_count, |item| PageCursor {
+        created_at: item.created_at.clone(),
+        id: item.id,
+    }))
+}
+
+/// A return line's stored enum column, or `fallback` together with the stored text when it is
+/// not a value this build knows, so imported data is kept rather than silently coerced
+fn stored_variant<T>(
+    row: &SqliteRow,
+    column: &str,
+    return_item_id: i64,
+    from_stored: fn(&str) -> Option<T>,
+    fallback: T,
+) -> AppResult<(T, Option<String>)> {
+    let stored: Option<String> = row.try_get(column)?;
+    match stored.as_deref().filter(|value| !value.is_empty()) {
+        None => Ok((fallback, None)),
+        Some(value) => match from_stored(value) {
+            Some(variant) => Ok((variant, None)),
+            None => {
+                eprintln!(
+                    "⚠️  Warning: return item {} has unrecognized {} '{}'",
+                    return_item_id, column, value
+                );
+                Ok((fallback, Some(value.to_string())))
+            }
+        },
+    }
+}
 
+pub(crate) async fn get_return_items_internal(
+    pool_ref: &SqlitePool,
+    return_id: i64,
+) -> AppResult<Vec<ComprehensiveReturnItem>> {
     let rows = sqlx::query(
         r#"
         SELECT 
             cri.id, cri.return_id, cri.product_id, cri.quantity, cri.unit_price, cri.line_total,
             cri.reason, cri.condition, cri.disposition, cri.batch_number, cri.expiry_date,
-            cri.notes, cri.created_at,
-            p.name as product_name, p.sku as product_sku
+            cri.notes, cri.created_at, cri.product_variant_id,
+            p.name as product_name, p.sku as product_sku,
+            pv.variant_name
         FROM comprehensive_return_items cri
         JOIN products p ON cri.product_id = p.id
+        LEFT JOIN product_variants pv ON pv.id = cri.product_variant_id
         WHERE cri.return_id = ?1
         ORDER BY cri.created_at
         "#
     )
     .bind(return_id)
     .fetch_all(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to fetch return items: {}", e))?;
+    .await?;
 
     let mut items = Vec::new();
     for row in rows {
+        let return_item_id: i64 = row.try_get("id")?;
+        let (reason, unrecognized_reason) = stored_variant(
+            &row,
+            "reason",
+            return_item_id,
+            ReturnReason::from_stored,
+            ReturnReason::Other,
+        )?;
+        let (condition, unrecognized_condition) = stored_variant(
+            &row,
+            "condition",
+            return_item_id,
+            ReturnCondition::from_stored,
+            ReturnCondition::New,
+        )?;
+        let (disposition, unrecognized_disposition) = stored_variant(
+            &row,
+            "disposition",
+            return_item_id,
+            DispositionAction::from_stored,
+            DispositionAction::Restock,
+        )?;
         let item = ComprehensiveReturnItem {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            return_id: row.try_get("return_id").map_err(|e| e.to_string())?,
-            product_id: row.try_get("product_id").map_err(|e| e.to_string())?,
-            product_name: row.try_get("product_name").map_err(|e| e.to_string())?,
-            product_sku: row.try_get("product_sku").map_err(|e| e.to_string())?,
-            quantity: row.try_get("quantity").map_err(|e| e.to_string())?,
-            unit_price: row.try_get("unit_price").map_err(|e| e.to_string())?,
-            line_total: row.try_get("line_total").map_err(|e| e.to_string())?,
-            reason: match row.try_get::<String, _>("reason").unwrap_or_default().as_str() {
-                "Defective" => ReturnReason::Defective,
-                "WrongItem" => ReturnReason::WrongItem,
-                "Damaged" => ReturnReason::Damaged,
-                "Expired" => ReturnReason::Expired,
-                "Overstock" => ReturnReason::Overstock,
-                "Recall" => ReturnReason::Recall,
-                "CustomerDissatisfaction" => ReturnReason::CustomerDissatisfaction,
-                "WrongShipment" => ReturnReason::WrongShipment,
-                "QualityIssue" => ReturnReason::QualityIssue,
-                other => ReturnReason::Other,
-            },
-            condition: match row.try_get::<String, _>("condition").unwrap_or_default().as_str() {
-                "New" => ReturnCondition::New,
-                "Opened" => ReturnCondition::Opened,
-                "Used" => ReturnCondition::Used,
-                "Damaged" => ReturnCondition::Damaged,
-                "Defective" => ReturnCondition::Defective,
-                "Sealed" => ReturnCondition::Sealed,
-                _ => ReturnCondition::New,
-            },
-            disposition: match row.try_get::<String, _>("disposition").unwrap_or_default().as_str() {
-                "Restock" => DispositionAction::Restock,
-                "Dispose" => DispositionAction::Dispose,
-                "ReturnToSupplier" => DispositionAction::ReturnToSupplier,
-                "Transfer" => DispositionAction::Transfer,
-                "Repair" => DispositionAction::Repair,
-                "WriteOff" => DispositionAction::WriteOff,
-                _ => DispositionAction::Restock,
-            },
+            id: return_item_id,
+            return_id: row.try_get("return_id")?,
+            product_id: row.try_get("product_id")?,
+            product_name: row.try_get("product_name")?,
+            product_sku: row.try_get("product_sku")?,
+            product_variant_id: row.try_get("product_variant_id")?,
+            variant_name: row.try_get("variant_name")?,
+            quantity: row.try_get("quantity")?,
+            unit_price: row.try_get("unit_price")?,
+            line_total: row.try_get("line_total")?,
+            reason,
+            condition,
+            disposition,
+            unrecognized_reason,
+            unrecognized_condition,
+            unrecognized_disposition,
             batch_number: row.try_get("batch_number").ok(),
             expiry_date: row.try_get("expiry_date").ok(),
             notes: row.try_get("notes").ok(),
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
+            created_at: row.try_get("created_at")?,
         };
         items.push(item);
     }
@@ -582,48 +1184,226 @@ pub async fn get_return_items(
     Ok(items)
 }
 
+/// NOT_FOUND unless the return belongs to the session's organization
+async fn require_return_in_organization(
+    pool: &SqlitePool,
+    session_token: &str,
+    return_id: i64,
+) -> AppResult<()> {
+    let organization_id = tenancy::active_organization(session_token)?;
+    let mut conn = pool.acquire().await?;
+    tenancy::require_in_organization(
+        &mut conn,
+        "comprehensive_returns",
+        return_id,
+        organization_id,
+        "Return",
+    )
+    .await
+}
+
 #[command]
-pub async fn approve_return(
+pub async fn get_return_items(
     pool: State<'_, SqlitePool>,
     return_id: i64,
+    session_token: String,
+) -> Result<Vec<ComprehensiveReturnItem>, AppError> {
+    require_return_in_organization(pool.inner(), &session_token, return_id).await?;
+    get_return_items_internal(pool.inner(), return_id).await
+}
+
+/// Statuses a return can move to from `from`: Pending → Approved → Processing → Completed,
+/// with Rejected as a final branch before processing starts
+fn can_transition(from: &str, to: &str) -> bool {
+    matches!(
+        (from, to),
+        ("Pending", "Approved")
+            | ("Approved", "Processing")
+            | ("Processing", "Completed")
+            | ("Pending", "Rejected")
+            | ("Approved", "Rejected")
+    )
+}
+
+/// Check that the return can move to `to` and hand back its type
+async fn check_transition(
+    conn: &mut SqliteConnection,
+    return_id: i64,
+    to: &str,
+) -> AppResult<String> {
+    let (status, return_type): (String, String) =
+        sqlx::query_as("SELECT status, return_type FROM comprehensive_returns WHERE id = ?1")
+            .bind(return_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::not_found("Return"))?;
+    if !can_transition(&status, to) {
+        return Err(AppError::validation(
+            "status",
+            &format!(
+                "A {} return cannot be moved to {}",
+                status.to_lowercase(),
+                to.to_lowercase()
+            ),
+        ));
+    }
+    Ok(return_type)
+}
+
+pub(crate) async fn approve_return_internal(
+    pool: &SqlitePool,
+    return_id: i64,
     approved_by: i64,
     notes: Option<String>,
-) -> Result<(), String> {
-    let pool_ref = pool.inner();
+) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+    require_manager(&mut tx, approved_by, "approve a return").await?;
+    check_transition(&mut tx, return_id, "Approved").await?;
+    let (previous_status, organization_id): (String, i64) =
+        sqlx::query_as("SELECT status, organization_id FROM comprehensive_returns WHERE id = ?1")
+            .bind(return_id)
+            .fetch_one(&mut *tx)
+            .await?;
 
     sqlx::query(
         r#"
         UPDATE comprehensive_returns 
-        SET status = 'Approved', approved_by = ?1, approved_at = CURRENT_TIMESTAMP, notes = COALESCE(?2, notes)
+        SET status = 'Approved', approved_by = ?1, approved_at = CURRENT_TIMESTAMP, notes = COALESCE(?2, notes),
+            updated_at = CURRENT_TIMESTAMP
         WHERE id = ?3
         "#
     )
     .bind(approved_by)
-    .bind(notes)
+    .bind(&notes)
     .bind(return_id)
-    .execute(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to approve return: {}", e))?;
+    .execute(&mut *tx)
+    .await?;
+    audit::log_audit(
+        &mut tx,
+        organization_id,
+        Some(approved_by),
+        "approve_return",
+        "return",
+        Some(return_id),
+        Some(&serde_json::json!({ "status": previous_status })),
+        Some(&serde_json::json!({ "status": "Approved", "notes": notes })),
+    )
+    .await?;
+    enqueue_change(&mut tx, "return", return_id, SyncOperation::Update).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[command]
+pub async fn approve_return(
+    pool: State<'_, SqlitePool>,
+    return_id: i64,
+    approved_by: i64,
+    notes: Option<String>,
+    session_token: String,
+) -> Result<(), AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    require_return_in_organization(pool.inner(), &session_token, return_id).await?;
+    approve_return_internal(pool.inner(), return_id, approved_by, notes).await
+}
+
+pub(crate) async fn process_return_internal(pool: &SqlitePool, return_id: i64) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+    check_transition(&mut tx, return_id, "Processing").await?;
+
+    sqlx::query(
+        "UPDATE comprehensive_returns SET status = 'Processing', updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+    )
+    .bind(return_id)
+    .execute(&mut *tx)
+    .await?;
+    enqueue_change(&mut tx, "return", return_id, SyncOperation::Update).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Start working an approved return: goods are being packed, inspected or shipped
+#[command]
+pub async fn process_return(
+    pool: State<'_, SqlitePool>,
+    return_id: i64,
+    session_token: String,
+) -> Result<(), AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    require_return_in_organization(pool.inner(), &session_token, return_id).await?;
+    process_return_internal(pool.inner(), return_id).await
+}
+
+pub(crate) async fn void_return_internal(
+    pool: &SqlitePool,
+    return_id: i64,
+    voided_by: i64,
+    reason: &str,
+) -> AppResult<()> {
+    validate_required(reason, "reason")?;
+
+    let mut tx = pool.begin().await?;
+    check_transition(&mut tx, return_id, "Rejected").await?;
+
+    reverse_refund(&mut tx, return_id, voided_by).await?;
+    sqlx::query(
+        r#"
+        UPDATE comprehensive_returns
+        SET status = 'Rejected', notes = COALESCE(notes || ' | ', '') || 'Voided: ' || ?1,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = ?2
+        "#
+    )
+    .bind(reason.trim())
+    .bind(return_id)
+    .execute(&mut *tx)
+    .await?;
+    enqueue_change(&mut tx, "return", return_id, SyncOperation::Update).await?;
+    tx.commit().await?;
 
     Ok(())
 }
 
+/// Void a pending or approved return, paying its refund back into the shift
+#[command]
+pub async fn void_return(
+    pool: State<'_, SqlitePool>,
+    return_id: i64,
+    voided_by: i64,
+    reason: String,
+    session_token: String,
+) -> Result<(), AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    require_return_in_organization(pool.inner(), &session_token, return_id).await?;
+    void_return_internal(pool.inner(), return_id, voided_by, &reason).await
+}
+
 #[command]
 pub async fn get_return_by_id(
     pool: State<'_, SqlitePool>,
     return_id: i64,
-) -> Result<ComprehensiveReturn, String> {
-    let pool_ref = pool.inner();
+    session_token: String,
+) -> Result<ComprehensiveReturn, AppError> {
+    require_return_in_organization(pool.inner(), &session_token, return_id).await?;
+    get_return_by_id_internal(pool.inner(), return_id).await
+}
 
+pub(crate) async fn get_return_by_id_internal(
+    pool_ref: &SqlitePool,
+    return_id: i64,
+) -> AppResult<ComprehensiveReturn> {
     let row = sqlx::query(
         r#"
         SELECT 
             cr.id, cr.return_number, cr.return_type, cr.reference_id, cr.reference_number,
-            cr.supplier_id, s.name as supplier_name, cr.from_location_id, fl.name as from_location_name,
+            cr.supplier_id, s.company_name as supplier_name, cr.from_location_id, fl.name as from_location_name,
             cr.to_location_id, tl.name as to_location_name, cr.subtotal, cr.tax_amount, cr.total_amount,
             cr.refund_method, cr.credit_method, cr.expected_credit_date, cr.status, cr.processed_by,
-            u.name as processed_by_name, cr.approved_by, au.name as approved_by_name, cr.approved_at,
-            cr.completed_at, cr.reason, cr.notes, cr.created_at, cr.updated_at,
+            u.first_name || ' ' || u.last_name as processed_by_name, cr.approved_by,
+            au.first_name || ' ' || au.last_name as approved_by_name, cr.approved_at,
+            cr.completed_by, cr.completed_at, cr.reason, cr.notes, cr.created_at, cr.updated_at,
             (SELECT COUNT(*) FROM comprehensive_return_items cri WHERE cri.return_id = cr.id) as items_count
         FROM comprehensive_returns cr
         LEFT JOIN suppliers s ON cr.supplier_id = s.id
@@ -635,14 +1415,14 @@ pub async fn get_return_by_id(
         "#
     )
     .bind(return_id)
-    .fetch_one(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to fetch return: {}", e))?;
+    .fetch_optional(pool_ref)
+    .await?
+    .ok_or_else(|| AppError::not_found("return"))?;
 
     let return_record = ComprehensiveReturn {
-        id: row.try_get("id").map_err(|e| e.to_string())?,
-        return_number: row.try_get("return_number").map_err(|e| e.to_string())?,
-        return_type: row.try_get::<String, _>("return_type").map_err(|e| e.to_string())?,
+        id: row.try_get("id")?,
+        return_number: row.try_get("return_number")?,
+        return_type: row.try_get::<String, _>("return_type")?,
         reference_id: row.try_get("reference_id").ok(),
         reference_number: row.try_get("reference_number").ok(),
         supplier_id: row.try_get("supplier_id").ok(),
@@ -651,234 +1431,763 @@ pub async fn get_return_by_id(
         from_location_name: row.try_get("from_location_name").ok(),
         to_location_id: row.try_get("to_location_id").ok(),
         to_location_name: row.try_get("to_location_name").ok(),
-        subtotal: row.try_get("subtotal").map_err(|e| e.to_string())?,
-        tax_amount: row.try_get("tax_amount").map_err(|e| e.to_string())?,
-        total_amount: row.try_get("total_amount").map_err(|e| e.to_string())?,
+        subtotal: row.try_get("subtotal")?,
+        tax_amount: row.try_get("tax_amount")?,
+        total_amount: row.try_get("total_amount")?,
         refund_method: row.try_get("refund_method").ok(),
         credit_method: row.try_get("credit_method").ok(),
         expected_credit_date: row.try_get("expected_credit_date").ok(),
-        status: row.try_get("status").map_err(|e| e.to_string())?,
-        processed_by: row.try_get("processed_by").map_err(|e| e.to_string())?,
+        status: row.try_get("status")?,
+        processed_by: row.try_get("processed_by")?,
         processed_by_name: row.try_get("processed_by_name").ok(),
         approved_by: row.try_get("approved_by").ok(),
         approved_by_name: row.try_get("approved_by_name").ok(),
         approved_at: row.try_get("approved_at").ok(),
+        completed_by: row.try_get("completed_by").ok().flatten(),
         completed_at: row.try_get("completed_at").ok(),
         reason: row.try_get("reason").ok(),
         notes: row.try_get("notes").ok(),
-        items_count: row.try_get("items_count").map_err(|e| e.to_string())?,
-        created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-        updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
+        items_count: row.try_get("items_count")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
     };
 
     Ok(return_record)
 }
 
-#[command]
-pub async fn get_sale_for_return(
-    pool: State<'_, SqlitePool>,
+/// A sale line with how much of it can still come back
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReturnableSaleItem {
+    pub sale_item_id: i64,
+    pub product_id: i64,
+    pub product_name: String,
+    pub product_sku: String,
+    pub unit_price: Money,
+    pub quantity_sold: i64,
+    pub quantity_returned: i64,
+    pub quantity_returnable: i64,
+}
+
+/// A sale as loaded on the return screen
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaleForReturn {
+    pub id: i64,
+    pub sale_number: String,
+    pub customer_name: Option<String>,
+    pub customer_phone: Option<String>,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub total_amount: Money,
+    pub payment_method: String,
+    pub payment_status: Option<String>,
+    pub is_voided: bool,
+    pub created_at: String,
+    pub items: Vec<ReturnableSaleItem>,
+}
+
+pub(crate) async fn get_sale_for_return_internal(
+    pool: &SqlitePool,
     sale_id: i64,
-) -> Result<serde_json::Value, String> {
-    let pool_ref = pool.inner();
+) -> AppResult<SaleForReturn> {
+    let mut conn = pool.acquire().await?;
 
     let row = sqlx::query(
         r#"
-        SELECT 
-            s.id, s.sale_number, s.customer_id, c.name as customer_name,
-            s.subtotal, s.tax_amount, s.total_amount, s.payment_method, s.status,
-            s.created_at
-        FROM sales s
-        LEFT JOIN customers c ON s.customer_id = c.id
-        WHERE s.id = ?1
+        SELECT id, sale_number, customer_name, customer_phone, subtotal, tax_amount,
+               total_amount, payment_method, payment_status, is_voided, created_at
+        FROM sales
+        WHERE id = ?1
         "#
     )
     .bind(sale_id)
-    .fetch_one(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to fetch sale: {}", e))?;
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(AppError::sale_not_found)?;
+    let is_voided: bool = row.try_get("is_voided")?;
+
+    let lines = sqlx::query(
+        r#"
+        SELECT si.id, si.product_id, p.name as product_name, p.sku as product_sku,
+               si.unit_price, si.quantity
+        FROM sale_items si
+        JOIN products p ON p.id = si.product_id
+        WHERE si.sale_id = ?1
+        ORDER BY si.id
+        "#
+    )
+    .bind(sale_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // Returns are tracked per product, so earlier lines absorb them first when a
+    // product was rung up on more than one line
+    let mut returned = returned_quantities(&mut conn, sale_id).await?;
+    let mut items = Vec::with_capacity(lines.len());
+    for line in lines {
+        let product_id: i64 = line.try_get("product_id")?;
+        let quantity_sold: i64 = line.try_get("quantity")?;
+        let quantity_returned = match returned.get_mut(&product_id) {
+            Some(totals) => {
+                let taken = totals.returned.min(quantity_sold);
+                totals.returned -= taken;
+                taken
+            }
+            None => 0,
+        };
+        items.push(ReturnableSaleItem {
+            sale_item_id: line.try_get("id")?,
+            product_id,
+            product_name: line.try_get("product_name")?,
+            product_sku: line.try_get("product_sku")?,
+            unit_price: line.try_get("unit_price")?,
+            quantity_sold,
+            quantity_returned,
+            quantity_returnable: if is_voided { 0 } else { quantity_sold - quantity_returned },
+        });
+    }
+
+    Ok(SaleForReturn {
+        id: row.try_get("id")?,
+        sale_number: row.try_get("sale_number")?,
+        customer_name: row.try_get("customer_name")?,
+        customer_phone: row.try_get("customer_phone")?,
+        subtotal: row.try_get("subtotal")?,
+        tax_amount: row.try_get("tax_amount")?,
+        total_amount: row.try_get("total_amount")?,
+        payment_method: row.try_get("payment_method")?,
+        payment_status: row.try_get("payment_status")?,
+        is_voided,
+        created_at: row.try_get("created_at")?,
+        items,
+    })
+}
+
+#[command]
+pub async fn get_sale_for_return(
+    pool: State<'_, SqlitePool>,
+    sale_id: i64,
+    session_token: String,
+) -> Result<SaleForReturn, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let mut conn = pool.acquire().await?;
+    tenancy::require_in_organization(&mut conn, "sales", sale_id, organization_id, "Sale").await?;
+    drop(conn);
+    get_sale_for_return_internal(pool.inner(), sale_id).await
+}
+
+/// One cashier's sales returns over a report period, scored for loss-prevention review
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CashierReturnRisk {
+    pub user_id: i64,
+    pub user_name: String,
+    pub return_count: i64,
+    pub return_value: Money,
+    /// Returns processed without an original sale
+    pub unreferenced_count: i64,
+    /// Count and value as multiples of the store thresholds, plus unreferenced returns
+    /// as a multiple of the count threshold. 1.0 or more on any part flags the cashier.
+    pub score: f64,
+    pub flagged: bool,
+    pub reasons: Vec<String>,
+}
 
-    let sale = serde_json::json!({
-        "id": row.try_get::<i64, _>("id").map_err(|e| e.to_string())?,
-        "sale_number": row.try_get::<String, _>("sale_number").map_err(|e| e.to_string())?,
-        "customer_id": row.try_get::<Option<i64>, _>("customer_id").ok(),
-        "customer_name": row.try_get::<Option<String>, _>("customer_name").ok(),
-        "subtotal": row.try_get::<f64, _>("subtotal").map_err(|e| e.to_string())?,
-        "tax_amount": row.try_get::<f64, _>("tax_amount").map_err(|e| e.to_string())?,
-        "total_amount": row.try_get::<f64, _>("total_amount").map_err(|e| e.to_string())?,
-        "payment_method": row.try_get::<String, _>("payment_method").map_err(|e| e.to_string())?,
-        "status": row.try_get::<String, _>("status").map_err(|e| e.to_string())?,
-        "created_at": row.try_get::<String, _>("created_at").map_err(|e| e.to_string())?,
-    });
+/// A sales return with no original sale behind it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnreferencedReturn {
+    pub return_id: i64,
+    pub return_number: String,
+    pub processed_by: i64,
+    pub processed_by_name: String,
+    pub total_amount: Money,
+    pub refund_method: Option<String>,
+    pub created_at: String,
+}
 
-    Ok(sale)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReturnRiskReport {
+    pub start_date: String,
+    pub end_date: String,
+    pub count_threshold: i64,
+    pub value_threshold: Money,
+    /// Highest score first
+    pub cashiers: Vec<CashierReturnRisk>,
+    pub unreferenced_returns: Vec<UnreferencedReturn>,
+}
+
+/// `value` as a multiple of `threshold`; a threshold of zero turns the check off
+fn threshold_ratio(value: f64, threshold: f64) -> f64 {
+    if threshold > 0.0 {
+        value / threshold
+    } else {
+        0.0
+    }
+}
+
+pub(crate) async fn get_return_risk_report_internal(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+    organization_id: i64,
+) -> AppResult<ReturnRiskReport> {
+    let scope = tenancy::organization_scope("cr.organization_id", organization_id);
+    let (count_threshold, value_threshold): (i64, Money) = sqlx::query_as(
+        "SELECT return_alert_count, return_alert_value FROM locations WHERE id = ?1",
+    )
+    .bind(DEFAULT_LOCATION_ID)
+    .fetch_optional(pool)
+    .await?
+    .unwrap_or((10, Money::from_major(500.0)));
+
+    // Rejected returns paid nothing out, so they don't count against anyone
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT cr.processed_by,
+               COALESCE(u.first_name || ' ' || u.last_name, 'Unknown') as user_name,
+               COUNT(*) as return_count,
+               COALESCE(SUM(cr.total_amount), 0.0) as return_value,
+               COUNT(CASE WHEN cr.reference_id IS NULL THEN 1 END) as unreferenced_count
+        FROM comprehensive_returns cr
+        LEFT JOIN users u ON u.id = cr.processed_by
+        WHERE cr.return_type = 'SalesReturn'
+          AND cr.status != 'Rejected'
+          AND DATE(cr.created_at) BETWEEN ?1 AND ?2{scope}
+        GROUP BY cr.processed_by
+        "#
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+
+    let mut cashiers = Vec::with_capacity(rows.len());
+    for row in rows {
+        let return_count: i64 = row.try_get("return_count")?;
+        let return_value: Money = row.try_get("return_value")?;
+        let unreferenced_count: i64 = row.try_get("unreferenced_count")?;
+
+        let count_ratio = threshold_ratio(return_count as f64, count_threshold as f64);
+        let value_ratio = threshold_ratio(return_value.to_major(), value_threshold.to_major());
+        let unreferenced_ratio = threshold_ratio(unreferenced_count as f64, count_threshold as f64);
+
+        let mut reasons = Vec::new();
+        if count_ratio >= 1.0 {
+            reasons.push(format!("{} returns (threshold {})", return_count, count_threshold));
+        }
+        if value_ratio >= 1.0 {
+            reasons.push(format!("{} refunded (threshold {})", return_value, value_threshold));
+        }
+        if unreferenced_count > 0 {
+            reasons.push(format!("{} without an original sale", unreferenced_count));
+        }
+
+        cashiers.push(CashierReturnRisk {
+            user_id: row.try_get("processed_by")?,
+            user_name: row.try_get("user_name")?,
+            return_count,
+            return_value,
+            unreferenced_count,
+            score: ((count_ratio + value_ratio + unreferenced_ratio) * 100.0).round() / 100.0,
+            flagged: count_ratio >= 1.0 || value_ratio >= 1.0,
+            reasons,
+        });
+    }
+    cashiers.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.user_id.cmp(&b.user_id)));
+
+    let unreferenced_returns = sqlx::query(&format!(
+        r#"
+        SELECT cr.id, cr.return_number, cr.processed_by,
+               COALESCE(u.first_name || ' ' || u.last_name, 'Unknown') as processed_by_name,
+               cr.total_amount, cr.refund_method, cr.created_at
+        FROM comprehensive_returns cr
+        LEFT JOIN users u ON u.id = cr.processed_by
+        WHERE cr.return_type = 'SalesReturn'
+          AND cr.reference_id IS NULL
+          AND cr.status != 'Rejected'
+          AND DATE(cr.created_at) BETWEEN ?1 AND ?2{scope}
+        ORDER BY cr.total_amount DESC, cr.id
+        "#
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(UnreferencedReturn {
+            return_id: row.try_get("id")?,
+            return_number: row.try_get("return_number")?,
+            processed_by: row.try_get("processed_by")?,
+            processed_by_name: row.try_get("processed_by_name")?,
+            total_amount: row.try_get("total_amount")?,
+            refund_method: row.try_get("refund_method")?,
+            created_at: row.try_get("created_at")?,
+        })
+    })
+    .collect::<AppResult<Vec<_>>>()?;
+
+    Ok(ReturnRiskReport {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        count_threshold,
+        value_threshold,
+        cashiers,
+        unreferenced_returns,
+    })
+}
+
+/// Cashiers with unusually many or large sales returns, and returns with no original sale
+#[command]
+pub async fn get_return_risk_report(
+    pool: State<'_, SqlitePool>,
+    start_date: String,
+    end_date: String,
+    session_token: String,
+) -> Result<ReturnRiskReport, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_return_risk_report_internal(pool.inner(), &start_date, &end_date, organization_id).await
+}
+
+/// Sales return lines sharing one reason, condition or disposition
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ReturnBreakdown {
+    pub key: String,
+    /// Distinct returns with at least one such line
+    pub return_count: i64,
+    pub quantity: i64,
+    pub value: Money,
+}
+
+/// A product's sales returns against what was sold of it over the same period
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductReturnRate {
+    pub product_id: i64,
+    pub sku: String,
+    pub product_name: String,
+    pub returned_quantity: i64,
+    pub returned_value: Money,
+    /// Sold on sales that were not voided
+    pub sold_quantity: i64,
+    /// `returned_quantity / sold_quantity`, 0 when nothing was sold in the period
+    pub return_rate: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReturnsAnalytics {
+    pub start_date: String,
+    pub end_date: String,
+    pub total_returns: i64,
+    pub total_value: Money,
+    pub by_reason: Vec<ReturnBreakdown>,
+    pub by_condition: Vec<ReturnBreakdown>,
+    pub by_disposition: Vec<ReturnBreakdown>,
+    /// The 10 most-returned products by quantity
+    pub top_products: Vec<ProductReturnRate>,
+}
+
+/// Customer returns that paid out, i.e. not rejected, created in the period
+const ANALYTICS_RETURNS: &str = "cr.return_type = 'SalesReturn'
+          AND cr.status != 'Rejected'
+          AND DATE(cr.created_at) BETWEEN ?1 AND ?2";
+
+async fn return_breakdown(
+    pool: &SqlitePool,
+    column: &str,
+    start_date: &str,
+    end_date: &str,
+    organization_id: i64,
+) -> AppResult<Vec<ReturnBreakdown>> {
+    let scope = tenancy::organization_scope("cr.organization_id", organization_id);
+    let query = format!(
+        "SELECT cri.{column} AS key, COUNT(DISTINCT cr.id) AS return_count,
+                SUM(cri.quantity) AS quantity, SUM(cri.line_total) AS value
+         FROM comprehensive_return_items cri
+         JOIN comprehensive_returns cr ON cr.id = cri.return_id
+         WHERE {ANALYTICS_RETURNS}{scope}
+         GROUP BY cri.{column}
+         ORDER BY value DESC, key"
+    );
+    sqlx::query(&query)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(ReturnBreakdown {
+                key: row.try_get("key")?,
+                return_count: row.try_get("return_count")?,
+                quantity: row.try_get("quantity")?,
+                value: row.try_get("value")?,
+            })
+        })
+        .collect()
+}
+
+pub(crate) async fn get_returns_analytics_internal(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+    organization_id: i64,
+) -> AppResult<ReturnsAnalytics> {
+    let scope = tenancy::organization_scope("cr.organization_id", organization_id);
+    let (total_returns, total_value): (i64, Money) = sqlx::query_as(&format!(
+        "SELECT COUNT(*), COALESCE(SUM(cr.total_amount), 0.0)
+         FROM comprehensive_returns cr
+         WHERE {ANALYTICS_RETURNS}{scope}"
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(pool)
+    .await?;
+
+    let top_products = sqlx::query(&format!(
+        "SELECT p.id, p.sku, p.name,
+                SUM(cri.quantity) AS returned_quantity,
+                SUM(cri.line_total) AS returned_value,
+                (SELECT COALESCE(SUM(si.quantity), 0)
+                 FROM sale_items si
+                 JOIN sales s ON s.id = si.sale_id
+                 WHERE si.product_id = p.id
+                   AND s.is_voided = 0
+                   AND DATE(s.created_at) BETWEEN ?1 AND ?2) AS sold_quantity
+         FROM comprehensive_return_items cri
+         JOIN comprehensive_returns cr ON cr.id = cri.return_id
+         JOIN products p ON p.id = cri.product_id
+         WHERE {ANALYTICS_RETURNS}{scope}
+         GROUP BY p.id
+         ORDER BY returned_quantity DESC, p.id
+         LIMIT 10"
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        let returned_quantity: i64 = row.try_get("returned_quantity")?;
+        let sold_quantity: i64 = row.try_get("sold_quantity")?;
+        Ok(ProductReturnRate {
+            product_id: row.try_get("id")?,
+            sku: row.try_get("sku")?,
+            product_name: row.try_get("name")?,
+            returned_quantity,
+            returned_value: row.try_get("returned_value")?,
+            sold_quantity,
+            return_rate: if sold_quantity > 0 {
+                returned_quantity as f64 / sold_quantity as f64
+            } else {
+                0.0
+            },
+        })
+    })
+    .collect::<AppResult<Vec<_>>>()?;
+
+    Ok(ReturnsAnalytics {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        total_returns,
+        total_value,
+        by_reason: return_breakdown(pool, "reason", start_date, end_date, organization_id).await?,
+        by_condition: return_breakdown(pool, "condition", start_date, end_date, organization_id)
+            .await?,
+        by_disposition: return_breakdown(
+            pool,
+            "disposition",
+            start_date,
+            end_date,
+            organization_id,
+        )
+        .await?,
+        top_products,
+    })
+}
+
+/// Why customers brought goods back over a period, and the products returned most
+#[command]
+pub async fn get_returns_analytics(
+    pool: State<'_, SqlitePool>,
+    start_date: String,
+    end_date: String,
+    session_token: String,
+) -> Result<ReturnsAnalytics, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_returns_analytics_internal(pool.inner(), &start_date, &end_date, organization_id).await
+}
+
+/// Every return line created in the period, of any return type and status, as CSV
+pub(crate) async fn export_return_lines_csv_internal(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+    organization_id: i64,
+) -> AppResult<String> {
+    let headers = [
+        "Return Number",
+        "Date",
+        "Type",
+        "Status",
+        "Reference",
+        "SKU",
+        "Product",
+        "Quantity",
+        "Unit Price",
+        "Line Total",
+        "Reason",
+        "Condition",
+        "Disposition",
+    ];
+    let rows = sqlx::query(&format!(
+        "SELECT cr.return_number, cr.created_at, cr.return_type, cr.status, cr.reference_number,
+                p.sku, p.name, cri.quantity, cri.unit_price, cri.line_total,
+                cri.reason, cri.condition, cri.disposition
+         FROM comprehensive_return_items cri
+         JOIN comprehensive_returns cr ON cr.id = cri.return_id
+         LEFT JOIN products p ON p.id = cri.product_id
+         WHERE DATE(cr.created_at) BETWEEN ?1 AND ?2{scope}
+         ORDER BY cr.created_at, cr.id, cri.id",
+        scope = tenancy::organization_scope("cr.organization_id", organization_id)
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(vec![
+            row.try_get::<String, _>("return_number")?,
+            row.try_get::<String, _>("created_at")?,
+            row.try_get::<String, _>("return_type")?,
+            row.try_get::<String, _>("status")?,
+            row.try_get::<Option<String>, _>("reference_number")?.unwrap_or_default(),
+            row.try_get::<Option<String>, _>("sku")?.unwrap_or_default(),
+            row.try_get::<Option<String>, _>("name")?.unwrap_or_default(),
+            row.try_get::<i64, _>("quantity")?.to_string(),
+            row.try_get::<Money, _>("unit_price")?.to_string(),
+            row.try_get::<Money, _>("line_total")?.to_string(),
+            row.try_get::<String, _>("reason")?,
+            row.try_get::<String, _>("condition")?,
+            row.try_get::<String, _>("disposition")?,
+        ])
+    })
+    .collect::<AppResult<Vec<_>>>()?;
+    Ok(csv_export::to_csv(&headers, &rows))
+}
+
+#[command]
+pub async fn export_return_lines_csv(
+    pool: State<'_, SqlitePool>,
+    start_date: String,
+    end_date: String,
+    session_token: String,
+) -> Result<String, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    export_return_lines_csv_internal(pool.inner(), &start_date, &end_date, organization_id).await
 }
 
 #[command]
 pub async fn get_returns_count(
     pool: State<'_, SqlitePool>,
     status: Option<String>,
-) -> Result<i64, String> {
+    session_token: String,
+) -> Result<i64, AppError> {
     let pool_ref = pool.inner();
+    let scope = tenancy::organization_scope(
+        "organization_id",
+        tenancy::active_organization(&session_token)?,
+    );
 
     let count = if let Some(status_filter) = status {
-        sqlx::query_scalar("SELECT COUNT(*) FROM comprehensive_returns WHERE status = ?1")
-            .bind(status_filter)
-            .fetch_one(pool_ref)
-            .await
-            .map_err(|e| format!("Failed to count returns: {}", e))?
+        sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM comprehensive_returns WHERE status = ?1{}",
+            scope
+        ))
+        .bind(status_filter)
+        .fetch_one(pool_ref)
+        .await?
     } else {
-        sqlx::query_scalar("SELECT COUNT(*) FROM comprehensive_returns")
-            .fetch_one(pool_ref)
-            .await
-            .map_err(|e| format!("Failed to count returns: {}", e))?
+        sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM comprehensive_returns WHERE 1=1{}",
+            scope
+        ))
+        .fetch_one(pool_ref)
+        .await?
     };
 
     Ok(count)
 }
 
-#[command]
-pub async fn complete_return(
-    pool: State<'_, SqlitePool>,
+pub(crate) async fn complete_return_internal(
+    pool: &SqlitePool,
     return_id: i64,
     completed_by: i64,
     notes: Option<String>,
-) -> Result<(), String> {
-    let pool_ref = pool.inner();
+) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+    let return_type = check_transition(&mut tx, return_id, "Completed").await?;
 
     sqlx::query(
         r#"
         UPDATE comprehensive_returns 
-        SET status = 'Completed', completed_at = CURRENT_TIMESTAMP
-        WHERE id = ?1
+        SET status = 'Completed', completed_by = ?1, completed_at = CURRENT_TIMESTAMP,
+            notes = COALESCE(?2, notes), updated_at = CURRENT_TIMESTAMP
+        WHERE id = ?3
         "#
     )
+    .bind(completed_by)
+    .bind(notes)
     .bind(return_id)
-    .execute(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to complete return: {}", e))?;
+    .execute(&mut *tx)
+    .await?;
+
+    // Sales returns moved their stock when they were rung up
+    if return_type != "SalesReturn" {
+        post_return_items(&mut tx, return_id, completed_by).await?;
+    }
+    // Goods sent back to a supplier leave a credit to draw on
+    if return_type == "PurchaseReturn" {
+        supplier_credits::issue_credit_memo(&mut tx, return_id).await?;
+    }
+    enqueue_change(&mut tx, "return", return_id, SyncOperation::Update).await?;
+    tx.commit().await?;
 
     Ok(())
 }
 
 #[command]
-pub async fn create_return_offline(
+pub async fn complete_return(
     pool: State<'_, SqlitePool>,
-    return_data: serde_json::Value,
-) -> Result<i64, String> {
-    let pool_ref = pool.inner();
-
-    // Generate return number
-    let return_number = format!("RET-{}{:04}", chrono::Utc::now().format("%Y%m%d"), 
-                                generate_random_number(1, 9999));
+    return_id: i64,
+    completed_by: i64,
+    notes: Option<String>,
+    session_token: String,
+) -> Result<(), AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    require_return_in_organization(pool.inner(), &session_token, return_id).await?;
+    complete_return_internal(pool.inner(), return_id, completed_by, notes).await
+}
 
-    let mut tx = pool_ref.begin().await
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+pub(crate) async fn create_return_offline_internal(
+    pool_ref: &SqlitePool,
+    payload: OfflineReturnPayload,
+    organization_id: i64,
+) -> AppResult<i64> {
+    let mut tx = pool_ref.begin().await?;
+
+    // Insert return, drawing a new number if this one is already taken
+    let mut attempts = 0;
+    let return_id: i64 = loop {
+        attempts += 1;
+        let return_number = next_document_number(&mut *tx, payload.return_type.doc_type()).await?;
+        let inserted = sqlx::query_scalar(
+            r#"
+            INSERT INTO comprehensive_returns (
+                return_number, return_type, reference_id, reference_number,
+                supplier_id, from_location_id, to_location_id, subtotal,
+                tax_amount, total_amount, refund_method, credit_method,
+                expected_credit_date, status, processed_by, reason, notes, organization_id
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
+            RETURNING id
+            "#
+        )
+        .bind(&return_number)
+        .bind(format!("{:?}", payload.return_type))
+        .bind(payload.reference_id)
+        .bind(&payload.reference_number)
+        .bind(payload.supplier_id)
+        .bind(payload.from_location_id)
+        .bind(payload.to_location_id)
+        .bind(payload.subtotal)
+        .bind(payload.tax_amount)
+        .bind(payload.total_amount)
+        .bind(&payload.refund_method)
+        .bind(&payload.credit_method)
+        .bind(&payload.expected_credit_date)
+        .bind(payload.status.as_deref().unwrap_or("Pending"))
+        .bind(payload.processed_by)
+        .bind(&payload.reason)
+        .bind(&payload.notes)
+        .bind(organization_id)
+        .fetch_one(&mut *tx)
+        .await;
+
+        match inserted {
+            Ok(id) => break id,
+            Err(e)
+                if attempts < DOCUMENT_NUMBER_ATTEMPTS
+                    && is_duplicate_number(&e, "comprehensive_returns", "return_number") => {}
+            Err(e) => return Err(e.into()),
+        }
+    };
 
-    // Insert return
-    let return_id = sqlx::query_scalar(
-        r#"
-        INSERT INTO comprehensive_returns (
-            return_number, return_type, reference_id, reference_number,
-            supplier_id, from_location_id, to_location_id, subtotal,
-            tax_amount, total_amount, refund_method, credit_method,
-            expected_credit_date, status, processed_by, reason, notes
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
-        RETURNING id
-        "#
-    )
-    .bind(&return_number)
-    .bind(return_data.get("return_type").and_then(|v| v.as_str()).unwrap_or("SalesReturn"))
-    .bind(return_data.get("reference_id").and_then(|v| v.as_i64()))
-    .bind(return_data.get("reference_number").and_then(|v| v.as_str()))
-    .bind(return_data.get("supplier_id").and_then(|v| v.as_i64()))
-    .bind(return_data.get("from_location_id").and_then(|v| v.as_i64()))
-    .bind(return_data.get("to_location_id").and_then(|v| v.as_i64()))
-    .bind(return_data.get("subtotal").and_then(|v| v.as_f64()).unwrap_or(0.0))
-    .bind(return_data.get("tax_amount").and_then(|v| v.as_f64()).unwrap_or(0.0))
-    .bind(return_data.get("total_amount").and_then(|v| v.as_f64()).unwrap_or(0.0))
-    .bind(return_data.get("refund_method").and_then(|v| v.as_str()))
-    .bind(return_data.get("credit_method").and_then(|v| v.as_str()))
-    .bind(return_data.get("expected_credit_date").and_then(|v| v.as_str()))
-    .bind(return_data.get("status").and_then(|v| v.as_str()).unwrap_or("Pending"))
-    .bind(return_data.get("processed_by").and_then(|v| v.as_i64()).unwrap_or(1))
-    .bind(return_data.get("reason").and_then(|v| v.as_str()))
-    .bind(return_data.get("notes").and_then(|v| v.as_str()))
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| format!("Failed to create return: {}", e))?;
+    enqueue_change(&mut tx, "return", return_id, SyncOperation::Create).await?;
 
     // Commit transaction
-    tx.commit()
-        .await
-        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    tx.commit().await?;
+    webhooks::emit_entity_event(pool_ref, EVENT_RETURN_CREATED, "return", return_id).await;
 
     Ok(return_id)
 }
 
 #[command]
-pub async fn sync_return_from_supabase(
+pub async fn create_return_offline(
     pool: State<'_, SqlitePool>,
     return_data: serde_json::Value,
-) -> Result<(), String> {
-    let pool_ref = pool.inner();
+    session_token: String,
+) -> Result<i64, AppError> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    let payload = OfflineReturnPayload::from_json(return_data)?;
+    create_return_offline_internal(pool.inner(), payload, organization_id).await
+}
 
-    sqlx::query(
-        r#"
-        INSERT OR REPLACE INTO comprehensive_returns (
-            id, return_number, return_type, reference_id, reference_number,
-            supplier_id, from_location_id, to_location_id, subtotal,
-            tax_amount, total_amount, refund_method, credit_method,
-            expected_credit_date, status, processed_by, approved_by,
-            approved_at, completed_at, reason, notes, created_at, updated_at
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)
-        "#
-    )
-    .bind(return_data.get("id").and_then(|v| v.as_i64()))
-    .bind(return_data.get("return_number").and_then(|v| v.as_str()))
-    .bind(return_data.get("return_type").and_then(|v| v.as_str()))
-    .bind(return_data.get("reference_id").and_then(|v| v.as_i64()))
-    .bind(return_data.get("reference_number").and_then(|v| v.as_str()))
-    .bind(return_data.get("supplier_id").and_then(|v| v.as_i64()))
-    .bind(return_data.get("from_location_id").and_then(|v| v.as_i64()))
-    .bind(return_data.get("to_location_id").and_then(|v| v.as_i64()))
-    .bind(return_data.get("subtotal").and_then(|v| v.as_f64()))
-    .bind(return_data.get("tax_amount").and_then(|v| v.as_f64()))
-    .bind(return_data.get("total_amount").and_then(|v| v.as_f64()))
-    .bind(return_data.get("refund_method").and_then(|v| v.as_str()))
-    .bind(return_data.get("credit_method").and_then(|v| v.as_str()))
-    .bind(return_data.get("expected_credit_date").and_then(|v| v.as_str()))
-    .bind(return_data.get("status").and_then(|v| v.as_str()))
-    .bind(return_data.get("processed_by").and_then(|v| v.as_i64()))
-    .bind(return_data.get("approved_by").and_then(|v| v.as_i64()))
-    .bind(return_data.get("approved_at").and_then(|v| v.as_str()))
-    .bind(return_data.get("completed_at").and_then(|v| v.as_str()))
-    .bind(return_data.get("reason").and_then(|v| v.as_str()))
-    .bind(return_data.get("notes").and_then(|v| v.as_str()))
-    .bind(return_data.get("created_at").and_then(|v| v.as_str()))
-    .bind(return_data.get("updated_at").and_then(|v| v.as_str()))
-    .execute(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to sync return: {}", e))?;
+/// Check a return from the cloud has its identity and every required field before it is
+/// merged, so a partial record cannot overwrite the local row with defaults
+fn check_remote_return(return_data: &serde_json::Value) -> AppResult<()> {
+    serde_json::from_value::<RemoteReturnRecord>(return_data.clone())
+        .map_err(|e| AppError::validation("return_data", &format!("Invalid return record: {}", e)))?;
+    Ok(())
+}
 
+#[command]
+pub async fn sync_return_from_supabase(
+    pool: State<'_, SqlitePool>,
+    mut return_data: serde_json::Value,
+    session_token: String,
+) -> Result<(), AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    check_remote_return(&return_data)?;
+    // A return of another organization is left alone, and a new one joins the session's
+    let owner: Option<i64> =
+        sqlx::query_scalar("SELECT organization_id FROM comprehensive_returns WHERE id = ?1")
+            .bind(return_data["id"].as_i64())
+            .fetch_optional(pool.inner())
+            .await?;
+    if owner.is_some_and(|owner| owner != organization_id) {
+        return Err(AppError::not_found("Return"));
+    }
+    return_data["organization_id"] = serde_json::json!(organization_id);
+    // Compares updated_at with the local row instead of overwriting it
+    sync_inbound::apply_remote_record(pool.inner(), "comprehensive_returns", &return_data).await?;
     Ok(())
 }
 
 #[command]
 pub async fn get_pending_returns(
     pool: State<'_, SqlitePool>,
-) -> Result<Vec<serde_json::Value>, String> {
+    session_token: String,
+) -> Result<Vec<serde_json::Value>, AppError> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
 
-    let rows = sqlx::query(
+    let rows = sqlx::query(&format!(
         r#"
         SELECT * FROM comprehensive_returns 
-        WHERE sync_status = 'pending' OR sync_status = 'error'
+        WHERE (sync_status = 'pending' OR sync_status = 'error'){}
         ORDER BY created_at ASC
-        "#
-    )
+        "#,
+        tenancy::organization_scope("organization_id", organization_id)
+    ))
     .fetch_all(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to get pending returns: {}", e))?;
+    .await?;
 
     let mut returns = Vec::new();
     for row in rows {
@@ -917,7 +2226,9 @@ pub async fn get_pending_returns(
 pub async fn mark_return_as_synced(
     pool: State<'_, SqlitePool>,
     return_id: i64,
-) -> Result<(), String> {
+    session_token: String,
+) -> Result<(), AppError> {
+    require_return_in_organization(pool.inner(), &session_token, return_id).await?;
     let pool_ref = pool.inner();
 
     sqlx::query(
@@ -925,8 +2236,7 @@ pub async fn mark_return_as_synced(
     )
     .bind(return_id)
     .execute(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to mark return as synced: {}", e))?;
+    .await?;
 
     Ok(())
 }
@@ -936,7 +2246,9 @@ pub async fn mark_return_as_error(
     pool: State<'_, SqlitePool>,
     return_id: i64,
     error: String,
-) -> Result<(), String> {
+    session_token: String,
+) -> Result<(), AppError> {
+    require_return_in_organization(pool.inner(), &session_token, return_id).await?;
     let pool_ref = pool.inner();
 
     sqlx::query(
@@ -945,27 +2257,19 @@ pub async fn mark_return_as_error(
     .bind(error)
     .bind(return_id)
     .execute(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to mark return as error: {}", e))?;
+    .await?;
 
     Ok(())
 }
 
-fn generate_random_number(min: i32, max: i32) -> i32 {
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
-    rng.gen_range(min..=max)
-}
-
 #[command]
-pub async fn test_returns_tables(pool: State<'_, SqlitePool>) -> Result<String, String> {
+pub async fn test_returns_tables(pool: State<'_, SqlitePool>) -> Result<String, AppError> {
     let pool_ref = pool.inner();
     
     // Test if returns tables exist and are accessible
     let result = sqlx::query("SELECT name FROM sqlite_master WHERE type='table' AND name LIKE 'return_%'")
         .fetch_all(pool_ref)
-        .await
-        .map_err(|e| format!("Failed to query returns tables: {}", e))?;
+        .await?;
     
     let table_names: Vec<String> = result.iter()
         .map(|row| row.get::<String, _>("name"))
@@ -973,3 +2277,692 @@ pub async fn test_returns_tables(pool: State<'_, SqlitePool>) -> Result<String,
     
     Ok(format!("Found returns tables: {:?}", table_names))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::cash_drawer::cash_drawer_balance;
+    use crate::commands::sales::create_sale_internal;
+    use crate::models::{CreateSaleRequest, SaleItemRequest};
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+
+    fn returned_line(product_id: i64, quantity: i32) -> ReturnItem {
+        ReturnItem {
+            product_id,
+            product_variant_id: None,
+            quantity,
+            unit_price: Money::from_major(10.0),
+            line_total: Money::from_major(10.0).times(quantity),
+            reason: ReturnReason::Defective,
+            condition: ReturnCondition::Opened,
+            disposition: DispositionAction::Restock,
+            batch_number: None,
+            expiry_date: None,
+            notes: None,
+        }
+    }
+
+    fn sales_return(sale_id: i64, items: Vec<ReturnItem>, user_id: i64) -> NewReturn {
+        let total: Money = items.iter().map(|item| item.line_total).sum();
+        NewReturn {
+            return_type: ReturnType::SalesReturn,
+            reference_id: Some(sale_id),
+            reference_number: None,
+            supplier_id: None,
+            from_location_id: None,
+            to_location_id: None,
+            items,
+            subtotal: total,
+            tax_amount: Money::ZERO,
+            total_amount: total,
+            refund_method: Some("cash".to_string()),
+            credit_method: None,
+            expected_credit_date: None,
+            reason: None,
+            notes: None,
+            user_id,
+            shift_id: None,
+            organization_id: DEFAULT_ORGANIZATION_ID,
+        }
+    }
+
+    /// Units at 10.00, paid in cash
+    fn cash_sale(product_id: i64, quantity: i32) -> CreateSaleRequest {
+        let line_total = Money::from_major(10.0).times(quantity);
+        CreateSaleRequest {
+            items: vec![SaleItemRequest {
+                product_id,
+                quantity,
+                unit_price: Money::from_major(10.0),
+                discount_amount: Money::ZERO,
+                line_total,
+                measured_quantity: None,
+                price_override: None,
+                override_reason: None,
+            }],
+            subtotal: line_total,
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: line_total,
+            payment_method: "Cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partial_returns_cannot_exceed_quantity_sold() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let product = insert_test_product(&pool, "RET-1", 10.0, 10).await;
+
+        let sale = create_sale_internal(&pool, cash_sale(product, 3), cashier, None)
+            .await
+            .unwrap();
+        assert_eq!(current_stock(&pool, product).await, 7);
+
+        create_return_internal(&pool, sales_return(sale.id, vec![returned_line(product, 2)], cashier))
+            .await
+            .unwrap();
+        assert_eq!(current_stock(&pool, product).await, 9);
+
+        let loaded = get_sale_for_return_internal(&pool, sale.id).await.unwrap();
+        assert_eq!(loaded.items.len(), 1);
+        assert_eq!(loaded.items[0].quantity_sold, 3);
+        assert_eq!(loaded.items[0].quantity_returned, 2);
+        assert_eq!(loaded.items[0].quantity_returnable, 1);
+
+        let err = create_return_internal(&pool, sales_return(sale.id, vec![returned_line(product, 2)], cashier))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert_eq!(current_stock(&pool, product).await, 9);
+
+        // Nor can the limit be sidestepped by leaving the sale off
+        let unreferenced = NewReturn {
+            reference_id: None,
+            ..sales_return(sale.id, vec![returned_line(product, 5)], cashier)
+        };
+        let err = create_return_internal(&pool, unreferenced).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert_eq!(current_stock(&pool, product).await, 9);
+
+        let other_organization = NewReturn {
+            organization_id: DEFAULT_ORGANIZATION_ID + 1,
+            ..sales_return(sale.id, vec![returned_line(product, 1)], cashier)
+        };
+        let err = create_return_internal(&pool, other_organization).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+
+        create_return_internal(&pool, sales_return(sale.id, vec![returned_line(product, 1)], cashier))
+            .await
+            .unwrap();
+        let loaded = get_sale_for_return_internal(&pool, sale.id).await.unwrap();
+        assert_eq!(loaded.items[0].quantity_returnable, 0);
+    }
+
+    #[tokio::test]
+    async fn test_returns_search_matches_contained_products_and_customers() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let shampoo = insert_test_product(&pool, "SHAMPOO-500", 10.0, 10).await;
+        let soap = insert_test_product(&pool, "SOAP-BAR", 10.0, 10).await;
+
+        let mut shampoo_sale = cash_sale(shampoo, 1);
+        shampoo_sale.customer_name = Some("Ada Lovelace".to_string());
+        let shampoo_sale = create_sale_internal(&pool, shampoo_sale, cashier, None).await.unwrap();
+        let soap_sale = create_sale_internal(&pool, cash_sale(soap, 3), cashier, None)
+            .await
+            .unwrap();
+        let shampoo_lines = vec![returned_line(shampoo, 1)];
+        let shampoo_return =
+            create_return_internal(&pool, sales_return(shampoo_sale.id, shampoo_lines, cashier))
+                .await
+                .unwrap();
+        let soap_lines = vec![returned_line(soap, 3)];
+        let soap_return = create_return_internal(&pool, sales_return(soap_sale.id, soap_lines, cashier))
+            .await
+            .unwrap();
+
+        let all = ReturnsQuery {
+            organization_id: DEFAULT_ORGANIZATION_ID,
+            ..Default::default()
+        };
+        let search = |term| ReturnsQuery { search: Some(term), ..all };
+        let first_ten = Pagination::Offset { limit: 10, offset: 0 };
+        let page = get_returns_internal(&pool, &search("shampoo"), &first_ten, true).await.unwrap();
+        assert_eq!(page.total_count, 1);
+        assert_eq!(page.items[0].id, shampoo_return);
+        let page = get_returns_internal(&pool, &search("lovelace"), &first_ten, true).await.unwrap();
+        assert_eq!(page.items.iter().map(|r| r.id).collect::<Vec<_>>(), vec![shampoo_return]);
+
+        let by_amount = ReturnsQuery {
+            sort_by: Some("amount"),
+            sort_desc: Some(false),
+            ..all
+        };
+        let first = Pagination::Offset { limit: 1, offset: 0 };
+        let page = get_returns_internal(&pool, &by_amount, &first, true).await.unwrap();
+        assert_eq!(page.total_count, 2);
+        assert!(page.has_more);
+        assert_eq!(page.items[0].id, shampoo_return);
+        let page = get_returns_internal(&pool, &all, &first_ten, true).await.unwrap();
+        assert_eq!(page.items[0].id, soap_return);
+        let other_organization = ReturnsQuery {
+            organization_id: DEFAULT_ORGANIZATION_ID + 1,
+            ..all
+        };
+        let page = get_returns_internal(&pool, &other_organization, &first_ten, true).await.unwrap();
+        assert_eq!(page.total_count, 0);
+
+        let bad_sort = ReturnsQuery { sort_by: Some("cashier"), ..all };
+        let err = get_returns_internal(&pool, &bad_sort, &first_ten, true).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+
+    #[tokio::test]
+    async fn test_returns_analytics_groups_lines_and_rates_against_unvoided_sales() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let kettle = insert_test_product(&pool, "KETTLE", 10.0, 50).await;
+        let toaster = insert_test_product(&pool, "TOASTER", 10.0, 50).await;
+
+        let kettle_sale = create_sale_internal(&pool, cash_sale(kettle, 10), cashier, None)
+            .await
+            .unwrap();
+        let voided = create_sale_internal(&pool, cash_sale(kettle, 5), cashier, None)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE sales SET is_voided = 1 WHERE id = ?1")
+            .bind(voided.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let toaster_sale = create_sale_internal(&pool, cash_sale(toaster, 4), cashier, None)
+            .await
+            .unwrap();
+
+        let mut damaged = returned_line(kettle, 1);
+        damaged.reason = ReturnReason::Damaged;
+        damaged.condition = ReturnCondition::Damaged;
+        damaged.disposition = DispositionAction::WriteOff;
+        let kettle_lines = vec![returned_line(kettle, 2), damaged];
+        create_return_internal(&pool, sales_return(kettle_sale.id, kettle_lines, cashier))
+            .await
+            .unwrap();
+        let toaster_lines = vec![returned_line(toaster, 1)];
+        create_return_internal(&pool, sales_return(toaster_sale.id, toaster_lines, cashier))
+            .await
+            .unwrap();
+
+        let analytics = get_returns_analytics_internal(
+            &pool,
+            "2000-01-01",
+            "2999-12-31",
+            DEFAULT_ORGANIZATION_ID,
+        )
+        .await
+        .unwrap();
+        assert_eq!(analytics.total_returns, 2);
+        assert_eq!(analytics.total_value, Money::from_major(40.0));
+        let breakdown = |key: &str, return_count, quantity, value: f64| ReturnBreakdown {
+            key: key.to_string(),
+            return_count,
+            quantity,
+            value: Money::from_major(value),
+        };
+        assert_eq!(
+            analytics.by_reason,
+            vec![breakdown("Defective", 2, 3, 30.0), breakdown("Damaged", 1, 1, 10.0)]
+        );
+        assert_eq!(
+            analytics.by_disposition,
+            vec![breakdown("Restock", 2, 3, 30.0), breakdown("WriteOff", 1, 1, 10.0)]
+        );
+
+        // 3 of the 10 kettles on the sale that stood, the voided 5 don't count
+        let rates: Vec<(i64, i64, i64)> = analytics
+            .top_products
+            .iter()
+            .map(|p| (p.product_id, p.returned_quantity, p.sold_quantity))
+            .collect();
+        assert_eq!(rates, vec![(kettle, 3, 10), (toaster, 1, 4)]);
+        assert!((analytics.top_products[0].return_rate - 0.3).abs() < 1e-9);
+
+        let csv = export_return_lines_csv_internal(
+            &pool,
+            "2000-01-01",
+            "2999-12-31",
+            DEFAULT_ORGANIZATION_ID,
+        )
+        .await
+        .unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        let damaged_line = csv.lines().nth(2).unwrap();
+        assert!(damaged_line.ends_with("KETTLE,KETTLE,1,10.00,10.00,Damaged,Damaged,WriteOff"));
+    }
+
+    #[tokio::test]
+    async fn test_restock_returns_variants_to_variant_inventory() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let shirt = insert_test_product(&pool, "SHIRT", 10.0, 20).await;
+        let mug = insert_test_product(&pool, "MUG", 10.0, 20).await;
+        let large = sqlx::query(
+            "INSERT INTO product_variants (product_id, sku, variant_name) VALUES (?1, 'SHIRT-L', 'Large')",
+        )
+        .bind(shirt)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        sqlx::query(
+            "INSERT INTO variant_inventory (product_variant_id, current_stock, available_stock)
+             VALUES (?1, 5, 5)",
+        )
+        .bind(large)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let variant_stock = || async {
+            sqlx::query_scalar::<_, i32>(
+                "SELECT current_stock FROM variant_inventory WHERE product_variant_id = ?1",
+            )
+            .bind(large)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        };
+
+        let mut request = cash_sale(shirt, 2);
+        request.items.push(cash_sale(mug, 2).items.remove(0));
+        request.subtotal = Money::from_major(40.0);
+        request.total_amount = request.subtotal;
+        let sale = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+        assert_eq!(current_stock(&pool, shirt).await, 18);
+
+        let mut shirt_line = returned_line(shirt, 1);
+        shirt_line.product_variant_id = Some(large);
+        let lines = vec![shirt_line, returned_line(mug, 1)];
+        let return_id = create_return_internal(&pool, sales_return(sale.id, lines, cashier))
+            .await
+            .unwrap();
+
+        assert_eq!(variant_stock().await, 6);
+        assert_eq!(current_stock(&pool, shirt).await, 18);
+        assert_eq!(current_stock(&pool, mug).await, 19);
+        let items = get_return_items_internal(&pool, return_id).await.unwrap();
+        let variants: Vec<_> = items
+            .iter()
+            .map(|item| (item.product_id, item.product_variant_id, item.variant_name.clone()))
+            .collect();
+        assert!(variants.contains(&(shirt, Some(large), Some("Large".to_string()))));
+        assert!(variants.contains(&(mug, None, None)));
+
+        // A variant of another product is refused
+        let mut wrong = returned_line(mug, 1);
+        wrong.product_variant_id = Some(large);
+        let err = create_return_internal(&pool, sales_return(sale.id, vec![wrong], cashier))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert_eq!(variant_stock().await, 6);
+    }
+
+    #[tokio::test]
+    async fn test_refunds_reconcile_with_the_shift_and_reverse_on_void() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let product = insert_test_product(&pool, "RET-1", 10.0, 10).await;
+        let shift = sqlx::query(
+            "INSERT INTO shifts (user_id, start_time, opening_amount) VALUES (?1, CURRENT_TIMESTAMP, 100.0)",
+        )
+        .bind(cashier)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        let shift_returns = || async {
+            sqlx::query_scalar::<_, Money>("SELECT total_returns FROM shifts WHERE id = ?1")
+                .bind(shift)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
+
+        let sale = create_sale_internal(&pool, cash_sale(product, 3), cashier, Some(shift))
+            .await
+            .unwrap();
+        let mut cash_refund = sales_return(sale.id, vec![returned_line(product, 1)], cashier);
+        cash_refund.shift_id = Some(shift);
+        let cash_return = create_return_internal(&pool, cash_refund).await.unwrap();
+
+        // 100.00 float + 30.00 sale - 10.00 refund
+        assert_eq!(cash_drawer_balance(&pool, shift).await.unwrap(), Money::from_major(120.0));
+        assert_eq!(shift_returns().await, Money::from_major(10.0));
+
+        let mut card_refund = sales_return(sale.id, vec![returned_line(product, 1)], cashier);
+        card_refund.shift_id = Some(shift);
+        card_refund.refund_method = Some("Card".to_string());
+        let card_return = create_return_internal(&pool, card_refund).await.unwrap();
+        let card_split: Money = sqlx::query_scalar(
+            "SELECT base_amount FROM sale_payments WHERE return_id = ?1 AND payment_method = 'Card'",
+        )
+        .bind(card_return)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(card_split, Money::from_major(-10.0));
+        assert_eq!(cash_drawer_balance(&pool, shift).await.unwrap(), Money::from_major(120.0));
+        assert_eq!(shift_returns().await, Money::from_major(20.0));
+
+        void_return_internal(&pool, cash_return, cashier, "Keyed in error").await.unwrap();
+        void_return_internal(&pool, card_return, cashier, "Keyed in error").await.unwrap();
+        assert_eq!(cash_drawer_balance(&pool, shift).await.unwrap(), Money::from_major(130.0));
+        assert_eq!(shift_returns().await, Money::ZERO);
+        let card_net: Money = sqlx::query_scalar(
+            "SELECT SUM(base_amount) FROM sale_payments WHERE return_id = ?1",
+        )
+        .bind(card_return)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(card_net, Money::ZERO);
+
+        let err = void_return_internal(&pool, cash_return, cashier, "Again").await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+
+    #[tokio::test]
+    async fn test_return_risk_report_flags_heavy_returners() {
+        let pool = test_pool().await;
+        let heavy = insert_test_user(&pool, "cashier1").await;
+        let light = insert_test_user(&pool, "cashier2").await;
+        sqlx::query("UPDATE locations SET return_alert_count = 3, return_alert_value = 100 WHERE id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let returns = [
+            ("SR-1", heavy, Some(1), 20.0, "Completed"),
+            ("SR-2", heavy, None, 30.0, "Completed"),
+            ("SR-3", heavy, Some(2), 10.0, "Pending"),
+            ("SR-4", heavy, Some(3), 500.0, "Rejected"),
+            ("SR-5", light, Some(4), 40.0, "Completed"),
+        ];
+        for (number, user, sale, total, status) in returns {
+            sqlx::query(
+                "INSERT INTO comprehensive_returns (return_number, return_type, reference_id, subtotal,
+                                                   total_amount, status, processed_by, created_at)
+                 VALUES (?1, 'SalesReturn', ?2, ?3, ?3, ?4, ?5, '2024-06-01 10:00:00')",
+            )
+            .bind(number)
+            .bind(sale)
+            .bind(total)
+            .bind(status)
+            .bind(user)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let report = get_return_risk_report_internal(
+            &pool,
+            "2024-06-01",
+            "2024-06-30",
+            DEFAULT_ORGANIZATION_ID,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.count_threshold, 3);
+        assert_eq!(report.cashiers.len(), 2);
+        let top = &report.cashiers[0];
+        assert_eq!(top.user_id, heavy);
+        assert_eq!(top.return_count, 3);
+        assert_eq!(top.return_value, Money::from_major(60.0));
+        assert_eq!(top.unreferenced_count, 1);
+        // 3/3 + 60/100 + 1/3
+        assert_eq!(top.score, 1.93);
+        assert!(top.flagged);
+        assert_eq!(top.reasons.len(), 2);
+        assert!(!report.cashiers[1].flagged);
+        assert_eq!(report.unreferenced_returns.len(), 1);
+        assert_eq!(report.unreferenced_returns[0].return_number, "SR-2");
+    }
+
+    #[tokio::test]
+    async fn test_get_return_by_id_names_supplier_and_users() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let manager = insert_test_user(&pool, "manager1").await;
+        sqlx::query("UPDATE users SET role = 'Manager', first_name = 'Maria', last_name = 'Lopez' WHERE id = ?1")
+            .bind(manager)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let supplier: i64 = sqlx::query_scalar(
+            "INSERT INTO suppliers (supplier_number, company_name) VALUES ('SUP000001', 'Acme Wholesale') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let product = insert_test_product(&pool, "RET-1", 10.0, 10).await;
+        let mut line = returned_line(product, 1);
+        line.disposition = DispositionAction::ReturnToSupplier;
+        let request = NewReturn {
+            return_type: ReturnType::PurchaseReturn,
+            reference_id: None,
+            refund_method: None,
+            supplier_id: Some(supplier),
+            ..sales_return(0, vec![line], cashier)
+        };
+        let return_id = create_return_internal(&pool, request).await.unwrap();
+        approve_return_internal(&pool, return_id, manager, None).await.unwrap();
+
+        let fetched = get_return_by_id_internal(&pool, return_id).await.unwrap();
+        assert_eq!(fetched.supplier_id, Some(supplier));
+        assert_eq!(fetched.supplier_name.as_deref(), Some("Acme Wholesale"));
+        assert_eq!(fetched.processed_by_name.as_deref(), Some("Test User"));
+        assert_eq!(fetched.approved_by_name.as_deref(), Some("Maria Lopez"));
+        assert_eq!(fetched.items_count, 1);
+
+        let err = get_return_by_id_internal(&pool, 999).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_get_sale_for_return_loads_the_sale_header() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let product = insert_test_product(&pool, "RET-1", 10.0, 10).await;
+        let mut request = cash_sale(product, 2);
+        request.customer_name = Some("Ada Lovelace".to_string());
+        request.customer_phone = Some("555-0100".to_string());
+        let sale = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+
+        let loaded = get_sale_for_return_internal(&pool, sale.id).await.unwrap();
+        assert_eq!(loaded.sale_number, sale.sale_number);
+        assert_eq!(loaded.customer_name.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(loaded.customer_phone.as_deref(), Some("555-0100"));
+        assert_eq!(loaded.payment_status.as_deref(), Some("Completed"));
+        assert_eq!(loaded.total_amount, Money::from_major(20.0));
+        assert!(!loaded.is_voided);
+
+        let err = get_sale_for_return_internal(&pool, 999).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_return_items_keep_unrecognized_reasons() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let product = insert_test_product(&pool, "RET-1", 10.0, 10).await;
+        let sale = create_sale_internal(&pool, cash_sale(product, 2), cashier, None).await.unwrap();
+        let return_id = create_return_internal(&pool, sales_return(sale.id, vec![returned_line(product, 1)], cashier))
+            .await
+            .unwrap();
+
+        let items = get_return_items_internal(&pool, return_id).await.unwrap();
+        assert!(matches!(items[0].reason, ReturnReason::Defective));
+        assert!(items[0].unrecognized_reason.is_none());
+        assert!(items[0].unrecognized_condition.is_none());
+
+        // Values written by another system, bypassing this schema's checks
+        sqlx::query("PRAGMA ignore_check_constraints = ON").execute(&pool).await.unwrap();
+        sqlx::query(
+            "UPDATE comprehensive_return_items SET reason = 'Warranty', condition = 'Refurbished'
+             WHERE return_id = ?1",
+        )
+        .bind(return_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("PRAGMA ignore_check_constraints = OFF").execute(&pool).await.unwrap();
+
+        let items = get_return_items_internal(&pool, return_id).await.unwrap();
+        assert!(matches!(items[0].reason, ReturnReason::Other));
+        assert_eq!(items[0].unrecognized_reason.as_deref(), Some("Warranty"));
+        assert!(matches!(items[0].condition, ReturnCondition::New));
+        assert_eq!(items[0].unrecognized_condition.as_deref(), Some("Refurbished"));
+        assert!(matches!(items[0].disposition, DispositionAction::Restock));
+        assert!(items[0].unrecognized_disposition.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_return_status_machine_rejects_illegal_transitions() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let manager = insert_test_user(&pool, "manager1").await;
+        sqlx::query("UPDATE users SET role = 'Manager' WHERE id = ?1")
+            .bind(manager)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let product = insert_test_product(&pool, "RET-1", 10.0, 10).await;
+
+        let supplier_return = |user_id| {
+            let mut line = returned_line(product, 2);
+            line.disposition = DispositionAction::ReturnToSupplier;
+            NewReturn {
+                return_type: ReturnType::PurchaseReturn,
+                reference_id: None,
+                refund_method: None,
+                ..sales_return(0, vec![line], user_id)
+            }
+        };
+        let expect_illegal = |result: AppResult<()>| {
+            assert_eq!(result.unwrap_err().code(), "VALIDATION_ERROR");
+        };
+
+        let return_id = create_return_internal(&pool, supplier_return(cashier)).await.unwrap();
+        // Waits for approval before any stock leaves
+        assert_eq!(current_stock(&pool, product).await, 10);
+
+        let err = approve_return_internal(&pool, return_id, cashier, None).await.unwrap_err();
+        assert_eq!(err.code(), "PERMISSION_DENIED");
+        expect_illegal(process_return_internal(&pool, return_id).await);
+        expect_illegal(complete_return_internal(&pool, return_id, manager, None).await);
+
+        approve_return_internal(&pool, return_id, manager, None).await.unwrap();
+        expect_illegal(approve_return_internal(&pool, return_id, manager, None).await);
+        expect_illegal(complete_return_internal(&pool, return_id, manager, None).await);
+
+        process_return_internal(&pool, return_id).await.unwrap();
+        expect_illegal(approve_return_internal(&pool, return_id, manager, None).await);
+        expect_illegal(process_return_internal(&pool, return_id).await);
+        expect_illegal(void_return_internal(&pool, return_id, manager, "Too late").await);
+
+        complete_return_internal(&pool, return_id, manager, None).await.unwrap();
+        assert_eq!(current_stock(&pool, product).await, 8);
+        let completed_by: Option<i64> =
+            sqlx::query_scalar("SELECT completed_by FROM comprehensive_returns WHERE id = ?1")
+                .bind(return_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(completed_by, Some(manager));
+        expect_illegal(approve_return_internal(&pool, return_id, manager, None).await);
+        expect_illegal(process_return_internal(&pool, return_id).await);
+        expect_illegal(complete_return_internal(&pool, return_id, manager, None).await);
+        expect_illegal(void_return_internal(&pool, return_id, manager, "Too late").await);
+
+        let rejected = create_return_internal(&pool, supplier_return(cashier)).await.unwrap();
+        void_return_internal(&pool, rejected, manager, "Supplier refused").await.unwrap();
+        expect_illegal(approve_return_internal(&pool, rejected, manager, None).await);
+        expect_illegal(process_return_internal(&pool, rejected).await);
+        expect_illegal(complete_return_internal(&pool, rejected, manager, None).await);
+        expect_illegal(void_return_internal(&pool, rejected, manager, "Again").await);
+        assert_eq!(current_stock(&pool, product).await, 8);
+    }
+
+    #[tokio::test]
+    async fn test_offline_return_payload_requires_amounts_and_processor() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+
+        let missing_total = serde_json::json!({
+            "return_type": "SalesReturn",
+            "subtotal": 20.0,
+            "tax_amount": 0.0,
+            "processed_by": cashier,
+        });
+        let err = OfflineReturnPayload::from_json(missing_total).unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert!(err.to_string().contains("total_amount"));
+
+        let unknown_type = serde_json::json!({
+            "return_type": "Refund",
+            "subtotal": 20.0,
+            "tax_amount": 0.0,
+            "total_amount": 20.0,
+            "processed_by": cashier,
+        });
+        assert!(OfflineReturnPayload::from_json(unknown_type).is_err());
+
+        let payload = OfflineReturnPayload::from_json(serde_json::json!({
+            "return_type": "PurchaseReturn",
+            "supplier_id": null,
+            "subtotal": 20.0,
+            "tax_amount": 1.5,
+            "total_amount": 21.5,
+            "processed_by": cashier,
+            "reason": "Damaged in transit",
+        }))
+        .unwrap();
+        let return_id = create_return_offline_internal(&pool, payload, DEFAULT_ORGANIZATION_ID)
+            .await
+            .unwrap();
+        let (return_type, total, status, processed_by): (String, f64, String, i64) = sqlx::query_as(
+            "SELECT return_type, total_amount, status, processed_by FROM comprehensive_returns WHERE id = ?1",
+        )
+        .bind(return_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            (return_type.as_str(), total, status.as_str(), processed_by),
+            ("PurchaseReturn", 21.5, "Pending", cashier)
+        );
+
+        // A cloud record also needs the identity it is merged by
+        let remote = serde_json::json!({
+            "return_type": "SalesReturn",
+            "subtotal": 20.0,
+            "tax_amount": 0.0,
+            "total_amount": 20.0,
+            "processed_by": cashier,
+        });
+        assert_eq!(check_remote_return(&remote).unwrap_err().code(), "VALIDATION_ERROR");
+    }
+}
@@ -1,17 +1,39 @@
-use crate::models::{CreateSaleRequest, Sale, SaleItem};
+use crate::audit;
+use crate::cash_rounding;
+use crate::commands::price_overrides::authorize_price_overrides;
+use crate::cost_history::SALE_ITEM_COST_SQL;
+use crate::currency;
+use crate::db_utils::Pagination;
+use crate::demo_mode;
+use crate::document_numbers::{is_duplicate_number, next_document_number, DOCUMENT_NUMBER_ATTEMPTS};
+use crate::error::{AppError, AppResult};
+use crate::location_stock;
+use crate::lots;
+use crate::margins::{check_sale_margins, enforce_sale_margins, MarginCheck};
+use crate::measure;
+use crate::money::Money;
+use crate::models::{CreateSaleRequest, Listing, Page, PageCursor, Sale, SaleItem};
+use crate::pending_sales::{self, PendingSale, PendingSaleResult};
+use crate::plans;
+use crate::promotions;
+use crate::session::SESSION_MANAGER;
+use crate::sync_outbox::{enqueue_change, SyncOperation};
+use crate::tax_rules::{self, TaxableLine};
+use crate::tenancy::{self, DEFAULT_ORGANIZATION_ID};
+use crate::validation::Validate;
+use crate::webhooks::{self, EVENT_SALE_COMPLETED, EVENT_SALE_VOIDED};
 use serde::{Deserialize, Serialize};
-use sqlx::{Row, SqlitePool};
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
 use tauri::{command, State};
-use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SaleWithDetails {
     pub id: i64,
     pub sale_number: String,
-    pub subtotal: f64,
-    pub tax_amount: f64,
-    pub discount_amount: f64,
-    pub total_amount: f64,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub discount_amount: Money,
+    pub total_amount: Money,
     pub payment_method: String,
     pub payment_status: String,
     pub cashier_id: i64,
@@ -27,313 +49,553 @@ pub struct SaleWithDetails {
     pub shift_id: Option<i64>,
     pub created_at: String,
     pub items_count: i32,
-    pub profit: f64,
+    /// Zero for a voided sale
+    pub profit: Money,
+    /// Whether any return, other than a rejected one, was recorded against the sale
+    pub has_returns: bool,
+    pub returned_amount: Money,
+    /// Rung up while demo mode was on
+    pub is_demo: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SalesStats {
-    pub total_sales: f64,
+    pub total_sales: Money,
     pub total_transactions: i32,
-    pub average_transaction: f64,
-    pub total_profit: f64,
+    pub average_transaction: Money,
+    pub total_profit: Money,
     pub profit_margin: f64,
-    pub cash_sales: f64,
-    pub card_sales: f64,
-    pub mobile_sales: f64,
-    pub check_sales: f64,
+    pub cash_sales: Money,
+    pub card_sales: Money,
+    pub mobile_sales: Money,
+    pub check_sales: Money,
 }
 
 #[command]
 pub async fn create_sale(
     pool: State<'_, SqlitePool>,
-    request: CreateSaleRequest,
+    mut request: CreateSaleRequest,
     cashier_id: i64,
     shift_id: Option<i64>,
-) -> Result<Sale, String> {
-    let pool_ref = pool.inner();
+    session_token: String,
+) -> Result<Sale, AppError> {
+    request.organization_id = Some(tenancy::active_organization(&session_token)?);
+    create_sale_internal(pool.inner(), request, cashier_id, shift_id).await
+}
 
-    // Generate unique sale number
-    let uuid_str = Uuid::new_v4().to_string();
-    let sale_number = format!(
-        "SALE-{}",
-        uuid_str.split('-').next().unwrap_or(&uuid_str[..8])
-    );
+/// Queue a sale rung up while the database or sync target was unavailable. `payload_json`
+/// carries the sale under a client-generated `client_uuid`; resending it is harmless.
+#[command]
+pub async fn create_sale_offline(
+    pool: State<'_, SqlitePool>,
+    payload_json: String,
+    session_token: String,
+) -> Result<PendingSale, AppError> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    pending_sales::queue_offline_sale(pool.inner(), &payload_json, organization_id).await
+}
 
-    // Start transaction
-    let mut tx = pool_ref
-        .begin()
-        .await
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+/// Ring up queued offline sales, reporting which went through and which are still queued
+#[command]
+pub async fn process_pending_sales(
+    pool: State<'_, SqlitePool>,
+) -> Result<Vec<PendingSaleResult>, AppError> {
+    pending_sales::process_pending_sales(pool.inner()).await
+}
 
-    // Create sale record
-    let payment_status = request.payment_status.as_deref().unwrap_or("Completed");
-    let sale_result = sqlx::query(
-        "INSERT INTO sales (sale_number, subtotal, tax_amount, discount_amount, total_amount,
-                           payment_method, payment_status, cashier_id, customer_name, customer_phone,
-                           customer_email, notes, shift_id)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)"
-    )
-    .bind(&sale_number)
-    .bind(request.subtotal)
-    .bind(request.tax_amount)
-    .bind(request.discount_amount)
-    .bind(request.total_amount)
-    .bind(&request.payment_method)
-    .bind(payment_status)
-    .bind(cashier_id)
-    .bind(&request.customer_name)
-    .bind(&request.customer_phone)
-    .bind(&request.customer_email)
-    .bind(&request.notes)
-    .bind(shift_id)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| format!("Failed to create sale: {}", e))?;
+/// Lines of a sale that would sell below cost, and whether the till's location would reject it
+#[command]
+pub async fn validate_sale_margins(
+    pool: State<'_, SqlitePool>,
+    request: CreateSaleRequest,
+) -> Result<MarginCheck, AppError> {
+    let mut conn = pool.acquire().await?;
+    check_sale_margins(&mut conn, &request).await
+}
+
+pub(crate) async fn create_sale_internal(
+    pool_ref: &SqlitePool,
+    mut request: CreateSaleRequest,
+    cashier_id: i64,
+    shift_id: Option<i64>,
+) -> AppResult<Sale> {
+    request.validate()?;
+
+    let organization_id = request.organization_id.unwrap_or(DEFAULT_ORGANIZATION_ID);
+    let currency = currency::organization_currency(pool_ref, organization_id).await?;
+    let override_approved_by = authorize_price_overrides(pool_ref, &request, cashier_id).await?;
+    let is_demo = demo_mode::is_enabled(pool_ref).await?;
+    // Only the cash part is rounded; card payments stay exact
+    let cash_rounding = cash_rounding::cash_rounding_rule(pool_ref, organization_id)
+        .await?
+        .map_or(Money::ZERO, |rule| rule.adjustment(request.cash_portion()));
+
+    // Promotion discounts must be part of the discount on the lines they fall on
+    let promotion_discounts = promotions::evaluate_cart(pool_ref, &request.promotion_codes, &request.items).await?;
+    for (line, item) in request.items.iter().enumerate() {
+        let promotion_discount = promotion_discounts.line_discount(line);
+        if item.discount_amount < promotion_discount {
+            return Err(AppError::validation(
+                "discount_amount",
+                &format!(
+                    "Line {} has a discount of {} but its promotions give {}",
+                    line + 1,
+                    item.discount_amount,
+                    promotion_discount
+                ),
+            ));
+        }
+    }
 
-    let sale_id = sale_result.last_insert_rowid();
+    // Take the write lock up front: a deferred transaction that reads first cannot wait
+    // for it later and fails with "database is locked" when two tills sell at once
+    let mut tx = pool_ref.begin_with("BEGIN IMMEDIATE").await?;
 
-    // Create sale items and update inventory
+    let location_id =
+        location_stock::resolve_sale_location(&mut tx, request.location_id, shift_id).await?;
+    request.location_id = Some(location_id);
+
+    // Only the organization's own products can be sold
+    plans::require_writable(&mut tx, organization_id).await?;
     for item in &request.items {
-        // Get product cost price for profit calculation
-        let product =
-            sqlx::query("SELECT cost_price, is_taxable, tax_rate FROM products WHERE id = ?1")
-                .bind(item.product_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| format!("Failed to get product: {}", e))?;
+        tenancy::require_in_organization(&mut tx, "products", item.product_id, organization_id, "Product")
+            .await?;
+    }
+
+    // Lines sold below cost: rejected here if the location blocks them without approval
+    let below_cost = enforce_sale_margins(&mut tx, &request).await?;
+    let below_cost_approved_by = if below_cost.is_empty() {
+        None
+    } else {
+        request.below_cost_approved_by
+    };
+
+    // Create sale record, drawing a new number if this one is already taken
+    let payment_status = request.payment_status.as_deref().unwrap_or("Completed");
+    let mut attempts = 0;
+    let sale_id = loop {
+        attempts += 1;
+        let sale_number = next_document_number(&mut *tx, "sale").await?;
+        let inserted = sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, tax_amount, discount_amount, total_amount,
+                               payment_method, payment_status, cashier_id, customer_name, customer_phone,
+                               customer_email, notes, shift_id, below_cost_approved_by, location_id,
+                               organization_id, is_demo, cash_rounding)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)"
+        )
+        .bind(&sale_number)
+        .bind(request.subtotal)
+        .bind(request.tax_amount)
+        .bind(request.discount_amount)
+        .bind(request.total_amount)
+        .bind(&request.payment_method)
+        .bind(payment_status)
+        .bind(cashier_id)
+        .bind(&request.customer_name)
+        .bind(&request.customer_phone)
+        .bind(&request.customer_email)
+        .bind(&request.notes)
+        .bind(shift_id)
+        .bind(below_cost_approved_by)
+        .bind(location_id)
+        .bind(organization_id)
+        .bind(is_demo)
+        .bind(cash_rounding)
+        .execute(&mut *tx)
+        .await;
+
+        match inserted {
+            Ok(result) => break result.last_insert_rowid(),
+            Err(e)
+                if attempts < DOCUMENT_NUMBER_ATTEMPTS
+                    && is_duplicate_number(&e, "sales", "sale_number") => {}
+            Err(e) => return Err(e.into()),
+        }
+    };
 
-        let cost_price: f64 = product.try_get("cost_price").map_err(|e| e.to_string())?;
-        let is_taxable: bool = product.try_get("is_taxable").map_err(|e| e.to_string())?;
-        let product_tax_rate: f64 = product.try_get("tax_rate").map_err(|e| e.to_string())?;
+    // Record the tender. A foreign-currency one keeps what was handed over alongside its
+    // store-currency value.
+    if let Some(tender) = &request.tender {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let amount_due = request.total_amount + cash_rounding;
+        let change = currency::change_due(
+            &mut tx,
+            &currency,
+            &tender.currency,
+            tender.amount,
+            amount_due,
+            &today,
+        )
+        .await?;
+        if change.change_base.minor() < -1 {
+            return Err(AppError::validation(
+                "tender_amount",
+                &format!(
+                    "{} {} is worth {} but {} is due",
+                    tender.amount,
+                    change.tender_currency,
+                    currency.format(change.tendered_base),
+                    currency.format(amount_due)
+                ),
+            ));
+        }
+        sqlx::query(
+            "INSERT INTO sale_payments (sale_id, payment_method, currency, amount, exchange_rate,
+                                        base_amount, change_base, change_amount)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )
+        .bind(sale_id)
+        .bind(&request.payment_method)
+        .bind(&change.tender_currency)
+        .bind(change.tender_amount)
+        .bind(change.exchange_rate)
+        .bind(change.tendered_base)
+        .bind(change.change_base.max(Money::ZERO))
+        .bind(change.change_tender.max(Money::ZERO))
+        .execute(&mut *tx)
+        .await?;
+    } else {
+        // A split tender is its rounded cash part plus the rest on the sale's payment method
+        let split_cash = request
+            .cash_amount
+            .filter(|_| !request.payment_method.eq_ignore_ascii_case("cash"));
+        let tenders = match split_cash {
+            Some(cash) => vec![
+                ("Cash", cash + cash_rounding),
+                (request.payment_method.as_str(), request.total_amount - cash),
+            ],
+            None => vec![(
+                request.payment_method.as_str(),
+                request.total_amount + cash_rounding,
+            )],
+        };
+        for (method, amount) in tenders.into_iter().filter(|(_, amount)| *amount != Money::ZERO) {
+            sqlx::query(
+                "INSERT INTO sale_payments (sale_id, payment_method, currency, amount,
+                                            exchange_rate, base_amount)
+                 VALUES (?1, ?2, ?3, ?4, 1.0, ?4)",
+            )
+            .bind(sale_id)
+            .bind(method)
+            .bind(&currency.code)
+            .bind(amount)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
 
-        // Calculate item tax if product is taxable
-        let item_tax = if is_taxable {
-            item.line_total * product_tax_rate
-        } else {
-            0.0
+    // Create sale items and update inventory
+    let rules = tax_rules::active_tax_rules(&mut tx).await?;
+    for (line, item) in request.items.iter().enumerate() {
+        // The product's moving-average cost, snapshotted on the line for COGS
+        let product = sqlx::query(
+            "SELECT name, category, unit_of_measure, selling_price, cost_price, is_taxable, tax_rate
+             FROM products WHERE id = ?1",
+        )
+        .bind(item.product_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found(&format!("product {}", item.product_id)))?;
+
+        let cost_price: Money = product.try_get("cost_price")?;
+        let is_taxable: bool = product.try_get("is_taxable")?;
+        let product_tax_rate: f64 = product.try_get("tax_rate")?;
+
+        let unit_of_measure: String = product.try_get("unit_of_measure")?;
+        if item.measured_quantity.is_some() && !measure::is_measured_unit(&unit_of_measure) {
+            let name: String = product.try_get("name")?;
+            return Err(AppError::validation(
+                "measured_quantity",
+                &format!("{} is sold in whole units ({})", name, unit_of_measure),
+            ));
+        }
+        let quantity = item.whole_quantity();
+
+        // Taxes from the tax rules, or the product's own rate when none are configured,
+        // each rounded to the store currency's smallest unit
+        let category: Option<String> = product.try_get("category")?;
+        let line_taxes = tax_rules::compute_line_taxes(
+            &rules,
+            &currency,
+            &TaxableLine {
+                product_id: item.product_id,
+                category: category.as_deref(),
+                is_taxable,
+                product_tax_rate,
+                line_total: item.line_total,
+            },
+        );
+        let item_tax: Money = line_taxes.iter().map(|tax| tax.tax_amount).sum();
+
+        // Overridden lines keep the list price they replaced
+        let original_unit_price: Option<Money> = match item.price_override {
+            Some(_) => Some(product.try_get("selling_price")?),
+            None => None,
         };
 
         // Create sale item
-        sqlx::query(
+        let sale_item_id = sqlx::query(
             "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, discount_amount,
-                                    line_total, tax_amount, cost_price)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                                    line_total, tax_amount, cost_price, measured_quantity,
+                                    original_unit_price, override_reason, override_approved_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
         )
         .bind(sale_id)
         .bind(item.product_id)
-        .bind(item.quantity)
-        .bind(item.unit_price)
+        .bind(quantity)
+        .bind(item.charged_price())
         .bind(item.discount_amount)
         .bind(item.line_total)
         .bind(item_tax)
         .bind(cost_price)
+        .bind(item.measured_quantity)
+        .bind(original_unit_price)
+        .bind(item.price_override.and(item.override_reason.as_deref()))
+        .bind(item.price_override.and(override_approved_by))
         .execute(&mut *tx)
-        .await
-        .map_err(|e| format!("Failed to create sale item: {}", e))?;
-
-        // Update inventory (decrease stock)
-        let inventory_update = sqlx::query(
-            "UPDATE inventory SET 
-                current_stock = current_stock - ?1,
-                available_stock = available_stock - ?1,
-                last_updated = CURRENT_TIMESTAMP
-             WHERE product_id = ?2",
-        )
-        .bind(item.quantity)
-        .bind(item.product_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| format!("Failed to update inventory: {}", e))?;
-
-        if inventory_update.rows_affected() == 0 {
-            return Err(format!(
-                "Product {} not found in inventory",
-                item.product_id
-            ));
+        .await?
+        .last_insert_rowid();
+        if let (Some(original), Some(charged)) = (original_unit_price, item.price_override) {
+            audit::log_audit(
+                &mut tx,
+                organization_id,
+                Some(cashier_id),
+                "price_override",
+                "sale_item",
+                Some(sale_item_id),
+                Some(&serde_json::json!({ "unit_price": original })),
+                Some(&serde_json::json!({
+                    "unit_price": charged,
+                    "reason": item.override_reason,
+                    "approved_by": override_approved_by,
+                })),
+            )
+            .await?;
         }
+        tax_rules::record_line_taxes(&mut tx, sale_item_id, &line_taxes).await?;
+        promotions::record_line_discounts(&mut tx, sale_item_id, &promotion_discounts, line).await?;
+
+        // Lot-tracked products are drawn from their lots, earliest expiry first
+        lots::allocate_sale_lots(&mut tx, sale_item_id, item.product_id, quantity).await?;
+
+        let (previous_stock, new_stock) = match item.measured_quantity {
+            // Weighed and measured lines deduct their decimal quantity
+            Some(measured) => measure::adjust_measured_stock(&mut tx, item.product_id, -measured).await?,
+            None => {
+                // Update inventory (decrease stock)
+                let inventory_update = sqlx::query(
+                    "UPDATE inventory SET 
+                        current_stock = current_stock - ?1,
+                        available_stock = available_stock - ?1,
+                        last_updated = CURRENT_TIMESTAMP
+                     WHERE product_id = ?2",
+                )
+                .bind(quantity)
+                .bind(item.product_id)
+                .execute(&mut *tx)
+                .await?;
+
+                if inventory_update.rows_affected() == 0 {
+                    return Err(AppError::inventory_not_found(item.product_id));
+                }
+
+                // Get current stock for movement record
+                let current_stock =
+                    sqlx::query("SELECT current_stock FROM inventory WHERE product_id = ?1")
+                        .bind(item.product_id)
+                        .fetch_one(&mut *tx)
+                        .await?;
+
+                let new_stock: i32 = current_stock.try_get("current_stock")?;
+                (new_stock + quantity, new_stock)
+            }
+        };
 
-        // Get previous stock for movement record
-        let prev_stock = sqlx::query(
-            "SELECT current_stock + ?1 as previous_stock FROM inventory WHERE product_id = ?2",
+        location_stock::adjust_location_stock(
+            &mut tx,
+            item.product_id,
+            location_id,
+            new_stock - previous_stock,
         )
-        .bind(item.quantity)
-        .bind(item.product_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| format!("Failed to get previous stock: {}", e))?;
-
-        let previous_stock: i32 = prev_stock
-            .try_get("previous_stock")
-            .map_err(|e| e.to_string())?;
+        .await?;
 
-        // Get current stock for movement record
-        let current_stock =
-            sqlx::query("SELECT current_stock FROM inventory WHERE product_id = ?1")
-                .bind(item.product_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| format!("Failed to get current stock: {}", e))?;
-
-        let new_stock: i32 = current_stock
-            .try_get("current_stock")
-            .map_err(|e| e.to_string())?;
+        // Keep the on-hand level right after this line was rung up for auditing
+        sqlx::query("UPDATE sale_items SET post_sale_stock = ?1 WHERE id = ?2")
+            .bind(new_stock)
+            .bind(sale_item_id)
+            .execute(&mut *tx)
+            .await?;
 
         // Record inventory movement
         sqlx::query(
             "INSERT INTO inventory_movements (product_id, movement_type, quantity_change, previous_stock,
-                                             new_stock, reference_id, reference_type, notes, user_id)
-             VALUES (?1, 'sale', ?2, ?3, ?4, ?5, 'sale', 'Sale transaction', ?6)"
+                                             new_stock, reference_id, reference_type, notes, user_id,
+                                             measured_change)
+             VALUES (?1, 'sale', ?2, ?3, ?4, ?5, 'sale', 'Sale transaction', ?6, ?7)"
         )
         .bind(item.product_id)
-        .bind(-item.quantity)
+        .bind(new_stock - previous_stock)
         .bind(previous_stock)
         .bind(new_stock)
         .bind(sale_id)
         .bind(cashier_id)
+        .bind(item.measured_quantity.map(|measured| -measured))
         .execute(&mut *tx)
-        .await
-        .map_err(|e| format!("Failed to record inventory movement: {}", e))?;
+        .await?;
     }
+    promotions::record_usage(&mut tx, sale_id, &promotion_discounts).await?;
+
+    enqueue_change(&mut tx, "sale", sale_id, SyncOperation::Create).await?;
 
     // Commit transaction
-    tx.commit()
-        .await
-        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    tx.commit().await?;
+    webhooks::emit_entity_event(pool_ref, EVENT_SALE_COMPLETED, "sale", sale_id).await;
 
     // Get the created sale
     let row = sqlx::query(
         "SELECT id, sale_number, subtotal, tax_amount, discount_amount, total_amount,
                 payment_method, payment_status, cashier_id, customer_name, customer_phone,
                 customer_email, notes, is_voided, voided_by, voided_at, void_reason,
-                shift_id, created_at
+                shift_id, created_at, cash_rounding
          FROM sales WHERE id = ?1",
     )
     .bind(sale_id)
     .fetch_one(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to fetch created sale: {}", e))?;
+    .await?;
 
     let sale = Sale {
-        id: row.try_get("id").map_err(|e| e.to_string())?,
-        sale_number: row.try_get("sale_number").map_err(|e| e.to_string())?,
-        subtotal: row.try_get("subtotal").map_err(|e| e.to_string())?,
-        tax_amount: row.try_get("tax_amount").map_err(|e| e.to_string())?,
-        discount_amount: row.try_get("discount_amount").map_err(|e| e.to_string())?,
-        total_amount: row.try_get("total_amount").map_err(|e| e.to_string())?,
-        payment_method: row.try_get("payment_method").map_err(|e| e.to_string())?,
-        payment_status: row.try_get("payment_status").map_err(|e| e.to_string())?,
-        cashier_id: row.try_get("cashier_id").map_err(|e| e.to_string())?,
+        id: row.try_get("id")?,
+        sale_number: row.try_get("sale_number")?,
+        subtotal: row.try_get("subtotal")?,
+        tax_amount: row.try_get("tax_amount")?,
+        discount_amount: row.try_get("discount_amount")?,
+        total_amount: row.try_get("total_amount")?,
+        payment_method: row.try_get("payment_method")?,
+        payment_status: row.try_get("payment_status")?,
+        cashier_id: row.try_get("cashier_id")?,
         customer_name: row.try_get("customer_name").ok().flatten(),
         customer_phone: row.try_get("customer_phone").ok().flatten(),
         customer_email: row.try_get("customer_email").ok().flatten(),
         notes: row.try_get("notes").ok().flatten(),
-        is_voided: row.try_get("is_voided").map_err(|e| e.to_string())?,
+        is_voided: row.try_get("is_voided")?,
         voided_by: row.try_get("voided_by").ok().flatten(),
         voided_at: row.try_get("voided_at").ok().flatten(),
         void_reason: row.try_get("void_reason").ok().flatten(),
         shift_id: row.try_get("shift_id").ok().flatten(),
-        created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
+        created_at: row.try_get("created_at")?,
+        reprint_count: 0,
+        cash_rounding: row.try_get("cash_rounding")?,
     };
 
     Ok(sale)
 }
 
-#[command]
-pub async fn get_sales_with_details(
-    pool: State<'_, SqlitePool>,
-    start_date: Option<String>,
-    end_date: Option<String>,
-    payment_method: Option<String>,
-    limit: Option<i32>,
-    offset: Option<i32>,
-) -> Result<Vec<SaleWithDetails>, String> {
-    let pool_ref = pool.inner();
+/// Conditions for the sales list, shared by the page query and its count. Every value is
+/// bound, so each combination of filters prepares one statement that the connection caches.
+pub(crate) struct SalesFilter<'a> {
+    organization_id: i64,
+    start_date: Option<&'a str>,
+    end_date: Option<&'a str>,
+    payment_method: Option<&'a str>,
+}
 
-    let limit = limit.unwrap_or(100);
-    let offset = offset.unwrap_or(0);
+impl<'a> SalesFilter<'a> {
+    pub(crate) fn new(
+        organization_id: i64,
+        start_date: Option<&'a str>,
+        end_date: Option<&'a str>,
+        payment_method: Option<&'a str>,
+    ) -> Self {
+        Self {
+            organization_id,
+            start_date: start_date.filter(|start| !start.is_empty()),
+            end_date: end_date.filter(|end| !end.is_empty()),
+            payment_method: payment_method.filter(|method| !method.is_empty() && *method != "all"),
+        }
+    }
+
+    /// Push `AND ...` conditions on the `s` alias for `sales`
+    fn push_conditions(&self, query: &mut QueryBuilder<'a, Sqlite>) {
+        query.push(" AND s.organization_id = ").push_bind(self.organization_id);
+        if let Some(start) = self.start_date {
+            query.push(" AND DATE(s.created_at) >= ").push_bind(start);
+        }
+        if let Some(end) = self.end_date {
+            query.push(" AND DATE(s.created_at) <= ").push_bind(end);
+        }
+        if let Some(method) = self.payment_method {
+            query.push(" AND s.payment_method = ").push_bind(method);
+        }
+    }
+}
 
-    let mut query = String::from(
+async fn fetch_sales_with_details(
+    pool_ref: &SqlitePool,
+    filter: &SalesFilter<'_>,
+    pagination: &Pagination,
+) -> AppResult<Vec<SaleWithDetails>> {
+    // Lines and returns are summed per sale in subqueries rather than joined and grouped,
+    // so neither multiplies the other and a sale without lines is still listed
+    let mut query = QueryBuilder::<Sqlite>::new(format!(
         "SELECT s.id, s.sale_number, s.subtotal, s.tax_amount, s.discount_amount, s.total_amount,
                 s.payment_method, s.payment_status, s.cashier_id, s.customer_name, s.customer_phone,
                 s.customer_email, s.notes, s.is_voided, s.voided_by, s.voided_at, s.void_reason,
-                s.shift_id, s.created_at,
+                s.shift_id, s.created_at, s.is_demo,
                 (u.first_name || ' ' || u.last_name) as cashier_name,
-                COUNT(si.id) as items_count,
-                COALESCE(SUM((si.unit_price - si.cost_price) * si.quantity), 0.0) as profit
+                (SELECT COUNT(*) FROM sale_items si WHERE si.sale_id = s.id) as items_count,
+                CASE WHEN s.is_voided THEN 0.0
+                     ELSE (SELECT COALESCE(SUM((si.unit_price - {cost}) * si.quantity), 0.0)
+                           FROM sale_items si WHERE si.sale_id = s.id)
+                END as profit,
+                EXISTS (SELECT 1 FROM comprehensive_returns cr
+                        WHERE cr.return_type = 'SalesReturn' AND cr.reference_id = s.id
+                          AND cr.status != 'Rejected')
+                    OR EXISTS (SELECT 1 FROM returns r WHERE r.original_sale_id = s.id) as has_returns,
+                (SELECT COALESCE(SUM(cr.total_amount), 0.0) FROM comprehensive_returns cr
+                 WHERE cr.return_type = 'SalesReturn' AND cr.reference_id = s.id
+                   AND cr.status != 'Rejected')
+                    + (SELECT COALESCE(SUM(r.total_amount), 0.0) FROM returns r
+                       WHERE r.original_sale_id = s.id) as returned_amount
          FROM sales s
          LEFT JOIN users u ON s.cashier_id = u.id
-         LEFT JOIN sale_items si ON s.id = si.sale_id
          WHERE 1=1",
-    );
-
-    let mut params: Vec<String> = Vec::new();
-    let mut param_count = 0;
-
-    if let Some(ref start) = start_date {
-        if !start.is_empty() {
-            param_count += 1;
-            query.push_str(&format!(" AND DATE(s.created_at) >= ?{}", param_count));
-            params.push(start.clone());
-        }
-    }
-
-    if let Some(ref end) = end_date {
-        if !end.is_empty() {
-            param_count += 1;
-            query.push_str(&format!(" AND DATE(s.created_at) <= ?{}", param_count));
-            params.push(end.clone());
-        }
-    }
-
-    if let Some(ref method) = payment_method {
-        if !method.is_empty() && method != "all" {
-            param_count += 1;
-            query.push_str(&format!(" AND s.payment_method = ?{}", param_count));
-            params.push(method.clone());
-        }
-    }
+        cost = SALE_ITEM_COST_SQL
+    ));
+    filter.push_conditions(&mut query);
+    pagination.push_after(&mut query, "s.created_at", "s.id");
+    query.push(" ORDER BY s.created_at DESC, s.id DESC");
+    pagination.push_limit(&mut query);
 
-    query.push_str(" GROUP BY s.id ORDER BY s.created_at DESC");
-    query.push_str(&format!(" LIMIT ?{}", param_count + 1));
-    query.push_str(&format!(" OFFSET ?{}", param_count + 2));
-    params.push(limit.to_string());
-    params.push(offset.to_string());
-
-    let mut sql_query = sqlx::query(&query);
-    for param in &params {
-        sql_query = sql_query.bind(param);
-    }
-
-    let rows = sql_query
-        .fetch_all(pool_ref)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
+    let rows = query.build().fetch_all(pool_ref).await?;
 
     let mut sales = Vec::new();
     for row in rows {
         let sale = SaleWithDetails {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            sale_number: row.try_get("sale_number").map_err(|e| e.to_string())?,
-            subtotal: row.try_get("subtotal").map_err(|e| e.to_string())?,
-            tax_amount: row.try_get("tax_amount").map_err(|e| e.to_string())?,
-            discount_amount: row.try_get("discount_amount").map_err(|e| e.to_string())?,
-            total_amount: row.try_get("total_amount").map_err(|e| e.to_string())?,
-            payment_method: row.try_get("payment_method").map_err(|e| e.to_string())?,
-            payment_status: row.try_get("payment_status").map_err(|e| e.to_string())?,
-            cashier_id: row.try_get("cashier_id").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            sale_number: row.try_get("sale_number")?,
+            subtotal: row.try_get("subtotal")?,
+            tax_amount: row.try_get("tax_amount")?,
+            discount_amount: row.try_get("discount_amount")?,
+            total_amount: row.try_get("total_amount")?,
+            payment_method: row.try_get("payment_method")?,
+            payment_status: row.try_get("payment_status")?,
+            cashier_id: row.try_get("cashier_id")?,
             cashier_name: row.try_get("cashier_name").ok(),
             customer_name: row.try_get("customer_name").ok().flatten(),
             customer_phone: row.try_get("customer_phone").ok().flatten(),
             customer_email: row.try_get("customer_email").ok().flatten(),
             notes: row.try_get("notes").ok().flatten(),
-            is_voided: row.try_get("is_voided").map_err(|e| e.to_string())?,
+            is_voided: row.try_get("is_voided")?,
             voided_by: row.try_get("voided_by").ok().flatten(),
             voided_at: row.try_get("voided_at").ok().flatten(),
             void_reason: row.try_get("void_reason").ok().flatten(),
             shift_id: row.try_get("shift_id").ok().flatten(),
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-            items_count: row.try_get("items_count").unwrap_or(0),
-            profit: row.try_get("profit").unwrap_or(0.0),
+            created_at: row.try_get("created_at")?,
+            items_count: row.try_get("items_count")?,
+            profit: row.try_get("profit")?,
+            has_returns: row.try_get("has_returns")?,
+            returned_amount: row.try_get("returned_amount")?,
+            is_demo: row.try_get("is_demo")?,
         };
         sales.push(sale);
     }
@@ -341,97 +603,97 @@ pub async fn get_sales_with_details(
     Ok(sales)
 }
 
+async fn count_sales(pool_ref: &SqlitePool, filter: &SalesFilter<'_>) -> AppResult<i64> {
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM sales s WHERE 1=1");
+    filter.push_conditions(&mut query);
+    Ok(query.build_query_scalar::<i64>().fetch_one(pool_ref).await?)
+}
+
+/// Sales newest first with their cashier, profit and returns, paged like `get_sales`
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_sales_with_details(
+    pool: State<'_, SqlitePool>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    payment_method: Option<String>,
+    limit: Option<i32>,
+    offset: Option<i32>,
+    after_id: Option<i64>,
+    after_created_at: Option<String>,
+    envelope: Option<bool>,
+    session_token: String,
+) -> Result<Listing<SaleWithDetails>, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let filter = SalesFilter::new(
+        organization_id,
+        start_date.as_deref(),
+        end_date.as_deref(),
+        payment_method.as_deref(),
+    );
+    let pagination = Pagination::from_params(i64::from(limit.unwrap_or(100)), offset, after_id, after_created_at)?;
+    let with_total = envelope.unwrap_or(false);
+    let page = get_sales_with_details_internal(pool.inner(), &filter, &pagination, with_total).await?;
+    Ok(Listing::new(page, envelope))
+}
+
+/// One page of sales with their details. The total is only counted when `with_total` is set.
+pub(crate) async fn get_sales_with_details_internal(
+    pool_ref: &SqlitePool,
+    filter: &SalesFilter<'_>,
+    pagination: &Pagination,
+    with_total: bool,
+) -> AppResult<Page<SaleWithDetails>> {
+    let items = fetch_sales_with_details(pool_ref, filter, pagination).await?;
+    let total_count = if with_total { count_sales(pool_ref, filter).await? } else { 0 };
+    Ok(pagination.into_page(items, total_count, |sale| PageCursor {
+        created_at: sale.created_at.clone(),
+        id: sale.id,
+    }))
+}
+
 #[command]
 pub async fn get_sales_stats(
     pool: State<'_, SqlitePool>,
     start_date: Option<String>,
     end_date: Option<String>,
-) -> Result<SalesStats, String> {
+    session_token: String,
+) -> Result<SalesStats, AppError> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
+
+    let filter = SalesFilter::new(organization_id, start_date.as_deref(), end_date.as_deref(), None);
 
-    let mut query = String::from(
+    let mut query = QueryBuilder::<Sqlite>::new(
         "SELECT 
-            COALESCE(SUM(total_amount), 0.0) as total_sales,
+            COALESCE(SUM(s.total_amount), 0.0) as total_sales,
             COUNT(*) as total_transactions,
-            COALESCE(AVG(total_amount), 0.0) as average_transaction,
-            COALESCE(SUM(CASE WHEN payment_method = 'cash' THEN total_amount ELSE 0.0 END), 0.0) as cash_sales,
-            COALESCE(SUM(CASE WHEN payment_method = 'card' THEN total_amount ELSE 0.0 END), 0.0) as card_sales,
-            COALESCE(SUM(CASE WHEN payment_method = 'mobile' THEN total_amount ELSE 0.0 END), 0.0) as mobile_sales,
-            COALESCE(SUM(CASE WHEN payment_method = 'check' THEN total_amount ELSE 0.0 END), 0.0) as check_sales
-         FROM sales
-         WHERE is_voided = 0",
+            COALESCE(AVG(s.total_amount), 0.0) as average_transaction,
+            COALESCE(SUM(CASE WHEN s.payment_method = 'cash' THEN s.total_amount ELSE 0.0 END), 0.0) as cash_sales,
+            COALESCE(SUM(CASE WHEN s.payment_method = 'card' THEN s.total_amount ELSE 0.0 END), 0.0) as card_sales,
+            COALESCE(SUM(CASE WHEN s.payment_method = 'mobile' THEN s.total_amount ELSE 0.0 END), 0.0) as mobile_sales,
+            COALESCE(SUM(CASE WHEN s.payment_method = 'check' THEN s.total_amount ELSE 0.0 END), 0.0) as check_sales
+         FROM sales s
+         WHERE s.is_voided = 0",
     );
-
-    let mut params: Vec<String> = Vec::new();
-    let mut param_count = 0;
-
-    if let Some(ref start) = start_date {
-        if !start.is_empty() {
-            param_count += 1;
-            query.push_str(&format!(" AND DATE(created_at) >= ?{}", param_count));
-            params.push(start.clone());
-        }
-    }
-
-    if let Some(ref end) = end_date {
-        if !end.is_empty() {
-            param_count += 1;
-            query.push_str(&format!(" AND DATE(created_at) <= ?{}", param_count));
-            params.push(end.clone());
-        }
-    }
-
-    let mut sql_query = sqlx::query(&query);
-    for param in &params {
-        sql_query = sql_query.bind(param);
-    }
-
-    let row = sql_query
-        .fetch_one(pool_ref)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
+    filter.push_conditions(&mut query);
+    let row = query.build().fetch_one(pool_ref).await?;
 
     // Calculate profit
-    let mut profit_query = String::from(
-        "SELECT COALESCE(SUM((si.unit_price - si.cost_price) * si.quantity), 0.0) as total_profit
+    let mut profit_query = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT COALESCE(SUM((si.unit_price - {}) * si.quantity), 0.0) as total_profit
          FROM sale_items si
          JOIN sales s ON si.sale_id = s.id
          WHERE s.is_voided = 0",
-    );
-
-    let mut profit_params: Vec<String> = Vec::new();
-    let mut profit_param_count = 0;
-
-    if let Some(ref start) = start_date {
-        if !start.is_empty() {
-            profit_param_count += 1;
-            profit_query.push_str(&format!(" AND DATE(s.created_at) >= ?{}", profit_param_count));
-            profit_params.push(start.clone());
-        }
-    }
-
-    if let Some(ref end) = end_date {
-        if !end.is_empty() {
-            profit_param_count += 1;
-            profit_query.push_str(&format!(" AND DATE(s.created_at) <= ?{}", profit_param_count));
-            profit_params.push(end.clone());
-        }
-    }
-
-    let mut profit_sql_query = sqlx::query(&profit_query);
-    for param in &profit_params {
-        profit_sql_query = profit_sql_query.bind(param);
-    }
-
-    let profit_row = profit_sql_query
-        .fetch_one(pool_ref)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
-
-    let total_sales: f64 = row.try_get("total_sales").unwrap_or(0.0);
-    let total_profit: f64 = profit_row.try_get("total_profit").unwrap_or(0.0);
-    let profit_margin = if total_sales > 0.0 {
-        (total_profit / total_sales) * 100.0
+        SALE_ITEM_COST_SQL
+    ));
+    filter.push_conditions(&mut profit_query);
+    let profit_row = profit_query.build().fetch_one(pool_ref).await?;
+
+    let total_sales: Money = row.try_get("total_sales").unwrap_or_default();
+    let total_profit: Money = profit_row.try_get("total_profit").unwrap_or_default();
+    let profit_margin = if total_sales > Money::ZERO {
+        (total_profit.to_major() / total_sales.to_major()) * 100.0
     } else {
         0.0
     };
@@ -439,182 +701,195 @@ pub async fn get_sales_stats(
     let stats = SalesStats {
         total_sales,
         total_transactions: row.try_get("total_transactions").unwrap_or(0),
-        average_transaction: row.try_get("average_transaction").unwrap_or(0.0),
+        average_transaction: row.try_get("average_transaction").unwrap_or_default(),
         total_profit,
         profit_margin,
-        cash_sales: row.try_get("cash_sales").unwrap_or(0.0),
-        card_sales: row.try_get("card_sales").unwrap_or(0.0),
-        mobile_sales: row.try_get("mobile_sales").unwrap_or(0.0),
-        check_sales: row.try_get("check_sales").unwrap_or(0.0),
+        cash_sales: row.try_get("cash_sales").unwrap_or_default(),
+        card_sales: row.try_get("card_sales").unwrap_or_default(),
+        mobile_sales: row.try_get("mobile_sales").unwrap_or_default(),
+        check_sales: row.try_get("check_sales").unwrap_or_default(),
     };
 
     Ok(stats)
 }
 
-#[command]
-pub async fn get_sales(
-    pool: State<'_, SqlitePool>,
-    start_date: Option<String>,
-    end_date: Option<String>,
-    limit: Option<i32>,
-    offset: Option<i32>,
-) -> Result<Vec<Sale>, String> {
-    let pool_ref = pool.inner();
-
-    let limit = limit.unwrap_or(100);
-    let offset = offset.unwrap_or(0);
-
-    let mut query = String::from(
-        "SELECT id, sale_number, subtotal, tax_amount, discount_amount, total_amount,
-                payment_method, payment_status, cashier_id, customer_name, customer_phone,
-                customer_email, notes, is_voided, voided_by, voided_at, void_reason,
-                shift_id, created_at
-         FROM sales
+fn push_sales_page<'a>(query: &mut QueryBuilder<'a, Sqlite>, filter: &SalesFilter<'a>, pagination: &Pagination) {
+    query.push(
+        "SELECT s.id, s.sale_number, s.subtotal, s.tax_amount, s.discount_amount, s.total_amount,
+                s.payment_method, s.payment_status, s.cashier_id, s.customer_name, s.customer_phone,
+                s.customer_email, s.notes, s.is_voided, s.voided_by, s.voided_at, s.void_reason,
+                s.shift_id, s.created_at, s.cash_rounding
+         FROM sales s
          WHERE 1=1",
     );
+    filter.push_conditions(query);
+    pagination.push_after(query, "s.created_at", "s.id");
+    query.push(" ORDER BY s.created_at DESC, s.id DESC");
+    pagination.push_limit(query);
+}
 
-    let mut params: Vec<String> = Vec::new();
-    let mut param_count = 0;
-
-    if let Some(ref start) = start_date {
-        if !start.is_empty() {
-            param_count += 1;
-            query.push_str(&format!(" AND DATE(created_at) >= ?{}", param_count));
-            params.push(start.clone());
-        }
-    }
-
-    if let Some(ref end) = end_date {
-        if !end.is_empty() {
-            param_count += 1;
-            query.push_str(&format!(" AND DATE(created_at) <= ?{}", param_count));
-            params.push(end.clone());
-        }
-    }
-
-    query.push_str(" ORDER BY created_at DESC");
-    query.push_str(&format!(" LIMIT ?{}", param_count + 1));
-    query.push_str(&format!(" OFFSET ?{}", param_count + 2));
-    params.push(limit.to_string());
-    params.push(offset.to_string());
-
-    let mut sql_query = sqlx::query(&query);
-    for param in &params {
-        sql_query = sql_query.bind(param);
-    }
-
-    let rows = sql_query
-        .fetch_all(pool_ref)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
+/// One page of `sales`, newest first. The total is only counted when `with_total` is set.
+pub(crate) async fn get_sales_internal(
+    pool_ref: &SqlitePool,
+    filter: &SalesFilter<'_>,
+    pagination: &Pagination,
+    with_total: bool,
+) -> AppResult<Page<Sale>> {
+    let mut query = QueryBuilder::<Sqlite>::new("");
+    push_sales_page(&mut query, filter, pagination);
+    let rows = query.build().fetch_all(pool_ref).await?;
 
     let mut sales = Vec::new();
     for row in rows {
         let sale = Sale {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            sale_number: row.try_get("sale_number").map_err(|e| e.to_string())?,
-            subtotal: row.try_get("subtotal").map_err(|e| e.to_string())?,
-            tax_amount: row.try_get("tax_amount").map_err(|e| e.to_string())?,
-            discount_amount: row.try_get("discount_amount").map_err(|e| e.to_string())?,
-            total_amount: row.try_get("total_amount").map_err(|e| e.to_string())?,
-            payment_method: row.try_get("payment_method").map_err(|e| e.to_string())?,
-            payment_status: row.try_get("payment_status").map_err(|e| e.to_string())?,
-            cashier_id: row.try_get("cashier_id").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            sale_number: row.try_get("sale_number")?,
+            subtotal: row.try_get("subtotal")?,
+            tax_amount: row.try_get("tax_amount")?,
+            discount_amount: row.try_get("discount_amount")?,
+            total_amount: row.try_get("total_amount")?,
+            payment_method: row.try_get("payment_method")?,
+            payment_status: row.try_get("payment_status")?,
+            cashier_id: row.try_get("cashier_id")?,
             customer_name: row.try_get("customer_name").ok().flatten(),
             customer_phone: row.try_get("customer_phone").ok().flatten(),
             customer_email: row.try_get("customer_email").ok().flatten(),
             notes: row.try_get("notes").ok().flatten(),
-            is_voided: row.try_get("is_voided").map_err(|e| e.to_string())?,
+            is_voided: row.try_get("is_voided")?,
             voided_by: row.try_get("voided_by").ok().flatten(),
             voided_at: row.try_get("voided_at").ok().flatten(),
             void_reason: row.try_get("void_reason").ok().flatten(),
             shift_id: row.try_get("shift_id").ok().flatten(),
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
+            created_at: row.try_get("created_at")?,
+            reprint_count: 0,
+            cash_rounding: row.try_get("cash_rounding")?,
         };
         sales.push(sale);
     }
 
-    Ok(sales)
+    let total_count = if with_total { count_sales(pool_ref, filter).await? } else { 0 };
+    Ok(pagination.into_page(sales, total_count, |sale| PageCursor {
+        created_at: sale.created_at.clone(),
+        id: sale.id,
+    }))
+}
+
+/// Sales newest first. Pass the previous page's `next_cursor` as `after_id` and
+/// `after_created_at` to page by keyset instead of `offset`; with `envelope` the reply
+/// is a `Page` carrying the total count and that cursor.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_sales(
+    pool: State<'_, SqlitePool>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    limit: Option<i32>,
+    offset: Option<i32>,
+    after_id: Option<i64>,
+    after_created_at: Option<String>,
+    envelope: Option<bool>,
+    session_token: String,
+) -> Result<Listing<Sale>, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let filter = SalesFilter::new(organization_id, start_date.as_deref(), end_date.as_deref(), None);
+    let pagination = Pagination::from_params(i64::from(limit.unwrap_or(100)), offset, after_id, after_created_at)?;
+    let page = get_sales_internal(pool.inner(), &filter, &pagination, envelope.unwrap_or(false)).await?;
+    Ok(Listing::new(page, envelope))
 }
 
 #[command]
 pub async fn get_sale_details(
     pool: State<'_, SqlitePool>,
     sale_id: i64,
-) -> Result<(Sale, Vec<SaleItem>), String> {
-    let pool_ref = pool.inner();
+    session_token: String,
+) -> Result<(Sale, Vec<SaleItem>), AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_organization_sale_details_internal(pool.inner(), organization_id, sale_id).await
+}
 
+/// A sale and its lines, as NOT_FOUND when the sale belongs to another organization
+pub(crate) async fn get_organization_sale_details_internal(
+    pool_ref: &SqlitePool,
+    organization_id: i64,
+    sale_id: i64,
+) -> AppResult<(Sale, Vec<SaleItem>)> {
+    let mut conn = pool_ref.acquire().await?;
+    tenancy::require_in_organization(&mut conn, "sales", sale_id, organization_id, "Sale").await?;
+    drop(conn);
+    get_sale_details_internal(pool_ref, sale_id).await
+}
+
+async fn get_sale_details_internal(
+    pool_ref: &SqlitePool,
+    sale_id: i64,
+) -> AppResult<(Sale, Vec<SaleItem>)> {
     // Get sale
     let sale_row = sqlx::query(
         "SELECT id, sale_number, subtotal, tax_amount, discount_amount, total_amount,
                 payment_method, payment_status, cashier_id, customer_name, customer_phone,
                 customer_email, notes, is_voided, voided_by, voided_at, void_reason,
-                shift_id, created_at
+                shift_id, created_at, cash_rounding,
+                (SELECT COUNT(*) FROM receipt_reprints r WHERE r.sale_id = sales.id) as reprint_count
          FROM sales WHERE id = ?1",
     )
     .bind(sale_id)
-    .fetch_one(pool_ref)
-    .await
-    .map_err(|e| format!("Sale not found: {}", e))?;
+    .fetch_optional(pool_ref)
+    .await?
+    .ok_or_else(AppError::sale_not_found)?;
 
     let sale = Sale {
-        id: sale_row.try_get("id").map_err(|e| e.to_string())?,
-        sale_number: sale_row.try_get("sale_number").map_err(|e| e.to_string())?,
-        subtotal: sale_row.try_get("subtotal").map_err(|e| e.to_string())?,
-        tax_amount: sale_row.try_get("tax_amount").map_err(|e| e.to_string())?,
-        discount_amount: sale_row
-            .try_get("discount_amount")
-            .map_err(|e| e.to_string())?,
-        total_amount: sale_row
-            .try_get("total_amount")
-            .map_err(|e| e.to_string())?,
-        payment_method: sale_row
-            .try_get("payment_method")
-            .map_err(|e| e.to_string())?,
-        payment_status: sale_row
-            .try_get("payment_status")
-            .map_err(|e| e.to_string())?,
-        cashier_id: sale_row.try_get("cashier_id").map_err(|e| e.to_string())?,
+        id: sale_row.try_get("id")?,
+        sale_number: sale_row.try_get("sale_number")?,
+        subtotal: sale_row.try_get("subtotal")?,
+        tax_amount: sale_row.try_get("tax_amount")?,
+        discount_amount: sale_row.try_get("discount_amount")?,
+        total_amount: sale_row.try_get("total_amount")?,
+        payment_method: sale_row.try_get("payment_method")?,
+        payment_status: sale_row.try_get("payment_status")?,
+        cashier_id: sale_row.try_get("cashier_id")?,
         customer_name: sale_row.try_get("customer_name").ok().flatten(),
         customer_phone: sale_row.try_get("customer_phone").ok().flatten(),
         customer_email: sale_row.try_get("customer_email").ok().flatten(),
         notes: sale_row.try_get("notes").ok().flatten(),
-        is_voided: sale_row.try_get("is_voided").map_err(|e| e.to_string())?,
+        is_voided: sale_row.try_get("is_voided")?,
         voided_by: sale_row.try_get("voided_by").ok().flatten(),
         voided_at: sale_row.try_get("voided_at").ok().flatten(),
         void_reason: sale_row.try_get("void_reason").ok().flatten(),
         shift_id: sale_row.try_get("shift_id").ok().flatten(),
-        created_at: sale_row.try_get("created_at").map_err(|e| e.to_string())?,
+        created_at: sale_row.try_get("created_at")?,
+        reprint_count: sale_row.try_get("reprint_count")?,
+        cash_rounding: sale_row.try_get("cash_rounding")?,
     };
 
     // Get sale items with product names
     let items_rows = sqlx::query(
-        "SELECT si.id, si.sale_id, si.product_id, si.quantity, si.unit_price, si.discount_amount,
-                si.line_total, si.tax_amount, si.cost_price, si.created_at,
-                p.name as product_name
+        "SELECT si.id, si.sale_id, si.product_id, si.quantity, si.measured_quantity, si.unit_price,
+                si.discount_amount, si.line_total, si.tax_amount, si.cost_price, si.created_at,
+                si.post_sale_stock, p.name as product_name
          FROM sale_items si
          LEFT JOIN products p ON si.product_id = p.id
          WHERE si.sale_id = ?1",
     )
     .bind(sale_id)
     .fetch_all(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to get sale items: {}", e))?;
+    .await?;
 
     let mut items = Vec::new();
     for row in items_rows {
         let product_name: Option<String> = row.try_get("product_name").ok();
         let item = SaleItem {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            sale_id: row.try_get("sale_id").map_err(|e| e.to_string())?,
-            product_id: row.try_get("product_id").map_err(|e| e.to_string())?,
-            quantity: row.try_get("quantity").map_err(|e| e.to_string())?,
-            unit_price: row.try_get("unit_price").map_err(|e| e.to_string())?,
-            discount_amount: row.try_get("discount_amount").map_err(|e| e.to_string())?,
-            line_total: row.try_get("line_total").map_err(|e| e.to_string())?,
-            tax_amount: row.try_get("tax_amount").map_err(|e| e.to_string())?,
-            cost_price: row.try_get("cost_price").map_err(|e| e.to_string())?,
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            sale_id: row.try_get("sale_id")?,
+            product_id: row.try_get("product_id")?,
+            quantity: row.try_get("quantity")?,
+            measured_quantity: row.try_get("measured_quantity")?,
+            unit_price: row.try_get("unit_price")?,
+            discount_amount: row.try_get("discount_amount")?,
+            line_total: row.try_get("line_total")?,
+            tax_amount: row.try_get("tax_amount")?,
+            cost_price: row.try_get("cost_price")?,
+            post_sale_stock: row.try_get("post_sale_stock")?,
+            created_at: row.try_get("created_at")?,
             product: product_name.map(|name| crate::models::Product {
                 id: row.try_get("product_id").unwrap_or(0),
                 sku: String::new(),
@@ -650,32 +925,43 @@ pub async fn void_sale(
     pool: State<'_, SqlitePool>,
     sale_id: i64,
     reason: String,
-    user_id: i64,
-) -> Result<bool, String> {
-    let pool_ref = pool.inner();
+    session_token: String,
+) -> Result<bool, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    void_sale_internal(pool.inner(), sale_id, &reason, session.user_id, session.organization_id)
+        .await
+}
 
+async fn void_sale_internal(
+    pool_ref: &SqlitePool,
+    sale_id: i64,
+    reason: &str,
+    user_id: i64,
+    organization_id: i64,
+) -> AppResult<bool> {
     // Check if sale exists and is not already voided
-    let sale_check = sqlx::query("SELECT is_voided FROM sales WHERE id = ?1")
-        .bind(sale_id)
-        .fetch_optional(pool_ref)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
+    let sale_check = sqlx::query(
+        "SELECT is_voided, location_id FROM sales WHERE id = ?1 AND organization_id = ?2",
+    )
+    .bind(sale_id)
+    .bind(organization_id)
+    .fetch_optional(pool_ref)
+    .await?;
 
     let sale_check = match sale_check {
         Some(s) => s,
-        None => return Err("Sale not found".to_string()),
+        None => return Err(AppError::sale_not_found()),
     };
 
-    let is_voided: bool = sale_check.try_get("is_voided").map_err(|e| e.to_string())?;
+    let is_voided: bool = sale_check.try_get("is_voided")?;
+    let location_id: i64 = sale_check.try_get("location_id")?;
     if is_voided {
-        return Err("Sale is already voided".to_string());
+        return Err(AppError::sale_already_voided());
     }
 
     // Start transaction
-    let mut tx = pool_ref
-        .begin()
-        .await
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut tx = pool_ref.begin().await?;
 
     // Mark sale as voided
     sqlx::query(
@@ -683,76 +969,97 @@ pub async fn void_sale(
             is_voided = 1,
             voided_by = ?1,
             voided_at = CURRENT_TIMESTAMP,
-            void_reason = ?2
+            void_reason = ?2,
+            updated_at = CURRENT_TIMESTAMP
          WHERE id = ?3",
     )
     .bind(user_id)
-    .bind(&reason)
+    .bind(reason)
     .bind(sale_id)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| format!("Failed to void sale: {}", e))?;
+    .await?;
+    audit::log_audit(
+        &mut tx,
+        organization_id,
+        Some(user_id),
+        "void_sale",
+        "sale",
+        Some(sale_id),
+        Some(&serde_json::json!({ "is_voided": false })),
+        Some(&serde_json::json!({ "is_voided": true, "void_reason": reason })),
+    )
+    .await?;
+
+    lots::restore_sale_lots(&mut tx, sale_id).await?;
 
     // Get sale items to restore inventory
-    let items = sqlx::query("SELECT product_id, quantity FROM sale_items WHERE sale_id = ?1")
-        .bind(sale_id)
-        .fetch_all(&mut *tx)
-        .await
-        .map_err(|e| format!("Failed to get sale items: {}", e))?;
+    let items =
+        sqlx::query("SELECT product_id, quantity, measured_quantity FROM sale_items WHERE sale_id = ?1")
+            .bind(sale_id)
+            .fetch_all(&mut *tx)
+            .await?;
 
     // Restore inventory for each item
     for item in items {
-        let product_id: i64 = item.try_get("product_id").map_err(|e| e.to_string())?;
-        let quantity: i32 = item.try_get("quantity").map_err(|e| e.to_string())?;
-
-        // Get previous stock for movement record
-        let prev_stock = sqlx::query("SELECT current_stock FROM inventory WHERE product_id = ?1")
-            .bind(product_id)
-            .fetch_one(&mut *tx)
-            .await
-            .map_err(|e| format!("Failed to get previous stock: {}", e))?;
-
-        let previous_stock: i32 = prev_stock
-            .try_get("current_stock")
-            .map_err(|e| e.to_string())?;
-
-        // Update inventory (increase stock)
-        sqlx::query(
-            "UPDATE inventory SET 
-                current_stock = current_stock + ?1,
-                available_stock = available_stock + ?1,
-                last_updated = CURRENT_TIMESTAMP
-             WHERE product_id = ?2",
-        )
-        .bind(quantity)
-        .bind(product_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| format!("Failed to restore inventory: {}", e))?;
-
-        let new_stock = previous_stock + quantity;
+        let product_id: i64 = item.try_get("product_id")?;
+        let quantity: i32 = item.try_get("quantity")?;
+        let measured_quantity: Option<f64> = item.try_get("measured_quantity")?;
+
+        let (previous_stock, new_stock) = match measured_quantity {
+            Some(measured) => measure::adjust_measured_stock(&mut tx, product_id, measured).await?,
+            None => {
+                // Get previous stock for movement record
+                let prev_stock =
+                    sqlx::query("SELECT current_stock FROM inventory WHERE product_id = ?1")
+                        .bind(product_id)
+                        .fetch_one(&mut *tx)
+                        .await?;
+
+                let previous_stock: i32 = prev_stock.try_get("current_stock")?;
+
+                // Update inventory (increase stock)
+                sqlx::query(
+                    "UPDATE inventory SET 
+                        current_stock = current_stock + ?1,
+                        available_stock = available_stock + ?1,
+                        last_updated = CURRENT_TIMESTAMP
+                     WHERE product_id = ?2",
+                )
+                .bind(quantity)
+                .bind(product_id)
+                .execute(&mut *tx)
+                .await?;
+
+                (previous_stock, previous_stock + quantity)
+            }
+        };
 
         // Record inventory movement
         sqlx::query(
             "INSERT INTO inventory_movements (product_id, movement_type, quantity_change, previous_stock,
-                                             new_stock, reference_id, reference_type, notes, user_id)
-             VALUES (?1, 'void', ?2, ?3, ?4, ?5, 'void', 'Sale voided', ?6)"
+                                             new_stock, reference_id, reference_type, notes, user_id,
+                                             measured_change)
+             VALUES (?1, 'void', ?2, ?3, ?4, ?5, 'void', 'Sale voided', ?6, ?7)"
         )
         .bind(product_id)
-        .bind(quantity)
+        .bind(new_stock - previous_stock)
         .bind(previous_stock)
         .bind(new_stock)
         .bind(sale_id)
         .bind(user_id)
+        .bind(measured_quantity)
         .execute(&mut *tx)
-        .await
-        .map_err(|e| format!("Failed to record inventory movement: {}", e))?;
+        .await?;
+
+        location_stock::adjust_location_stock(&mut tx, product_id, location_id, new_stock - previous_stock)
+            .await?;
     }
 
+    enqueue_change(&mut tx, "sale", sale_id, SyncOperation::Update).await?;
+
     // Commit transaction
-    tx.commit()
-        .await
-        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    tx.commit().await?;
+    webhooks::emit_entity_event(pool_ref, EVENT_SALE_VOIDED, "sale", sale_id).await;
 
     Ok(true)
 }
@@ -763,8 +1070,10 @@ pub async fn search_sales(
     query: String,
     limit: Option<i32>,
     offset: Option<i32>,
-) -> Result<Vec<Sale>, String> {
+    session_token: String,
+) -> Result<Vec<Sale>, AppError> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
 
     let limit = limit.unwrap_or(50);
     let offset = offset.unwrap_or(0);
@@ -773,44 +1082,924 @@ pub async fn search_sales(
         "SELECT id, sale_number, subtotal, tax_amount, discount_amount, total_amount,
                 payment_method, payment_status, cashier_id, customer_name, customer_phone,
                 customer_email, notes, is_voided, voided_by, voided_at, void_reason,
-                shift_id, created_at
+                shift_id, created_at, cash_rounding
          FROM sales
-         WHERE sale_number LIKE ?1 OR customer_name LIKE ?1 OR customer_phone LIKE ?1
+         WHERE (sale_number LIKE ?1 OR customer_name LIKE ?1 OR customer_phone LIKE ?1)
+           AND organization_id = ?4
          ORDER BY created_at DESC
          LIMIT ?2 OFFSET ?3",
     )
     .bind(format!("%{}%", query))
     .bind(limit)
     .bind(offset)
+    .bind(organization_id)
     .fetch_all(pool_ref)
-    .await
-    .map_err(|e| format!("Database error: {}", e))?;
+    .await?;
 
     let mut sales = Vec::new();
     for row in rows {
         let sale = Sale {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            sale_number: row.try_get("sale_number").map_err(|e| e.to_string())?,
-            subtotal: row.try_get("subtotal").map_err(|e| e.to_string())?,
-            tax_amount: row.try_get("tax_amount").map_err(|e| e.to_string())?,
-            discount_amount: row.try_get("discount_amount").map_err(|e| e.to_string())?,
-            total_amount: row.try_get("total_amount").map_err(|e| e.to_string())?,
-            payment_method: row.try_get("payment_method").map_err(|e| e.to_string())?,
-            payment_status: row.try_get("payment_status").map_err(|e| e.to_string())?,
-            cashier_id: row.try_get("cashier_id").map_err(|e| e.to_string())?,
+            id: row.try_get("id")?,
+            sale_number: row.try_get("sale_number")?,
+            subtotal: row.try_get("subtotal")?,
+            tax_amount: row.try_get("tax_amount")?,
+            discount_amount: row.try_get("discount_amount")?,
+            total_amount: row.try_get("total_amount")?,
+            payment_method: row.try_get("payment_method")?,
+            payment_status: row.try_get("payment_status")?,
+            cashier_id: row.try_get("cashier_id")?,
             customer_name: row.try_get("customer_name").ok().flatten(),
             customer_phone: row.try_get("customer_phone").ok().flatten(),
             customer_email: row.try_get("customer_email").ok().flatten(),
             notes: row.try_get("notes").ok().flatten(),
-            is_voided: row.try_get("is_voided").map_err(|e| e.to_string())?,
+            is_voided: row.try_get("is_voided")?,
             voided_by: row.try_get("voided_by").ok().flatten(),
             voided_at: row.try_get("voided_at").ok().flatten(),
             void_reason: row.try_get("void_reason").ok().flatten(),
             shift_id: row.try_get("shift_id").ok().flatten(),
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
+            created_at: row.try_get("created_at")?,
+            reprint_count: 0,
+            cash_rounding: row.try_get("cash_rounding")?,
         };
         sales.push(sale);
     }
 
     Ok(sales)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_rounding::RoundingDirection;
+    use crate::commands::cash_drawer::cash_drawer_balance;
+    use crate::commands::tax_rules::get_tax_report_by_rule_internal;
+    use crate::database::{apply_migrations, connect_options};
+    use crate::models::{SaleItemRequest, TenderRequest};
+    use crate::organization_settings;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+
+    fn sale_request(items: &[(i64, i32, f64)]) -> CreateSaleRequest {
+        let items: Vec<SaleItemRequest> = items
+            .iter()
+            .map(|&(product_id, quantity, unit_price)| {
+                let unit_price = Money::from_major(unit_price);
+                SaleItemRequest {
+                    product_id,
+                    quantity,
+                    unit_price,
+                    discount_amount: Money::ZERO,
+                    line_total: unit_price.times(quantity),
+                    measured_quantity: None,
+                    price_override: None,
+                    override_reason: None,
+                }
+            })
+            .collect();
+        let subtotal = items.iter().map(|i| i.line_total).sum();
+        CreateSaleRequest {
+            items,
+            subtotal,
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: subtotal,
+            payment_method: "cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        }
+    }
+
+    async fn movements(
+        pool: &SqlitePool,
+        sale_id: i64,
+        movement_type: &str,
+    ) -> Vec<(i64, i32, i32, i32)> {
+        sqlx::query_as(
+            "SELECT product_id, quantity_change, previous_stock, new_stock
+             FROM inventory_movements
+             WHERE reference_id = ?1 AND movement_type = ?2
+             ORDER BY product_id",
+        )
+        .bind(sale_id)
+        .bind(movement_type)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_create_sale_deducts_inventory_and_records_movements() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+        let gadget = insert_test_product(&pool, "GADGET", 4.5, 5).await;
+
+        let sale = create_sale_internal(
+            &pool,
+            sale_request(&[(widget, 3, 10.0), (gadget, 5, 4.5)]),
+            cashier,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(sale.total_amount, Money::from_minor(5250));
+        assert!(!sale.is_voided);
+        assert!(sale.sale_number.starts_with("SALE-"));
+        assert!(sale.sale_number.ends_with("-000001"));
+
+        assert_eq!(current_stock(&pool, widget).await, 17);
+        assert_eq!(current_stock(&pool, gadget).await, 0);
+        assert_eq!(
+            movements(&pool, sale.id, "sale").await,
+            vec![(widget, -3, 20, 17), (gadget, -5, 5, 0)]
+        );
+
+        let (_, items) = get_sale_details_internal(&pool, sale.id).await.unwrap();
+        assert_eq!(items.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sale_lines_record_stock_left_after_each_sale() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 10).await;
+
+        let mut stock_after = Vec::new();
+        for quantity in [3, 3] {
+            let sale = create_sale_internal(
+                &pool,
+                sale_request(&[(widget, quantity, 10.0)]),
+                cashier,
+                None,
+            )
+            .await
+            .unwrap();
+            let (_, items) = get_sale_details_internal(&pool, sale.id).await.unwrap();
+            stock_after.push(items[0].post_sale_stock);
+        }
+        assert_eq!(stock_after, vec![Some(7), Some(4)]);
+    }
+
+    #[tokio::test]
+    async fn test_user_in_another_organization_cannot_fetch_the_sale() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 10).await;
+        let org_b: i64 = sqlx::query_scalar(
+            "INSERT INTO organizations (name, slug) VALUES ('Other Shop', 'other-shop') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let sale = create_sale_internal(&pool, sale_request(&[(widget, 1, 10.0)]), cashier, None)
+            .await
+            .unwrap();
+
+        let token = crate::session::SESSION_MANAGER.create_session(
+            cashier,
+            "cashier1".to_string(),
+            "Cashier".to_string(),
+        );
+        crate::session::SESSION_MANAGER.set_organization(&token, org_b).unwrap();
+        let org = tenancy::active_organization(&token).unwrap();
+        assert_eq!(org, org_b);
+
+        let err = get_organization_sale_details_internal(&pool, org, sale.id).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+        let filter = SalesFilter::new(org, None, None, None);
+        let pagination = Pagination::Offset { limit: 10, offset: 0 };
+        let page = get_sales_with_details_internal(&pool, &filter, &pagination, true).await.unwrap();
+        assert_eq!(page.total_count, 0);
+        let (own, _) = get_organization_sale_details_internal(&pool, DEFAULT_ORGANIZATION_ID, sale.id)
+            .await
+            .unwrap();
+        assert_eq!(own.id, sale.id);
+
+        // Nor can it sell the other organization's products
+        let request = CreateSaleRequest {
+            organization_id: Some(org_b),
+            ..sale_request(&[(widget, 1, 10.0)])
+        };
+        let err = create_sale_internal(&pool, request, cashier, None).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+
+        // Nor void it
+        let err = void_sale_internal(&pool, sale.id, "Mistake", cashier, org_b).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+
+        // Leaving out the token is not a way into the default organization
+        let err = tenancy::active_organization("").unwrap_err();
+        assert_eq!(err.code(), "SESSION_INVALID");
+    }
+
+    #[tokio::test]
+    async fn test_two_locations_sell_the_same_sku_from_their_own_stock() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 10).await;
+        let branch: i64 = sqlx::query_scalar("INSERT INTO locations (name) VALUES ('Branch') RETURNING id")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO location_inventory (product_id, location_id, current_stock) VALUES (?1, ?2, 4)")
+            .bind(widget)
+            .bind(branch)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        create_sale_internal(&pool, sale_request(&[(widget, 2, 10.0)]), cashier, None)
+            .await
+            .unwrap();
+        let mut branch_sale = sale_request(&[(widget, 1, 10.0)]);
+        branch_sale.location_id = Some(branch);
+        create_sale_internal(&pool, branch_sale, cashier, None).await.unwrap();
+
+        let stock = location_stock::stock_by_location(&pool, widget).await.unwrap();
+        let stock: Vec<(i64, i32)> = stock.iter().map(|s| (s.location_id, s.current_stock)).collect();
+        assert_eq!(stock, vec![(1, 4), (branch, 3)]);
+        assert_eq!(current_stock(&pool, widget).await, 7);
+
+        let branch_report =
+            crate::commands::reports::get_sales_report_internal(&pool, DEFAULT_ORGANIZATION_ID, None, None, Some(branch), false)
+                .await
+                .unwrap();
+        assert_eq!(branch_report.total_transactions, 1);
+        assert_eq!(branch_report.total_sales, Money::from_minor(1000));
+        let valuation =
+            crate::commands::reports::get_inventory_valuation_internal(&pool, DEFAULT_ORGANIZATION_ID, Some(branch))
+                .await
+                .unwrap();
+        assert_eq!(valuation.total_units, 3);
+        assert_eq!(valuation.total_value, Money::from_major(15.0));
+    }
+
+    #[tokio::test]
+    async fn test_create_sale_rejects_non_positive_quantities() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+
+        for quantity in [0, -5] {
+            let request = sale_request(&[(widget, quantity, 10.0)]);
+            let err = create_sale_internal(&pool, request, cashier, None)
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), "VALIDATION_ERROR");
+            assert!(err.message().contains(&format!("product {}", widget)));
+        }
+
+        let request = sale_request(&[(widget, 1, -10.0)]);
+        let err = create_sale_internal(&pool, request, cashier, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert_eq!(current_stock(&pool, widget).await, 20);
+    }
+
+    #[tokio::test]
+    async fn test_create_sale_for_unknown_product_rolls_back() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+
+        let err = create_sale_internal(
+            &pool,
+            sale_request(&[(widget, 2, 10.0), (999, 1, 1.0)]),
+            cashier,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+
+        assert_eq!(current_stock(&pool, widget).await, 20);
+        let sales: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sales")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(sales, 0);
+        let outbox: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sync_outbox")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(outbox, 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_sale_skips_number_already_taken() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+
+        // A sale synced in from another till already holds the next number
+        let year = chrono::Local::now().format("%Y").to_string();
+        sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id)
+             VALUES (?1, 10.0, 10.0, 'cash', ?2)",
+        )
+        .bind(format!("SALE-{}-000001", year))
+        .bind(cashier)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let sale = create_sale_internal(&pool, sale_request(&[(widget, 1, 10.0)]), cashier, None)
+            .await
+            .unwrap();
+        assert_eq!(sale.sale_number, format!("SALE-{}-000002", year));
+    }
+
+    #[tokio::test]
+    async fn test_create_sale_enqueues_one_outbox_row() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+        let gadget = insert_test_product(&pool, "GADGET", 4.5, 5).await;
+
+        let sale = create_sale_internal(
+            &pool,
+            sale_request(&[(widget, 3, 10.0), (gadget, 1, 4.5)]),
+            cashier,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let batch = crate::sync_outbox::get_pending_batch(&pool, None).await.unwrap();
+        assert_eq!(batch.len(), 1);
+        let entry = &batch[0];
+        assert_eq!(entry.entity_type, "sale");
+        assert_eq!(entry.entity_id, sale.id);
+        assert_eq!(entry.operation, "create");
+        assert_eq!(entry.payload["sale_number"], sale.sale_number.as_str());
+        assert_eq!(entry.payload["items"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_void_sale_restores_inventory() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+
+        let sale = create_sale_internal(&pool, sale_request(&[(widget, 4, 10.0)]), cashier, None)
+            .await
+            .unwrap();
+        assert_eq!(current_stock(&pool, widget).await, 16);
+
+        assert!(
+            void_sale_internal(&pool, sale.id, "Customer changed mind", cashier, DEFAULT_ORGANIZATION_ID)
+                .await
+                .unwrap()
+        );
+        assert_eq!(current_stock(&pool, widget).await, 20);
+        assert_eq!(
+            movements(&pool, sale.id, "void").await,
+            vec![(widget, 4, 16, 20)]
+        );
+
+        let (voided, _) = get_sale_details_internal(&pool, sale.id).await.unwrap();
+        assert!(voided.is_voided);
+        assert_eq!(voided.void_reason.as_deref(), Some("Customer changed mind"));
+
+        // A second void is rejected and does not restock again
+        let err = void_sale_internal(&pool, sale.id, "again", cashier, DEFAULT_ORGANIZATION_ID)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+        assert_eq!(current_stock(&pool, widget).await, 20);
+    }
+
+    #[tokio::test]
+    async fn test_measured_sale_deducts_decimal_quantity() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let cement = insert_test_product(&pool, "CEMENT-MIX", 4.0, 10).await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+        sqlx::query("UPDATE products SET unit_of_measure = 'Kilogram' WHERE id = ?1")
+            .bind(cement)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 2.5 kg at 4.00/kg
+        let mut request = sale_request(&[(cement, 3, 4.0)]);
+        request.items[0].measured_quantity = Some(2.5);
+        request.items[0].line_total = Money::from_minor(1000);
+        request.subtotal = Money::from_minor(1000);
+        request.total_amount = Money::from_minor(1000);
+        let sale = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+        assert_eq!(sale.total_amount, Money::from_minor(1000));
+
+        // 7.5 kg left: 7 whole units plus half a unit
+        let fraction: f64 =
+            sqlx::query_scalar("SELECT stock_fraction FROM inventory WHERE product_id = ?1")
+                .bind(cement)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(current_stock(&pool, cement).await, 7);
+        assert_eq!(fraction, 0.5);
+        assert_eq!(movements(&pool, sale.id, "sale").await, vec![(cement, -3, 10, 7)]);
+
+        let (_, items) = get_sale_details_internal(&pool, sale.id).await.unwrap();
+        assert_eq!(items[0].measured_quantity, Some(2.5));
+        assert_eq!(items[0].quantity, 3);
+
+        // Another 2.5 kg brings it to exactly 5
+        let mut request = sale_request(&[(cement, 3, 4.0)]);
+        request.items[0].measured_quantity = Some(2.5);
+        request.items[0].line_total = Money::from_minor(1000);
+        request.subtotal = Money::from_minor(1000);
+        request.total_amount = Money::from_minor(1000);
+        let second = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+        assert_eq!(current_stock(&pool, cement).await, 5);
+        assert_eq!(movements(&pool, second.id, "sale").await, vec![(cement, -2, 7, 5)]);
+
+        // Voiding puts the 2.5 kg back
+        void_sale_internal(&pool, sale.id, "Wrong bag", cashier, DEFAULT_ORGANIZATION_ID).await.unwrap();
+        assert_eq!(current_stock(&pool, cement).await, 7);
+        assert_eq!(movements(&pool, sale.id, "void").await, vec![(cement, 2, 5, 7)]);
+
+        // Counted products cannot be sold by weight
+        let mut request = sale_request(&[(widget, 3, 10.0)]);
+        request.items[0].measured_quantity = Some(2.5);
+        request.items[0].line_total = Money::from_minor(2500);
+        request.subtotal = Money::from_minor(2500);
+        request.total_amount = Money::from_minor(2500);
+        let err = create_sale_internal(&pool, request, cashier, None).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert_eq!(current_stock(&pool, widget).await, 20);
+    }
+
+    #[tokio::test]
+    async fn test_foreign_tender_is_converted_and_kept() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.5, 20).await;
+
+        // No EUR rate yet
+        let mut request = sale_request(&[(widget, 1, 10.5)]);
+        request.tender = Some(TenderRequest {
+            currency: "EUR".to_string(),
+            amount: Money::from_minor(1000),
+        });
+        let err = create_sale_internal(&pool, request, cashier, None).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert_eq!(current_stock(&pool, widget).await, 20);
+
+        sqlx::query(
+            "INSERT INTO exchange_rates (currency, rate_to_base, effective_date)
+             VALUES ('EUR', 1.0857, '2020-01-01')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // 9.00 EUR is 9.77 USD, short of 10.50
+        let mut request = sale_request(&[(widget, 1, 10.5)]);
+        request.tender = Some(TenderRequest {
+            currency: "EUR".to_string(),
+            amount: Money::from_minor(900),
+        });
+        let err = create_sale_internal(&pool, request, cashier, None).await.unwrap_err();
+        assert_eq!(err.message(), "9.00 EUR is worth $9.77 but $10.50 is due");
+
+        let mut request = sale_request(&[(widget, 1, 10.5)]);
+        request.tender = Some(TenderRequest {
+            currency: "EUR".to_string(),
+            amount: Money::from_minor(1000),
+        });
+        let sale = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+        assert_eq!(sale.total_amount, Money::from_minor(1050));
+
+        let payment: (String, Money, f64, Money, Money, Money) = sqlx::query_as(
+            "SELECT currency, amount, exchange_rate, base_amount, change_base, change_amount
+             FROM sale_payments WHERE sale_id = ?1",
+        )
+        .bind(sale.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            payment,
+            (
+                "EUR".to_string(),
+                Money::from_minor(1000),
+                1.0857,
+                Money::from_minor(1086),
+                Money::from_minor(36),
+                Money::from_minor(33)
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cash_part_of_a_sale_is_rounded_to_the_store_increment() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+        let shift_id = sqlx::query(
+            "INSERT INTO shifts (user_id, start_time, opening_amount, status)
+             VALUES (?1, CURRENT_TIMESTAMP, 100, 'open')",
+        )
+        .bind(cashier)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        let mut conn = pool.acquire().await.unwrap();
+        let mut rounding = organization_settings::load(&mut conn, DEFAULT_ORGANIZATION_ID)
+            .await
+            .unwrap();
+        rounding.cash_rounding = Money::from_major(0.05);
+        rounding.cash_rounding_direction = RoundingDirection::Nearest;
+        organization_settings::save(&mut conn, DEFAULT_ORGANIZATION_ID, &rounding)
+            .await
+            .unwrap();
+        drop(conn);
+        let request = |price: f64, payment_method: &str, cash_amount: Option<f64>| {
+            let mut request = sale_request(&[(widget, 1, price)]);
+            request.payment_method = payment_method.to_string();
+            request.cash_amount = cash_amount.map(Money::from_major);
+            request
+        };
+
+        // 10.02 rounds down and 10.03 up; the totals stay exact
+        let down = create_sale_internal(&pool, request(10.02, "Cash", None), cashier, Some(shift_id))
+            .await
+            .unwrap();
+        assert_eq!(down.total_amount, Money::from_minor(1002));
+        assert_eq!(down.cash_rounding, Money::from_minor(-2));
+        let up = create_sale_internal(&pool, request(10.03, "Cash", None), cashier, Some(shift_id))
+            .await
+            .unwrap();
+        assert_eq!(up.cash_rounding, Money::from_minor(2));
+
+        // Split tender: only the 5.03 paid in cash is rounded, the card pays the rest exactly
+        let split = create_sale_internal(&pool, request(20.07, "Card", Some(5.03)), cashier, Some(shift_id))
+            .await
+            .unwrap();
+        assert_eq!(split.cash_rounding, Money::from_minor(2));
+        let tenders: Vec<(String, Money)> = sqlx::query_as(
+            "SELECT payment_method, base_amount FROM sale_payments WHERE sale_id = ?1 ORDER BY id",
+        )
+        .bind(split.id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            tenders,
+            vec![
+                ("Cash".to_string(), Money::from_minor(505)),
+                ("Card".to_string(), Money::from_minor(1504))
+            ]
+        );
+        let card = create_sale_internal(&pool, request(10.02, "Card", None), cashier, Some(shift_id))
+            .await
+            .unwrap();
+        assert_eq!(card.cash_rounding, Money::ZERO);
+        let err = create_sale_internal(&pool, request(10.0, "Card", Some(12.0)), cashier, Some(shift_id))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+
+        // The drawer expects 100 + 20.05 in cash sales and 0.02 net rounding
+        let balance = cash_drawer_balance(&pool, shift_id).await.unwrap();
+        assert_eq!(balance, Money::from_minor(12007));
+        let report = get_tax_report_by_rule_internal(&pool, DEFAULT_ORGANIZATION_ID, "2000-01-01", "2999-12-31")
+            .await
+            .unwrap();
+        assert_eq!(report.total_sales, Money::from_minor(5014));
+        assert_eq!(report.cash_rounding, Money::from_minor(2));
+
+        // A zero step takes cash to the cent again
+        let mut conn = pool.acquire().await.unwrap();
+        rounding.cash_rounding = Money::ZERO;
+        organization_settings::save(&mut conn, DEFAULT_ORGANIZATION_ID, &rounding)
+            .await
+            .unwrap();
+        drop(conn);
+        let exact = create_sale_internal(&pool, request(10.02, "Cash", None), cashier, Some(shift_id))
+            .await
+            .unwrap();
+        assert_eq!(exact.cash_rounding, Money::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_missing_sale_is_not_found() {
+        let pool = test_pool().await;
+
+        let err = get_sale_details_internal(&pool, 999).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_sales_page_counts_rows_matching_its_filters() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+        for method in ["cash", "card", "cash", "cash"] {
+            let request = CreateSaleRequest {
+                payment_method: method.to_string(),
+                ..sale_request(&[(widget, 1, 10.0)])
+            };
+            create_sale_internal(&pool, request, cashier, None).await.unwrap();
+        }
+
+        let cash = SalesFilter::new(DEFAULT_ORGANIZATION_ID, None, None, Some("cash"));
+        let first = Pagination::Offset { limit: 2, offset: 0 };
+        let first = get_sales_with_details_internal(&pool, &cash, &first, true).await.unwrap();
+        assert_eq!(first.items.len(), 2);
+        assert_eq!(first.total_count, 3);
+        assert!(first.has_more);
+
+        // The cursor picks up where the first page stopped, as offset 2 would
+        let cursor = first.next_cursor.clone().unwrap();
+        let after = Pagination::Keyset { limit: 2, after: cursor };
+        let last = get_sales_with_details_internal(&pool, &cash, &after, true).await.unwrap();
+        assert_eq!(last.items.len(), 1);
+        assert!(!last.has_more);
+        let offset = Pagination::Offset { limit: 2, offset: 2 };
+        let by_offset = get_sales_with_details_internal(&pool, &cash, &offset, false).await.unwrap();
+        assert_eq!(by_offset.items[0].id, last.items[0].id);
+        assert!(first.items.iter().all(|sale| sale.id != last.items[0].id));
+
+        let since = SalesFilter::new(DEFAULT_ORGANIZATION_ID, Some("2000-01-01"), None, Some("all"));
+        let pagination = Pagination::Offset { limit: 10, offset: 0 };
+        let all = get_sales_with_details_internal(&pool, &since, &pagination, true)
+            .await
+            .unwrap();
+        assert_eq!((all.items.len(), all.total_count, all.has_more), (4, 4, false));
+    }
+
+    #[tokio::test]
+    async fn test_sale_details_report_returns_and_sales_without_lines() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+        sqlx::query("UPDATE products SET cost_price = 4 WHERE id = ?1")
+            .bind(widget)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let mut sale_ids = Vec::new();
+        for quantity in [3, 2, 1] {
+            let sale = create_sale_internal(&pool, sale_request(&[(widget, quantity, 10.0)]), cashier, None)
+                .await
+                .unwrap();
+            sale_ids.push(sale.id);
+        }
+        let (returned, emptied, voided) = (sale_ids[0], sale_ids[1], sale_ids[2]);
+
+        // A completed and a rejected return against the first sale, plus a legacy one
+        for (number, total, status) in [("SR-1", 10.0, "Completed"), ("SR-2", 20.0, "Rejected")] {
+            sqlx::query(
+                "INSERT INTO comprehensive_returns (return_number, return_type, reference_id, subtotal,
+                                                   total_amount, status, processed_by)
+                 VALUES (?1, 'SalesReturn', ?2, ?3, ?3, ?4, ?5)",
+            )
+            .bind(number)
+            .bind(returned)
+            .bind(total)
+            .bind(status)
+            .bind(cashier)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO returns (return_number, original_sale_id, subtotal, total_amount, refund_method, processed_by)
+             VALUES ('R-1', ?1, 5, 5, 'cash', ?2)",
+        )
+        .bind(returned)
+        .bind(cashier)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("DELETE FROM sale_items WHERE sale_id = ?1")
+            .bind(emptied)
+            .execute(&pool)
+            .await
+            .unwrap();
+        void_sale_internal(&pool, voided, "Mistake", cashier, DEFAULT_ORGANIZATION_ID).await.unwrap();
+
+        let filter = SalesFilter::new(DEFAULT_ORGANIZATION_ID, None, None, None);
+        let pagination = Pagination::Offset { limit: 10, offset: 0 };
+        let sales = fetch_sales_with_details(&pool, &filter, &pagination).await.unwrap();
+        assert_eq!(sales.len(), 3);
+        let find = |id: i64| sales.iter().find(|sale| sale.id == id).unwrap();
+
+        let sale = find(returned);
+        assert_eq!((sale.items_count, sale.profit), (1, Money::from_minor(1800)));
+        assert!(sale.has_returns);
+        assert_eq!(sale.returned_amount, Money::from_minor(1500));
+
+        let sale = find(emptied);
+        assert_eq!((sale.items_count, sale.profit), (0, Money::ZERO));
+        assert_eq!(sale.total_amount, Money::from_minor(2000));
+        assert!(!sale.has_returns);
+        assert_eq!(sale.returned_amount, Money::ZERO);
+
+        let sale = find(voided);
+        assert!(sale.is_voided);
+        assert_eq!((sale.items_count, sale.profit), (1, Money::ZERO));
+    }
+
+    fn bench_database() -> PathBuf {
+        std::env::temp_dir().join(format!("qorbooks-bench-{}.db", uuid::Uuid::new_v4()))
+    }
+
+    /// Ring up `count` one-item sales at once on a fresh database opened with `options`,
+    /// the way the app's pool would, returning how long they took and what failed
+    async fn sell_concurrently(options: SqliteConnectOptions, count: usize) -> (Duration, Vec<AppError>) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(options)
+            .await
+            .unwrap();
+        apply_migrations(&pool).await.unwrap();
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, count as i32).await;
+
+        let started = Instant::now();
+        let tasks: Vec<_> = (0..count)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    create_sale_internal(&pool, sale_request(&[(widget, 1, 10.0)]), cashier, None).await
+                })
+            })
+            .collect();
+        let mut errors = Vec::new();
+        for task in tasks {
+            if let Err(e) = task.await.unwrap() {
+                errors.push(e);
+            }
+        }
+        let elapsed = started.elapsed();
+        pool.close().await;
+        (elapsed, errors)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_sales_do_not_hit_lock_errors() {
+        const SALES: usize = 500;
+        let before_path = bench_database();
+        let after_path = bench_database();
+
+        // sqlx defaults: rollback journal, FULL sync
+        let before_options = SqliteConnectOptions::new().filename(&before_path).create_if_missing(true);
+        let (before, before_errors) = sell_concurrently(before_options, SALES).await;
+        let after_options = connect_options(&format!("sqlite://{}", after_path.display())).unwrap();
+        let (after, after_errors) = sell_concurrently(after_options, SALES).await;
+
+        let per_second = |elapsed: Duration| SALES as f64 / elapsed.as_secs_f64();
+        println!(
+            "{} concurrent sales: default settings {:.0}/s ({} failed), WAL {:.0}/s ({} failed)",
+            SALES,
+            per_second(before),
+            before_errors.len(),
+            per_second(after),
+            after_errors.len()
+        );
+        for path in [&before_path, &after_path] {
+            for suffix in ["", "-wal", "-shm", "-journal"] {
+                let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+            }
+        }
+        assert!(after_errors.is_empty(), "first failure: {:?}", after_errors.first());
+    }
+
+    #[tokio::test]
+    async fn test_keyset_pages_return_every_sale_once() {
+        const SALES: i64 = 10_000;
+        const PAGE: i64 = 500;
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        // Seven sales share each timestamp, so the id has to break ties between pages
+        sqlx::query(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?1)
+             INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id, created_at)
+             SELECT 'BULK-' || i, 1.0, 1.0, 'cash', ?2, datetime('2026-01-01', '+' || (i / 7) || ' minutes')
+             FROM n",
+        )
+        .bind(SALES)
+        .bind(cashier)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let filter = SalesFilter::new(DEFAULT_ORGANIZATION_ID, None, None, None);
+        let mut pagination = Pagination::from_params(PAGE, None, None, None).unwrap();
+        let mut seen = std::collections::HashSet::new();
+        let mut last: Option<(String, i64)> = None;
+        let mut pages = 0;
+        loop {
+            let page = get_sales_internal(&pool, &filter, &pagination, pages == 0).await.unwrap();
+            if pages == 0 {
+                assert_eq!(page.total_count, SALES);
+            }
+            pages += 1;
+            for sale in &page.items {
+                assert!(seen.insert(sale.id), "sale {} returned twice", sale.id);
+                let position = (sale.created_at.clone(), sale.id);
+                assert!(last.as_ref().is_none_or(|last| position < *last), "out of order at {:?}", position);
+                last = Some(position);
+            }
+            match page.next_cursor {
+                Some(after) => pagination = Pagination::Keyset { limit: PAGE, after },
+                None => {
+                    assert!(!page.has_more);
+                    break;
+                }
+            }
+        }
+        assert_eq!(seen.len() as i64, SALES);
+        // One query per page, and no extra empty page at the end
+        assert_eq!(pages, SALES / PAGE);
+
+        // Later pages seek through the index rather than sorting the table
+        let mut plan_query = QueryBuilder::<Sqlite>::new("EXPLAIN QUERY PLAN ");
+        let deep = Pagination::Keyset {
+            limit: PAGE,
+            after: PageCursor { created_at: "2026-01-01 12:00:00".to_string(), id: 5000 },
+        };
+        push_sales_page(&mut plan_query, &filter, &deep);
+        let plan: Vec<String> = plan_query
+            .build()
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get("detail"))
+            .collect();
+        assert!(plan.iter().any(|step| step.contains("USING INDEX")), "{:?}", plan);
+        assert!(!plan.iter().any(|step| step.contains("TEMP B-TREE")), "{:?}", plan);
+    }
+
+    #[tokio::test]
+    async fn test_buy_x_get_y_discounts_are_recorded_per_line() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+        let gadget = insert_test_product(&pool, "GADGET", 4.0, 20).await;
+        sqlx::query(
+            "INSERT INTO promotions (code, name, discount_type, discount_value, start_date,
+                                     applicable_products, buy_quantity, get_quantity)
+             VALUES ('B2G1', 'Buy 2 get 1 free', 'Buy X Get Y', 0, '2000-01-01', ?1, 2, 1)",
+        )
+        .bind(widget.to_string())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // 7 widgets: 2 free. The gadget is not part of the promotion.
+        let promoted_sale = || {
+            let mut request = sale_request(&[(widget, 7, 10.0), (gadget, 1, 4.0)]);
+            request.promotion_codes = vec!["B2G1".to_string()];
+            request
+        };
+        let err = create_sale_internal(&pool, promoted_sale(), cashier, None).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+
+        let mut request = promoted_sale();
+        request.items[0].discount_amount = Money::from_major(20.0);
+        request.items[0].line_total = Money::from_major(50.0);
+        request.subtotal = Money::from_major(54.0);
+        request.total_amount = Money::from_major(54.0);
+        let sale = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+
+        let recorded: Vec<(i64, String, Money)> = sqlx::query_as(
+            "SELECT si.product_id, sip.code, sip.discount_amount
+             FROM sale_item_promotions sip JOIN sale_items si ON si.id = sip.sale_item_id
+             WHERE si.sale_id = ?1",
+        )
+        .bind(sale.id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(recorded, vec![(widget, "B2G1".to_string(), Money::from_major(20.0))]);
+
+        let (usage_count, used_on): (i32, i64) = sqlx::query_as(
+            "SELECT p.usage_count, pu.sale_id FROM promotions p
+             JOIN promotion_usage pu ON pu.promotion_id = p.id WHERE p.code = 'B2G1'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((usage_count, used_on), (1, sale.id));
+    }
+}
@@ -0,0 +1,446 @@
+//! Header search box: one query looked up in products, sales, customers and purchase orders,
+//! each with its own indexed lookup and limit, merged into a single ranked list.
+
+use crate::error::AppResult;
+use crate::money::Money;
+use crate::tenancy;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tauri::{command, State};
+
+/// Shorter queries would match most of the database
+const MIN_QUERY_CHARS: usize = 2;
+const DEFAULT_LIMIT_PER_TYPE: i64 = 5;
+const MAX_LIMIT_PER_TYPE: i64 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchEntity {
+    Product,
+    Sale,
+    Customer,
+    PurchaseOrder,
+}
+
+/// How a hit matched the query, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchQuality {
+    /// The query is the hit's number, SKU, barcode, email or phone
+    Exact,
+    /// A number or name starts with the query
+    Prefix,
+    /// The query appears elsewhere, e.g. inside a sale number or a product's words
+    Partial,
+}
+
+impl MatchQuality {
+    fn from_rank(rank: i64) -> Self {
+        match rank {
+            0 => MatchQuality::Exact,
+            1 => MatchQuality::Prefix,
+            _ => MatchQuality::Partial,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub entity: SearchEntity,
+    pub id: i64,
+    /// Product name, sale or PO number, customer name
+    pub title: String,
+    /// SKU, customer of a sale, customer number, supplier of a PO
+    pub subtitle: Option<String>,
+    /// Selling price, sale or PO total, customer's total spent
+    pub amount: Option<Money>,
+    pub status: Option<String>,
+    pub match_quality: MatchQuality,
+}
+
+/// `LIKE` pattern matching `term` literally
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// FTS5 query matching every word of `term` as a prefix, or None when it has no words
+fn fts_query(term: &str) -> Option<String> {
+    let words: Vec<String> = term
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+async fn search_products(
+    pool: &SqlitePool,
+    term: &str,
+    prefix: &str,
+    organization_id: i64,
+    limit: i64,
+) -> AppResult<Vec<SearchHit>> {
+    let Some(fts) = fts_query(term) else {
+        return Ok(Vec::new());
+    };
+    let rows = sqlx::query(
+        "SELECT p.id, p.name, p.sku, p.selling_price,
+                CASE WHEN p.sku = ?1 COLLATE NOCASE OR p.barcode = ?1 THEN 0
+                     WHEN p.name LIKE ?2 ESCAPE '\\' OR p.sku LIKE ?2 ESCAPE '\\' THEN 1
+                     ELSE 2 END AS match_rank
+         FROM products_fts
+         JOIN products p ON p.id = products_fts.rowid
+         WHERE products_fts MATCH ?3 AND p.organization_id = ?4
+           AND p.is_active = 1 AND p.archived_at IS NULL
+         ORDER BY match_rank, products_fts.rank
+         LIMIT ?5",
+    )
+    .bind(term)
+    .bind(prefix)
+    .bind(fts)
+    .bind(organization_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(SearchHit {
+                entity: SearchEntity::Product,
+                id: row.try_get("id")?,
+                title: row.try_get("name")?,
+                subtitle: Some(row.try_get("sku")?),
+                amount: Some(row.try_get("selling_price")?),
+                status: None,
+                match_quality: MatchQuality::from_rank(row.try_get("match_rank")?),
+            })
+        })
+        .collect()
+}
+
+async fn search_sales(
+    pool: &SqlitePool,
+    term: &str,
+    prefix: &str,
+    contains: &str,
+    organization_id: i64,
+    limit: i64,
+) -> AppResult<Vec<SearchHit>> {
+    // Cashiers often type the tail of a sale number, so the number is searched anywhere
+    let rows = sqlx::query(
+        "SELECT id, sale_number, customer_name, total_amount, is_voided,
+                CASE WHEN sale_number = ?1 COLLATE NOCASE THEN 0
+                     WHEN sale_number LIKE ?2 ESCAPE '\\' THEN 1
+                     ELSE 2 END AS match_rank
+         FROM sales
+         WHERE sale_number LIKE ?3 ESCAPE '\\' AND organization_id = ?4
+         ORDER BY match_rank, created_at DESC, id DESC
+         LIMIT ?5",
+    )
+    .bind(term)
+    .bind(prefix)
+    .bind(contains)
+    .bind(organization_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter()
+        .map(|row| {
+            let voided: Option<bool> = row.try_get("is_voided")?;
+            Ok(SearchHit {
+                entity: SearchEntity::Sale,
+                id: row.try_get("id")?,
+                title: row.try_get("sale_number")?,
+                subtitle: row.try_get("customer_name")?,
+                amount: Some(row.try_get("total_amount")?),
+                status: Some(
+                    if voided.unwrap_or(false) {
+                        "voided"
+                    } else {
+                        "completed"
+                    }
+                    .to_string(),
+                ),
+                match_quality: MatchQuality::from_rank(row.try_get("match_rank")?),
+            })
+        })
+        .collect()
+}
+
+async fn search_customers(
+    pool: &SqlitePool,
+    term: &str,
+    prefix: &str,
+    organization_id: i64,
+    limit: i64,
+) -> AppResult<Vec<SearchHit>> {
+    let rows = sqlx::query(
+        "SELECT id, customer_number, first_name, last_name, company, total_spent, status,
+                CASE WHEN customer_number = ?1 COLLATE NOCASE OR email = ?1 COLLATE NOCASE OR phone = ?1
+                     THEN 0 ELSE 1 END AS match_rank
+         FROM customers
+         WHERE (first_name LIKE ?2 ESCAPE '\\' OR last_name LIKE ?2 ESCAPE '\\'
+                OR first_name || ' ' || last_name LIKE ?2 ESCAPE '\\' OR company LIKE ?2 ESCAPE '\\'
+                OR customer_number LIKE ?2 ESCAPE '\\' OR email LIKE ?2 ESCAPE '\\' OR phone LIKE ?2 ESCAPE '\\')
+           AND organization_id = ?3 AND archived_at IS NULL
+         ORDER BY match_rank, last_name, first_name
+         LIMIT ?4",
+    )
+    .bind(term)
+    .bind(prefix)
+    .bind(organization_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter()
+        .map(|row| {
+            let first_name: String = row.try_get("first_name")?;
+            let last_name: String = row.try_get("last_name")?;
+            let company: Option<String> = row.try_get("company")?;
+            let customer_number: String = row.try_get("customer_number")?;
+            Ok(SearchHit {
+                entity: SearchEntity::Customer,
+                id: row.try_get("id")?,
+                title: format!("{} {}", first_name, last_name),
+                subtitle: Some(match company.filter(|company| !company.is_empty()) {
+                    Some(company) => format!("{} · {}", customer_number, company),
+                    None => customer_number,
+                }),
+                amount: row.try_get("total_spent")?,
+                status: row.try_get("status")?,
+                match_quality: MatchQuality::from_rank(row.try_get("match_rank")?),
+            })
+        })
+        .collect()
+}
+
+async fn search_purchase_orders(
+    pool: &SqlitePool,
+    term: &str,
+    prefix: &str,
+    contains: &str,
+    organization_id: i64,
+    limit: i64,
+) -> AppResult<Vec<SearchHit>> {
+    let rows = sqlx::query(
+        "SELECT po.id, po.po_number, po.total_amount, po.status, s.company_name,
+                CASE WHEN po.po_number = ?1 COLLATE NOCASE THEN 0
+                     WHEN po.po_number LIKE ?2 ESCAPE '\\' OR s.company_name LIKE ?2 ESCAPE '\\' THEN 1
+                     ELSE 2 END AS match_rank
+         FROM purchase_orders po
+         LEFT JOIN suppliers s ON s.id = po.supplier_id
+         WHERE (po.po_number LIKE ?3 ESCAPE '\\' OR s.company_name LIKE ?2 ESCAPE '\\')
+           AND po.organization_id = ?5
+         ORDER BY match_rank, po.order_date DESC, po.id DESC
+         LIMIT ?4",
+    )
+    .bind(term)
+    .bind(prefix)
+    .bind(contains)
+    .bind(limit)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(SearchHit {
+                entity: SearchEntity::PurchaseOrder,
+                id: row.try_get("id")?,
+                title: row.try_get("po_number")?,
+                subtitle: row.try_get("company_name")?,
+                amount: row.try_get("total_amount")?,
+                status: row.try_get("status")?,
+                match_quality: MatchQuality::from_rank(row.try_get("match_rank")?),
+            })
+        })
+        .collect()
+}
+
+/// Look `query` up in every searchable entity, at most `limit_per_type` hits each. Exact
+/// number matches come first, then prefix matches, then the rest; within a quality the
+/// hits keep each entity's own order.
+pub async fn global_search_internal(
+    pool: &SqlitePool,
+    query: &str,
+    limit_per_type: Option<i64>,
+    organization_id: i64,
+) -> AppResult<Vec<SearchHit>> {
+    let term = query.trim();
+    if term.chars().count() < MIN_QUERY_CHARS {
+        return Ok(Vec::new());
+    }
+    let limit = limit_per_type
+        .unwrap_or(DEFAULT_LIMIT_PER_TYPE)
+        .clamp(1, MAX_LIMIT_PER_TYPE);
+    let prefix = format!("{}%", escape_like(term));
+    let contains = format!("%{}%", escape_like(term));
+
+    let mut hits = search_sales(pool, term, &prefix, &contains, organization_id, limit).await?;
+    hits.extend(search_products(pool, term, &prefix, organization_id, limit).await?);
+    hits.extend(search_customers(pool, term, &prefix, organization_id, limit).await?);
+    hits.extend(
+        search_purchase_orders(pool, term, &prefix, &contains, organization_id, limit).await?,
+    );
+    hits.sort_by_key(|hit| hit.match_quality);
+    Ok(hits)
+}
+
+#[command]
+pub async fn global_search(
+    pool: State<'_, SqlitePool>,
+    query: String,
+    limit_per_type: Option<i64>,
+    session_token: String,
+) -> AppResult<Vec<SearchHit>> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    global_search_internal(pool.inner(), &query, limit_per_type, organization_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_product, test_pool};
+
+    async fn insert_sale(pool: &SqlitePool, sale_number: &str) -> i64 {
+        sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id, customer_name)
+             VALUES (?1, 10, 10, 'cash', 1, 'Walk-in')",
+        )
+        .bind(sale_number)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+    }
+
+    fn find(hits: &[SearchHit], entity: SearchEntity, id: i64) -> Option<&SearchHit> {
+        hits.iter().find(|hit| hit.entity == entity && hit.id == id)
+    }
+
+    #[tokio::test]
+    async fn test_every_entity_type_is_found() {
+        let pool = test_pool().await;
+        let product = insert_test_product(&pool, "BRK-RED-01", 0.85, 100).await;
+        sqlx::query("UPDATE products SET name = 'Red Clay Brick' WHERE id = ?1")
+            .bind(product)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let sale = insert_sale(&pool, "SALE-2026-009f3").await;
+        let customer = sqlx::query(
+            "INSERT INTO customers (customer_number, first_name, last_name, email)
+             VALUES ('CUST-0042', 'Alex', 'Johnson', 'alex@example.com')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        let supplier = sqlx::query(
+            "INSERT INTO suppliers (supplier_number, company_name) VALUES ('SUP-1', 'Brickworks Ltd')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        let order = sqlx::query(
+            "INSERT INTO purchase_orders (po_number, supplier_id, order_date) VALUES ('PO-2026-0007', ?1, '2026-01-05')",
+        )
+        .bind(supplier)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+
+        let hits = global_search_internal(&pool, "brick", None, 1)
+            .await
+            .unwrap();
+        let hit = find(&hits, SearchEntity::Product, product).expect("product by name");
+        assert_eq!(hit.subtitle.as_deref(), Some("BRK-RED-01"));
+        // The supplier's name finds its purchase orders
+        assert!(find(&hits, SearchEntity::PurchaseOrder, order).is_some());
+
+        let hits = global_search_internal(&pool, "9f3", None, 1).await.unwrap();
+        assert!(find(&hits, SearchEntity::Sale, sale).is_some());
+
+        let hits = global_search_internal(&pool, "Johnson", None, 1)
+            .await
+            .unwrap();
+        let hit = find(&hits, SearchEntity::Customer, customer).expect("customer by last name");
+        assert_eq!(hit.title, "Alex Johnson");
+
+        let hits = global_search_internal(&pool, "PO-2026", None, 1)
+            .await
+            .unwrap();
+        assert!(find(&hits, SearchEntity::PurchaseOrder, order).is_some());
+
+        // Renaming a product updates the search index
+        sqlx::query("UPDATE products SET name = 'Cement Bag' WHERE id = ?1")
+            .bind(product)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let hits = global_search_internal(&pool, "clay", None, 1)
+            .await
+            .unwrap();
+        assert!(find(&hits, SearchEntity::Product, product).is_none());
+
+        // Other organizations' rows are not found
+        let hits = global_search_internal(&pool, "Johnson", None, 2)
+            .await
+            .unwrap();
+        assert!(hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_exact_number_matches_rank_first() {
+        let pool = test_pool().await;
+        insert_sale(&pool, "SALE-1000-2").await;
+        insert_sale(&pool, "SALE-1000-20").await;
+        let exact = insert_sale(&pool, "SALE-1000").await;
+        insert_test_product(&pool, "SALE-1000-KIT", 5.0, 1).await;
+
+        let hits = global_search_internal(&pool, "sale-1000", None, 1)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 4);
+        assert_eq!((hits[0].entity, hits[0].id), (SearchEntity::Sale, exact));
+        assert_eq!(hits[0].match_quality, MatchQuality::Exact);
+        assert!(hits[1..]
+            .iter()
+            .all(|hit| hit.match_quality == MatchQuality::Prefix));
+
+        let hits = global_search_internal(&pool, "sale", Some(2), 1)
+            .await
+            .unwrap();
+        assert_eq!(
+            hits.iter()
+                .filter(|hit| hit.entity == SearchEntity::Sale)
+                .count(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_short_queries_return_nothing() {
+        let pool = test_pool().await;
+        insert_sale(&pool, "S-1").await;
+        assert!(global_search_internal(&pool, "", None, 1)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(global_search_internal(&pool, " S ", None, 1)
+            .await
+            .unwrap()
+            .is_empty());
+        // LIKE wildcards are matched literally
+        assert!(global_search_internal(&pool, "%%", None, 1)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
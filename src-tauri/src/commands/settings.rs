@@ -0,0 +1,149 @@
+// src-tauri/src/commands/settings.rs - App settings by organization, location or user, and demo mode
+use crate::demo_mode::{self, DemoModeStatus};
+use crate::error::{AppError, AppResult};
+use crate::seeder::TableRowCount;
+use crate::session::{Session, SESSION_MANAGER};
+use crate::settings::{self, SettingScope};
+use crate::tenancy;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use tauri::{command, State};
+
+/// The scope a settings command works on, always one the session belongs to: its
+/// organization, one of that organization's locations (by default the one it works at)
+/// or its own user. An id naming any other is NOT_FOUND.
+async fn resolve_scope(
+    pool: &SqlitePool,
+    session: &Session,
+    scope: Option<&str>,
+    scope_id: Option<i64>,
+) -> AppResult<SettingScope> {
+    let scope = scope.unwrap_or("organization");
+    let (own_id, entity) = match scope {
+        "organization" => (session.organization_id, "Organization"),
+        "location" => (scope_id.unwrap_or(session.location_id), "Location"),
+        "user" => (session.user_id, "User"),
+        other => return SettingScope::parse(other, 0),
+    };
+    if scope_id.is_some_and(|id| id != own_id) {
+        return Err(AppError::not_found(entity));
+    }
+    if scope == "location" {
+        let mut conn = pool.acquire().await?;
+        tenancy::require_in_organization(
+            &mut conn,
+            "locations",
+            own_id,
+            session.organization_id,
+            entity,
+        )
+        .await?;
+    }
+    SettingScope::parse(scope, own_id)
+}
+
+#[command]
+pub async fn get_settings(
+    pool: State<'_, SqlitePool>,
+    scope: Option<String>,
+    scope_id: Option<i64>,
+    session_token: String,
+) -> Result<HashMap<String, Value>, AppError> {
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    let scope = resolve_scope(pool.inner(), &session, scope.as_deref(), scope_id).await?;
+    settings::settings_for_scope(pool.inner(), scope).await
+}
+
+/// Store several settings of one scope. Every key is checked before any is written. Users
+/// signed in with a password change their own settings; organization and location
+/// settings need an admin. Demo mode only changes through `set_demo_mode`.
+#[command]
+pub async fn update_settings(
+    pool: State<'_, SqlitePool>,
+    values: HashMap<String, Value>,
+    scope: Option<String>,
+    scope_id: Option<i64>,
+    session_token: String,
+) -> Result<HashMap<String, Value>, AppError> {
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    if session.pin_session {
+        return Err(AppError::password_login_required());
+    }
+    let scope = resolve_scope(pool.inner(), &session, scope.as_deref(), scope_id).await?;
+    if !matches!(scope, SettingScope::User(_)) {
+        SESSION_MANAGER.require_full_session(&session_token, &["Admin"])?;
+    }
+    for (key, value) in &values {
+        if key == "demo_mode" {
+            return Err(AppError::validation(key, "Demo mode is changed with set_demo_mode"));
+        }
+        settings::validate_setting(key, value)?;
+    }
+    for (key, value) in &values {
+        settings::set_setting(pool.inner(), key, scope, value).await?;
+    }
+    settings::settings_for_scope(pool.inner(), scope).await
+}
+
+#[command]
+pub async fn get_demo_mode(pool: State<'_, SqlitePool>) -> Result<DemoModeStatus, AppError> {
+    demo_mode::demo_mode_status(pool.inner()).await
+}
+
+/// Turn demo mode on or off (admins only). The reply warns when demo records are left.
+#[command]
+pub async fn set_demo_mode(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    enabled: bool,
+) -> Result<DemoModeStatus, AppError> {
+    SESSION_MANAGER.require_full_session(&session_token, &["Admin"])?;
+    demo_mode::set_demo_mode(pool.inner(), enabled).await
+}
+
+/// Remove every sale, return and expense recorded in demo mode (admins only)
+#[command]
+pub async fn purge_demo_data(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<Vec<TableRowCount>, AppError> {
+    SESSION_MANAGER.require_full_session(&session_token, &["Admin"])?;
+    demo_mode::purge_demo_data(pool.inner()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_user, test_pool};
+
+    #[tokio::test]
+    async fn test_scopes_stay_within_the_session() {
+        let pool = test_pool().await;
+        let user = insert_test_user(&pool, "clerk").await;
+        let token = SESSION_MANAGER.create_session(user, "clerk".to_string(), "Cashier".to_string());
+        let session = SESSION_MANAGER.validate_session(&token).unwrap();
+        let other_org: i64 = sqlx::query_scalar(
+            "INSERT INTO organizations (name, slug) VALUES ('Other', 'other') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let other_branch = sqlx::query("INSERT INTO locations (name, organization_id) VALUES ('Away', ?1)")
+            .bind(other_org)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+
+        let own = resolve_scope(&pool, &session, Some("user"), None).await.unwrap();
+        assert_eq!(own, SettingScope::User(user));
+        let store = resolve_scope(&pool, &session, Some("location"), None).await.unwrap();
+        assert_eq!(store, SettingScope::Location(session.location_id));
+
+        for (scope, id) in [("organization", other_org), ("user", user + 1), ("location", other_branch)] {
+            let err = resolve_scope(&pool, &session, Some(scope), Some(id)).await.unwrap_err();
+            assert_eq!(err.code(), "NOT_FOUND");
+        }
+    }
+}
@@ -1,13 +1,46 @@
+use crate::location_stock;
+use crate::margins::DEFAULT_LOCATION_ID;
+use crate::money::Money;
 use crate::models::{CloseShiftRequest, CreateShiftRequest, Shift};
+use crate::plans;
+use sqlx::sqlite::SqliteRow;
 use sqlx::{Row, SqlitePool};
 use tauri::{command, State};
 
+const SHIFT_COLUMNS: &str = "id, user_id, location_id, start_time, end_time, opening_amount, closing_amount,
+    total_sales, total_returns, cash_sales, card_sales, cash_rounding, status, notes, created_at,
+    (SELECT COUNT(*) FROM cash_drawer_transactions c
+     WHERE c.shift_id = shifts.id AND c.transaction_type = 'no_sale') as no_sale_count";
+
+fn shift_from_row(row: &SqliteRow) -> Result<Shift, String> {
+    Ok(Shift {
+        id: row.try_get("id").map_err(|e| e.to_string())?,
+        user_id: row.try_get("user_id").map_err(|e| e.to_string())?,
+        location_id: row.try_get("location_id").map_err(|e| e.to_string())?,
+        start_time: row.try_get("start_time").map_err(|e| e.to_string())?,
+        end_time: row.try_get("end_time").ok().flatten(),
+        opening_amount: row.try_get("opening_amount").map_err(|e| e.to_string())?,
+        closing_amount: row.try_get("closing_amount").ok().flatten(),
+        total_sales: row.try_get("total_sales").map_err(|e| e.to_string())?,
+        total_returns: row.try_get("total_returns").map_err(|e| e.to_string())?,
+        cash_sales: row.try_get("cash_sales").map_err(|e| e.to_string())?,
+        card_sales: row.try_get("card_sales").map_err(|e| e.to_string())?,
+        cash_rounding: row.try_get("cash_rounding").map_err(|e| e.to_string())?,
+        status: row.try_get("status").map_err(|e| e.to_string())?,
+        notes: row.try_get("notes").ok().flatten(),
+        no_sale_count: row.try_get("no_sale_count").map_err(|e| e.to_string())?,
+        created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
+    })
+}
+
 #[command]
 pub async fn create_shift(
     pool: State<'_, SqlitePool>,
     user_id: i64,
     request: CreateShiftRequest,
+    session_token: String,
 ) -> Result<Shift, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     // Check if user already has an open shift
@@ -22,12 +55,18 @@ pub async fn create_shift(
         return Err("User already has an open shift".to_string());
     }
 
+    let location_id = request.location_id.unwrap_or(DEFAULT_LOCATION_ID);
+    let mut conn = pool_ref.acquire().await.map_err(|e| e.to_string())?;
+    location_stock::require_active_location(&mut conn, location_id).await?;
+    drop(conn);
+
     // Create new shift
     let result = sqlx::query(
-        "INSERT INTO shifts (user_id, start_time, opening_amount, status) 
-         VALUES (?1, CURRENT_TIMESTAMP, ?2, 'open')",
+        "INSERT INTO shifts (user_id, location_id, start_time, opening_amount, status) 
+         VALUES (?1, ?2, CURRENT_TIMESTAMP, ?3, 'open')",
     )
     .bind(user_id)
+    .bind(location_id)
     .bind(request.opening_amount)
     .execute(pool_ref)
     .await
@@ -36,33 +75,14 @@ pub async fn create_shift(
     let shift_id = result.last_insert_rowid();
 
     // Get the created shift
-    let row = sqlx::query(
-        "SELECT id, user_id, start_time, end_time, opening_amount, closing_amount, 
-                total_sales, total_returns, cash_sales, card_sales, status, notes, created_at
-         FROM shifts WHERE id = ?1",
-    )
-    .bind(shift_id)
-    .fetch_one(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to fetch created shift: {}", e))?;
-
-    let shift = Shift {
-        id: row.try_get("id").map_err(|e| e.to_string())?,
-        user_id: row.try_get("user_id").map_err(|e| e.to_string())?,
-        start_time: row.try_get("start_time").map_err(|e| e.to_string())?,
-        end_time: row.try_get("end_time").ok().flatten(),
-        opening_amount: row.try_get("opening_amount").map_err(|e| e.to_string())?,
-        closing_amount: row.try_get("closing_amount").ok().flatten(),
-        total_sales: row.try_get("total_sales").map_err(|e| e.to_string())?,
-        total_returns: row.try_get("total_returns").map_err(|e| e.to_string())?,
-        cash_sales: row.try_get("cash_sales").map_err(|e| e.to_string())?,
-        card_sales: row.try_get("card_sales").map_err(|e| e.to_string())?,
-        status: row.try_get("status").map_err(|e| e.to_string())?,
-        notes: row.try_get("notes").ok().flatten(),
-        created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-    };
+    let query = format!("SELECT {} FROM shifts WHERE id = ?1", SHIFT_COLUMNS);
+    let row = sqlx::query(&query)
+        .bind(shift_id)
+        .fetch_one(pool_ref)
+        .await
+        .map_err(|e| format!("Failed to fetch created shift: {}", e))?;
 
-    Ok(shift)
+    shift_from_row(&row)
 }
 
 #[command]
@@ -71,7 +91,9 @@ pub async fn close_shift(
     shift_id: i64,
     user_id: i64,
     request: CloseShiftRequest,
+    session_token: String,
 ) -> Result<Shift, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     // Verify shift exists and belongs to user
@@ -97,7 +119,8 @@ pub async fn close_shift(
         "SELECT 
             COALESCE(SUM(total_amount), 0) as total_sales,
             COALESCE(SUM(CASE WHEN payment_method = 'Cash' THEN total_amount ELSE 0 END), 0) as cash_sales,
-            COALESCE(SUM(CASE WHEN payment_method != 'Cash' THEN total_amount ELSE 0 END), 0) as card_sales
+            COALESCE(SUM(CASE WHEN payment_method != 'Cash' THEN total_amount ELSE 0 END), 0) as card_sales,
+            COALESCE(SUM(cash_rounding), 0) as cash_rounding
          FROM sales WHERE shift_id = ?1 AND is_voided = 0"
     )
     .bind(shift_id)
@@ -105,15 +128,18 @@ pub async fn close_shift(
     .await
     .map_err(|e| format!("Failed to calculate sales totals: {}", e))?;
 
-    let total_sales: f64 = sales_totals
+    let total_sales: Money = sales_totals
         .try_get("total_sales")
         .map_err(|e| e.to_string())?;
-    let cash_sales: f64 = sales_totals
+    let cash_sales: Money = sales_totals
         .try_get("cash_sales")
         .map_err(|e| e.to_string())?;
-    let card_sales: f64 = sales_totals
+    let card_sales: Money = sales_totals
         .try_get("card_sales")
         .map_err(|e| e.to_string())?;
+    let cash_rounding: Money = sales_totals
+        .try_get("cash_rounding")
+        .map_err(|e| e.to_string())?;
 
     // Close the shift
     sqlx::query(
@@ -123,14 +149,16 @@ pub async fn close_shift(
             total_sales = ?2,
             cash_sales = ?3,
             card_sales = ?4,
+            cash_rounding = ?5,
             status = 'closed',
-            notes = ?5
-         WHERE id = ?6",
+            notes = ?6
+         WHERE id = ?7",
     )
     .bind(request.closing_amount)
     .bind(total_sales)
     .bind(cash_sales)
     .bind(card_sales)
+    .bind(cash_rounding)
     .bind(&request.notes)
     .bind(shift_id)
     .execute(pool_ref)
@@ -138,33 +166,14 @@ pub async fn close_shift(
     .map_err(|e| format!("Failed to close shift: {}", e))?;
 
     // Get the updated shift
-    let row = sqlx::query(
-        "SELECT id, user_id, start_time, end_time, opening_amount, closing_amount, 
-                total_sales, total_returns, cash_sales, card_sales, status, notes, created_at
-         FROM shifts WHERE id = ?1",
-    )
-    .bind(shift_id)
-    .fetch_one(pool_ref)
-    .await
-    .map_err(|e| format!("Failed to fetch updated shift: {}", e))?;
-
-    let shift = Shift {
-        id: row.try_get("id").map_err(|e| e.to_string())?,
-        user_id: row.try_get("user_id").map_err(|e| e.to_string())?,
-        start_time: row.try_get("start_time").map_err(|e| e.to_string())?,
-        end_time: row.try_get("end_time").ok().flatten(),
-        opening_amount: row.try_get("opening_amount").map_err(|e| e.to_string())?,
-        closing_amount: row.try_get("closing_amount").ok().flatten(),
-        total_sales: row.try_get("total_sales").map_err(|e| e.to_string())?,
-        total_returns: row.try_get("total_returns").map_err(|e| e.to_string())?,
-        cash_sales: row.try_get("cash_sales").map_err(|e| e.to_string())?,
-        card_sales: row.try_get("card_sales").map_err(|e| e.to_string())?,
-        status: row.try_get("status").map_err(|e| e.to_string())?,
-        notes: row.try_get("notes").ok().flatten(),
-        created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-    };
+    let query = format!("SELECT {} FROM shifts WHERE id = ?1", SHIFT_COLUMNS);
+    let row = sqlx::query(&query)
+        .bind(shift_id)
+        .fetch_one(pool_ref)
+        .await
+        .map_err(|e| format!("Failed to fetch updated shift: {}", e))?;
 
-    Ok(shift)
+    shift_from_row(&row)
 }
 
 #[command]
@@ -174,37 +183,17 @@ pub async fn get_current_shift(
 ) -> Result<Option<Shift>, String> {
     let pool_ref = pool.inner();
 
-    let row = sqlx::query(
-        "SELECT id, user_id, start_time, end_time, opening_amount, closing_amount, 
-                total_sales, total_returns, cash_sales, card_sales, status, notes, created_at
-         FROM shifts WHERE user_id = ?1 AND status = 'open'",
-    )
-    .bind(user_id)
-    .fetch_optional(pool_ref)
-    .await
-    .map_err(|e| format!("Database error: {}", e))?;
+    let query = format!(
+        "SELECT {} FROM shifts WHERE user_id = ?1 AND status = 'open'",
+        SHIFT_COLUMNS
+    );
+    let row = sqlx::query(&query)
+        .bind(user_id)
+        .fetch_optional(pool_ref)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
 
-    match row {
-        Some(r) => {
-            let shift = Shift {
-                id: r.try_get("id").map_err(|e| e.to_string())?,
-                user_id: r.try_get("user_id").map_err(|e| e.to_string())?,
-                start_time: r.try_get("start_time").map_err(|e| e.to_string())?,
-                end_time: r.try_get("end_time").ok().flatten(),
-                opening_amount: r.try_get("opening_amount").map_err(|e| e.to_string())?,
-                closing_amount: r.try_get("closing_amount").ok().flatten(),
-                total_sales: r.try_get("total_sales").map_err(|e| e.to_string())?,
-                total_returns: r.try_get("total_returns").map_err(|e| e.to_string())?,
-                cash_sales: r.try_get("cash_sales").map_err(|e| e.to_string())?,
-                card_sales: r.try_get("card_sales").map_err(|e| e.to_string())?,
-                status: r.try_get("status").map_err(|e| e.to_string())?,
-                notes: r.try_get("notes").ok().flatten(),
-                created_at: r.try_get("created_at").map_err(|e| e.to_string())?,
-            };
-            Ok(Some(shift))
-        }
-        None => Ok(None),
-    }
+    row.as_ref().map(shift_from_row).transpose()
 }
 
 #[command]
@@ -220,24 +209,23 @@ pub async fn get_shift_history(
     let offset = offset.unwrap_or(0);
 
     let query = if let Some(_uid) = user_id {
-        "SELECT id, user_id, start_time, end_time, opening_amount, closing_amount, 
-                total_sales, total_returns, cash_sales, card_sales, status, notes, created_at
-         FROM shifts WHERE user_id = ?1 ORDER BY start_time DESC LIMIT ?2 OFFSET ?3"
+        format!(
+            "SELECT {} FROM shifts WHERE user_id = ?1 ORDER BY start_time DESC LIMIT ?2 OFFSET ?3",
+            SHIFT_COLUMNS
+        )
     } else {
-        "SELECT id, user_id, start_time, end_time, opening_amount, closing_amount, 
-                total_sales, total_returns, cash_sales, card_sales, status, notes, created_at
-         FROM shifts ORDER BY start_time DESC LIMIT ?1 OFFSET ?2"
+        format!("SELECT {} FROM shifts ORDER BY start_time DESC LIMIT ?1 OFFSET ?2", SHIFT_COLUMNS)
     };
 
     let rows = if let Some(uid) = user_id {
-        sqlx::query(query)
+        sqlx::query(&query)
             .bind(uid)
             .bind(limit)
             .bind(offset)
             .fetch_all(pool_ref)
             .await
     } else {
-        sqlx::query(query)
+        sqlx::query(&query)
             .bind(limit)
             .bind(offset)
             .fetch_all(pool_ref)
@@ -245,25 +233,5 @@ pub async fn get_shift_history(
     }
     .map_err(|e| format!("Database error: {}", e))?;
 
-    let mut shifts = Vec::new();
-    for row in rows {
-        let shift = Shift {
-            id: row.try_get("id").map_err(|e| e.to_string())?,
-            user_id: row.try_get("user_id").map_err(|e| e.to_string())?,
-            start_time: row.try_get("start_time").map_err(|e| e.to_string())?,
-            end_time: row.try_get("end_time").ok().flatten(),
-            opening_amount: row.try_get("opening_amount").map_err(|e| e.to_string())?,
-            closing_amount: row.try_get("closing_amount").ok().flatten(),
-            total_sales: row.try_get("total_sales").map_err(|e| e.to_string())?,
-            total_returns: row.try_get("total_returns").map_err(|e| e.to_string())?,
-            cash_sales: row.try_get("cash_sales").map_err(|e| e.to_string())?,
-            card_sales: row.try_get("card_sales").map_err(|e| e.to_string())?,
-            status: row.try_get("status").map_err(|e| e.to_string())?,
-            notes: row.try_get("notes").ok().flatten(),
-            created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
-        };
-        shifts.push(shift);
-    }
-
-    Ok(shifts)
+    rows.iter().map(shift_from_row).collect()
 }
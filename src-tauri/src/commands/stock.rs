@@ -1,5 +1,18 @@
 // src-tauri/src/commands/stock.rs - Stock Management Commands
-use sqlx::{Row, SqlitePool};
+use crate::commands::products::require_product_in_organization;
+use crate::cost_history::{receive_at_cost, CostSource};
+use crate::db_utils::require_manager;
+use crate::error::{AppError, AppResult};
+use crate::location_stock::{self, LocationReorderLevels, LocationStock};
+use crate::lots;
+use crate::margins::DEFAULT_LOCATION_ID;
+use crate::models::StockUpdateRequest;
+use crate::money::Money;
+use crate::plans;
+use crate::tenancy::{active_organization, organization_scope};
+use crate::validation::{validate_required, Validate};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{FromRow, Row, SqlitePool};
 use tauri::{command, State};
 use serde::{Deserialize, Serialize};
 
@@ -11,15 +24,40 @@ pub struct StockReceiptRequest {
     pub supplier: Option<String>,
     pub reference_number: Option<String>,
     pub notes: Option<String>,
+    /// Required for lot-tracked products
+    #[serde(default)]
+    pub lot_number: Option<String>,
+    #[serde(default)]
+    pub expiry_date: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct StockAdjustmentRequest {
+/// Approval threshold used when the store location has none configured
+pub const DEFAULT_ADJUSTMENT_APPROVAL_THRESHOLD: f64 = 250.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StockAdjustment {
+    pub id: i64,
     pub product_id: i64,
-    pub adjustment_type: String, // 'add' or 'subtract'
-    pub quantity: i32,
-    pub reason: String,
+    pub quantity_change: i32,
+    pub reason_code: String,
     pub notes: Option<String>,
+    /// Quantity times cost price at the time of the request
+    pub adjustment_value: Money,
+    /// `applied`, `pending`, `approved` or `rejected`
+    pub status: String,
+    pub requested_by: Option<i64>,
+    pub decided_by: Option<i64>,
+    pub decided_at: Option<String>,
+    pub decision_notes: Option<String>,
+    pub movement_id: Option<i64>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AdjustmentReason {
+    pub code: String,
+    pub label: String,
+    pub is_active: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,7 +75,10 @@ pub async fn receive_stock(
     pool: State<'_, SqlitePool>,
     request: StockReceiptRequest,
     user_id: i64,
+    session_token: String,
 ) -> Result<String, String> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    require_product_in_organization(pool.inner(), request.product_id, organization_id).await?;
     let pool_ref = pool.inner();
     let mut tx = pool_ref
         .begin()
@@ -54,6 +95,21 @@ pub async fn receive_stock(
     let previous_stock: i32 = current.try_get("current_stock").map_err(|e| e.to_string())?;
     let new_stock = previous_stock + request.quantity;
 
+    // Fold a known cost into the average before the stock lands, keeping the old one in its history
+    let unit_cost = (request.cost_price > 0.0).then_some(request.cost_price);
+    if let Some(unit_cost) = unit_cost {
+        receive_at_cost(
+            &mut tx,
+            request.product_id,
+            request.quantity,
+            unit_cost,
+            CostSource::Receipt,
+            None,
+            Some(user_id),
+        )
+        .await?;
+    }
+
     // Update inventory
     sqlx::query(
         "UPDATE inventory SET 
@@ -68,14 +124,19 @@ pub async fn receive_stock(
     .await
     .map_err(|e| format!("Failed to update inventory: {}", e))?;
 
-    // Update product cost price if provided
-    if request.cost_price > 0.0 {
-        sqlx::query("UPDATE products SET cost_price = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2")
-            .bind(request.cost_price)
-            .bind(request.product_id)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| format!("Failed to update cost price: {}", e))?;
+    // Lot-tracked products need to know which lot the delivery belongs to
+    if lots::is_lot_tracked(&mut tx, request.product_id).await? {
+        let lot_number = request.lot_number.as_deref().ok_or_else(|| {
+            AppError::validation("lot_number", "A lot number is required for this product")
+        })?;
+        lots::receive_into_lot(
+            &mut tx,
+            request.product_id,
+            lot_number,
+            request.expiry_date.as_deref(),
+            request.quantity,
+        )
+        .await?;
     }
 
     // Create inventory movement record
@@ -90,8 +151,8 @@ pub async fn receive_stock(
     sqlx::query(
         "INSERT INTO inventory_movements 
             (product_id, movement_type, quantity_change, previous_stock, new_stock, 
-             reference_type, notes, user_id)
-         VALUES (?1, 'receipt', ?2, ?3, ?4, ?5, ?6, ?7)",
+             reference_type, notes, user_id, unit_cost)
+         VALUES (?1, 'receipt', ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
     )
     .bind(request.product_id)
     .bind(request.quantity)
@@ -100,6 +161,7 @@ pub async fn receive_stock(
     .bind(request.reference_number.as_ref().map(|r| r.as_str()).unwrap_or("manual"))
     .bind(&notes)
     .bind(user_id)
+    .bind(unit_cost)
     .execute(&mut *tx)
     .await
     .map_err(|e| format!("Failed to create movement record: {}", e))?;
@@ -111,83 +173,405 @@ pub async fn receive_stock(
     Ok(format!("Successfully received {} units", request.quantity))
 }
 
-/// Adjust stock (add or subtract)
+/// Adjust a product's stock under a reason code. Small adjustments move stock straight away;
+/// ones worth more than the location's approval threshold wait as `pending` for a manager.
 #[command]
 pub async fn adjust_stock(
     pool: State<'_, SqlitePool>,
-    request: StockAdjustmentRequest,
-    user_id: i64,
-) -> Result<String, String> {
-    let pool_ref = pool.inner();
-    let mut tx = pool_ref
-        .begin()
-        .await
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
-
-    // Get current stock
-    let current = sqlx::query("SELECT current_stock FROM inventory WHERE product_id = ?1")
-        .bind(request.product_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| format!("Product not found in inventory: {}", e))?;
+    request: StockUpdateRequest,
+    session_token: String,
+) -> Result<StockAdjustment, AppError> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    require_product_in_organization(pool.inner(), request.product_id, organization_id).await?;
+    adjust_stock_internal(pool.inner(), request).await
+}
 
-    let previous_stock: i32 = current.try_get("current_stock").map_err(|e| e.to_string())?;
-    
-    let quantity_change = if request.adjustment_type == "add" {
-        request.quantity
-    } else {
-        -request.quantity
-    };
+const ADJUSTMENT_COLUMNS: &str = "id, product_id, quantity_change, reason_code, notes, adjustment_value,
+    status, requested_by, decided_by, decided_at, decision_notes, movement_id,
+    COALESCE(created_at, '') as created_at";
+
+async fn fetch_adjustment(conn: &mut SqliteConnection, adjustment_id: i64) -> AppResult<StockAdjustment> {
+    let query = format!("SELECT {} FROM stock_adjustments WHERE id = ?1", ADJUSTMENT_COLUMNS);
+    sqlx::query_as::<_, StockAdjustment>(&query)
+        .bind(adjustment_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Stock adjustment"))
+}
 
+/// Move stock for an adjustment and write its `adjustment` movement; returns the movement id
+async fn apply_adjustment(
+    conn: &mut SqliteConnection,
+    adjustment_id: i64,
+    product_id: i64,
+    quantity_change: i32,
+    notes: &str,
+    user_id: Option<i64>,
+) -> AppResult<i64> {
+    let previous_stock: i32 =
+        sqlx::query_scalar("SELECT current_stock FROM inventory WHERE product_id = ?1")
+            .bind(product_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::inventory_not_found(product_id))?;
     let new_stock = previous_stock + quantity_change;
-
     if new_stock < 0 {
-        return Err("Cannot adjust stock below zero".to_string());
+        return Err(AppError::validation(
+            "quantity_change",
+            "Stock cannot go below zero",
+        ));
     }
 
-    // Update inventory
     sqlx::query(
-        "UPDATE inventory SET 
+        "UPDATE inventory SET
             current_stock = current_stock + ?1,
             available_stock = available_stock + ?1,
             last_updated = CURRENT_TIMESTAMP
          WHERE product_id = ?2",
     )
     .bind(quantity_change)
+    .bind(product_id)
+    .execute(&mut *conn)
+    .await?;
+
+    let movement_id = sqlx::query(
+        "INSERT INTO inventory_movements
+            (product_id, movement_type, quantity_change, previous_stock, new_stock,
+             reference_id, reference_type, notes, user_id)
+         VALUES (?1, 'adjustment', ?2, ?3, ?4, ?5, 'stock_adjustment', ?6, ?7)",
+    )
+    .bind(product_id)
+    .bind(quantity_change)
+    .bind(previous_stock)
+    .bind(new_stock)
+    .bind(adjustment_id)
+    .bind(notes)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+    Ok(movement_id)
+}
+
+fn movement_notes(reason_label: &str, notes: Option<&str>) -> String {
+    match notes.filter(|n| !n.trim().is_empty()) {
+        Some(notes) => format!("Stock adjustment ({}): {}", reason_label, notes),
+        None => format!("Stock adjustment ({})", reason_label),
+    }
+}
+
+async fn reason_label(conn: &mut SqliteConnection, reason_code: &str) -> AppResult<String> {
+    sqlx::query_scalar("SELECT label FROM stock_adjustment_reasons WHERE code = ?1")
+        .bind(reason_code)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Adjustment reason"))
+}
+
+/// Label of a reason code that can be used for new adjustments and write-offs
+pub(crate) async fn active_reason_label(
+    conn: &mut SqliteConnection,
+    reason_code: &str,
+) -> AppResult<String> {
+    let reason: Option<(String, bool)> = sqlx::query_as(
+        "SELECT label, is_active FROM stock_adjustment_reasons WHERE code = ?1",
+    )
+    .bind(reason_code)
+    .fetch_optional(&mut *conn)
+    .await?;
+    match reason {
+        Some((label, true)) => Ok(label),
+        _ => Err(AppError::validation(
+            "reason_code",
+            &format!("Unknown adjustment reason '{}'", reason_code),
+        )),
+    }
+}
+
+/// Value above which stock adjustments and write-offs need a manager
+pub(crate) async fn adjustment_approval_threshold(conn: &mut SqliteConnection) -> AppResult<Money> {
+    let threshold: f64 = sqlx::query_scalar(
+        "SELECT adjustment_approval_threshold FROM locations WHERE id = ?1",
+    )
+    .bind(DEFAULT_LOCATION_ID)
+    .fetch_optional(&mut *conn)
+    .await?
+    .unwrap_or(DEFAULT_ADJUSTMENT_APPROVAL_THRESHOLD);
+    Ok(Money::from_major(threshold))
+}
+
+pub(crate) async fn adjust_stock_internal(
+    pool: &SqlitePool,
+    request: StockUpdateRequest,
+) -> AppResult<StockAdjustment> {
+    request.validate()?;
+    let reason_code = request
+        .reason_code
+        .as_deref()
+        .map(str::trim)
+        .filter(|code| !code.is_empty())
+        .ok_or_else(|| AppError::validation("reason_code", "A reason code is required"))?;
+
+    let mut tx = pool.begin().await?;
+
+    let label = active_reason_label(&mut tx, reason_code).await?;
+
+    let cost_price: Money = sqlx::query_scalar("SELECT cost_price FROM products WHERE id = ?1")
+        .bind(request.product_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("Product"))?;
+    let adjustment_value = cost_price.times(request.quantity_change.abs());
+
+    // Checked again on approval, as stock may have moved while the request was pending
+    let on_hand: i32 = sqlx::query_scalar("SELECT current_stock FROM inventory WHERE product_id = ?1")
+        .bind(request.product_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::inventory_not_found(request.product_id))?;
+    if on_hand + request.quantity_change < 0 {
+        return Err(AppError::validation(
+            "quantity_change",
+            "Stock cannot go below zero",
+        ));
+    }
+
+    let needs_approval = adjustment_value > adjustment_approval_threshold(&mut tx).await?;
+
+    let adjustment_id = sqlx::query(
+        "INSERT INTO stock_adjustments
+            (product_id, quantity_change, reason_code, notes, adjustment_value, status, requested_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )
     .bind(request.product_id)
+    .bind(request.quantity_change)
+    .bind(reason_code)
+    .bind(&request.notes)
+    .bind(adjustment_value)
+    .bind(if needs_approval { "pending" } else { "applied" })
+    .bind(request.user_id)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| format!("Failed to update inventory: {}", e))?;
+    .await?
+    .last_insert_rowid();
+
+    if !needs_approval {
+        let movement_id = apply_adjustment(
+            &mut tx,
+            adjustment_id,
+            request.product_id,
+            request.quantity_change,
+            &movement_notes(&label, request.notes.as_deref()),
+            request.user_id,
+        )
+        .await?;
+        sqlx::query("UPDATE stock_adjustments SET movement_id = ?1 WHERE id = ?2")
+            .bind(movement_id)
+            .bind(adjustment_id)
+            .execute(&mut *tx)
+            .await?;
+    }
 
-    // Create inventory movement record
-    let notes = format!(
-        "Stock adjustment ({}): {} - {}",
-        request.adjustment_type,
-        request.reason,
-        request.notes.as_ref().map(|n| n.as_str()).unwrap_or("")
-    );
+    let adjustment = fetch_adjustment(&mut tx, adjustment_id).await?;
+    tx.commit().await?;
+    Ok(adjustment)
+}
+
+/// Fail with NOT_FOUND unless the adjustment is of a product of the organization
+async fn require_adjustment_in_organization(
+    pool: &SqlitePool,
+    adjustment_id: i64,
+    organization_id: i64,
+) -> AppResult<()> {
+    let product_id: i64 =
+        sqlx::query_scalar("SELECT product_id FROM stock_adjustments WHERE id = ?1")
+            .bind(adjustment_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("Stock adjustment"))?;
+    require_product_in_organization(pool, product_id, organization_id)
+        .await
+        .map_err(|_| AppError::not_found("Stock adjustment"))
+}
+
+/// Approve a pending adjustment and move the stock now
+#[command]
+pub async fn approve_stock_adjustment(
+    pool: State<'_, SqlitePool>,
+    adjustment_id: i64,
+    approver_id: i64,
+    notes: Option<String>,
+    session_token: String,
+) -> Result<StockAdjustment, AppError> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    require_adjustment_in_organization(pool.inner(), adjustment_id, organization_id).await?;
+    decide_stock_adjustment_internal(pool.inner(), adjustment_id, approver_id, true, notes).await
+}
+
+/// Reject a pending adjustment; stock is left as it is
+#[command]
+pub async fn reject_stock_adjustment(
+    pool: State<'_, SqlitePool>,
+    adjustment_id: i64,
+    approver_id: i64,
+    notes: Option<String>,
+    session_token: String,
+) -> Result<StockAdjustment, AppError> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    require_adjustment_in_organization(pool.inner(), adjustment_id, organization_id).await?;
+    decide_stock_adjustment_internal(pool.inner(), adjustment_id, approver_id, false, notes).await
+}
+
+pub(crate) async fn decide_stock_adjustment_internal(
+    pool: &SqlitePool,
+    adjustment_id: i64,
+    approver_id: i64,
+    approve: bool,
+    notes: Option<String>,
+) -> AppResult<StockAdjustment> {
+    let mut tx = pool.begin().await?;
+    require_manager(&mut tx, approver_id, "decide on a stock adjustment").await?;
+
+    let adjustment = fetch_adjustment(&mut tx, adjustment_id).await?;
+    if adjustment.status != "pending" {
+        return Err(AppError::Conflict {
+            message: format!("Stock adjustment is already {}", adjustment.status),
+        });
+    }
+
+    let movement_id = if approve {
+        let label = reason_label(&mut tx, &adjustment.reason_code).await?;
+        Some(
+            apply_adjustment(
+                &mut tx,
+                adjustment.id,
+                adjustment.product_id,
+                adjustment.quantity_change,
+                &movement_notes(&label, adjustment.notes.as_deref()),
+                Some(approver_id),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
 
     sqlx::query(
-        "INSERT INTO inventory_movements 
-            (product_id, movement_type, quantity_change, previous_stock, new_stock, 
-             reference_type, notes, user_id)
-         VALUES (?1, 'adjustment', ?2, ?3, ?4, 'manual', ?5, ?6)",
+        "UPDATE stock_adjustments
+         SET status = ?1, decided_by = ?2, decided_at = CURRENT_TIMESTAMP,
+             decision_notes = ?3, movement_id = ?4
+         WHERE id = ?5",
     )
-    .bind(request.product_id)
-    .bind(quantity_change)
-    .bind(previous_stock)
-    .bind(new_stock)
+    .bind(if approve { "approved" } else { "rejected" })
+    .bind(approver_id)
     .bind(&notes)
-    .bind(user_id)
+    .bind(movement_id)
+    .bind(adjustment_id)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| format!("Failed to create movement record: {}", e))?;
+    .await?;
 
-    tx.commit()
-        .await
-        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    let adjustment = fetch_adjustment(&mut tx, adjustment_id).await?;
+    tx.commit().await?;
+    Ok(adjustment)
+}
 
-    Ok(format!("Successfully adjusted stock by {} units", quantity_change))
+/// Adjustments newest first, optionally only those in one status (e.g. `pending`)
+#[command]
+pub async fn get_stock_adjustments(
+    pool: State<'_, SqlitePool>,
+    status: Option<String>,
+    limit: Option<i64>,
+    session_token: String,
+) -> Result<Vec<StockAdjustment>, AppError> {
+    let organization_id = active_organization(&session_token)?;
+    let query = format!(
+        "SELECT {} FROM stock_adjustments
+         WHERE (?1 IS NULL OR status = ?1)
+           AND product_id IN (SELECT id FROM products WHERE 1=1{})
+         ORDER BY id DESC
+         LIMIT ?2",
+        ADJUSTMENT_COLUMNS,
+        organization_scope("organization_id", organization_id)
+    );
+    let adjustments = sqlx::query_as::<_, StockAdjustment>(&query)
+        .bind(status)
+        .bind(limit.unwrap_or(100).clamp(1, 1000))
+        .fetch_all(pool.inner())
+        .await?;
+    Ok(adjustments)
+}
+
+#[command]
+pub async fn get_adjustment_reasons(
+    pool: State<'_, SqlitePool>,
+    include_inactive: Option<bool>,
+) -> Result<Vec<AdjustmentReason>, AppError> {
+    let reasons = sqlx::query_as::<_, AdjustmentReason>(
+        "SELECT code, label, is_active FROM stock_adjustment_reasons
+         WHERE is_active = 1 OR ?1
+         ORDER BY label",
+    )
+    .bind(include_inactive.unwrap_or(false))
+    .fetch_all(pool.inner())
+    .await?;
+    Ok(reasons)
+}
+
+/// Add a reason code or rename/deactivate an existing one
+#[command]
+pub async fn save_adjustment_reason(
+    pool: State<'_, SqlitePool>,
+    reason: AdjustmentReason,
+    session_token: String,
+) -> Result<AdjustmentReason, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    let code = reason.code.trim().to_lowercase();
+    validate_required(&code, "code")?;
+    validate_required(&reason.label, "label")?;
+
+    let saved = sqlx::query_as::<_, AdjustmentReason>(
+        "INSERT INTO stock_adjustment_reasons (code, label, is_active) VALUES (?1, ?2, ?3)
+         ON CONFLICT(code) DO UPDATE SET label = excluded.label, is_active = excluded.is_active
+         RETURNING code, label, is_active",
+    )
+    .bind(&code)
+    .bind(reason.label.trim())
+    .bind(reason.is_active)
+    .fetch_one(pool.inner())
+    .await?;
+    Ok(saved)
+}
+
+/// A product's stock at each active store and branch
+#[command]
+pub async fn get_stock_by_location(
+    pool: State<'_, SqlitePool>,
+    product_id: i64,
+    session_token: String,
+) -> Result<Vec<LocationStock>, AppError> {
+    let organization_id = active_organization(&session_token)?;
+    require_product_in_organization(pool.inner(), product_id, organization_id).await?;
+    location_stock::stock_by_location(pool.inner(), product_id).await
+}
+
+/// Set the minimum, maximum and reorder point of a product at one store or branch
+#[command]
+pub async fn set_location_reorder_levels(
+    pool: State<'_, SqlitePool>,
+    product_id: i64,
+    location_id: i64,
+    minimum_stock: Option<i32>,
+    maximum_stock: Option<i32>,
+    reorder_point: Option<i32>,
+    session_token: String,
+) -> Result<(), AppError> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    require_product_in_organization(pool.inner(), product_id, organization_id).await?;
+    let levels = LocationReorderLevels {
+        minimum_stock,
+        maximum_stock,
+        reorder_point,
+    };
+    location_stock::set_location_reorder_levels(pool.inner(), product_id, location_id, &levels).await
 }
 
 /// Reserve stock (for orders, quotes, etc.)
@@ -198,7 +582,10 @@ pub async fn reserve_stock(
     quantity: i32,
     user_id: i64,
     notes: Option<String>,
+    session_token: String,
 ) -> Result<String, String> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    require_product_in_organization(pool.inner(), product_id, organization_id).await?;
     let pool_ref = pool.inner();
     let mut tx = pool_ref
         .begin()
@@ -262,7 +649,10 @@ pub async fn release_reserved_stock(
     product_id: i64,
     quantity: i32,
     _user_id: i64,
+    session_token: String,
 ) -> Result<String, String> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    require_product_in_organization(pool.inner(), product_id, organization_id).await?;
     let pool_ref = pool.inner();
 
     // Update inventory (decrease reserved, increase available)
@@ -290,7 +680,10 @@ pub async fn stock_take(
     actual_count: i32,
     user_id: i64,
     notes: Option<String>,
+    session_token: String,
 ) -> Result<String, String> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    require_product_in_organization(pool.inner(), product_id, organization_id).await?;
     let pool_ref = pool.inner();
     let mut tx = pool_ref
         .begin()
@@ -358,3 +751,129 @@ pub async fn stock_take(
         difference
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+
+    fn adjustment(product_id: i64, quantity_change: i32, reason_code: &str, user_id: i64) -> StockUpdateRequest {
+        StockUpdateRequest {
+            product_id,
+            quantity_change,
+            movement_type: "adjustment".to_string(),
+            notes: Some("Shelf check".to_string()),
+            reference_id: None,
+            reference_type: None,
+            user_id: Some(user_id),
+            reason_code: Some(reason_code.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_small_adjustment_applies_immediately() {
+        let pool = test_pool().await;
+        let clerk = insert_test_user(&pool, "clerk").await;
+        // Cost 5.00, so 3 units are worth 15.00, under the default threshold
+        let product_id = insert_test_product(&pool, "BRICK", 10.0, 40).await;
+
+        let applied = adjust_stock_internal(&pool, adjustment(product_id, -3, "damaged", clerk))
+            .await
+            .unwrap();
+        assert_eq!(applied.status, "applied");
+        assert_eq!(applied.adjustment_value, Money::from_major(15.0));
+        assert_eq!(current_stock(&pool, product_id).await, 37);
+
+        let movement: (String, i32, i32, Option<i64>, String) = sqlx::query_as(
+            "SELECT movement_type, previous_stock, new_stock, reference_id, notes
+             FROM inventory_movements WHERE id = ?1",
+        )
+        .bind(applied.movement_id.unwrap())
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            movement,
+            (
+                "adjustment".to_string(),
+                40,
+                37,
+                Some(applied.id),
+                "Stock adjustment (Damaged): Shelf check".to_string()
+            )
+        );
+
+        let err = adjust_stock_internal(&pool, adjustment(product_id, -1, "misplaced", clerk))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        let mut no_reason = adjustment(product_id, -1, "damaged", clerk);
+        no_reason.reason_code = None;
+        let err = adjust_stock_internal(&pool, no_reason).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        let err = adjust_stock_internal(&pool, adjustment(product_id, -100, "theft", clerk))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert_eq!(current_stock(&pool, product_id).await, 37);
+    }
+
+    #[tokio::test]
+    async fn test_large_adjustment_waits_for_manager() {
+        let pool = test_pool().await;
+        let clerk = insert_test_user(&pool, "clerk").await;
+        let manager = insert_test_user(&pool, "manager").await;
+        sqlx::query("UPDATE users SET role = 'Manager' WHERE id = ?1")
+            .bind(manager)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE locations SET adjustment_approval_threshold = 100.0 WHERE id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+        // Cost 50.00, so 4 units are worth 200.00
+        let product_id = insert_test_product(&pool, "MIXER", 100.0, 10).await;
+
+        let pending = adjust_stock_internal(&pool, adjustment(product_id, -4, "theft", clerk))
+            .await
+            .unwrap();
+        assert_eq!(pending.status, "pending");
+        assert!(pending.movement_id.is_none());
+        assert_eq!(current_stock(&pool, product_id).await, 10);
+
+        let err = decide_stock_adjustment_internal(&pool, pending.id, clerk, true, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "PERMISSION_DENIED");
+
+        let approved = decide_stock_adjustment_internal(&pool, pending.id, manager, true, None)
+            .await
+            .unwrap();
+        assert_eq!(approved.status, "approved");
+        assert_eq!(approved.decided_by, Some(manager));
+        assert!(approved.movement_id.is_some());
+        assert_eq!(current_stock(&pool, product_id).await, 6);
+
+        let err = decide_stock_adjustment_internal(&pool, pending.id, manager, true, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+
+        let second = adjust_stock_internal(&pool, adjustment(product_id, 5, "found", clerk))
+            .await
+            .unwrap();
+        let rejected = decide_stock_adjustment_internal(
+            &pool,
+            second.id,
+            manager,
+            false,
+            Some("Recount first".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(rejected.status, "rejected");
+        assert_eq!(rejected.decision_notes.as_deref(), Some("Recount first"));
+        assert_eq!(current_stock(&pool, product_id).await, 6);
+    }
+}
@@ -1,5 +1,12 @@
 use tauri::{command, State, AppHandle, Manager};
+use crate::currency::{self, Currency};
+use crate::document_numbers::{self, DocumentSequence, UpdateDocumentSequenceRequest};
+use crate::error::AppError;
 use crate::models::{StoreConfig, UpdateStoreConfigRequest};
+use crate::money::Money;
+use crate::organization_settings;
+use crate::settings::{self, STORE_SETTINGS};
+use crate::tenancy::DEFAULT_ORGANIZATION_ID;
 use sqlx::{SqlitePool, Row};
 use std::fs;
 use std::path::PathBuf;
@@ -24,8 +31,11 @@ pub async fn get_store_config(pool: State<'_, SqlitePool>) -> Result<StoreConfig
         zip_code: row.try_get("zip_code").ok().flatten(),
         phone: row.try_get("phone").ok().flatten(),
         email: row.try_get("email").ok().flatten(),
-        tax_rate: row.try_get("tax_rate").map_err(|e| e.to_string())?,
-        currency: row.try_get("currency").map_err(|e| e.to_string())?,
+        tax_rate: match settings::get_setting(pool_ref, "tax_rate", STORE_SETTINGS).await? {
+            Some(tax_rate) => tax_rate,
+            None => row.try_get("tax_rate").map_err(|e| e.to_string())?,
+        },
+        currency: currency::store_currency(pool_ref).await?.code,
         logo_url: row.try_get("logo_url").ok().flatten(),
         created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
         updated_at: row.try_get("updated_at").map_err(|e| e.to_string())?,
@@ -38,6 +48,8 @@ pub async fn get_store_config(pool: State<'_, SqlitePool>) -> Result<StoreConfig
 pub async fn update_store_config(pool: State<'_, SqlitePool>, request: UpdateStoreConfigRequest) -> Result<StoreConfig, String> {
     let pool_ref = pool.inner();
 
+    currency::validate_currency_code(pool_ref, &request.currency).await?;
+
     sqlx::query("UPDATE locations SET name = ?1, address = ?2, city = ?3, state = ?4, zip_code = ?5, phone = ?6, email = ?7, tax_rate = ?8, currency = ?9, logo_url = ?10, updated_at = CURRENT_TIMESTAMP WHERE id = 1")
         .bind(&request.name)
         .bind(&request.address)
@@ -54,10 +66,44 @@ pub async fn update_store_config(pool: State<'_, SqlitePool>, request: UpdateSto
         .map_err(|e| {
             format!("Failed to update store config: {}", e)
         })?;
+    settings::set_setting(pool_ref, "tax_rate", STORE_SETTINGS, &request.tax_rate).await?;
+    let mut conn = pool_ref.acquire().await.map_err(AppError::from)?;
+    let mut organization = organization_settings::load(&mut conn, DEFAULT_ORGANIZATION_ID).await?;
+    organization.currency = request.currency.clone();
+    organization_settings::save(&mut conn, DEFAULT_ORGANIZATION_ID, &organization).await?;
+    drop(conn);
 
     get_store_config(pool).await
 }
 
+#[command]
+pub async fn get_currencies(pool: State<'_, SqlitePool>) -> Result<Vec<Currency>, String> {
+    Ok(currency::get_currencies(pool.inner()).await?)
+}
+
+/// Format an amount in the store currency (for receipts and exports)
+#[command]
+pub async fn format_money(pool: State<'_, SqlitePool>, amount: Money) -> Result<String, String> {
+    Ok(currency::format_money(pool.inner(), amount).await?)
+}
+
+#[command]
+pub async fn get_document_sequences(
+    pool: State<'_, SqlitePool>,
+) -> Result<Vec<DocumentSequence>, String> {
+    Ok(document_numbers::get_document_sequences(pool.inner()).await?)
+}
+
+/// Change a document number prefix or turn yearly numbering resets on or off
+#[command]
+pub async fn update_document_sequence(
+    pool: State<'_, SqlitePool>,
+    doc_type: String,
+    request: UpdateDocumentSequenceRequest,
+) -> Result<DocumentSequence, String> {
+    Ok(document_numbers::update_document_sequence(pool.inner(), &doc_type, &request).await?)
+}
+
 #[command]
 pub async fn upload_store_logo(
     app: AppHandle,
@@ -0,0 +1,345 @@
+// src-tauri/src/commands/supplier_credits.rs - Credit memos owed by suppliers for returned goods
+use crate::document_numbers::next_document_number;
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use crate::plans;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqliteConnection, SqlitePool};
+use tauri::{command, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SupplierCreditMemo {
+    pub id: i64,
+    pub memo_number: String,
+    pub supplier_id: i64,
+    pub supplier_name: Option<String>,
+    pub return_id: i64,
+    pub purchase_order_id: Option<i64>,
+    pub amount: Money,
+    pub applied_amount: Money,
+    /// Open until the whole amount has been applied
+    pub status: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl SupplierCreditMemo {
+    pub fn balance(&self) -> Money {
+        self.amount - self.applied_amount
+    }
+}
+
+const MEMO_COLUMNS: &str = "m.id, m.memo_number, m.supplier_id, s.company_name as supplier_name,
+    m.return_id, m.purchase_order_id, m.amount, m.applied_amount, m.status, m.created_at, m.updated_at";
+
+async fn fetch_memo(conn: &mut SqliteConnection, credit_memo_id: i64) -> AppResult<SupplierCreditMemo> {
+    let query = format!(
+        "SELECT {} FROM supplier_credit_memos m
+         LEFT JOIN suppliers s ON s.id = m.supplier_id
+         WHERE m.id = ?1",
+        MEMO_COLUMNS
+    );
+    sqlx::query_as::<_, SupplierCreditMemo>(&query)
+        .bind(credit_memo_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Credit memo"))
+}
+
+/// Issue the credit memo for a completed purchase return, linked to the purchase order it
+/// references. Returns without a supplier, or with nothing to credit, get none.
+pub(crate) async fn issue_credit_memo(
+    conn: &mut SqliteConnection,
+    return_id: i64,
+) -> AppResult<Option<i64>> {
+    let source: Option<(Option<i64>, Option<i64>, Money)> = sqlx::query_as(
+        "SELECT COALESCE(cr.supplier_id, po.supplier_id), po.id, cr.total_amount
+         FROM comprehensive_returns cr
+         LEFT JOIN purchase_orders po ON po.id = cr.reference_id
+         WHERE cr.id = ?1 AND cr.return_type = 'PurchaseReturn'",
+    )
+    .bind(return_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some((Some(supplier_id), purchase_order_id, amount)) = source else {
+        return Ok(None);
+    };
+    if amount <= Money::ZERO {
+        return Ok(None);
+    }
+
+    let memo_number = next_document_number(&mut *conn, "credit_memo").await?;
+    let memo_id = sqlx::query(
+        "INSERT INTO supplier_credit_memos (memo_number, supplier_id, return_id, purchase_order_id, amount)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(&memo_number)
+    .bind(supplier_id)
+    .bind(return_id)
+    .bind(purchase_order_id)
+    .bind(amount)
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+    Ok(Some(memo_id))
+}
+
+pub(crate) async fn get_open_supplier_credits_internal(
+    pool: &SqlitePool,
+    supplier_id: Option<i64>,
+) -> AppResult<Vec<SupplierCreditMemo>> {
+    let query = format!(
+        "SELECT {} FROM supplier_credit_memos m
+         LEFT JOIN suppliers s ON s.id = m.supplier_id
+         WHERE m.status = 'Open' AND (?1 IS NULL OR m.supplier_id = ?1)
+         ORDER BY m.supplier_id, m.created_at, m.id",
+        MEMO_COLUMNS
+    );
+    Ok(sqlx::query_as::<_, SupplierCreditMemo>(&query)
+        .bind(supplier_id)
+        .fetch_all(pool)
+        .await?)
+}
+
+/// Credit memos with a balance left, for one supplier or all of them
+#[command]
+pub async fn get_open_supplier_credits(
+    pool: State<'_, SqlitePool>,
+    supplier_id: Option<i64>,
+) -> Result<Vec<SupplierCreditMemo>, AppError> {
+    get_open_supplier_credits_internal(pool.inner(), supplier_id).await
+}
+
+/// Pay `amount` of what is owed to the memo's supplier out of the memo. The credit is
+/// recorded as a supplier payment, against `purchase_order_id` when given.
+pub(crate) async fn apply_supplier_credit_internal(
+    pool: &SqlitePool,
+    credit_memo_id: i64,
+    purchase_order_id: Option<i64>,
+    amount: Money,
+    applied_by: i64,
+) -> AppResult<SupplierCreditMemo> {
+    if amount <= Money::ZERO {
+        return Err(AppError::validation("amount", "Credit to apply must be more than zero"));
+    }
+
+    let mut tx = pool.begin().await?;
+    let memo = fetch_memo(&mut tx, credit_memo_id).await?;
+    if amount > memo.balance() {
+        return Err(AppError::validation(
+            "amount",
+            &format!("Only {} is left on credit memo {}", memo.balance(), memo.memo_number),
+        ));
+    }
+
+    if let Some(po_id) = purchase_order_id {
+        let po_supplier: i64 = sqlx::query_scalar("SELECT supplier_id FROM purchase_orders WHERE id = ?1")
+            .bind(po_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::not_found("Purchase order"))?;
+        if po_supplier != memo.supplier_id {
+            return Err(AppError::validation(
+                "purchase_order_id",
+                "The purchase order belongs to a different supplier",
+            ));
+        }
+    }
+
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let payment_id = sqlx::query(
+        "INSERT INTO supplier_payments (supplier_id, purchase_order_id, payment_date, amount,
+                                        payment_method, reference_number, notes, created_by)
+         VALUES (?1, ?2, ?3, ?4, 'Other', ?5, ?6, ?7)",
+    )
+    .bind(memo.supplier_id)
+    .bind(purchase_order_id)
+    .bind(&today)
+    .bind(amount)
+    .bind(&memo.memo_number)
+    .bind(format!("Applied credit memo {}", memo.memo_number))
+    .bind(applied_by)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    sqlx::query(
+        "INSERT INTO supplier_credit_applications (credit_memo_id, supplier_payment_id, amount, applied_by)
+         VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(credit_memo_id)
+    .bind(payment_id)
+    .bind(amount)
+    .bind(applied_by)
+    .execute(&mut *tx)
+    .await?;
+
+    let applied_amount = memo.applied_amount + amount;
+    sqlx::query(
+        "UPDATE supplier_credit_memos
+         SET applied_amount = ?1, status = ?2, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?3",
+    )
+    .bind(applied_amount)
+    .bind(if applied_amount >= memo.amount { "Applied" } else { "Open" })
+    .bind(credit_memo_id)
+    .execute(&mut *tx)
+    .await?;
+
+    // The purchase order is paid once its payments, credits included, cover the total
+    if let Some(po_id) = purchase_order_id {
+        sqlx::query(
+            "UPDATE purchase_orders SET
+                payment_status = CASE
+                    WHEN (SELECT COALESCE(SUM(amount), 0) FROM supplier_payments WHERE purchase_order_id = ?1)
+                         >= total_amount - 0.005 THEN 'Paid'
+                    ELSE 'Partial'
+                END,
+                updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+        )
+        .bind(po_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let memo = fetch_memo(&mut tx, credit_memo_id).await?;
+    tx.commit().await?;
+    Ok(memo)
+}
+
+#[command]
+pub async fn apply_supplier_credit(
+    pool: State<'_, SqlitePool>,
+    credit_memo_id: i64,
+    purchase_order_id: Option<i64>,
+    amount: Money,
+    applied_by: i64,
+    session_token: String,
+) -> Result<SupplierCreditMemo, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    apply_supplier_credit_internal(pool.inner(), credit_memo_id, purchase_order_id, amount, applied_by)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::reports::get_payables_aging_internal;
+    use crate::commands::returns::{
+        approve_return_internal, complete_return_internal, create_return_internal, process_return_internal,
+        DispositionAction, NewReturn, ReturnCondition, ReturnItem, ReturnReason, ReturnType,
+    };
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    #[tokio::test]
+    async fn test_purchase_return_issues_memo_that_applies_partially() {
+        let pool = test_pool().await;
+        let manager = insert_test_user(&pool, "manager1").await;
+        sqlx::query("UPDATE users SET role = 'Manager' WHERE id = ?1")
+            .bind(manager)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let product = insert_test_product(&pool, "SUP-1", 10.0, 10).await;
+        let supplier = sqlx::query(
+            "INSERT INTO suppliers (supplier_number, company_name) VALUES ('SUP000001', 'Acme Wholesale')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        let purchase_order = sqlx::query(
+            "INSERT INTO purchase_orders (po_number, supplier_id, order_date, total_amount, status)
+             VALUES ('PO-1', ?1, DATE('now'), 100.0, 'Received')",
+        )
+        .bind(supplier)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+
+        let line_total = Money::from_major(20.0);
+        let return_id = create_return_internal(
+            &pool,
+            NewReturn {
+                return_type: ReturnType::PurchaseReturn,
+                reference_id: Some(purchase_order),
+                reference_number: Some("PO-1".to_string()),
+                supplier_id: None,
+                from_location_id: None,
+                to_location_id: None,
+                items: vec![ReturnItem {
+                    product_id: product,
+                    product_variant_id: None,
+                    quantity: 2,
+                    unit_price: Money::from_major(10.0),
+                    line_total,
+                    reason: ReturnReason::Defective,
+                    condition: ReturnCondition::Defective,
+                    disposition: DispositionAction::ReturnToSupplier,
+                    batch_number: None,
+                    expiry_date: None,
+                    notes: None,
+                }],
+                subtotal: line_total,
+                tax_amount: Money::ZERO,
+                total_amount: line_total,
+                refund_method: None,
+                credit_method: Some("credit_memo".to_string()),
+                expected_credit_date: None,
+                reason: None,
+                notes: None,
+                user_id: manager,
+                shift_id: None,
+                organization_id: DEFAULT_ORGANIZATION_ID,
+            },
+        )
+        .await
+        .unwrap();
+        approve_return_internal(&pool, return_id, manager, None).await.unwrap();
+        process_return_internal(&pool, return_id).await.unwrap();
+        complete_return_internal(&pool, return_id, manager, None).await.unwrap();
+
+        let open = get_open_supplier_credits_internal(&pool, Some(supplier)).await.unwrap();
+        assert_eq!(open.len(), 1);
+        let memo = &open[0];
+        assert_eq!(memo.amount, line_total);
+        assert_eq!(memo.purchase_order_id, Some(purchase_order));
+        assert!(memo.memo_number.starts_with("CM-"));
+
+        let aging = get_payables_aging_internal(&pool, DEFAULT_ORGANIZATION_ID, None).await.unwrap();
+        assert_eq!(aging.suppliers[0].total_outstanding, Money::from_major(100.0));
+        assert_eq!(aging.suppliers[0].open_credits, Money::from_major(20.0));
+        assert_eq!(aging.suppliers[0].net_payable, Money::from_major(80.0));
+
+        let memo = apply_supplier_credit_internal(&pool, memo.id, Some(purchase_order), Money::from_major(15.0), manager)
+            .await
+            .unwrap();
+        assert_eq!(memo.balance(), Money::from_major(5.0));
+        assert_eq!(memo.status, "Open");
+        let payment_status: String =
+            sqlx::query_scalar("SELECT payment_status FROM purchase_orders WHERE id = ?1")
+                .bind(purchase_order)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(payment_status, "Partial");
+
+        let err = apply_supplier_credit_internal(&pool, memo.id, None, Money::from_major(10.0), manager)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+
+        let aging = get_payables_aging_internal(&pool, DEFAULT_ORGANIZATION_ID, None).await.unwrap();
+        assert_eq!(aging.suppliers[0].total_outstanding, Money::from_major(85.0));
+        assert_eq!(aging.suppliers[0].open_credits, Money::from_major(5.0));
+
+        let memo = apply_supplier_credit_internal(&pool, memo.id, None, Money::from_major(5.0), manager)
+            .await
+            .unwrap();
+        assert_eq!(memo.status, "Applied");
+        assert!(get_open_supplier_credits_internal(&pool, None).await.unwrap().is_empty());
+    }
+}
@@ -1,4 +1,9 @@
+use crate::db_utils::require_unreferenced;
+use crate::error::AppError;
 use crate::models::{CreateSupplierRequest, Supplier, UpdateSupplierRequest};
+use crate::plans;
+use crate::tenancy;
+use crate::validation::Validate;
 use sqlx::{Row, SqlitePool};
 use tauri::{command, State};
 
@@ -16,10 +21,18 @@ async fn generate_supplier_number(pool: &SqlitePool) -> Result<String, String> {
 pub async fn get_suppliers(
     pool: State<'_, SqlitePool>,
     is_active: Option<bool>,
+    include_archived: Option<bool>,
+    session_token: String,
 ) -> Result<Vec<Supplier>, String> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
 
     let mut query = String::from("SELECT * FROM suppliers WHERE 1=1");
+    query.push_str(&tenancy::organization_scope("organization_id", organization_id));
+
+    if !include_archived.unwrap_or(false) {
+        query.push_str(" AND archived_at IS NULL");
+    }
 
     if is_active.is_some() {
         query.push_str(" AND is_active = ?");
@@ -78,11 +91,14 @@ pub async fn get_suppliers(
 pub async fn get_supplier(
     pool: State<'_, SqlitePool>,
     supplier_id: i64,
+    session_token: String,
 ) -> Result<Supplier, String> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
 
-    let row = sqlx::query("SELECT * FROM suppliers WHERE id = ?1")
+    let row = sqlx::query("SELECT * FROM suppliers WHERE id = ?1 AND organization_id = ?2")
         .bind(supplier_id)
+        .bind(organization_id)
         .fetch_optional(pool_ref)
         .await
         .map_err(|e| {
@@ -127,7 +143,11 @@ pub async fn get_supplier(
 pub async fn create_supplier(
     pool: State<'_, SqlitePool>,
     request: CreateSupplierRequest,
+    session_token: String,
 ) -> Result<Supplier, String> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    request.validate()?;
+
     let pool_ref = pool.inner();
 
     // Generate supplier number
@@ -136,8 +156,9 @@ pub async fn create_supplier(
     let result = sqlx::query(
         "INSERT INTO suppliers (
             supplier_number, company_name, contact_name, email, phone, website,
-            address, city, state, zip_code, country, payment_terms, tax_id, notes, rating
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            address, city, state, zip_code, country, payment_terms, tax_id, notes, rating,
+            organization_id
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
     )
     .bind(&supplier_number)
     .bind(&request.company_name)
@@ -154,6 +175,7 @@ pub async fn create_supplier(
     .bind(&request.tax_id)
     .bind(&request.notes)
     .bind(&request.rating)
+    .bind(organization_id)
     .execute(pool_ref)
     .await
     .map_err(|e| {
@@ -162,7 +184,7 @@ pub async fn create_supplier(
 
     let supplier_id = result.last_insert_rowid();
 
-    get_supplier(pool, supplier_id).await
+    get_supplier(pool, supplier_id, session_token).await
 }
 
 #[command]
@@ -170,12 +192,17 @@ pub async fn update_supplier(
     pool: State<'_, SqlitePool>,
     supplier_id: i64,
     request: UpdateSupplierRequest,
+    session_token: String,
 ) -> Result<Supplier, String> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    request.validate()?;
+
     let pool_ref = pool.inner();
 
     // Check if supplier exists
-    let exists = sqlx::query("SELECT id FROM suppliers WHERE id = ?1")
+    let exists = sqlx::query("SELECT id FROM suppliers WHERE id = ?1 AND organization_id = ?2")
         .bind(supplier_id)
+        .bind(organization_id)
         .fetch_optional(pool_ref)
         .await
         .map_err(|e| {
@@ -298,19 +325,85 @@ pub async fn update_supplier(
     })?;
 
 
-    get_supplier(pool, supplier_id).await
+    get_supplier(pool, supplier_id, session_token).await
+}
+
+/// Tables whose rows keep a supplier in the history; while any exist it can only be archived
+const SUPPLIER_REFERENCES: &[(&str, &str)] = &[
+    ("purchase_orders", "supplier_id"),
+    ("supplier_payments", "supplier_id"),
+    ("comprehensive_returns", "supplier_id"),
+    ("supplier_credit_memos", "supplier_id"),
+];
+
+/// Take a supplier out of search and the default lists while keeping their history
+#[command]
+pub async fn archive_supplier(
+    pool: State<'_, SqlitePool>,
+    supplier_id: i64,
+    session_token: String,
+) -> Result<String, String> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    let result = sqlx::query(
+        "UPDATE suppliers SET is_active = 0, archived_at = COALESCE(archived_at, CURRENT_TIMESTAMP),
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1 AND organization_id = ?2",
+    )
+    .bind(supplier_id)
+    .bind(organization_id)
+    .execute(pool.inner())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err("Supplier not found".to_string());
+    }
+
+    Ok("Supplier archived successfully".to_string())
+}
+
+#[command]
+pub async fn restore_supplier(
+    pool: State<'_, SqlitePool>,
+    supplier_id: i64,
+    session_token: String,
+) -> Result<String, String> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
+    let result = sqlx::query(
+        "UPDATE suppliers SET is_active = 1, archived_at = NULL, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1 AND organization_id = ?2",
+    )
+    .bind(supplier_id)
+    .bind(organization_id)
+    .execute(pool.inner())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err("Supplier not found".to_string());
+    }
+
+    Ok("Supplier restored successfully".to_string())
 }
 
+/// Delete a supplier for good. Only possible while nothing references them; suppliers
+/// with history are archived instead.
 #[command]
 pub async fn delete_supplier(
     pool: State<'_, SqlitePool>,
     supplier_id: i64,
+    session_token: String,
 ) -> Result<String, String> {
+    let organization_id = plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
+    let mut tx = pool_ref.begin().await.map_err(AppError::from)?;
+    tenancy::require_in_organization(&mut tx, "suppliers", supplier_id, organization_id, "Supplier")
+        .await?;
+    require_unreferenced(&mut tx, "Supplier", SUPPLIER_REFERENCES, supplier_id).await?;
     let result = sqlx::query("DELETE FROM suppliers WHERE id = ?1")
         .bind(supplier_id)
-        .execute(pool_ref)
+        .execute(&mut *tx)
         .await
         .map_err(|e| {
             format!("Database error: {}", e)
@@ -319,6 +412,7 @@ pub async fn delete_supplier(
     if result.rows_affected() == 0 {
         return Err("Supplier not found".to_string());
     }
+    tx.commit().await.map_err(AppError::from)?;
 
     Ok("Supplier deleted successfully".to_string())
 }
@@ -327,22 +421,26 @@ pub async fn delete_supplier(
 pub async fn search_suppliers(
     pool: State<'_, SqlitePool>,
     query: String,
+    session_token: String,
 ) -> Result<Vec<Supplier>, String> {
     let pool_ref = pool.inner();
+    let organization_id = tenancy::active_organization(&session_token)?;
 
     let search_pattern = format!("%{}%", query);
 
     let rows = sqlx::query(
         "SELECT * FROM suppliers
-         WHERE company_name LIKE ?1
+         WHERE (company_name LIKE ?1
             OR contact_name LIKE ?1
             OR email LIKE ?1
             OR phone LIKE ?1
-            OR supplier_number LIKE ?1
+            OR supplier_number LIKE ?1)
+           AND archived_at IS NULL AND organization_id = ?2
          ORDER BY company_name ASC
          LIMIT 50",
     )
     .bind(&search_pattern)
+    .bind(organization_id)
     .fetch_all(pool_ref)
     .await
     .map_err(|e| {
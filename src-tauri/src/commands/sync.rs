@@ -1,5 +1,8 @@
 // src-tauri/src/commands/sync.rs
 
+use crate::error::AppError;
+use crate::sync_inbound::{self, ConflictSide, SyncConflict};
+use crate::sync_outbox::{self, SyncOutboxEntry};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sqlx::{Row, SqlitePool, Column};
@@ -94,61 +97,9 @@ async fn sync_table_to_local(
     Ok(synced_count)
 }
 
-/// Generic upsert function for any table
+/// Apply a cloud record without clobbering newer local edits; conflicts are kept for review
 async fn upsert_record(pool: &SqlitePool, table_name: &str, record: &Value) -> Result<(), String> {
-    let record_obj = record
-        .as_object()
-        .ok_or_else(|| "Record is not a valid object".to_string())?;
-
-    // Get all field names and values
-    let fields: Vec<String> = record_obj.keys().cloned().collect();
-    let placeholders: Vec<String> = (1..=fields.len()).map(|i| format!("?{}", i)).collect();
-
-    // Build UPDATE clause, excluding 'id' field
-    let update_clauses: Vec<String> = fields
-        .iter()
-        .enumerate()
-        .filter(|(_, field)| *field != "id") // Skip id field by name, not index
-        .map(|(i, field)| format!("{} = ?{}", field, i + 1))
-        .collect();
-
-    // Build UPSERT query
-    let query = format!(
-        "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
-        table_name,
-        fields.join(", "),
-        placeholders.join(", "),
-        update_clauses.join(", ")
-    );
-
-    // Bind values dynamically
-    let mut query_builder = sqlx::query(&query);
-    for field in &fields {
-        let value = &record_obj[field];
-        match value {
-            Value::String(s) => query_builder = query_builder.bind(s),
-            Value::Number(n) => {
-                if let Some(i) = n.as_i64() {
-                    query_builder = query_builder.bind(i);
-                } else if let Some(f) = n.as_f64() {
-                    query_builder = query_builder.bind(f);
-                } else {
-                    return Err(format!("Unsupported number type for field {}", field));
-                }
-            }
-            Value::Bool(b) => query_builder = query_builder.bind(b),
-            Value::Null => query_builder = query_builder.bind(Option::<String>::None),
-            _ => {
-                return Err(format!("Unsupported value type for field {}", field));
-            }
-        }
-    }
-
-    query_builder
-        .execute(pool)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
-
+    sync_inbound::apply_remote_record(pool, table_name, record).await?;
     Ok(())
 }
 
@@ -306,4 +257,45 @@ pub async fn delete_local_record(
 
     println!("✅ Successfully deleted record from {}", table);
     Ok(format!("Record deleted from {}", table))
-}
\ No newline at end of file
+}
+
+/// Oldest local changes still waiting to be pushed (default 100 per batch)
+#[tauri::command]
+pub async fn get_pending_sync_batch(
+    pool: State<'_, SqlitePool>,
+    limit: Option<i64>,
+) -> Result<Vec<SyncOutboxEntry>, AppError> {
+    sync_outbox::get_pending_batch(pool.inner(), limit).await
+}
+
+/// Mark outbox entries as pushed to the cloud
+#[tauri::command]
+pub async fn mark_synced(pool: State<'_, SqlitePool>, ids: Vec<i64>) -> Result<u64, AppError> {
+    sync_outbox::mark_synced(pool.inner(), &ids).await
+}
+
+/// Record a failed push so the entry is retried later
+#[tauri::command]
+pub async fn mark_sync_error(
+    pool: State<'_, SqlitePool>,
+    id: i64,
+    error: String,
+) -> Result<(), AppError> {
+    sync_outbox::mark_error(pool.inner(), id, &error).await
+}
+
+/// Inbound changes that disagreed with local edits and still need a look
+#[tauri::command]
+pub async fn get_sync_conflicts(pool: State<'_, SqlitePool>) -> Result<Vec<SyncConflict>, AppError> {
+    sync_inbound::get_open_conflicts(pool.inner()).await
+}
+
+/// Settle a sync conflict by keeping the local or the remote version
+#[tauri::command]
+pub async fn resolve_sync_conflict(
+    pool: State<'_, SqlitePool>,
+    id: i64,
+    keep: ConflictSide,
+) -> Result<SyncConflict, AppError> {
+    sync_inbound::resolve_conflict(pool.inner(), id, keep).await
+}
@@ -0,0 +1,499 @@
+// src-tauri/src/commands/tax_rules.rs - Tax rule setup and tax collected per rule
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use crate::tax_rules::{TaxRule, TAX_RULE_COLUMNS, TAX_RULE_TARGETS};
+use crate::tenancy;
+use crate::validation::{validate_enum, validate_required, validate_tax_rate};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use tauri::{command, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxRuleRequest {
+    pub name: String,
+    pub rate: f64,
+    pub applies_to: String,
+    pub category: Option<String>,
+    pub product_id: Option<i64>,
+    #[serde(default)]
+    pub is_compound: bool,
+    #[serde(default)]
+    pub priority: i32,
+    pub exempt_categories: Option<String>,
+}
+
+/// Tax collected under one rule (or the legacy product rate) over a period
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TaxRuleSummary {
+    pub tax_rule_id: Option<i64>,
+    pub name: String,
+    pub rate: f64,
+    pub line_count: i64,
+    pub taxable_amount: Money,
+    pub tax_amount: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxRuleReport {
+    pub start_date: String,
+    pub end_date: String,
+    pub rules: Vec<TaxRuleSummary>,
+    pub total_tax: Money,
+    /// Totals of the sales the tax was charged on
+    pub total_sales: Money,
+    /// Net cash rounding on those sales. It is not taxed, so takings reconcile as
+    /// `total_sales + cash_rounding`.
+    pub cash_rounding: Money,
+}
+
+/// Sales taxed at one rate over a period. A line taxed by a compound rule counts under
+/// each of its rates.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TaxRateSummary {
+    pub rate: f64,
+    pub line_count: i64,
+    pub taxable_amount: Money,
+    pub tax_amount: Money,
+}
+
+/// Taxable sales and tax collected per rate, as needed for a sales tax filing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxRateReport {
+    pub start_date: String,
+    pub end_date: String,
+    pub rates: Vec<TaxRateSummary>,
+    /// Lines that were taxed, each counted once
+    pub total_taxable_sales: Money,
+    pub total_tax: Money,
+    /// Lines no tax was charged on: non-taxable products and category exemptions
+    pub exempt_line_count: i64,
+    pub exempt_sales: Money,
+}
+
+fn validate_rule(request: &TaxRuleRequest) -> AppResult<()> {
+    validate_required(&request.name, "name")?;
+    validate_tax_rate(request.rate, "rate")?;
+    validate_enum(&request.applies_to.as_str(), &TAX_RULE_TARGETS, "applies_to")?;
+    if request.applies_to == "category"
+        && request.category.as_deref().is_none_or(|c| c.trim().is_empty())
+    {
+        return Err(AppError::validation("category", "Category rules need a category"));
+    }
+    if request.applies_to == "product" && request.product_id.is_none() {
+        return Err(AppError::validation("product_id", "Product rules need a product"));
+    }
+    Ok(())
+}
+
+async fn fetch_rule(pool: &SqlitePool, rule_id: i64) -> AppResult<TaxRule> {
+    let query = format!("SELECT {} FROM tax_rules WHERE id = ?1", TAX_RULE_COLUMNS);
+    sqlx::query_as::<_, TaxRule>(&query)
+        .bind(rule_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("Tax rule"))
+}
+
+#[command]
+pub async fn get_tax_rules(
+    pool: State<'_, SqlitePool>,
+    active_only: Option<bool>,
+) -> Result<Vec<TaxRule>, AppError> {
+    let query = format!(
+        "SELECT {} FROM tax_rules WHERE (?1 = 0 OR is_active = 1) ORDER BY priority, id",
+        TAX_RULE_COLUMNS
+    );
+    Ok(sqlx::query_as::<_, TaxRule>(&query)
+        .bind(active_only.unwrap_or(false))
+        .fetch_all(pool.inner())
+        .await?)
+}
+
+#[command]
+pub async fn create_tax_rule(
+    pool: State<'_, SqlitePool>,
+    request: TaxRuleRequest,
+) -> Result<TaxRule, AppError> {
+    validate_rule(&request)?;
+    let rule_id = sqlx::query(
+        "INSERT INTO tax_rules (name, rate, applies_to, category, product_id, is_compound, priority,
+                                exempt_categories)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )
+    .bind(request.name.trim())
+    .bind(request.rate)
+    .bind(&request.applies_to)
+    .bind(request.category.as_deref().filter(|_| request.applies_to == "category"))
+    .bind(request.product_id.filter(|_| request.applies_to == "product"))
+    .bind(request.is_compound)
+    .bind(request.priority)
+    .bind(request.exempt_categories.as_deref().filter(|c| !c.trim().is_empty()))
+    .execute(pool.inner())
+    .await?
+    .last_insert_rowid();
+
+    fetch_rule(pool.inner(), rule_id).await
+}
+
+#[command]
+pub async fn update_tax_rule(
+    pool: State<'_, SqlitePool>,
+    rule_id: i64,
+    request: TaxRuleRequest,
+) -> Result<TaxRule, AppError> {
+    validate_rule(&request)?;
+    let updated = sqlx::query(
+        "UPDATE tax_rules SET
+            name = ?1, rate = ?2, applies_to = ?3, category = ?4, product_id = ?5, is_compound = ?6,
+            priority = ?7, exempt_categories = ?8, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?9",
+    )
+    .bind(request.name.trim())
+    .bind(request.rate)
+    .bind(&request.applies_to)
+    .bind(request.category.as_deref().filter(|_| request.applies_to == "category"))
+    .bind(request.product_id.filter(|_| request.applies_to == "product"))
+    .bind(request.is_compound)
+    .bind(request.priority)
+    .bind(request.exempt_categories.as_deref().filter(|c| !c.trim().is_empty()))
+    .bind(rule_id)
+    .execute(pool.inner())
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::not_found("Tax rule"));
+    }
+
+    fetch_rule(pool.inner(), rule_id).await
+}
+
+/// Rules are switched off rather than deleted so past sales keep their link
+#[command]
+pub async fn set_tax_rule_active(
+    pool: State<'_, SqlitePool>,
+    rule_id: i64,
+    is_active: bool,
+) -> Result<TaxRule, AppError> {
+    let updated = sqlx::query(
+        "UPDATE tax_rules SET is_active = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+    )
+    .bind(is_active)
+    .bind(rule_id)
+    .execute(pool.inner())
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::not_found("Tax rule"));
+    }
+
+    fetch_rule(pool.inner(), rule_id).await
+}
+
+pub(crate) async fn get_tax_report_by_rule_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> AppResult<TaxRuleReport> {
+    let scope = tenancy::organization_scope("s.organization_id", organization_id);
+    let rules = sqlx::query_as::<_, TaxRuleSummary>(&format!(
+        "SELECT t.tax_rule_id, t.name, t.rate,
+                COUNT(*) as line_count,
+                COALESCE(SUM(t.taxable_amount), 0.0) as taxable_amount,
+                COALESCE(SUM(t.tax_amount), 0.0) as tax_amount
+         FROM sale_item_taxes t
+         JOIN sale_items si ON si.id = t.sale_item_id
+         JOIN sales s ON s.id = si.sale_id
+         WHERE s.is_voided = 0 AND DATE(s.created_at) BETWEEN ?1 AND ?2{}
+         GROUP BY t.tax_rule_id, t.name, t.rate
+         ORDER BY t.name, t.rate",
+        scope
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+    let (total_sales, cash_rounding): (Money, Money) = sqlx::query_as(&format!(
+        "SELECT COALESCE(SUM(s.total_amount), 0.0), COALESCE(SUM(s.cash_rounding), 0.0)
+         FROM sales s
+         WHERE s.is_voided = 0 AND DATE(s.created_at) BETWEEN ?1 AND ?2{}",
+        scope
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(TaxRuleReport {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        total_tax: rules.iter().map(|rule| rule.tax_amount).sum(),
+        rules,
+        total_sales,
+        cash_rounding,
+    })
+}
+
+#[command]
+pub async fn get_tax_report_by_rule(
+    pool: State<'_, SqlitePool>,
+    start_date: String,
+    end_date: String,
+    session_token: String,
+) -> Result<TaxRuleReport, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_tax_report_by_rule_internal(pool.inner(), organization_id, &start_date, &end_date).await
+}
+
+pub(crate) async fn get_tax_report_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> AppResult<TaxRateReport> {
+    let scope = tenancy::organization_scope("s.organization_id", organization_id);
+    let rates = sqlx::query_as::<_, TaxRateSummary>(&format!(
+        "SELECT t.rate,
+                COUNT(*) as line_count,
+                COALESCE(SUM(t.taxable_amount), 0.0) as taxable_amount,
+                COALESCE(SUM(t.tax_amount), 0.0) as tax_amount
+         FROM sale_item_taxes t
+         JOIN sale_items si ON si.id = t.sale_item_id
+         JOIN sales s ON s.id = si.sale_id
+         WHERE s.is_voided = 0 AND s.is_demo = 0 AND DATE(s.created_at) BETWEEN ?1 AND ?2{}
+         GROUP BY t.rate
+         ORDER BY t.rate",
+        scope
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+    // Taxed lines are counted once whatever the number of rates on them
+    let totals: (Money, i64, Money) = sqlx::query_as(&format!(
+        "SELECT COALESCE(SUM(CASE WHEN si.tax_amount > 0 THEN si.line_total END), 0.0),
+                COUNT(CASE WHEN COALESCE(si.tax_amount, 0) = 0 THEN 1 END),
+                COALESCE(SUM(CASE WHEN COALESCE(si.tax_amount, 0) = 0 THEN si.line_total END), 0.0)
+         FROM sale_items si
+         JOIN sales s ON s.id = si.sale_id
+         WHERE s.is_voided = 0 AND s.is_demo = 0 AND DATE(s.created_at) BETWEEN ?1 AND ?2{}",
+        scope
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(pool)
+    .await?;
+    let (total_taxable_sales, exempt_line_count, exempt_sales) = totals;
+
+    Ok(TaxRateReport {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        total_tax: rates.iter().map(|rate| rate.tax_amount).sum(),
+        rates,
+        total_taxable_sales,
+        exempt_line_count,
+        exempt_sales,
+    })
+}
+
+/// Tax report for filing: taxable sales and tax per rate, plus exempt sales
+#[command]
+pub async fn get_tax_report(
+    pool: State<'_, SqlitePool>,
+    start_date: String,
+    end_date: String,
+    session_token: String,
+) -> Result<TaxRateReport, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_tax_report_internal(pool.inner(), organization_id, &start_date, &end_date).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::sales::create_sale_internal;
+    use crate::models::{CreateSaleRequest, SaleItemRequest};
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    fn sale(lines: &[(i64, f64)], tax_amount: i64) -> CreateSaleRequest {
+        let items: Vec<SaleItemRequest> = lines
+            .iter()
+            .map(|&(product_id, price)| SaleItemRequest {
+                product_id,
+                quantity: 1,
+                unit_price: Money::from_major(price),
+                discount_amount: Money::ZERO,
+                line_total: Money::from_major(price),
+                measured_quantity: None,
+                price_override: None,
+                override_reason: None,
+            })
+            .collect();
+        let subtotal: Money = items.iter().map(|item| item.line_total).sum();
+        CreateSaleRequest {
+            items,
+            subtotal,
+            tax_amount: Money::from_minor(tax_amount),
+            discount_amount: Money::ZERO,
+            total_amount: subtotal + Money::from_minor(tax_amount),
+            payment_method: "cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        }
+    }
+
+    async fn line_taxes(pool: &SqlitePool, sale_id: i64) -> Vec<(i64, String, Money)> {
+        sqlx::query_as(
+            "SELECT si.product_id, t.name, t.tax_amount
+             FROM sale_item_taxes t JOIN sale_items si ON si.id = t.sale_item_id
+             WHERE si.sale_id = ?1
+             ORDER BY si.id, t.id",
+        )
+        .bind(sale_id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sale_records_tax_breakdown_per_rule() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let drill = insert_test_product(&pool, "DRILL", 100.0, 10).await;
+        let bread = insert_test_product(&pool, "BREAD", 10.0, 10).await;
+        sqlx::query(
+            "UPDATE products SET is_taxable = 1, tax_rate = 5,
+                category = CASE sku WHEN 'BREAD' THEN 'Groceries' ELSE 'Hardware' END",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Without rules the product rate applies: 5% of 100.00 and of 10.00
+        let request = sale(&[(drill, 100.0), (bread, 10.0)], 550);
+        let legacy = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+        assert_eq!(
+            line_taxes(&pool, legacy.id).await,
+            vec![
+                (drill, "Sales tax".to_string(), Money::from_minor(500)),
+                (bread, "Sales tax".to_string(), Money::from_minor(50)),
+            ]
+        );
+
+        // State 6% exempts groceries; county 1% compounds on top of it
+        sqlx::query(
+            "INSERT INTO tax_rules (name, rate, is_compound, priority, exempt_categories) VALUES
+                ('State', 6, 0, 1, 'Groceries'),
+                ('County', 1, 1, 2, NULL)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let request = sale(&[(drill, 100.0), (bread, 10.0)], 716);
+        let ruled = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+        assert_eq!(
+            line_taxes(&pool, ruled.id).await,
+            vec![
+                (drill, "State".to_string(), Money::from_minor(600)),
+                (drill, "County".to_string(), Money::from_minor(106)),
+                (bread, "County".to_string(), Money::from_minor(10)),
+            ]
+        );
+        let item_tax: Money = sqlx::query_scalar(
+            "SELECT tax_amount FROM sale_items WHERE sale_id = ?1 AND product_id = ?2",
+        )
+        .bind(ruled.id)
+        .bind(drill)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(item_tax, Money::from_minor(706));
+
+        let report = get_tax_report_by_rule_internal(&pool, DEFAULT_ORGANIZATION_ID, "2000-01-01", "2999-12-31")
+            .await
+            .unwrap();
+        let by_name: Vec<(&str, i64, Money)> = report
+            .rules
+            .iter()
+            .map(|rule| (rule.name.as_str(), rule.line_count, rule.tax_amount))
+            .collect();
+        assert_eq!(
+            by_name,
+            vec![
+                ("County", 2, Money::from_minor(116)),
+                ("Sales tax", 2, Money::from_minor(550)),
+                ("State", 1, Money::from_minor(600)),
+            ]
+        );
+        assert_eq!(report.total_tax, Money::from_minor(1266));
+
+        let other = get_tax_report_by_rule_internal(&pool, 2, "2000-01-01", "2999-12-31")
+            .await
+            .unwrap();
+        assert!(other.rules.is_empty());
+        assert_eq!(other.total_sales, Money::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_tax_report_groups_sales_by_rate() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let drill = insert_test_product(&pool, "DRILL", 100.0, 10).await;
+        let wine = insert_test_product(&pool, "WINE", 20.0, 10).await;
+        let bread = insert_test_product(&pool, "BREAD", 10.0, 10).await;
+        sqlx::query(
+            "UPDATE products SET
+                is_taxable = CASE sku WHEN 'BREAD' THEN 0 ELSE 1 END,
+                tax_rate = CASE sku WHEN 'WINE' THEN 10 ELSE 5 END",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        for _ in 0..2 {
+            let request = sale(&[(drill, 100.0), (wine, 20.0), (bread, 10.0)], 700);
+            create_sale_internal(&pool, request, cashier, None).await.unwrap();
+        }
+        let voided = create_sale_internal(&pool, sale(&[(drill, 100.0)], 500), cashier, None)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE sales SET is_voided = 1 WHERE id = ?1")
+            .bind(voided.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let report = get_tax_report_internal(&pool, DEFAULT_ORGANIZATION_ID, "2000-01-01", "2999-12-31")
+            .await
+            .unwrap();
+        let by_rate: Vec<(f64, i64, Money, Money)> = report
+            .rates
+            .iter()
+            .map(|rate| (rate.rate, rate.line_count, rate.taxable_amount, rate.tax_amount))
+            .collect();
+        assert_eq!(
+            by_rate,
+            vec![
+                (5.0, 2, Money::from_minor(20000), Money::from_minor(1000)),
+                (10.0, 2, Money::from_minor(4000), Money::from_minor(400)),
+            ]
+        );
+        assert_eq!(report.total_taxable_sales, Money::from_minor(24000));
+        assert_eq!(report.total_tax, Money::from_minor(1400));
+        assert_eq!(report.exempt_line_count, 2);
+        assert_eq!(report.exempt_sales, Money::from_minor(2000));
+
+        let other = get_tax_report_internal(&pool, 2, "2000-01-01", "2999-12-31").await.unwrap();
+        assert!(other.rates.is_empty());
+        assert_eq!(other.total_taxable_sales, Money::ZERO);
+    }
+}
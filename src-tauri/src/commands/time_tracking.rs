@@ -1,5 +1,6 @@
 // src-tauri/src/commands/time_tracking.rs
 use crate::models::*;
+use crate::plans;
 use sqlx::SqlitePool;
 use tauri::State;
 
@@ -54,7 +55,9 @@ pub async fn get_time_entry(
 pub async fn create_time_entry(
     pool: State<'_, SqlitePool>,
     request: CreateTimeEntryRequest,
+    session_token: String,
 ) -> Result<TimeEntry, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     // Get employee's hourly rate
     let employee: Option<(f64,)> = sqlx::query_as(
         "SELECT e.hourly_rate FROM employees e WHERE e.id = ?"
@@ -129,7 +132,9 @@ pub async fn clock_in(
     pool: State<'_, SqlitePool>,
     employee_id: i64,
     hourly_rate: f64,
+    session_token: String,
 ) -> Result<TimeEntry, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     // Check if employee has an active time entry
     let active_entry: Option<TimeEntry> = sqlx::query_as::<_, TimeEntry>(
         "SELECT * FROM time_entries WHERE employee_id = ? AND status = 'Active' LIMIT 1"
@@ -163,7 +168,9 @@ pub async fn clock_out(
     pool: State<'_, SqlitePool>,
     entry_id: i64,
     break_minutes: Option<i32>,
+    session_token: String,
 ) -> Result<TimeEntry, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     // Calculate total hours and pay
     let break_min = break_minutes.unwrap_or(0);
 
@@ -192,7 +199,9 @@ pub async fn update_time_entry(
     pool: State<'_, SqlitePool>,
     entry_id: i64,
     request: UpdateTimeEntryRequest,
+    session_token: String,
 ) -> Result<TimeEntry, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     sqlx::query(
         "UPDATE time_entries SET
             clock_in = COALESCE(?, clock_in),
@@ -245,7 +254,9 @@ pub async fn update_time_entry(
 pub async fn delete_time_entry(
     pool: State<'_, SqlitePool>,
     entry_id: i64,
+    session_token: String,
 ) -> Result<(), String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     sqlx::query("DELETE FROM time_entries WHERE id = ?")
         .bind(entry_id)
         .execute(pool.inner())
@@ -0,0 +1,406 @@
+// src-tauri/src/commands/transfers.rs - Stock transfers between locations
+use crate::document_numbers::next_document_number;
+use crate::error::{AppError, AppResult};
+use crate::location_stock::{self, post_transfer_leg, TransferLeg};
+use crate::plans;
+use crate::tenancy;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{FromRow, SqlitePool};
+use tauri::{command, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferLine {
+    pub product_id: i64,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTransferRequest {
+    pub from_location_id: i64,
+    pub to_location_id: i64,
+    pub items: Vec<TransferLine>,
+    pub notes: Option<String>,
+    pub user_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StockTransfer {
+    pub id: i64,
+    pub transfer_number: String,
+    pub from_location_id: i64,
+    pub to_location_id: i64,
+    /// `pending`, `in_transit`, `received` or `cancelled`
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_by: Option<i64>,
+    pub shipped_by: Option<i64>,
+    pub received_by: Option<i64>,
+    pub shipped_at: Option<String>,
+    pub received_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StockTransferItem {
+    pub product_id: i64,
+    pub product_name: String,
+    pub sku: String,
+    pub quantity: i32,
+}
+
+/// Stock of a product shipped but not yet received, by destination
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct InTransitStock {
+    pub product_id: i64,
+    pub product_name: String,
+    pub to_location_id: i64,
+    pub quantity: i64,
+}
+
+const TRANSFER_COLUMNS: &str = "id, transfer_number, from_location_id, to_location_id, status, notes,
+    created_by, shipped_by, received_by, shipped_at, received_at, COALESCE(created_at, '') as created_at";
+
+async fn fetch_transfer(conn: &mut SqliteConnection, transfer_id: i64) -> AppResult<StockTransfer> {
+    let query = format!("SELECT {} FROM stock_transfers WHERE id = ?1", TRANSFER_COLUMNS);
+    sqlx::query_as::<_, StockTransfer>(&query)
+        .bind(transfer_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Stock transfer"))
+}
+
+async fn fetch_transfer_items(
+    conn: &mut SqliteConnection,
+    transfer_id: i64,
+) -> AppResult<Vec<StockTransferItem>> {
+    let items = sqlx::query_as::<_, StockTransferItem>(
+        "SELECT ti.product_id, p.name as product_name, p.sku, ti.quantity
+         FROM stock_transfer_items ti
+         JOIN products p ON p.id = ti.product_id
+         WHERE ti.transfer_id = ?1
+         ORDER BY ti.id",
+    )
+    .bind(transfer_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(items)
+}
+
+/// Fail unless the transfer is in `expected` status
+fn require_status(transfer: &StockTransfer, expected: &str) -> AppResult<()> {
+    if transfer.status != expected {
+        return Err(AppError::Conflict {
+            message: format!(
+                "Stock transfer {} is {}",
+                transfer.transfer_number, transfer.status
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Post one side of every line of a transfer: out of the source when shipped, into the
+/// destination when received. Both sides share the transfer as their reference.
+async fn post_transfer_side(
+    conn: &mut SqliteConnection,
+    transfer: &StockTransfer,
+    location_id: i64,
+    sign: i32,
+    user_id: i64,
+) -> AppResult<()> {
+    let notes = format!(
+        "Transfer {} from location {} to {}",
+        transfer.transfer_number, transfer.from_location_id, transfer.to_location_id
+    );
+    for item in fetch_transfer_items(conn, transfer.id).await? {
+        post_transfer_leg(
+            conn,
+            &TransferLeg {
+                product_id: item.product_id,
+                location_id,
+                quantity_change: sign * item.quantity,
+                reference_type: "stock_transfer",
+                reference_id: transfer.id,
+                notes: &notes,
+                user_id,
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Plan a transfer of stock between two locations. Nothing moves until it is shipped.
+#[command]
+pub async fn create_transfer(
+    pool: State<'_, SqlitePool>,
+    request: CreateTransferRequest,
+    session_token: String,
+) -> Result<StockTransfer, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    {
+        let mut conn = pool.acquire().await?;
+        plans::require_writable(&mut conn, organization_id).await?;
+        plans::require_feature(&mut conn, organization_id, "multi_location").await?;
+    }
+    create_transfer_internal(pool.inner(), request).await
+}
+
+pub(crate) async fn create_transfer_internal(
+    pool: &SqlitePool,
+    request: CreateTransferRequest,
+) -> AppResult<StockTransfer> {
+    if request.from_location_id == request.to_location_id {
+        return Err(AppError::validation(
+            "to_location_id",
+            "A transfer must go to a different location",
+        ));
+    }
+    if request.items.is_empty() {
+        return Err(AppError::validation("items", "A transfer needs at least one item"));
+    }
+    if let Some(item) = request.items.iter().find(|item| item.quantity <= 0) {
+        return Err(AppError::validation(
+            "quantity",
+            &format!("Quantity for product {} must be greater than zero", item.product_id),
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+    location_stock::require_active_location(&mut tx, request.from_location_id).await?;
+    location_stock::require_active_location(&mut tx, request.to_location_id).await?;
+
+    let transfer_number = next_document_number(&mut *tx, "stock_transfer").await?;
+    let transfer_id = sqlx::query(
+        "INSERT INTO stock_transfers (transfer_number, from_location_id, to_location_id, notes, created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(&transfer_number)
+    .bind(request.from_location_id)
+    .bind(request.to_location_id)
+    .bind(&request.notes)
+    .bind(request.user_id)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    for item in &request.items {
+        sqlx::query(
+            "INSERT INTO stock_transfer_items (transfer_id, product_id, quantity) VALUES (?1, ?2, ?3)",
+        )
+        .bind(transfer_id)
+        .bind(item.product_id)
+        .bind(item.quantity)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let transfer = fetch_transfer(&mut tx, transfer_id).await?;
+    tx.commit().await?;
+    Ok(transfer)
+}
+
+/// Send a pending transfer: its stock leaves the source and is in transit
+#[command]
+pub async fn ship_transfer(
+    pool: State<'_, SqlitePool>,
+    transfer_id: i64,
+    user_id: i64,
+    session_token: String,
+) -> Result<StockTransfer, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    ship_transfer_internal(pool.inner(), transfer_id, user_id).await
+}
+
+pub(crate) async fn ship_transfer_internal(
+    pool: &SqlitePool,
+    transfer_id: i64,
+    user_id: i64,
+) -> AppResult<StockTransfer> {
+    let mut tx = pool.begin().await?;
+    let transfer = fetch_transfer(&mut tx, transfer_id).await?;
+    require_status(&transfer, "pending")?;
+
+    post_transfer_side(&mut tx, &transfer, transfer.from_location_id, -1, user_id).await?;
+    sqlx::query(
+        "UPDATE stock_transfers SET status = 'in_transit', shipped_by = ?1, shipped_at = CURRENT_TIMESTAMP
+         WHERE id = ?2",
+    )
+    .bind(user_id)
+    .bind(transfer_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let transfer = fetch_transfer(&mut tx, transfer_id).await?;
+    tx.commit().await?;
+    Ok(transfer)
+}
+
+/// Take delivery of a transfer in transit: its stock arrives at the destination
+#[command]
+pub async fn receive_transfer(
+    pool: State<'_, SqlitePool>,
+    transfer_id: i64,
+    user_id: i64,
+    session_token: String,
+) -> Result<StockTransfer, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    receive_transfer_internal(pool.inner(), transfer_id, user_id).await
+}
+
+pub(crate) async fn receive_transfer_internal(
+    pool: &SqlitePool,
+    transfer_id: i64,
+    user_id: i64,
+) -> AppResult<StockTransfer> {
+    let mut tx = pool.begin().await?;
+    let transfer = fetch_transfer(&mut tx, transfer_id).await?;
+    require_status(&transfer, "in_transit")?;
+
+    post_transfer_side(&mut tx, &transfer, transfer.to_location_id, 1, user_id).await?;
+    sqlx::query(
+        "UPDATE stock_transfers SET status = 'received', received_by = ?1, received_at = CURRENT_TIMESTAMP
+         WHERE id = ?2",
+    )
+    .bind(user_id)
+    .bind(transfer_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let transfer = fetch_transfer(&mut tx, transfer_id).await?;
+    tx.commit().await?;
+    Ok(transfer)
+}
+
+#[command]
+pub async fn get_transfers(
+    pool: State<'_, SqlitePool>,
+    status: Option<String>,
+    location_id: Option<i64>,
+) -> Result<Vec<StockTransfer>, AppError> {
+    let query = format!(
+        "SELECT {} FROM stock_transfers
+         WHERE (?1 IS NULL OR status = ?1)
+           AND (?2 IS NULL OR from_location_id = ?2 OR to_location_id = ?2)
+         ORDER BY created_at DESC, id DESC",
+        TRANSFER_COLUMNS
+    );
+    let transfers = sqlx::query_as::<_, StockTransfer>(&query)
+        .bind(status)
+        .bind(location_id)
+        .fetch_all(pool.inner())
+        .await?;
+    Ok(transfers)
+}
+
+#[command]
+pub async fn get_transfer_items(
+    pool: State<'_, SqlitePool>,
+    transfer_id: i64,
+) -> Result<Vec<StockTransferItem>, AppError> {
+    let mut conn = pool.inner().acquire().await?;
+    fetch_transfer(&mut conn, transfer_id).await?;
+    fetch_transfer_items(&mut conn, transfer_id).await
+}
+
+/// Stock shipped but not yet received, optionally for one product
+#[command]
+pub async fn get_in_transit_stock(
+    pool: State<'_, SqlitePool>,
+    product_id: Option<i64>,
+    session_token: String,
+) -> Result<Vec<InTransitStock>, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_in_transit_stock_internal(pool.inner(), organization_id, product_id).await
+}
+
+pub(crate) async fn get_in_transit_stock_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    product_id: Option<i64>,
+) -> AppResult<Vec<InTransitStock>> {
+    let stock = sqlx::query_as::<_, InTransitStock>(&format!(
+        "SELECT ti.product_id, p.name as product_name, t.to_location_id,
+                SUM(ti.quantity) as quantity
+         FROM stock_transfer_items ti
+         JOIN stock_transfers t ON t.id = ti.transfer_id
+         JOIN products p ON p.id = ti.product_id
+         WHERE t.status = 'in_transit' AND (?1 IS NULL OR ti.product_id = ?1){}
+         GROUP BY ti.product_id, p.name, t.to_location_id
+         ORDER BY p.name, t.to_location_id",
+        tenancy::organization_scope("p.organization_id", organization_id)
+    ))
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(stock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+
+    #[tokio::test]
+    async fn test_transfer_moves_stock_through_transit_to_destination() {
+        let pool = test_pool().await;
+        let user = insert_test_user(&pool, "clerk").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 10).await;
+        let branch: i64 = sqlx::query_scalar("INSERT INTO locations (name) VALUES ('Branch') RETURNING id")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let at = |location_id: i64| {
+            let pool = pool.clone();
+            async move {
+                let mut conn = pool.acquire().await.unwrap();
+                location_stock::stock_at_location(&mut conn, widget, location_id).await.unwrap()
+            }
+        };
+
+        let request = |quantity| CreateTransferRequest {
+            from_location_id: 1,
+            to_location_id: branch,
+            items: vec![TransferLine { product_id: widget, quantity }],
+            notes: None,
+            user_id: user,
+        };
+        let too_many = create_transfer_internal(&pool, request(11)).await.unwrap();
+        let err = ship_transfer_internal(&pool, too_many.id, user).await.unwrap_err();
+        assert_eq!(err.code(), "INSUFFICIENT_STOCK");
+
+        let transfer = create_transfer_internal(&pool, request(4)).await.unwrap();
+        let err = receive_transfer_internal(&pool, transfer.id, user).await.unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+
+        let shipped = ship_transfer_internal(&pool, transfer.id, user).await.unwrap();
+        assert_eq!(shipped.status, "in_transit");
+        assert_eq!((at(1).await, at(branch).await), (6, 0));
+        let in_transit = get_in_transit_stock_internal(&pool, DEFAULT_ORGANIZATION_ID, Some(widget))
+            .await
+            .unwrap();
+        assert_eq!(in_transit.len(), 1);
+        assert_eq!((in_transit[0].to_location_id, in_transit[0].quantity), (branch, 4));
+        assert!(get_in_transit_stock_internal(&pool, 2, None).await.unwrap().is_empty());
+
+        let received = receive_transfer_internal(&pool, transfer.id, user).await.unwrap();
+        assert_eq!(received.status, "received");
+        assert_eq!((at(1).await, at(branch).await), (6, 4));
+        assert_eq!(current_stock(&pool, widget).await, 10);
+        let in_transit = get_in_transit_stock_internal(&pool, DEFAULT_ORGANIZATION_ID, None).await.unwrap();
+        assert!(in_transit.is_empty());
+
+        let legs: Vec<(i64, i32)> = sqlx::query_as(
+            "SELECT location_id, quantity_change FROM inventory_movements
+             WHERE movement_type = 'transfer' AND reference_type = 'stock_transfer' AND reference_id = ?1
+             ORDER BY id",
+        )
+        .bind(transfer.id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(legs, vec![(1, -4), (branch, 4)]);
+    }
+}
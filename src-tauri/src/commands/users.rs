@@ -1,6 +1,18 @@
 use tauri::{command, State};
 use bcrypt::{hash, verify, DEFAULT_COST};
+use crate::activity::{self, DailyActivitySummary, UserActivity};
+use crate::audit::{self, AuditEntry, AuditLogFilter};
+use crate::db_utils::ensure_email_available;
+use crate::error::AppError;
+use crate::fraud::{self, CashierFraudIndicators};
+use crate::lockout;
+use crate::password_reset::{self, PasswordResetCode};
+use crate::pin;
 use crate::models::{User, CreateUserRequest, UpdateProfileRequest, ChangePasswordRequest};
+use crate::plans::{self, PlanResource};
+use crate::session::SESSION_MANAGER;
+use crate::validation::Validate;
+use serde::{Deserialize, Serialize};
 use sqlx::{SqlitePool, Row};
 
 #[command]
@@ -43,12 +55,29 @@ pub async fn get_users(pool: State<'_, SqlitePool>) -> Result<Vec<User>, String>
 }
 
 #[command]
-pub async fn create_user(pool: State<'_, SqlitePool>, request: CreateUserRequest) -> Result<User, String> {
-    let pool_ref = pool.inner();
+pub async fn create_user(
+    pool: State<'_, SqlitePool>,
+    request: CreateUserRequest,
+    session_token: String,
+) -> Result<User, String> {
+    let session = SESSION_MANAGER.require_full_session(&session_token, &["Admin"])?;
+    create_user_internal(pool.inner(), request, session.organization_id).await
+}
+
+/// Create a user as a member of the organization
+async fn create_user_internal(
+    pool_ref: &SqlitePool,
+    request: CreateUserRequest,
+    organization_id: i64,
+) -> Result<User, String> {
+    request.validate()?;
+    {
+        let mut conn = pool_ref.acquire().await.map_err(AppError::from)?;
+        plans::check_plan_limit(&mut conn, organization_id, PlanResource::Users).await?;
+    }
 
-    let exists = sqlx::query("SELECT id FROM users WHERE username = ?1 OR email = ?2")
+    let exists = sqlx::query("SELECT id FROM users WHERE username = ?1")
         .bind(&request.username)
-        .bind(&request.email)
         .fetch_optional(pool_ref)
         .await
         .map_err(|e| {
@@ -56,8 +85,9 @@ pub async fn create_user(pool: State<'_, SqlitePool>, request: CreateUserRequest
         })?;
 
     if exists.is_some() {
-        return Err("Username or email already exists".to_string());
+        return Err(AppError::duplicate_entry("username").into());
     }
+    ensure_email_available(pool_ref, "users", &request.email, None).await?;
 
     let password_hash = hash(request.password, DEFAULT_COST).map_err(|e| {
         format!("Password hashing error: {}", e)
@@ -65,16 +95,14 @@ pub async fn create_user(pool: State<'_, SqlitePool>, request: CreateUserRequest
 
     sqlx::query("INSERT INTO users (username, email, password_hash, first_name, last_name, role) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
         .bind(&request.username)
-        .bind(&request.email)
+        .bind(request.email.trim())
         .bind(&password_hash)
         .bind(&request.first_name)
         .bind(&request.last_name)
         .bind(&request.role)
         .execute(pool_ref)
         .await
-        .map_err(|e| {
-            format!("Failed to create user: {}", e)
-        })?;
+        .map_err(AppError::from)?;
 
     let row = sqlx::query("SELECT id, username, email, first_name, last_name, role, is_active, profile_image_url, last_login, created_at, updated_at FROM users WHERE username = ?1")
         .bind(&request.username)
@@ -84,8 +112,18 @@ pub async fn create_user(pool: State<'_, SqlitePool>, request: CreateUserRequest
             format!("Failed to fetch created user: {}", e)
         })?;
 
+    let user_id: i64 = row.try_get("id").map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT OR IGNORE INTO organization_users (organization_id, user_id, role) VALUES (?1, ?2, 'User')",
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .execute(pool_ref)
+    .await
+    .map_err(AppError::from)?;
+
     let user = User {
-        id: row.try_get("id").map_err(|e| e.to_string())?,
+        id: user_id,
         username: row.try_get("username").map_err(|e| e.to_string())?,
         email: row.try_get("email").map_err(|e| e.to_string())?,
         first_name: row.try_get("first_name").map_err(|e| e.to_string())?,
@@ -108,12 +146,20 @@ pub async fn create_user(pool: State<'_, SqlitePool>, request: CreateUserRequest
 }
 
 #[command]
-pub async fn update_user(pool: State<'_, SqlitePool>, user_id: i64, request: CreateUserRequest) -> Result<User, String> {
+pub async fn update_user(
+    pool: State<'_, SqlitePool>,
+    user_id: i64,
+    request: CreateUserRequest,
+    session_token: String,
+) -> Result<User, String> {
+    request.validate()?;
+    // Roles are changed here, so only an admin signed in with a password may edit users
+    let session = SESSION_MANAGER.require_full_session(&session_token, &["Admin"])?;
+
     let pool_ref = pool.inner();
 
-    let exists = sqlx::query("SELECT id FROM users WHERE (username = ?1 OR email = ?2) AND id != ?3")
+    let exists = sqlx::query("SELECT id FROM users WHERE username = ?1 AND id != ?2")
         .bind(&request.username)
-        .bind(&request.email)
         .bind(user_id)
         .fetch_optional(pool_ref)
         .await
@@ -122,26 +168,47 @@ pub async fn update_user(pool: State<'_, SqlitePool>, user_id: i64, request: Cre
         })?;
 
     if exists.is_some() {
-        return Err("Username or email already exists".to_string());
+        return Err(AppError::duplicate_entry("username").into());
     }
+    ensure_email_available(pool_ref, "users", &request.email, Some(user_id)).await?;
 
     let password_hash = hash(request.password, DEFAULT_COST).map_err(|e| {
         format!("Password hashing error: {}", e)
     })?;
 
+    let mut tx = pool_ref.begin().await.map_err(AppError::from)?;
+    let previous_role: Option<String> = sqlx::query_scalar("SELECT role FROM users WHERE id = ?1")
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
     sqlx::query("UPDATE users SET username = ?1, email = ?2, password_hash = ?3, first_name = ?4, last_name = ?5, role = ?6, updated_at = CURRENT_TIMESTAMP WHERE id = ?7")
         .bind(&request.username)
-        .bind(&request.email)
+        .bind(request.email.trim())
         .bind(&password_hash)
         .bind(&request.first_name)
         .bind(&request.last_name)
         .bind(&request.role)
         .bind(user_id)
-        .execute(pool_ref)
+        .execute(&mut *tx)
         .await
         .map_err(|e| {
             format!("Failed to update user: {}", e)
         })?;
+    if let Some(previous_role) = previous_role.filter(|role| *role != request.role) {
+        audit::log_audit(
+            &mut tx,
+            session.organization_id,
+            Some(session.user_id),
+            "change_role",
+            "user",
+            Some(user_id),
+            Some(&serde_json::json!({ "role": previous_role })),
+            Some(&serde_json::json!({ "role": request.role })),
+        )
+        .await?;
+    }
+    tx.commit().await.map_err(AppError::from)?;
 
     let row = sqlx::query("SELECT id, username, email, first_name, last_name, role, is_active, profile_image_url, last_login, created_at, updated_at FROM users WHERE id = ?1")
         .bind(user_id)
@@ -175,7 +242,12 @@ pub async fn update_user(pool: State<'_, SqlitePool>, user_id: i64, request: Cre
 }
 
 #[command]
-pub async fn delete_user(pool: State<'_, SqlitePool>, user_id: i64) -> Result<bool, String> {
+pub async fn delete_user(
+    pool: State<'_, SqlitePool>,
+    user_id: i64,
+    session_token: String,
+) -> Result<bool, String> {
+    SESSION_MANAGER.require_full_session(&session_token, &["Admin"])?;
     let pool_ref = pool.inner();
 
     sqlx::query("UPDATE users SET is_active = 0, updated_at = CURRENT_TIMESTAMP WHERE id = ?1")
@@ -195,6 +267,8 @@ pub async fn update_user_profile(
     user_id: i64,
     request: UpdateProfileRequest,
 ) -> Result<User, String> {
+    request.validate()?;
+
     let pool_ref = pool.inner();
 
     // Check if username is already taken by another user
@@ -212,18 +286,7 @@ pub async fn update_user_profile(
     }
 
     // Check if email is already taken by another user
-    let email_exists = sqlx::query("SELECT id FROM users WHERE email = ?1 AND id != ?2")
-        .bind(&request.email)
-        .bind(user_id)
-        .fetch_optional(pool_ref)
-        .await
-        .map_err(|e| {
-            format!("Database error: {}", e)
-        })?;
-
-    if email_exists.is_some() {
-        return Err("Email already in use by another account".to_string());
-    }
+    ensure_email_available(pool_ref, "users", &request.email, Some(user_id)).await?;
 
     // Update user profile including username and profile image
     sqlx::query(
@@ -232,7 +295,7 @@ pub async fn update_user_profile(
     .bind(&request.username)
     .bind(&request.first_name)
     .bind(&request.last_name)
-    .bind(&request.email)
+    .bind(request.email.trim())
     .bind(&request.profile_image_url)
     .bind(user_id)
     .execute(pool_ref)
@@ -317,4 +380,214 @@ pub async fn change_user_password(
         })?;
 
     Ok(true)
-}
\ No newline at end of file
+}
+
+#[command]
+pub async fn unlock_user(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    user_id: i64,
+) -> Result<bool, String> {
+    SESSION_MANAGER
+        .require_full_session(&session_token, &["Admin"])
+        .map_err(|e| e.message())?;
+
+    lockout::unlock_user_account(pool.inner(), user_id)
+        .await
+        .map_err(|e| e.message())?;
+
+    Ok(true)
+}
+
+#[command]
+pub async fn set_user_pin(
+    pool: State<'_, SqlitePool>,
+    user_id: i64,
+    password: String,
+    pin: String,
+) -> Result<bool, String> {
+    pin::set_user_pin(pool.inner(), user_id, &password, &pin)
+        .await
+        .map_err(|e| e.message())?;
+
+    Ok(true)
+}
+
+/// Admin-only: issue a one-time code the user can redeem with `reset_password_with_code`
+#[command]
+pub async fn generate_password_reset_code(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    user_id: i64,
+) -> Result<PasswordResetCode, String> {
+    let session = SESSION_MANAGER
+        .require_full_session(&session_token, &["Admin"])
+        .map_err(|e| e.message())?;
+
+    password_reset::create_reset_code(pool.inner(), user_id, session.user_id, chrono::Utc::now())
+        .await
+        .map_err(|e| e.message())
+}
+
+/// A user's sign-in events and daily sales work over a date range
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserActivityReport {
+    pub user_id: i64,
+    pub events: Vec<UserActivity>,
+    pub daily: Vec<DailyActivitySummary>,
+}
+
+/// Sign-ins and daily sales of a user between two dates (today when omitted). Users can
+/// see their own; admins and managers anyone's.
+#[command]
+pub async fn get_user_activity(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    user_id: i64,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<UserActivityReport, AppError> {
+    let session = SESSION_MANAGER.validate_session(&session_token)?;
+    activity::require_activity_access(session.user_id, &session.role, user_id)?;
+
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let start_date = start_date.unwrap_or_else(|| today.clone());
+    let end_date = end_date.unwrap_or(today);
+    Ok(UserActivityReport {
+        user_id,
+        events: activity::user_activity(pool.inner(), user_id, &start_date, &end_date).await?,
+        daily: activity::daily_summary(pool.inner(), user_id, &start_date, &end_date).await?,
+    })
+}
+
+/// Sensitive changes from the audit log, for admins and managers
+#[command]
+pub async fn get_audit_log(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    filter: Option<AuditLogFilter>,
+) -> Result<Vec<AuditEntry>, AppError> {
+    let session = SESSION_MANAGER.require_role(&session_token, activity::ACTIVITY_VIEWER_ROLES)?;
+    audit::get_audit_log(pool.inner(), session.organization_id, &filter.unwrap_or_default()).await
+}
+
+/// Void, reprint, no-sale and discount indicators per cashier between two dates (today
+/// when omitted), for admins and managers. Cashiers above the thresholds are notified on.
+#[command]
+pub async fn get_fraud_indicators(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Vec<CashierFraudIndicators>, AppError> {
+    SESSION_MANAGER.require_role(&session_token, activity::ACTIVITY_VIEWER_ROLES)?;
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let start_date = start_date.unwrap_or_else(|| today.clone());
+    let end_date = end_date.unwrap_or(today);
+    fraud::get_fraud_indicators(pool.inner(), &start_date, &end_date).await
+}
+
+/// A signed-in user as shown in the "who's logged in" view
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActiveSession {
+    pub user_id: i64,
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub role: String,
+    pub location_id: i64,
+    pub pin_session: bool,
+    pub signed_in_at: String,
+    pub last_seen: String,
+}
+
+/// Who is signed in to the session's organization right now, most recently active first
+#[command]
+pub async fn get_active_sessions(
+    pool: State<'_, SqlitePool>,
+    session_token: String,
+) -> Result<Vec<ActiveSession>, AppError> {
+    let caller = SESSION_MANAGER.require_role(&session_token, activity::ACTIVITY_VIEWER_ROLES)?;
+    let timestamp = |seconds: u64| {
+        chrono::DateTime::from_timestamp(seconds as i64, 0)
+            .map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default()
+    };
+
+    let mut sessions: Vec<_> = SESSION_MANAGER
+        .active_sessions()
+        .into_iter()
+        .filter(|session| session.organization_id == caller.organization_id)
+        .collect();
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.last_activity));
+
+    let mut active = Vec::with_capacity(sessions.len());
+    for session in sessions {
+        let names: Option<(String, String)> =
+            sqlx::query_as("SELECT first_name, last_name FROM users WHERE id = ?1")
+                .bind(session.user_id)
+                .fetch_optional(pool.inner())
+                .await?;
+        let (first_name, last_name) = names.unwrap_or_default();
+        active.push(ActiveSession {
+            user_id: session.user_id,
+            username: session.username,
+            first_name,
+            last_name,
+            role: session.role,
+            location_id: session.location_id,
+            pin_session: session.pin_session,
+            signed_in_at: timestamp(session.created_at),
+            last_seen: timestamp(session.last_activity),
+        });
+    }
+    Ok(active)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_user, test_pool};
+
+    fn user_request(username: &str, email: &str) -> CreateUserRequest {
+        CreateUserRequest {
+            username: username.to_string(),
+            email: email.to_string(),
+            password: "Secret123".to_string(),
+            first_name: "Test".to_string(),
+            last_name: "User".to_string(),
+            role: "Cashier".to_string(),
+        }
+    }
+
+    fn error_of(err: &str) -> serde_json::Value {
+        serde_json::from_str(err).expect("error should be a structured AppError")
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_and_malformed_user_emails_are_rejected() {
+        let pool = test_pool().await;
+        insert_test_user(&pool, "existing").await;
+
+        let err = create_user_internal(&pool, user_request("newbie", "Existing@Example.com"), 1)
+            .await
+            .unwrap_err();
+        let err = error_of(&err);
+        assert_eq!(err["code"], "CONFLICT");
+        assert_eq!(err["message"], "Email already in use");
+
+        for email in ["not-an-email", "user@@example.com", ""] {
+            let err = create_user_internal(&pool, user_request("newbie", email), 1)
+                .await
+                .unwrap_err();
+            let err = error_of(&err);
+            assert_eq!(err["code"], "VALIDATION_ERROR");
+            assert_eq!(err["field"], "email");
+        }
+
+        let user = create_user_internal(&pool, user_request("newbie", "newbie@example.com"), 1)
+            .await
+            .unwrap();
+        assert_eq!(user.email, "newbie@example.com");
+    }
+}
@@ -1,4 +1,5 @@
 use crate::models::*;
+use crate::plans;
 use sqlx::{Row, SqlitePool};
 use tauri::{command, State};
 
@@ -43,7 +44,9 @@ pub async fn get_variant_type(pool: State<'_, SqlitePool>, id: i64) -> Result<Va
 pub async fn create_variant_type(
     pool: State<'_, SqlitePool>,
     request: CreateVariantTypeRequest,
+    session_token: String,
 ) -> Result<VariantType, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     let display_order = request.display_order.unwrap_or(0);
@@ -67,7 +70,9 @@ pub async fn update_variant_type(
     pool: State<'_, SqlitePool>,
     id: i64,
     request: UpdateVariantTypeRequest,
+    session_token: String,
 ) -> Result<VariantType, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     let display_order = request.display_order.unwrap_or(0);
@@ -89,7 +94,12 @@ pub async fn update_variant_type(
 }
 
 #[command]
-pub async fn delete_variant_type(pool: State<'_, SqlitePool>, id: i64) -> Result<(), String> {
+pub async fn delete_variant_type(
+    pool: State<'_, SqlitePool>,
+    id: i64,
+    session_token: String,
+) -> Result<(), String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     sqlx::query("DELETE FROM variant_types WHERE id = ?1")
@@ -166,7 +176,9 @@ pub async fn get_variant_value(
 pub async fn create_variant_value(
     pool: State<'_, SqlitePool>,
     request: CreateVariantValueRequest,
+    session_token: String,
 ) -> Result<VariantValue, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     let display_order = request.display_order.unwrap_or(0);
@@ -192,7 +204,9 @@ pub async fn update_variant_value(
     pool: State<'_, SqlitePool>,
     id: i64,
     request: UpdateVariantValueRequest,
+    session_token: String,
 ) -> Result<VariantValue, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     let display_order = request.display_order.unwrap_or(0);
@@ -215,7 +229,12 @@ pub async fn update_variant_value(
 }
 
 #[command]
-pub async fn delete_variant_value(pool: State<'_, SqlitePool>, id: i64) -> Result<(), String> {
+pub async fn delete_variant_value(
+    pool: State<'_, SqlitePool>,
+    id: i64,
+    session_token: String,
+) -> Result<(), String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     sqlx::query("DELETE FROM variant_values WHERE id = ?1")
@@ -283,7 +302,9 @@ pub async fn get_product_variants(
 pub async fn create_product_variant(
     pool: State<'_, SqlitePool>,
     request: CreateProductVariantRequest,
+    session_token: String,
 ) -> Result<ProductVariantWithValues, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     // Create the variant
@@ -337,7 +358,9 @@ pub async fn update_product_variant(
     pool: State<'_, SqlitePool>,
     variant_id: i64,
     request: UpdateProductVariantRequest,
+    session_token: String,
 ) -> Result<ProductVariant, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     sqlx::query(
@@ -385,7 +408,9 @@ pub async fn get_product_variant(
 pub async fn delete_product_variant(
     pool: State<'_, SqlitePool>,
     variant_id: i64,
+    session_token: String,
 ) -> Result<(), String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     sqlx::query("DELETE FROM product_variants WHERE id = ?1")
@@ -406,7 +431,9 @@ pub async fn update_variant_inventory(
     current_stock: i32,
     minimum_stock: i32,
     maximum_stock: i32,
+    session_token: String,
 ) -> Result<VariantInventory, String> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
     let pool_ref = pool.inner();
 
     let available_stock = current_stock; // Can be enhanced with reserved stock logic
@@ -0,0 +1,632 @@
+// src-tauri/src/commands/writeoffs.rs - Damaged / lost stock write-offs
+use crate::commands::stock::{active_reason_label, adjustment_approval_threshold};
+use crate::db_utils::require_manager;
+use crate::error::{AppError, AppResult};
+use crate::lots;
+use crate::money::Money;
+use crate::plans;
+use crate::tenancy;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{FromRow, Row, SqlitePool};
+use tauri::{command, State};
+
+/// Stock to write off: a product, or one of its variants
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockWriteoffRequest {
+    pub product_id: Option<i64>,
+    pub variant_id: Option<i64>,
+    /// Lot the stock comes from; required for lot-tracked products
+    #[serde(default)]
+    pub lot_id: Option<i64>,
+    pub quantity: i32,
+    pub reason_code: String,
+    pub notes: Option<String>,
+    /// Path of a photo of the damage
+    pub photo_attachment: Option<String>,
+    pub user_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StockWriteoff {
+    pub id: i64,
+    pub product_id: i64,
+    pub variant_id: Option<i64>,
+    pub lot_id: Option<i64>,
+    pub quantity: i32,
+    pub reason_code: String,
+    pub notes: Option<String>,
+    pub photo_attachment: Option<String>,
+    /// Cost price at the time of the request
+    pub unit_cost: Money,
+    pub total_cost: Money,
+    /// `applied`, `pending`, `approved` or `rejected`
+    pub status: String,
+    pub requested_by: Option<i64>,
+    pub decided_by: Option<i64>,
+    pub decided_at: Option<String>,
+    pub decision_notes: Option<String>,
+    pub movement_id: Option<i64>,
+    /// When the stock left inventory; `None` while pending or once rejected
+    pub applied_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WriteoffReasonSummary {
+    pub reason_code: String,
+    pub reason_label: String,
+    pub writeoff_count: i64,
+    pub quantity: i64,
+    pub total_cost: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WriteoffCategorySummary {
+    pub category: String,
+    pub writeoff_count: i64,
+    pub quantity: i64,
+    pub total_cost: Money,
+}
+
+/// Shrinkage from write-offs whose stock left inventory in the period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteoffReport {
+    pub start_date: String,
+    pub end_date: String,
+    pub by_reason: Vec<WriteoffReasonSummary>,
+    pub by_category: Vec<WriteoffCategorySummary>,
+    pub total_quantity: i64,
+    pub total_cost: Money,
+}
+
+const WRITEOFF_COLUMNS: &str = "id, product_id, variant_id, lot_id, quantity, reason_code, notes,
+    photo_attachment, unit_cost, total_cost, status, requested_by, decided_by, decided_at,
+    decision_notes, movement_id, applied_at, COALESCE(created_at, '') as created_at";
+
+/// Write-offs that took stock out of inventory
+const APPLIED_STATUSES: &str = "'applied', 'approved'";
+
+async fn fetch_writeoff(conn: &mut SqliteConnection, writeoff_id: i64) -> AppResult<StockWriteoff> {
+    let query = format!("SELECT {} FROM stock_writeoffs WHERE id = ?1", WRITEOFF_COLUMNS);
+    sqlx::query_as::<_, StockWriteoff>(&query)
+        .bind(writeoff_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Stock write-off"))
+}
+
+/// Stock on hand of the product or variant being written off
+async fn on_hand(conn: &mut SqliteConnection, product_id: i64, variant_id: Option<i64>) -> AppResult<i32> {
+    match variant_id {
+        Some(variant_id) => sqlx::query_scalar(
+            "SELECT current_stock FROM variant_inventory WHERE product_variant_id = ?1",
+        )
+        .bind(variant_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Variant inventory")),
+        None => sqlx::query_scalar("SELECT current_stock FROM inventory WHERE product_id = ?1")
+            .bind(product_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::inventory_not_found(product_id)),
+    }
+}
+
+/// Take written-off stock out of inventory (and its lot) with a `damage` movement
+async fn apply_writeoff(
+    conn: &mut SqliteConnection,
+    writeoff: &StockWriteoff,
+    user_id: i64,
+) -> AppResult<()> {
+    let previous_stock = on_hand(conn, writeoff.product_id, writeoff.variant_id).await?;
+    let new_stock = previous_stock - writeoff.quantity;
+    if new_stock < 0 {
+        return Err(AppError::insufficient_stock(
+            &format!("product {}", writeoff.product_id),
+            previous_stock,
+            writeoff.quantity,
+        ));
+    }
+
+    let mut notes = format!(
+        "Write-off ({})",
+        active_reason_label(conn, &writeoff.reason_code).await?
+    );
+    match writeoff.variant_id {
+        Some(variant_id) => {
+            sqlx::query(
+                "UPDATE variant_inventory SET
+                    current_stock = current_stock - ?1,
+                    available_stock = available_stock - ?1,
+                    last_updated = CURRENT_TIMESTAMP
+                 WHERE product_variant_id = ?2",
+            )
+            .bind(writeoff.quantity)
+            .bind(variant_id)
+            .execute(&mut *conn)
+            .await?;
+            let sku: String = sqlx::query_scalar("SELECT sku FROM product_variants WHERE id = ?1")
+                .bind(variant_id)
+                .fetch_one(&mut *conn)
+                .await?;
+            notes.push_str(&format!(" variant {}", sku));
+        }
+        None => {
+            sqlx::query(
+                "UPDATE inventory SET
+                    current_stock = current_stock - ?1,
+                    available_stock = available_stock - ?1,
+                    last_updated = CURRENT_TIMESTAMP
+                 WHERE product_id = ?2",
+            )
+            .bind(writeoff.quantity)
+            .bind(writeoff.product_id)
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    if let Some(lot_id) = writeoff.lot_id {
+        let updated = sqlx::query(
+            "UPDATE product_lots SET quantity = quantity - ?1 WHERE id = ?2 AND quantity >= ?1",
+        )
+        .bind(writeoff.quantity)
+        .bind(lot_id)
+        .execute(&mut *conn)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(AppError::validation(
+                "quantity",
+                "The lot does not hold that much stock",
+            ));
+        }
+    }
+    if let Some(extra) = writeoff.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+        notes.push_str(&format!(": {}", extra));
+    }
+
+    let movement_id = sqlx::query(
+        "INSERT INTO inventory_movements
+            (product_id, movement_type, quantity_change, previous_stock, new_stock,
+             reference_id, reference_type, notes, user_id)
+         VALUES (?1, 'damage', ?2, ?3, ?4, ?5, 'stock_writeoff', ?6, ?7)",
+    )
+    .bind(writeoff.product_id)
+    .bind(-writeoff.quantity)
+    .bind(previous_stock)
+    .bind(new_stock)
+    .bind(writeoff.id)
+    .bind(&notes)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    sqlx::query(
+        "UPDATE stock_writeoffs SET movement_id = ?1, applied_at = CURRENT_TIMESTAMP WHERE id = ?2",
+    )
+    .bind(movement_id)
+    .bind(writeoff.id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Write off damaged or lost stock that was never sold. Write-offs worth more than the
+/// location's adjustment approval threshold wait as `pending` for a manager.
+#[command]
+pub async fn record_stock_writeoff(
+    pool: State<'_, SqlitePool>,
+    request: StockWriteoffRequest,
+    session_token: String,
+) -> Result<StockWriteoff, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    record_stock_writeoff_internal(pool.inner(), request).await
+}
+
+pub(crate) async fn record_stock_writeoff_internal(
+    pool: &SqlitePool,
+    request: StockWriteoffRequest,
+) -> AppResult<StockWriteoff> {
+    if request.quantity <= 0 {
+        return Err(AppError::validation("quantity", "Quantity must be greater than zero"));
+    }
+    let reason_code = request.reason_code.trim();
+    let mut tx = pool.begin().await?;
+    active_reason_label(&mut tx, reason_code).await?;
+
+    // Variants take their own cost when they have one
+    let (product_id, unit_cost): (i64, Money) = match (request.product_id, request.variant_id) {
+        (product_id, Some(variant_id)) => {
+            let row = sqlx::query(
+                "SELECT v.product_id, COALESCE(NULLIF(v.cost_price, 0), p.cost_price, 0) as cost
+                 FROM product_variants v JOIN products p ON v.product_id = p.id
+                 WHERE v.id = ?1",
+            )
+            .bind(variant_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::not_found("Product variant"))?;
+            let variant_product: i64 = row.try_get("product_id")?;
+            if product_id.is_some_and(|id| id != variant_product) {
+                return Err(AppError::validation(
+                    "variant_id",
+                    "The variant belongs to a different product",
+                ));
+            }
+            (variant_product, row.try_get("cost")?)
+        }
+        (Some(product_id), None) => {
+            let cost: Money =
+                sqlx::query_scalar("SELECT COALESCE(cost_price, 0) FROM products WHERE id = ?1")
+                    .bind(product_id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .ok_or_else(|| AppError::not_found("Product"))?;
+            (product_id, cost)
+        }
+        (None, None) => {
+            return Err(AppError::validation(
+                "product_id",
+                "A product or variant to write off is required",
+            ))
+        }
+    };
+
+    if request.variant_id.is_none() && lots::is_lot_tracked(&mut tx, product_id).await? {
+        let lot_id = request.lot_id.ok_or_else(|| {
+            AppError::validation("lot_id", "Choose the lot the written-off stock comes from")
+        })?;
+        let lot_quantity: i32 = sqlx::query_scalar(
+            "SELECT quantity FROM product_lots WHERE id = ?1 AND product_id = ?2",
+        )
+        .bind(lot_id)
+        .bind(product_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("Product lot"))?;
+        if lot_quantity < request.quantity {
+            return Err(AppError::validation(
+                "quantity",
+                "The lot does not hold that much stock",
+            ));
+        }
+    } else if request.lot_id.is_some() {
+        return Err(AppError::validation("lot_id", "This product is not tracked by lot"));
+    }
+
+    // Checked again on approval, as stock may have moved while the request was pending
+    let available = on_hand(&mut tx, product_id, request.variant_id).await?;
+    if available < request.quantity {
+        return Err(AppError::insufficient_stock(
+            &format!("product {}", product_id),
+            available,
+            request.quantity,
+        ));
+    }
+
+    let total_cost = unit_cost.times(request.quantity);
+    let needs_approval = total_cost > adjustment_approval_threshold(&mut tx).await?;
+
+    let writeoff_id = sqlx::query(
+        "INSERT INTO stock_writeoffs
+            (product_id, variant_id, lot_id, quantity, reason_code, notes, photo_attachment,
+             unit_cost, total_cost, status, requested_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+    )
+    .bind(product_id)
+    .bind(request.variant_id)
+    .bind(request.lot_id)
+    .bind(request.quantity)
+    .bind(reason_code)
+    .bind(&request.notes)
+    .bind(&request.photo_attachment)
+    .bind(unit_cost)
+    .bind(total_cost)
+    .bind(if needs_approval { "pending" } else { "applied" })
+    .bind(request.user_id)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    if !needs_approval {
+        let writeoff = fetch_writeoff(&mut tx, writeoff_id).await?;
+        apply_writeoff(&mut tx, &writeoff, request.user_id).await?;
+    }
+
+    let writeoff = fetch_writeoff(&mut tx, writeoff_id).await?;
+    tx.commit().await?;
+    Ok(writeoff)
+}
+
+/// Approve a pending write-off and take the stock out now
+#[command]
+pub async fn approve_stock_writeoff(
+    pool: State<'_, SqlitePool>,
+    writeoff_id: i64,
+    approver_id: i64,
+    notes: Option<String>,
+    session_token: String,
+) -> Result<StockWriteoff, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    decide_stock_writeoff_internal(pool.inner(), writeoff_id, approver_id, true, notes).await
+}
+
+/// Reject a pending write-off; stock is left as it is
+#[command]
+pub async fn reject_stock_writeoff(
+    pool: State<'_, SqlitePool>,
+    writeoff_id: i64,
+    approver_id: i64,
+    notes: Option<String>,
+    session_token: String,
+) -> Result<StockWriteoff, AppError> {
+    plans::writable_organization(pool.inner(), &session_token).await?;
+    decide_stock_writeoff_internal(pool.inner(), writeoff_id, approver_id, false, notes).await
+}
+
+pub(crate) async fn decide_stock_writeoff_internal(
+    pool: &SqlitePool,
+    writeoff_id: i64,
+    approver_id: i64,
+    approve: bool,
+    notes: Option<String>,
+) -> AppResult<StockWriteoff> {
+    let mut tx = pool.begin().await?;
+    require_manager(&mut tx, approver_id, "decide on a stock write-off").await?;
+
+    let writeoff = fetch_writeoff(&mut tx, writeoff_id).await?;
+    if writeoff.status != "pending" {
+        return Err(AppError::Conflict {
+            message: format!("Stock write-off is already {}", writeoff.status),
+        });
+    }
+
+    sqlx::query(
+        "UPDATE stock_writeoffs
+         SET status = ?1, decided_by = ?2, decided_at = CURRENT_TIMESTAMP, decision_notes = ?3
+         WHERE id = ?4",
+    )
+    .bind(if approve { "approved" } else { "rejected" })
+    .bind(approver_id)
+    .bind(&notes)
+    .bind(writeoff_id)
+    .execute(&mut *tx)
+    .await?;
+    if approve {
+        apply_writeoff(&mut tx, &writeoff, approver_id).await?;
+    }
+
+    let writeoff = fetch_writeoff(&mut tx, writeoff_id).await?;
+    tx.commit().await?;
+    Ok(writeoff)
+}
+
+#[command]
+pub async fn get_stock_writeoffs(
+    pool: State<'_, SqlitePool>,
+    status: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<StockWriteoff>, AppError> {
+    let query = format!(
+        "SELECT {} FROM stock_writeoffs
+         WHERE (?1 IS NULL OR status = ?1)
+         ORDER BY id DESC
+         LIMIT ?2",
+        WRITEOFF_COLUMNS
+    );
+    let writeoffs = sqlx::query_as::<_, StockWriteoff>(&query)
+        .bind(status)
+        .bind(limit.unwrap_or(100).clamp(1, 1000))
+        .fetch_all(pool.inner())
+        .await?;
+    Ok(writeoffs)
+}
+
+/// Shrinkage value by reason and by product category between two dates (inclusive).
+/// Pending and rejected write-offs never left inventory and are not counted.
+#[command]
+pub async fn get_writeoff_report(
+    pool: State<'_, SqlitePool>,
+    start_date: String,
+    end_date: String,
+    session_token: String,
+) -> Result<WriteoffReport, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_writeoff_report_internal(pool.inner(), organization_id, &start_date, &end_date).await
+}
+
+pub(crate) async fn get_writeoff_report_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> AppResult<WriteoffReport> {
+    let scope = tenancy::organization_scope("p.organization_id", organization_id);
+    let by_reason_query = format!(
+        "SELECT w.reason_code, COALESCE(r.label, w.reason_code) as reason_label,
+                COUNT(*) as writeoff_count, SUM(w.quantity) as quantity,
+                SUM(w.total_cost) as total_cost
+         FROM stock_writeoffs w
+         JOIN products p ON w.product_id = p.id
+         LEFT JOIN stock_adjustment_reasons r ON w.reason_code = r.code
+         WHERE w.status IN ({})
+           AND DATE(w.applied_at) BETWEEN DATE(?1) AND DATE(?2){}
+         GROUP BY w.reason_code
+         ORDER BY total_cost DESC, w.reason_code",
+        APPLIED_STATUSES, scope
+    );
+    let by_reason = sqlx::query_as::<_, WriteoffReasonSummary>(&by_reason_query)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(pool)
+        .await?;
+
+    let by_category_query = format!(
+        "SELECT COALESCE(NULLIF(p.category, ''), 'Uncategorized') as category,
+                COUNT(*) as writeoff_count, SUM(w.quantity) as quantity,
+                SUM(w.total_cost) as total_cost
+         FROM stock_writeoffs w
+         JOIN products p ON w.product_id = p.id
+         WHERE w.status IN ({})
+           AND DATE(w.applied_at) BETWEEN DATE(?1) AND DATE(?2){}
+         GROUP BY 1
+         ORDER BY total_cost DESC, category",
+        APPLIED_STATUSES, scope
+    );
+    let by_category = sqlx::query_as::<_, WriteoffCategorySummary>(&by_category_query)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(WriteoffReport {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        total_quantity: by_reason.iter().map(|r| r.quantity).sum(),
+        total_cost: by_reason.iter().map(|r| r.total_cost).sum(),
+        by_reason,
+        by_category,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+
+    fn writeoff(product_id: i64, quantity: i32, reason_code: &str, user_id: i64) -> StockWriteoffRequest {
+        StockWriteoffRequest {
+            product_id: Some(product_id),
+            variant_id: None,
+            lot_id: None,
+            quantity,
+            reason_code: reason_code.to_string(),
+            notes: Some("Dropped from the racking".to_string()),
+            photo_attachment: Some("writeoffs/pallet.jpg".to_string()),
+            user_id,
+        }
+    }
+
+    async fn insert_manager(pool: &SqlitePool) -> i64 {
+        let manager = insert_test_user(pool, "manager").await;
+        sqlx::query("UPDATE users SET role = 'Manager' WHERE id = ?1")
+            .bind(manager)
+            .execute(pool)
+            .await
+            .unwrap();
+        manager
+    }
+
+    #[tokio::test]
+    async fn test_writeoffs_over_threshold_wait_for_manager() {
+        let pool = test_pool().await;
+        // Cost 50.00 a unit against the default 250.00 threshold
+        let product_id = insert_test_product(&pool, "TILE-BOX", 100.0, 20).await;
+        let clerk = insert_test_user(&pool, "clerk").await;
+        let manager = insert_manager(&pool).await;
+
+        let small = record_stock_writeoff_internal(&pool, writeoff(product_id, 5, "damaged", clerk))
+            .await
+            .unwrap();
+        assert_eq!(small.status, "applied");
+        assert_eq!(small.total_cost, Money::from_major(250.0));
+        assert!(small.movement_id.is_some());
+        assert_eq!(current_stock(&pool, product_id).await, 15);
+        let movement_type: String =
+            sqlx::query_scalar("SELECT movement_type FROM inventory_movements WHERE id = ?1")
+                .bind(small.movement_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(movement_type, "damage");
+
+        let large = record_stock_writeoff_internal(&pool, writeoff(product_id, 6, "damaged", clerk))
+            .await
+            .unwrap();
+        assert_eq!(large.status, "pending");
+        assert_eq!(current_stock(&pool, product_id).await, 15);
+
+        let err = decide_stock_writeoff_internal(&pool, large.id, clerk, true, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "PERMISSION_DENIED");
+        let approved = decide_stock_writeoff_internal(&pool, large.id, manager, true, None)
+            .await
+            .unwrap();
+        assert_eq!(approved.status, "approved");
+        assert!(approved.applied_at.is_some());
+        assert_eq!(current_stock(&pool, product_id).await, 9);
+
+        let err = decide_stock_writeoff_internal(&pool, large.id, manager, false, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+
+        let err = record_stock_writeoff_internal(&pool, writeoff(product_id, 1, "bogus", clerk))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        let err = record_stock_writeoff_internal(&pool, writeoff(product_id, 10, "damaged", clerk))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "INSUFFICIENT_STOCK");
+    }
+
+    #[tokio::test]
+    async fn test_report_excludes_pending_and_rejected_writeoffs() {
+        let pool = test_pool().await;
+        let tiles = insert_test_product(&pool, "TILE", 10.0, 100).await;
+        let paint = insert_test_product(&pool, "PAINT", 20.0, 100).await;
+        sqlx::query("UPDATE products SET category = 'Flooring' WHERE id = ?1")
+            .bind(tiles)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE products SET category = 'Paint' WHERE id = ?1")
+            .bind(paint)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let clerk = insert_test_user(&pool, "clerk").await;
+        let manager = insert_manager(&pool).await;
+
+        // 4 x 5.00 and 2 x 10.00 applied straight away
+        record_stock_writeoff_internal(&pool, writeoff(tiles, 4, "damaged", clerk)).await.unwrap();
+        record_stock_writeoff_internal(&pool, writeoff(paint, 2, "expired", clerk)).await.unwrap();
+        // 30 x 10.00 pending, and 40 x 10.00 rejected
+        record_stock_writeoff_internal(&pool, writeoff(paint, 30, "damaged", clerk)).await.unwrap();
+        let rejected = record_stock_writeoff_internal(&pool, writeoff(paint, 40, "damaged", clerk))
+            .await
+            .unwrap();
+        decide_stock_writeoff_internal(&pool, rejected.id, manager, false, None)
+            .await
+            .unwrap();
+
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let report = get_writeoff_report_internal(&pool, DEFAULT_ORGANIZATION_ID, "2000-01-01", &today)
+            .await
+            .unwrap();
+        assert_eq!(report.total_quantity, 6);
+        assert_eq!(report.total_cost, Money::from_major(40.0));
+        assert_eq!(report.by_reason.len(), 2);
+        assert_eq!(report.by_reason[0].reason_code, "damaged");
+        assert_eq!(report.by_reason[0].total_cost, Money::from_major(20.0));
+        assert_eq!(report.by_reason[0].writeoff_count, 1);
+        assert_eq!(report.by_category.len(), 2);
+        assert_eq!(report.by_category[0].category, "Flooring");
+        assert_eq!(current_stock(&pool, paint).await, 98);
+
+        let other = get_writeoff_report_internal(&pool, 2, "2000-01-01", &today).await.unwrap();
+        assert!(other.by_reason.is_empty() && other.by_category.is_empty());
+
+        let report = get_writeoff_report_internal(&pool, DEFAULT_ORGANIZATION_ID, "2000-01-01", "2000-12-31")
+            .await
+            .unwrap();
+        assert!(report.by_reason.is_empty());
+        assert_eq!(report.total_cost, Money::ZERO);
+    }
+}
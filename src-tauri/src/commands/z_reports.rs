@@ -0,0 +1,459 @@
+// src-tauri/src/commands/z_reports.rs - End-of-day Z-report for a shift or a trading day
+use crate::commands::cash_drawer::cash_drawer_balance;
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use crate::receipt;
+use crate::tenancy;
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use tauri::{command, State};
+
+/// Tenders of one payment method, net of change given
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PaymentMethodTotal {
+    pub payment_method: String,
+    pub transaction_count: i64,
+    pub amount: Money,
+}
+
+/// One shift's drawer: what it opened with, what should be in it and what was counted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZReportDrawer {
+    pub shift_id: i64,
+    pub user_id: i64,
+    pub status: String,
+    pub opening_amount: Money,
+    pub paid_in: Money,
+    pub paid_out: Money,
+    pub expected_amount: Money,
+    /// Counted at close; none while the shift is open
+    pub closing_amount: Option<Money>,
+    /// Counted less expected; negative when the drawer is short
+    pub variance: Option<Money>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZReport {
+    /// The shift reported on, or the day (`YYYY-MM-DD`) when reporting a whole day
+    pub shift_id: Option<i64>,
+    pub date: Option<String>,
+    pub generated_at: String,
+    /// Totals of completed sales, after their discounts and including tax
+    pub gross_sales: Money,
+    pub returns: Money,
+    pub net_sales: Money,
+    pub tax_collected: Money,
+    /// Line and sale discounts given
+    pub discounts: Money,
+    pub cash_rounding: Money,
+    pub transaction_count: i64,
+    pub payment_methods: Vec<PaymentMethodTotal>,
+    pub void_count: i64,
+    pub void_amount: Money,
+    pub drawers: Vec<ZReportDrawer>,
+    pub opening_amount: Money,
+    pub expected_cash: Money,
+    /// Sum of the counted drawers, none while any of them is still open
+    pub closing_amount: Option<Money>,
+    pub variance: Option<Money>,
+}
+
+/// Rows of `alias` in scope: those of shift `?1`, or else those whose `time` falls between
+/// `?2` and `?3`. Ranging on the raw timestamp lets SQLite use the column's index.
+fn scope_condition(alias: &str, time: &str, shift_column: &str) -> String {
+    format!(
+        "((?1 IS NOT NULL AND {alias}.{shift_column} = ?1)
+          OR (?1 IS NULL AND {alias}.{time} >= ?2 AND {alias}.{time} < ?3))",
+        alias = alias,
+        time = time,
+        shift_column = shift_column
+    )
+}
+
+/// UTC timestamps bounding the local calendar day `date`, since rows are stamped with
+/// `CURRENT_TIMESTAMP` in UTC and a trading day runs from the store's local midnight
+fn local_day_bounds(date: NaiveDate) -> AppResult<(String, String)> {
+    let utc_midnight = |day: NaiveDate| {
+        Local
+            .from_local_datetime(&day.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|midnight| midnight.naive_utc().format("%Y-%m-%d %H:%M:%S").to_string())
+            .ok_or_else(|| AppError::validation("date", "Date has no local midnight"))
+    };
+    let next_day = date
+        .succ_opt()
+        .ok_or_else(|| AppError::validation("date", "Date is out of range"))?;
+    Ok((utc_midnight(date)?, utc_midnight(next_day)?))
+}
+
+pub(crate) async fn get_z_report_internal(
+    pool: &SqlitePool,
+    organization_id: i64,
+    shift_id: Option<i64>,
+    date: Option<&str>,
+) -> AppResult<ZReport> {
+    let shifts_scope = format!(
+        " AND sh.location_id IN (SELECT id FROM locations WHERE organization_id = {})",
+        organization_id
+    );
+    let (date, day_start, day_end) = match (shift_id, date) {
+        (Some(_), Some(_)) | (None, None) => {
+            return Err(AppError::validation(
+                "shift_id",
+                "Give either a shift or a date",
+            ));
+        }
+        (Some(id), None) => {
+            let exists: Option<i64> = sqlx::query_scalar(&format!(
+                "SELECT sh.id FROM shifts sh WHERE sh.id = ?1{}",
+                shifts_scope
+            ))
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+            exists.ok_or_else(|| AppError::not_found("Shift"))?;
+            (None, None, None)
+        }
+        (None, Some(date)) => {
+            let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| AppError::validation("date", "Date must be YYYY-MM-DD"))?;
+            let (start, end) = local_day_bounds(day)?;
+            (Some(date.to_string()), Some(start), Some(end))
+        }
+    };
+    let sales_scope = format!(
+        "{}{}",
+        scope_condition("s", "created_at", "shift_id"),
+        tenancy::organization_scope("s.organization_id", organization_id)
+    );
+
+    let (gross_sales, tax_collected, sale_discounts, cash_rounding, transaction_count): (
+        Money,
+        Money,
+        Money,
+        Money,
+        i64,
+    ) = sqlx::query_as(&format!(
+        "SELECT COALESCE(SUM(s.total_amount), 0.0), COALESCE(SUM(s.tax_amount), 0.0),
+                COALESCE(SUM(s.discount_amount), 0.0), COALESCE(SUM(s.cash_rounding), 0.0),
+                COUNT(*)
+         FROM sales s WHERE s.is_voided = 0 AND s.is_demo = 0 AND {}",
+        sales_scope
+    ))
+    .bind(shift_id)
+    .bind(&day_start)
+    .bind(&day_end)
+    .fetch_one(pool)
+    .await?;
+
+    let line_discounts: Money = sqlx::query_scalar(&format!(
+        "SELECT COALESCE(SUM(si.discount_amount), 0.0)
+         FROM sale_items si JOIN sales s ON s.id = si.sale_id
+         WHERE s.is_voided = 0 AND s.is_demo = 0 AND {}",
+        sales_scope
+    ))
+    .bind(shift_id)
+    .bind(&day_start)
+    .bind(&day_end)
+    .fetch_one(pool)
+    .await?;
+
+    // Tenders as taken, in the store currency; refunds paid back are counted under returns
+    let payment_methods = sqlx::query_as::<_, PaymentMethodTotal>(&format!(
+        "SELECT sp.payment_method, COUNT(DISTINCT sp.sale_id) as transaction_count,
+                COALESCE(SUM(sp.base_amount - sp.change_base), 0.0) as amount
+         FROM sale_payments sp JOIN sales s ON s.id = sp.sale_id
+         WHERE sp.return_id IS NULL AND s.is_voided = 0 AND s.is_demo = 0 AND {}
+         GROUP BY sp.payment_method
+         ORDER BY sp.payment_method",
+        sales_scope
+    ))
+    .bind(shift_id)
+    .bind(&day_start)
+    .bind(&day_end)
+    .fetch_all(pool)
+    .await?;
+
+    let (void_count, void_amount): (i64, Money) = sqlx::query_as(&format!(
+        "SELECT COUNT(*), COALESCE(SUM(s.total_amount), 0.0)
+         FROM sales s WHERE s.is_voided = 1 AND s.is_demo = 0 AND {}",
+        sales_scope
+    ))
+    .bind(shift_id)
+    .bind(&day_start)
+    .bind(&day_end)
+    .fetch_one(pool)
+    .await?;
+
+    // Sales returns from both the returns workflow and the legacy returns table
+    let (workflow_returns, legacy_returns): (Money, Money) = sqlx::query_as(&format!(
+        "SELECT (SELECT COALESCE(SUM(cr.total_amount), 0.0) FROM comprehensive_returns cr
+                 WHERE cr.return_type = 'SalesReturn' AND cr.status != 'Rejected' AND {}{}),
+                (SELECT COALESCE(SUM(r.total_amount), 0.0) FROM returns r
+                 WHERE {} AND r.original_sale_id IN (
+                     SELECT id FROM sales WHERE organization_id = {}))",
+        scope_condition("cr", "created_at", "shift_id"),
+        tenancy::organization_scope("cr.organization_id", organization_id),
+        scope_condition("r", "created_at", "shift_id"),
+        organization_id
+    ))
+    .bind(shift_id)
+    .bind(&day_start)
+    .bind(&day_end)
+    .fetch_one(pool)
+    .await?;
+    let returns = workflow_returns + legacy_returns;
+
+    let shifts: Vec<(i64, i64, String, Money, Option<Money>, Money, Money)> =
+        sqlx::query_as(&format!(
+            "SELECT sh.id, sh.user_id, sh.status, sh.opening_amount, sh.closing_amount,
+                (SELECT COALESCE(SUM(c.amount), 0.0) FROM cash_drawer_transactions c
+                 WHERE c.shift_id = sh.id AND c.transaction_type = 'deposit'),
+                (SELECT COALESCE(SUM(c.amount), 0.0) FROM cash_drawer_transactions c
+                 WHERE c.shift_id = sh.id AND c.transaction_type = 'withdrawal')
+         FROM shifts sh WHERE {}{}
+         ORDER BY sh.id",
+            scope_condition("sh", "start_time", "id"),
+            shifts_scope
+        ))
+        .bind(shift_id)
+        .bind(&day_start)
+        .bind(&day_end)
+        .fetch_all(pool)
+        .await?;
+    let mut drawers = Vec::with_capacity(shifts.len());
+    for (id, user_id, status, opening_amount, closing_amount, paid_in, paid_out) in shifts {
+        let expected_amount = cash_drawer_balance(pool, id).await?;
+        drawers.push(ZReportDrawer {
+            shift_id: id,
+            user_id,
+            status,
+            opening_amount,
+            paid_in,
+            paid_out,
+            expected_amount,
+            closing_amount,
+            variance: closing_amount.map(|counted| counted - expected_amount),
+        });
+    }
+
+    let expected_cash = drawers.iter().map(|drawer| drawer.expected_amount).sum();
+    let closing_amount = drawers
+        .iter()
+        .map(|drawer| drawer.closing_amount)
+        .sum::<Option<Money>>()
+        .filter(|_| !drawers.is_empty());
+    Ok(ZReport {
+        shift_id,
+        date,
+        generated_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        gross_sales,
+        returns,
+        net_sales: gross_sales - returns,
+        tax_collected,
+        discounts: sale_discounts + line_discounts,
+        cash_rounding,
+        transaction_count,
+        payment_methods,
+        void_count,
+        void_amount,
+        opening_amount: drawers.iter().map(|drawer| drawer.opening_amount).sum(),
+        expected_cash,
+        closing_amount,
+        variance: closing_amount.map(|counted| counted - expected_cash),
+        drawers,
+    })
+}
+
+/// Z-report for a shift, or for every shift and sale of a day (`YYYY-MM-DD`)
+#[command]
+pub async fn get_z_report(
+    pool: State<'_, SqlitePool>,
+    shift_id: Option<i64>,
+    date: Option<String>,
+    session_token: String,
+) -> Result<ZReport, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    get_z_report_internal(pool.inner(), organization_id, shift_id, date.as_deref()).await
+}
+
+/// Z-report laid out for the receipt printer
+#[command]
+pub async fn render_z_report(
+    pool: State<'_, SqlitePool>,
+    shift_id: Option<i64>,
+    date: Option<String>,
+    session_token: String,
+) -> Result<String, AppError> {
+    let organization_id = tenancy::active_organization(&session_token)?;
+    let report =
+        get_z_report_internal(pool.inner(), organization_id, shift_id, date.as_deref()).await?;
+    receipt::render_z_report(pool.inner(), &report).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::sales::create_sale_internal;
+    use crate::models::{CreateSaleRequest, SaleItemRequest};
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    fn sale(
+        product_id: i64,
+        quantity: i32,
+        discount: f64,
+        payment_method: &str,
+    ) -> CreateSaleRequest {
+        let discount_amount = Money::from_major(discount);
+        let line_total = Money::from_major(10.0).times(quantity) - discount_amount;
+        CreateSaleRequest {
+            items: vec![SaleItemRequest {
+                product_id,
+                quantity,
+                unit_price: Money::from_major(10.0),
+                discount_amount,
+                line_total,
+                measured_quantity: None,
+                price_override: None,
+                override_reason: None,
+            }],
+            subtotal: line_total,
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: line_total,
+            payment_method: payment_method.to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_z_report_summarizes_a_shift() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 50).await;
+        let shift_id = sqlx::query(
+            "INSERT INTO shifts (user_id, start_time, opening_amount, status)
+             VALUES (?1, CURRENT_TIMESTAMP, 100, 'open')",
+        )
+        .bind(cashier)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+
+        let mut sales = Vec::new();
+        for (quantity, discount, method) in [
+            (3, 2.0, "Cash"),
+            (2, 0.0, "Card"),
+            (1, 0.0, "Cash"),
+            (4, 0.0, "Cash"),
+        ] {
+            let request = sale(widget, quantity, discount, method);
+            sales.push(
+                create_sale_internal(&pool, request, cashier, Some(shift_id))
+                    .await
+                    .unwrap(),
+            );
+        }
+        let voided = &sales[3];
+        sqlx::query("UPDATE sales SET is_voided = 1 WHERE id = ?1")
+            .bind(voided.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO returns (return_number, original_sale_id, subtotal, total_amount,
+                                  refund_method, processed_by, shift_id)
+             VALUES ('RET-1', ?1, 10, 10, 'Cash', ?2, ?3)",
+        )
+        .bind(sales[2].id)
+        .bind(cashier)
+        .bind(shift_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO cash_drawer_transactions (shift_id, transaction_type, amount, reason, user_id)
+             VALUES (?1, 'withdrawal', 20, 'Bank run', ?2)",
+        )
+        .bind(shift_id)
+        .bind(cashier)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let err = get_z_report_internal(&pool, DEFAULT_ORGANIZATION_ID, None, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        // Another organization sees neither the shift nor its sales
+        let err = get_z_report_internal(&pool, 2, Some(shift_id), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+
+        let report = get_z_report_internal(&pool, DEFAULT_ORGANIZATION_ID, Some(shift_id), None)
+            .await
+            .unwrap();
+        assert_eq!(report.gross_sales, Money::from_major(58.0));
+        assert_eq!(report.returns, Money::from_major(10.0));
+        assert_eq!(report.net_sales, Money::from_major(48.0));
+        assert_eq!(report.discounts, Money::from_major(2.0));
+        assert_eq!(report.transaction_count, 3);
+        let methods: Vec<(&str, i64, Money)> = report
+            .payment_methods
+            .iter()
+            .map(|m| (m.payment_method.as_str(), m.transaction_count, m.amount))
+            .collect();
+        assert_eq!(
+            methods,
+            vec![
+                ("Card", 1, Money::from_major(20.0)),
+                ("Cash", 2, Money::from_major(38.0))
+            ]
+        );
+        assert_eq!(
+            (report.void_count, report.void_amount),
+            (1, Money::from_major(40.0))
+        );
+        // 100 float + 38 cash sales - 20 paid out - 10 refunded
+        assert_eq!(report.expected_cash, Money::from_major(108.0));
+        assert_eq!(report.closing_amount, None);
+
+        sqlx::query("UPDATE shifts SET status = 'closed', closing_amount = 105 WHERE id = ?1")
+            .bind(shift_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let other = get_z_report_internal(&pool, 2, None, Some(&today))
+            .await
+            .unwrap();
+        assert_eq!(other.gross_sales, Money::ZERO);
+        assert!(other.drawers.is_empty());
+        let report = get_z_report_internal(&pool, DEFAULT_ORGANIZATION_ID, None, Some(&today))
+            .await
+            .unwrap();
+        assert_eq!(report.gross_sales, Money::from_major(58.0));
+        assert_eq!(report.drawers.len(), 1);
+        assert_eq!(report.drawers[0].paid_out, Money::from_major(20.0));
+        assert_eq!(report.variance, Some(Money::from_major(-3.0)));
+
+        let text = receipt::render_z_report(&pool, &report).await.unwrap();
+        assert!(text.contains("Z REPORT"), "{}", text);
+        assert!(text.contains("Net sales"), "{}", text);
+    }
+}
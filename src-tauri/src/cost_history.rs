@@ -0,0 +1,409 @@
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{FromRow, SqlitePool};
+
+/// Cost of a sale line for profit figures, for queries aliasing `sale_items si` and `sales s`.
+/// Lines recorded without a cost fall back to the product's cost history as of the sale.
+pub const SALE_ITEM_COST_SQL: &str = "COALESCE(NULLIF(si.cost_price, 0), (
+        SELECT h.new_cost FROM product_cost_history h
+        WHERE h.product_id = si.product_id AND h.changed_at <= s.created_at
+        ORDER BY h.changed_at DESC, h.id DESC LIMIT 1), 0)";
+
+/// Changes smaller than this are rounding noise rather than a new cost
+const COST_EPSILON: f64 = 0.005;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostSource {
+    Initial,
+    Manual,
+    Receipt,
+    PurchaseOrder,
+    Recalculation,
+}
+
+impl CostSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostSource::Initial => "initial",
+            CostSource::Manual => "manual",
+            CostSource::Receipt => "receipt",
+            CostSource::PurchaseOrder => "purchase_order",
+            CostSource::Recalculation => "recalculation",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CostHistoryEntry {
+    pub id: i64,
+    pub product_id: i64,
+    pub old_cost: Option<f64>,
+    pub new_cost: f64,
+    pub source: String,
+    /// Purchase order for `purchase_order` changes
+    pub reference_id: Option<i64>,
+    pub changed_by: Option<i64>,
+    pub changed_at: String,
+}
+
+async fn insert_entry(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    old_cost: Option<f64>,
+    new_cost: f64,
+    source: CostSource,
+    reference_id: Option<i64>,
+    changed_by: Option<i64>,
+) -> AppResult<()> {
+    sqlx::query(
+        "INSERT INTO product_cost_history
+            (product_id, old_cost, new_cost, source, reference_id, changed_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )
+    .bind(product_id)
+    .bind(old_cost)
+    .bind(new_cost)
+    .bind(source.as_str())
+    .bind(reference_id)
+    .bind(changed_by)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Start the history of a newly created product
+pub async fn record_initial_cost(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    cost: f64,
+    changed_by: Option<i64>,
+) -> AppResult<()> {
+    insert_entry(conn, product_id, None, cost, CostSource::Initial, None, changed_by).await
+}
+
+/// Set a product's cost price, recording the change in its history.
+/// Returns false when the cost is unchanged and nothing was written.
+pub async fn set_cost_price(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    new_cost: f64,
+    source: CostSource,
+    reference_id: Option<i64>,
+    changed_by: Option<i64>,
+) -> AppResult<bool> {
+    let old_cost: Option<f64> = sqlx::query_scalar("SELECT cost_price FROM products WHERE id = ?1")
+        .bind(product_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Product"))?;
+
+    if old_cost.is_some_and(|old| (old - new_cost).abs() < COST_EPSILON) {
+        return Ok(false);
+    }
+
+    sqlx::query("UPDATE products SET cost_price = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2")
+        .bind(new_cost)
+        .bind(product_id)
+        .execute(&mut *conn)
+        .await?;
+    insert_entry(conn, product_id, old_cost, new_cost, source, reference_id, changed_by).await?;
+    Ok(true)
+}
+
+/// Average unit cost after receiving `quantity` at `unit_cost` on top of `on_hand` units
+/// costed at `average`, to the cent. Stock that is out or oversold carries no weight, so the
+/// receipt sets the cost outright.
+pub fn moving_average(on_hand: i64, average: f64, quantity: i64, unit_cost: f64) -> f64 {
+    if quantity <= 0 {
+        return average;
+    }
+    if on_hand <= 0 {
+        return Money::from_major(unit_cost).to_major();
+    }
+    let total = on_hand as f64 * average + quantity as f64 * unit_cost;
+    Money::from_major(total / (on_hand + quantity) as f64).to_major()
+}
+
+/// Fold stock received at `unit_cost` into the product's moving-average cost and return
+/// the new average. Call it in the receiving transaction before the stock is added, so the
+/// units on hand are the ones the old average covers. Positive adjustments come in at the
+/// average and leave it unchanged, so they need no call.
+pub async fn receive_at_cost(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    quantity: i32,
+    unit_cost: f64,
+    source: CostSource,
+    reference_id: Option<i64>,
+    changed_by: Option<i64>,
+) -> AppResult<f64> {
+    let (average, on_hand): (Option<f64>, i64) = sqlx::query_as(
+        "SELECT p.cost_price, COALESCE(i.current_stock, 0)
+         FROM products p LEFT JOIN inventory i ON i.product_id = p.id
+         WHERE p.id = ?1",
+    )
+    .bind(product_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::not_found("Product"))?;
+
+    let new_cost = moving_average(on_hand, average.unwrap_or(0.0), i64::from(quantity), unit_cost);
+    set_cost_price(conn, product_id, new_cost, source, reference_id, changed_by).await?;
+    Ok(new_cost)
+}
+
+/// A product whose average cost was corrected by `recalculate_average_costs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecalculatedCost {
+    pub product_id: i64,
+    pub old_cost: Option<f64>,
+    pub new_cost: f64,
+}
+
+/// Rebuild the average cost of every product of the organization that has received stock at
+/// a known cost by replaying its movements oldest first. Stock on hand before the first
+/// movement is taken at the product's initial cost; reservations hold stock without moving
+/// it and are skipped.
+/// Returns the products whose cost changed.
+pub async fn recalculate_average_costs(
+    pool: &SqlitePool,
+    organization_id: i64,
+    changed_by: Option<i64>,
+) -> AppResult<Vec<RecalculatedCost>> {
+    let mut tx = pool.begin().await?;
+    let product_ids: Vec<i64> = sqlx::query_scalar(
+        "SELECT DISTINCT m.product_id FROM inventory_movements m
+         JOIN products p ON p.id = m.product_id
+         WHERE m.unit_cost IS NOT NULL AND p.organization_id = ?1
+         ORDER BY m.product_id",
+    )
+    .bind(organization_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut recalculated = Vec::new();
+    for product_id in product_ids {
+        let (old_cost, current_stock): (Option<f64>, i64) = sqlx::query_as(
+            "SELECT p.cost_price, COALESCE(i.current_stock, 0)
+             FROM products p LEFT JOIN inventory i ON i.product_id = p.id
+             WHERE p.id = ?1",
+        )
+        .bind(product_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let initial_cost: Option<f64> = sqlx::query_scalar(
+            "SELECT new_cost FROM product_cost_history
+             WHERE product_id = ?1 AND source = 'initial'
+             ORDER BY changed_at, id LIMIT 1",
+        )
+        .bind(product_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let movements: Vec<(i64, Option<f64>)> = sqlx::query_as(
+            "SELECT quantity_change, unit_cost FROM inventory_movements
+             WHERE product_id = ?1 AND movement_type != 'reservation'
+             ORDER BY created_at, id",
+        )
+        .bind(product_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let moved: i64 = movements.iter().map(|(change, _)| change).sum();
+        let mut on_hand = current_stock - moved;
+        let mut average = initial_cost.or(old_cost).unwrap_or(0.0);
+        for (change, unit_cost) in movements {
+            if let Some(unit_cost) = unit_cost {
+                average = moving_average(on_hand, average, change, unit_cost);
+            }
+            on_hand += change;
+        }
+
+        if set_cost_price(&mut tx, product_id, average, CostSource::Recalculation, None, changed_by).await? {
+            recalculated.push(RecalculatedCost {
+                product_id,
+                old_cost,
+                new_cost: average,
+            });
+        }
+    }
+    tx.commit().await?;
+    Ok(recalculated)
+}
+
+/// Cost changes for a product, newest first
+pub async fn get_cost_history(pool: &SqlitePool, product_id: i64) -> AppResult<Vec<CostHistoryEntry>> {
+    let entries = sqlx::query_as::<_, CostHistoryEntry>(
+        "SELECT id, product_id, old_cost, new_cost, source, reference_id, changed_by,
+                COALESCE(changed_at, '') as changed_at
+         FROM product_cost_history
+         WHERE product_id = ?1
+         ORDER BY changed_at DESC, id DESC",
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_product, test_pool};
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+
+    #[tokio::test]
+    async fn test_cost_changes_are_recorded() {
+        let pool = test_pool().await;
+        let product_id = insert_test_product(&pool, "NAILS", 10.0, 0).await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        assert!(!set_cost_price(&mut conn, product_id, 5.0, CostSource::Manual, None, None)
+            .await
+            .unwrap());
+        assert!(set_cost_price(&mut conn, product_id, 6.0, CostSource::Receipt, None, None)
+            .await
+            .unwrap());
+        assert!(set_cost_price(&mut conn, product_id, 6.5, CostSource::PurchaseOrder, Some(3), None)
+            .await
+            .unwrap());
+        let err = set_cost_price(&mut conn, 9999, 1.0, CostSource::Manual, None, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+        drop(conn);
+
+        let history = get_cost_history(&pool, product_id).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].source, "purchase_order");
+        assert_eq!(history[0].old_cost, Some(6.0));
+        assert_eq!(history[0].new_cost, 6.5);
+        assert_eq!(history[0].reference_id, Some(3));
+        assert_eq!(history[1].old_cost, Some(5.0));
+
+        let cost: f64 = sqlx::query_scalar("SELECT cost_price FROM products WHERE id = ?1")
+            .bind(product_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(cost, 6.5);
+    }
+
+    #[test]
+    fn test_moving_average_weights_stock_on_hand() {
+        assert_eq!(moving_average(0, 5.0, 10, 5.0), 5.0);
+        assert_eq!(moving_average(10, 5.0, 10, 7.0), 6.0);
+        assert_eq!(moving_average(3, 1.0, 0, 9.0), 1.0);
+        // Oversold stock carries no weight
+        assert_eq!(moving_average(-4, 5.0, 10, 8.0), 8.0);
+        assert_eq!(moving_average(2, 1.0, 1, 2.0), 1.33);
+    }
+
+    #[tokio::test]
+    async fn test_recalculation_replays_costed_receipts() {
+        let pool = test_pool().await;
+        let product_id = insert_test_product(&pool, "NAILS", 10.0, 20).await;
+        let untouched = insert_test_product(&pool, "SCREWS", 10.0, 3).await;
+        for (kind, change, cost, at) in [
+            ("receipt", 10, Some(5.0), "2024-01-01 09:00:00"),
+            ("receipt", 10, Some(7.0), "2024-01-02 09:00:00"),
+            ("sale", -5, None, "2024-01-03 09:00:00"),
+            ("reservation", 2, None, "2024-01-03 10:00:00"),
+            ("receipt", 5, Some(9.0), "2024-01-04 09:00:00"),
+        ] {
+            sqlx::query(
+                "INSERT INTO inventory_movements
+                    (product_id, movement_type, quantity_change, previous_stock, new_stock, unit_cost, created_at)
+                 VALUES (?1, ?2, ?3, 0, 0, ?4, ?5)",
+            )
+            .bind(product_id)
+            .bind(kind)
+            .bind(change)
+            .bind(cost)
+            .bind(at)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        sqlx::query("UPDATE products SET cost_price = 9.0")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let recalculated = recalculate_average_costs(&pool, DEFAULT_ORGANIZATION_ID, None).await.unwrap();
+        assert_eq!(recalculated.len(), 1);
+        assert_eq!(recalculated[0].product_id, product_id);
+        assert_eq!(recalculated[0].old_cost, Some(9.0));
+        // 15 left at 6.00, then 5 more at 9.00
+        assert_eq!(recalculated[0].new_cost, 6.75);
+
+        let history = get_cost_history(&pool, product_id).await.unwrap();
+        assert_eq!(history[0].source, "recalculation");
+        let cost: f64 = sqlx::query_scalar("SELECT cost_price FROM products WHERE id = ?1")
+            .bind(untouched)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(cost, 9.0);
+
+        assert!(recalculate_average_costs(&pool, DEFAULT_ORGANIZATION_ID, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_uncosted_sale_lines_use_cost_of_the_day() {
+        let pool = test_pool().await;
+        let product_id = insert_test_product(&pool, "CEMENT", 10.0, 0).await;
+        sqlx::query(
+            "INSERT INTO product_cost_history (product_id, old_cost, new_cost, source, changed_at)
+             VALUES (?1, NULL, 4.0, 'initial', '2024-01-01 00:00:00'),
+                    (?1, 4.0, 7.0, 'receipt', '2024-03-01 00:00:00')",
+        )
+        .bind(product_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        for (number, cost, date) in [
+            ("S-1", 0.0, "2024-02-01 10:00:00"),
+            ("S-2", 0.0, "2024-04-01 10:00:00"),
+            ("S-3", 5.5, "2024-02-01 10:00:00"),
+        ] {
+            let sale_id = sqlx::query(
+                "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id, created_at)
+                 VALUES (?1, 10, 10, 'cash', 1, ?2)",
+            )
+            .bind(number)
+            .bind(date)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+            sqlx::query(
+                "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, line_total, cost_price)
+                 VALUES (?1, ?2, 1, 10, 10, ?3)",
+            )
+            .bind(sale_id)
+            .bind(product_id)
+            .bind(cost)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let query = format!(
+            "SELECT s.sale_number, {} FROM sale_items si JOIN sales s ON si.sale_id = s.id ORDER BY s.id",
+            SALE_ITEM_COST_SQL
+        );
+        let costs: Vec<(String, f64)> = sqlx::query_as(&query).fetch_all(&pool).await.unwrap();
+        assert_eq!(
+            costs,
+            vec![
+                ("S-1".to_string(), 4.0),
+                ("S-2".to_string(), 7.0),
+                ("S-3".to_string(), 5.5)
+            ]
+        );
+    }
+}
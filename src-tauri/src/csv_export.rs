@@ -0,0 +1,38 @@
+/// Quote a field when it contains a comma, quote or line break, doubling inner quotes
+pub fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Render a header row and data rows as CSV text, one line per row
+pub fn to_csv(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut csv = String::new();
+    let header: Vec<String> = headers.iter().map(|h| escape_field(h)).collect();
+    csv.push_str(&header.join(","));
+    csv.push('\n');
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|f| escape_field(f)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_are_quoted_only_when_needed() {
+        assert_eq!(escape_field("Acme"), "Acme");
+        assert_eq!(escape_field("Nuts, Bolts"), "\"Nuts, Bolts\"");
+        assert_eq!(escape_field("2\" pipe"), "\"2\"\" pipe\"");
+        assert_eq!(
+            to_csv(&["a", "b"], &[vec!["1".to_string(), "x\ny".to_string()]]),
+            "a,b\n1,\"x\ny\"\n"
+        );
+    }
+}
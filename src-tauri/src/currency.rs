@@ -0,0 +1,356 @@
+use crate::error::{AppError, AppResult};
+use crate::money::{self, Money, STORED_DECIMALS};
+use crate::organization_settings;
+use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{FromRow, SqlitePool};
+
+/// Display and rounding rules for a currency, seeded in the `currencies` table
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Currency {
+    pub code: String,
+    pub name: String,
+    pub symbol: String,
+    pub decimal_places: u32,
+    pub thousands_separator: String,
+    pub decimal_separator: String,
+    /// "before" (`$1.00`) or "after" (`1 000 FCFA`)
+    pub symbol_position: String,
+}
+
+impl Currency {
+    /// Fallback when the store currency is missing from the table
+    pub fn usd() -> Self {
+        Currency {
+            code: "USD".to_string(),
+            name: "US Dollar".to_string(),
+            symbol: "$".to_string(),
+            decimal_places: 2,
+            thousands_separator: ",".to_string(),
+            decimal_separator: ".".to_string(),
+            symbol_position: "before".to_string(),
+        }
+    }
+
+    /// Minor units per major unit, from the currency's decimal places (1 for XAF, JPY)
+    pub fn minor_units(&self) -> i64 {
+        money::minor_units(self.decimal_places)
+    }
+
+    /// Round an amount to this currency's smallest unit (whole units for XAF, JPY)
+    pub fn round(&self, amount: Money) -> Money {
+        amount.round_to_decimals(self.decimal_places)
+    }
+
+    /// Tax at `rate` percent, rounded half-up to the currency's smallest unit
+    pub fn tax(&self, amount: Money, rate: f64) -> Money {
+        self.round(amount.percent(rate))
+    }
+
+    /// Format an amount for receipts and exports, e.g. `$1,234.50` or `1 235 FCFA`
+    pub fn format(&self, amount: Money) -> String {
+        let rounded = self.round(amount);
+        // Amounts are stored to STORED_DECIMALS places; count in the currency's own minor units
+        let places = self.decimal_places.min(STORED_DECIMALS);
+        let minor =
+            rounded.minor().unsigned_abs() / money::minor_units(STORED_DECIMALS - places) as u64;
+        let per_major = money::minor_units(places) as u64;
+        let digits: Vec<char> = (minor / per_major).to_string().chars().collect();
+        let groups: Vec<String> = digits
+            .rchunks(3)
+            .rev()
+            .map(|group| group.iter().collect())
+            .collect();
+
+        let mut number = groups.join(&self.thousands_separator);
+        if places > 0 {
+            number.push_str(&self.decimal_separator);
+            number.push_str(&format!("{:0width$}", minor % per_major, width = places as usize));
+        }
+
+        let sign = if rounded.is_negative() { "-" } else { "" };
+        if self.symbol_position == "after" {
+            format!("{}{} {}", sign, number, self.symbol)
+        } else {
+            format!("{}{}{}", sign, self.symbol, number)
+        }
+    }
+}
+
+pub async fn get_currencies(pool: &SqlitePool) -> AppResult<Vec<Currency>> {
+    let currencies = sqlx::query_as::<_, Currency>(
+        "SELECT code, name, symbol, decimal_places, thousands_separator, decimal_separator,
+                symbol_position
+         FROM currencies
+         ORDER BY code",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(currencies)
+}
+
+pub async fn get_currency(pool: &SqlitePool, code: &str) -> AppResult<Option<Currency>> {
+    let currency = sqlx::query_as::<_, Currency>(
+        "SELECT code, name, symbol, decimal_places, thousands_separator, decimal_separator,
+                symbol_position
+         FROM currencies
+         WHERE code = ?1",
+    )
+    .bind(code.trim().to_uppercase())
+    .fetch_optional(pool)
+    .await?;
+    Ok(currency)
+}
+
+/// Reject currency codes that are not in the `currencies` table
+pub async fn validate_currency_code(pool: &SqlitePool, code: &str) -> AppResult<()> {
+    match get_currency(pool, code).await? {
+        Some(_) => Ok(()),
+        None => Err(AppError::validation(
+            "currency",
+            &format!("Unsupported currency code: {}", code),
+        )),
+    }
+}
+
+/// The currency in the organization's settings, falling back to USD
+pub async fn organization_currency(pool: &SqlitePool, organization_id: i64) -> AppResult<Currency> {
+    let mut conn = pool.acquire().await?;
+    let code = organization_settings::load(&mut conn, organization_id)
+        .await?
+        .currency;
+    drop(conn);
+    Ok(get_currency(pool, &code)
+        .await?
+        .unwrap_or_else(Currency::usd))
+}
+
+/// The store's currency: that of the organization created with the database
+pub async fn store_currency(pool: &SqlitePool) -> AppResult<Currency> {
+    organization_currency(pool, DEFAULT_ORGANIZATION_ID).await
+}
+
+/// Format an amount in the store currency
+pub async fn format_money(pool: &SqlitePool, amount: Money) -> AppResult<String> {
+    Ok(store_currency(pool).await?.format(amount))
+}
+
+/// A foreign-currency tender converted into the store currency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeDue {
+    pub tender_currency: String,
+    /// Amount handed over, in the tender currency
+    pub tender_amount: Money,
+    /// Store-currency units per unit of the tender currency
+    pub exchange_rate: f64,
+    /// The tender in the store currency
+    pub tendered_base: Money,
+    pub amount_due: Money,
+    /// Change in the store currency; negative while the tender falls short
+    pub change_base: Money,
+    /// The same change in the tender currency
+    pub change_tender: Money,
+}
+
+/// Store-currency units one unit of `code` buys: the latest rate effective on or before
+/// `on_date`. Rates dated in the future are never used.
+pub async fn exchange_rate(
+    conn: &mut SqliteConnection,
+    base: &Currency,
+    code: &str,
+    on_date: &str,
+) -> AppResult<f64> {
+    let code = code.trim().to_uppercase();
+    if code == base.code {
+        return Ok(1.0);
+    }
+    let rate: Option<f64> = sqlx::query_scalar(
+        "SELECT rate_to_base FROM exchange_rates
+         WHERE currency = ?1 AND effective_date <= ?2
+         ORDER BY effective_date DESC
+         LIMIT 1",
+    )
+    .bind(&code)
+    .bind(on_date)
+    .fetch_optional(&mut *conn)
+    .await?;
+    rate.ok_or_else(|| {
+        AppError::validation(
+            "tender_currency",
+            &format!("No exchange rate from {} to {} in effect on {}", code, base.code, on_date),
+        )
+    })
+}
+
+/// Convert a tender into the store currency at `rate`, rounded to the store currency
+pub fn to_base(base: &Currency, amount: Money, rate: f64) -> Money {
+    base.round(amount.times_decimal(rate))
+}
+
+/// Convert a store-currency amount back into the tender currency at `rate`
+pub fn from_base(tender: &Currency, amount: Money, rate: f64) -> Money {
+    tender.round(amount.times_decimal(1.0 / rate))
+}
+
+/// Convert a tender at the rate in effect on `on_date` and work out the change on `amount_due`
+pub async fn change_due(
+    conn: &mut SqliteConnection,
+    base: &Currency,
+    tender_currency: &str,
+    tender_amount: Money,
+    amount_due: Money,
+    on_date: &str,
+) -> AppResult<ChangeDue> {
+    let tender = sqlx::query_as::<_, Currency>(
+        "SELECT code, name, symbol, decimal_places, thousands_separator, decimal_separator,
+                symbol_position
+         FROM currencies
+         WHERE code = ?1",
+    )
+    .bind(tender_currency.trim().to_uppercase())
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| {
+        AppError::validation(
+            "tender_currency",
+            &format!("Unsupported currency code: {}", tender_currency),
+        )
+    })?;
+    let rate = exchange_rate(conn, base, &tender.code, on_date).await?;
+
+    let tendered_base = to_base(base, tender_amount, rate);
+    let change_base = tendered_base - amount_due;
+    Ok(ChangeDue {
+        change_tender: from_base(&tender, change_base, rate),
+        tender_currency: tender.code,
+        tender_amount,
+        exchange_rate: rate,
+        tendered_base,
+        amount_due,
+        change_base,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_pool;
+
+    async fn currency(pool: &SqlitePool, code: &str) -> Currency {
+        get_currency(pool, code).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_formats_seeded_currencies() {
+        let pool = test_pool().await;
+        let amount = Money::from_major(1234567.891);
+
+        assert_eq!(currency(&pool, "USD").await.format(amount), "$1,234,567.89");
+        assert_eq!(
+            currency(&pool, "EUR").await.format(amount),
+            "1.234.567,89 €"
+        );
+        assert_eq!(
+            currency(&pool, "XAF").await.format(amount),
+            "1 234 568 FCFA"
+        );
+        assert_eq!(
+            currency(&pool, "usd").await.format(Money::from_major(-5.5)),
+            "-$5.50"
+        );
+        assert_eq!(
+            currency(&pool, "XAF")
+                .await
+                .format(Money::from_major(999.0)),
+            "999 FCFA"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zero_decimal_tax_rounds_to_whole_units() {
+        let pool = test_pool().await;
+        let xaf = currency(&pool, "XAF").await;
+
+        // 1 999 at 19.25% is 384.8075
+        let tax = xaf.tax(Money::from_major(1999.0), 19.25);
+        assert_eq!(tax, Money::from_major(385.0));
+        assert_eq!(
+            xaf.tax(Money::from_major(1000.0), 0.05),
+            Money::from_major(1.0)
+        );
+
+        let usd = currency(&pool, "USD").await;
+        assert_eq!((xaf.minor_units(), usd.minor_units()), (1, 100));
+        assert_eq!(
+            usd.tax(Money::from_major(1999.0), 19.25),
+            Money::from_major(384.81)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tenders_convert_at_the_rate_in_effect() {
+        let pool = test_pool().await;
+        let usd = currency(&pool, "USD").await;
+        sqlx::query(
+            "INSERT INTO exchange_rates (currency, rate_to_base, effective_date) VALUES
+                ('EUR', 1.05, '2026-01-01'),
+                ('EUR', 1.0857, '2026-06-01'),
+                ('EUR', 2.0, '2999-01-01'),
+                ('XAF', 0.0016, '2026-01-01')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+
+        // 10.00 EUR at 1.0857 is 10.857 USD; 0.36 change is 0.3316 EUR
+        let eur = change_due(
+            &mut conn,
+            &usd,
+            "eur",
+            Money::from_minor(1000),
+            Money::from_minor(1050),
+            "2026-07-15",
+        )
+        .await
+        .unwrap();
+        assert_eq!(eur.exchange_rate, 1.0857);
+        assert_eq!(eur.tendered_base, Money::from_minor(1086));
+        assert_eq!(eur.change_base, Money::from_minor(36));
+        assert_eq!(eur.change_tender, Money::from_minor(33));
+
+        // The older rate applies before the newer one takes effect, the future one never
+        assert_eq!(exchange_rate(&mut conn, &usd, "EUR", "2026-03-01").await.unwrap(), 1.05);
+        assert_eq!(exchange_rate(&mut conn, &usd, "EUR", "2027-01-01").await.unwrap(), 1.0857);
+
+        // Change in a whole-unit currency is rounded to whole francs: 3.40 USD is 2 125 XAF
+        let xaf = change_due(
+            &mut conn,
+            &usd,
+            "XAF",
+            Money::from_major(10000.0),
+            Money::from_minor(1260),
+            "2026-07-15",
+        )
+        .await
+        .unwrap();
+        assert_eq!(xaf.tendered_base, Money::from_minor(1600));
+        assert_eq!(xaf.change_tender, Money::from_major(2125.0));
+
+        assert_eq!(exchange_rate(&mut conn, &usd, "USD", "2026-07-15").await.unwrap(), 1.0);
+        let err = exchange_rate(&mut conn, &usd, "GBP", "2026-07-15").await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        let err = exchange_rate(&mut conn, &usd, "XAF", "2025-12-31").await.unwrap_err();
+        assert!(err.message().contains("No exchange rate from XAF to USD"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_currency_is_rejected() {
+        let pool = test_pool().await;
+        assert!(validate_currency_code(&pool, "XAF").await.is_ok());
+
+        let err = validate_currency_code(&pool, "ABC").await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+}
@@ -1,3 +1,8 @@
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
+use sqlx::{Executor, SqlitePool};
+use std::str::FromStr;
+use std::time::Duration;
 use tauri_plugin_sql::{Migration, MigrationKind};
 
 pub fn get_migrations() -> Vec<Migration> {
@@ -1376,5 +1381,1815 @@ pub fn get_migrations() -> Vec<Migration> {
             "#,
             kind: MigrationKind::Up,
         },
+        Migration {
+            version: 27,
+            description: "create_login_attempts_table",
+            sql: r#"
+                -- Failed login tracking for account lockout (keyed by the login identifier as typed,
+                -- so unknown usernames are throttled exactly like real ones)
+                CREATE TABLE IF NOT EXISTS login_attempts (
+                    username TEXT PRIMARY KEY,
+                    failed_count INTEGER NOT NULL DEFAULT 0,
+                    lockout_count INTEGER NOT NULL DEFAULT 0,
+                    last_failed_at DATETIME,
+                    locked_until DATETIME
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 28,
+            description: "create_user_pins_tables",
+            sql: r#"
+                -- Optional register PIN per user (bcrypt hash) for fast user switching
+                CREATE TABLE IF NOT EXISTS user_pins (
+                    user_id INTEGER PRIMARY KEY,
+                    pin_hash TEXT NOT NULL,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+                );
+
+                -- Previous PIN hashes, used to block reuse of recent PINs
+                CREATE TABLE IF NOT EXISTS user_pin_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_id INTEGER NOT NULL,
+                    pin_hash TEXT NOT NULL,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_user_pin_history_user ON user_pin_history(user_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 29,
+            description: "create_password_reset_codes_table",
+            sql: r#"
+                -- Admin-issued single-use password reset codes (bcrypt hash only)
+                CREATE TABLE IF NOT EXISTS password_reset_codes (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_id INTEGER NOT NULL,
+                    code_hash TEXT NOT NULL,
+                    expires_at DATETIME NOT NULL,
+                    used_at DATETIME,
+                    created_by INTEGER,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+                    FOREIGN KEY (created_by) REFERENCES users(id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_password_reset_codes_user ON password_reset_codes(user_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 30,
+            description: "add_is_archived_to_notifications",
+            sql: r#"
+                -- Archived notifications are kept but hidden from the default list
+                ALTER TABLE notifications ADD COLUMN is_archived BOOLEAN DEFAULT 0;
+
+                CREATE INDEX IF NOT EXISTS idx_notifications_is_archived ON notifications(is_archived);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 31,
+            description: "round_money_columns_to_cents",
+            sql: r#"
+                -- Money is now computed in integer cents. Snap stored REAL amounts to the
+                -- nearest cent so earlier float drift (e.g. 104.99999999999999) disappears.
+                -- Already-rounded rows are skipped, so re-running this is a no-op.
+                UPDATE sales SET
+                    subtotal = ROUND(subtotal, 2),
+                    tax_amount = ROUND(tax_amount, 2),
+                    discount_amount = ROUND(discount_amount, 2),
+                    total_amount = ROUND(total_amount, 2)
+                WHERE subtotal != ROUND(subtotal, 2)
+                   OR tax_amount != ROUND(tax_amount, 2)
+                   OR discount_amount != ROUND(discount_amount, 2)
+                   OR total_amount != ROUND(total_amount, 2);
+
+                UPDATE sale_items SET
+                    unit_price = ROUND(unit_price, 2),
+                    discount_amount = ROUND(discount_amount, 2),
+                    line_total = ROUND(line_total, 2),
+                    tax_amount = ROUND(tax_amount, 2),
+                    cost_price = ROUND(cost_price, 2)
+                WHERE unit_price != ROUND(unit_price, 2)
+                   OR discount_amount != ROUND(discount_amount, 2)
+                   OR line_total != ROUND(line_total, 2)
+                   OR tax_amount != ROUND(tax_amount, 2)
+                   OR cost_price != ROUND(cost_price, 2);
+
+                UPDATE returns SET
+                    subtotal = ROUND(subtotal, 2),
+                    tax_amount = ROUND(tax_amount, 2),
+                    total_amount = ROUND(total_amount, 2)
+                WHERE subtotal != ROUND(subtotal, 2)
+                   OR tax_amount != ROUND(tax_amount, 2)
+                   OR total_amount != ROUND(total_amount, 2);
+
+                UPDATE return_items SET
+                    unit_price = ROUND(unit_price, 2),
+                    line_total = ROUND(line_total, 2)
+                WHERE unit_price != ROUND(unit_price, 2)
+                   OR line_total != ROUND(line_total, 2);
+
+                UPDATE shifts SET
+                    opening_amount = ROUND(opening_amount, 2),
+                    closing_amount = ROUND(closing_amount, 2),
+                    total_sales = ROUND(total_sales, 2),
+                    total_returns = ROUND(total_returns, 2),
+                    cash_sales = ROUND(cash_sales, 2),
+                    card_sales = ROUND(card_sales, 2)
+                WHERE opening_amount != ROUND(opening_amount, 2)
+                   OR closing_amount != ROUND(closing_amount, 2)
+                   OR total_sales != ROUND(total_sales, 2)
+                   OR total_returns != ROUND(total_returns, 2)
+                   OR cash_sales != ROUND(cash_sales, 2)
+                   OR card_sales != ROUND(card_sales, 2);
+
+                UPDATE cash_drawer_transactions SET
+                    amount = ROUND(amount, 2)
+                WHERE amount != ROUND(amount, 2);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 32,
+            description: "create_currencies_table",
+            sql: r#"
+                -- Currency display and rounding rules used by format_money and tax rounding
+                CREATE TABLE IF NOT EXISTS currencies (
+                    code TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    symbol TEXT NOT NULL,
+                    decimal_places INTEGER NOT NULL DEFAULT 2,
+                    thousands_separator TEXT NOT NULL DEFAULT ',',
+                    decimal_separator TEXT NOT NULL DEFAULT '.',
+                    symbol_position TEXT NOT NULL DEFAULT 'before'
+                        CHECK (symbol_position IN ('before', 'after'))
+                );
+
+                INSERT OR IGNORE INTO currencies
+                    (code, name, symbol, decimal_places, thousands_separator, decimal_separator, symbol_position)
+                VALUES
+                    ('USD', 'US Dollar', '$', 2, ',', '.', 'before'),
+                    ('EUR', 'Euro', '€', 2, '.', ',', 'after'),
+                    ('GBP', 'British Pound', '£', 2, ',', '.', 'before'),
+                    ('XAF', 'Central African CFA Franc', 'FCFA', 0, ' ', ',', 'after'),
+                    ('XOF', 'West African CFA Franc', 'CFA', 0, ' ', ',', 'after'),
+                    ('NGN', 'Nigerian Naira', '₦', 2, ',', '.', 'before'),
+                    ('CAD', 'Canadian Dollar', 'C$', 2, ',', '.', 'before'),
+                    ('JPY', 'Japanese Yen', '¥', 0, ',', '.', 'before'),
+                    ('CNY', 'Chinese Yuan', '¥', 2, ',', '.', 'before')
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 33,
+            description: "create_document_sequences",
+            sql: r#"
+                -- Counters behind sequential document numbers such as SALE-2024-000123
+                CREATE TABLE IF NOT EXISTS document_sequences (
+                    doc_type TEXT PRIMARY KEY,
+                    prefix TEXT NOT NULL,
+                    next_value INTEGER NOT NULL DEFAULT 1,
+                    year INTEGER NOT NULL DEFAULT 0,
+                    reset_yearly BOOLEAN NOT NULL DEFAULT false,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                );
+
+                INSERT OR IGNORE INTO document_sequences (doc_type, prefix) VALUES
+                    ('sale', 'SALE'),
+                    ('sales_return', 'SR'),
+                    ('purchase_return', 'PR'),
+                    ('inventory_return', 'IR'),
+                    ('transfer_return', 'TR'),
+                    ('purchase_order', 'PO'),
+                    ('expense', 'EXP')
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 34,
+            description: "create_sync_outbox",
+            sql: r#"
+                -- Local changes waiting to be pushed to the cloud, oldest first
+                CREATE TABLE IF NOT EXISTS sync_outbox (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    entity_type TEXT NOT NULL,
+                    entity_id INTEGER NOT NULL,
+                    operation TEXT NOT NULL CHECK (operation IN ('create', 'update', 'delete')),
+                    payload TEXT NOT NULL,
+                    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'synced', 'error')),
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    last_error TEXT,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    synced_at DATETIME
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_sync_outbox_status ON sync_outbox(status, id);
+                CREATE INDEX IF NOT EXISTS idx_sync_outbox_entity ON sync_outbox(entity_type, entity_id);
+
+                -- Id of the record in the cloud once it has been pushed
+                ALTER TABLE products ADD COLUMN remote_id TEXT;
+                ALTER TABLE customers ADD COLUMN remote_id TEXT;
+                ALTER TABLE sales ADD COLUMN remote_id TEXT;
+                ALTER TABLE suppliers ADD COLUMN remote_id TEXT;
+                ALTER TABLE expenses ADD COLUMN remote_id TEXT;
+                ALTER TABLE purchase_orders ADD COLUMN remote_id TEXT;
+                ALTER TABLE comprehensive_returns ADD COLUMN remote_id TEXT;
+
+                -- Sales were never updated in place before voids started syncing
+                ALTER TABLE sales ADD COLUMN updated_at DATETIME;
+                UPDATE sales SET updated_at = created_at WHERE updated_at IS NULL
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 35,
+            description: "create_sync_conflicts",
+            sql: r#"
+                -- Inbound changes that disagreed with local edits, kept for review
+                CREATE TABLE IF NOT EXISTS sync_conflicts (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    entity_type TEXT NOT NULL,
+                    entity_id INTEGER NOT NULL,
+                    local_payload TEXT NOT NULL,
+                    remote_payload TEXT NOT NULL,
+                    resolution TEXT NOT NULL CHECK (resolution IN ('local', 'remote', 'merged')),
+                    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'resolved')),
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    resolved_at DATETIME
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_sync_conflicts_status ON sync_conflicts(status, id);
+
+                -- Stock count last received from the cloud, the base for merging stock deltas
+                ALTER TABLE inventory ADD COLUMN synced_stock INTEGER
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 36,
+            description: "create_webhooks",
+            sql: r#"
+                -- Outgoing HTTP callbacks, events is a JSON array of subscribed event names
+                CREATE TABLE IF NOT EXISTS webhooks (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    url TEXT NOT NULL,
+                    secret TEXT NOT NULL,
+                    events TEXT NOT NULL DEFAULT '[]',
+                    is_active BOOLEAN NOT NULL DEFAULT true,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                );
+
+                CREATE TABLE IF NOT EXISTS webhook_deliveries (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    webhook_id INTEGER NOT NULL,
+                    event TEXT NOT NULL,
+                    payload TEXT NOT NULL,
+                    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'failed')),
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    response_status INTEGER,
+                    last_error TEXT,
+                    next_attempt_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    delivered_at DATETIME,
+                    FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at);
+                CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 37,
+            description: "create_api_keys",
+            sql: r#"
+                -- Keys for the LAN REST API, only a SHA-256 of each key is stored
+                CREATE TABLE IF NOT EXISTS api_keys (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    key_prefix TEXT NOT NULL,
+                    key_hash TEXT NOT NULL UNIQUE,
+                    scopes TEXT NOT NULL DEFAULT '[]',
+                    is_active BOOLEAN NOT NULL DEFAULT true,
+                    created_by INTEGER,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    last_used_at DATETIME,
+                    revoked_at DATETIME,
+                    FOREIGN KEY (created_by) REFERENCES users(id)
+                )
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 38,
+            description: "create_product_cost_history",
+            sql: r#"
+                -- Every change to products.cost_price, so old sales can be costed at the price of the day
+                CREATE TABLE IF NOT EXISTS product_cost_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    product_id INTEGER NOT NULL,
+                    old_cost REAL,
+                    new_cost REAL NOT NULL,
+                    source TEXT NOT NULL CHECK (source IN ('initial', 'manual', 'receipt', 'purchase_order')),
+                    reference_id INTEGER,
+                    changed_by INTEGER,
+                    changed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+                    FOREIGN KEY (changed_by) REFERENCES users(id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_product_cost_history_product ON product_cost_history(product_id, changed_at);
+
+                -- Existing products start their history at today's cost
+                INSERT INTO product_cost_history (product_id, old_cost, new_cost, source, changed_at)
+                SELECT p.id, NULL, COALESCE(p.cost_price, 0), 'initial', COALESCE(p.created_at, CURRENT_TIMESTAMP)
+                FROM products p
+                WHERE NOT EXISTS (SELECT 1 FROM product_cost_history h WHERE h.product_id = p.id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 39,
+            description: "add_below_cost_policy",
+            sql: r#"
+                -- What a till at this location does with lines sold below cost: 'warn' or 'block'
+                ALTER TABLE locations ADD COLUMN below_cost_policy TEXT NOT NULL DEFAULT 'warn' CHECK (below_cost_policy IN ('warn', 'block'));
+
+                -- Manager who let a blocked below-cost sale through
+                ALTER TABLE sales ADD COLUMN below_cost_approved_by INTEGER REFERENCES users(id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 40,
+            description: "create_stock_adjustments",
+            sql: r#"
+                -- Reason codes a manual adjustment must be filed under, editable from settings
+                CREATE TABLE IF NOT EXISTS stock_adjustment_reasons (
+                    code TEXT PRIMARY KEY,
+                    label TEXT NOT NULL,
+                    is_active BOOLEAN NOT NULL DEFAULT true,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                );
+
+                INSERT OR IGNORE INTO stock_adjustment_reasons (code, label) VALUES
+                    ('damaged', 'Damaged'),
+                    ('expired', 'Expired'),
+                    ('theft', 'Theft or loss'),
+                    ('count_correction', 'Count correction'),
+                    ('found', 'Found stock'),
+                    ('internal_use', 'Internal use');
+
+                -- Manual adjustments: 'applied' straight away, or 'pending' manager approval when
+                -- their value is over the location threshold
+                CREATE TABLE IF NOT EXISTS stock_adjustments (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    product_id INTEGER NOT NULL,
+                    quantity_change INTEGER NOT NULL,
+                    reason_code TEXT NOT NULL,
+                    notes TEXT,
+                    adjustment_value REAL NOT NULL DEFAULT 0.0,
+                    status TEXT NOT NULL CHECK (status IN ('applied', 'pending', 'approved', 'rejected')),
+                    requested_by INTEGER,
+                    decided_by INTEGER,
+                    decided_at DATETIME,
+                    decision_notes TEXT,
+                    movement_id INTEGER,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (product_id) REFERENCES products(id),
+                    FOREIGN KEY (reason_code) REFERENCES stock_adjustment_reasons(code),
+                    FOREIGN KEY (requested_by) REFERENCES users(id),
+                    FOREIGN KEY (decided_by) REFERENCES users(id),
+                    FOREIGN KEY (movement_id) REFERENCES inventory_movements(id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_stock_adjustments_status ON stock_adjustments(status, created_at);
+
+                -- Adjustments worth more than this (quantity x cost) wait for a manager
+                ALTER TABLE locations ADD COLUMN adjustment_approval_threshold REAL NOT NULL DEFAULT 250.0
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 41,
+            description: "create_product_lots",
+            sql: r#"
+                -- Stock of lot-tracked products by lot, drawn first-expiry-first-out on sale
+                CREATE TABLE IF NOT EXISTS product_lots (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    product_id INTEGER NOT NULL,
+                    lot_number TEXT NOT NULL,
+                    expiry_date DATE,
+                    quantity INTEGER NOT NULL DEFAULT 0 CHECK (quantity >= 0),
+                    received_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    UNIQUE(product_id, lot_number),
+                    FOREIGN KEY (product_id) REFERENCES products(id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_product_lots_expiry ON product_lots(expiry_date);
+
+                -- Which lots each sale line drew from, so a void puts stock back in the same lots
+                CREATE TABLE IF NOT EXISTS sale_item_lots (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    sale_item_id INTEGER NOT NULL,
+                    lot_id INTEGER NOT NULL,
+                    quantity INTEGER NOT NULL,
+                    FOREIGN KEY (sale_item_id) REFERENCES sale_items(id) ON DELETE CASCADE,
+                    FOREIGN KEY (lot_id) REFERENCES product_lots(id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_sale_item_lots_item ON sale_item_lots(sale_item_id);
+
+                ALTER TABLE products ADD COLUMN lot_tracked BOOLEAN NOT NULL DEFAULT false
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 42,
+            description: "create_stock_writeoffs",
+            sql: r#"
+                -- Stock damaged or lost before it was sold, with its cost. Write-offs worth more
+                -- than the location's adjustment approval threshold wait as 'pending' for a manager
+                CREATE TABLE IF NOT EXISTS stock_writeoffs (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    product_id INTEGER NOT NULL,
+                    variant_id INTEGER,
+                    lot_id INTEGER,
+                    quantity INTEGER NOT NULL CHECK (quantity > 0),
+                    reason_code TEXT NOT NULL,
+                    notes TEXT,
+                    photo_attachment TEXT,
+                    unit_cost REAL NOT NULL DEFAULT 0.0,
+                    total_cost REAL NOT NULL DEFAULT 0.0,
+                    status TEXT NOT NULL CHECK (status IN ('applied', 'pending', 'approved', 'rejected')),
+                    requested_by INTEGER,
+                    decided_by INTEGER,
+                    decided_at DATETIME,
+                    decision_notes TEXT,
+                    movement_id INTEGER,
+                    applied_at DATETIME,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (product_id) REFERENCES products(id),
+                    FOREIGN KEY (variant_id) REFERENCES product_variants(id),
+                    FOREIGN KEY (lot_id) REFERENCES product_lots(id),
+                    FOREIGN KEY (reason_code) REFERENCES stock_adjustment_reasons(code),
+                    FOREIGN KEY (requested_by) REFERENCES users(id),
+                    FOREIGN KEY (decided_by) REFERENCES users(id),
+                    FOREIGN KEY (movement_id) REFERENCES inventory_movements(id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_stock_writeoffs_status ON stock_writeoffs(status, created_at);
+                CREATE INDEX IF NOT EXISTS idx_stock_writeoffs_applied ON stock_writeoffs(applied_at)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 43,
+            description: "seed_barcode_sequence",
+            sql: r#"
+                -- Auto-assigned product barcodes: EAN-13 starting with the prefix (in-store range 20-29)
+                INSERT OR IGNORE INTO document_sequences (doc_type, prefix) VALUES ('barcode', '200')
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 44,
+            description: "measured_quantities",
+            sql: r#"
+                -- Weighed and measured lines keep their decimal quantity, sale_items.quantity
+                -- holds the whole units started (3 for 2.5 kg)
+                ALTER TABLE sale_items ADD COLUMN measured_quantity REAL;
+
+                -- Part-unit stock of measured products on top of the whole units in current_stock
+                ALTER TABLE inventory ADD COLUMN stock_fraction REAL NOT NULL DEFAULT 0;
+
+                ALTER TABLE inventory_movements ADD COLUMN measured_change REAL
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 45,
+            description: "create_discount_rules",
+            sql: r#"
+                -- Discounts applied automatically at the till, largest benefit wins per line
+                CREATE TABLE IF NOT EXISTS discount_rules (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    scope TEXT NOT NULL CHECK (scope IN ('product', 'category', 'all')),
+                    product_id INTEGER,
+                    category TEXT,
+                    discount_type TEXT NOT NULL CHECK (discount_type IN ('percent', 'fixed')),
+                    value REAL NOT NULL CHECK (value > 0),
+                    min_quantity INTEGER NOT NULL DEFAULT 1 CHECK (min_quantity >= 1),
+                    start_date DATE,
+                    end_date DATE,
+                    is_active BOOLEAN NOT NULL DEFAULT true,
+                    created_by INTEGER,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    CHECK (scope != 'product' OR product_id IS NOT NULL),
+                    CHECK (scope != 'category' OR category IS NOT NULL),
+                    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+                    FOREIGN KEY (created_by) REFERENCES users(id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_discount_rules_active ON discount_rules(is_active, scope)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 46,
+            description: "create_exchange_rates_and_sale_payments",
+            sql: r#"
+                -- Store-currency units one unit of a foreign currency buys, from effective_date on
+                CREATE TABLE IF NOT EXISTS exchange_rates (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    currency TEXT NOT NULL,
+                    rate_to_base REAL NOT NULL CHECK (rate_to_base > 0),
+                    effective_date DATE NOT NULL,
+                    created_by INTEGER,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    UNIQUE (currency, effective_date),
+                    FOREIGN KEY (currency) REFERENCES currencies(code),
+                    FOREIGN KEY (created_by) REFERENCES users(id)
+                );
+
+                -- The tender as handed over, and its conversion into the store currency
+                CREATE TABLE IF NOT EXISTS sale_payments (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    sale_id INTEGER NOT NULL,
+                    payment_method TEXT NOT NULL,
+                    currency TEXT NOT NULL,
+                    amount REAL NOT NULL,
+                    exchange_rate REAL NOT NULL,
+                    base_amount REAL NOT NULL,
+                    change_base REAL NOT NULL DEFAULT 0,
+                    change_amount REAL NOT NULL DEFAULT 0,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (sale_id) REFERENCES sales(id) ON DELETE CASCADE
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_sale_payments_sale ON sale_payments(sale_id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 47,
+            description: "create_parked_sales",
+            sql: r#"
+                -- Carts put on hold at the till, kept across restarts until resumed
+                CREATE TABLE IF NOT EXISTS parked_sales (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    cashier_id INTEGER NOT NULL,
+                    label TEXT NOT NULL,
+                    cart_json TEXT NOT NULL,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (cashier_id) REFERENCES users(id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_parked_sales_cashier ON parked_sales(cashier_id, created_at)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 48,
+            description: "create_tax_rules",
+            sql: r#"
+                -- Stackable tax rules. Once any is active they replace products.tax_rate.
+                CREATE TABLE IF NOT EXISTS tax_rules (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    rate REAL NOT NULL CHECK (rate >= 0 AND rate <= 100),
+                    applies_to TEXT NOT NULL DEFAULT 'all' CHECK (applies_to IN ('all', 'category', 'product')),
+                    category TEXT,
+                    product_id INTEGER,
+                    is_compound BOOLEAN NOT NULL DEFAULT false,
+                    priority INTEGER NOT NULL DEFAULT 0,
+                    exempt_categories TEXT,
+                    is_active BOOLEAN NOT NULL DEFAULT true,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    CHECK (applies_to != 'category' OR category IS NOT NULL),
+                    CHECK (applies_to != 'product' OR product_id IS NOT NULL),
+                    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+                );
+
+                -- Each tax charged on a sale line, with the rule name and rate at the time of sale
+                CREATE TABLE IF NOT EXISTS sale_item_taxes (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    sale_item_id INTEGER NOT NULL,
+                    tax_rule_id INTEGER,
+                    name TEXT NOT NULL,
+                    rate REAL NOT NULL,
+                    taxable_amount REAL NOT NULL,
+                    tax_amount REAL NOT NULL,
+                    FOREIGN KEY (sale_item_id) REFERENCES sale_items(id) ON DELETE CASCADE,
+                    FOREIGN KEY (tax_rule_id) REFERENCES tax_rules(id) ON DELETE SET NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_tax_rules_active ON tax_rules(is_active, priority);
+                CREATE INDEX IF NOT EXISTS idx_sale_item_taxes_item ON sale_item_taxes(sale_item_id);
+                CREATE INDEX IF NOT EXISTS idx_sale_item_taxes_rule ON sale_item_taxes(tax_rule_id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 49,
+            description: "price_overrides_and_audit_log",
+            sql: r#"
+                -- Who changed what, with the record before and after as JSON
+                CREATE TABLE IF NOT EXISTS audit_log (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_id INTEGER,
+                    action TEXT NOT NULL,
+                    entity_type TEXT NOT NULL,
+                    entity_id INTEGER,
+                    before_json TEXT,
+                    after_json TEXT,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (user_id) REFERENCES users(id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity_type, entity_id);
+                CREATE INDEX IF NOT EXISTS idx_audit_log_user ON audit_log(user_id, created_at);
+
+                -- Lines sold at a price overridden at the register: the regular price, why, and who allowed it
+                ALTER TABLE sale_items ADD COLUMN original_unit_price REAL;
+                ALTER TABLE sale_items ADD COLUMN override_reason TEXT;
+                ALTER TABLE sale_items ADD COLUMN override_approved_by INTEGER REFERENCES users(id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 50,
+            description: "add_minimum_margins",
+            sql: r#"
+                -- Markup over cost a sale line or new selling price must keep, in percent.
+                -- 0 only guards against selling below cost.
+                ALTER TABLE locations ADD COLUMN min_margin_percent REAL NOT NULL DEFAULT 0;
+
+                -- Per-category minimum, NULL to use the store's
+                ALTER TABLE categories ADD COLUMN min_margin_percent REAL
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 51,
+            description: "add_no_sale_drawer_events",
+            sql: r#"
+                -- Rebuild cash_drawer_transactions so it accepts 'no_sale' rows (the drawer
+                -- opened for change without a sale) and 'refund' rows (cash paid back on a
+                -- return, stored as a negative amount)
+                DROP TABLE IF EXISTS cash_drawer_transactions_old;
+                ALTER TABLE cash_drawer_transactions RENAME TO cash_drawer_transactions_old;
+                CREATE TABLE cash_drawer_transactions (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    shift_id INTEGER NOT NULL,
+                    transaction_type TEXT NOT NULL CHECK (transaction_type IN ('opening', 'closing', 'adjustment', 'withdrawal', 'deposit', 'no_sale', 'refund')),
+                    amount REAL NOT NULL,
+                    reason TEXT,
+                    user_id INTEGER NOT NULL,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                );
+                INSERT INTO cash_drawer_transactions (id, shift_id, transaction_type, amount, reason, user_id, created_at)
+                    SELECT id, shift_id, transaction_type, amount, reason, user_id, created_at
+                    FROM cash_drawer_transactions_old;
+                DROP TABLE cash_drawer_transactions_old;
+                CREATE INDEX IF NOT EXISTS idx_cash_drawer_transactions_shift ON cash_drawer_transactions(shift_id, transaction_type);
+
+                -- Receipt printer the drawer hangs off: a device path, or tcp://host:port
+                ALTER TABLE locations ADD COLUMN receipt_printer TEXT;
+
+                -- No-sale drawer openings per shift before managers are warned
+                ALTER TABLE locations ADD COLUMN no_sale_alert_threshold INTEGER NOT NULL DEFAULT 5
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 52,
+            description: "add_refund_payment_splits",
+            sql: r#"
+                -- Non-cash refunds are split out against the original sale as negative
+                -- sale_payments rows tagged with the return that paid them
+                ALTER TABLE sale_payments ADD COLUMN return_id INTEGER;
+                CREATE INDEX IF NOT EXISTS idx_sale_payments_return ON sale_payments(return_id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 53,
+            description: "add_return_risk_thresholds",
+            sql: r#"
+                -- Sales returns one cashier can process in a report period before loss
+                -- prevention reviews them, by count and by refunded value
+                ALTER TABLE locations ADD COLUMN return_alert_count INTEGER NOT NULL DEFAULT 10;
+                ALTER TABLE locations ADD COLUMN return_alert_value REAL NOT NULL DEFAULT 500
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 54,
+            description: "add_return_completed_by",
+            sql: r#"
+                ALTER TABLE comprehensive_returns ADD COLUMN completed_by INTEGER REFERENCES users(id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 55,
+            description: "add_sales_report_covering_index",
+            sql: r#"
+                -- Date-range sales reports range on the raw created_at and read only these
+                -- columns, so they can be answered from the index alone
+                CREATE INDEX IF NOT EXISTS idx_sales_created_report ON sales(
+                    created_at, is_voided, payment_method, total_amount, tax_amount, discount_amount
+                )
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 56,
+            description: "create_supplier_credit_memos",
+            sql: r#"
+                -- Credit owed by a supplier for goods sent back on a completed purchase return
+                CREATE TABLE IF NOT EXISTS supplier_credit_memos (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    memo_number TEXT UNIQUE NOT NULL,
+                    supplier_id INTEGER NOT NULL,
+                    return_id INTEGER NOT NULL UNIQUE,
+                    purchase_order_id INTEGER,
+                    amount REAL NOT NULL,
+                    applied_amount REAL NOT NULL DEFAULT 0,
+                    status TEXT NOT NULL DEFAULT 'Open' CHECK (status IN ('Open', 'Applied')),
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (supplier_id) REFERENCES suppliers(id),
+                    FOREIGN KEY (purchase_order_id) REFERENCES purchase_orders(id)
+                );
+
+                -- Each use of a memo, paid as a supplier payment
+                CREATE TABLE IF NOT EXISTS supplier_credit_applications (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    credit_memo_id INTEGER NOT NULL,
+                    supplier_payment_id INTEGER NOT NULL,
+                    amount REAL NOT NULL,
+                    applied_by INTEGER NOT NULL,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (credit_memo_id) REFERENCES supplier_credit_memos(id),
+                    FOREIGN KEY (supplier_payment_id) REFERENCES supplier_payments(id),
+                    FOREIGN KEY (applied_by) REFERENCES users(id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_supplier_credit_memos_supplier ON supplier_credit_memos(supplier_id, status);
+                CREATE INDEX IF NOT EXISTS idx_supplier_credit_applications_memo ON supplier_credit_applications(credit_memo_id);
+
+                INSERT OR IGNORE INTO document_sequences (doc_type, prefix) VALUES ('credit_memo', 'CM')
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 57,
+            description: "add_sale_item_post_sale_stock",
+            sql: r#"
+                ALTER TABLE sale_items ADD COLUMN post_sale_stock INTEGER
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 58,
+            description: "add_receipt_footer_and_tax_breakdown",
+            sql: r#"
+                ALTER TABLE locations ADD COLUMN receipt_footer TEXT NOT NULL DEFAULT 'Thank you for your business!';
+
+                -- The seeded sale receipt lists tax per rate and prints the store's own footer
+                UPDATE receipt_templates
+                SET template_content = REPLACE(
+                        REPLACE(template_content, 'Tax: {{tax_amount}}', '{{tax_breakdown}}'),
+                        'Thank you for your business!', '{{footer}}')
+                WHERE name = 'Default Sale Receipt' AND template_type = 'sale'
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 59,
+            description: "add_return_item_variant",
+            sql: r#"
+                ALTER TABLE comprehensive_return_items ADD COLUMN product_variant_id INTEGER REFERENCES product_variants(id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 60,
+            description: "add_warehouse_pick_ticket_template",
+            sql: r#"
+                -- Rebuild receipt_templates so it accepts 'warehouse' templates: pick tickets
+                -- printed for the yard or back room, listing what to pull without prices
+                DROP TABLE IF EXISTS receipt_templates_old;
+                ALTER TABLE receipt_templates RENAME TO receipt_templates_old;
+                CREATE TABLE receipt_templates (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    template_type TEXT NOT NULL CHECK (template_type IN ('sale', 'return', 'void', 'warehouse')),
+                    printer_type TEXT NOT NULL CHECK (printer_type IN ('thermal', 'inkjet', 'laser')),
+                    template_content TEXT NOT NULL,
+                    is_default BOOLEAN DEFAULT false,
+                    paper_width INTEGER DEFAULT 80,
+                    font_size INTEGER DEFAULT 12,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                );
+                INSERT INTO receipt_templates (id, name, template_type, printer_type, template_content, is_default, paper_width, font_size, created_at, updated_at)
+                    SELECT id, name, template_type, printer_type, template_content, is_default, paper_width, font_size, created_at, updated_at
+                    FROM receipt_templates_old;
+                DROP TABLE receipt_templates_old;
+
+                INSERT INTO receipt_templates (name, template_type, printer_type, template_content, is_default, paper_width, font_size)
+                SELECT 'Default Pick Ticket', 'warehouse', 'thermal', '{{store_name}}\nPICK TICKET\n\nSALE #{{sale_number}}\nDate: {{sale_date}}\nCashier: {{cashier_name}}\nCustomer: {{customer_name}}\n\n{{items}}\n\nLines: {{line_count}}  Units: {{unit_count}}\n{{notes}}', 1, 80, 12
+                WHERE NOT EXISTS (SELECT 1 FROM receipt_templates WHERE template_type = 'warehouse')
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 61,
+            description: "add_per_location_inventory",
+            sql: r#"
+                -- Sales and shifts belong to a location, the main store (id 1) for existing rows
+                ALTER TABLE sales ADD COLUMN location_id INTEGER NOT NULL DEFAULT 1;
+                ALTER TABLE shifts ADD COLUMN location_id INTEGER NOT NULL DEFAULT 1;
+                CREATE INDEX IF NOT EXISTS idx_sales_location ON sales(location_id, created_at);
+
+                -- Stock held at branches. The main store holds the rest of inventory.current_stock
+                CREATE TABLE IF NOT EXISTS location_inventory (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    product_id INTEGER NOT NULL,
+                    location_id INTEGER NOT NULL,
+                    current_stock INTEGER NOT NULL DEFAULT 0,
+                    last_updated DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (product_id) REFERENCES products (id) ON DELETE CASCADE,
+                    FOREIGN KEY (location_id) REFERENCES locations (id) ON DELETE CASCADE,
+                    UNIQUE (product_id, location_id)
+                )
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 62,
+            description: "add_stock_transfers",
+            sql: r#"
+                -- Transfer movements name the location whose stock they moved
+                ALTER TABLE inventory_movements ADD COLUMN location_id INTEGER;
+
+                -- Stock sent from one location to another: pending, then in_transit once
+                -- shipped (out of the source, not yet at the destination), then received
+                CREATE TABLE IF NOT EXISTS stock_transfers (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    transfer_number TEXT UNIQUE NOT NULL,
+                    from_location_id INTEGER NOT NULL,
+                    to_location_id INTEGER NOT NULL,
+                    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'in_transit', 'received', 'cancelled')),
+                    notes TEXT,
+                    created_by INTEGER,
+                    shipped_by INTEGER,
+                    received_by INTEGER,
+                    shipped_at DATETIME,
+                    received_at DATETIME,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (from_location_id) REFERENCES locations (id),
+                    FOREIGN KEY (to_location_id) REFERENCES locations (id),
+                    FOREIGN KEY (created_by) REFERENCES users (id),
+                    FOREIGN KEY (shipped_by) REFERENCES users (id),
+                    FOREIGN KEY (received_by) REFERENCES users (id)
+                );
+                CREATE INDEX IF NOT EXISTS idx_stock_transfers_status ON stock_transfers(status);
+
+                CREATE TABLE IF NOT EXISTS stock_transfer_items (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    transfer_id INTEGER NOT NULL,
+                    product_id INTEGER NOT NULL,
+                    quantity INTEGER NOT NULL CHECK (quantity > 0),
+                    FOREIGN KEY (transfer_id) REFERENCES stock_transfers (id) ON DELETE CASCADE,
+                    FOREIGN KEY (product_id) REFERENCES products (id)
+                );
+                CREATE INDEX IF NOT EXISTS idx_stock_transfer_items_transfer ON stock_transfer_items(transfer_id);
+
+                INSERT OR IGNORE INTO document_sequences (doc_type, prefix) VALUES ('stock_transfer', 'ST')
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 63,
+            description: "scope_core_tables_by_organization",
+            sql: r#"
+                -- Products, customers and sales belong to an organization, the default one
+                -- (id 1) for existing rows
+                ALTER TABLE products ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1;
+                ALTER TABLE customers ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1;
+                ALTER TABLE sales ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1;
+                CREATE INDEX IF NOT EXISTS idx_products_organization ON products(organization_id);
+                CREATE INDEX IF NOT EXISTS idx_customers_organization ON customers(organization_id);
+                CREATE INDEX IF NOT EXISTS idx_sales_organization ON sales(organization_id, created_at)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 64,
+            description: "add_per_location_reorder_levels",
+            sql: r#"
+                -- Reorder thresholds per location. NULL falls back to the product-wide level
+                -- on inventory and products. The main store keeps its levels on a row of its
+                -- own whose current_stock is unused, its stock being the remainder.
+                ALTER TABLE location_inventory ADD COLUMN minimum_stock INTEGER;
+                ALTER TABLE location_inventory ADD COLUMN maximum_stock INTEGER;
+                ALTER TABLE location_inventory ADD COLUMN reorder_point INTEGER
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 65,
+            description: "add_organization_invitations",
+            sql: r#"
+                -- Memberships offered by email, pending until the invited user accepts the code
+                CREATE TABLE IF NOT EXISTS organization_invitations (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    organization_id INTEGER NOT NULL,
+                    email TEXT NOT NULL,
+                    role TEXT NOT NULL CHECK (role IN ('Owner', 'Admin', 'Manager', 'User')),
+                    permissions TEXT,
+                    invite_code TEXT UNIQUE NOT NULL,
+                    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'accepted', 'revoked')),
+                    invited_by INTEGER,
+                    accepted_by INTEGER,
+                    expires_at DATETIME NOT NULL,
+                    accepted_at DATETIME,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (organization_id) REFERENCES organizations (id) ON DELETE CASCADE,
+                    FOREIGN KEY (invited_by) REFERENCES users (id),
+                    FOREIGN KEY (accepted_by) REFERENCES users (id)
+                );
+                CREATE INDEX IF NOT EXISTS idx_organization_invitations_org ON organization_invitations(organization_id, status)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 66,
+            description: "scope_inventory_by_organization",
+            sql: r#"
+                -- Inventory rows belong to the organization of their product
+                ALTER TABLE inventory ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1;
+                UPDATE inventory SET organization_id = (
+                    SELECT p.organization_id FROM products p WHERE p.id = inventory.product_id
+                ) WHERE product_id IN (SELECT id FROM products);
+                CREATE INDEX IF NOT EXISTS idx_inventory_organization ON inventory(organization_id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 67,
+            description: "add_plan_limits",
+            sql: r#"
+                -- Overrides of the built-in plan limits. NULL keeps the built-in value and a
+                -- negative cap means unlimited.
+                CREATE TABLE IF NOT EXISTS plan_limits (
+                    plan TEXT PRIMARY KEY CHECK (plan IN ('Free', 'Starter', 'Professional', 'Enterprise')),
+                    max_products INTEGER,
+                    max_users INTEGER,
+                    max_locations INTEGER,
+                    multi_location BOOLEAN,
+                    crm BOOLEAN,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                );
+                -- Locations count against the plan of the organization they belong to
+                ALTER TABLE locations ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1;
+                CREATE INDEX IF NOT EXISTS idx_locations_organization ON locations(organization_id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 68,
+            description: "add_app_settings",
+            sql: r#"
+                -- Typed settings as JSON values, each for an organization, location or user
+                CREATE TABLE IF NOT EXISTS app_settings (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    key TEXT NOT NULL,
+                    value TEXT NOT NULL,
+                    scope TEXT NOT NULL CHECK (scope IN ('organization', 'location', 'user')),
+                    scope_id INTEGER NOT NULL,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    UNIQUE(key, scope, scope_id)
+                );
+                -- The store's currency and tax rate default move over from its location row
+                INSERT OR IGNORE INTO app_settings (key, value, scope, scope_id)
+                SELECT 'currency', json_quote(currency), 'location', id FROM locations
+                WHERE id = 1 AND currency IS NOT NULL;
+                INSERT OR IGNORE INTO app_settings (key, value, scope, scope_id)
+                SELECT 'tax_rate', json_quote(tax_rate), 'location', id FROM locations
+                WHERE id = 1 AND tax_rate IS NOT NULL
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 69,
+            description: "add_user_activity",
+            sql: r#"
+                -- Sign-in events. user_id is NULL for failed attempts on unknown usernames.
+                CREATE TABLE IF NOT EXISTS user_activity (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_id INTEGER,
+                    username TEXT NOT NULL,
+                    event_type TEXT NOT NULL CHECK (event_type IN ('login', 'pin_login', 'logout', 'login_failed')),
+                    location_id INTEGER,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE SET NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_user_activity_user ON user_activity(user_id, created_at)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 70,
+            description: "add_archived_at",
+            sql: r#"
+                -- Archived records stay for history but drop out of search and default lists
+                ALTER TABLE products ADD COLUMN archived_at DATETIME;
+                ALTER TABLE customers ADD COLUMN archived_at DATETIME;
+                ALTER TABLE suppliers ADD COLUMN archived_at DATETIME;
+                -- Products deactivated before archiving existed count as archived
+                UPDATE products SET archived_at = COALESCE(updated_at, CURRENT_TIMESTAMP)
+                WHERE is_active = 0 AND archived_at IS NULL
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 71,
+            description: "add_category_hierarchy",
+            sql: r#"
+                ALTER TABLE categories ADD COLUMN parent_id INTEGER REFERENCES categories (id);
+                ALTER TABLE products ADD COLUMN category_id INTEGER REFERENCES categories (id);
+                CREATE INDEX IF NOT EXISTS idx_categories_parent ON categories(parent_id);
+                CREATE INDEX IF NOT EXISTS idx_products_category_id ON products(category_id);
+                -- Map the free-text categories products were created with onto category rows
+                INSERT OR IGNORE INTO categories (name)
+                SELECT DISTINCT TRIM(category) FROM products
+                WHERE category_id IS NULL AND category IS NOT NULL AND TRIM(category) != '';
+                UPDATE products SET category_id = (SELECT c.id FROM categories c WHERE c.name = TRIM(products.category))
+                WHERE category_id IS NULL AND category IS NOT NULL AND TRIM(category) != ''
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 72,
+            description: "add_single_default_receipt_template",
+            sql: r#"
+                -- Keep the oldest default where several templates claim the same type and printer
+                UPDATE receipt_templates SET is_default = 0
+                WHERE is_default = 1 AND id NOT IN (
+                    SELECT MIN(id) FROM receipt_templates WHERE is_default = 1
+                    GROUP BY template_type, printer_type
+                );
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_receipt_templates_default
+                ON receipt_templates(template_type, printer_type) WHERE is_default = 1
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 73,
+            description: "add_receipt_reprints",
+            sql: r#"
+                -- The first print of a receipt is stamped on the sale, every later one is a reprint
+                ALTER TABLE sales ADD COLUMN receipt_printed_at TIMESTAMP;
+                CREATE TABLE IF NOT EXISTS receipt_reprints (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    sale_id INTEGER NOT NULL,
+                    user_id INTEGER,
+                    printed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (sale_id) REFERENCES sales(id),
+                    FOREIGN KEY (user_id) REFERENCES users(id)
+                );
+                CREATE INDEX IF NOT EXISTS idx_receipt_reprints_sale_id ON receipt_reprints(sale_id);
+                CREATE INDEX IF NOT EXISTS idx_receipt_reprints_user_id ON receipt_reprints(user_id, printed_at)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 74,
+            description: "create_pending_sales",
+            sql: r#"
+                -- Sales rung up while the database or sync target was unavailable, replayed
+                -- through create_sale later. Failed payloads stay queued with their error.
+                CREATE TABLE IF NOT EXISTS pending_sales (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    client_uuid TEXT NOT NULL UNIQUE,
+                    payload TEXT NOT NULL,
+                    organization_id INTEGER NOT NULL DEFAULT 1,
+                    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'failed', 'processed')),
+                    sale_id INTEGER,
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    last_error TEXT,
+                    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    processed_at TIMESTAMP,
+                    FOREIGN KEY (sale_id) REFERENCES sales(id)
+                );
+                CREATE INDEX IF NOT EXISTS idx_pending_sales_status ON pending_sales(status, id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 75,
+            description: "add_moving_average_cost",
+            sql: r#"
+                -- Unit cost of stock received, so average costs can be replayed from movements
+                ALTER TABLE inventory_movements ADD COLUMN unit_cost REAL;
+
+                -- Rebuild product_cost_history so it accepts 'recalculation' rows written when
+                -- average costs are replayed
+                DROP TABLE IF EXISTS product_cost_history_old;
+                ALTER TABLE product_cost_history RENAME TO product_cost_history_old;
+                CREATE TABLE product_cost_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    product_id INTEGER NOT NULL,
+                    old_cost REAL,
+                    new_cost REAL NOT NULL,
+                    source TEXT NOT NULL CHECK (source IN ('initial', 'manual', 'receipt', 'purchase_order', 'recalculation')),
+                    reference_id INTEGER,
+                    changed_by INTEGER,
+                    changed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+                    FOREIGN KEY (changed_by) REFERENCES users(id)
+                );
+                INSERT INTO product_cost_history (id, product_id, old_cost, new_cost, source, reference_id, changed_by, changed_at)
+                    SELECT id, product_id, old_cost, new_cost, source, reference_id, changed_by, changed_at
+                    FROM product_cost_history_old;
+                DROP TABLE product_cost_history_old;
+                CREATE INDEX IF NOT EXISTS idx_product_cost_history_product ON product_cost_history(product_id, changed_at)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 76,
+            description: "add_keyset_pagination_indexes",
+            sql: r#"
+                -- Keyset pages walk `created_at DESC, id DESC`. An index ends with the rowid, so
+                -- idx_sales_organization, idx_comprehensive_returns_date and
+                -- idx_notifications_created_at already cover theirs; products had none
+                CREATE INDEX IF NOT EXISTS idx_products_organization_created ON products(organization_id, created_at)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 77,
+            description: "add_products_search_index",
+            sql: r#"
+                -- Full-text index over what a cashier types to find a product, kept in step
+                -- with products by triggers; the rowid is the product id
+                CREATE VIRTUAL TABLE IF NOT EXISTS products_fts USING fts5(
+                    sku, barcode, name, brand, category
+                );
+                INSERT INTO products_fts (rowid, sku, barcode, name, brand, category)
+                    SELECT id, sku, COALESCE(barcode, ''), name, COALESCE(brand, ''), COALESCE(category, '')
+                    FROM products
+                    WHERE id NOT IN (SELECT rowid FROM products_fts);
+                CREATE TRIGGER IF NOT EXISTS trg_products_fts_insert AFTER INSERT ON products
+                BEGIN
+                    INSERT INTO products_fts (rowid, sku, barcode, name, brand, category)
+                    VALUES (new.id, new.sku, COALESCE(new.barcode, ''), new.name, COALESCE(new.brand, ''), COALESCE(new.category, ''));
+                END;
+                CREATE TRIGGER IF NOT EXISTS trg_products_fts_update AFTER UPDATE OF sku, barcode, name, brand, category ON products
+                BEGIN
+                    DELETE FROM products_fts WHERE rowid = old.id;
+                    INSERT INTO products_fts (rowid, sku, barcode, name, brand, category)
+                    VALUES (new.id, new.sku, COALESCE(new.barcode, ''), new.name, COALESCE(new.brand, ''), COALESCE(new.category, ''));
+                END;
+                CREATE TRIGGER IF NOT EXISTS trg_products_fts_delete AFTER DELETE ON products
+                BEGIN
+                    DELETE FROM products_fts WHERE rowid = old.id;
+                END
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 78,
+            description: "add_reorder_policies",
+            sql: r#"
+                -- How each product's minimum stock and reorder point are set, as the JSON of
+                -- a ReorderPolicy, so they can be recomputed as sales change
+                CREATE TABLE IF NOT EXISTS reorder_policies (
+                    product_id INTEGER PRIMARY KEY,
+                    policy TEXT NOT NULL,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+                )
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 79,
+            description: "add_demo_template_markers",
+            sql: r#"
+                -- Code of the demo template that seeded the row; NULL for the store's own data
+                ALTER TABLE users ADD COLUMN demo_template TEXT;
+                ALTER TABLE categories ADD COLUMN demo_template TEXT;
+                ALTER TABLE products ADD COLUMN demo_template TEXT;
+                ALTER TABLE services ADD COLUMN demo_template TEXT;
+                ALTER TABLE customers ADD COLUMN demo_template TEXT;
+                ALTER TABLE suppliers ADD COLUMN demo_template TEXT;
+                ALTER TABLE shifts ADD COLUMN demo_template TEXT;
+                ALTER TABLE sales ADD COLUMN demo_template TEXT
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 80,
+            description: "add_demo_mode_flags",
+            sql: r#"
+                -- Recorded while demo mode was on: kept out of reports and purged on request
+                ALTER TABLE sales ADD COLUMN is_demo BOOLEAN NOT NULL DEFAULT 0;
+                ALTER TABLE comprehensive_returns ADD COLUMN is_demo BOOLEAN NOT NULL DEFAULT 0;
+                ALTER TABLE expenses ADD COLUMN is_demo BOOLEAN NOT NULL DEFAULT 0
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 81,
+            description: "add_cash_rounding",
+            sql: r#"
+                -- Cash collected minus the cash portion of the total, under the store's cash
+                -- rounding rule; the sale total itself stays exact
+                ALTER TABLE sales ADD COLUMN cash_rounding REAL NOT NULL DEFAULT 0;
+                ALTER TABLE shifts ADD COLUMN cash_rounding REAL NOT NULL DEFAULT 0
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 82,
+            description: "add_receipt_footer_rules",
+            sql: r#"
+                -- Messages printed under qualifying receipts; a rule with a coupon discount
+                -- issues a single-use promotion whose code replaces {code} in the message
+                CREATE TABLE IF NOT EXISTS receipt_footer_rules (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    message TEXT NOT NULL,
+                    min_amount REAL NOT NULL DEFAULT 0,
+                    start_date DATE,
+                    end_date DATE,
+                    coupon_discount_type TEXT CHECK (coupon_discount_type IN ('Percentage', 'Fixed Amount')),
+                    coupon_discount_value REAL,
+                    coupon_valid_days INTEGER,
+                    priority INTEGER NOT NULL DEFAULT 0,
+                    is_active BOOLEAN NOT NULL DEFAULT 1,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                );
+
+                -- Coupons remember the rule and the sale whose receipt issued them
+                ALTER TABLE promotions ADD COLUMN footer_rule_id INTEGER
+                    REFERENCES receipt_footer_rules(id) ON DELETE SET NULL;
+                ALTER TABLE promotions ADD COLUMN source_sale_id INTEGER
+                    REFERENCES sales(id) ON DELETE SET NULL;
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_promotions_footer_coupon
+                    ON promotions(footer_rule_id, source_sale_id)
+                    WHERE footer_rule_id IS NOT NULL AND source_sale_id IS NOT NULL
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 83,
+            description: "add_buy_x_get_y_promotions",
+            sql: r#"
+                -- Buy X Get Y: for every buy_quantity + get_quantity eligible units, the
+                -- cheapest get_quantity are discounted by get_discount_percent
+                ALTER TABLE promotions ADD COLUMN buy_quantity INTEGER;
+                ALTER TABLE promotions ADD COLUMN get_quantity INTEGER;
+                ALTER TABLE promotions ADD COLUMN get_discount_percent REAL;
+
+                -- Discount each promotion gave on each sale line
+                CREATE TABLE IF NOT EXISTS sale_item_promotions (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    sale_item_id INTEGER NOT NULL,
+                    promotion_id INTEGER,
+                    code TEXT NOT NULL,
+                    discount_amount REAL NOT NULL,
+                    FOREIGN KEY (sale_item_id) REFERENCES sale_items(id) ON DELETE CASCADE,
+                    FOREIGN KEY (promotion_id) REFERENCES promotions(id) ON DELETE SET NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_sale_item_promotions_item ON sale_item_promotions(sale_item_id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 84,
+            description: "add_promotion_campaigns",
+            sql: r#"
+                -- Sales made with a campaign's promotions are attributed to the campaign
+                ALTER TABLE promotions ADD COLUMN campaign_id INTEGER
+                    REFERENCES campaigns(id) ON DELETE SET NULL;
+                CREATE INDEX IF NOT EXISTS idx_promotions_campaign ON promotions(campaign_id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 85,
+            description: "add_api_key_cashier",
+            sql: r#"
+                -- User the sales posted with a key are rung up as
+                ALTER TABLE api_keys ADD COLUMN cashier_id INTEGER REFERENCES users(id)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 86,
+            description: "scope_returns_by_organization",
+            sql: r#"
+                -- Returns belong to an organization; sales returns to the one of their sale
+                ALTER TABLE comprehensive_returns ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1;
+                UPDATE comprehensive_returns SET organization_id = (
+                    SELECT s.organization_id FROM sales s WHERE s.id = comprehensive_returns.reference_id
+                ) WHERE return_type = 'SalesReturn' AND reference_id IN (SELECT id FROM sales);
+                CREATE INDEX IF NOT EXISTS idx_comprehensive_returns_organization
+                    ON comprehensive_returns(organization_id, created_at)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 87,
+            description: "scope_purchase_orders_by_organization",
+            sql: r#"
+                -- Purchase orders belong to an organization; existing ones to their products' one
+                ALTER TABLE purchase_orders ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1;
+                UPDATE purchase_orders SET organization_id = (
+                    SELECT p.organization_id FROM purchase_order_items poi
+                    JOIN products p ON p.id = poi.product_id
+                    WHERE poi.purchase_order_id = purchase_orders.id
+                    LIMIT 1
+                ) WHERE id IN (SELECT purchase_order_id FROM purchase_order_items);
+                CREATE INDEX IF NOT EXISTS idx_purchase_orders_organization
+                    ON purchase_orders(organization_id, order_date)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 88,
+            description: "scope_suppliers_by_organization",
+            sql: r#"
+                -- Suppliers belong to an organization; existing ones to that of their orders
+                ALTER TABLE suppliers ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1;
+                UPDATE suppliers SET organization_id = (
+                    SELECT po.organization_id FROM purchase_orders po
+                    WHERE po.supplier_id = suppliers.id
+                    ORDER BY po.id
+                    LIMIT 1
+                ) WHERE id IN (SELECT supplier_id FROM purchase_orders);
+                CREATE INDEX IF NOT EXISTS idx_suppliers_organization
+                    ON suppliers(organization_id, company_name)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 89,
+            description: "backfill_sale_payments",
+            sql: r#"
+                -- Every sale's tender is a sale_payments row; sales rung up in the store
+                -- currency before only had their payment_method
+                INSERT INTO sale_payments (sale_id, payment_method, currency, amount, exchange_rate,
+                                           base_amount, created_at)
+                SELECT s.id, s.payment_method, COALESCE(l.currency, 'USD'),
+                       s.total_amount + s.cash_rounding, 1.0, s.total_amount + s.cash_rounding,
+                       s.created_at
+                FROM sales s
+                LEFT JOIN locations l ON l.id = s.location_id
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM sale_payments sp WHERE sp.sale_id = s.id AND sp.return_id IS NULL
+                )
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 90,
+            description: "move_currency_and_cash_rounding_to_organization_settings",
+            sql: r#"
+                -- The currency and cash rounding are organization settings. Carry over what
+                -- the organization's locations had, then drop the old and never-read keys.
+                UPDATE organizations SET settings = json_set(
+                    CASE WHEN json_valid(settings) THEN settings ELSE '{}' END,
+                    '$.currency',
+                    COALESCE(
+                        (SELECT json_extract(a.value, '$') FROM app_settings a
+                         JOIN locations l ON l.id = a.scope_id
+                         WHERE a.key = 'currency' AND a.scope = 'location'
+                           AND l.organization_id = organizations.id
+                         ORDER BY l.id LIMIT 1),
+                        (SELECT l.currency FROM locations l
+                         WHERE l.organization_id = organizations.id AND l.currency IS NOT NULL
+                         ORDER BY l.id LIMIT 1),
+                        'USD'
+                    )
+                )
+                WHERE json_extract(
+                    CASE WHEN json_valid(settings) THEN settings ELSE '{}' END, '$.currency'
+                ) IS NULL;
+                UPDATE organizations SET settings = json_set(
+                    settings,
+                    '$.cash_rounding', json_extract(a.value, '$.increment'),
+                    '$.cash_rounding_direction', json_extract(a.value, '$.direction')
+                )
+                FROM app_settings a
+                JOIN locations l ON l.id = a.scope_id
+                WHERE a.key = 'cash_rounding' AND a.scope = 'location'
+                  AND l.organization_id = organizations.id
+                  AND json_valid(organizations.settings)
+                  AND json_extract(organizations.settings, '$.cash_rounding') IS NULL;
+                DELETE FROM app_settings
+                WHERE key IN ('currency', 'cash_rounding', 'allow_negative_stock', 'blind_close',
+                              'loyalty_earn_rate')
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 91,
+            description: "scope_audit_log_by_organization",
+            sql: r#"
+                -- Audit entries belong to the organization the change was made in; existing
+                -- ones to that of the sale, product, return or membership they are about
+                ALTER TABLE audit_log ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1;
+                UPDATE audit_log SET organization_id = (
+                    SELECT s.organization_id FROM sales s WHERE s.id = audit_log.entity_id
+                ) WHERE entity_type = 'sale' AND entity_id IN (SELECT id FROM sales);
+                UPDATE audit_log SET organization_id = (
+                    SELECT s.organization_id FROM sale_items si
+                    JOIN sales s ON s.id = si.sale_id
+                    WHERE si.id = audit_log.entity_id
+                ) WHERE entity_type = 'sale_item' AND entity_id IN (SELECT id FROM sale_items);
+                UPDATE audit_log SET organization_id = (
+                    SELECT p.organization_id FROM products p WHERE p.id = audit_log.entity_id
+                ) WHERE entity_type = 'product' AND entity_id IN (SELECT id FROM products);
+                UPDATE audit_log SET organization_id = (
+                    SELECT cr.organization_id FROM comprehensive_returns cr
+                    WHERE cr.id = audit_log.entity_id
+                ) WHERE entity_type = 'return'
+                  AND entity_id IN (SELECT id FROM comprehensive_returns);
+                UPDATE audit_log
+                SET organization_id = json_extract(before_json, '$.organization_id')
+                WHERE entity_type = 'organization_user' AND json_valid(before_json)
+                  AND json_extract(before_json, '$.organization_id') IS NOT NULL;
+                CREATE INDEX IF NOT EXISTS idx_audit_log_organization
+                    ON audit_log(organization_id, created_at)
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 92,
+            description: "scope_api_keys_by_organization",
+            sql: r#"
+                -- A REST API key reads and sells for one organization; existing keys for
+                -- the default one, which is what they used to reach
+                ALTER TABLE api_keys ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1
+            "#,
+            kind: MigrationKind::Up,
+        },
     ]
 }
+
+/// How long a connection waits for another one's write lock before giving up with
+/// "database is locked"
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connection settings for the app database. WAL lets reports read while the till writes,
+/// the busy timeout makes concurrent writers queue instead of failing, NORMAL sync is
+/// durable under WAL, and foreign keys are enforced.
+pub fn connect_options(conn_str: &str) -> Result<SqliteConnectOptions, String> {
+    Ok(SqliteConnectOptions::from_str(conn_str)
+        .map_err(|e| format!("Invalid database connection string '{}': {}", conn_str, e))?
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(BUSY_TIMEOUT)
+        .synchronous(SqliteSynchronous::Normal)
+        .foreign_keys(true))
+}
+
+/// Schema version of this build: the highest migration version
+pub fn schema_version() -> i64 {
+    get_migrations()
+        .iter()
+        .map(|mig| mig.version)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Schema version the database was last migrated to, from `PRAGMA user_version`.
+/// Databases from before the version was recorded report 0.
+pub async fn applied_schema_version(pool: &SqlitePool) -> Result<i64, String> {
+    sqlx::query_scalar("PRAGMA user_version")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to read schema version: {}", e))
+}
+
+/// Refuse a database migrated by a newer build, and log the migrations this build adds.
+/// Run before `apply_migrations` so an older binary never touches a future schema.
+/// `apply_migrations` still re-applies every migration, since they are idempotent and
+/// carry data fixups; only the new ones change the schema.
+pub async fn check_schema_version(pool: &SqlitePool) -> Result<(), String> {
+    let applied = applied_schema_version(pool).await?;
+    let expected = schema_version();
+    if applied > expected {
+        return Err(format!(
+            "The database is at schema version {} but this version of the app only supports up to {}. \
+             It was opened by a newer release; install that release or later to continue.",
+            applied, expected
+        ));
+    }
+    let pending: Vec<String> = get_migrations()
+        .into_iter()
+        .filter(|mig| mig.version > applied)
+        .map(|mig| format!("v{} {}", mig.version, mig.description))
+        .collect();
+    if pending.is_empty() {
+        println!(
+            "DEBUG(main): schema is current at v{}, re-applying idempotent migrations",
+            applied
+        );
+    } else {
+        println!(
+            "DEBUG(main): schema is at v{}, this build (v{}) adds: {}; re-applying all migrations",
+            applied,
+            expected,
+            pending.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Migrations that rebuild a table to widen a CHECK constraint, which SQLite cannot alter
+/// in place, with the table and a value only the rebuilt CHECK accepts. Copying the table
+/// again on every start would be slow and drop any column added after the rebuild, so each
+/// is skipped once the table's schema has the value.
+const TABLE_REBUILDS: &[(i64, &str, &str)] = &[
+    (51, "cash_drawer_transactions", "'no_sale'"),
+    (60, "receipt_templates", "'warehouse'"),
+    (75, "product_cost_history", "'recalculation'"),
+];
+
+/// Whether migration `version` rebuilds a table that has already been rebuilt
+async fn table_already_rebuilt(pool: &SqlitePool, version: i64) -> Result<bool, String> {
+    let (table, marker) = match TABLE_REBUILDS.iter().find(|(v, _, _)| *v == version) {
+        Some((_, table, marker)) => (table, marker),
+        None => return Ok(false),
+    };
+    let sql: Option<String> =
+        sqlx::query_scalar("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1")
+            .bind(table)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to read the schema of {}: {}", table, e))?;
+    Ok(matches!(sql, Some(sql) if sql.contains(marker)))
+}
+
+/// Apply migrations (runs all migration SQL statements)
+pub async fn apply_migrations(pool: &SqlitePool) -> Result<(), String> {
+    let migrations = get_migrations();
+    println!("DEBUG(main): applying {} migration(s)", migrations.len());
+
+    for mig in migrations {
+        if table_already_rebuilt(pool, mig.version).await? {
+            println!(
+                "DEBUG(main): skipping migration version {}: {} (table already rebuilt)",
+                mig.version, mig.description
+            );
+            continue;
+        }
+        println!(
+            "DEBUG(main): applying migration version {}: {}",
+            mig.version, mig.description
+        );
+
+        for stmt in split_statements(mig.sql) {
+            let s = stmt.as_str();
+            let preview = if s.len() > 80 { &s[..80] } else { s };
+            println!("DEBUG(main): executing statement (preview): {}", preview);
+
+            if let Err(e) = pool.execute(s).await {
+                // SQLite has no ADD COLUMN IF NOT EXISTS; the column is already there
+                if is_duplicate_column_error(s, &e) {
+                    continue;
+                }
+                return Err(format!(
+                    "Migration failed (v{}): {} -- stmt: {}",
+                    mig.version, e, preview
+                ));
+            }
+        }
+    }
+
+    // PRAGMA takes no bound parameters; the version is our own integer
+    pool.execute(format!("PRAGMA user_version = {}", schema_version()).as_str())
+        .await
+        .map_err(|e| format!("Failed to record schema version: {}", e))?;
+
+    println!("✅ DEBUG(main): migrations applied successfully");
+    Ok(())
+}
+
+/// Split a migration into its statements. A trigger body has statements of its own, so
+/// everything from `CREATE TRIGGER` up to its closing `END` stays one statement.
+fn split_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut trigger: Option<String> = None;
+    for part in sql.split(';') {
+        let part = part.trim();
+        if let Some(body) = trigger.as_mut() {
+            body.push_str(";\n");
+            body.push_str(part);
+            if part.eq_ignore_ascii_case("END") {
+                statements.extend(trigger.take());
+            }
+        } else if part.to_uppercase().starts_with("CREATE TRIGGER") {
+            trigger = Some(part.to_string());
+        } else if !part.is_empty() {
+            statements.push(part.to_string());
+        }
+    }
+    statements.extend(trigger);
+    statements
+}
+
+fn is_duplicate_column_error(stmt: &str, err: &sqlx::Error) -> bool {
+    let stmt = stmt.to_uppercase();
+    stmt.contains("ALTER TABLE")
+        && stmt.contains("ADD COLUMN")
+        && err.to_string().contains("duplicate column name")
+}
+
+/// A row whose foreign key points at a missing parent, from `PRAGMA foreign_key_check`
+#[derive(Debug, Clone, Serialize)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    pub rowid: Option<i64>,
+    pub parent: String,
+}
+
+/// Result of checking the database before relying on foreign key enforcement
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityReport {
+    /// True when both checks came back clean
+    pub ok: bool,
+    /// Problems reported by `PRAGMA integrity_check`, or a table that could not be checked
+    pub integrity_errors: Vec<String>,
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+}
+
+/// Run `PRAGMA integrity_check` and `PRAGMA foreign_key_check` on every table. Read only:
+/// it reports rows that enforcement would reject, it does not repair them.
+pub async fn integrity_report(pool: &SqlitePool) -> Result<IntegrityReport, String> {
+    let mut integrity_errors: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Integrity check failed: {}", e))?;
+    integrity_errors.retain(|message| message != "ok");
+
+    let tables: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master
+         WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND sql NOT LIKE 'CREATE VIRTUAL TABLE%'
+         ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list tables: {}", e))?;
+
+    // One table at a time, so a table whose key cannot be checked is reported
+    // instead of aborting the whole report
+    let mut foreign_key_violations = Vec::new();
+    for table in tables {
+        // PRAGMA takes no bound parameters; the name comes from sqlite_master
+        let check = format!("PRAGMA foreign_key_check(\"{}\")", table.replace('"', "\"\""));
+        let rows: Vec<(String, Option<i64>, String, i64)> = match sqlx::query_as(&check).fetch_all(pool).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                integrity_errors.push(format!("{}: {}", table, e));
+                continue;
+            }
+        };
+        foreign_key_violations.extend(rows.into_iter().map(|(table, rowid, parent, _)| ForeignKeyViolation {
+            table,
+            rowid,
+            parent,
+        }));
+    }
+
+    Ok(IntegrityReport {
+        ok: integrity_errors.is_empty() && foreign_key_violations.is_empty(),
+        integrity_errors,
+        foreign_key_violations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_pool;
+
+    #[tokio::test]
+    async fn test_money_columns_are_rounded_to_cents() {
+        let pool = test_pool().await;
+        sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id)
+             VALUES ('S-1', 104.99999999999999, 105.004, 'cash', 1)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        apply_migrations(&pool).await.unwrap();
+
+        let (subtotal, total): (f64, f64) =
+            sqlx::query_as("SELECT subtotal, total_amount FROM sales WHERE sale_number = 'S-1'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(subtotal, 105.0);
+        assert_eq!(total, 105.0);
+    }
+
+    #[tokio::test]
+    async fn test_migrations_can_be_reapplied() {
+        let pool = test_pool().await;
+        apply_migrations(&pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_table_rebuilds_run_once() {
+        let pool = test_pool().await;
+        for (_, table, _) in TABLE_REBUILDS {
+            pool.execute(format!("ALTER TABLE {} ADD COLUMN later_column TEXT", table).as_str())
+                .await
+                .unwrap();
+        }
+
+        apply_migrations(&pool).await.unwrap();
+
+        for (_, table, _) in TABLE_REBUILDS {
+            let columns: Vec<String> =
+                sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+                    .fetch_all(&pool)
+                    .await
+                    .unwrap();
+            assert!(
+                columns.iter().any(|column| column == "later_column"),
+                "{} was rebuilt",
+                table
+            );
+        }
+    }
+
+    #[test]
+    fn test_trigger_bodies_stay_one_statement() {
+        let statements = split_statements(
+            "CREATE TABLE t (a); CREATE TRIGGER tr AFTER INSERT ON t BEGIN DELETE FROM t; INSERT INTO t VALUES (1); END; DROP TABLE u",
+        );
+        assert_eq!(statements.len(), 3);
+        assert!(statements[1].starts_with("CREATE TRIGGER") && statements[1].ends_with("END"));
+        assert_eq!(statements[2], "DROP TABLE u");
+    }
+
+    #[tokio::test]
+    async fn test_database_from_a_newer_build_is_refused() {
+        let pool = test_pool().await;
+        assert_eq!(applied_schema_version(&pool).await.unwrap(), schema_version());
+        check_schema_version(&pool).await.unwrap();
+
+        pool.execute(format!("PRAGMA user_version = {}", schema_version() + 1).as_str())
+            .await
+            .unwrap();
+        let err = check_schema_version(&pool).await.unwrap_err();
+        assert!(err.contains(&format!("schema version {}", schema_version() + 1)));
+    }
+
+    #[tokio::test]
+    async fn test_integrity_report_lists_orphaned_rows() {
+        let pool = test_pool().await;
+        // Simulate a row written before foreign keys were enforced
+        pool.execute("PRAGMA foreign_keys = OFF").await.unwrap();
+        sqlx::query("INSERT INTO receipt_reprints (sale_id) VALUES (9999)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool.execute("PRAGMA foreign_keys = ON").await.unwrap();
+
+        let report = integrity_report(&pool).await.unwrap();
+        assert!(!report.ok);
+        assert!(report
+            .foreign_key_violations
+            .iter()
+            .any(|violation| violation.table == "receipt_reprints" && violation.parent == "sales"));
+    }
+}
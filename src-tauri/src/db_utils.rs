@@ -1,5 +1,7 @@
 use crate::error::{AppError, AppResult};
-use sqlx::{Pool, Sqlite, Transaction};
+use crate::models::{Page, PageCursor};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{Pool, QueryBuilder, Sqlite, Transaction};
 use std::future::Future;
 
 /// Execute a database operation with automatic rollback on error
@@ -62,6 +64,28 @@ where
     }
 }
 
+/// Whether `user_id` is an active Manager or Admin
+pub async fn is_manager(conn: &mut SqliteConnection, user_id: i64) -> AppResult<bool> {
+    let role: Option<String> =
+        sqlx::query_scalar("SELECT role FROM users WHERE id = ?1 AND is_active = 1")
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    Ok(matches!(role.as_deref(), Some("Admin") | Some("Manager")))
+}
+
+/// Check that `user_id` is an active Manager or Admin before they approve something.
+/// `action` completes the denial message, e.g. "approve a sale below cost".
+pub async fn require_manager(conn: &mut SqliteConnection, user_id: i64, action: &str) -> AppResult<()> {
+    if is_manager(conn, user_id).await? {
+        Ok(())
+    } else {
+        Err(AppError::PermissionDenied {
+            message: format!("Only a manager or admin can {}", action),
+        })
+    }
+}
+
 /// Check if a record exists
 pub async fn record_exists(
     pool: &Pool<Sqlite>,
@@ -83,12 +107,40 @@ pub async fn record_exists(
     Ok(count > 0)
 }
 
-/// Check if a record is referenced by other tables
-pub async fn check_references(
+/// Check that no other record in `table` uses `email` (case-insensitive), so a collision
+/// surfaces as a friendly error instead of the UNIQUE constraint failure
+pub async fn ensure_email_available(
     pool: &Pool<Sqlite>,
-    checks: Vec<(String, String, i64)>, // (table, column, id)
+    table: &str,
+    email: &str,
+    exclude_id: Option<i64>,
+) -> AppResult<()> {
+    let query = format!(
+        "SELECT COUNT(*) as count FROM {} WHERE LOWER(email) = LOWER(?1) AND id != ?2",
+        table
+    );
+
+    let count: i64 = sqlx::query_scalar(&query)
+        .bind(email.trim())
+        .bind(exclude_id.unwrap_or(0))
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    if count > 0 {
+        Err(AppError::email_in_use())
+    } else {
+        Ok(())
+    }
+}
+
+/// First of `references` (table, column) with a row pointing at `id`, if any
+pub async fn check_references(
+    conn: &mut SqliteConnection,
+    references: &[(&str, &str)],
+    id: i64,
 ) -> AppResult<Option<String>> {
-    for (table, column, id) in checks {
+    for (table, column) in references {
         let query = format!(
             "SELECT COUNT(*) as count FROM {} WHERE {} = ?",
             table, column
@@ -96,18 +148,37 @@ pub async fn check_references(
 
         let count: i64 = sqlx::query_scalar(&query)
             .bind(id)
-            .fetch_one(pool)
+            .fetch_one(&mut *conn)
             .await
             .map_err(|e| AppError::database_error(&e.to_string()))?;
 
         if count > 0 {
-            return Ok(Some(table));
+            return Ok(Some(table.to_string()));
         }
     }
 
     Ok(None)
 }
 
+/// Fail with a conflict if anything still references the record, pointing the user to
+/// archiving instead. `entity` names the record in the message, e.g. "Product".
+pub async fn require_unreferenced(
+    conn: &mut SqliteConnection,
+    entity: &str,
+    references: &[(&str, &str)],
+    id: i64,
+) -> AppResult<()> {
+    match check_references(conn, references, id).await? {
+        Some(table) => Err(AppError::Conflict {
+            message: format!(
+                "{} is still referenced by {} and cannot be deleted; archive it instead",
+                entity, table
+            ),
+        }),
+        None => Ok(()),
+    }
+}
+
 /// Generate a unique number for entities (sale_number, po_number, etc.)
 pub async fn generate_unique_number(
     pool: &Pool<Sqlite>,
@@ -208,10 +279,10 @@ where
                 }
 
                 // Check if error is retryable (database locked, connection issues)
-                let is_retryable = match &e.code[..] {
-                    "DB_001" | "DB_002" | "TXN_003" => true,
-                    _ => false,
-                };
+                let is_retryable = matches!(
+                    e,
+                    AppError::Database { .. } | AppError::ConcurrentModification
+                );
 
                 if !is_retryable {
                     return Err(e);
@@ -225,6 +296,95 @@ where
     }
 }
 
+/// How a newest-first list (`created_at DESC, id DESC`) is paged. Keyset paging seeks past
+/// the last row seen through the index, so a deep page costs the same as the first one;
+/// offset paging counts its way there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pagination {
+    Offset { limit: i64, offset: i64 },
+    Keyset { limit: i64, after: PageCursor },
+}
+
+impl Pagination {
+    /// Keyset paging when the caller passes the previous page's `next_cursor` as `after_id`
+    /// and `after_created_at`, offset paging otherwise
+    pub fn from_params(
+        limit: i64,
+        offset: Option<i32>,
+        after_id: Option<i64>,
+        after_created_at: Option<String>,
+    ) -> AppResult<Self> {
+        match (after_id, after_created_at) {
+            (None, None) => Ok(Pagination::Offset {
+                limit,
+                offset: i64::from(offset.unwrap_or(0)),
+            }),
+            (Some(id), Some(created_at)) => {
+                if offset.unwrap_or(0) != 0 {
+                    return Err(AppError::validation(
+                        "offset",
+                        "offset cannot be combined with after_id",
+                    ));
+                }
+                Ok(Pagination::Keyset {
+                    limit,
+                    after: PageCursor { created_at, id },
+                })
+            }
+            _ => Err(AppError::validation(
+                "after_id",
+                "after_id and after_created_at must be given together",
+            )),
+        }
+    }
+
+    pub fn limit(&self) -> i64 {
+        match self {
+            Pagination::Offset { limit, .. } | Pagination::Keyset { limit, .. } => *limit,
+        }
+    }
+
+    pub fn is_keyset(&self) -> bool {
+        matches!(self, Pagination::Keyset { .. })
+    }
+
+    /// Push `AND` the row comes after the cursor; nothing for offset paging
+    pub fn push_after(&self, query: &mut QueryBuilder<'_, Sqlite>, created_at_column: &str, id_column: &str) {
+        if let Pagination::Keyset { after, .. } = self {
+            query
+                .push(format!(" AND ({}, {}) < (", created_at_column, id_column))
+                .push_bind(after.created_at.clone())
+                .push(", ")
+                .push_bind(after.id)
+                .push(")");
+        }
+    }
+
+    /// Push `LIMIT`/`OFFSET` after the caller's `ORDER BY`. One row more than the page is
+    /// fetched so `into_page` can tell whether another page follows without counting.
+    pub fn push_limit(&self, query: &mut QueryBuilder<'_, Sqlite>) {
+        query.push(" LIMIT ").push_bind(self.limit() + 1);
+        if let Pagination::Offset { offset, .. } = self {
+            query.push(" OFFSET ").push_bind(*offset);
+        }
+    }
+
+    /// Drop the extra row `push_limit` asked for and wrap the rest as a page. `cursor`
+    /// gives a row's position, which becomes `next_cursor` when more rows follow.
+    pub fn into_page<T>(&self, mut items: Vec<T>, total_count: i64, cursor: impl Fn(&T) -> PageCursor) -> Page<T> {
+        let limit = usize::try_from(self.limit()).unwrap_or(0);
+        let has_more = items.len() > limit;
+        items.truncate(limit);
+        let next_cursor = if has_more { items.last().map(cursor) } else { None };
+        Page {
+            items,
+            total_count,
+            has_more,
+            next_cursor,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,4 +393,30 @@ mod tests {
     async fn test_generate_unique_number() {
         // Would need a test database setup
     }
+
+    #[test]
+    fn test_pagination_needs_the_whole_cursor() {
+        let keyset = Pagination::from_params(50, None, Some(7), Some("2026-01-01 10:00:00".to_string())).unwrap();
+        assert!(keyset.is_keyset());
+        assert_eq!(Pagination::from_params(50, Some(20), None, None).unwrap(), Pagination::Offset { limit: 50, offset: 20 });
+
+        let half = Pagination::from_params(50, None, Some(7), None).unwrap_err();
+        assert_eq!(half.code(), "VALIDATION_ERROR");
+        let both = Pagination::from_params(50, Some(20), Some(7), Some("2026-01-01 10:00:00".to_string())).unwrap_err();
+        assert_eq!(both.code(), "VALIDATION_ERROR");
+    }
+
+    #[test]
+    fn test_into_page_drops_the_lookahead_row() {
+        let pagination = Pagination::Offset { limit: 2, offset: 0 };
+        let cursor = |id: &i64| PageCursor { created_at: "2026-01-01".to_string(), id: *id };
+        let page = pagination.into_page(vec![3, 2, 1], 3, cursor);
+        assert_eq!(page.items, vec![3, 2]);
+        assert!(page.has_more);
+        assert_eq!(page.next_cursor.unwrap().id, 2);
+
+        let last = pagination.into_page(vec![1], 3, cursor);
+        assert!(!last.has_more);
+        assert!(last.next_cursor.is_none());
+    }
 }
@@ -0,0 +1,416 @@
+//! Demo mode: a store-wide switch sales reps turn on to show the app on a customer's own
+//! install. Sales, returns and expenses recorded while it is on are flagged `is_demo`,
+//! stay out of reports unless asked for, never reach the sync outbox or webhooks, and can
+//! be purged in one go. Operations that would overwrite the store's real data are refused.
+
+use crate::error::{AppError, AppResult};
+use crate::location_stock;
+use crate::measure;
+use crate::money::Money;
+use crate::seeder::TableRowCount;
+use crate::settings::{self, STORE_SETTINGS};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+pub const DEMO_MODE_SETTING: &str = "demo_mode";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DemoModeStatus {
+    pub enabled: bool,
+    /// Demo-flagged sales, returns and expenses still in the database
+    pub demo_records: i64,
+    /// Set when demo mode was turned off with demo records left behind
+    pub warning: Option<String>,
+}
+
+pub async fn is_enabled(pool: &SqlitePool) -> AppResult<bool> {
+    let enabled = settings::get_setting(pool, DEMO_MODE_SETTING, STORE_SETTINGS).await?;
+    Ok(enabled.unwrap_or(false))
+}
+
+/// Refuse `operation` while demo mode is on
+pub async fn require_live(pool: &SqlitePool, operation: &str) -> AppResult<()> {
+    if is_enabled(pool).await? {
+        return Err(AppError::Conflict {
+            message: format!("{} is not allowed in demo mode", operation),
+        });
+    }
+    Ok(())
+}
+
+async fn demo_records(pool: &SqlitePool) -> AppResult<i64> {
+    Ok(sqlx::query_scalar(
+        "SELECT (SELECT COUNT(*) FROM sales WHERE is_demo = 1)
+              + (SELECT COUNT(*) FROM comprehensive_returns WHERE is_demo = 1)
+              + (SELECT COUNT(*) FROM expenses WHERE is_demo = 1)",
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+pub async fn demo_mode_status(pool: &SqlitePool) -> AppResult<DemoModeStatus> {
+    Ok(DemoModeStatus {
+        enabled: is_enabled(pool).await?,
+        demo_records: demo_records(pool).await?,
+        warning: None,
+    })
+}
+
+/// Turn demo mode on or off. Turning it off with demo records left warns that they are
+/// still there until purged.
+pub async fn set_demo_mode(pool: &SqlitePool, enabled: bool) -> AppResult<DemoModeStatus> {
+    settings::set_setting(pool, DEMO_MODE_SETTING, STORE_SETTINGS, &enabled).await?;
+    let mut status = demo_mode_status(pool).await?;
+    if !enabled && status.demo_records > 0 {
+        status.warning = Some(format!(
+            "{} demo records are still in the database; purge them before trading for real",
+            status.demo_records
+        ));
+    }
+    Ok(status)
+}
+
+/// Rows of demo records and what depends on them, children before parents
+const PURGE_STATEMENTS: [(&str, &str); 13] = [
+    (
+        "inventory_movements",
+        "DELETE FROM inventory_movements
+         WHERE (reference_type IN ('sale', 'void') AND reference_id IN (SELECT id FROM sales WHERE is_demo = 1))
+            OR (reference_type = 'comprehensive_return'
+                AND reference_id IN (SELECT id FROM comprehensive_returns WHERE is_demo = 1))",
+    ),
+    (
+        "cash_drawer_transactions",
+        "DELETE FROM cash_drawer_transactions
+         WHERE transaction_type = 'refund'
+           AND EXISTS (SELECT 1 FROM comprehensive_returns cr
+                       WHERE cr.is_demo = 1 AND cr.shift_id = cash_drawer_transactions.shift_id
+                         AND cash_drawer_transactions.reason IN ('Refund ' || cr.return_number,
+                                                                 'Refund ' || cr.return_number || ' voided'))",
+    ),
+    (
+        "sale_payments",
+        "DELETE FROM sale_payments
+         WHERE sale_id IN (SELECT id FROM sales WHERE is_demo = 1)
+            OR return_id IN (SELECT id FROM comprehensive_returns WHERE is_demo = 1)",
+    ),
+    (
+        "comprehensive_return_items",
+        "DELETE FROM comprehensive_return_items
+         WHERE return_id IN (SELECT id FROM comprehensive_returns WHERE is_demo = 1)",
+    ),
+    ("comprehensive_returns", "DELETE FROM comprehensive_returns WHERE is_demo = 1"),
+    (
+        "returns",
+        "DELETE FROM returns WHERE original_sale_id IN (SELECT id FROM sales WHERE is_demo = 1)",
+    ),
+    (
+        "receipt_reprints",
+        "DELETE FROM receipt_reprints WHERE sale_id IN (SELECT id FROM sales WHERE is_demo = 1)",
+    ),
+    (
+        "promotion_usage",
+        "DELETE FROM promotion_usage WHERE sale_id IN (SELECT id FROM sales WHERE is_demo = 1)",
+    ),
+    (
+        "pending_sales",
+        "DELETE FROM pending_sales WHERE sale_id IN (SELECT id FROM sales WHERE is_demo = 1)",
+    ),
+    (
+        "loyalty_transactions",
+        "DELETE FROM loyalty_transactions WHERE sale_id IN (SELECT id FROM sales WHERE is_demo = 1)",
+    ),
+    (
+        "sale_items",
+        "DELETE FROM sale_items WHERE sale_id IN (SELECT id FROM sales WHERE is_demo = 1)",
+    ),
+    ("sales", "DELETE FROM sales WHERE is_demo = 1"),
+    ("expenses", "DELETE FROM expenses WHERE is_demo = 1"),
+];
+
+/// Remove every demo-flagged sale, return and expense with what depends on them, in one
+/// transaction. Stock the demo records moved goes back on hand and refunds paid out of a
+/// shift come off its returns total, so the real books read as if the demo never happened.
+pub async fn purge_demo_data(pool: &SqlitePool) -> AppResult<Vec<TableRowCount>> {
+    let mut tx = pool.begin().await?;
+
+    // Undo the stock movements of demo sales, their voids and demo returns, newest
+    // first. A returned variant's stock was moved on the variant, and a transfer leg at
+    // its own location.
+    let movements = sqlx::query(
+        "SELECT m.product_id, m.quantity_change, m.measured_change,
+                COALESCE(m.location_id, s.location_id) AS location_id,
+                CASE WHEN m.reference_type = 'comprehensive_return' AND m.movement_type != 'transfer' THEN
+                    (SELECT cri.product_variant_id FROM comprehensive_return_items cri
+                     WHERE cri.return_id = m.reference_id AND cri.product_id = m.product_id
+                       AND cri.product_variant_id IS NOT NULL
+                     LIMIT 1)
+                END AS product_variant_id
+         FROM inventory_movements m
+         LEFT JOIN sales s ON m.reference_type IN ('sale', 'void') AND s.id = m.reference_id
+         WHERE (m.reference_type IN ('sale', 'void') AND s.is_demo = 1)
+            OR (m.reference_type = 'comprehensive_return'
+                AND m.reference_id IN (SELECT id FROM comprehensive_returns WHERE is_demo = 1))
+         ORDER BY m.id DESC",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    for movement in movements {
+        let product_id: i64 = movement.try_get("product_id")?;
+        let quantity_change: i32 = movement.try_get("quantity_change")?;
+        let measured_change: Option<f64> = movement.try_get("measured_change")?;
+        let variant_id: Option<i64> = movement.try_get("product_variant_id")?;
+        match (variant_id, measured_change) {
+            (Some(variant_id), _) => {
+                sqlx::query(
+                    "UPDATE variant_inventory SET current_stock = current_stock - ?1,
+                        available_stock = available_stock - ?1, last_updated = CURRENT_TIMESTAMP
+                     WHERE product_variant_id = ?2",
+                )
+                .bind(quantity_change)
+                .bind(variant_id)
+                .execute(&mut *tx)
+                .await?;
+                continue;
+            }
+            (None, Some(measured)) => {
+                measure::adjust_measured_stock(&mut tx, product_id, -measured).await?;
+            }
+            (None, None) => {
+                sqlx::query(
+                    "UPDATE inventory SET current_stock = current_stock - ?1,
+                        available_stock = available_stock - ?1, last_updated = CURRENT_TIMESTAMP
+                     WHERE product_id = ?2",
+                )
+                .bind(quantity_change)
+                .bind(product_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        if let Some(location_id) = movement.try_get::<Option<i64>, _>("location_id")? {
+            location_stock::adjust_location_stock(
+                &mut tx,
+                product_id,
+                location_id,
+                -quantity_change,
+            )
+            .await?;
+        }
+    }
+
+    // Refunds still counted against their shift; voided ones were already taken back off
+    let refunds: Vec<(i64, Money)> = sqlx::query_as(
+        "SELECT shift_id, SUM(total_amount) FROM comprehensive_returns
+         WHERE is_demo = 1 AND shift_id IS NOT NULL AND refund_method IS NOT NULL
+           AND return_type = 'SalesReturn' AND status != 'Rejected'
+         GROUP BY shift_id",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    for (shift_id, amount) in refunds {
+        sqlx::query("UPDATE shifts SET total_returns = total_returns - ?1 WHERE id = ?2")
+            .bind(amount)
+            .bind(shift_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let mut removed = Vec::new();
+    for (table, statement) in PURGE_STATEMENTS {
+        let rows = sqlx::query(statement)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected() as i64;
+        if rows > 0 {
+            removed.push(TableRowCount {
+                table: table.to_string(),
+                rows,
+            });
+        }
+    }
+    tx.commit().await?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::reports::get_sales_report_internal;
+    use crate::commands::returns::{
+        create_return_internal, DispositionAction, NewReturn, ReturnCondition, ReturnItem,
+        ReturnReason, ReturnType,
+    };
+    use crate::commands::sales::create_sale_internal;
+    use crate::models::{CreateSaleRequest, SaleItemRequest};
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+
+    /// Units at 10.00, paid in cash
+    fn cash_sale(product_id: i64, quantity: i32) -> CreateSaleRequest {
+        let line_total = Money::from_major(10.0).times(quantity);
+        CreateSaleRequest {
+            items: vec![SaleItemRequest {
+                product_id,
+                quantity,
+                unit_price: Money::from_major(10.0),
+                discount_amount: Money::ZERO,
+                line_total,
+                measured_quantity: None,
+                price_override: None,
+                override_reason: None,
+            }],
+            subtotal: line_total,
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: line_total,
+            payment_method: "Cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        }
+    }
+
+    fn restocked_return(sale_id: i64, product_id: i64, user_id: i64) -> NewReturn {
+        let total = Money::from_major(10.0);
+        NewReturn {
+            return_type: ReturnType::SalesReturn,
+            reference_id: Some(sale_id),
+            reference_number: None,
+            supplier_id: None,
+            from_location_id: None,
+            to_location_id: None,
+            items: vec![ReturnItem {
+                product_id,
+                product_variant_id: None,
+                quantity: 1,
+                unit_price: total,
+                line_total: total,
+                reason: ReturnReason::Defective,
+                condition: ReturnCondition::Opened,
+                disposition: DispositionAction::Restock,
+                batch_number: None,
+                expiry_date: None,
+                notes: None,
+            }],
+            subtotal: total,
+            tax_amount: Money::ZERO,
+            total_amount: total,
+            refund_method: Some("cash".to_string()),
+            credit_method: None,
+            expected_credit_date: None,
+            reason: None,
+            notes: None,
+            user_id,
+            shift_id: None,
+            organization_id: DEFAULT_ORGANIZATION_ID,
+        }
+    }
+
+    async fn count(pool: &SqlitePool, query: &str, id: i64) -> i64 {
+        sqlx::query_scalar(query)
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_demo_records_stay_out_of_reports_and_purge_completely() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 10).await;
+        let real = create_sale_internal(&pool, cash_sale(widget, 2), cashier, None)
+            .await
+            .unwrap();
+
+        set_demo_mode(&pool, true).await.unwrap();
+        let demo = create_sale_internal(&pool, cash_sale(widget, 3), cashier, None)
+            .await
+            .unwrap();
+        create_return_internal(&pool, restocked_return(demo.id, widget, cashier))
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO expenses (expense_number, description, amount, expense_date, is_demo)
+             VALUES ('EXP-DEMO', 'Demo rent', 500, DATE('now'), 1)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(current_stock(&pool, widget).await, 6);
+        let err = require_live(&pool, "Restoring a backup").await.unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+
+        // Nothing about the demo sale was queued for the cloud
+        let queued = count(
+            &pool,
+            "SELECT COUNT(*) FROM sync_outbox WHERE entity_type = 'sale' AND entity_id = ?1",
+            demo.id,
+        )
+        .await;
+        assert_eq!(queued, 0);
+
+        // Reports leave the demo sale out unless asked for it
+        let report = get_sales_report_internal(&pool, DEFAULT_ORGANIZATION_ID, None, None, None, false)
+            .await
+            .unwrap();
+        assert_eq!(report.total_transactions, 1);
+        assert_eq!(report.total_sales, Money::from_minor(2000));
+        let with_demo = get_sales_report_internal(&pool, DEFAULT_ORGANIZATION_ID, None, None, None, true)
+            .await
+            .unwrap();
+        assert_eq!(with_demo.total_transactions, 2);
+
+        let status = set_demo_mode(&pool, false).await.unwrap();
+        assert_eq!(status.demo_records, 3);
+        assert!(status.warning.is_some());
+
+        let removed = purge_demo_data(&pool).await.unwrap();
+        let rows = |table: &str| {
+            removed
+                .iter()
+                .find(|r| r.table == table)
+                .map_or(0, |r| r.rows)
+        };
+        assert_eq!(rows("sales"), 1);
+        assert_eq!(rows("comprehensive_returns"), 1);
+        assert_eq!(rows("expenses"), 1);
+
+        // The real sale and its stock are all that is left
+        assert_eq!(current_stock(&pool, widget).await, 8);
+        let sales: Vec<i64> = sqlx::query_scalar("SELECT id FROM sales")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(sales, vec![real.id]);
+        let movements = count(
+            &pool,
+            "SELECT COUNT(*) FROM inventory_movements WHERE reference_id = ?1 AND reference_type = 'sale'",
+            demo.id,
+        )
+        .await;
+        assert_eq!(movements, 0);
+        assert_eq!(
+            count(
+                &pool,
+                "SELECT COUNT(*) FROM sale_items WHERE sale_id = ?1",
+                demo.id
+            )
+            .await,
+            0
+        );
+        let status = demo_mode_status(&pool).await.unwrap();
+        assert_eq!((status.enabled, status.demo_records), (false, 0));
+        assert!(status.warning.is_none());
+    }
+}
@@ -0,0 +1,148 @@
+//! Snapshot of the app's state for support: where the database lives, how big it is, which
+//! schema it is on and whether it is set up the way the app expects. Users paste it into
+//! bug reports, so it holds no business data beyond row counts.
+
+use crate::database::{applied_schema_version, schema_version};
+use crate::error::AppResult;
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+/// Tables whose row counts are included in the report
+const COUNTED_TABLES: [&str; 3] = ["products", "sales", "customers"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableCount {
+    pub table: String,
+    pub rows: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemDiagnostics {
+    pub app_version: String,
+    /// `None` for an in-memory database
+    pub database_path: Option<String>,
+    pub database_size_bytes: Option<u64>,
+    /// Size of the `-wal` file next to the database, when there is one
+    pub wal_size_bytes: Option<u64>,
+    /// Schema version the database was migrated to
+    pub schema_version: i64,
+    /// Schema version this build migrates to
+    pub expected_schema_version: i64,
+    pub journal_mode: String,
+    pub wal_enabled: bool,
+    pub foreign_keys_enabled: bool,
+    pub table_counts: Vec<TableCount>,
+    /// Local changes not yet pushed to the cloud, failed ones included
+    pub pending_sync_count: i64,
+    /// Offline sales still waiting to be replayed
+    pub queued_offline_sales: i64,
+    /// Misconfigurations found, in plain words
+    pub warnings: Vec<String>,
+}
+
+fn file_size(path: &str) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|meta| meta.len())
+}
+
+pub async fn system_diagnostics(pool: &SqlitePool) -> AppResult<SystemDiagnostics> {
+    // `file` is empty for an in-memory database
+    let (_, _, file): (i64, String, String) = sqlx::query_as("PRAGMA database_list")
+        .fetch_one(pool)
+        .await?;
+    let database_path = Some(file).filter(|file| !file.is_empty());
+    let database_size_bytes = database_path.as_deref().and_then(file_size);
+    let wal_size_bytes = database_path
+        .as_deref()
+        .and_then(|path| file_size(&format!("{}-wal", path)));
+
+    let applied = applied_schema_version(pool).await?;
+    let expected = schema_version();
+    let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+        .fetch_one(pool)
+        .await?;
+    let wal_enabled = journal_mode.eq_ignore_ascii_case("wal");
+    let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys")
+        .fetch_one(pool)
+        .await?;
+
+    let mut table_counts = Vec::with_capacity(COUNTED_TABLES.len());
+    for table in COUNTED_TABLES {
+        // Table names come from the constant above
+        let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(pool)
+            .await?;
+        table_counts.push(TableCount {
+            table: table.to_string(),
+            rows,
+        });
+    }
+
+    let pending_sync_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM sync_outbox WHERE status IN ('pending', 'error')")
+            .fetch_one(pool)
+            .await?;
+    let queued_offline_sales: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pending_sales WHERE status IN ('pending', 'failed')",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let mut warnings = Vec::new();
+    if database_path.is_some() && !wal_enabled {
+        warnings.push(format!(
+            "Journal mode is {}, not WAL: concurrent sales may fail with \"database is locked\"",
+            journal_mode
+        ));
+    }
+    if foreign_keys == 0 {
+        warnings.push("Foreign key enforcement is off".to_string());
+    }
+    if applied != expected {
+        warnings.push(format!(
+            "Database is at schema version {} but this build expects {}",
+            applied, expected
+        ));
+    }
+
+    Ok(SystemDiagnostics {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        database_path,
+        database_size_bytes,
+        wal_size_bytes,
+        schema_version: applied,
+        expected_schema_version: expected,
+        journal_mode,
+        wal_enabled,
+        foreign_keys_enabled: foreign_keys != 0,
+        table_counts,
+        pending_sync_count,
+        queued_offline_sales,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_product, test_pool};
+
+    #[tokio::test]
+    async fn test_diagnostics_report_counts_and_schema() {
+        let pool = test_pool().await;
+        insert_test_product(&pool, "DIAG-1", 10.0, 5).await;
+        insert_test_product(&pool, "DIAG-2", 10.0, 5).await;
+
+        let report = system_diagnostics(&pool).await.unwrap();
+        assert_eq!(report.database_path, None);
+        assert_eq!(report.schema_version, schema_version());
+        assert!(report.foreign_keys_enabled);
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+        let products = report
+            .table_counts
+            .iter()
+            .find(|count| count.table == "products")
+            .unwrap();
+        assert_eq!(products.rows, 2);
+        assert_eq!(report.app_version, env!("CARGO_PKG_VERSION"));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::barcode::BARCODE_SEQUENCE;
+use crate::error::{AppError, AppResult};
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Sqlite, SqlitePool};
+
+/// Counter behind one kind of document number, seeded in the `document_sequences` table
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DocumentSequence {
+    pub doc_type: String,
+    pub prefix: String,
+    pub next_value: i64,
+    pub year: i64,
+    /// Start again at 1 on the first document of each year
+    pub reset_yearly: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateDocumentSequenceRequest {
+    pub prefix: Option<String>,
+    pub reset_yearly: Option<bool>,
+}
+
+/// Draws of a fresh number before giving up when each one is already taken
+pub const DOCUMENT_NUMBER_ATTEMPTS: u32 = 5;
+
+/// Format a document number, e.g. `SALE-2024-000123`
+pub fn format_document_number(prefix: &str, year: i32, value: i64) -> String {
+    format!("{}-{}-{:06}", prefix, year, value)
+}
+
+/// Take the next number for `doc_type` in a single UPDATE so concurrent callers never share one.
+/// Pass the open transaction when the document is created in one, so a rollback gives the
+/// number back.
+pub async fn next_document_number<'e, E>(executor: E, doc_type: &str) -> AppResult<String>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let (prefix, year, value) = take_next_value(executor, doc_type).await?;
+    Ok(format_document_number(&prefix, year, value))
+}
+
+/// Take the next value of a counter for numbers that are not `PREFIX-YEAR-NNNNNN`
+/// documents, such as barcodes. Returns the prefix and the value.
+pub async fn next_sequence_value<'e, E>(executor: E, doc_type: &str) -> AppResult<(String, i64)>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let (prefix, _, value) = take_next_value(executor, doc_type).await?;
+    Ok((prefix, value))
+}
+
+async fn take_next_value<'e, E>(executor: E, doc_type: &str) -> AppResult<(String, i32, i64)>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let year = chrono::Local::now().year();
+
+    // SET expressions see the old row, so `year != ?2` compares against the last document's year
+    let row: Option<(String, i64)> = sqlx::query_as(
+        "UPDATE document_sequences
+         SET next_value = CASE WHEN reset_yearly AND year != ?2 THEN 2 ELSE next_value + 1 END,
+             year = ?2,
+             updated_at = CURRENT_TIMESTAMP
+         WHERE doc_type = ?1
+         RETURNING prefix, next_value - 1",
+    )
+    .bind(doc_type)
+    .bind(year)
+    .fetch_optional(executor)
+    .await?;
+
+    match row {
+        Some((prefix, value)) => Ok((prefix, year, value)),
+        None => Err(AppError::not_found(&format!(
+            "Document sequence '{}'",
+            doc_type
+        ))),
+    }
+}
+
+/// Whether an insert failed because the drawn number is already on `table.column`, e.g. a
+/// record synced in from another till or a sequence moved back by hand
+pub fn is_duplicate_number(err: &sqlx::Error, table: &str, column: &str) -> bool {
+    match err {
+        sqlx::Error::Database(db_err) => {
+            db_err.is_unique_violation()
+                && db_err.message().ends_with(&format!("{}.{}", table, column))
+        }
+        _ => false,
+    }
+}
+
+pub async fn get_document_sequences(pool: &SqlitePool) -> AppResult<Vec<DocumentSequence>> {
+    let sequences = sqlx::query_as::<_, DocumentSequence>(
+        "SELECT doc_type, prefix, next_value, year, reset_yearly
+         FROM document_sequences
+         ORDER BY doc_type",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(sequences)
+}
+
+pub async fn update_document_sequence(
+    pool: &SqlitePool,
+    doc_type: &str,
+    request: &UpdateDocumentSequenceRequest,
+) -> AppResult<DocumentSequence> {
+    if let Some(prefix) = &request.prefix {
+        let valid = !prefix.is_empty()
+            && prefix.len() <= 10
+            && prefix.chars().all(|c| c.is_ascii_alphanumeric());
+        if !valid {
+            return Err(AppError::validation(
+                "prefix",
+                "Prefix must be 1-10 letters or digits",
+            ));
+        }
+        // Barcode prefixes lead a 12-digit EAN-13 body
+        let valid_barcode = (2..=6).contains(&prefix.len()) && prefix.chars().all(|c| c.is_ascii_digit());
+        if doc_type == BARCODE_SEQUENCE && !valid_barcode {
+            return Err(AppError::validation(
+                "prefix",
+                "Barcode prefix must be 2-6 digits",
+            ));
+        }
+    }
+
+    let sequence = sqlx::query_as::<_, DocumentSequence>(
+        "UPDATE document_sequences
+         SET prefix = COALESCE(?2, prefix),
+             reset_yearly = COALESCE(?3, reset_yearly),
+             updated_at = CURRENT_TIMESTAMP
+         WHERE doc_type = ?1
+         RETURNING doc_type, prefix, next_value, year, reset_yearly",
+    )
+    .bind(doc_type)
+    .bind(request.prefix.as_ref().map(|p| p.to_uppercase()))
+    .bind(request.reset_yearly)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::not_found(&format!("Document sequence '{}'", doc_type)))?;
+    Ok(sequence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_pool;
+
+    fn this_year() -> i32 {
+        chrono::Local::now().year()
+    }
+
+    #[tokio::test]
+    async fn test_numbers_are_sequential_per_doc_type() {
+        let pool = test_pool().await;
+        let year = this_year();
+
+        assert_eq!(
+            next_document_number(&pool, "sale").await.unwrap(),
+            format!("SALE-{}-000001", year)
+        );
+        assert_eq!(
+            next_document_number(&pool, "sale").await.unwrap(),
+            format!("SALE-{}-000002", year)
+        );
+        assert_eq!(
+            next_document_number(&pool, "purchase_order").await.unwrap(),
+            format!("PO-{}-000001", year)
+        );
+
+        let err = next_document_number(&pool, "invoice").await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_rolled_back_number_is_reused() {
+        let pool = test_pool().await;
+
+        let mut tx = pool.begin().await.unwrap();
+        let first = next_document_number(&mut *tx, "expense").await.unwrap();
+        tx.rollback().await.unwrap();
+
+        assert_eq!(next_document_number(&pool, "expense").await.unwrap(), first);
+    }
+
+    #[tokio::test]
+    async fn test_yearly_reset_only_when_configured() {
+        let pool = test_pool().await;
+        let year = this_year();
+        sqlx::query("UPDATE document_sequences SET next_value = 42, year = ?1 - 1")
+            .bind(year)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            next_document_number(&pool, "sale").await.unwrap(),
+            format!("SALE-{}-000042", year)
+        );
+
+        let request = UpdateDocumentSequenceRequest {
+            prefix: Some("inv".to_string()),
+            reset_yearly: Some(true),
+        };
+        let sequence = update_document_sequence(&pool, "expense", &request)
+            .await
+            .unwrap();
+        assert_eq!(sequence.prefix, "INV");
+        assert_eq!(
+            next_document_number(&pool, "expense").await.unwrap(),
+            format!("INV-{}-000001", year)
+        );
+        assert_eq!(
+            next_document_number(&pool, "expense").await.unwrap(),
+            format!("INV-{}-000002", year)
+        );
+
+        let bad = UpdateDocumentSequenceRequest {
+            prefix: Some("NO SPACES".to_string()),
+            reset_yearly: None,
+        };
+        let err = update_document_sequence(&pool, "sale", &bad).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+}
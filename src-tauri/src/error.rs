@@ -1,195 +1,446 @@
-use serde::{Deserialize, Serialize};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 use std::fmt;
 
-/// Custom error types for the application
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AppError {
-    pub code: String,
-    pub message: String,
-    pub details: Option<String>,
+/// Application error returned by commands.
+///
+/// Serialized to the frontend as `{ code, message, field?, details? }` where `code` is stable
+/// and safe to branch on (e.g. `NOT_FOUND`, `CONFLICT`, `VALIDATION_ERROR`).
+#[derive(Debug, Clone)]
+pub enum AppError {
+    NotFound {
+        resource: String,
+    },
+    Validation {
+        field: Option<String>,
+        message: String,
+    },
+    Conflict {
+        message: String,
+    },
+    PermissionDenied {
+        message: String,
+    },
+    /// Authentication and session failures; `code` distinguishes the reason
+    Auth {
+        code: &'static str,
+        message: String,
+    },
+    InsufficientStock {
+        product: String,
+        available: i32,
+        requested: i32,
+    },
+    /// Another writer changed the record first; safe to retry
+    ConcurrentModification,
+    /// The organization's subscription plan allows no more of `limit`
+    PlanLimitExceeded {
+        plan: String,
+        limit: String,
+        max: i64,
+    },
+    /// The organization's trial has ended; only billing and settings can change
+    TrialExpired,
+    Database {
+        message: String,
+    },
+    Internal {
+        message: String,
+    },
 }
 
 impl AppError {
-    pub fn new(code: &str, message: &str) -> Self {
-        Self {
-            code: code.to_string(),
-            message: message.to_string(),
-            details: None,
+    /// Stable machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound { .. } => "NOT_FOUND",
+            AppError::Validation { .. } => "VALIDATION_ERROR",
+            AppError::Conflict { .. } => "CONFLICT",
+            AppError::PermissionDenied { .. } => "PERMISSION_DENIED",
+            AppError::Auth { code, .. } => code,
+            AppError::InsufficientStock { .. } => "INSUFFICIENT_STOCK",
+            AppError::ConcurrentModification => "CONCURRENT_MODIFICATION",
+            AppError::PlanLimitExceeded { .. } => "PLAN_LIMIT_EXCEEDED",
+            AppError::TrialExpired => "TRIAL_EXPIRED",
+            AppError::Database { .. } => "DATABASE_ERROR",
+            AppError::Internal { .. } => "INTERNAL_ERROR",
         }
     }
 
-    pub fn with_details(code: &str, message: &str, details: &str) -> Self {
-        Self {
-            code: code.to_string(),
-            message: message.to_string(),
-            details: Some(details.to_string()),
+    /// Human-readable message
+    pub fn message(&self) -> String {
+        match self {
+            AppError::NotFound { resource } => format!("{} not found", capitalize(resource)),
+            AppError::Validation { message, .. }
+            | AppError::Conflict { message }
+            | AppError::PermissionDenied { message }
+            | AppError::Auth { message, .. }
+            | AppError::Database { message }
+            | AppError::Internal { message } => message.clone(),
+            AppError::InsufficientStock {
+                product,
+                available,
+                requested,
+            } => format!(
+                "Insufficient stock for {}. Available: {}, Requested: {}",
+                product, available, requested
+            ),
+            AppError::ConcurrentModification => {
+                "Concurrent modification detected. Please retry".to_string()
+            }
+            AppError::PlanLimitExceeded { plan, limit, max } => format!(
+                "The {} plan allows at most {} {}. Upgrade the plan to add more",
+                plan,
+                max,
+                limit.replace('_', " ")
+            ),
+            AppError::TrialExpired => {
+                "The trial has ended. Choose a plan to keep making changes".to_string()
+            }
+        }
+    }
+
+    fn details(&self) -> Option<String> {
+        match self {
+            AppError::NotFound { resource } => Some(resource.clone()),
+            AppError::InsufficientStock { product, .. } => Some(product.clone()),
+            AppError::PlanLimitExceeded { limit, .. } => Some(limit.clone()),
+            _ => None,
+        }
+    }
+
+    fn field(&self) -> Option<&str> {
+        match self {
+            AppError::Validation { field, .. } => field.as_deref(),
+            _ => None,
         }
     }
 
     // Authentication errors
     pub fn invalid_credentials() -> Self {
-        Self::new("AUTH_001", "Invalid username or password")
+        Self::auth("INVALID_CREDENTIALS", "Invalid username or password")
     }
 
     pub fn session_expired() -> Self {
-        Self::new("AUTH_002", "Session has expired. Please log in again")
+        Self::auth("SESSION_EXPIRED", "Session has expired. Please log in again")
     }
 
     pub fn session_invalid() -> Self {
-        Self::new("AUTH_003", "Invalid session token")
+        Self::auth("SESSION_INVALID", "Invalid session token")
+    }
+
+    pub fn api_key_invalid() -> Self {
+        Self::auth("API_KEY_INVALID", "Missing or invalid API key")
     }
 
     pub fn rate_limit_exceeded() -> Self {
-        Self::new("AUTH_004", "Too many login attempts. Please try again later")
+        Self::auth(
+            "RATE_LIMITED",
+            "Too many login attempts. Please try again later",
+        )
     }
 
     pub fn weak_password() -> Self {
-        Self::new("AUTH_005", "Password does not meet complexity requirements")
+        Self::validation(
+            "password",
+            "Password does not meet complexity requirements",
+        )
     }
 
     pub fn user_inactive() -> Self {
-        Self::new("AUTH_006", "User account is inactive")
+        Self::auth("USER_INACTIVE", "User account is inactive")
+    }
+
+    pub fn password_login_required() -> Self {
+        AppError::PermissionDenied {
+            message: "This action requires signing in with your password".to_string(),
+        }
+    }
+
+    pub fn reset_code_invalid() -> Self {
+        Self::auth("RESET_CODE_INVALID", "Invalid username or reset code")
+    }
+
+    pub fn reset_code_used() -> Self {
+        Self::auth("RESET_CODE_USED", "This reset code has already been used")
+    }
+
+    /// Too many wrong reset codes or usernames; `locked_until` is shown as UTC
+    pub fn reset_locked(locked_until: &str) -> Self {
+        Self::auth(
+            "RESET_LOCKED",
+            &format!("Too many failed attempts. Try again after {} UTC", locked_until),
+        )
+    }
+
+    pub fn reset_code_expired() -> Self {
+        Self::auth(
+            "RESET_CODE_EXPIRED",
+            "This reset code has expired. Ask an administrator for a new one",
+        )
+    }
+
+    fn auth(code: &'static str, message: &str) -> Self {
+        AppError::Auth {
+            code,
+            message: message.to_string(),
+        }
     }
 
     // Validation errors
     pub fn validation_error(message: &str) -> Self {
-        Self::new("VAL_001", message)
+        AppError::Validation {
+            field: None,
+            message: message.to_string(),
+        }
+    }
+
+    /// Validation error tied to a specific input field
+    pub fn validation(field: &str, message: &str) -> Self {
+        AppError::Validation {
+            field: Some(field.to_string()),
+            message: message.to_string(),
+        }
     }
 
     pub fn duplicate_entry(field: &str) -> Self {
-        Self::with_details("VAL_002", "Duplicate entry found", field)
+        AppError::Conflict {
+            message: format!("A record with this {} already exists", field),
+        }
+    }
+
+    pub fn email_in_use() -> Self {
+        AppError::Conflict {
+            message: "Email already in use".to_string(),
+        }
     }
 
     pub fn not_found(resource: &str) -> Self {
-        Self::with_details("VAL_003", "Resource not found", resource)
+        AppError::NotFound {
+            resource: resource.to_string(),
+        }
     }
 
     pub fn invalid_format(field: &str) -> Self {
-        Self::with_details("VAL_004", "Invalid format", field)
+        Self::validation(field, &format!("Invalid {} format", field))
     }
 
     pub fn negative_value(field: &str) -> Self {
-        Self::with_details("VAL_005", "Value cannot be negative", field)
+        Self::validation(field, &format!("{} cannot be negative", field))
     }
 
     // Inventory errors
     pub fn insufficient_stock(product: &str, available: i32, requested: i32) -> Self {
-        Self::with_details(
-            "INV_001",
-            &format!("Insufficient stock. Available: {}, Requested: {}", available, requested),
-            product,
-        )
+        AppError::InsufficientStock {
+            product: product.to_string(),
+            available,
+            requested,
+        }
     }
 
     pub fn product_inactive(product: &str) -> Self {
-        Self::with_details("INV_002", "Product is inactive", product)
+        Self::validation("product", &format!("Product {} is inactive", product))
     }
 
     pub fn inventory_not_found(product_id: i64) -> Self {
-        Self::with_details(
-            "INV_003",
-            "Inventory record not found",
-            &product_id.to_string(),
-        )
+        Self::not_found(&format!("inventory record for product {}", product_id))
     }
 
     // Transaction errors
     pub fn transaction_failed(reason: &str) -> Self {
-        Self::with_details("TXN_001", "Transaction failed", reason)
+        Self::database_error(&format!("Transaction failed: {}", reason))
     }
 
     pub fn rollback_failed() -> Self {
-        Self::new("TXN_002", "Failed to rollback transaction")
+        Self::database_error("Failed to rollback transaction")
     }
 
     pub fn concurrent_modification() -> Self {
-        Self::new("TXN_003", "Concurrent modification detected. Please retry")
+        AppError::ConcurrentModification
     }
 
     // Sales errors
     pub fn sale_already_voided() -> Self {
-        Self::new("SALE_001", "Sale has already been voided")
+        AppError::Conflict {
+            message: "Sale has already been voided".to_string(),
+        }
     }
 
     pub fn sale_not_found() -> Self {
-        Self::new("SALE_002", "Sale not found")
+        Self::not_found("sale")
     }
 
     pub fn invalid_payment_amount() -> Self {
-        Self::new("SALE_003", "Payment amount is invalid")
+        Self::validation("amount_paid", "Payment amount is invalid")
     }
 
     // Shift errors
     pub fn shift_already_open() -> Self {
-        Self::new("SHIFT_001", "A shift is already open for this user")
+        AppError::Conflict {
+            message: "A shift is already open for this user".to_string(),
+        }
     }
 
     pub fn shift_not_found() -> Self {
-        Self::new("SHIFT_002", "Shift not found or already closed")
+        Self::not_found("open shift")
     }
 
     pub fn shift_has_discrepancy(expected: f64, actual: f64) -> Self {
-        Self::with_details(
-            "SHIFT_003",
-            &format!("Cash drawer discrepancy. Expected: {:.2}, Actual: {:.2}", expected, actual),
-            &format!("Difference: {:.2}", actual - expected),
+        Self::validation(
+            "actual_cash",
+            &format!(
+                "Cash drawer discrepancy. Expected: {:.2}, Actual: {:.2}",
+                expected, actual
+            ),
         )
     }
 
     // Reference integrity errors
     pub fn referenced_by_other_records(resource: &str, references: &str) -> Self {
-        Self::with_details(
-            "REF_001",
-            &format!("Cannot delete {}. Referenced by {}", resource, references),
-            references,
-        )
+        AppError::Conflict {
+            message: format!("Cannot delete {}. Referenced by {}", resource, references),
+        }
     }
 
     // Database errors
     pub fn database_error(message: &str) -> Self {
-        Self::new("DB_001", message)
+        AppError::Database {
+            message: message.to_string(),
+        }
     }
 
     pub fn connection_failed() -> Self {
-        Self::new("DB_002", "Database connection failed")
+        Self::database_error("Database connection failed")
     }
 
     pub fn query_timeout() -> Self {
-        Self::new("DB_003", "Query execution timeout")
+        Self::database_error("Query execution timeout")
     }
 
     // General errors
     pub fn internal_error() -> Self {
-        Self::new("SYS_001", "An internal error occurred. Please try again")
+        AppError::Internal {
+            message: "An internal error occurred. Please try again".to_string(),
+        }
     }
 
     pub fn operation_timeout() -> Self {
-        Self::new("SYS_002", "Operation timed out")
+        AppError::Internal {
+            message: "Operation timed out".to_string(),
+        }
     }
 
     pub fn permission_denied() -> Self {
-        Self::new("SYS_003", "Permission denied")
+        AppError::PermissionDenied {
+            message: "Permission denied".to_string(),
+        }
+    }
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
     }
 }
 
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "[{}] {}", self.code, self.message)?;
-        if let Some(details) = &self.details {
-            write!(f, " ({})", details)?;
-        }
-        Ok(())
+        write!(f, "[{}] {}", self.code(), self.message())
     }
 }
 
 impl std::error::Error for AppError {}
 
-// Convert to string for Tauri commands
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.message())?;
+        match self.field() {
+            Some(field) => state.serialize_field("field", field)?,
+            None => state.skip_field("field")?,
+        }
+        match self.details() {
+            Some(details) => state.serialize_field("details", &details)?,
+            None => state.skip_field("details")?,
+        }
+        state.end()
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        match &err {
+            sqlx::Error::RowNotFound => AppError::not_found("record"),
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+                // SQLite reports e.g. "UNIQUE constraint failed: products.sku"
+                let field = db_err
+                    .message()
+                    .rsplit('.')
+                    .next()
+                    .unwrap_or("value")
+                    .trim()
+                    .to_string();
+                AppError::duplicate_entry(&field)
+            }
+            sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation() => {
+                AppError::Conflict {
+                    message: "Operation conflicts with related records".to_string(),
+                }
+            }
+            _ => AppError::database_error(&err.to_string()),
+        }
+    }
+}
+
+impl From<bcrypt::BcryptError> for AppError {
+    fn from(err: bcrypt::BcryptError) -> Self {
+        AppError::Internal {
+            message: format!("Password hashing error: {}", err),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::Internal {
+            message: format!("File error: {}", err),
+        }
+    }
+}
+
+// Fallback shims while commands are ported from `Result<T, String>`: unported commands can
+// still `?` an AppError (serialized as JSON), and ported ones can `?` String-error helpers.
 impl From<AppError> for String {
     fn from(err: AppError) -> String {
-        serde_json::to_string(&err).unwrap_or_else(|_| err.message)
+        serde_json::to_string(&err).unwrap_or_else(|_| err.message())
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal { message }
     }
 }
 
 // Helper type for Results
 pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_stable_code() {
+        let json = serde_json::to_value(AppError::not_found("sale")).unwrap();
+        assert_eq!(json["code"], "NOT_FOUND");
+        assert_eq!(json["message"], "Sale not found");
+        assert_eq!(json["details"], "sale");
+
+        let json = serde_json::to_value(AppError::validation("sku", "Invalid SKU format")).unwrap();
+        assert_eq!(json["code"], "VALIDATION_ERROR");
+        assert_eq!(json["field"], "sku");
+        assert!(json.get("details").is_none());
+    }
+}
@@ -0,0 +1,362 @@
+//! Receipt reprints and the per-cashier indicators auditors watch for: void rate, reprint
+//! rate, no-sale drawer opens and discount share. Indicators above the store's thresholds
+//! raise warning notifications.
+
+use crate::error::AppResult;
+use crate::settings::{self, STORE_SETTINGS};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+
+pub const VOID_RATE_SETTING: &str = "fraud_void_rate_percent";
+pub const REPRINT_RATE_SETTING: &str = "fraud_reprint_rate_percent";
+pub const NO_SALE_LIMIT_SETTING: &str = "fraud_no_sale_limit";
+pub const DISCOUNT_SETTING: &str = "fraud_discount_percent";
+
+/// Levels above which an indicator is flagged, from the store settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FraudThresholds {
+    /// Voided sales as a percentage of sales rung up
+    pub void_rate_percent: f64,
+    /// Reprints as a percentage of sales rung up
+    pub reprint_rate_percent: f64,
+    /// No-sale drawer opens over the period
+    pub no_sale_limit: i64,
+    /// Discounts as a percentage of the subtotal of sales kept
+    pub discount_percent: f64,
+}
+
+impl Default for FraudThresholds {
+    fn default() -> Self {
+        Self {
+            void_rate_percent: 5.0,
+            reprint_rate_percent: 10.0,
+            no_sale_limit: 10,
+            discount_percent: 15.0,
+        }
+    }
+}
+
+impl FraudThresholds {
+    pub async fn load(pool: &SqlitePool) -> AppResult<Self> {
+        let defaults = Self::default();
+        Ok(Self {
+            void_rate_percent: settings::get_setting(pool, VOID_RATE_SETTING, STORE_SETTINGS)
+                .await?
+                .unwrap_or(defaults.void_rate_percent),
+            reprint_rate_percent: settings::get_setting(pool, REPRINT_RATE_SETTING, STORE_SETTINGS)
+                .await?
+                .unwrap_or(defaults.reprint_rate_percent),
+            no_sale_limit: settings::get_setting(pool, NO_SALE_LIMIT_SETTING, STORE_SETTINGS)
+                .await?
+                .unwrap_or(defaults.no_sale_limit),
+            discount_percent: settings::get_setting(pool, DISCOUNT_SETTING, STORE_SETTINGS)
+                .await?
+                .unwrap_or(defaults.discount_percent),
+        })
+    }
+}
+
+/// One cashier's indicators over a period
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CashierFraudIndicators {
+    pub user_id: i64,
+    pub cashier_name: String,
+    /// Sales rung up, voided ones included
+    pub sales_count: i64,
+    pub voided_count: i64,
+    pub void_rate_percent: f64,
+    /// Receipts this user printed again after the first print
+    pub reprint_count: i64,
+    pub reprint_rate_percent: f64,
+    pub no_sale_count: i64,
+    pub discount_percent: f64,
+    /// Indicators above their threshold, in words
+    pub warnings: Vec<String>,
+}
+
+/// Record a print of a sale's receipt: the first one is stamped on the sale, every later
+/// one is logged as a reprint by `user_id`
+pub async fn record_receipt_print(
+    conn: &mut SqliteConnection,
+    sale_id: i64,
+    user_id: Option<i64>,
+) -> AppResult<()> {
+    let first_print = sqlx::query(
+        "UPDATE sales SET receipt_printed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND receipt_printed_at IS NULL",
+    )
+    .bind(sale_id)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+    if !first_print {
+        sqlx::query("INSERT INTO receipt_reprints (sale_id, user_id) VALUES (?1, ?2)")
+            .bind(sale_id)
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+fn percent_of(part: f64, whole: f64) -> f64 {
+    if whole > 0.0 {
+        (part / whole * 10_000.0).round() / 100.0
+    } else {
+        0.0
+    }
+}
+
+fn warnings(indicators: &CashierFraudIndicators, thresholds: &FraudThresholds) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if indicators.void_rate_percent > thresholds.void_rate_percent {
+        warnings.push(format!(
+            "Void rate {:.2}% is above {:.2}%",
+            indicators.void_rate_percent, thresholds.void_rate_percent
+        ));
+    }
+    if indicators.reprint_rate_percent > thresholds.reprint_rate_percent {
+        warnings.push(format!(
+            "Reprint rate {:.2}% is above {:.2}%",
+            indicators.reprint_rate_percent, thresholds.reprint_rate_percent
+        ));
+    }
+    if indicators.no_sale_count > thresholds.no_sale_limit {
+        warnings.push(format!(
+            "{} no-sale drawer opens, above {}",
+            indicators.no_sale_count, thresholds.no_sale_limit
+        ));
+    }
+    if indicators.discount_percent > thresholds.discount_percent {
+        warnings.push(format!(
+            "Discounts {:.2}% of sales, above {:.2}%",
+            indicators.discount_percent, thresholds.discount_percent
+        ));
+    }
+    warnings
+}
+
+/// Indicators per cashier between two dates (inclusive), for everyone who rang up a sale,
+/// reprinted a receipt or opened the drawer without a sale. Each flagged cashier gets a
+/// warning notification, once per period and set of findings.
+pub async fn get_fraud_indicators(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+) -> AppResult<Vec<CashierFraudIndicators>> {
+    let thresholds = FraudThresholds::load(pool).await?;
+    let rows: Vec<(i64, String, i64, i64, f64, f64, i64, i64)> = sqlx::query_as(
+        "WITH sale_stats AS (
+             SELECT cashier_id AS user_id, COUNT(*) AS sales_count,
+                    SUM(CASE WHEN is_voided = 1 THEN 1 ELSE 0 END) AS voided_count,
+                    COALESCE(SUM(CASE WHEN is_voided = 0 THEN discount_amount ELSE 0 END), 0) AS discount,
+                    COALESCE(SUM(CASE WHEN is_voided = 0 THEN subtotal ELSE 0 END), 0) AS subtotal
+             FROM sales
+             WHERE DATE(created_at) BETWEEN ?1 AND ?2
+             GROUP BY cashier_id
+         ),
+         reprint_stats AS (
+             SELECT COALESCE(r.user_id, s.cashier_id) AS user_id, COUNT(*) AS reprint_count
+             FROM receipt_reprints r
+             JOIN sales s ON s.id = r.sale_id
+             WHERE DATE(r.printed_at) BETWEEN ?1 AND ?2
+             GROUP BY COALESCE(r.user_id, s.cashier_id)
+         ),
+         no_sale_stats AS (
+             SELECT user_id, COUNT(*) AS no_sale_count
+             FROM cash_drawer_transactions
+             WHERE transaction_type = 'no_sale' AND DATE(created_at) BETWEEN ?1 AND ?2
+             GROUP BY user_id
+         )
+         SELECT u.id, u.first_name || ' ' || u.last_name,
+                COALESCE(ss.sales_count, 0), COALESCE(ss.voided_count, 0),
+                CAST(COALESCE(ss.discount, 0) AS REAL), CAST(COALESCE(ss.subtotal, 0) AS REAL),
+                COALESCE(rs.reprint_count, 0), COALESCE(ns.no_sale_count, 0)
+         FROM users u
+         LEFT JOIN sale_stats ss ON ss.user_id = u.id
+         LEFT JOIN reprint_stats rs ON rs.user_id = u.id
+         LEFT JOIN no_sale_stats ns ON ns.user_id = u.id
+         WHERE ss.user_id IS NOT NULL OR rs.user_id IS NOT NULL OR ns.user_id IS NOT NULL
+         ORDER BY u.last_name, u.first_name, u.id",
+    )
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    let mut report = Vec::with_capacity(rows.len());
+    for (
+        user_id,
+        cashier_name,
+        sales_count,
+        voided_count,
+        discount,
+        subtotal,
+        reprint_count,
+        no_sale_count,
+    ) in rows
+    {
+        let mut indicators = CashierFraudIndicators {
+            user_id,
+            cashier_name,
+            sales_count,
+            voided_count,
+            void_rate_percent: percent_of(voided_count as f64, sales_count as f64),
+            reprint_count,
+            reprint_rate_percent: percent_of(reprint_count as f64, sales_count as f64),
+            no_sale_count,
+            discount_percent: percent_of(discount, subtotal),
+            warnings: Vec::new(),
+        };
+        indicators.warnings = warnings(&indicators, &thresholds);
+        if !indicators.warnings.is_empty() {
+            let message = format!(
+                "{} between {} and {}: {}",
+                indicators.cashier_name,
+                start_date,
+                end_date,
+                indicators.warnings.join("; ")
+            );
+            sqlx::query(
+                "INSERT INTO notifications (notification_type, title, message, severity, reference_id, reference_type)
+                 SELECT 'fraud_indicator', 'Unusual Cashier Activity', ?1, 'warning', ?2, 'user'
+                 WHERE NOT EXISTS (
+                    SELECT 1 FROM notifications
+                    WHERE notification_type = 'fraud_indicator' AND reference_id = ?2
+                    AND reference_type = 'user' AND message = ?1
+                 )",
+            )
+            .bind(&message)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        report.push(indicators);
+    }
+    tx.commit().await?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_user, test_pool};
+
+    async fn sale(
+        pool: &SqlitePool,
+        number: &str,
+        cashier: i64,
+        subtotal: f64,
+        discount: f64,
+        voided: bool,
+    ) -> i64 {
+        sqlx::query_scalar(
+            "INSERT INTO sales (sale_number, subtotal, discount_amount, total_amount, payment_method,
+                                cashier_id, is_voided, created_at)
+             VALUES (?1, ?2, ?3, ?2 - ?3, 'cash', ?4, ?5, '2026-04-10 10:00:00') RETURNING id",
+        )
+        .bind(number)
+        .bind(subtotal)
+        .bind(discount)
+        .bind(cashier)
+        .bind(voided)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn no_sale(pool: &SqlitePool, user_id: i64) {
+        sqlx::query(
+            "INSERT INTO cash_drawer_transactions (shift_id, transaction_type, amount, user_id, created_at)
+             VALUES (1, 'no_sale', 0, ?1, '2026-04-10 11:00:00')",
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fraud_indicators_rates_and_warnings() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier2").await;
+        let careful = insert_test_user(&pool, "cashier3").await;
+
+        let mut first = 0;
+        for i in 0..4 {
+            let id = sale(&pool, &format!("S-{}", i), cashier, 100.0, 10.0, i == 3).await;
+            if i == 0 {
+                first = id;
+            }
+        }
+        let clean = sale(&pool, "S-9", careful, 50.0, 0.0, false).await;
+        let mut conn = pool.acquire().await.unwrap();
+        // First print of each receipt is not a reprint
+        record_receipt_print(&mut conn, first, Some(cashier))
+            .await
+            .unwrap();
+        record_receipt_print(&mut conn, first, Some(cashier))
+            .await
+            .unwrap();
+        record_receipt_print(&mut conn, first, Some(cashier))
+            .await
+            .unwrap();
+        record_receipt_print(&mut conn, clean, Some(careful))
+            .await
+            .unwrap();
+        sqlx::query("UPDATE receipt_reprints SET printed_at = '2026-04-10 12:00:00'")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        drop(conn);
+        for _ in 0..11 {
+            no_sale(&pool, cashier).await;
+        }
+        let reprints: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM receipt_reprints WHERE sale_id = ?1")
+                .bind(first)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(reprints, 2);
+
+        let report = get_fraud_indicators(&pool, "2026-04-01", "2026-04-30")
+            .await
+            .unwrap();
+        let flagged = report.iter().find(|r| r.user_id == cashier).unwrap();
+        assert_eq!((flagged.sales_count, flagged.voided_count), (4, 1));
+        assert_eq!(flagged.void_rate_percent, 25.0);
+        assert_eq!(
+            (flagged.reprint_count, flagged.reprint_rate_percent),
+            (2, 50.0)
+        );
+        assert_eq!(flagged.no_sale_count, 11);
+        assert_eq!(flagged.discount_percent, 10.0);
+        assert_eq!(flagged.warnings.len(), 3);
+
+        let fine = report.iter().find(|r| r.user_id == careful).unwrap();
+        assert_eq!(
+            (fine.sales_count, fine.reprint_count, fine.discount_percent),
+            (1, 0, 0.0)
+        );
+        assert!(fine.warnings.is_empty());
+
+        // Re-running the report does not repeat the notification
+        get_fraud_indicators(&pool, "2026-04-01", "2026-04-30")
+            .await
+            .unwrap();
+        let notifications: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM notifications WHERE notification_type = 'fraud_indicator' AND reference_id = ?1",
+        )
+        .bind(cashier)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(notifications, 1);
+        assert!(get_fraud_indicators(&pool, "2026-05-01", "2026-05-31")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,379 @@
+use crate::barcode::{self, Symbology};
+use crate::currency;
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+const POINTS_PER_MM: f64 = 72.0 / 25.4;
+/// Rough Helvetica advance width as a share of the font size, for fitting text
+const AVERAGE_CHAR_WIDTH: f64 = 0.55;
+const LABEL_PADDING_MM: f64 = 2.0;
+/// Narrowest bar printers reproduce reliably
+const MAX_MODULE_MM: f64 = 0.33;
+
+/// Label stock to lay labels out on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LabelTemplate {
+    /// 24 labels of 70 x 37 mm, 3 across and 8 down an A4 sheet
+    #[serde(rename = "a4_3x8")]
+    A4Sheet24,
+    /// 30 labels of 66.7 x 25.4 mm, 3 across and 10 down a US Letter sheet
+    #[serde(rename = "letter_3x10")]
+    LetterSheet30,
+    /// One 58 x 40 mm label per page, for thermal label printers
+    #[serde(rename = "thermal_58x40")]
+    Thermal58x40,
+}
+
+/// Sheet geometry in millimetres
+struct Layout {
+    page_width: f64,
+    page_height: f64,
+    columns: usize,
+    rows: usize,
+    label_width: f64,
+    label_height: f64,
+    margin_left: f64,
+    margin_top: f64,
+    gap_x: f64,
+    gap_y: f64,
+}
+
+impl LabelTemplate {
+    fn layout(&self) -> Layout {
+        match self {
+            LabelTemplate::A4Sheet24 => Layout {
+                page_width: 210.0,
+                page_height: 297.0,
+                columns: 3,
+                rows: 8,
+                label_width: 70.0,
+                label_height: 37.0,
+                margin_left: 0.0,
+                margin_top: 0.5,
+                gap_x: 0.0,
+                gap_y: 0.0,
+            },
+            LabelTemplate::LetterSheet30 => Layout {
+                page_width: 215.9,
+                page_height: 279.4,
+                columns: 3,
+                rows: 10,
+                label_width: 66.675,
+                label_height: 25.4,
+                margin_left: 4.7625,
+                margin_top: 12.7,
+                gap_x: 3.175,
+                gap_y: 0.0,
+            },
+            LabelTemplate::Thermal58x40 => Layout {
+                page_width: 58.0,
+                page_height: 40.0,
+                columns: 1,
+                rows: 1,
+                label_width: 58.0,
+                label_height: 40.0,
+                margin_left: 0.0,
+                margin_top: 0.0,
+                gap_x: 0.0,
+                gap_y: 0.0,
+            },
+        }
+    }
+}
+
+/// What goes on one shelf label
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShelfLabel {
+    pub name: String,
+    /// Already formatted in the store currency
+    pub price: String,
+    pub unit: String,
+    pub barcode: String,
+}
+
+/// Text as a PDF string literal in WinAnsi encoding; characters it lacks print as `?`
+fn pdf_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('(');
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{20ac}' => out.push_str("\\200"),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            _ => out.push('?'),
+        }
+    }
+    out.push(')');
+    out
+}
+
+/// Cut text to what fits in `width_pt` at `size`, marking the cut with an ellipsis
+fn fit_text(text: &str, size: f64, width_pt: f64) -> String {
+    let max_chars = (width_pt / (size * AVERAGE_CHAR_WIDTH)).floor().max(1.0) as usize;
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+    format!("{}...", kept.trim_end())
+}
+
+fn text_width(text: &str, size: f64) -> f64 {
+    text.chars().count() as f64 * size * AVERAGE_CHAR_WIDTH
+}
+
+/// Draw commands for one label whose top-left corner is at (`x`, `top`) in points from the
+/// page's bottom-left corner
+fn draw_label(out: &mut String, label: &ShelfLabel, x: f64, top: f64, width: f64, height: f64) -> AppResult<()> {
+    let pad = LABEL_PADDING_MM * POINTS_PER_MM;
+    let inner_width = width - 2.0 * pad;
+    let name_size = (height * 0.11).clamp(6.0, 10.0);
+    let price_size = (height * 0.2).clamp(9.0, 18.0);
+    let unit_size = (height * 0.08).clamp(5.0, 8.0);
+    let code_size = (height * 0.07).clamp(5.0, 7.0);
+
+    let name = fit_text(&label.name, name_size, inner_width);
+    let name_baseline = top - pad - name_size;
+    let _ = writeln!(
+        out,
+        "BT /F2 {:.1} Tf {:.2} {:.2} Td {} Tj ET",
+        name_size,
+        x + pad,
+        name_baseline,
+        pdf_string(&name)
+    );
+
+    let price_baseline = name_baseline - price_size * 1.1;
+    let _ = writeln!(
+        out,
+        "BT /F2 {:.1} Tf {:.2} {:.2} Td {} Tj ET",
+        price_size,
+        x + pad,
+        price_baseline,
+        pdf_string(&label.price)
+    );
+    let unit = fit_text(
+        &format!("/ {}", label.unit),
+        unit_size,
+        inner_width - text_width(&label.price, price_size) - 4.0,
+    );
+    let _ = writeln!(
+        out,
+        "BT /F1 {:.1} Tf {:.2} {:.2} Td {} Tj ET",
+        unit_size,
+        x + pad + text_width(&label.price, price_size) + 4.0,
+        price_baseline,
+        pdf_string(&unit)
+    );
+
+    // Bars fill the rest of the label above the human-readable code
+    let symbology = Symbology::detect(&label.barcode);
+    let modules = barcode::encode(&label.barcode, symbology)?;
+    let module_width = (inner_width / modules.len() as f64).min(MAX_MODULE_MM * POINTS_PER_MM);
+    let bars_width = module_width * modules.len() as f64;
+    let bars_left = x + (width - bars_width) / 2.0;
+    let code_baseline = top - height + pad;
+    let bars_bottom = code_baseline + code_size + 1.0;
+    let bars_height = (price_baseline - price_size * 0.3) - bars_bottom;
+    if bars_height > 0.0 {
+        let mut i = 0;
+        while i < modules.len() {
+            if !modules[i] {
+                i += 1;
+                continue;
+            }
+            // One rectangle per run of adjacent bar modules
+            let start = i;
+            while i < modules.len() && modules[i] {
+                i += 1;
+            }
+            let _ = writeln!(
+                out,
+                "{:.3} {:.2} {:.3} {:.2} re f",
+                bars_left + start as f64 * module_width,
+                bars_bottom,
+                (i - start) as f64 * module_width,
+                bars_height
+            );
+        }
+    }
+    let _ = writeln!(
+        out,
+        "BT /F1 {:.1} Tf {:.2} {:.2} Td {} Tj ET",
+        code_size,
+        x + (width - text_width(&label.barcode, code_size)) / 2.0,
+        code_baseline,
+        pdf_string(&label.barcode)
+    );
+    Ok(())
+}
+
+/// Assemble a PDF from one content stream per page, using the standard Helvetica fonts
+fn build_pdf(pages: &[String], page_width: f64, page_height: f64) -> Vec<u8> {
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    let mut kids = Vec::with_capacity(pages.len());
+    for content in pages {
+        let page_id = objects.len() + 1;
+        kids.push(format!("{} 0 R", page_id));
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            page_width,
+            page_height,
+            page_id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        pages.len()
+    );
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        let _ = write!(pdf, "{} 0 obj\n{}\nendobj\n", i + 1, object);
+    }
+    let xref = pdf.len();
+    let _ = write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(pdf, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.into_bytes()
+}
+
+/// Lay labels out on the template's sheets, filling each row left to right
+pub fn render_labels_pdf(labels: &[ShelfLabel], template: LabelTemplate) -> AppResult<Vec<u8>> {
+    if labels.is_empty() {
+        return Err(AppError::validation("product_ids", "Choose at least one product"));
+    }
+    let layout = template.layout();
+    let per_page = layout.columns * layout.rows;
+    let mm = |value: f64| value * POINTS_PER_MM;
+
+    let mut pages = Vec::new();
+    for sheet in labels.chunks(per_page) {
+        let mut content = String::new();
+        for (i, label) in sheet.iter().enumerate() {
+            let column = (i % layout.columns) as f64;
+            let row = (i / layout.columns) as f64;
+            let x = layout.margin_left + column * (layout.label_width + layout.gap_x);
+            let top = layout.page_height - layout.margin_top - row * (layout.label_height + layout.gap_y);
+            draw_label(
+                &mut content,
+                label,
+                mm(x),
+                mm(top),
+                mm(layout.label_width),
+                mm(layout.label_height),
+            )?;
+        }
+        pages.push(content);
+    }
+    Ok(build_pdf(&pages, mm(layout.page_width), mm(layout.page_height)))
+}
+
+/// Shelf labels for the products, in the order given; repeat an id for more copies.
+/// Products without a barcode get one from the barcode sequence first.
+pub async fn load_shelf_labels(pool: &SqlitePool, product_ids: &[i64]) -> AppResult<Vec<ShelfLabel>> {
+    let currency = currency::store_currency(pool).await?;
+    let mut tx = pool.begin().await?;
+    let mut labels = Vec::with_capacity(product_ids.len());
+    for &product_id in product_ids {
+        let barcode = barcode::assign_barcode(&mut tx, product_id).await?;
+        let row = sqlx::query("SELECT name, selling_price, unit_of_measure FROM products WHERE id = ?1")
+            .bind(product_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        labels.push(ShelfLabel {
+            name: row.try_get("name")?,
+            price: currency.format(row.try_get("selling_price")?),
+            unit: row
+                .try_get::<Option<String>, _>("unit_of_measure")?
+                .unwrap_or_else(|| "each".to_string()),
+            barcode,
+        });
+    }
+    tx.commit().await?;
+    Ok(labels)
+}
+
+/// Write a label PDF for the products into `dir` and return its path
+pub async fn generate_labels_pdf(
+    pool: &SqlitePool,
+    product_ids: &[i64],
+    template: LabelTemplate,
+    dir: &Path,
+) -> AppResult<PathBuf> {
+    let labels = load_shelf_labels(pool, product_ids).await?;
+    let pdf = render_labels_pdf(&labels, template)?;
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        "labels-{}.pdf",
+        chrono::Local::now().format("%Y%m%d-%H%M%S%3f")
+    ));
+    std::fs::write(&path, pdf)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_product, test_pool};
+
+    #[tokio::test]
+    async fn test_labels_fill_sheets_and_get_barcodes() {
+        let pool = test_pool().await;
+        let cement = insert_test_product(&pool, "CEMENT", 12.5, 0).await;
+        let sand = insert_test_product(&pool, "SAND", 3.0, 0).await;
+        sqlx::query("UPDATE products SET barcode = '5901234123457', name = 'Sand (washed)' WHERE id = ?1")
+            .bind(sand)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let mut ids = vec![cement; 20];
+        ids.extend([sand; 5]);
+        let labels = load_shelf_labels(&pool, &ids).await.unwrap();
+        assert_eq!(labels[0].barcode, "2000000000015");
+        assert_eq!(labels[0].price, "$12.50");
+        assert_eq!(labels[24].barcode, "5901234123457");
+
+        let pdf = String::from_utf8(render_labels_pdf(&labels, LabelTemplate::A4Sheet24).unwrap()).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4"));
+        assert!(pdf.contains("/Count 2"));
+        assert!(pdf.contains("(Sand \\(washed\\))"));
+        let thermal = String::from_utf8(render_labels_pdf(&labels, LabelTemplate::Thermal58x40).unwrap()).unwrap();
+        assert!(thermal.contains("/Count 25"));
+
+        let err = render_labels_pdf(&[], LabelTemplate::A4Sheet24).unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+}
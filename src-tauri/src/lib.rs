@@ -1,14 +1,52 @@
 // src-tauri/src/lib.rs
 
+pub mod activity;
 pub mod app;
+pub mod archive;
+pub mod audit;
+pub mod backup;
+pub mod barcode;
+pub mod cash_rounding;
 pub mod commands;
+pub mod cost_history;
+pub mod csv_export;
+pub mod currency;
 pub mod database;
 pub mod db_utils;
+pub mod demo_mode;
+pub mod diagnostics;
+pub mod document_numbers;
 pub mod error;
+pub mod fraud;
+pub mod labels;
+pub mod lockout;
+pub mod location_stock;
+pub mod lots;
+pub mod margins;
+pub mod measure;
 pub mod models;
-pub mod seeder_building_materials;
+pub mod money;
+pub mod organization_settings;
+pub mod password_reset;
+pub mod pending_sales;
+pub mod pin;
+pub mod plans;
+pub mod printer;
+pub mod promotions;
+pub mod receipt;
+pub mod receipt_footers;
+pub mod rest_api;
+pub mod seeder;
 pub mod session;
+pub mod settings;
+pub mod sync_inbound;
+pub mod sync_outbox;
+pub mod tax_rules;
+pub mod tenancy;
+#[cfg(test)]
+pub mod test_utils;
 pub mod validation;
+pub mod webhooks;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
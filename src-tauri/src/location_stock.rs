@@ -0,0 +1,256 @@
+//! Stock per location. Branches keep their own rows in `location_inventory`, while the
+//! main store holds whatever part of a product's `inventory` total no branch holds. Every
+//! stock path that predates locations (receiving, adjustments, counts) therefore keeps
+//! moving the main store's stock without knowing about branches.
+
+use crate::error::{AppError, AppResult};
+use crate::margins::DEFAULT_LOCATION_ID;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{FromRow, SqlitePool};
+
+/// A product's stock at one location
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct LocationStock {
+    pub location_id: i64,
+    pub location_name: String,
+    pub current_stock: i32,
+}
+
+/// SQL for the stock of the product in `product_column` at the location bound to
+/// `location_param`, for queries that read `inventory i`
+pub fn location_stock_sql(product_column: &str, location_param: &str) -> String {
+    format!(
+        "CASE WHEN {loc} = {main}
+              THEN i.current_stock - COALESCE((SELECT SUM(li.current_stock) FROM location_inventory li
+                                               WHERE li.product_id = {product} AND li.location_id != {main}), 0)
+              ELSE COALESCE((SELECT li.current_stock FROM location_inventory li
+                             WHERE li.product_id = {product} AND li.location_id = {loc}), 0)
+         END",
+        loc = location_param,
+        main = DEFAULT_LOCATION_ID,
+        product = product_column,
+    )
+}
+
+/// The location to sell from: the one asked for, else the shift's, else the main store
+pub async fn resolve_sale_location(
+    conn: &mut SqliteConnection,
+    location_id: Option<i64>,
+    shift_id: Option<i64>,
+) -> AppResult<i64> {
+    if let Some(location_id) = location_id {
+        return Ok(location_id);
+    }
+    let shift_location: Option<i64> = match shift_id {
+        Some(shift_id) => sqlx::query_scalar("SELECT location_id FROM shifts WHERE id = ?1")
+            .bind(shift_id)
+            .fetch_optional(&mut *conn)
+            .await?,
+        None => None,
+    };
+    Ok(shift_location.unwrap_or(DEFAULT_LOCATION_ID))
+}
+
+/// Fail unless the location exists and is active
+pub async fn require_active_location(conn: &mut SqliteConnection, location_id: i64) -> AppResult<()> {
+    let active: Option<bool> = sqlx::query_scalar("SELECT is_active FROM locations WHERE id = ?1")
+        .bind(location_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    match active {
+        Some(true) => Ok(()),
+        Some(false) => Err(AppError::validation("location_id", "That location is inactive")),
+        None => Err(AppError::not_found("Location")),
+    }
+}
+
+/// Move a product's stock at a branch by `change`, alongside the change already made to
+/// its `inventory` total. The main store needs nothing more, its stock is the remainder.
+pub async fn adjust_location_stock(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    location_id: i64,
+    change: i32,
+) -> AppResult<()> {
+    if location_id == DEFAULT_LOCATION_ID || change == 0 {
+        return Ok(());
+    }
+    sqlx::query(
+        "INSERT INTO location_inventory (product_id, location_id, current_stock)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(product_id, location_id) DO UPDATE SET
+            current_stock = current_stock + excluded.current_stock,
+            last_updated = CURRENT_TIMESTAMP",
+    )
+    .bind(product_id)
+    .bind(location_id)
+    .bind(change)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// A product's stock at one location
+pub async fn stock_at_location(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    location_id: i64,
+) -> AppResult<i32> {
+    let query = format!(
+        "SELECT {} FROM inventory i WHERE i.product_id = ?1",
+        location_stock_sql("i.product_id", "?2")
+    );
+    sqlx::query_scalar(&query)
+        .bind(product_id)
+        .bind(location_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::inventory_not_found(product_id))
+}
+
+/// Stock leaving or arriving at one location as part of a transfer
+pub struct TransferLeg<'a> {
+    pub product_id: i64,
+    pub location_id: i64,
+    /// Negative when the stock leaves the location
+    pub quantity_change: i32,
+    pub reference_type: &'a str,
+    pub reference_id: i64,
+    pub notes: &'a str,
+    pub user_id: i64,
+}
+
+/// Move stock out of or into a location with a `transfer` movement recording that
+/// location's stock before and after. Stock on its way between locations is in neither,
+/// so the business-wide `inventory` total moves with it.
+pub async fn post_transfer_leg(conn: &mut SqliteConnection, leg: &TransferLeg<'_>) -> AppResult<()> {
+    let previous_stock = stock_at_location(conn, leg.product_id, leg.location_id).await?;
+    let new_stock = previous_stock + leg.quantity_change;
+    if new_stock < 0 {
+        return Err(AppError::insufficient_stock(
+            &format!("product {} at location {}", leg.product_id, leg.location_id),
+            previous_stock,
+            -leg.quantity_change,
+        ));
+    }
+
+    sqlx::query(
+        "UPDATE inventory SET
+            current_stock = current_stock + ?1,
+            available_stock = available_stock + ?1,
+            last_updated = CURRENT_TIMESTAMP
+         WHERE product_id = ?2",
+    )
+    .bind(leg.quantity_change)
+    .bind(leg.product_id)
+    .execute(&mut *conn)
+    .await?;
+    adjust_location_stock(conn, leg.product_id, leg.location_id, leg.quantity_change).await?;
+
+    sqlx::query(
+        "INSERT INTO inventory_movements
+            (product_id, movement_type, quantity_change, previous_stock, new_stock,
+             reference_id, reference_type, notes, user_id, location_id)
+         VALUES (?1, 'transfer', ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )
+    .bind(leg.product_id)
+    .bind(leg.quantity_change)
+    .bind(previous_stock)
+    .bind(new_stock)
+    .bind(leg.reference_id)
+    .bind(leg.reference_type)
+    .bind(leg.notes)
+    .bind(leg.user_id)
+    .bind(leg.location_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Join of the reorder levels set at the location bound to `location_param`, as `lv`, for
+/// queries that read `inventory i`
+pub fn location_levels_join(location_param: &str) -> String {
+    format!(
+        "LEFT JOIN location_inventory lv ON lv.product_id = i.product_id AND lv.location_id = {}",
+        location_param
+    )
+}
+
+/// Reorder thresholds of a product at one location; `None` keeps the product-wide level
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocationReorderLevels {
+    pub minimum_stock: Option<i32>,
+    pub maximum_stock: Option<i32>,
+    pub reorder_point: Option<i32>,
+}
+
+/// Set the thresholds a location reorders a product at
+pub async fn set_location_reorder_levels(
+    pool: &SqlitePool,
+    product_id: i64,
+    location_id: i64,
+    levels: &LocationReorderLevels,
+) -> AppResult<()> {
+    for (field, level) in [
+        ("minimum_stock", levels.minimum_stock),
+        ("maximum_stock", levels.maximum_stock),
+        ("reorder_point", levels.reorder_point),
+    ] {
+        if level.is_some_and(|level| level < 0) {
+            return Err(AppError::validation(field, "Stock levels cannot be negative"));
+        }
+    }
+    if let (Some(minimum), Some(maximum)) = (levels.minimum_stock, levels.maximum_stock) {
+        if minimum > maximum {
+            return Err(AppError::validation(
+                "maximum_stock",
+                "Maximum stock must be at least the minimum stock",
+            ));
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+    require_active_location(&mut tx, location_id).await?;
+    stock_at_location(&mut tx, product_id, location_id).await?;
+    sqlx::query(
+        "INSERT INTO location_inventory (product_id, location_id, current_stock, minimum_stock,
+                                         maximum_stock, reorder_point)
+         VALUES (?1, ?2, 0, ?3, ?4, ?5)
+         ON CONFLICT(product_id, location_id) DO UPDATE SET
+            minimum_stock = excluded.minimum_stock,
+            maximum_stock = excluded.maximum_stock,
+            reorder_point = excluded.reorder_point",
+    )
+    .bind(product_id)
+    .bind(location_id)
+    .bind(levels.minimum_stock)
+    .bind(levels.maximum_stock)
+    .bind(levels.reorder_point)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// A product's stock at every active location, main store first
+pub async fn stock_by_location(pool: &SqlitePool, product_id: i64) -> AppResult<Vec<LocationStock>> {
+    let query = format!(
+        "SELECT l.id AS location_id, l.name AS location_name,
+                {} AS current_stock
+         FROM locations l
+         JOIN inventory i ON i.product_id = ?1
+         WHERE l.is_active = 1
+         ORDER BY l.id != {}, l.name",
+        location_stock_sql("i.product_id", "l.id"),
+        DEFAULT_LOCATION_ID
+    );
+    let stock = sqlx::query_as::<_, LocationStock>(&query)
+        .bind(product_id)
+        .fetch_all(pool)
+        .await?;
+    if stock.is_empty() {
+        return Err(AppError::inventory_not_found(product_id));
+    }
+    Ok(stock)
+}
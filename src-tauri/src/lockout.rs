@@ -0,0 +1,371 @@
+use crate::error::{AppError, AppResult};
+use crate::settings::{self, STORE_SETTINGS};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use sqlx::{Row, SqlitePool};
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub const MAX_FAILED_ATTEMPTS_SETTING: &str = "lockout_max_failed_attempts";
+pub const LOCKOUT_MINUTES_SETTING: &str = "lockout_minutes";
+
+/// Account lockout settings.
+/// Each consecutive lockout (without a successful login in between) doubles
+/// the lockout duration, up to `max_lockout`.
+#[derive(Debug, Clone)]
+pub struct LockoutPolicy {
+    pub max_failed_attempts: u32,
+    pub base_lockout: Duration,
+    pub max_lockout: Duration,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self {
+            max_failed_attempts: 5,
+            base_lockout: Duration::minutes(15),
+            max_lockout: Duration::hours(24),
+        }
+    }
+}
+
+impl LockoutPolicy {
+    /// The store's policy: the failures allowed and the first lockout's length come from
+    /// its settings, the defaults filling in whichever is not set
+    pub async fn load(pool: &SqlitePool) -> AppResult<Self> {
+        let defaults = Self::default();
+        let max_failed_attempts: Option<u32> =
+            settings::get_setting(pool, MAX_FAILED_ATTEMPTS_SETTING, STORE_SETTINGS).await?;
+        let lockout_minutes: Option<i64> =
+            settings::get_setting(pool, LOCKOUT_MINUTES_SETTING, STORE_SETTINGS).await?;
+        Ok(Self {
+            max_failed_attempts: max_failed_attempts.unwrap_or(defaults.max_failed_attempts),
+            base_lockout: lockout_minutes.map(Duration::minutes).unwrap_or(defaults.base_lockout),
+            ..defaults
+        })
+    }
+
+    /// Duration of the n-th consecutive lockout (1-based)
+    pub fn lockout_duration(&self, lockout_count: u32) -> Duration {
+        let exponent = lockout_count.saturating_sub(1).min(16);
+        let duration = self.base_lockout * 2i32.pow(exponent);
+        duration.min(self.max_lockout)
+    }
+}
+
+/// Result of checking or recording a login attempt
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoginAttemptStatus {
+    Allowed { remaining_attempts: u32 },
+    Locked { locked_until: DateTime<Utc> },
+}
+
+fn user_key(user_id: i64) -> String {
+    format!("user:{}", user_id)
+}
+
+/// The key a login's failures count against: the account the identifier names, whether by
+/// username, email or badge number, so every way of naming it shares one counter. An
+/// identifier naming no account is its own key, so unknown usernames are throttled the
+/// same way as real ones.
+pub async fn attempt_key(pool: &SqlitePool, identifier: &str) -> AppResult<String> {
+    let user_id: Option<i64> = sqlx::query_scalar(
+        "SELECT u.id FROM users u
+         LEFT JOIN employees e ON e.user_id = u.id
+         WHERE u.username = ?1 OR u.email = ?1 OR e.employee_number = ?1
+         LIMIT 1",
+    )
+    .bind(identifier.trim())
+    .fetch_optional(pool)
+    .await?;
+    Ok(match user_id {
+        Some(id) => user_key(id),
+        None => identifier.trim().to_lowercase(),
+    })
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, TIMESTAMP_FORMAT)
+        .ok()
+        .map(|dt| dt.and_utc())
+}
+
+/// Check whether a login attempt under this `attempt_key` is currently allowed
+pub async fn check_login_allowed(
+    pool: &SqlitePool,
+    key: &str,
+    policy: &LockoutPolicy,
+    now: DateTime<Utc>,
+) -> AppResult<LoginAttemptStatus> {
+    let row =
+        sqlx::query("SELECT failed_count, locked_until FROM login_attempts WHERE username = ?1")
+            .bind(key)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    let row = match row {
+        Some(r) => r,
+        None => {
+            return Ok(LoginAttemptStatus::Allowed {
+                remaining_attempts: policy.max_failed_attempts,
+            })
+        }
+    };
+
+    let failed_count: i64 = row.try_get("failed_count").unwrap_or(0);
+    let locked_until: Option<String> = row.try_get("locked_until").ok().flatten();
+
+    if let Some(until) = locked_until.as_deref().and_then(parse_timestamp) {
+        if until > now {
+            return Ok(LoginAttemptStatus::Locked {
+                locked_until: until,
+            });
+        }
+    }
+
+    Ok(LoginAttemptStatus::Allowed {
+        remaining_attempts: policy
+            .max_failed_attempts
+            .saturating_sub(failed_count as u32),
+    })
+}
+
+/// Record a failed login attempt under an `attempt_key`, locking the account once the
+/// limit is reached. The counter moves in one statement, so concurrent failures each count.
+pub async fn record_failed_login(
+    pool: &SqlitePool,
+    key: &str,
+    policy: &LockoutPolicy,
+    now: DateTime<Utc>,
+) -> AppResult<LoginAttemptStatus> {
+    let now_str = now.format(TIMESTAMP_FORMAT).to_string();
+    // A first failure that already reaches the limit locks straight away
+    let locks_at_once = policy.max_failed_attempts <= 1;
+    let first_lock = locks_at_once
+        .then(|| (now + policy.lockout_duration(1)).format(TIMESTAMP_FORMAT).to_string());
+
+    // The failure counter restarts once the account is locked; lockout_count keeps growing
+    // until a successful login so repeat offenders are locked out for longer each time.
+    let (failed_count, lockout_count, locked_until): (i64, i64, Option<String>) = sqlx::query_as(
+        "INSERT INTO login_attempts (username, failed_count, lockout_count, last_failed_at, locked_until)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(username) DO UPDATE SET
+            failed_count = CASE WHEN failed_count + 1 >= ?6 THEN 0 ELSE failed_count + 1 END,
+            lockout_count = lockout_count + (failed_count + 1 >= ?6),
+            locked_until = CASE WHEN failed_count + 1 >= ?6
+                THEN datetime(?4, '+' || MIN(?7 << MIN(lockout_count, 16), ?8) || ' seconds')
+                ELSE locked_until END,
+            last_failed_at = excluded.last_failed_at
+         RETURNING failed_count, lockout_count, locked_until",
+    )
+    .bind(key)
+    .bind(if locks_at_once { 0 } else { 1 })
+    .bind(locks_at_once as i64)
+    .bind(&now_str)
+    .bind(&first_lock)
+    .bind(policy.max_failed_attempts as i64)
+    .bind(policy.base_lockout.num_seconds())
+    .bind(policy.max_lockout.num_seconds())
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    if failed_count == 0 && lockout_count > 0 {
+        let locked_until = now + policy.lockout_duration(lockout_count as u32);
+        return Ok(LoginAttemptStatus::Locked { locked_until });
+    }
+    if let Some(until) = locked_until.as_deref().and_then(parse_timestamp) {
+        if until > now {
+            return Ok(LoginAttemptStatus::Locked { locked_until: until });
+        }
+    }
+    Ok(LoginAttemptStatus::Allowed {
+        remaining_attempts: policy.max_failed_attempts.saturating_sub(failed_count as u32),
+    })
+}
+
+/// Reset all failure tracking under an `attempt_key` (on successful login)
+pub async fn clear_failed_logins(pool: &SqlitePool, key: &str) -> AppResult<()> {
+    sqlx::query("DELETE FROM login_attempts WHERE username = ?1")
+        .bind(key)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    Ok(())
+}
+
+/// Remove any lockout for a user, whichever identifier they were locked out under
+pub async fn unlock_user_account(pool: &SqlitePool, user_id: i64) -> AppResult<()> {
+    let exists: Option<i64> = sqlx::query_scalar("SELECT id FROM users WHERE id = ?1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+    if exists.is_none() {
+        return Err(AppError::not_found("user"));
+    }
+
+    clear_failed_logins(pool, &user_key(user_id)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_user, test_pool};
+
+    #[tokio::test]
+    async fn test_lockout_after_max_failures() {
+        let pool = test_pool().await;
+        let policy = LockoutPolicy::default();
+        let now = Utc::now();
+        let key = attempt_key(&pool, "cashier1").await.unwrap();
+
+        for expected_remaining in (1..5).rev() {
+            let status = record_failed_login(&pool, &key, &policy, now)
+                .await
+                .unwrap();
+            assert_eq!(
+                status,
+                LoginAttemptStatus::Allowed {
+                    remaining_attempts: expected_remaining
+                }
+            );
+        }
+
+        let status = record_failed_login(&pool, &key, &policy, now)
+            .await
+            .unwrap();
+        assert!(matches!(status, LoginAttemptStatus::Locked { .. }));
+
+        let key = attempt_key(&pool, "Cashier1").await.unwrap();
+        let status = check_login_allowed(&pool, &key, &policy, now)
+            .await
+            .unwrap();
+        assert!(matches!(status, LoginAttemptStatus::Locked { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_username_and_email_share_the_store_policy_counter() {
+        let pool = test_pool().await;
+        let user = insert_test_user(&pool, "cashier1").await;
+        let email: String = sqlx::query_scalar("SELECT email FROM users WHERE id = ?1")
+            .bind(user)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        settings::set_setting(&pool, MAX_FAILED_ATTEMPTS_SETTING, STORE_SETTINGS, &3)
+            .await
+            .unwrap();
+        settings::set_setting(&pool, LOCKOUT_MINUTES_SETTING, STORE_SETTINGS, &5)
+            .await
+            .unwrap();
+        let policy = LockoutPolicy::load(&pool).await.unwrap();
+        assert_eq!(policy.max_failed_attempts, 3);
+        assert_eq!(policy.base_lockout, Duration::minutes(5));
+
+        sqlx::query("INSERT INTO employees (user_id, employee_number) VALUES (?1, 'EMP-042')")
+            .bind(user)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let by_name = attempt_key(&pool, "cashier1").await.unwrap();
+        let by_email = attempt_key(&pool, &email).await.unwrap();
+        let by_badge = attempt_key(&pool, "EMP-042").await.unwrap();
+        assert_eq!(by_name, by_email);
+        assert_eq!(by_name, by_badge);
+        let now = Utc::now();
+        record_failed_login(&pool, &by_name, &policy, now).await.unwrap();
+        record_failed_login(&pool, &by_email, &policy, now).await.unwrap();
+        let status = record_failed_login(&pool, &by_badge, &policy, now).await.unwrap();
+        assert_eq!(
+            status,
+            LoginAttemptStatus::Locked {
+                locked_until: now + Duration::minutes(5)
+            }
+        );
+
+        unlock_user_account(&pool, user).await.unwrap();
+        let status = check_login_allowed(&pool, &by_email, &policy, now).await.unwrap();
+        assert_eq!(
+            status,
+            LoginAttemptStatus::Allowed {
+                remaining_attempts: 3
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lockout_expires_and_backs_off() {
+        let pool = test_pool().await;
+        let policy = LockoutPolicy::default();
+        let now = Utc::now();
+
+        for _ in 0..5 {
+            record_failed_login(&pool, "cashier1", &policy, now)
+                .await
+                .unwrap();
+        }
+
+        let after_first_lock = now + Duration::minutes(16);
+        let status = check_login_allowed(&pool, "cashier1", &policy, after_first_lock)
+            .await
+            .unwrap();
+        assert_eq!(
+            status,
+            LoginAttemptStatus::Allowed {
+                remaining_attempts: 5
+            }
+        );
+
+        let mut status = status;
+        for _ in 0..5 {
+            status = record_failed_login(&pool, "cashier1", &policy, after_first_lock)
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            status,
+            LoginAttemptStatus::Locked {
+                locked_until: after_first_lock + Duration::minutes(30)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_success_resets_failure_count() {
+        let pool = test_pool().await;
+        let policy = LockoutPolicy::default();
+        let now = Utc::now();
+
+        for _ in 0..3 {
+            record_failed_login(&pool, "cashier1", &policy, now)
+                .await
+                .unwrap();
+        }
+        clear_failed_logins(&pool, "cashier1").await.unwrap();
+
+        let mut status = check_login_allowed(&pool, "cashier1", &policy, now)
+            .await
+            .unwrap();
+        for _ in 0..4 {
+            status = record_failed_login(&pool, "cashier1", &policy, now)
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            status,
+            LoginAttemptStatus::Allowed {
+                remaining_attempts: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_lockout_duration_is_capped() {
+        let policy = LockoutPolicy::default();
+        assert_eq!(policy.lockout_duration(1), Duration::minutes(15));
+        assert_eq!(policy.lockout_duration(3), Duration::minutes(60));
+        assert_eq!(policy.lockout_duration(20), Duration::hours(24));
+    }
+}
@@ -0,0 +1,368 @@
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use crate::tenancy;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{FromRow, Row, SqlitePool};
+
+/// How far ahead the expiry report and notifications look by default
+pub const DEFAULT_EXPIRY_WINDOW_DAYS: i64 = 30;
+
+/// Lot holding the stock a product already had when lot tracking was switched on
+pub const OPENING_LOT_NUMBER: &str = "OPENING";
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProductLot {
+    pub id: i64,
+    pub product_id: i64,
+    pub lot_number: String,
+    pub expiry_date: Option<String>,
+    pub quantity: i32,
+    pub received_at: String,
+}
+
+/// Quantity a sale line took from one lot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LotAllocation {
+    pub lot_id: i64,
+    pub lot_number: String,
+    pub quantity: i32,
+}
+
+/// A lot with stock left that expires within the reporting window, or already has
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExpiringLot {
+    pub lot_id: i64,
+    pub product_id: i64,
+    pub sku: String,
+    pub product_name: String,
+    pub lot_number: String,
+    pub expiry_date: String,
+    pub quantity: i32,
+    /// Negative once the lot has expired
+    pub days_until_expiry: i64,
+    /// Quantity at the product's cost price: what is lost if the lot is not sold
+    pub value_at_cost: Money,
+}
+
+pub async fn is_lot_tracked(conn: &mut SqliteConnection, product_id: i64) -> AppResult<bool> {
+    let tracked: Option<bool> = sqlx::query_scalar("SELECT lot_tracked FROM products WHERE id = ?1")
+        .bind(product_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    tracked.ok_or_else(|| AppError::not_found(&format!("product {}", product_id)))
+}
+
+/// Turn lot tracking on or off. Stock on hand not yet in any lot is put in an
+/// `OPENING` lot without expiry so it can still be sold once tracking is on.
+pub async fn set_lot_tracking(pool: &SqlitePool, product_id: i64, lot_tracked: bool) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+    is_lot_tracked(&mut tx, product_id).await?;
+
+    sqlx::query("UPDATE products SET lot_tracked = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2")
+        .bind(lot_tracked)
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await?;
+
+    if lot_tracked {
+        let untracked: i32 = sqlx::query_scalar(
+            "SELECT COALESCE(MAX(i.current_stock, 0), 0)
+                    - (SELECT COALESCE(SUM(l.quantity), 0) FROM product_lots l WHERE l.product_id = ?1)
+             FROM inventory i WHERE i.product_id = ?1",
+        )
+        .bind(product_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .unwrap_or(0);
+        if untracked > 0 {
+            receive_into_lot(&mut tx, product_id, OPENING_LOT_NUMBER, None, untracked).await?;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Add received stock to a lot, creating it on first receipt. Returns the lot id.
+pub async fn receive_into_lot(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    lot_number: &str,
+    expiry_date: Option<&str>,
+    quantity: i32,
+) -> AppResult<i64> {
+    if lot_number.trim().is_empty() {
+        return Err(AppError::validation("lot_number", "A lot number is required"));
+    }
+    let lot_id: i64 = sqlx::query_scalar(
+        "INSERT INTO product_lots (product_id, lot_number, expiry_date, quantity)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(product_id, lot_number) DO UPDATE SET
+            quantity = quantity + excluded.quantity,
+            expiry_date = COALESCE(excluded.expiry_date, expiry_date)
+         RETURNING id",
+    )
+    .bind(product_id)
+    .bind(lot_number.trim())
+    .bind(expiry_date)
+    .bind(quantity)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(lot_id)
+}
+
+/// Take a sale line's quantity from a lot-tracked product's lots, earliest expiry first,
+/// and record the lots used against the line. Products without lot tracking are left alone.
+/// Fails if the sale would draw from an expired lot or the lots do not hold enough stock.
+pub async fn allocate_sale_lots(
+    conn: &mut SqliteConnection,
+    sale_item_id: i64,
+    product_id: i64,
+    quantity: i32,
+) -> AppResult<Vec<LotAllocation>> {
+    if !is_lot_tracked(conn, product_id).await? {
+        return Ok(Vec::new());
+    }
+
+    let sku: String = sqlx::query_scalar("SELECT sku FROM products WHERE id = ?1")
+        .bind(product_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    // Lots without an expiry date go last
+    let lots = sqlx::query(
+        "SELECT id, lot_number, expiry_date, quantity,
+                expiry_date IS NOT NULL AND DATE(expiry_date) < DATE('now', 'localtime') as expired
+         FROM product_lots
+         WHERE product_id = ?1 AND quantity > 0
+         ORDER BY expiry_date IS NULL, DATE(expiry_date), id",
+    )
+    .bind(product_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut remaining = quantity;
+    let mut allocations = Vec::new();
+    for lot in &lots {
+        if remaining == 0 {
+            break;
+        }
+        let lot_number: String = lot.try_get("lot_number")?;
+        if lot.try_get::<bool, _>("expired")? {
+            let expiry_date: String = lot.try_get("expiry_date")?;
+            return Err(AppError::validation(
+                "lot",
+                &format!(
+                    "Lot {} of {} expired on {}; write it off before selling this product",
+                    lot_number, sku, expiry_date
+                ),
+            ));
+        }
+        let available: i32 = lot.try_get("quantity")?;
+        let taken = available.min(remaining);
+        allocations.push(LotAllocation {
+            lot_id: lot.try_get("id")?,
+            lot_number,
+            quantity: taken,
+        });
+        remaining -= taken;
+    }
+
+    if remaining > 0 {
+        return Err(AppError::insufficient_stock(&sku, quantity - remaining, quantity));
+    }
+
+    for allocation in &allocations {
+        sqlx::query("UPDATE product_lots SET quantity = quantity - ?1 WHERE id = ?2")
+            .bind(allocation.quantity)
+            .bind(allocation.lot_id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("INSERT INTO sale_item_lots (sale_item_id, lot_id, quantity) VALUES (?1, ?2, ?3)")
+            .bind(sale_item_id)
+            .bind(allocation.lot_id)
+            .bind(allocation.quantity)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(allocations)
+}
+
+/// Put the stock of a voided sale back into the lots it was drawn from
+pub async fn restore_sale_lots(conn: &mut SqliteConnection, sale_id: i64) -> AppResult<()> {
+    sqlx::query(
+        "UPDATE product_lots SET quantity = quantity + (
+            SELECT SUM(sil.quantity) FROM sale_item_lots sil
+            JOIN sale_items si ON sil.sale_item_id = si.id
+            WHERE si.sale_id = ?1 AND sil.lot_id = product_lots.id)
+         WHERE id IN (
+            SELECT sil.lot_id FROM sale_item_lots sil
+            JOIN sale_items si ON sil.sale_item_id = si.id
+            WHERE si.sale_id = ?1)",
+    )
+    .bind(sale_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Lots of a product, earliest expiry first
+pub async fn get_product_lots(pool: &SqlitePool, product_id: i64, include_empty: bool) -> AppResult<Vec<ProductLot>> {
+    let lots = sqlx::query_as::<_, ProductLot>(
+        "SELECT id, product_id, lot_number, expiry_date, quantity,
+                COALESCE(received_at, '') as received_at
+         FROM product_lots
+         WHERE product_id = ?1 AND (?2 OR quantity > 0)
+         ORDER BY expiry_date IS NULL, DATE(expiry_date), id",
+    )
+    .bind(product_id)
+    .bind(include_empty)
+    .fetch_all(pool)
+    .await?;
+    Ok(lots)
+}
+
+/// Lots with stock left that expire within `days`, including ones already expired
+pub async fn get_expiring_lots(
+    pool: &SqlitePool,
+    organization_id: i64,
+    days: i64,
+) -> AppResult<Vec<ExpiringLot>> {
+    if days < 0 {
+        return Err(AppError::negative_value("days"));
+    }
+    let query = format!(
+        "SELECT l.id as lot_id, l.product_id, p.sku, p.name as product_name, l.lot_number,
+                l.expiry_date, l.quantity,
+                CAST(julianday(DATE(l.expiry_date)) - julianday(DATE('now', 'localtime')) AS INTEGER)
+                    as days_until_expiry,
+                l.quantity * COALESCE(p.cost_price, 0) as value_at_cost
+         FROM product_lots l
+         JOIN products p ON l.product_id = p.id
+         WHERE l.quantity > 0
+           AND l.expiry_date IS NOT NULL
+           AND DATE(l.expiry_date) <= DATE('now', 'localtime', '+' || ?1 || ' days'){}
+         ORDER BY DATE(l.expiry_date), p.name, l.lot_number",
+        tenancy::organization_scope("p.organization_id", organization_id)
+    );
+    let lots = sqlx::query_as::<_, ExpiringLot>(&query)
+        .bind(days)
+        .fetch_all(pool)
+        .await?;
+    Ok(lots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_product, test_pool};
+
+    async fn insert_lot(pool: &SqlitePool, product_id: i64, lot_number: &str, expiry_offset_days: i64, quantity: i32) {
+        sqlx::query(
+            "INSERT INTO product_lots (product_id, lot_number, expiry_date, quantity)
+             VALUES (?1, ?2, DATE('now', 'localtime', ?3 || ' days'), ?4)",
+        )
+        .bind(product_id)
+        .bind(lot_number)
+        .bind(format!("{:+}", expiry_offset_days))
+        .bind(quantity)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    /// A sale line for the allocations to be recorded against
+    async fn insert_sale_item(pool: &SqlitePool, product_id: i64) -> i64 {
+        let sale_id = sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, total_amount, payment_method, cashier_id)
+             VALUES ('S-' || (SELECT COUNT(*) + 1 FROM sales), 0, 0, 'cash', 1)",
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        sqlx::query(
+            "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, line_total)
+             VALUES (?1, ?2, 1, 0, 0)",
+        )
+        .bind(sale_id)
+        .bind(product_id)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+    }
+
+    async fn lot_quantities(pool: &SqlitePool, product_id: i64) -> Vec<(String, i32)> {
+        get_product_lots(pool, product_id, true)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|lot| (lot.lot_number, lot.quantity))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sales_draw_first_expiry_first() {
+        let pool = test_pool().await;
+        let product_id = insert_test_product(&pool, "MILK", 2.0, 12).await;
+        set_lot_tracking(&pool, product_id, true).await.unwrap();
+        // The 12 units already on hand become an opening lot without expiry
+        assert_eq!(lot_quantities(&pool, product_id).await, vec![("OPENING".to_string(), 12)]);
+        sqlx::query("DELETE FROM product_lots").execute(&pool).await.unwrap();
+
+        insert_lot(&pool, product_id, "L-LATE", 20, 10).await;
+        insert_lot(&pool, product_id, "L-SOON", 3, 4).await;
+        let sale_item_id = insert_sale_item(&pool, product_id).await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let allocations = allocate_sale_lots(&mut conn, sale_item_id, product_id, 6)
+            .await
+            .unwrap();
+        assert_eq!(
+            allocations.iter().map(|a| (a.lot_number.as_str(), a.quantity)).collect::<Vec<_>>(),
+            vec![("L-SOON", 4), ("L-LATE", 2)]
+        );
+        let err = allocate_sale_lots(&mut conn, sale_item_id, product_id, 9).await.unwrap_err();
+        assert_eq!(err.code(), "INSUFFICIENT_STOCK");
+        drop(conn);
+        assert_eq!(
+            lot_quantities(&pool, product_id).await,
+            vec![("L-SOON".to_string(), 0), ("L-LATE".to_string(), 8)]
+        );
+
+        // Untracked products are not touched
+        let loose = insert_test_product(&pool, "SAND", 2.0, 5).await;
+        let mut conn = pool.acquire().await.unwrap();
+        assert!(allocate_sale_lots(&mut conn, sale_item_id, loose, 2)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expired_lots_block_sales_and_show_in_report() {
+        let pool = test_pool().await;
+        let product_id = insert_test_product(&pool, "YOGURT", 4.0, 0).await;
+        set_lot_tracking(&pool, product_id, true).await.unwrap();
+        insert_lot(&pool, product_id, "L-OLD", -1, 3).await;
+        insert_lot(&pool, product_id, "L-NEW", 10, 5).await;
+        insert_lot(&pool, product_id, "L-FAR", 90, 5).await;
+        let sale_item_id = insert_sale_item(&pool, product_id).await;
+
+        let mut conn = pool.acquire().await.unwrap();
+        let err = allocate_sale_lots(&mut conn, sale_item_id, product_id, 1).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert!(err.message().contains("L-OLD"));
+        drop(conn);
+
+        let expiring = get_expiring_lots(&pool, tenancy::DEFAULT_ORGANIZATION_ID, 30).await.unwrap();
+        assert_eq!(expiring.len(), 2);
+        assert_eq!(expiring[0].lot_number, "L-OLD");
+        assert_eq!(expiring[0].days_until_expiry, -1);
+        // 3 units at a cost of 2.00
+        assert_eq!(expiring[0].value_at_cost, Money::from_major(6.0));
+        assert_eq!(expiring[1].lot_number, "L-NEW");
+        assert_eq!(expiring[1].days_until_expiry, 10);
+    }
+}
@@ -2,15 +2,53 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod activity;
 mod app;
+mod archive;
+mod audit;
+mod backup;
+mod barcode;
+mod cash_rounding;
 mod commands;
+mod cost_history;
+mod csv_export;
+mod currency;
 mod database;
 mod db_utils;
+mod demo_mode;
+mod diagnostics;
+mod document_numbers;
 mod error;
+mod fraud;
+mod labels;
+mod lockout;
+mod location_stock;
+mod lots;
+mod margins;
+mod measure;
 mod models;
-mod seeder_building_materials;
+mod money;
+mod organization_settings;
+mod password_reset;
+mod pending_sales;
+mod pin;
+mod plans;
+mod printer;
+mod promotions;
+mod receipt;
+mod receipt_footers;
+mod rest_api;
+mod seeder;
 mod session;
+mod settings;
+mod sync_inbound;
+mod sync_outbox;
+mod tax_rules;
+mod tenancy;
+#[cfg(test)]
+mod test_utils;
 mod validation;
+mod webhooks;
 
 fn main() {
     // Call the same app run function synchronously
@@ -0,0 +1,414 @@
+use crate::db_utils::require_manager;
+use crate::error::{AppError, AppResult};
+use crate::models::{CreateSaleRequest, SaleItemRequest};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::Row;
+
+/// Location used when a sale does not name one: the store configured in settings
+pub const DEFAULT_LOCATION_ID: i64 = 1;
+
+/// What a till does with lines whose net price is under cost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BelowCostPolicy {
+    /// Let the sale through; the UI shows the offending lines
+    Warn,
+    /// Reject the sale unless a manager approves it
+    Block,
+}
+
+impl BelowCostPolicy {
+    pub fn parse(value: &str) -> AppResult<Self> {
+        match value {
+            "warn" => Ok(BelowCostPolicy::Warn),
+            "block" => Ok(BelowCostPolicy::Block),
+            other => Err(AppError::validation(
+                "below_cost_policy",
+                &format!("Unknown below-cost policy '{}', expected warn or block", other),
+            )),
+        }
+    }
+}
+
+/// A sale line or new selling price under the product's cost plus its minimum margin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BelowCostItem {
+    pub product_id: i64,
+    pub sku: String,
+    pub product_name: String,
+    pub quantity: i32,
+    pub unit_price: Money,
+    pub discount_per_unit: Money,
+    pub net_unit_price: Money,
+    pub cost_price: Money,
+    /// Markup of the net price over cost, in percent
+    pub margin_percent: f64,
+    /// Markup the product's category or the store requires
+    pub min_margin_percent: f64,
+    /// Lowest net unit price that keeps the minimum margin
+    pub minimum_unit_price: Money,
+}
+
+/// A product with no cost price: exempt from the margin check, but worth fixing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UncostedItem {
+    pub product_id: i64,
+    pub sku: String,
+    pub product_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarginCheck {
+    pub policy: BelowCostPolicy,
+    pub items: Vec<BelowCostItem>,
+    pub uncosted_items: Vec<UncostedItem>,
+    /// The sale will be rejected without `below_cost_approved_by`
+    pub requires_override: bool,
+}
+
+pub async fn below_cost_policy(
+    conn: &mut SqliteConnection,
+    location_id: Option<i64>,
+) -> AppResult<BelowCostPolicy> {
+    let location_id = location_id.unwrap_or(DEFAULT_LOCATION_ID);
+    let policy: Option<String> =
+        sqlx::query_scalar("SELECT below_cost_policy FROM locations WHERE id = ?1")
+            .bind(location_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    match policy {
+        Some(policy) => BelowCostPolicy::parse(&policy),
+        None if location_id == DEFAULT_LOCATION_ID => Ok(BelowCostPolicy::Warn),
+        None => Err(AppError::not_found("Location")),
+    }
+}
+
+/// The store-wide minimum margin at a location, used where a category sets none
+pub async fn location_min_margin(
+    conn: &mut SqliteConnection,
+    location_id: Option<i64>,
+) -> AppResult<f64> {
+    let min_margin: Option<f64> =
+        sqlx::query_scalar("SELECT min_margin_percent FROM locations WHERE id = ?1")
+            .bind(location_id.unwrap_or(DEFAULT_LOCATION_ID))
+            .fetch_optional(&mut *conn)
+            .await?;
+    Ok(min_margin.unwrap_or(0.0))
+}
+
+/// A product's cost and the minimum margin its category, or else the store, requires
+struct ProductMargin {
+    sku: String,
+    name: String,
+    cost_price: Money,
+    min_margin_percent: f64,
+}
+
+impl ProductMargin {
+    async fn load(conn: &mut SqliteConnection, product_id: i64, store_min: f64) -> AppResult<Self> {
+        let product = sqlx::query(
+            "SELECT p.sku, p.name, p.cost_price, c.min_margin_percent
+             FROM products p
+             LEFT JOIN categories c ON c.name = p.category
+             WHERE p.id = ?1",
+        )
+        .bind(product_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::not_found(&format!("product {}", product_id)))?;
+        let category_min: Option<f64> = product.try_get("min_margin_percent")?;
+        Ok(ProductMargin {
+            sku: product.try_get("sku")?,
+            name: product.try_get("name")?,
+            cost_price: product.try_get("cost_price")?,
+            min_margin_percent: category_min.unwrap_or(store_min),
+        })
+    }
+
+    fn markup(&self) -> f64 {
+        1.0 + self.min_margin_percent / 100.0
+    }
+
+    fn uncosted(&self, product_id: i64) -> UncostedItem {
+        UncostedItem {
+            product_id,
+            sku: self.sku.clone(),
+            product_name: self.name.clone(),
+        }
+    }
+}
+
+fn margin_percent(net_unit_price: Money, cost_price: Money) -> f64 {
+    let markup = (net_unit_price - cost_price).minor() as f64 / cost_price.minor() as f64 * 100.0;
+    (markup * 10.0).round() / 10.0
+}
+
+/// Lines whose price after their own discount is under cost plus the minimum margin,
+/// and the lines skipped because their product has no cost price
+pub async fn find_below_cost_items(
+    conn: &mut SqliteConnection,
+    items: &[SaleItemRequest],
+    store_min_margin: f64,
+) -> AppResult<(Vec<BelowCostItem>, Vec<UncostedItem>)> {
+    let mut below_cost = Vec::new();
+    let mut uncosted = Vec::new();
+    for item in items {
+        if item.whole_quantity() <= 0 {
+            continue;
+        }
+        let product = ProductMargin::load(conn, item.product_id, store_min_margin).await?;
+        if product.cost_price <= Money::ZERO {
+            uncosted.push(product.uncosted(item.product_id));
+            continue;
+        }
+
+        // Compare whole lines so a discount that does not split evenly per unit is not rounded away
+        let net_line = item.extend(item.charged_price()) - item.discount_amount;
+        if net_line >= item.extend(product.cost_price).times_decimal(product.markup()) {
+            continue;
+        }
+        let discount_per_unit =
+            Money::from_minor(item.discount_amount.minor() / item.whole_quantity() as i64);
+        let net_unit_price = item.charged_price() - discount_per_unit;
+        below_cost.push(BelowCostItem {
+            product_id: item.product_id,
+            quantity: item.whole_quantity(),
+            unit_price: item.charged_price(),
+            discount_per_unit,
+            net_unit_price,
+            margin_percent: margin_percent(net_unit_price, product.cost_price),
+            min_margin_percent: product.min_margin_percent,
+            minimum_unit_price: product.cost_price.times_decimal(product.markup()),
+            cost_price: product.cost_price,
+            sku: product.sku,
+            product_name: product.name,
+        });
+    }
+    Ok((below_cost, uncosted))
+}
+
+/// Whether a new selling price keeps the product's minimum margin at the store.
+/// Returns the shortfall, if any, and whether the product has no cost to check against.
+pub async fn check_selling_price(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    selling_price: Money,
+    store_min_margin: f64,
+) -> AppResult<(Option<BelowCostItem>, Option<UncostedItem>)> {
+    let product = ProductMargin::load(conn, product_id, store_min_margin).await?;
+    if product.cost_price <= Money::ZERO {
+        return Ok((None, Some(product.uncosted(product_id))));
+    }
+    let minimum_unit_price = product.cost_price.times_decimal(product.markup());
+    if selling_price >= minimum_unit_price {
+        return Ok((None, None));
+    }
+    Ok((
+        Some(BelowCostItem {
+            product_id,
+            quantity: 1,
+            unit_price: selling_price,
+            discount_per_unit: Money::ZERO,
+            net_unit_price: selling_price,
+            margin_percent: margin_percent(selling_price, product.cost_price),
+            min_margin_percent: product.min_margin_percent,
+            minimum_unit_price,
+            cost_price: product.cost_price,
+            sku: product.sku,
+            product_name: product.name,
+        }),
+        None,
+    ))
+}
+
+/// "TILE at 4.50 (margin -10.0%, minimum 20%)" for each line, for rejection messages
+pub fn describe_below_cost(items: &[BelowCostItem]) -> String {
+    items
+        .iter()
+        .map(|item| {
+            format!(
+                "{} at {} (margin {:.1}%, minimum {}%)",
+                item.sku, item.net_unit_price, item.margin_percent, item.min_margin_percent
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The offending lines of a sale and whether its location would reject it
+pub async fn check_sale_margins(
+    conn: &mut SqliteConnection,
+    request: &CreateSaleRequest,
+) -> AppResult<MarginCheck> {
+    let policy = below_cost_policy(conn, request.location_id).await?;
+    let store_min_margin = location_min_margin(conn, request.location_id).await?;
+    let (items, uncosted_items) =
+        find_below_cost_items(conn, &request.items, store_min_margin).await?;
+    let requires_override = policy == BelowCostPolicy::Block && !items.is_empty();
+    Ok(MarginCheck {
+        policy,
+        items,
+        uncosted_items,
+        requires_override,
+    })
+}
+
+/// Reject a sale with lines under their minimum margin where the location blocks them,
+/// unless an active manager or admin approved it. Returns the offending lines so callers
+/// can log them.
+pub async fn enforce_sale_margins(
+    conn: &mut SqliteConnection,
+    request: &CreateSaleRequest,
+) -> AppResult<Vec<BelowCostItem>> {
+    let check = check_sale_margins(conn, request).await?;
+    if !check.requires_override {
+        return Ok(check.items);
+    }
+
+    let Some(approver) = request.below_cost_approved_by else {
+        return Err(AppError::Validation {
+            field: Some("below_cost_approved_by".to_string()),
+            message: format!(
+                "Manager approval required: {} priced below the minimum margin",
+                describe_below_cost(&check.items)
+            ),
+        });
+    };
+
+    require_manager(conn, approver, "approve a sale below cost").await?;
+    Ok(check.items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    fn sale(product_id: i64, quantity: i32, unit_price: f64, discount: f64) -> CreateSaleRequest {
+        let unit_price = Money::from_major(unit_price);
+        let discount_amount = Money::from_major(discount);
+        let line_total = unit_price.times(quantity) - discount_amount;
+        CreateSaleRequest {
+            items: vec![SaleItemRequest {
+                product_id,
+                quantity,
+                unit_price,
+                discount_amount,
+                line_total,
+                measured_quantity: None,
+                price_override: None,
+                override_reason: None,
+            }],
+            subtotal: line_total,
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: line_total,
+            payment_method: "cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_block_policy_needs_manager_approval() {
+        let pool = test_pool().await;
+        // Cost is half the selling price: 5.00
+        let product_id = insert_test_product(&pool, "TILE", 10.0, 50).await;
+        let cashier = insert_test_user(&pool, "cashier").await;
+        let manager = insert_test_user(&pool, "manager").await;
+        sqlx::query("UPDATE users SET role = 'Manager' WHERE id = ?1")
+            .bind(manager)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+
+        // 3 x 10.00 - 15.00 = 15.00, exactly at cost
+        let at_cost = sale(product_id, 3, 10.0, 15.0);
+        assert!(check_sale_margins(&mut conn, &at_cost).await.unwrap().items.is_empty());
+
+        let mut below = sale(product_id, 3, 10.0, 16.5);
+        let check = check_sale_margins(&mut conn, &below).await.unwrap();
+        assert_eq!(check.policy, BelowCostPolicy::Warn);
+        assert!(!check.requires_override);
+        assert_eq!(check.items.len(), 1);
+        assert_eq!(check.items[0].net_unit_price, Money::from_major(4.5));
+        assert_eq!(check.items[0].cost_price, Money::from_major(5.0));
+        assert_eq!(enforce_sale_margins(&mut conn, &below).await.unwrap().len(), 1);
+
+        sqlx::query("UPDATE locations SET below_cost_policy = 'block' WHERE id = 1")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        let err = enforce_sale_margins(&mut conn, &below).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert!(err.message().contains("TILE"));
+
+        below.below_cost_approved_by = Some(cashier);
+        let err = enforce_sale_margins(&mut conn, &below).await.unwrap_err();
+        assert_eq!(err.code(), "PERMISSION_DENIED");
+
+        below.below_cost_approved_by = Some(manager);
+        assert!(enforce_sale_margins(&mut conn, &below).await.is_ok());
+
+        below.location_id = Some(99);
+        let err = enforce_sale_margins(&mut conn, &below).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_minimum_margin_by_store_and_category() {
+        let pool = test_pool().await;
+        // Cost 5.00 each
+        let tile = insert_test_product(&pool, "TILE", 10.0, 50).await;
+        let grout = insert_test_product(&pool, "GROUT", 10.0, 50).await;
+        let freebie = insert_test_product(&pool, "FREEBIE", 1.0, 50).await;
+        for (sql, product_id) in [
+            ("UPDATE products SET category = 'Clearance' WHERE id = ?1", grout),
+            ("UPDATE products SET cost_price = 0 WHERE id = ?1", freebie),
+        ] {
+            sqlx::query(sql).bind(product_id).execute(&pool).await.unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO categories (name, min_margin_percent) VALUES ('Clearance', 10);
+             UPDATE locations SET min_margin_percent = 25, below_cost_policy = 'block' WHERE id = 1",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+
+        // 6.00 is above cost but under the store's 25% (6.25)
+        let check = check_sale_margins(&mut conn, &sale(tile, 2, 6.0, 0.0)).await.unwrap();
+        assert!(check.requires_override);
+        assert_eq!(check.items[0].margin_percent, 20.0);
+        assert_eq!(check.items[0].minimum_unit_price, Money::from_major(6.25));
+        let err = enforce_sale_margins(&mut conn, &sale(tile, 2, 6.0, 0.0)).await.unwrap_err();
+        assert!(err.message().contains("TILE at 6.00 (margin 20.0%, minimum 25%)"));
+        assert!(enforce_sale_margins(&mut conn, &sale(tile, 2, 6.25, 0.0)).await.is_ok());
+
+        // Clearance only needs 10%, so 6.00 passes for grout
+        let check = check_sale_margins(&mut conn, &sale(grout, 2, 6.0, 0.0)).await.unwrap();
+        assert!(check.items.is_empty());
+        let check = check_sale_margins(&mut conn, &sale(grout, 2, 5.4, 0.0)).await.unwrap();
+        assert_eq!(check.items[0].min_margin_percent, 10.0);
+
+        // No cost price: exempt, but reported
+        let check = check_sale_margins(&mut conn, &sale(freebie, 1, 0.0, 0.0)).await.unwrap();
+        assert!(check.items.is_empty());
+        assert!(!check.requires_override);
+        assert_eq!(check.uncosted_items[0].sku, "FREEBIE");
+    }
+}
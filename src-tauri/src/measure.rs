@@ -0,0 +1,58 @@
+use crate::error::{AppError, AppResult};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::Row;
+
+/// Units of measure sold in decimal quantities, by name or abbreviation (case-insensitive)
+pub const MEASURED_UNITS: &[&str] = &[
+    "kilogram", "kg", "gram", "g", "pound", "lb", "meter", "m", "liter", "l", "square meter", "m²",
+];
+
+/// Stock of measured products is tracked to the gram, millilitre or millimetre
+const THOUSANDTHS: i64 = 1000;
+
+/// Whether products in this unit are weighed or measured rather than counted
+pub fn is_measured_unit(unit_of_measure: &str) -> bool {
+    let unit = unit_of_measure.trim().to_lowercase();
+    MEASURED_UNITS.contains(&unit.as_str())
+}
+
+/// Move a product's stock by a decimal quantity (negative to deduct). Whole units stay in
+/// `current_stock` and the part-unit left over in `stock_fraction`, so code that counts whole
+/// units keeps working. Returns the whole stock before and after.
+pub async fn adjust_measured_stock(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    change: f64,
+) -> AppResult<(i32, i32)> {
+    let row = sqlx::query("SELECT current_stock, stock_fraction FROM inventory WHERE product_id = ?1")
+        .bind(product_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::inventory_not_found(product_id))?;
+    let previous_stock: i32 = row.try_get("current_stock")?;
+    let fraction: f64 = row.try_get("stock_fraction")?;
+
+    // Work in thousandths so repeated part-unit sales never drift
+    let total = previous_stock as i64 * THOUSANDTHS
+        + (fraction * THOUSANDTHS as f64).round() as i64
+        + (change * THOUSANDTHS as f64).round() as i64;
+    let new_stock = total.div_euclid(THOUSANDTHS) as i32;
+    let new_fraction = total.rem_euclid(THOUSANDTHS) as f64 / THOUSANDTHS as f64;
+
+    sqlx::query(
+        "UPDATE inventory SET
+            current_stock = ?1,
+            available_stock = available_stock + ?2,
+            stock_fraction = ?3,
+            last_updated = CURRENT_TIMESTAMP
+         WHERE product_id = ?4",
+    )
+    .bind(new_stock)
+    .bind(new_stock - previous_stock)
+    .bind(new_fraction)
+    .bind(product_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok((previous_stock, new_stock))
+}
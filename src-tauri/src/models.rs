@@ -1,3 +1,4 @@
+use crate::money::Money;
 use serde::{Deserialize, Serialize};
 
 // User models
@@ -45,12 +46,19 @@ pub struct ChangePasswordRequest {
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
+    /// Store or branch to work at; the main store when omitted
+    #[serde(default)]
+    pub location_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginResponse {
     pub user: User,
     pub session_token: String,
+    /// The session's current location
+    pub location_id: i64,
+    /// The organization whose data the session works with
+    pub organization_id: i64,
 }
 
 // Customer models
@@ -204,6 +212,8 @@ pub struct Expense {
     pub created_by: Option<i64>,
     pub created_at: String,
     pub updated_at: String,
+    /// Recorded while demo mode was on
+    pub is_demo: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -349,6 +359,9 @@ pub struct CreateProductRequest {
     pub dimensions: Option<String>,
     pub supplier_info: Option<String>,
     pub reorder_point: i32,
+    /// Category row of the product; takes precedence over the legacy `category` name
+    #[serde(default)]
+    pub category_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -388,6 +401,9 @@ pub struct StockUpdateRequest {
     pub reference_id: Option<i64>,
     pub reference_type: Option<String>,
     pub user_id: Option<i64>,
+    /// Code from `stock_adjustment_reasons`, required by `adjust_stock`
+    #[serde(default)]
+    pub reason_code: Option<String>,
 }
 
 // Sales models
@@ -395,10 +411,10 @@ pub struct StockUpdateRequest {
 pub struct Sale {
     pub id: i64,
     pub sale_number: String,
-    pub subtotal: f64,
-    pub tax_amount: f64,
-    pub discount_amount: f64,
-    pub total_amount: f64,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub discount_amount: Money,
+    pub total_amount: Money,
     pub payment_method: String,
     pub payment_status: String,
     pub cashier_id: i64,
@@ -412,30 +428,116 @@ pub struct Sale {
     pub void_reason: Option<String>,
     pub shift_id: Option<i64>,
     pub created_at: String,
+    /// Times the receipt was printed again, filled in by `get_sale_details`
+    #[serde(default)]
+    pub reprint_count: i64,
+    /// Added to the total by cash rounding (negative when rounded down); the cash collected
+    /// is the total plus this
+    #[serde(default)]
+    pub cash_rounding: Money,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateSaleRequest {
     pub items: Vec<SaleItemRequest>,
-    pub subtotal: f64,
-    pub tax_amount: f64,
-    pub discount_amount: f64,
-    pub total_amount: f64,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub discount_amount: Money,
+    pub total_amount: Money,
     pub payment_method: String,
     pub payment_status: Option<String>,
     pub customer_name: Option<String>,
     pub customer_phone: Option<String>,
     pub customer_email: Option<String>,
     pub notes: Option<String>,
+    /// Till location, for its below-cost policy; the main store when omitted
+    #[serde(default)]
+    pub location_id: Option<i64>,
+    /// Organization the sale belongs to, taken from the session and never from the client
+    #[serde(skip)]
+    pub organization_id: Option<i64>,
+    /// Manager or admin who approved lines sold below cost where the location blocks them
+    #[serde(default)]
+    pub below_cost_approved_by: Option<i64>,
+    /// Cash handed over in another currency; converted to the store currency at the active rate
+    #[serde(default)]
+    pub tender: Option<TenderRequest>,
+    /// Manager PIN entered at the till to allow a cashier's price overrides on this sale only
+    #[serde(default)]
+    pub override_approval: Option<ManagerApproval>,
+    /// Part of the total paid in cash when the rest goes on a card. Omitted, it is the whole
+    /// total for a cash sale and nothing otherwise.
+    #[serde(default)]
+    pub cash_amount: Option<Money>,
+    /// Promotion codes entered at the till. Their discounts are part of the lines' discounts
+    /// and are recorded per line.
+    #[serde(default)]
+    pub promotion_codes: Vec<String>,
+}
+
+impl CreateSaleRequest {
+    /// Part of the total the store's cash rounding applies to
+    pub fn cash_portion(&self) -> Money {
+        match self.cash_amount {
+            Some(cash) => cash,
+            None if self.payment_method.eq_ignore_ascii_case("cash") => self.total_amount,
+            None => Money::ZERO,
+        }
+    }
+}
+
+/// A manager's credentials entered on the cashier's till
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManagerApproval {
+    pub username_or_badge: String,
+    pub pin: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenderRequest {
+    pub currency: String,
+    pub amount: Money,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SaleItemRequest {
     pub product_id: i64,
     pub quantity: i32,
-    pub unit_price: f64,
-    pub discount_amount: f64,
-    pub line_total: f64,
+    pub unit_price: Money,
+    pub discount_amount: Money,
+    pub line_total: Money,
+    /// Decimal quantity for products sold by weight, length or volume (2.5 kg). When set it
+    /// prices the line and `quantity` is taken as the whole units started (3 for 2.5).
+    #[serde(default)]
+    pub measured_quantity: Option<f64>,
+    /// Unit price charged instead of the regular one, e.g. to match a competitor
+    #[serde(default)]
+    pub price_override: Option<Money>,
+    #[serde(default)]
+    pub override_reason: Option<String>,
+}
+
+impl SaleItemRequest {
+    /// Unit price actually charged: the override when there is one
+    pub fn charged_price(&self) -> Money {
+        self.price_override.unwrap_or(self.unit_price)
+    }
+
+    /// Whole units of the line: the measured quantity rounded up, or the counted quantity
+    pub fn whole_quantity(&self) -> i32 {
+        match self.measured_quantity {
+            Some(measured) => measured.ceil() as i32,
+            None => self.quantity,
+        }
+    }
+
+    /// A per-unit amount (price or cost) extended over the line's quantity
+    pub fn extend(&self, per_unit: Money) -> Money {
+        match self.measured_quantity {
+            Some(measured) => per_unit.times_decimal(measured),
+            None => per_unit.times(self.quantity),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -444,11 +546,15 @@ pub struct SaleItem {
     pub sale_id: i64,
     pub product_id: i64,
     pub quantity: i32,
-    pub unit_price: f64,
-    pub discount_amount: f64,
-    pub line_total: f64,
-    pub tax_amount: f64,
-    pub cost_price: f64,
+    /// Decimal quantity of a weighed or measured line, `None` for counted items
+    pub measured_quantity: Option<f64>,
+    pub unit_price: Money,
+    pub discount_amount: Money,
+    pub line_total: Money,
+    pub tax_amount: Money,
+    pub cost_price: Money,
+    /// On-hand stock of the product immediately after this line was rung up
+    pub post_sale_stock: Option<i32>,
     pub created_at: String,
     pub product: Option<Product>,
 }
@@ -490,27 +596,36 @@ pub struct UpdateStoreConfigRequest {
 pub struct Shift {
     pub id: i64,
     pub user_id: i64,
+    /// Store or branch the register was at
+    pub location_id: i64,
     pub start_time: String,
     pub end_time: Option<String>,
-    pub opening_amount: f64,
-    pub closing_amount: Option<f64>,
-    pub total_sales: f64,
-    pub total_returns: f64,
-    pub cash_sales: f64,
-    pub card_sales: f64,
+    pub opening_amount: Money,
+    pub closing_amount: Option<Money>,
+    pub total_sales: Money,
+    pub total_returns: Money,
+    pub cash_sales: Money,
+    pub card_sales: Money,
+    /// Net cash rounding on the shift's sales, part of the cash that should be in the drawer
+    pub cash_rounding: Money,
     pub status: String,
     pub notes: Option<String>,
+    /// Times the drawer was opened without a sale
+    pub no_sale_count: i64,
     pub created_at: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateShiftRequest {
-    pub opening_amount: f64,
+    pub opening_amount: Money,
+    /// Store or branch the shift is worked at; the main store when omitted
+    #[serde(default)]
+    pub location_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CloseShiftRequest {
-    pub closing_amount: f64,
+    pub closing_amount: Money,
     pub notes: Option<String>,
 }
 
@@ -520,7 +635,7 @@ pub struct CashDrawerTransaction {
     pub id: i64,
     pub shift_id: i64,
     pub transaction_type: String,
-    pub amount: f64,
+    pub amount: Money,
     pub reason: Option<String>,
     pub user_id: i64,
     pub created_at: String,
@@ -530,12 +645,12 @@ pub struct CashDrawerTransaction {
 pub struct CreateCashDrawerTransactionRequest {
     pub shift_id: i64,
     pub transaction_type: String,
-    pub amount: f64,
+    pub amount: Money,
     pub reason: Option<String>,
 }
 
 // Receipt template models
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
 pub struct ReceiptTemplate {
     pub id: i64,
     pub name: String,
@@ -884,6 +999,22 @@ pub struct Location {
     pub currency: String,
     pub logo_url: Option<String>,
     pub is_active: bool,
+    /// `warn` or `block` for sale lines priced below cost
+    pub below_cost_policy: String,
+    /// Markup over cost, in percent, sale lines and selling prices must keep
+    pub min_margin_percent: f64,
+    /// Device path or `tcp://host:port` of the receipt printer the cash drawer is wired to
+    pub receipt_printer: Option<String>,
+    /// No-sale drawer openings in one shift before a warning notification
+    pub no_sale_alert_threshold: i64,
+    /// Manual stock adjustments worth more than this need a manager's approval
+    pub adjustment_approval_threshold: f64,
+    /// Sales returns by one cashier in a report period before they are flagged
+    pub return_alert_count: i64,
+    /// Refunded value by one cashier in a report period before they are flagged
+    pub return_alert_value: f64,
+    /// Closing message printed at the bottom of sale receipts
+    pub receipt_footer: String,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -917,6 +1048,14 @@ pub struct UpdateLocationRequest {
     pub currency: Option<String>,
     pub logo_url: Option<String>,
     pub is_active: Option<bool>,
+    pub below_cost_policy: Option<String>,
+    pub min_margin_percent: Option<f64>,
+    pub receipt_printer: Option<String>,
+    pub no_sale_alert_threshold: Option<i64>,
+    pub adjustment_approval_threshold: Option<f64>,
+    pub return_alert_count: Option<i64>,
+    pub return_alert_value: Option<f64>,
+    pub receipt_footer: Option<String>,
 }
 
 // ==================== PROMOTION MODELS ====================
@@ -942,6 +1081,29 @@ pub struct Promotion {
     pub created_by: Option<i64>,
     pub created_at: String,
     pub updated_at: String,
+    /// Receipt footer rule that issued this promotion as a coupon
+    pub footer_rule_id: Option<i64>,
+    /// Sale whose receipt the coupon was printed on
+    pub source_sale_id: Option<i64>,
+    /// Buy X Get Y: units bought for each group of `get_quantity` discounted units
+    pub buy_quantity: Option<i32>,
+    pub get_quantity: Option<i32>,
+    /// Percent off the discounted units; 100 when unset, making them free
+    pub get_discount_percent: Option<f64>,
+    /// Marketing campaign the promotion's sales are attributed to
+    pub campaign_id: Option<i64>,
+}
+
+/// A promotion used on a sale
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PromotionUsage {
+    pub id: i64,
+    pub promotion_id: i64,
+    pub code: String,
+    pub sale_id: i64,
+    pub discount_amount: f64,
+    /// Sale whose receipt issued the coupon, for coupons printed on receipts
+    pub source_sale_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -959,6 +1121,15 @@ pub struct CreatePromotionRequest {
     pub customer_type: Option<String>,
     pub applicable_products: Option<String>,
     pub applicable_categories: Option<String>,
+    /// Buy X Get Y parameters, required for that discount type
+    #[serde(default)]
+    pub buy_quantity: Option<i32>,
+    #[serde(default)]
+    pub get_quantity: Option<i32>,
+    #[serde(default)]
+    pub get_discount_percent: Option<f64>,
+    #[serde(default)]
+    pub campaign_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -977,6 +1148,14 @@ pub struct UpdatePromotionRequest {
     pub applicable_products: Option<String>,
     pub applicable_categories: Option<String>,
     pub is_active: Option<bool>,
+    #[serde(default)]
+    pub buy_quantity: Option<i32>,
+    #[serde(default)]
+    pub get_quantity: Option<i32>,
+    #[serde(default)]
+    pub get_discount_percent: Option<f64>,
+    #[serde(default)]
+    pub campaign_id: Option<i64>,
 }
 
 // ==================== APPOINTMENT MODELS ====================
@@ -1068,3 +1247,47 @@ pub struct UpdateIntegrationRequest {
     pub config: Option<String>,
     pub is_enabled: Option<bool>,
 }
+
+/// Where the next page of a newest-first list starts: the `created_at` and `id` of the
+/// last row already returned
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCursor {
+    pub created_at: String,
+    pub id: i64,
+}
+
+/// One page of a list along with how many rows match its filters in total
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total_count: i64,
+    pub has_more: bool,
+    /// Pass back as `after_id`/`after_created_at` for the next page; `None` on the last one
+    pub next_cursor: Option<PageCursor>,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, total_count: i64, offset: i32) -> Self {
+        let has_more = (offset as i64) + (items.len() as i64) < total_count;
+        Page { items, total_count, has_more, next_cursor: None }
+    }
+}
+
+/// What a list command returns: the bare rows as it always has, or the whole `Page`
+/// when the caller opts in with `envelope`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Listing<T> {
+    Items(Vec<T>),
+    Page(Page<T>),
+}
+
+impl<T> Listing<T> {
+    pub fn new(page: Page<T>, envelope: Option<bool>) -> Self {
+        if envelope.unwrap_or(false) {
+            Listing::Page(page)
+        } else {
+            Listing::Items(page.items)
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{Sqlite, SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Decode, Encode, Type};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+/// Decimal places amounts are kept to: hundredths, the smallest unit of the finest currency
+/// the app has. Each currency rounds to its own smallest unit, see `minor_units`.
+pub const STORED_DECIMALS: u32 = 2;
+
+/// Minor units per major unit of a currency with `decimal_places` decimals: 100 for USD,
+/// 1 for XAF and JPY
+pub const fn minor_units(decimal_places: u32) -> i64 {
+    10_i64.pow(decimal_places)
+}
+
+/// Stored units per major unit
+const SCALE: i64 = minor_units(STORED_DECIMALS);
+
+/// A monetary amount stored as integer minor units (cents) so sums never drift.
+///
+/// Crosses the frontend and database boundaries as a decimal number in major units
+/// (`12.34`), rounded half-up to the nearest minor unit on the way in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+/// Round a value to the nearest integer, halves away from zero.
+/// Scaled values such as `1.005 * 100` come out as `100.49999999999999`, so the
+/// representation error is removed before rounding.
+pub fn round_half_up(value: f64) -> i64 {
+    let cleaned = (value * 1e6).round() / 1e6;
+    cleaned.round() as i64
+}
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_minor(minor: i64) -> Self {
+        Money(minor)
+    }
+
+    /// Convert a major-unit amount (e.g. `12.345`), rounding half-up to the nearest cent
+    pub fn from_major(amount: f64) -> Self {
+        Money(round_half_up(amount * SCALE as f64))
+    }
+
+    pub const fn minor(self) -> i64 {
+        self.0
+    }
+
+    pub fn to_major(self) -> f64 {
+        self.0 as f64 / SCALE as f64
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// Round half-up to the minor unit of a currency with `decimals` places (0 for
+    /// whole-unit currencies). Places at or beyond `STORED_DECIMALS` are a no-op.
+    pub fn round_to_decimals(self, decimals: u32) -> Self {
+        if decimals >= STORED_DECIMALS {
+            return self;
+        }
+        let step = SCALE / minor_units(decimals);
+        let rounded = (self.0.abs() + step / 2) / step * step;
+        Money(rounded * self.0.signum())
+    }
+
+    /// Unit price times quantity
+    pub fn times(self, quantity: i32) -> Self {
+        Money(self.0 * quantity as i64)
+    }
+
+    /// Unit price times a decimal quantity (2.5 kg), rounded half-up to the cent
+    pub fn times_decimal(self, quantity: f64) -> Self {
+        Money(round_half_up(self.0 as f64 * quantity))
+    }
+
+    /// `rate` percent of this amount (e.g. `8.5` for 8.5% tax), rounded half-up per line
+    pub fn percent(self, rate: f64) -> Self {
+        Money(round_half_up(self.0 as f64 * rate / 100.0))
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        Money(iter.map(|m| m.0).sum())
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let scale = SCALE as u64;
+        let places = STORED_DECIMALS as usize;
+        write!(f, "{}{}.{:0places$}", sign, abs / scale, abs % scale, places = places)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_major())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(Money::from_major)
+    }
+}
+
+// Money columns stay REAL in major units so existing SQL (SUM, comparisons, reports) keeps working
+impl Type<Sqlite> for Money {
+    fn type_info() -> SqliteTypeInfo {
+        <f64 as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <f64 as Type<Sqlite>>::compatible(ty) || <i64 as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Money {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> Result<IsNull, BoxDynError> {
+        <f64 as Encode<'q, Sqlite>>::encode(self.to_major(), buf)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for Money {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        <f64 as Decode<'r, Sqlite>>::decode(value).map(Money::from_major)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounds_half_up() {
+        assert_eq!(Money::from_major(1.005).minor(), 101);
+        assert_eq!(Money::from_major(2.675).minor(), 268);
+        assert_eq!(Money::from_major(0.004).minor(), 0);
+        assert_eq!(Money::from_major(-1.005).minor(), -101);
+        assert_eq!(Money::from_minor(1999).percent(8.5).minor(), 170); // 169.915
+        assert_eq!(Money::from_minor(1000).percent(12.25).minor(), 123); // 122.5
+    }
+
+    #[test]
+    fn test_rounds_to_currency_decimals() {
+        assert_eq!(
+            Money::from_major(384.81).round_to_decimals(0),
+            Money::from_major(385.0)
+        );
+        assert_eq!(
+            Money::from_major(384.5).round_to_decimals(0),
+            Money::from_major(385.0)
+        );
+        assert_eq!(
+            Money::from_major(384.49).round_to_decimals(0),
+            Money::from_major(384.0)
+        );
+        assert_eq!(
+            Money::from_major(-2.5).round_to_decimals(0),
+            Money::from_major(-3.0)
+        );
+        assert_eq!(
+            Money::from_major(1.25).round_to_decimals(1),
+            Money::from_major(1.3)
+        );
+        assert_eq!(
+            Money::from_major(1.23).round_to_decimals(2),
+            Money::from_major(1.23)
+        );
+    }
+
+    #[test]
+    fn test_sums_do_not_drift() {
+        // Summing in f64 gives 104.99999999999999
+        let lines = [47.65, 29.11, 28.24];
+        let float_total: f64 = lines.iter().sum();
+        assert_eq!(float_total, 104.99999999999999);
+
+        let total: Money = lines.iter().map(|&l| Money::from_major(l)).sum();
+        assert_eq!(total, Money::from_minor(10500));
+        assert_eq!(total.to_major(), 105.0);
+
+        // Three items at 0.10 plus 10% tax per line
+        let line = Money::from_major(0.1).times(3);
+        assert_eq!(line + line.percent(10.0), Money::from_minor(33));
+    }
+
+    #[test]
+    fn test_serde_uses_major_units() {
+        let amount: Money = serde_json::from_str("19.999").unwrap();
+        assert_eq!(amount.minor(), 2000);
+        assert_eq!(
+            serde_json::to_string(&Money::from_minor(1234)).unwrap(),
+            "12.34"
+        );
+        assert_eq!(Money::from_minor(-5).to_string(), "-0.05");
+    }
+
+    #[tokio::test]
+    async fn test_round_trips_through_sqlite() {
+        let pool = crate::test_utils::test_pool().await;
+        let (stored, summed): (Money, Money) = sqlx::query_as(
+            "SELECT ?1, (SELECT SUM(v) FROM (SELECT 0.1 AS v UNION ALL SELECT 0.2))",
+        )
+        .bind(Money::from_minor(10499))
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(stored, Money::from_minor(10499));
+        assert_eq!(summed, Money::from_minor(30));
+    }
+}
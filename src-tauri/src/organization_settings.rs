@@ -0,0 +1,143 @@
+//! Typed organization settings, stored as JSON in `organizations.settings`. Missing keys
+//! take their defaults, so older or hand-edited blobs keep loading.
+
+use crate::cash_rounding::RoundingDirection;
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrganizationSettings {
+    /// ISO code of the currency the organization reports in
+    pub currency: String,
+    /// Step cash totals are rounded to, e.g. 0.05; zero leaves them unrounded
+    pub cash_rounding: Money,
+    /// Which way cash totals are rounded to the step
+    pub cash_rounding_direction: RoundingDirection,
+    /// Footer printed on receipts instead of the location's own, when set
+    pub receipt_footer: Option<String>,
+    /// Share of revenue the profit and cash flow reports estimate for operating expenses
+    pub operating_expense_percent: f64,
+}
+
+impl Default for OrganizationSettings {
+    fn default() -> Self {
+        OrganizationSettings {
+            currency: "USD".to_string(),
+            cash_rounding: Money::ZERO,
+            cash_rounding_direction: RoundingDirection::Nearest,
+            receipt_footer: None,
+            operating_expense_percent: 15.0,
+        }
+    }
+}
+
+impl OrganizationSettings {
+    /// Parse the settings column; an empty or unreadable blob gives the defaults
+    pub fn parse(settings: Option<&str>) -> Self {
+        settings
+            .filter(|settings| !settings.trim().is_empty())
+            .and_then(|settings| serde_json::from_str(settings).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn validate(&self) -> AppResult<()> {
+        let currency = self.currency.as_str();
+        if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(AppError::validation(
+                "currency",
+                "Currency must be a three-letter ISO code such as USD",
+            ));
+        }
+        if self.cash_rounding.is_negative() {
+            return Err(AppError::negative_value("cash_rounding"));
+        }
+        if !(0.0..=100.0).contains(&self.operating_expense_percent) {
+            return Err(AppError::validation(
+                "operating_expense_percent",
+                "Operating expense percent must be between 0 and 100",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Settings of an organization, the defaults when none are stored
+pub async fn load(conn: &mut SqliteConnection, organization_id: i64) -> AppResult<OrganizationSettings> {
+    let settings: Option<Option<String>> =
+        sqlx::query_scalar("SELECT settings FROM organizations WHERE id = ?1")
+            .bind(organization_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    let settings = settings.ok_or_else(|| AppError::not_found("Organization"))?;
+    Ok(OrganizationSettings::parse(settings.as_deref()))
+}
+
+/// Validate and store an organization's settings
+pub async fn save(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    settings: &OrganizationSettings,
+) -> AppResult<()> {
+    settings.validate()?;
+    let known: Option<i64> = sqlx::query_scalar("SELECT 1 FROM currencies WHERE code = ?1")
+        .bind(&settings.currency)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if known.is_none() {
+        return Err(AppError::validation(
+            "currency",
+            &format!("Unknown currency {}", settings.currency),
+        ));
+    }
+
+    let json = serde_json::to_string(settings).map_err(|e| AppError::Internal {
+        message: format!("Failed to serialize settings: {}", e),
+    })?;
+    let updated = sqlx::query(
+        "UPDATE organizations SET settings = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+    )
+    .bind(json)
+    .bind(organization_id)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    if updated == 0 {
+        return Err(AppError::not_found("Organization"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_pool;
+
+    #[tokio::test]
+    async fn test_settings_round_trip_and_fill_missing_keys() {
+        let pool = test_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        sqlx::query("UPDATE organizations SET settings = '{\"currency\": \"XAF\"}' WHERE id = 1")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let mut settings = load(&mut conn, 1).await.unwrap();
+        assert_eq!(settings.currency, "XAF");
+        assert_eq!(settings.operating_expense_percent, 15.0);
+
+        settings.cash_rounding = Money::from_major(0.05);
+        settings.cash_rounding_direction = RoundingDirection::Up;
+        save(&mut conn, 1, &settings).await.unwrap();
+        assert_eq!(load(&mut conn, 1).await.unwrap(), settings);
+
+        settings.cash_rounding = Money::from_major(-0.05);
+        let err = save(&mut conn, 1, &settings).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        settings.cash_rounding = Money::from_major(0.05);
+        settings.currency = "ZZZ".to_string();
+        assert!(save(&mut conn, 1, &settings).await.is_err());
+    }
+}
@@ -0,0 +1,367 @@
+use crate::error::{AppError, AppResult};
+use crate::lockout::{self, LockoutPolicy, LoginAttemptStatus};
+use crate::session::SessionManager;
+use crate::validation;
+use bcrypt::{hash, verify, DEFAULT_COST};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use rand::Rng;
+use sqlx::{Row, SqlitePool};
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// How long a generated reset code stays valid
+pub const RESET_CODE_TTL_MINUTES: i64 = 60;
+
+const RESET_CODE_LENGTH: usize = 8;
+// No 0/O or 1/I so codes can be read out loud or copied from paper
+const RESET_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+// bcrypt at the default cost is very slow in unoptimised test builds
+const HASH_COST: u32 = if cfg!(test) { 4 } else { DEFAULT_COST };
+
+/// A freshly generated reset code; the plain code is only ever returned here
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PasswordResetCode {
+    pub user_id: i64,
+    pub code: String,
+    pub expires_at: String,
+}
+
+fn generate_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..RESET_CODE_LENGTH)
+        .map(|_| RESET_CODE_ALPHABET[rng.gen_range(0..RESET_CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+fn locked_error(locked_until: DateTime<Utc>) -> AppError {
+    AppError::reset_locked(&locked_until.format(TIMESTAMP_FORMAT).to_string())
+}
+
+/// Count a wrong username or code against the account's lockout, which locks redemption
+/// and sign-in alike once the store's limit is reached
+async fn reject_attempt(
+    pool: &SqlitePool,
+    attempt_key: &str,
+    policy: &LockoutPolicy,
+    now: DateTime<Utc>,
+) -> AppResult<AppError> {
+    Ok(match lockout::record_failed_login(pool, attempt_key, policy, now).await? {
+        LoginAttemptStatus::Locked { locked_until } => locked_error(locked_until),
+        LoginAttemptStatus::Allowed { .. } => AppError::reset_code_invalid(),
+    })
+}
+
+/// Create a single-use reset code for a user. Only the bcrypt hash is stored.
+pub async fn create_reset_code(
+    pool: &SqlitePool,
+    user_id: i64,
+    created_by: i64,
+    now: DateTime<Utc>,
+) -> AppResult<PasswordResetCode> {
+    let exists: Option<i64> = sqlx::query_scalar("SELECT id FROM users WHERE id = ?1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+    if exists.is_none() {
+        return Err(AppError::not_found("user"));
+    }
+
+    let code = generate_code();
+    let code_hash = hash(&code, HASH_COST)
+        .map_err(|e| AppError::database_error(&format!("Code hashing error: {}", e)))?;
+    let expires_at = (now + Duration::minutes(RESET_CODE_TTL_MINUTES))
+        .format(TIMESTAMP_FORMAT)
+        .to_string();
+
+    sqlx::query(
+        "INSERT INTO password_reset_codes (user_id, code_hash, expires_at, created_by, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(user_id)
+    .bind(&code_hash)
+    .bind(&expires_at)
+    .bind(created_by)
+    .bind(now.format(TIMESTAMP_FORMAT).to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    Ok(PasswordResetCode {
+        user_id,
+        code,
+        expires_at,
+    })
+}
+
+/// Redeem a reset code: set the new password and sign the user out everywhere
+pub async fn reset_password_with_code(
+    pool: &SqlitePool,
+    sessions: &SessionManager,
+    username: &str,
+    code: &str,
+    new_password: &str,
+    now: DateTime<Utc>,
+) -> AppResult<()> {
+    validation::validate_required(username, "username")?;
+    validation::validate_required(code, "code")?;
+    validation::validate_password_strength(new_password)?;
+
+    // Guesses share the login failure counter, so a code cannot be brute-forced here
+    // any faster than the password can at sign-in
+    let policy = LockoutPolicy::load(pool).await?;
+    let attempt_key = lockout::attempt_key(pool, username).await?;
+    if let LoginAttemptStatus::Locked { locked_until } =
+        lockout::check_login_allowed(pool, &attempt_key, &policy, now).await?
+    {
+        return Err(locked_error(locked_until));
+    }
+
+    let user_id: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM users WHERE username = ?1 AND is_active = 1")
+            .bind(username)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::database_error(&e.to_string()))?;
+    let Some(user_id) = user_id else {
+        return Err(reject_attempt(pool, &attempt_key, &policy, now).await?);
+    };
+
+    let rows = sqlx::query(
+        "SELECT id, code_hash, expires_at, used_at
+         FROM password_reset_codes
+         WHERE user_id = ?1
+         ORDER BY id DESC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    let code = normalize_code(code);
+    let matched = rows.into_iter().find(|row| {
+        let code_hash: String = row.try_get("code_hash").unwrap_or_default();
+        verify(&code, &code_hash).unwrap_or(false)
+    });
+    let Some(row) = matched else {
+        return Err(reject_attempt(pool, &attempt_key, &policy, now).await?);
+    };
+
+    let code_id: i64 = row
+        .try_get("id")
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+    let used_at: Option<String> = row.try_get("used_at").ok().flatten();
+    let expires_at: String = row
+        .try_get("expires_at")
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    if used_at.is_some() {
+        return Err(AppError::reset_code_used());
+    }
+    let expired = NaiveDateTime::parse_from_str(&expires_at, TIMESTAMP_FORMAT)
+        .map(|dt| dt.and_utc() <= now)
+        .unwrap_or(true);
+    if expired {
+        return Err(AppError::reset_code_expired());
+    }
+
+    let password_hash = hash(new_password, HASH_COST)
+        .map_err(|e| AppError::database_error(&format!("Password hashing error: {}", e)))?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    // Guard on used_at so two concurrent redemptions cannot both succeed
+    let claimed = sqlx::query(
+        "UPDATE password_reset_codes SET used_at = ?1 WHERE id = ?2 AND used_at IS NULL",
+    )
+    .bind(now.format(TIMESTAMP_FORMAT).to_string())
+    .bind(code_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::database_error(&e.to_string()))?;
+    if claimed.rows_affected() == 0 {
+        return Err(AppError::reset_code_used());
+    }
+
+    sqlx::query(
+        "UPDATE users SET password_hash = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+    )
+    .bind(&password_hash)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    sessions.invalidate_user_sessions(user_id)?;
+    lockout::unlock_user_account(pool, user_id).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_pool;
+
+    async fn insert_user(pool: &SqlitePool, username: &str) -> i64 {
+        sqlx::query(
+            "INSERT INTO users (username, email, password_hash, first_name, last_name, role)
+             VALUES (?1, ?1 || '@example.com', 'x', 'Test', 'User', 'Cashier')",
+        )
+        .bind(username)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+    }
+
+    #[tokio::test]
+    async fn test_reset_updates_password_and_invalidates_sessions() {
+        let pool = test_pool().await;
+        let sessions = SessionManager::new();
+        let user_id = insert_user(&pool, "cashier1").await;
+        let token = sessions.create_session(user_id, "cashier1".to_string(), "Cashier".to_string());
+        let now = Utc::now();
+
+        let reset = create_reset_code(&pool, user_id, 1, now).await.unwrap();
+        reset_password_with_code(
+            &pool,
+            &sessions,
+            "cashier1",
+            &reset.code.to_lowercase(),
+            "NewPassword1",
+            now,
+        )
+        .await
+        .unwrap();
+
+        let stored: String = sqlx::query_scalar("SELECT password_hash FROM users WHERE id = ?1")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(verify("NewPassword1", &stored).unwrap());
+        assert!(sessions.validate_session(&token).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_used_and_expired_codes_fail_distinctly() {
+        let pool = test_pool().await;
+        let sessions = SessionManager::new();
+        let user_id = insert_user(&pool, "cashier1").await;
+        let now = Utc::now();
+
+        let reset = create_reset_code(&pool, user_id, 1, now).await.unwrap();
+        reset_password_with_code(
+            &pool,
+            &sessions,
+            "cashier1",
+            &reset.code,
+            "NewPassword1",
+            now,
+        )
+        .await
+        .unwrap();
+        let err =
+            reset_password_with_code(&pool, &sessions, "cashier1", &reset.code, "OtherPass1", now)
+                .await
+                .unwrap_err();
+        assert_eq!(err.code(), AppError::reset_code_used().code());
+
+        let reset = create_reset_code(&pool, user_id, 1, now).await.unwrap();
+        let later = now + Duration::minutes(RESET_CODE_TTL_MINUTES + 1);
+        let err = reset_password_with_code(
+            &pool,
+            &sessions,
+            "cashier1",
+            &reset.code,
+            "OtherPass1",
+            later,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code(), AppError::reset_code_expired().code());
+
+        let err =
+            reset_password_with_code(&pool, &sessions, "cashier1", "WRONGCODE", "OtherPass1", now)
+                .await
+                .unwrap_err();
+        assert_eq!(err.code(), AppError::reset_code_invalid().code());
+    }
+
+    #[tokio::test]
+    async fn test_wrong_codes_lock_the_account() {
+        let pool = test_pool().await;
+        let sessions = SessionManager::new();
+        let user_id = insert_user(&pool, "cashier1").await;
+        let now = Utc::now();
+        let reset = create_reset_code(&pool, user_id, 1, now).await.unwrap();
+        let policy = LockoutPolicy::default();
+
+        let redeem = |code: String, now: DateTime<Utc>| {
+            let (pool, sessions) = (&pool, &sessions);
+            async move {
+                reset_password_with_code(pool, sessions, "cashier1", &code, "NewPassword1", now)
+                    .await
+            }
+        };
+
+        for _ in 1..policy.max_failed_attempts {
+            let err = redeem("WRONGCODE".to_string(), now).await.unwrap_err();
+            assert_eq!(err.code(), AppError::reset_code_invalid().code());
+        }
+        let err = redeem("WRONGCODE".to_string(), now).await.unwrap_err();
+        assert_eq!(err.code(), "RESET_LOCKED");
+
+        // Even the right code waits out the lockout, as does signing in
+        let err = redeem(reset.code.clone(), now).await.unwrap_err();
+        assert_eq!(err.code(), "RESET_LOCKED");
+        let key = lockout::attempt_key(&pool, "cashier1").await.unwrap();
+        let status = lockout::check_login_allowed(&pool, &key, &policy, now).await.unwrap();
+        assert!(matches!(status, LoginAttemptStatus::Locked { .. }));
+
+        let later = now + policy.lockout_duration(1) + Duration::seconds(1);
+        let reset = create_reset_code(&pool, user_id, 1, later).await.unwrap();
+        redeem(reset.code, later).await.unwrap();
+        let status = lockout::check_login_allowed(&pool, &key, &policy, later).await.unwrap();
+        assert!(matches!(status, LoginAttemptStatus::Allowed { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_reset_enforces_password_policy() {
+        let pool = test_pool().await;
+        let sessions = SessionManager::new();
+        let user_id = insert_user(&pool, "cashier1").await;
+        let now = Utc::now();
+
+        let reset = create_reset_code(&pool, user_id, 1, now).await.unwrap();
+        let err = reset_password_with_code(&pool, &sessions, "cashier1", &reset.code, "weak", now)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), AppError::weak_password().code());
+
+        // A rejected password does not burn the code
+        reset_password_with_code(
+            &pool,
+            &sessions,
+            "cashier1",
+            &reset.code,
+            "NewPassword1",
+            now,
+        )
+        .await
+        .unwrap();
+    }
+}
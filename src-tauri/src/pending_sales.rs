@@ -0,0 +1,286 @@
+//! Offline sale queue: sales the till rang up while the database was locked or a sync target
+//! was down, stored as their raw payload under a client-generated UUID and replayed through
+//! `create_sale` later, when they get their real sale numbers.
+
+use crate::commands::sales::create_sale_internal;
+use crate::error::{AppError, AppResult};
+use crate::models::CreateSaleRequest;
+use crate::validation::Validate;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+/// The payload the till queues: the sale as it would be sent to `create_sale`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OfflineSale {
+    /// Generated on the till so resending the same sale is harmless
+    pub client_uuid: String,
+    pub cashier_id: i64,
+    #[serde(default)]
+    pub shift_id: Option<i64>,
+    pub sale: CreateSaleRequest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingSale {
+    pub id: i64,
+    pub client_uuid: String,
+    pub status: String,
+    pub sale_id: Option<i64>,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub processed_at: Option<String>,
+}
+
+/// Outcome of replaying one queued sale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingSaleResult {
+    pub pending_sale_id: i64,
+    pub client_uuid: String,
+    pub sale_id: Option<i64>,
+    pub sale_number: Option<String>,
+    pub error: Option<String>,
+}
+
+fn parse_offline_sale(payload_json: &str) -> AppResult<OfflineSale> {
+    serde_json::from_str(payload_json)
+        .map_err(|e| AppError::validation("payload", &format!("Invalid offline sale: {}", e)))
+}
+
+async fn find_pending_sale(pool: &SqlitePool, client_uuid: &str) -> AppResult<PendingSale> {
+    let row = sqlx::query(
+        "SELECT id, client_uuid, status, sale_id, attempts, last_error, created_at, processed_at
+         FROM pending_sales WHERE client_uuid = ?1",
+    )
+    .bind(client_uuid)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("pending sale"))?;
+    Ok(PendingSale {
+        id: row.try_get("id")?,
+        client_uuid: row.try_get("client_uuid")?,
+        status: row.try_get("status")?,
+        sale_id: row.try_get("sale_id")?,
+        attempts: row.try_get("attempts")?,
+        last_error: row.try_get("last_error")?,
+        created_at: row.try_get("created_at")?,
+        processed_at: row.try_get("processed_at")?,
+    })
+}
+
+/// Queue a sale for later. Only the payload's shape is checked here: stock, prices and the
+/// organization's plan are checked when it is replayed. Queueing a UUID that is already in
+/// the queue returns the existing entry unchanged.
+pub async fn queue_offline_sale(
+    pool: &SqlitePool,
+    payload_json: &str,
+    organization_id: i64,
+) -> AppResult<PendingSale> {
+    let offline = parse_offline_sale(payload_json)?;
+    let client_uuid = uuid::Uuid::parse_str(&offline.client_uuid)
+        .map_err(|_| AppError::validation("client_uuid", "client_uuid must be a UUID"))?
+        .to_string();
+    offline.sale.validate()?;
+
+    sqlx::query(
+        "INSERT INTO pending_sales (client_uuid, payload, organization_id) VALUES (?1, ?2, ?3)
+         ON CONFLICT(client_uuid) DO NOTHING",
+    )
+    .bind(&client_uuid)
+    .bind(payload_json)
+    .bind(organization_id)
+    .execute(pool)
+    .await?;
+    find_pending_sale(pool, &client_uuid).await
+}
+
+/// Replay every queued sale that has not gone through yet, oldest first, failed ones included.
+/// A sale that fails again keeps its payload and records the error for the next run.
+pub async fn process_pending_sales(pool: &SqlitePool) -> AppResult<Vec<PendingSaleResult>> {
+    let queued: Vec<(i64, String, String, i64)> = sqlx::query_as(
+        "SELECT id, client_uuid, payload, organization_id FROM pending_sales
+         WHERE status IN ('pending', 'failed')
+         ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut results = Vec::with_capacity(queued.len());
+    for (id, client_uuid, payload, organization_id) in queued {
+        let replayed = match parse_offline_sale(&payload) {
+            Ok(offline) => {
+                let mut request = offline.sale;
+                request.organization_id = Some(organization_id);
+                create_sale_internal(pool, request, offline.cashier_id, offline.shift_id).await
+            }
+            Err(e) => Err(e),
+        };
+        let result = match replayed {
+            Ok(sale) => {
+                sqlx::query(
+                    "UPDATE pending_sales SET status = 'processed', sale_id = ?1, attempts = attempts + 1,
+                            last_error = NULL, processed_at = CURRENT_TIMESTAMP
+                     WHERE id = ?2",
+                )
+                .bind(sale.id)
+                .bind(id)
+                .execute(pool)
+                .await?;
+                PendingSaleResult {
+                    pending_sale_id: id,
+                    client_uuid,
+                    sale_id: Some(sale.id),
+                    sale_number: Some(sale.sale_number),
+                    error: None,
+                }
+            }
+            Err(e) => {
+                let error = e.to_string();
+                sqlx::query(
+                    "UPDATE pending_sales SET status = 'failed', attempts = attempts + 1, last_error = ?1
+                     WHERE id = ?2",
+                )
+                .bind(&error)
+                .bind(id)
+                .execute(pool)
+                .await?;
+                PendingSaleResult {
+                    pending_sale_id: id,
+                    client_uuid,
+                    sale_id: None,
+                    sale_number: None,
+                    error: Some(error),
+                }
+            }
+        };
+        results.push(result);
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SaleItemRequest;
+    use crate::money::Money;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+
+    fn offline_sale(client_uuid: &str, cashier_id: i64, product_id: i64, quantity: i32) -> String {
+        let unit_price = Money::from_major(10.0);
+        let line_total = unit_price.times(quantity);
+        let sale = CreateSaleRequest {
+            items: vec![SaleItemRequest {
+                product_id,
+                quantity,
+                unit_price,
+                discount_amount: Money::ZERO,
+                line_total,
+                measured_quantity: None,
+                price_override: None,
+                override_reason: None,
+            }],
+            subtotal: line_total,
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: line_total,
+            payment_method: "cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        };
+        serde_json::to_string(&OfflineSale {
+            client_uuid: client_uuid.to_string(),
+            cashier_id,
+            shift_id: None,
+            sale,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replay_keeps_failed_sales_queued() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let plenty = insert_test_product(&pool, "OFF-1", 10.0, 10).await;
+        let scarce = insert_test_product(&pool, "OFF-2", 10.0, 1).await;
+        let good_uuid = "6f1c2a9e-3b4d-4e5f-8a7b-1c2d3e4f5a6b";
+        let bad_uuid = "0a1b2c3d-4e5f-4a7b-8c9d-0e1f2a3b4c5d";
+
+        let good = queue_offline_sale(&pool, &offline_sale(good_uuid, cashier, plenty, 2), 1)
+            .await
+            .unwrap();
+        queue_offline_sale(&pool, &offline_sale(bad_uuid, cashier, scarce, 5), 1)
+            .await
+            .unwrap();
+        // Sending the same sale twice queues it once
+        let resent = queue_offline_sale(&pool, &offline_sale(good_uuid, cashier, plenty, 2), 1)
+            .await
+            .unwrap();
+        assert_eq!(resent.id, good.id);
+        let queued: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pending_sales")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(queued, 2);
+
+        let results = process_pending_sales(&pool).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].error.is_none());
+        assert!(results[0].sale_number.is_some());
+        assert!(results[1].sale_id.is_none());
+        assert!(results[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("Insufficient stock"));
+        assert_eq!(current_stock(&pool, plenty).await, 8);
+        assert_eq!(current_stock(&pool, scarce).await, 1);
+
+        let failed = find_pending_sale(&pool, bad_uuid).await.unwrap();
+        assert_eq!((failed.status.as_str(), failed.attempts), ("failed", 1));
+        assert!(failed.last_error.is_some());
+
+        // Processed sales are not replayed; the failed one is retried and still kept
+        let results = process_pending_sales(&pool).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].client_uuid, bad_uuid);
+        assert_eq!(
+            find_pending_sale(&pool, bad_uuid).await.unwrap().attempts,
+            2
+        );
+        let sales: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sales")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(sales, 1);
+
+        // Re-queueing an already processed sale does not replay it
+        let processed = queue_offline_sale(&pool, &offline_sale(good_uuid, cashier, plenty, 2), 1)
+            .await
+            .unwrap();
+        assert_eq!(processed.status, "processed");
+    }
+
+    #[tokio::test]
+    async fn test_queue_rejects_malformed_payloads() {
+        let pool = test_pool().await;
+        let err = queue_offline_sale(&pool, "{\"client_uuid\": \"x\"}", 1)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        let product = insert_test_product(&pool, "OFF-1", 10.0, 10).await;
+        let err = queue_offline_sale(&pool, &offline_sale("not-a-uuid", 1, product, 1), 1)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+}
@@ -0,0 +1,219 @@
+use crate::error::{AppError, AppResult};
+use crate::validation;
+use bcrypt::{hash, verify, DEFAULT_COST};
+use sqlx::{Row, SqlitePool};
+
+/// Number of previous PINs (including the current one) that cannot be reused
+pub const PIN_HISTORY_SIZE: i64 = 3;
+
+// bcrypt at the default cost is very slow in unoptimised test builds
+const PIN_HASH_COST: u32 = if cfg!(test) { 4 } else { DEFAULT_COST };
+
+/// Set or replace a user's register PIN after re-checking their password
+pub async fn set_user_pin(
+    pool: &SqlitePool,
+    user_id: i64,
+    password: &str,
+    pin: &str,
+) -> AppResult<()> {
+    validation::validate_pin(pin)?;
+
+    let password_hash: String =
+        sqlx::query_scalar("SELECT password_hash FROM users WHERE id = ?1 AND is_active = 1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::database_error(&e.to_string()))?
+            .ok_or_else(|| AppError::not_found("user"))?;
+
+    let password_valid =
+        verify(password, &password_hash).map_err(|_| AppError::invalid_credentials())?;
+    if !password_valid {
+        return Err(AppError::invalid_credentials());
+    }
+
+    let recent_hashes: Vec<String> = sqlx::query_scalar(
+        "SELECT pin_hash FROM user_pin_history WHERE user_id = ?1 ORDER BY id DESC LIMIT ?2",
+    )
+    .bind(user_id)
+    .bind(PIN_HISTORY_SIZE)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    for recent in &recent_hashes {
+        if verify(pin, recent).unwrap_or(false) {
+            return Err(AppError::validation_error(&format!(
+                "PIN cannot match any of your last {} PINs",
+                PIN_HISTORY_SIZE
+            )));
+        }
+    }
+
+    let pin_hash = hash(pin, PIN_HASH_COST)
+        .map_err(|e| AppError::database_error(&format!("PIN hashing error: {}", e)))?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    sqlx::query(
+        "INSERT INTO user_pins (user_id, pin_hash, updated_at)
+         VALUES (?1, ?2, CURRENT_TIMESTAMP)
+         ON CONFLICT(user_id) DO UPDATE SET
+            pin_hash = excluded.pin_hash,
+            updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(user_id)
+    .bind(&pin_hash)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    sqlx::query("INSERT INTO user_pin_history (user_id, pin_hash) VALUES (?1, ?2)")
+        .bind(user_id)
+        .bind(&pin_hash)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    sqlx::query(
+        "DELETE FROM user_pin_history
+         WHERE user_id = ?1
+         AND id NOT IN (
+            SELECT id FROM user_pin_history WHERE user_id = ?1 ORDER BY id DESC LIMIT ?2
+         )",
+    )
+    .bind(user_id)
+    .bind(PIN_HISTORY_SIZE)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    Ok(())
+}
+
+/// Check a PIN for an active user identified by username or employee badge number.
+/// Returns the user id on success, `None` if the user, PIN, or match is missing.
+pub async fn verify_user_pin(
+    pool: &SqlitePool,
+    username_or_badge: &str,
+    pin: &str,
+) -> AppResult<Option<i64>> {
+    let row = sqlx::query(
+        "SELECT u.id, up.pin_hash
+         FROM users u
+         JOIN user_pins up ON up.user_id = u.id
+         LEFT JOIN employees e ON e.user_id = u.id
+         WHERE (u.username = ?1 OR e.employee_number = ?1) AND u.is_active = 1
+         LIMIT 1",
+    )
+    .bind(username_or_badge)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    let row = match row {
+        Some(r) => r,
+        None => return Ok(None),
+    };
+
+    let user_id: i64 = row
+        .try_get("id")
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+    let pin_hash: String = row
+        .try_get("pin_hash")
+        .map_err(|e| AppError::database_error(&e.to_string()))?;
+
+    if verify(pin, &pin_hash).unwrap_or(false) {
+        Ok(Some(user_id))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_pool;
+
+    async fn insert_user(pool: &SqlitePool, username: &str, password: &str) -> i64 {
+        let password_hash = hash(password, 4).unwrap();
+        sqlx::query(
+            "INSERT INTO users (username, email, password_hash, first_name, last_name, role)
+             VALUES (?1, ?1 || '@example.com', ?2, 'Test', 'User', 'Cashier')",
+        )
+        .bind(username)
+        .bind(password_hash)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+    }
+
+    #[tokio::test]
+    async fn test_pin_login_by_username_and_badge() {
+        let pool = test_pool().await;
+        let user_id = insert_user(&pool, "cashier1", "Password1").await;
+        sqlx::query("INSERT INTO employees (user_id, employee_number) VALUES (?1, 'EMP-042')")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(set_user_pin(&pool, user_id, "WrongPass1", "4821")
+            .await
+            .is_err());
+        assert_eq!(
+            verify_user_pin(&pool, "cashier1", "4821").await.unwrap(),
+            None
+        );
+
+        set_user_pin(&pool, user_id, "Password1", "4821")
+            .await
+            .unwrap();
+        assert_eq!(
+            verify_user_pin(&pool, "cashier1", "4821").await.unwrap(),
+            Some(user_id)
+        );
+        assert_eq!(
+            verify_user_pin(&pool, "EMP-042", "4821").await.unwrap(),
+            Some(user_id)
+        );
+        assert_eq!(
+            verify_user_pin(&pool, "cashier1", "0000").await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recent_pins_cannot_be_reused() {
+        let pool = test_pool().await;
+        let user_id = insert_user(&pool, "cashier1", "Password1").await;
+
+        for pin in ["1111", "2222", "3333"] {
+            set_user_pin(&pool, user_id, "Password1", pin)
+                .await
+                .unwrap();
+        }
+        assert!(set_user_pin(&pool, user_id, "Password1", "1111")
+            .await
+            .is_err());
+        assert!(set_user_pin(&pool, user_id, "Password1", "3333")
+            .await
+            .is_err());
+
+        // "1111" drops out of the history once a fourth PIN is set
+        set_user_pin(&pool, user_id, "Password1", "4444")
+            .await
+            .unwrap();
+        set_user_pin(&pool, user_id, "Password1", "1111")
+            .await
+            .unwrap();
+    }
+}
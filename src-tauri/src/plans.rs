@@ -0,0 +1,427 @@
+//! Subscription plan limits. Each plan caps the products, users and locations an
+//! organization can have and switches features on or off. The limits below are the
+//! defaults; a row in `plan_limits` overrides any of them for a plan. The default
+//! organization is the standalone install's own business rather than a subscriber, so
+//! nothing caps it.
+
+use crate::error::{AppError, AppResult};
+use crate::tenancy::{self, DEFAULT_ORGANIZATION_ID};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{FromRow, SqlitePool};
+
+/// What a plan allows. `None` means unlimited.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanLimits {
+    pub max_products: Option<i64>,
+    pub max_users: Option<i64>,
+    pub max_locations: Option<i64>,
+    /// Stock transfers between locations
+    pub multi_location: bool,
+    /// Loyalty, campaigns and other customer relationship features
+    pub crm: bool,
+}
+
+/// A countable resource a plan caps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanResource {
+    Products,
+    Users,
+    Locations,
+}
+
+impl PlanResource {
+    fn key(self) -> &'static str {
+        match self {
+            PlanResource::Products => "products",
+            PlanResource::Users => "users",
+            PlanResource::Locations => "locations",
+        }
+    }
+
+    fn max(self, limits: &PlanLimits) -> Option<i64> {
+        match self {
+            PlanResource::Products => limits.max_products,
+            PlanResource::Users => limits.max_users,
+            PlanResource::Locations => limits.max_locations,
+        }
+    }
+
+    /// Rows of the resource the organization has now
+    async fn count(self, conn: &mut SqliteConnection, organization_id: i64) -> AppResult<i64> {
+        let sql = match self {
+            PlanResource::Products => {
+                "SELECT COUNT(*) FROM products WHERE organization_id = ?1 AND is_active = 1"
+            }
+            PlanResource::Users => {
+                "SELECT COUNT(*) FROM organization_users WHERE organization_id = ?1 AND is_active = 1"
+            }
+            PlanResource::Locations => {
+                "SELECT COUNT(*) FROM locations WHERE organization_id = ?1 AND is_active = 1"
+            }
+        };
+        Ok(sqlx::query_scalar(sql)
+            .bind(organization_id)
+            .fetch_one(&mut *conn)
+            .await?)
+    }
+}
+
+/// Built-in limits of a plan; unknown plans get the Free limits
+pub fn default_limits(plan: &str) -> PlanLimits {
+    match plan {
+        "Starter" => PlanLimits {
+            max_products: Some(1_000),
+            max_users: Some(5),
+            max_locations: Some(2),
+            multi_location: true,
+            crm: false,
+        },
+        "Professional" => PlanLimits {
+            max_products: Some(10_000),
+            max_users: Some(25),
+            max_locations: Some(10),
+            multi_location: true,
+            crm: true,
+        },
+        "Enterprise" => PlanLimits {
+            max_products: None,
+            max_users: None,
+            max_locations: None,
+            multi_location: true,
+            crm: true,
+        },
+        _ => PlanLimits {
+            max_products: Some(100),
+            max_users: Some(2),
+            max_locations: Some(1),
+            multi_location: false,
+            crm: false,
+        },
+    }
+}
+
+/// An organization's plan and subscription state
+struct Subscription {
+    plan: String,
+    trial_expired: bool,
+}
+
+async fn subscription(conn: &mut SqliteConnection, organization_id: i64) -> AppResult<Subscription> {
+    let row: Option<(Option<String>, bool)> = sqlx::query_as(
+        "SELECT subscription_plan,
+                subscription_status = 'Trial' AND trial_ends_at IS NOT NULL
+                    AND DATE(trial_ends_at) < DATE('now')
+         FROM organizations WHERE id = ?1",
+    )
+    .bind(organization_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let (plan, trial_expired) = row.ok_or_else(|| AppError::not_found("Organization"))?;
+    Ok(Subscription {
+        plan: plan.unwrap_or_else(|| "Free".to_string()),
+        trial_expired,
+    })
+}
+
+/// A `plan_limits` row; NULL columns keep the built-in limit
+#[derive(FromRow)]
+struct PlanLimitOverride {
+    max_products: Option<i64>,
+    max_users: Option<i64>,
+    max_locations: Option<i64>,
+    multi_location: Option<bool>,
+    crm: Option<bool>,
+}
+
+/// Limits of a plan, with any overrides from `plan_limits` applied
+pub async fn limits_for_plan(conn: &mut SqliteConnection, plan: &str) -> AppResult<PlanLimits> {
+    let defaults = default_limits(plan);
+    let row = sqlx::query_as::<_, PlanLimitOverride>(
+        "SELECT max_products, max_users, max_locations, multi_location, crm
+         FROM plan_limits WHERE plan = ?1",
+    )
+    .bind(plan)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(row) = row else {
+        return Ok(defaults);
+    };
+    // A negative override lifts the cap
+    let cap = |value: Option<i64>, default: Option<i64>| match value {
+        Some(value) if value < 0 => None,
+        Some(value) => Some(value),
+        None => default,
+    };
+    Ok(PlanLimits {
+        max_products: cap(row.max_products, defaults.max_products),
+        max_users: cap(row.max_users, defaults.max_users),
+        max_locations: cap(row.max_locations, defaults.max_locations),
+        multi_location: row.multi_location.unwrap_or(defaults.multi_location),
+        crm: row.crm.unwrap_or(defaults.crm),
+    })
+}
+
+/// Limits that apply to an organization: those of its plan, or none for the default one
+async fn organization_limits(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    plan: &str,
+) -> AppResult<PlanLimits> {
+    if organization_id == DEFAULT_ORGANIZATION_ID {
+        return Ok(default_limits("Enterprise"));
+    }
+    limits_for_plan(conn, plan).await
+}
+
+/// Fail with TRIAL_EXPIRED once the organization's trial has ended. Called by commands
+/// that change data; billing and settings commands skip it so the organization can upgrade.
+pub async fn require_writable(conn: &mut SqliteConnection, organization_id: i64) -> AppResult<()> {
+    if subscription(conn, organization_id).await?.trial_expired {
+        return Err(AppError::TrialExpired);
+    }
+    Ok(())
+}
+
+/// The session's organization, once `require_writable` lets it change data
+pub async fn writable_organization(pool: &SqlitePool, session_token: &str) -> AppResult<i64> {
+    let organization_id = tenancy::active_organization(session_token)?;
+    let mut conn = pool.acquire().await?;
+    require_writable(&mut conn, organization_id).await?;
+    Ok(organization_id)
+}
+
+/// The session's organization, once its plan includes the CRM: customer management,
+/// appointments and campaigns. Customer lookups stay open so any till can attach a
+/// customer to a sale.
+pub async fn crm_organization(pool: &SqlitePool, session_token: &str) -> AppResult<i64> {
+    let organization_id = tenancy::active_organization(session_token)?;
+    let mut conn = pool.acquire().await?;
+    require_feature(&mut conn, organization_id, "crm").await?;
+    Ok(organization_id)
+}
+
+/// `crm_organization` for commands that change CRM data
+pub async fn writable_crm_organization(pool: &SqlitePool, session_token: &str) -> AppResult<i64> {
+    let organization_id = tenancy::active_organization(session_token)?;
+    let mut conn = pool.acquire().await?;
+    require_writable(&mut conn, organization_id).await?;
+    require_feature(&mut conn, organization_id, "crm").await?;
+    Ok(organization_id)
+}
+
+/// Fail with PLAN_LIMIT_EXCEEDED if the organization cannot add another `resource`
+pub async fn check_plan_limit(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    resource: PlanResource,
+) -> AppResult<()> {
+    let subscription = subscription(conn, organization_id).await?;
+    if subscription.trial_expired {
+        return Err(AppError::TrialExpired);
+    }
+    let limits = organization_limits(conn, organization_id, &subscription.plan).await?;
+    if let Some(max) = resource.max(&limits) {
+        if resource.count(conn, organization_id).await? >= max {
+            return Err(AppError::PlanLimitExceeded {
+                plan: subscription.plan,
+                limit: resource.key().to_string(),
+                max,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Fail with PLAN_LIMIT_EXCEEDED unless the organization's plan includes `feature`,
+/// one of `multi_location` or `crm`
+pub async fn require_feature(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    feature: &str,
+) -> AppResult<()> {
+    let subscription = subscription(conn, organization_id).await?;
+    let limits = organization_limits(conn, organization_id, &subscription.plan).await?;
+    let enabled = match feature {
+        "multi_location" => limits.multi_location,
+        "crm" => limits.crm,
+        _ => false,
+    };
+    if enabled {
+        Ok(())
+    } else {
+        Err(AppError::PlanLimitExceeded {
+            plan: subscription.plan,
+            limit: feature.to_string(),
+            max: 0,
+        })
+    }
+}
+
+/// Current use of one capped resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    pub used: i64,
+    pub limit: Option<i64>,
+}
+
+/// An organization's use of its plan, for the settings screen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanUsage {
+    pub plan: String,
+    pub trial_expired: bool,
+    pub products: ResourceUsage,
+    pub users: ResourceUsage,
+    pub locations: ResourceUsage,
+    pub multi_location: bool,
+    pub crm: bool,
+}
+
+pub async fn plan_usage(conn: &mut SqliteConnection, organization_id: i64) -> AppResult<PlanUsage> {
+    let subscription = subscription(conn, organization_id).await?;
+    let limits = organization_limits(conn, organization_id, &subscription.plan).await?;
+    let products = ResourceUsage {
+        used: PlanResource::Products.count(conn, organization_id).await?,
+        limit: limits.max_products,
+    };
+    let users = ResourceUsage {
+        used: PlanResource::Users.count(conn, organization_id).await?,
+        limit: limits.max_users,
+    };
+    let locations = ResourceUsage {
+        used: PlanResource::Locations.count(conn, organization_id).await?,
+        limit: limits.max_locations,
+    };
+    Ok(PlanUsage {
+        plan: subscription.plan,
+        trial_expired: subscription.trial_expired,
+        products,
+        users,
+        locations,
+        multi_location: limits.multi_location,
+        crm: limits.crm,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::SESSION_MANAGER;
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    async fn subscriber(pool: &SqlitePool, plan: &str, status: &str, trial_ends_at: Option<&str>) -> i64 {
+        sqlx::query_scalar(
+            "INSERT INTO organizations (name, slug, subscription_plan, subscription_status, trial_ends_at)
+             VALUES ('Subscriber', 'subscriber', ?1, ?2, ?3) RETURNING id",
+        )
+        .bind(plan)
+        .bind(status)
+        .bind(trial_ends_at)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn exceeded(pool: &SqlitePool, organization_id: i64, resource: PlanResource) -> Option<String> {
+        let mut conn = pool.acquire().await.unwrap();
+        match check_plan_limit(&mut conn, organization_id, resource).await {
+            Ok(()) => None,
+            Err(AppError::PlanLimitExceeded { limit, .. }) => Some(limit),
+            Err(err) => panic!("unexpected error {}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_each_limit_stops_at_the_plan_cap() {
+        let pool = test_pool().await;
+        let org = subscriber(&pool, "Free", "Active", None).await;
+        sqlx::query("INSERT INTO plan_limits (plan, max_products) VALUES ('Free', 2)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        for sku in ["A", "B"] {
+            assert_eq!(exceeded(&pool, org, PlanResource::Products).await, None);
+            let product = insert_test_product(&pool, sku, 1.0, 0).await;
+            sqlx::query("UPDATE products SET organization_id = ?1 WHERE id = ?2")
+                .bind(org)
+                .bind(product)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        assert_eq!(exceeded(&pool, org, PlanResource::Products).await.as_deref(), Some("products"));
+
+        for name in ["first", "second"] {
+            assert_eq!(exceeded(&pool, org, PlanResource::Users).await, None);
+            let user = insert_test_user(&pool, name).await;
+            sqlx::query("INSERT INTO organization_users (organization_id, user_id) VALUES (?1, ?2)")
+                .bind(org)
+                .bind(user)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        assert_eq!(exceeded(&pool, org, PlanResource::Users).await.as_deref(), Some("users"));
+
+        assert_eq!(exceeded(&pool, org, PlanResource::Locations).await, None);
+        sqlx::query("INSERT INTO locations (name, organization_id) VALUES ('Branch', ?1)")
+            .bind(org)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(exceeded(&pool, org, PlanResource::Locations).await.as_deref(), Some("locations"));
+
+        // The default organization is not on a plan
+        assert_eq!(exceeded(&pool, DEFAULT_ORGANIZATION_ID, PlanResource::Locations).await, None);
+        let mut conn = pool.acquire().await.unwrap();
+        let usage = plan_usage(&mut conn, org).await.unwrap();
+        assert_eq!((usage.products.used, usage.products.limit), (2, Some(2)));
+        assert!(!usage.multi_location);
+    }
+
+    #[tokio::test]
+    async fn test_expired_trial_blocks_changes() {
+        let pool = test_pool().await;
+        let expired = subscriber(&pool, "Professional", "Trial", Some("2000-01-01")).await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let err = require_writable(&mut conn, expired).await.unwrap_err();
+        assert_eq!(err.code(), "TRIAL_EXPIRED");
+        let err = check_plan_limit(&mut conn, expired, PlanResource::Products).await.unwrap_err();
+        assert_eq!(err.code(), "TRIAL_EXPIRED");
+        assert!(plan_usage(&mut conn, expired).await.unwrap().trial_expired);
+
+        sqlx::query("UPDATE organizations SET subscription_status = 'Active' WHERE id = ?1")
+            .bind(expired)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        require_writable(&mut conn, expired).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_session_helpers_check_the_session_organization() {
+        let pool = test_pool().await;
+        let expired = subscriber(&pool, "Professional", "Trial", Some("2000-01-01")).await;
+        let token = SESSION_MANAGER.create_session(1, "owner".to_string(), "Admin".to_string());
+        assert_eq!(writable_organization(&pool, &token).await.unwrap(), DEFAULT_ORGANIZATION_ID);
+
+        SESSION_MANAGER.set_organization(&token, expired).unwrap();
+        let err = writable_organization(&pool, &token).await.unwrap_err();
+        assert_eq!(err.code(), "TRIAL_EXPIRED");
+        // Reads stay open to an expired trial whose plan includes the CRM
+        assert_eq!(crm_organization(&pool, &token).await.unwrap(), expired);
+        let err = writable_crm_organization(&pool, &token).await.unwrap_err();
+        assert_eq!(err.code(), "TRIAL_EXPIRED");
+
+        sqlx::query("UPDATE organizations SET subscription_plan = 'Starter' WHERE id = ?1")
+            .bind(expired)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let err = crm_organization(&pool, &token).await.unwrap_err();
+        assert_eq!(err.code(), "PLAN_LIMIT_EXCEEDED");
+        assert!(writable_organization(&pool, "not-a-session").await.is_err());
+    }
+}
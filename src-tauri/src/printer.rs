@@ -0,0 +1,70 @@
+use crate::error::{AppError, AppResult};
+use crate::margins::DEFAULT_LOCATION_ID;
+use sqlx::SqlitePool;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// ESC p 0: pulse the drawer on connector pin 2 for 50 ms on, 500 ms off
+pub const DRAWER_KICK: [u8; 5] = [0x1B, 0x70, 0x00, 0x19, 0xFA];
+
+/// ESC @: reset the printer to its default font and code page
+const INITIALIZE: [u8; 2] = [0x1B, 0x40];
+
+/// ESC d 4 then GS V B 0: feed the text past the cutter and partially cut
+const FEED_AND_CUT: [u8; 7] = [0x1B, 0x64, 0x04, 0x1D, 0x56, 0x42, 0x00];
+
+/// Network printers are addressed as `tcp://host:port`, anything else is a device path
+const NETWORK_PREFIX: &str = "tcp://";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Device path or address of the store's receipt printer
+pub async fn receipt_printer(pool: &SqlitePool) -> AppResult<String> {
+    let printer: Option<String> =
+        sqlx::query_scalar("SELECT receipt_printer FROM locations WHERE id = ?1")
+            .bind(DEFAULT_LOCATION_ID)
+            .fetch_optional(pool)
+            .await?
+            .flatten();
+    printer
+        .map(|printer| printer.trim().to_string())
+        .filter(|printer| !printer.is_empty())
+        .ok_or_else(|| AppError::validation("receipt_printer", "No receipt printer is configured"))
+}
+
+/// ESC/POS job printing already laid-out receipt text, then cutting the paper.
+/// Characters outside ASCII print as `?` since the default code page can't be relied on.
+pub fn receipt_job(text: &str) -> Vec<u8> {
+    let mut bytes = INITIALIZE.to_vec();
+    bytes.extend(text.chars().map(|c| if c.is_ascii() { c as u8 } else { b'?' }));
+    bytes.push(b'\n');
+    bytes.extend_from_slice(&FEED_AND_CUT);
+    bytes
+}
+
+fn printer_error(printer: &str, e: impl std::fmt::Display) -> AppError {
+    AppError::Internal {
+        message: format!("Could not reach receipt printer {}: {}", printer, e),
+    }
+}
+
+/// Send raw ESC/POS bytes to the receipt printer
+pub async fn send_raw(printer: &str, bytes: &[u8]) -> AppResult<()> {
+    if let Some(address) = printer.strip_prefix(NETWORK_PREFIX) {
+        let mut stream = tokio::time::timeout(CONNECT_TIMEOUT, tokio::net::TcpStream::connect(address))
+            .await
+            .map_err(|_| printer_error(printer, "timed out"))?
+            .map_err(|e| printer_error(printer, e))?;
+        stream.write_all(bytes).await.map_err(|e| printer_error(printer, e))?;
+        stream.flush().await.map_err(|e| printer_error(printer, e))?;
+    } else {
+        let mut device = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(printer)
+            .await
+            .map_err(|e| printer_error(printer, e))?;
+        device.write_all(bytes).await.map_err(|e| printer_error(printer, e))?;
+        device.flush().await.map_err(|e| printer_error(printer, e))?;
+    }
+    Ok(())
+}
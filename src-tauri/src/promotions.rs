@@ -0,0 +1,467 @@
+//! Promotion engine: what the promotions entered at the till take off a cart, line by line.
+//! Percentage and fixed promotions discount the eligible lines; Buy X Get Y discounts the
+//! cheapest eligible units. Which kind goes first when both apply is a store setting.
+
+use crate::commands::promotions::validate_promotion_internal;
+use crate::error::{AppError, AppResult};
+use crate::models::{Promotion, SaleItemRequest};
+use crate::money::Money;
+use crate::settings::{self, STORE_SETTINGS};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::BTreeMap;
+
+pub const BUY_X_GET_Y: &str = "Buy X Get Y";
+
+pub const PROMOTION_PRECEDENCE_SETTING: &str = "promotion_precedence";
+
+/// How promotions of different kinds on one cart combine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromotionPrecedence {
+    /// Buy X Get Y first, then percentage and fixed promotions on what is left to pay
+    #[default]
+    BuyXGetYFirst,
+    /// Percentage and fixed promotions first, then Buy X Get Y on the reduced prices
+    PercentageFirst,
+    /// No stacking: only the promotion worth the most applies
+    Exclusive,
+}
+
+/// What the engine needs to know about a cart line
+#[derive(Debug, Clone)]
+pub struct CartLine {
+    pub product_id: i64,
+    pub category: Option<String>,
+    pub quantity: i32,
+    pub unit_price: Money,
+}
+
+/// Discount one promotion gives on one line, `line` being its index in the cart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineDiscount {
+    pub line: usize,
+    pub promotion_id: i64,
+    pub code: String,
+    pub discount_amount: Money,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromotionEvaluation {
+    pub discounts: Vec<LineDiscount>,
+    pub total_discount: Money,
+}
+
+impl PromotionEvaluation {
+    fn new(discounts: Vec<LineDiscount>) -> Self {
+        let total_discount = discounts.iter().map(|d| d.discount_amount).sum();
+        PromotionEvaluation {
+            discounts,
+            total_discount,
+        }
+    }
+
+    /// Everything promotions take off line `line`
+    pub fn line_discount(&self, line: usize) -> Money {
+        self.discounts
+            .iter()
+            .filter(|d| d.line == line)
+            .map(|d| d.discount_amount)
+            .sum()
+    }
+}
+
+/// Non-empty entries of a comma-separated list
+fn listed(list: &Option<String>) -> Vec<&str> {
+    list.as_deref()
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+/// A promotion limited to products (by id) or categories (by name) covers only those;
+/// otherwise it covers the whole cart
+fn covers_line(promotion: &Promotion, line: &CartLine) -> bool {
+    let products = listed(&promotion.applicable_products);
+    let categories = listed(&promotion.applicable_categories);
+    if products.is_empty() && categories.is_empty() {
+        return true;
+    }
+    products.iter().any(|id| id.parse() == Ok(line.product_id))
+        || line.category.as_deref().is_some_and(|category| {
+            categories
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(category.trim()))
+        })
+}
+
+/// The cheapest `get_quantity` of every `buy_quantity + get_quantity` eligible units,
+/// priced at what is still left to pay on their lines
+fn buy_x_get_y(
+    promotion: &Promotion,
+    lines: &[CartLine],
+    remaining: &[Money],
+) -> BTreeMap<usize, Money> {
+    let mut discounts = BTreeMap::new();
+    let (Some(buy), Some(get)) = (promotion.buy_quantity, promotion.get_quantity) else {
+        return discounts;
+    };
+    if buy <= 0 || get <= 0 {
+        return discounts;
+    }
+    let mut units: Vec<(Money, usize)> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if line.quantity > 0 && covers_line(promotion, line) {
+            let unit_price = Money::from_minor(remaining[index].minor() / line.quantity as i64);
+            units.extend(std::iter::repeat_n(
+                (unit_price, index),
+                line.quantity as usize,
+            ));
+        }
+    }
+    let discounted = units.len() / (buy + get) as usize * get as usize;
+    units.sort();
+    let percent = promotion.get_discount_percent.unwrap_or(100.0);
+    for (unit_price, index) in units.into_iter().take(discounted) {
+        *discounts.entry(index).or_insert(Money::ZERO) += unit_price.percent(percent);
+    }
+    discounts
+}
+
+/// Percentage or fixed amount off the eligible lines, capped by the promotion's maximum
+/// discount and taken from the lines in cart order
+fn amount_off(
+    promotion: &Promotion,
+    lines: &[CartLine],
+    remaining: &[Money],
+) -> BTreeMap<usize, Money> {
+    let eligible: Vec<usize> = (0..lines.len())
+        .filter(|&index| covers_line(promotion, &lines[index]))
+        .collect();
+    let mut budget = match promotion.discount_type.as_str() {
+        "Percentage" => eligible
+            .iter()
+            .map(|&index| remaining[index].percent(promotion.discount_value))
+            .sum(),
+        _ => Money::from_major(promotion.discount_value),
+    };
+    if let Some(max) = promotion.max_discount_amount {
+        budget = budget.min(Money::from_major(max));
+    }
+
+    let mut discounts = BTreeMap::new();
+    for index in eligible {
+        let discount = match promotion.discount_type.as_str() {
+            "Percentage" => remaining[index].percent(promotion.discount_value),
+            _ => remaining[index],
+        }
+        .min(budget);
+        if discount > Money::ZERO {
+            discounts.insert(index, discount);
+            budget -= discount;
+        }
+    }
+    discounts
+}
+
+fn apply(promotion: &Promotion, lines: &[CartLine], remaining: &mut [Money]) -> Vec<LineDiscount> {
+    let discounts = if promotion.discount_type == BUY_X_GET_Y {
+        buy_x_get_y(promotion, lines, remaining)
+    } else {
+        amount_off(promotion, lines, remaining)
+    };
+    discounts
+        .into_iter()
+        .filter(|(_, amount)| *amount > Money::ZERO)
+        .map(|(line, discount_amount)| {
+            remaining[line] -= discount_amount;
+            LineDiscount {
+                line,
+                promotion_id: promotion.id,
+                code: promotion.code.clone(),
+                discount_amount,
+            }
+        })
+        .collect()
+}
+
+/// Discounts `promotions` give on `lines`, combined as `precedence` says. Promotions of
+/// the same kind apply in the order given, each on what the ones before left to pay.
+pub fn evaluate(
+    promotions: &[Promotion],
+    lines: &[CartLine],
+    precedence: PromotionPrecedence,
+) -> PromotionEvaluation {
+    let line_totals: Vec<Money> = lines
+        .iter()
+        .map(|line| line.unit_price.times(line.quantity))
+        .collect();
+    if precedence == PromotionPrecedence::Exclusive {
+        return promotions
+            .iter()
+            .map(|promotion| {
+                PromotionEvaluation::new(apply(promotion, lines, &mut line_totals.clone()))
+            })
+            .max_by_key(|evaluation| evaluation.total_discount)
+            .unwrap_or_default();
+    }
+
+    let bxgy_first = precedence == PromotionPrecedence::BuyXGetYFirst;
+    let mut ordered: Vec<&Promotion> = promotions.iter().collect();
+    ordered.sort_by_key(|promotion| (promotion.discount_type == BUY_X_GET_Y) != bxgy_first);
+    let mut remaining = line_totals;
+    let discounts = ordered
+        .into_iter()
+        .flat_map(|promotion| apply(promotion, lines, &mut remaining))
+        .collect();
+    PromotionEvaluation::new(discounts)
+}
+
+/// Evaluate the promotions entered for a sale. Each code must be valid for the cart's
+/// value before discounts; lines are looked up for their product category.
+pub async fn evaluate_cart(
+    pool: &SqlitePool,
+    codes: &[String],
+    items: &[SaleItemRequest],
+) -> AppResult<PromotionEvaluation> {
+    if codes.is_empty() {
+        return Ok(PromotionEvaluation::default());
+    }
+    let subtotal: Money = items
+        .iter()
+        .map(|item| item.extend(item.charged_price()))
+        .sum();
+    let mut promotions = Vec::with_capacity(codes.len());
+    for code in codes {
+        let promotion = validate_promotion_internal(pool, code, subtotal.to_major(), None)
+            .await
+            .map_err(|message| {
+                AppError::validation("promotion_codes", &format!("{}: {}", code, message))
+            })?;
+        promotions.push(promotion);
+    }
+
+    let mut lines = Vec::with_capacity(items.len());
+    for item in items {
+        let category: Option<String> =
+            sqlx::query_scalar("SELECT category FROM products WHERE id = ?1")
+                .bind(item.product_id)
+                .fetch_optional(pool)
+                .await?
+                .flatten();
+        lines.push(CartLine {
+            product_id: item.product_id,
+            category,
+            quantity: item.whole_quantity(),
+            unit_price: item.charged_price(),
+        });
+    }
+
+    let precedence = settings::get_setting(pool, PROMOTION_PRECEDENCE_SETTING, STORE_SETTINGS)
+        .await?
+        .unwrap_or_default();
+    Ok(evaluate(&promotions, &lines, precedence))
+}
+
+/// Keep which promotion gave which discount on a sale line
+pub async fn record_line_discounts(
+    conn: &mut SqliteConnection,
+    sale_item_id: i64,
+    evaluation: &PromotionEvaluation,
+    line: usize,
+) -> AppResult<()> {
+    for discount in evaluation.discounts.iter().filter(|d| d.line == line) {
+        sqlx::query(
+            "INSERT INTO sale_item_promotions (sale_item_id, promotion_id, code, discount_amount)
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(sale_item_id)
+        .bind(discount.promotion_id)
+        .bind(&discount.code)
+        .bind(discount.discount_amount)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Count each promotion that gave a discount once against its usage limit. Fails when
+/// another sale used up the last use in the meantime.
+pub async fn record_usage(
+    conn: &mut SqliteConnection,
+    sale_id: i64,
+    evaluation: &PromotionEvaluation,
+) -> AppResult<()> {
+    let mut per_promotion: BTreeMap<i64, (&str, Money)> = BTreeMap::new();
+    for discount in &evaluation.discounts {
+        per_promotion
+            .entry(discount.promotion_id)
+            .or_insert((discount.code.as_str(), Money::ZERO))
+            .1 += discount.discount_amount;
+    }
+    for (promotion_id, (code, discount_amount)) in per_promotion {
+        let counted = sqlx::query(
+            "UPDATE promotions SET usage_count = usage_count + 1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1 AND (usage_limit IS NULL OR usage_count < usage_limit)",
+        )
+        .bind(promotion_id)
+        .execute(&mut *conn)
+        .await?;
+        if counted.rows_affected() == 0 {
+            return Err(AppError::Conflict {
+                message: format!("Promotion {} has reached its usage limit", code),
+            });
+        }
+        sqlx::query("INSERT INTO promotion_usage (promotion_id, sale_id, discount_amount) VALUES (?1, ?2, ?3)")
+            .bind(promotion_id)
+            .bind(sale_id)
+            .bind(discount_amount)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn promotion(id: i64, discount_type: &str, discount_value: f64) -> Promotion {
+        Promotion {
+            id,
+            code: format!("PROMO{}", id),
+            name: format!("Promotion {}", id),
+            description: None,
+            discount_type: discount_type.to_string(),
+            discount_value,
+            min_purchase_amount: 0.0,
+            max_discount_amount: None,
+            start_date: "2000-01-01".to_string(),
+            end_date: None,
+            usage_limit: None,
+            usage_count: 0,
+            customer_type: None,
+            applicable_products: None,
+            applicable_categories: None,
+            is_active: true,
+            created_by: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            footer_rule_id: None,
+            source_sale_id: None,
+            buy_quantity: None,
+            get_quantity: None,
+            get_discount_percent: None,
+            campaign_id: None,
+        }
+    }
+
+    fn buy_2_get_1(id: i64) -> Promotion {
+        Promotion {
+            buy_quantity: Some(2),
+            get_quantity: Some(1),
+            ..promotion(id, BUY_X_GET_Y, 0.0)
+        }
+    }
+
+    fn line(product_id: i64, quantity: i32, unit_price: f64) -> CartLine {
+        CartLine {
+            product_id,
+            category: None,
+            quantity,
+            unit_price: Money::from_major(unit_price),
+        }
+    }
+
+    #[test]
+    fn test_buy_2_get_1_discounts_one_unit_in_every_three() {
+        for (quantity, free) in [(3, 1), (4, 1), (7, 2)] {
+            let evaluation = evaluate(
+                &[buy_2_get_1(1)],
+                &[line(1, quantity, 10.0)],
+                PromotionPrecedence::default(),
+            );
+            assert_eq!(
+                evaluation.total_discount,
+                Money::from_major(10.0).times(free),
+                "{} items",
+                quantity
+            );
+            assert_eq!(evaluation.line_discount(0), evaluation.total_discount);
+        }
+    }
+
+    #[test]
+    fn test_buy_x_get_y_discounts_the_cheapest_eligible_units() {
+        let lines = [line(1, 2, 10.0), line(2, 3, 4.0), line(3, 2, 1.0)];
+        let promotion = Promotion {
+            applicable_products: Some("1, 2".to_string()),
+            ..buy_2_get_1(1)
+        };
+        // 5 eligible units give one free unit, the cheapest eligible one; product 3 is not eligible
+        let evaluation = evaluate(&[promotion], &lines, PromotionPrecedence::default());
+        assert_eq!(
+            evaluation.discounts,
+            vec![LineDiscount {
+                line: 1,
+                promotion_id: 1,
+                code: "PROMO1".to_string(),
+                discount_amount: Money::from_major(4.0),
+            }]
+        );
+
+        // Half off instead of free, by category
+        let promotion = Promotion {
+            applicable_categories: Some("drinks".to_string()),
+            get_discount_percent: Some(50.0),
+            ..buy_2_get_1(1)
+        };
+        let lines = [CartLine {
+            category: Some("Drinks".to_string()),
+            ..line(1, 7, 3.0)
+        }];
+        let evaluation = evaluate(&[promotion], &lines, PromotionPrecedence::default());
+        assert_eq!(evaluation.total_discount, Money::from_major(3.0));
+    }
+
+    #[test]
+    fn test_precedence_decides_how_promotions_stack() {
+        let lines = [line(1, 3, 10.0)];
+        let promotions = [promotion(1, "Fixed Amount", 5.0), buy_2_get_1(2)];
+        let total = |precedence| evaluate(&promotions, &lines, precedence).total_discount;
+
+        // One free unit, then 5 off the 20 left
+        assert_eq!(
+            total(PromotionPrecedence::BuyXGetYFirst),
+            Money::from_major(15.0)
+        );
+        // 5 off the 30, then one free unit at 25 / 3
+        assert_eq!(
+            total(PromotionPrecedence::PercentageFirst),
+            Money::from_major(13.33)
+        );
+        // Only the free unit, the larger of the two
+        assert_eq!(
+            total(PromotionPrecedence::Exclusive),
+            Money::from_major(10.0)
+        );
+    }
+
+    #[test]
+    fn test_percentage_discount_is_capped() {
+        let promotion = Promotion {
+            max_discount_amount: Some(4.0),
+            ..promotion(1, "Percentage", 10.0)
+        };
+        let evaluation = evaluate(
+            &[promotion],
+            &[line(1, 2, 15.0), line(2, 1, 20.0)],
+            PromotionPrecedence::default(),
+        );
+        assert_eq!(evaluation.line_discount(0), Money::from_major(3.0));
+        assert_eq!(evaluation.line_discount(1), Money::from_major(1.0));
+        assert_eq!(evaluation.total_discount, Money::from_major(4.0));
+    }
+}
@@ -0,0 +1,689 @@
+//! Fill receipt templates with a sale's details
+
+use crate::commands::z_reports::ZReport;
+use crate::currency::{self, Currency};
+use crate::error::{AppError, AppResult};
+use crate::margins::DEFAULT_LOCATION_ID;
+use crate::money::Money;
+use crate::organization_settings;
+use crate::receipt_footers;
+use serde::Serialize;
+use sqlx::{FromRow, Row, SqlitePool};
+
+/// Dots the print head covers at 203 dpi: 48 mm of a 58 mm roll, 72 mm of an 80 mm roll
+fn printable_dots(paper_width: i32) -> i32 {
+    match paper_width {
+        58 => 384,
+        80 => 576,
+        other => (other - 8).max(24) * 8,
+    }
+}
+
+/// Column layout of receipt lines for one paper width and font size, shared by the
+/// plain-text receipt and the ESC/POS job sent to the printer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReceiptLayout {
+    pub chars_per_line: usize,
+}
+
+impl ReceiptLayout {
+    /// Quantity right-aligned in 4 characters, then a space
+    const QUANTITY_WIDTH: usize = 5;
+    /// Room for amounts up to `$99,999.99`
+    const AMOUNT_WIDTH: usize = 10;
+    /// Lines a long product name wraps onto before it is cut short
+    const MAX_NAME_LINES: usize = 2;
+
+    /// `font_size` is the character width in dots: 12 for the standard font,
+    /// giving 32 characters on 58 mm paper and 48 on 80 mm
+    pub fn new(paper_width: i32, font_size: i32) -> Self {
+        let chars = printable_dots(paper_width) / font_size.max(8);
+        ReceiptLayout {
+            chars_per_line: chars.max(24) as usize,
+        }
+    }
+
+    fn name_width(&self) -> usize {
+        self.chars_per_line - Self::QUANTITY_WIDTH - Self::AMOUNT_WIDTH - 1
+    }
+
+    /// An item as `qty name amount`, the amount flush right and a long name wrapped
+    /// under itself
+    pub fn item_lines(&self, quantity: &str, name: &str, amount: &str) -> Vec<String> {
+        let name_width = self.name_width();
+        let mut names = wrap(name, name_width);
+        if names.len() > Self::MAX_NAME_LINES {
+            names.truncate(Self::MAX_NAME_LINES);
+        }
+        let quantity: String = quantity.chars().take(Self::QUANTITY_WIDTH - 1).collect();
+
+        let mut lines = Vec::with_capacity(names.len());
+        for (i, name) in names.into_iter().enumerate() {
+            if i == 0 {
+                lines.push(format!(
+                    "{:>qw$} {:<nw$} {:>aw$}",
+                    quantity,
+                    name,
+                    amount,
+                    qw = Self::QUANTITY_WIDTH - 1,
+                    nw = name_width,
+                    aw = Self::AMOUNT_WIDTH,
+                ));
+            } else {
+                lines.push(format!("{:qw$}{}", "", name, qw = Self::QUANTITY_WIDTH));
+            }
+        }
+        lines
+    }
+
+    /// A pick ticket item: quantity and SKU, then the name and any details indented under them
+    pub fn pick_lines(
+        &self,
+        quantity: &str,
+        sku: &str,
+        name: &str,
+        details: &[String],
+    ) -> Vec<String> {
+        let indent = " ".repeat(Self::QUANTITY_WIDTH);
+        let text_width = self.chars_per_line - Self::QUANTITY_WIDTH;
+        let quantity: String = quantity.chars().take(Self::QUANTITY_WIDTH - 1).collect();
+
+        let mut lines = vec![format!("{:>qw$} {}", quantity, sku, qw = Self::QUANTITY_WIDTH - 1)];
+        for text in std::iter::once(name).chain(details.iter().map(String::as_str)) {
+            let wrapped = wrap(text, text_width);
+            lines.extend(wrapped.into_iter().map(|line| format!("{}{}", indent, line)));
+        }
+        lines
+    }
+
+    /// A label on the left and an amount flush right, e.g. `Total` and `$12.00`
+    pub fn amount_line(&self, label: &str, amount: &str) -> String {
+        let amount_width = amount.chars().count();
+        let label_width = self.chars_per_line.saturating_sub(amount_width + 1);
+        let label: String = label.chars().take(label_width).collect();
+        format!("{:<lw$} {}", label, amount, lw = label_width)
+    }
+}
+
+/// Word-wrap `text` into lines of at most `width` characters, splitting words that are longer
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        let line_len = line.chars().count();
+        if line_len > 0 && line_len + 1 + word.len() <= width {
+            line.push(' ');
+            line.extend(word.iter());
+            continue;
+        }
+        if line_len > 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > width {
+            lines.push(word.drain(..width).collect());
+        }
+        line = word.into_iter().collect();
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Tax charged at one rate across all lines of a sale
+#[derive(Debug, Clone, PartialEq, Serialize, FromRow)]
+pub struct TaxRateTotal {
+    /// Percentage, e.g. `8.25`
+    pub rate: f64,
+    pub taxable_amount: Money,
+    pub tax_amount: Money,
+}
+
+/// The sale's line taxes summed per distinct rate, lowest rate first
+pub async fn sale_tax_breakdown(pool: &SqlitePool, sale_id: i64) -> AppResult<Vec<TaxRateTotal>> {
+    Ok(sqlx::query_as::<_, TaxRateTotal>(
+        "SELECT sit.rate, SUM(sit.taxable_amount) AS taxable_amount, SUM(sit.tax_amount) AS tax_amount
+         FROM sale_item_taxes sit
+         JOIN sale_items si ON si.id = sit.sale_item_id
+         WHERE si.sale_id = ?1
+         GROUP BY sit.rate
+         ORDER BY sit.rate",
+    )
+    .bind(sale_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// One `Tax 8.25% on $100.00` line per rate with its tax flush right, or a single zero tax line
+pub fn format_tax_breakdown(
+    totals: &[TaxRateTotal],
+    currency: &Currency,
+    layout: &ReceiptLayout,
+) -> String {
+    if totals.is_empty() {
+        return layout.amount_line("Tax", &currency.format(Money::ZERO));
+    }
+    totals
+        .iter()
+        .map(|total| {
+            let label = format!("Tax {}% on {}", total.rate, currency.format(total.taxable_amount));
+            layout.amount_line(&label, &currency.format(total.tax_amount))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replace each `{{name}}` in the template with its value, leaving unknown placeholders as they are
+pub fn fill_template(template: &str, values: &[(&str, String)]) -> String {
+    values.iter().fold(template.to_string(), |content, (name, value)| {
+        content.replace(&format!("{{{{{}}}}}", name), value)
+    })
+}
+
+/// Placeholders on a sale receipt; void receipts reprint the sale
+const SALE_PLACEHOLDERS: &[&str] = &[
+    "store_name", "store_address", "store_phone", "sale_number", "sale_date", "cashier_name",
+    "items", "subtotal", "tax_amount", "tax_breakdown", "total_amount", "footer",
+];
+const RETURN_PLACEHOLDERS: &[&str] = &[
+    "store_name", "store_address", "store_phone", "return_number", "return_date", "user_name",
+    "items", "total_amount", "footer",
+];
+const WAREHOUSE_PLACEHOLDERS: &[&str] = &[
+    "store_name", "sale_number", "sale_date", "cashier_name", "customer_name", "notes", "items",
+    "line_count", "unit_count",
+];
+
+/// Placeholders a template of the type can use, empty for an unknown type
+pub fn known_placeholders(template_type: &str) -> &'static [&'static str] {
+    match template_type {
+        "sale" | "void" => SALE_PLACEHOLDERS,
+        "return" => RETURN_PLACEHOLDERS,
+        "warehouse" => WAREHOUSE_PLACEHOLDERS,
+        _ => &[],
+    }
+}
+
+/// Every `{{name}}` in the content that templates of the type cannot fill, in order of
+/// first use
+pub fn unknown_placeholders(template_type: &str, content: &str) -> Vec<String> {
+    let known = known_placeholders(template_type);
+    let mut unknown: Vec<String> = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let name = &after[..end];
+        if !known.contains(&name) && !unknown.iter().any(|u| u == name) {
+            unknown.push(name.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+    unknown
+}
+
+/// A template filled with made-up but realistic values, laid out for its paper, so a
+/// design can be checked before any sale uses it
+pub fn render_sample(
+    template_type: &str,
+    content: &str,
+    paper_width: i32,
+    font_size: i32,
+    currency: &Currency,
+) -> String {
+    let layout = ReceiptLayout::new(paper_width, font_size);
+    let lines = [
+        ("2", "SKU-1001", "Cordless Drill Driver 18V", Money::from_minor(25_998)),
+        ("1", "SKU-2040", "Wood Screws 4x40 (200 pack)", Money::from_minor(899)),
+        ("0.75", "SKU-3300", "Copper Wire 2.5mm", Money::from_minor(310)),
+    ];
+    let subtotal: Money = lines.iter().map(|line| line.3).sum();
+    let tax = Money::from_minor(2_161);
+    let taxes = [TaxRateTotal {
+        rate: 8.0,
+        taxable_amount: subtotal,
+        tax_amount: tax,
+    }];
+    let items: Vec<String> = if template_type == "warehouse" {
+        lines
+            .iter()
+            .flat_map(|(quantity, sku, name, _)| layout.pick_lines(quantity, sku, name, &[]))
+            .collect()
+    } else {
+        lines
+            .iter()
+            .flat_map(|(quantity, _, name, amount)| layout.item_lines(quantity, name, &currency.format(*amount)))
+            .collect()
+    };
+
+    let values = [
+        ("store_name", "Sample Hardware".to_string()),
+        ("store_address", "12 Market Street".to_string()),
+        ("store_phone", "555-0100".to_string()),
+        ("sale_number", "SAL-000123".to_string()),
+        ("sale_date", "2024-06-01 14:32:00".to_string()),
+        ("return_number", "RET-000045".to_string()),
+        ("return_date", "2024-06-03 10:05:00".to_string()),
+        ("cashier_name", "Jane Doe".to_string()),
+        ("user_name", "Jane Doe".to_string()),
+        ("customer_name", "Acme Builders".to_string()),
+        ("notes", "Deliver to the side gate".to_string()),
+        ("items", items.join("\n")),
+        ("line_count", lines.len().to_string()),
+        ("unit_count", "4".to_string()),
+        ("subtotal", currency.format(subtotal)),
+        ("tax_amount", currency.format(tax)),
+        ("tax_breakdown", format_tax_breakdown(&taxes, currency, &layout)),
+        ("total_amount", currency.format(subtotal + tax)),
+        ("footer", "Thank you for your business!".to_string()),
+    ];
+    fill_template(&content.replace("\\n", "\n"), &values)
+}
+
+/// Content of a template and the layout of the paper it prints on, the given template or
+/// else the default thermal one of the type. Seeded templates store line breaks as the
+/// two characters `\n`, so those become real line breaks.
+async fn load_template(
+    pool: &SqlitePool,
+    template_type: &str,
+    template_id: Option<i64>,
+) -> AppResult<(String, ReceiptLayout)> {
+    let template: Option<(String, i32, i32)> = match template_id {
+        Some(id) => sqlx::query_as(
+            "SELECT template_content, paper_width, font_size FROM receipt_templates WHERE id = ?1",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?,
+        None => sqlx::query_as(
+            "SELECT template_content, paper_width, font_size FROM receipt_templates
+             WHERE template_type = ?1 AND printer_type = 'thermal' AND is_default = 1
+             ORDER BY id LIMIT 1",
+        )
+        .bind(template_type)
+        .fetch_optional(pool)
+        .await?,
+    };
+    let (content, paper_width, font_size) =
+        template.ok_or_else(|| AppError::not_found("Receipt template"))?;
+    Ok((content.replace("\\n", "\n"), ReceiptLayout::new(paper_width, font_size)))
+}
+
+/// Text of a sale's receipt, from the given template or the default thermal sale receipt
+pub async fn render_sale_receipt(
+    pool: &SqlitePool,
+    sale_id: i64,
+    template_id: Option<i64>,
+) -> AppResult<String> {
+    let (template, layout) = load_template(pool, "sale", template_id).await?;
+    let currency = currency::store_currency(pool).await?;
+
+    let store = sqlx::query("SELECT name, address, phone, receipt_footer FROM locations WHERE id = ?1")
+        .bind(DEFAULT_LOCATION_ID)
+        .fetch_one(pool)
+        .await?;
+    let sale = sqlx::query(
+        "SELECT s.sale_number, s.subtotal, s.tax_amount, s.total_amount, s.created_at,
+                s.organization_id, u.first_name || ' ' || u.last_name AS cashier_name
+         FROM sales s
+         LEFT JOIN users u ON u.id = s.cashier_id
+         WHERE s.id = ?1",
+    )
+    .bind(sale_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(AppError::sale_not_found)?;
+
+    let item_rows = sqlx::query(
+        "SELECT p.name, si.quantity, si.measured_quantity, si.line_total
+         FROM sale_items si
+         JOIN products p ON p.id = si.product_id
+         WHERE si.sale_id = ?1
+         ORDER BY si.id",
+    )
+    .bind(sale_id)
+    .fetch_all(pool)
+    .await?;
+    let mut items = Vec::new();
+    for row in item_rows {
+        let name: String = row.try_get("name")?;
+        let quantity: i32 = row.try_get("quantity")?;
+        let measured: Option<f64> = row.try_get("measured_quantity")?;
+        let line_total: Money = row.try_get("line_total")?;
+        let quantity = measured.map_or_else(|| quantity.to_string(), |m| m.to_string());
+        items.extend(layout.item_lines(&quantity, &name, &currency.format(line_total)));
+    }
+
+    let tax_breakdown = sale_tax_breakdown(pool, sale_id).await?;
+
+    // The organization's footer, or else the store's own, then whatever footer rules the
+    // sale qualifies for
+    let mut conn = pool.acquire().await?;
+    let organization_footer =
+        organization_settings::load(&mut conn, sale.try_get("organization_id")?)
+            .await?
+            .receipt_footer;
+    drop(conn);
+    let mut footer: Vec<String> = organization_footer
+        .filter(|text| !text.trim().is_empty())
+        .or(store.try_get::<Option<String>, _>("receipt_footer")?)
+        .into_iter()
+        .filter(|text| !text.is_empty())
+        .collect();
+    for message in receipt_footers::footer_messages(pool, sale_id).await? {
+        footer.extend(wrap(&message, layout.chars_per_line));
+    }
+
+    let values = [
+        ("store_name", store.try_get::<String, _>("name")?),
+        ("store_address", store.try_get::<Option<String>, _>("address")?.unwrap_or_default()),
+        ("store_phone", store.try_get::<Option<String>, _>("phone")?.unwrap_or_default()),
+        ("sale_number", sale.try_get("sale_number")?),
+        ("sale_date", sale.try_get("created_at")?),
+        ("cashier_name", sale.try_get::<Option<String>, _>("cashier_name")?.unwrap_or_default()),
+        ("items", items.join("\n")),
+        ("subtotal", currency.format(sale.try_get("subtotal")?)),
+        ("tax_amount", currency.format(sale.try_get("tax_amount")?)),
+        ("tax_breakdown", format_tax_breakdown(&tax_breakdown, &currency, &layout)),
+        ("total_amount", currency.format(sale.try_get("total_amount")?)),
+        ("footer", footer.join("\n")),
+    ];
+    Ok(fill_template(&template, &values))
+}
+
+/// Pull list for the warehouse from the default `warehouse` template: what to pick for a
+/// sale, with SKUs, quantities and dimensions but no prices
+pub async fn render_pick_ticket(pool: &SqlitePool, sale_id: i64) -> AppResult<String> {
+    let (template, layout) = load_template(pool, "warehouse", None).await?;
+
+    let store_name: String = sqlx::query_scalar("SELECT name FROM locations WHERE id = ?1")
+        .bind(DEFAULT_LOCATION_ID)
+        .fetch_one(pool)
+        .await?;
+    let sale = sqlx::query(
+        "SELECT s.sale_number, s.created_at, s.customer_name, s.notes,
+                u.first_name || ' ' || u.last_name AS cashier_name
+         FROM sales s
+         LEFT JOIN users u ON u.id = s.cashier_id
+         WHERE s.id = ?1",
+    )
+    .bind(sale_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(AppError::sale_not_found)?;
+
+    let item_rows = sqlx::query(
+        "SELECT p.sku, p.name, p.unit_of_measure, p.dimensions, si.quantity, si.measured_quantity
+         FROM sale_items si
+         JOIN products p ON p.id = si.product_id
+         WHERE si.sale_id = ?1
+         ORDER BY si.id",
+    )
+    .bind(sale_id)
+    .fetch_all(pool)
+    .await?;
+    let mut items = Vec::new();
+    let mut unit_count = 0;
+    for row in &item_rows {
+        let quantity: i32 = row.try_get("quantity")?;
+        let measured: Option<f64> = row.try_get("measured_quantity")?;
+        let unit_of_measure: Option<String> = row.try_get("unit_of_measure")?;
+        let dimensions: Option<String> = row.try_get("dimensions")?;
+        unit_count += quantity;
+
+        let quantity = match measured {
+            Some(measured) => format!("{} {}", measured, unit_of_measure.unwrap_or_default()),
+            None => quantity.to_string(),
+        };
+        let details: Vec<String> = dimensions
+            .filter(|d| !d.trim().is_empty())
+            .map(|d| format!("Dimensions: {}", d.trim()))
+            .into_iter()
+            .collect();
+        items.extend(layout.pick_lines(
+            quantity.trim(),
+            &row.try_get::<String, _>("sku")?,
+            &row.try_get::<String, _>("name")?,
+            &details,
+        ));
+    }
+
+    let values = [
+        ("store_name", store_name),
+        ("sale_number", sale.try_get("sale_number")?),
+        ("sale_date", sale.try_get("created_at")?),
+        ("cashier_name", sale.try_get::<Option<String>, _>("cashier_name")?.unwrap_or_default()),
+        ("customer_name", sale.try_get::<Option<String>, _>("customer_name")?.unwrap_or_default()),
+        ("notes", sale.try_get::<Option<String>, _>("notes")?.unwrap_or_default()),
+        ("items", items.join("\n")),
+        ("line_count", item_rows.len().to_string()),
+        ("unit_count", unit_count.to_string()),
+    ];
+    Ok(fill_template(&template, &values))
+}
+
+/// Z-report text laid out for the paper of the default sale receipt
+pub async fn render_z_report(pool: &SqlitePool, report: &ZReport) -> AppResult<String> {
+    let (_, layout) = load_template(pool, "sale", None).await?;
+    let currency = currency::store_currency(pool).await?;
+    let store_name: String = sqlx::query_scalar("SELECT name FROM locations WHERE id = ?1")
+        .bind(DEFAULT_LOCATION_ID)
+        .fetch_one(pool)
+        .await?;
+    let rule = "-".repeat(layout.chars_per_line);
+    let amount = |label: &str, value: Money| layout.amount_line(label, &currency.format(value));
+
+    let mut lines = vec![store_name, "Z REPORT".to_string()];
+    match (report.shift_id, &report.date) {
+        (Some(shift_id), _) => lines.push(format!("Shift #{}", shift_id)),
+        (None, Some(date)) => lines.push(format!("Day {}", date)),
+        (None, None) => {}
+    }
+    lines.push(format!("Printed {}", report.generated_at));
+    lines.push(rule.clone());
+    lines.push(amount("Gross sales", report.gross_sales));
+    lines.push(amount("Returns", -report.returns));
+    lines.push(amount("Net sales", report.net_sales));
+    lines.push(amount("Tax collected", report.tax_collected));
+    lines.push(amount("Discounts", report.discounts));
+    if report.cash_rounding != Money::ZERO {
+        lines.push(amount("Cash rounding", report.cash_rounding));
+    }
+    lines.push(layout.amount_line("Transactions", &report.transaction_count.to_string()));
+    lines.push(amount(&format!("Voids ({})", report.void_count), report.void_amount));
+    lines.push(rule.clone());
+    for method in &report.payment_methods {
+        let label = format!("{} ({})", method.payment_method, method.transaction_count);
+        lines.push(amount(&label, method.amount));
+    }
+    lines.push(rule);
+    lines.push(amount("Opening float", report.opening_amount));
+    lines.push(amount("Expected cash", report.expected_cash));
+    match (report.closing_amount, report.variance) {
+        (Some(counted), Some(variance)) => {
+            lines.push(amount("Counted cash", counted));
+            lines.push(amount("Variance", variance));
+        }
+        _ => lines.push(layout.amount_line("Counted cash", "Open")),
+    }
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::sales::create_sale_internal;
+    use crate::models::{CreateSaleRequest, SaleItemRequest};
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    #[test]
+    fn test_item_columns_line_up_on_narrow_and_wide_paper() {
+        let narrow = ReceiptLayout::new(58, 12);
+        assert_eq!(narrow.chars_per_line, 32);
+        assert_eq!(
+            narrow.item_lines("2", "Cordless Drill Driver 18V Kit With Two Batteries", "$1,299.00"),
+            vec![
+                "   2 Cordless Drill    $1,299.00".to_string(),
+                "     Driver 18V Kit".to_string(),
+            ]
+        );
+        assert_eq!(narrow.amount_line("Total", "$12.50"), "Total                     $12.50");
+
+        let wide = ReceiptLayout::new(80, 12);
+        assert_eq!(wide.chars_per_line, 48);
+        let lines = wide.item_lines("0.75", "Copper Wire", "$3.10");
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].chars().count(), 48);
+        assert!(lines[0].starts_with("0.75 Copper Wire "));
+        assert!(lines[0].ends_with(" $3.10"));
+        for line in wide.item_lines("12", "Extra-long-sku-name-without-any-spaces-at-all", "$9.99") {
+            assert!(line.chars().count() <= 48, "{:?} overflows", line);
+        }
+    }
+
+    fn line(product_id: i64, price: f64) -> SaleItemRequest {
+        SaleItemRequest {
+            product_id,
+            quantity: 1,
+            unit_price: Money::from_major(price),
+            discount_amount: Money::ZERO,
+            line_total: Money::from_major(price),
+            measured_quantity: None,
+            price_override: None,
+            override_reason: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_receipt_groups_tax_by_rate_and_prints_store_footer() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let drill = insert_test_product(&pool, "DRILL", 100.0, 10).await;
+        let saw = insert_test_product(&pool, "SAW", 50.0, 10).await;
+        let bread = insert_test_product(&pool, "BREAD", 10.0, 10).await;
+        sqlx::query(
+            "UPDATE products SET is_taxable = 1,
+                tax_rate = CASE sku WHEN 'BREAD' THEN 2.5 ELSE 8 END",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE locations SET receipt_footer = 'See you soon' WHERE id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let subtotal = Money::from_major(160.0);
+        let tax = Money::from_minor(1225);
+        let request = CreateSaleRequest {
+            items: vec![line(drill, 100.0), line(saw, 50.0), line(bread, 10.0)],
+            subtotal,
+            tax_amount: tax,
+            discount_amount: Money::ZERO,
+            total_amount: subtotal + tax,
+            payment_method: "cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        };
+        let sale = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+
+        assert_eq!(
+            sale_tax_breakdown(&pool, sale.id).await.unwrap(),
+            vec![
+                TaxRateTotal {
+                    rate: 2.5,
+                    taxable_amount: Money::from_major(10.0),
+                    tax_amount: Money::from_minor(25),
+                },
+                TaxRateTotal {
+                    rate: 8.0,
+                    taxable_amount: Money::from_major(150.0),
+                    tax_amount: Money::from_major(12.0),
+                },
+            ]
+        );
+
+        let receipt = render_sale_receipt(&pool, sale.id, None).await.unwrap();
+        let layout = ReceiptLayout::new(80, 12);
+        let breakdown = format!(
+            "{}\n{}",
+            layout.amount_line("Tax 2.5% on $10.00", "$0.25"),
+            layout.amount_line("Tax 8% on $150.00", "$12.00")
+        );
+        assert!(receipt.contains(&breakdown));
+        assert!(receipt.ends_with("See you soon"));
+        assert!(!receipt.contains("{{"));
+
+        // The organization's footer takes the store's place
+        let mut conn = pool.acquire().await.unwrap();
+        let mut settings = organization_settings::load(&mut conn, 1).await.unwrap();
+        settings.receipt_footer = Some("Thanks from all our stores".to_string());
+        organization_settings::save(&mut conn, 1, &settings).await.unwrap();
+        drop(conn);
+        let receipt = render_sale_receipt(&pool, sale.id, None).await.unwrap();
+        assert!(receipt.ends_with("Thanks from all our stores"));
+        assert!(!receipt.contains("See you soon"));
+    }
+
+    #[tokio::test]
+    async fn test_pick_ticket_lists_skus_and_dimensions_without_prices() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier1").await;
+        let cement = insert_test_product(&pool, "CEM-50", 12.5, 40).await;
+        sqlx::query(
+            "UPDATE products SET name = 'Portland Cement 50kg', dimensions = '60x40x12 cm'
+             WHERE id = ?1",
+        )
+        .bind(cement)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let subtotal = Money::from_major(37.5);
+        let mut item = line(cement, 12.5);
+        item.quantity = 3;
+        item.line_total = subtotal;
+        let request = CreateSaleRequest {
+            items: vec![item],
+            subtotal,
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: subtotal,
+            payment_method: "cash".to_string(),
+            payment_status: None,
+            customer_name: Some("Yard Builders".to_string()),
+            customer_phone: None,
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        };
+        let sale = create_sale_internal(&pool, request, cashier, None).await.unwrap();
+
+        let ticket = render_pick_ticket(&pool, sale.id).await.unwrap();
+        let item = "   3 CEM-50\n     Portland Cement 50kg\n     Dimensions: 60x40x12 cm";
+        assert!(ticket.contains(item));
+        assert!(ticket.contains("Customer: Yard Builders"));
+        assert!(ticket.contains("Lines: 1  Units: 3"));
+        assert!(!ticket.contains('$') && !ticket.contains("12.5") && !ticket.contains("37.5"));
+        assert!(!ticket.contains("{{") && !ticket.contains("\\n"));
+    }
+}
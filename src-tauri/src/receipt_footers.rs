@@ -0,0 +1,124 @@
+//! Receipt footer rules: messages printed under a sale's receipt when the sale qualifies.
+//! A coupon rule also issues a single-use promotion for the customer's next visit and
+//! prints its code in place of `{code}`.
+
+use crate::error::AppResult;
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+
+/// Replaced by the issued promotion code in a coupon rule's message
+pub const COUPON_CODE_PLACEHOLDER: &str = "{code}";
+
+pub const COUPON_DISCOUNT_TYPES: [&str; 2] = ["Percentage", "Fixed Amount"];
+
+/// Issued codes start with this, so staff can tell a receipt coupon at a glance
+const COUPON_CODE_PREFIX: &str = "RC-";
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReceiptFooterRule {
+    pub id: i64,
+    pub name: String,
+    pub message: String,
+    /// Sales totalling less than this do not get the message
+    pub min_amount: Money,
+    /// First and last day of sales the rule applies to, either end open
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    /// `Percentage` or `Fixed Amount`; the rule only prints a message when unset
+    pub coupon_discount_type: Option<String>,
+    pub coupon_discount_value: Option<f64>,
+    /// Days the coupon can be used for from the day it is issued; no expiry when unset
+    pub coupon_valid_days: Option<i32>,
+    /// Rules print in ascending priority
+    pub priority: i32,
+    pub is_active: bool,
+    pub created_at: String,
+}
+
+pub const RECEIPT_FOOTER_RULE_COLUMNS: &str = "id, name, message, min_amount, start_date, end_date,
+    coupon_discount_type, coupon_discount_value, coupon_valid_days, priority, is_active,
+    COALESCE(created_at, '') as created_at";
+
+impl ReceiptFooterRule {
+    pub fn issues_coupon(&self) -> bool {
+        self.coupon_discount_type.is_some()
+    }
+
+    /// Whether a sale of `total` made on `sale_date` (`YYYY-MM-DD`) gets the message
+    pub fn applies_to(&self, total: Money, sale_date: &str) -> bool {
+        total >= self.min_amount
+            && self.start_date.as_deref().is_none_or(|start| sale_date >= start)
+            && self.end_date.as_deref().is_none_or(|end| sale_date <= end)
+    }
+}
+
+/// The single-use promotion `rule` issued on the receipt of `sale_id`, created the first
+/// time the receipt is rendered. Reprints print the same code.
+async fn issue_coupon(pool: &SqlitePool, rule: &ReceiptFooterRule, sale_id: i64) -> AppResult<String> {
+    let today = chrono::Local::now().date_naive();
+    let end_date = rule
+        .coupon_valid_days
+        .map(|days| (today + chrono::Duration::days(days as i64)).to_string());
+    let code = format!(
+        "{}{}",
+        COUPON_CODE_PREFIX,
+        uuid::Uuid::new_v4().simple().to_string()[..8].to_uppercase()
+    );
+    sqlx::query(
+        "INSERT INTO promotions (code, name, description, discount_type, discount_value,
+                                 min_purchase_amount, start_date, end_date, usage_limit,
+                                 customer_type, footer_rule_id, source_sale_id)
+         SELECT ?1, ?2, 'Issued on the receipt of sale ' || s.sale_number, ?3, ?4, 0, ?5, ?6, 1,
+                'All', ?7, s.id
+         FROM sales s WHERE s.id = ?8
+         ON CONFLICT(footer_rule_id, source_sale_id)
+             WHERE footer_rule_id IS NOT NULL AND source_sale_id IS NOT NULL DO NOTHING",
+    )
+    .bind(&code)
+    .bind(&rule.name)
+    .bind(&rule.coupon_discount_type)
+    .bind(rule.coupon_discount_value.unwrap_or(0.0))
+    .bind(today.to_string())
+    .bind(end_date)
+    .bind(rule.id)
+    .bind(sale_id)
+    .execute(pool)
+    .await?;
+
+    Ok(sqlx::query_scalar("SELECT code FROM promotions WHERE footer_rule_id = ?1 AND source_sale_id = ?2")
+        .bind(rule.id)
+        .bind(sale_id)
+        .fetch_one(pool)
+        .await?)
+}
+
+/// Messages of the active rules the sale qualifies for, in priority order, with coupon
+/// codes filled in. Voided sales get none.
+pub async fn footer_messages(pool: &SqlitePool, sale_id: i64) -> AppResult<Vec<String>> {
+    let sale: Option<(Money, String)> = sqlx::query_as(
+        "SELECT total_amount, DATE(created_at) FROM sales WHERE id = ?1 AND is_voided = 0",
+    )
+    .bind(sale_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some((total, sale_date)) = sale else {
+        return Ok(Vec::new());
+    };
+
+    let query = format!(
+        "SELECT {} FROM receipt_footer_rules WHERE is_active = 1 ORDER BY priority, id",
+        RECEIPT_FOOTER_RULE_COLUMNS
+    );
+    let rules = sqlx::query_as::<_, ReceiptFooterRule>(&query).fetch_all(pool).await?;
+    let mut messages = Vec::new();
+    for rule in rules.iter().filter(|rule| rule.applies_to(total, &sale_date)) {
+        if rule.issues_coupon() {
+            let code = issue_coupon(pool, rule, sale_id).await?;
+            messages.push(rule.message.replace(COUPON_CODE_PLACEHOLDER, &code));
+        } else {
+            messages.push(rule.message.clone());
+        }
+    }
+    Ok(messages)
+}
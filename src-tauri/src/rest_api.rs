@@ -0,0 +1,637 @@
+use crate::commands::products::{
+    get_products_by_ids_internal, get_products_with_stock_internal, ProductWithStock,
+};
+use crate::commands::sales::create_sale_internal;
+use crate::error::{AppError, AppResult};
+use crate::models::{CreateSaleRequest, Sale};
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{Row, SqlitePool};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+pub const SCOPE_PRODUCTS_READ: &str = "products:read";
+pub const SCOPE_INVENTORY_READ: &str = "inventory:read";
+pub const SCOPE_SALES_WRITE: &str = "sales:write";
+pub const API_SCOPES: &[&str] = &[SCOPE_PRODUCTS_READ, SCOPE_INVENTORY_READ, SCOPE_SALES_WRITE];
+
+const API_KEY_PREFIX: &str = "pos_";
+/// Characters of a key kept in clear so it can be recognised in the key list
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: i64,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    /// Organization whose products the key reads and whose sales it posts
+    pub organization_id: i64,
+    /// User the key's sales are rung up as
+    pub cashier_id: Option<i64>,
+    pub is_active: bool,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+/// A freshly created key; `key` is shown once and never stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewApiKey {
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    /// Required with the `sales:write` scope
+    #[serde(default)]
+    pub cashier_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InventoryLevel {
+    pub product_id: i64,
+    pub sku: String,
+    pub name: String,
+    pub current_stock: i32,
+    pub available_stock: i32,
+    pub minimum_stock: i32,
+}
+
+/// Body of `POST /api/sales`. The sale is rung up as the key's cashier.
+#[derive(Debug, Deserialize)]
+pub struct PostSaleRequest {
+    /// An open shift of the key's cashier
+    pub shift_id: Option<i64>,
+    pub sale: CreateSaleRequest,
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+fn api_key_from_row(row: &sqlx::sqlite::SqliteRow) -> AppResult<ApiKey> {
+    let scopes: String = row.try_get("scopes")?;
+    Ok(ApiKey {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        key_prefix: row.try_get("key_prefix")?,
+        scopes: serde_json::from_str(&scopes).unwrap_or_default(),
+        organization_id: row.try_get("organization_id")?,
+        cashier_id: row.try_get("cashier_id")?,
+        is_active: row.try_get("is_active")?,
+        created_at: row.try_get("created_at")?,
+        last_used_at: row.try_get("last_used_at")?,
+    })
+}
+
+const API_KEY_COLUMNS: &str = "id, name, key_prefix, scopes, organization_id, cashier_id, is_active,
+    COALESCE(created_at, '') as created_at, last_used_at";
+
+pub async fn create_api_key(
+    pool: &SqlitePool,
+    organization_id: i64,
+    request: &CreateApiKeyRequest,
+    created_by: Option<i64>,
+) -> AppResult<NewApiKey> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err(AppError::validation("name", "API key name is required"));
+    }
+    if request.scopes.is_empty() {
+        return Err(AppError::validation("scopes", "Grant the API key at least one scope"));
+    }
+    if let Some(unknown) = request
+        .scopes
+        .iter()
+        .find(|scope| !API_SCOPES.contains(&scope.as_str()))
+    {
+        return Err(AppError::validation(
+            "scopes",
+            &format!("Unknown API scope '{}'", unknown),
+        ));
+    }
+    if request
+        .scopes
+        .iter()
+        .any(|scope| scope == SCOPE_SALES_WRITE)
+    {
+        let cashier_id = request.cashier_id.ok_or_else(|| {
+            AppError::validation("cashier_id", "A key that posts sales needs a cashier")
+        })?;
+        let active: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM users WHERE id = ?1 AND is_active = 1")
+                .bind(cashier_id)
+                .fetch_optional(pool)
+                .await?;
+        active
+            .ok_or_else(|| AppError::validation("cashier_id", "Cashier not found or inactive"))?;
+    }
+
+    let key = format!(
+        "{}{}{}",
+        API_KEY_PREFIX,
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    let scopes = serde_json::to_string(&request.scopes).map_err(|e| AppError::from(e.to_string()))?;
+    let query = format!(
+        "INSERT INTO api_keys (name, key_prefix, key_hash, scopes, created_by, cashier_id,
+                               organization_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         RETURNING {}",
+        API_KEY_COLUMNS
+    );
+    let row = sqlx::query(&query)
+        .bind(name)
+        .bind(&key[..DISPLAY_PREFIX_LEN])
+        .bind(hash_key(&key))
+        .bind(scopes)
+        .bind(created_by)
+        .bind(request.cashier_id)
+        .bind(organization_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(NewApiKey {
+        api_key: api_key_from_row(&row)?,
+        key,
+    })
+}
+
+pub async fn get_api_keys(pool: &SqlitePool, organization_id: i64) -> AppResult<Vec<ApiKey>> {
+    let query = format!(
+        "SELECT {} FROM api_keys WHERE organization_id = ?1 AND revoked_at IS NULL ORDER BY id",
+        API_KEY_COLUMNS
+    );
+    let rows = sqlx::query(&query).bind(organization_id).fetch_all(pool).await?;
+    rows.iter().map(api_key_from_row).collect()
+}
+
+pub async fn revoke_api_key(
+    pool: &SqlitePool,
+    organization_id: i64,
+    api_key_id: i64,
+) -> AppResult<()> {
+    let result = sqlx::query(
+        "UPDATE api_keys SET is_active = 0, revoked_at = CURRENT_TIMESTAMP
+         WHERE id = ?1 AND organization_id = ?2 AND revoked_at IS NULL",
+    )
+    .bind(api_key_id)
+    .bind(organization_id)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("API key"));
+    }
+    Ok(())
+}
+
+/// What an accepted key acts as
+struct KeyGrant {
+    organization_id: i64,
+    cashier_id: Option<i64>,
+}
+
+/// Check the bearer key on a request and that it carries `scope`, returning the key's
+/// organization and cashier
+async fn authorize(pool: &SqlitePool, headers: &HeaderMap, scope: &str) -> AppResult<KeyGrant> {
+    let key = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .ok_or_else(AppError::api_key_invalid)?;
+
+    let row = sqlx::query(
+        "SELECT id, scopes, organization_id, cashier_id FROM api_keys
+         WHERE key_hash = ?1 AND is_active = 1 AND revoked_at IS NULL",
+    )
+    .bind(hash_key(key))
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(AppError::api_key_invalid)?;
+
+    let id: i64 = row.try_get("id")?;
+    let scopes: String = row.try_get("scopes")?;
+    let scopes: Vec<String> = serde_json::from_str(&scopes).unwrap_or_default();
+    if !scopes.iter().any(|granted| granted == scope) {
+        return Err(AppError::PermissionDenied {
+            message: format!("API key lacks the '{}' scope", scope),
+        });
+    }
+
+    sqlx::query("UPDATE api_keys SET last_used_at = CURRENT_TIMESTAMP WHERE id = ?1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(KeyGrant {
+        organization_id: row.try_get("organization_id")?,
+        cashier_id: row.try_get("cashier_id")?,
+    })
+}
+
+/// AppError rendered as its usual JSON body with a matching HTTP status
+struct ApiError(AppError);
+
+impl From<AppError> for ApiError {
+    fn from(err: AppError) -> Self {
+        ApiError(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match &self.0 {
+            AppError::NotFound { .. } => StatusCode::NOT_FOUND,
+            AppError::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Conflict { .. }
+            | AppError::InsufficientStock { .. }
+            | AppError::ConcurrentModification => StatusCode::CONFLICT,
+            AppError::PermissionDenied { .. } => StatusCode::FORBIDDEN,
+            AppError::PlanLimitExceeded { .. } | AppError::TrialExpired => {
+                StatusCode::PAYMENT_REQUIRED
+            }
+            AppError::Auth { .. } => StatusCode::UNAUTHORIZED,
+            AppError::Database { .. } | AppError::Internal { .. } => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        (status, Json(self.0)).into_response()
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+async fn list_products(
+    State(pool): State<SqlitePool>,
+    headers: HeaderMap,
+) -> ApiResult<Json<Vec<ProductWithStock>>> {
+    let grant = authorize(&pool, &headers, SCOPE_PRODUCTS_READ).await?;
+    Ok(Json(get_products_with_stock_internal(&pool, grant.organization_id).await?))
+}
+
+async fn get_product(
+    State(pool): State<SqlitePool>,
+    headers: HeaderMap,
+    Path(product_id): Path<i64>,
+) -> ApiResult<Json<ProductWithStock>> {
+    let grant = authorize(&pool, &headers, SCOPE_PRODUCTS_READ).await?;
+    let product = get_products_by_ids_internal(&pool, &[product_id], grant.organization_id)
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::not_found("Product"))?;
+    Ok(Json(product))
+}
+
+async fn list_inventory(
+    State(pool): State<SqlitePool>,
+    headers: HeaderMap,
+) -> ApiResult<Json<Vec<InventoryLevel>>> {
+    let grant = authorize(&pool, &headers, SCOPE_INVENTORY_READ).await?;
+    let levels = get_products_with_stock_internal(&pool, grant.organization_id)
+        .await?
+        .into_iter()
+        .map(|product| InventoryLevel {
+            product_id: product.id,
+            sku: product.sku,
+            name: product.name,
+            current_stock: product.current_stock,
+            available_stock: product.available_stock,
+            minimum_stock: product.minimum_stock,
+        })
+        .collect();
+    Ok(Json(levels))
+}
+
+async fn post_sale(
+    State(pool): State<SqlitePool>,
+    headers: HeaderMap,
+    Json(mut request): Json<PostSaleRequest>,
+) -> ApiResult<(StatusCode, Json<Sale>)> {
+    let grant = authorize(&pool, &headers, SCOPE_SALES_WRITE).await?;
+    let cashier_id = grant
+        .cashier_id
+        .ok_or_else(|| AppError::PermissionDenied {
+            message: "API key has no cashier to post sales as".to_string(),
+        })?;
+    if let Some(shift_id) = request.shift_id {
+        let open: Option<i64> = sqlx::query_scalar(
+            "SELECT id FROM shifts WHERE id = ?1 AND user_id = ?2 AND status = 'open'",
+        )
+        .bind(shift_id)
+        .bind(cashier_id)
+        .fetch_optional(&pool)
+        .await?;
+        open.ok_or_else(|| {
+            AppError::validation(
+                "shift_id",
+                "Shift is not an open shift of the key's cashier",
+            )
+        })?;
+    }
+    // Rung up in the key's organization, as a desktop sale is in the session's
+    request.sale.organization_id = Some(grant.organization_id);
+    let sale = create_sale_internal(&pool, request.sale, cashier_id, request.shift_id).await?;
+    Ok((StatusCode::CREATED, Json(sale)))
+}
+
+pub fn router(pool: SqlitePool) -> Router {
+    Router::new()
+        .route("/api/products", get(list_products))
+        .route("/api/products/:id", get(get_product))
+        .route("/api/inventory", get(list_inventory))
+        .route("/api/sales", axum::routing::post(post_sale))
+        .with_state(pool)
+}
+
+/// Running REST API server, kept in managed state so it can be stopped on exit
+pub struct RestApiServer {
+    addr: SocketAddr,
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl RestApiServer {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop accepting connections and wait for in-flight requests to finish
+    pub async fn shutdown(&self) {
+        if let Some(tx) = self.shutdown.lock().unwrap().take() {
+            let _ = tx.send(());
+        }
+        let task = self.task.lock().unwrap().take();
+        if let Some(task) = task {
+            let _ = task.await;
+        }
+    }
+}
+
+/// Serve the REST API on `addr` (port 0 picks a free one) using the app's pool
+pub async fn start(pool: SqlitePool, addr: SocketAddr) -> AppResult<RestApiServer> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let addr = listener.local_addr()?;
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+    let task = tokio::spawn(async move {
+        let server = axum::serve(listener, router(pool)).with_graceful_shutdown(async {
+            let _ = shutdown_rx.await;
+        });
+        if let Err(e) = server.await {
+            log::error!("REST API server stopped: {}", e);
+        }
+    });
+
+    Ok(RestApiServer {
+        addr,
+        shutdown: Mutex::new(Some(shutdown_tx)),
+        task: Mutex::new(Some(task)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_ORGANIZATION_ID;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+    use serde_json::{json, Value};
+
+    async fn key_with(pool: &SqlitePool, scopes: &[&str], cashier_id: Option<i64>) -> String {
+        let request = CreateApiKeyRequest {
+            name: "Kitchen display".to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            cashier_id,
+        };
+        create_api_key(pool, DEFAULT_ORGANIZATION_ID, &request, None).await.unwrap().key
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_require_a_scoped_key() {
+        let pool = test_pool().await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+        let reader = key_with(&pool, &[SCOPE_PRODUCTS_READ], None).await;
+        let server = start(pool.clone(), "127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let base = format!("http://{}", server.local_addr());
+        let client = reqwest::Client::new();
+
+        let response = client.get(format!("{}/api/products", base)).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["code"], "API_KEY_INVALID");
+
+        let response = client
+            .get(format!("{}/api/products/{}", base, widget))
+            .bearer_auth(&reader)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["sku"], "WIDGET");
+        assert_eq!(body["current_stock"], 20);
+
+        let response = client
+            .get(format!("{}/api/inventory", base))
+            .bearer_auth(&reader)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403);
+
+        let revoked = get_api_keys(&pool, DEFAULT_ORGANIZATION_ID).await.unwrap()[0].id;
+        let err = revoke_api_key(&pool, DEFAULT_ORGANIZATION_ID + 1, revoked).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+        revoke_api_key(&pool, DEFAULT_ORGANIZATION_ID, revoked).await.unwrap();
+        let response = client
+            .get(format!("{}/api/products", base))
+            .bearer_auth(&reader)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+
+        server.shutdown().await;
+        assert!(client.get(format!("{}/api/products", base)).send().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_key_only_reaches_its_own_organization() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "till2").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+        let org_b: i64 = sqlx::query_scalar(
+            "INSERT INTO organizations (name, slug) VALUES ('Other Shop', 'other-shop') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let request = CreateApiKeyRequest {
+            name: "Other web shop".to_string(),
+            scopes: vec![SCOPE_PRODUCTS_READ.to_string(), SCOPE_SALES_WRITE.to_string()],
+            cashier_id: Some(cashier),
+        };
+        let key = create_api_key(&pool, org_b, &request, None).await.unwrap();
+        assert_eq!(key.api_key.organization_id, org_b);
+        assert!(get_api_keys(&pool, DEFAULT_ORGANIZATION_ID).await.unwrap().is_empty());
+        let server = start(pool.clone(), "127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let base = format!("http://{}", server.local_addr());
+        let client = reqwest::Client::new();
+
+        let products: Vec<Value> = client
+            .get(format!("{}/api/products", base))
+            .bearer_auth(&key.key)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(products.is_empty());
+        let response = client
+            .get(format!("{}/api/products/{}", base, widget))
+            .bearer_auth(&key.key)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+
+        // Naming the default organization in the body does not reach its products
+        let body = json!({
+            "sale": {
+                "organization_id": DEFAULT_ORGANIZATION_ID,
+                "items": [{
+                    "product_id": widget,
+                    "quantity": 1,
+                    "unit_price": 10.0,
+                    "discount_amount": 0.0,
+                    "line_total": 10.0
+                }],
+                "subtotal": 10.0,
+                "tax_amount": 0.0,
+                "discount_amount": 0.0,
+                "total_amount": 10.0,
+                "payment_method": "cash"
+            }
+        });
+        let response = client
+            .post(format!("{}/api/sales", base))
+            .bearer_auth(&key.key)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+        assert_eq!(current_stock(&pool, widget).await, 20);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_sales_key_needs_a_cashier() {
+        let pool = test_pool().await;
+        let request = CreateApiKeyRequest {
+            name: "Web shop".to_string(),
+            scopes: vec![SCOPE_SALES_WRITE.to_string()],
+            cashier_id: None,
+        };
+        let err = create_api_key(&pool, DEFAULT_ORGANIZATION_ID, &request, None).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+
+    #[tokio::test]
+    async fn test_post_sale_creates_sale() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "till2").await;
+        let widget = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+        let key = key_with(&pool, &[SCOPE_SALES_WRITE, SCOPE_INVENTORY_READ], Some(cashier)).await;
+        let server = start(pool.clone(), "127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let base = format!("http://{}", server.local_addr());
+        let client = reqwest::Client::new();
+
+        let body = json!({
+            "sale": {
+                "items": [{
+                    "product_id": widget,
+                    "quantity": 2,
+                    "unit_price": 10.0,
+                    "discount_amount": 0.0,
+                    "line_total": 20.0
+                }],
+                "subtotal": 20.0,
+                "tax_amount": 0.0,
+                "discount_amount": 0.0,
+                "total_amount": 20.0,
+                "payment_method": "cash"
+            }
+        });
+        let response = client
+            .post(format!("{}/api/sales", base))
+            .bearer_auth(&key)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+        let sale: Value = response.json().await.unwrap();
+        assert!(sale["sale_number"].as_str().unwrap().starts_with("SALE-"));
+        assert_eq!(sale["cashier_id"], cashier);
+        assert_eq!(current_stock(&pool, widget).await, 18);
+
+        let mut bad = body.clone();
+        bad["sale"]["items"][0]["quantity"] = json!(0);
+        let response = client
+            .post(format!("{}/api/sales", base))
+            .bearer_auth(&key)
+            .json(&bad)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 422);
+
+        // The key posts as its own cashier, never into someone else's shift
+        let other = insert_test_user(&pool, "till3").await;
+        let other_shift = sqlx::query(
+            "INSERT INTO shifts (user_id, start_time, opening_amount, status)
+             VALUES (?1, CURRENT_TIMESTAMP, 100, 'open')",
+        )
+        .bind(other)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        let mut foreign_shift = body.clone();
+        foreign_shift["shift_id"] = json!(other_shift);
+        let response = client
+            .post(format!("{}/api/sales", base))
+            .bearer_auth(&key)
+            .json(&foreign_shift)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 422);
+
+        let levels: Vec<InventoryLevel> = client
+            .get(format!("{}/api/inventory", base))
+            .bearer_auth(&key)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(levels[0].current_stock, 18);
+
+        server.shutdown().await;
+    }
+}
@@ -0,0 +1,867 @@
+//! Building materials wholesaler: bulk cement, lumber, roofing and fasteners sold to
+//! contractors, all taxed at the Colorado rate.
+
+use super::{DemoCustomer, DemoProduct, DemoSupplier, DemoTemplate, DemoUser};
+
+pub struct BuildingMaterials;
+
+impl DemoTemplate for BuildingMaterials {
+    fn code(&self) -> &'static str {
+        "building_materials"
+    }
+
+    fn number_prefix(&self) -> &'static str {
+        "BM"
+    }
+
+    fn users(&self) -> &'static [DemoUser] {
+        USERS
+    }
+
+    fn categories(&self) -> &'static [&'static str] {
+        CATEGORIES
+    }
+
+    fn products(&self) -> &'static [DemoProduct] {
+        PRODUCTS
+    }
+
+    fn customers(&self) -> &'static [DemoCustomer] {
+        CUSTOMERS
+    }
+
+    fn suppliers(&self) -> &'static [DemoSupplier] {
+        SUPPLIERS
+    }
+
+    fn sample_sales(&self) -> i32 {
+        15
+    }
+
+    fn payment_methods(&self) -> &'static [&'static str] {
+        &["cash", "card", "check", "bank_transfer"]
+    }
+}
+
+const USERS: &[DemoUser] = &[
+    DemoUser {
+        username: "manager",
+        email: "manager@buildco.com",
+        password: "Manager123",
+        first_name: "Sarah",
+        last_name: "Johnson",
+        role: "Manager",
+    },
+    DemoUser {
+        username: "cashier1",
+        email: "cashier1@buildco.com",
+        password: "Cashier123",
+        first_name: "Michael",
+        last_name: "Smith",
+        role: "Cashier",
+    },
+    DemoUser {
+        username: "cashier2",
+        email: "cashier2@buildco.com",
+        password: "Cashier123",
+        first_name: "Emily",
+        last_name: "Davis",
+        role: "Cashier",
+    },
+    DemoUser {
+        username: "warehouse",
+        email: "warehouse@buildco.com",
+        password: "Warehouse123",
+        first_name: "James",
+        last_name: "Wilson",
+        role: "Warehouse",
+    },
+    DemoUser {
+        username: "stock",
+        email: "stock@buildco.com",
+        password: "Stock123",
+        first_name: "Linda",
+        last_name: "Martinez",
+        role: "StockKeeper",
+    },
+];
+
+const CATEGORIES: &[&str] = &[
+    "Cement & Concrete",
+    "Lumber",
+    "Drywall & Insulation",
+    "Roofing",
+    "Siding & Exterior",
+    "Windows & Doors",
+    "Fasteners & Hardware",
+    "Electrical",
+    "Plumbing",
+    "Masonry",
+];
+
+const PRODUCTS: &[DemoProduct] = &[
+    DemoProduct {
+        sku: "CEM-P1-50",
+        barcode: "8901234001",
+        name: "Portland Cement Type I - 50kg",
+        description: "High-quality general purpose cement for all construction",
+        category: "Cement & Concrete",
+        brand: "CemEx",
+        unit: "Bag",
+        cost: 6.20,
+        price: 8.50,
+        wholesale_price: 7.00,
+        tax_rate: 6.5,
+        reorder_point: 100,
+        stock: 15,
+    },
+    DemoProduct {
+        sku: "CEM-P1-94",
+        barcode: "8901234002",
+        name: "Portland Cement Type I - 94lb",
+        description: "Standard 94lb bag for commercial projects",
+        category: "Cement & Concrete",
+        brand: "Quikrete",
+        unit: "Bag",
+        cost: 9.50,
+        price: 12.99,
+        wholesale_price: 10.50,
+        tax_rate: 6.5,
+        reorder_point: 80,
+        stock: 50,
+    },
+    DemoProduct {
+        sku: "CMX-001",
+        barcode: "8901234003",
+        name: "Ready Mix Concrete 3000 PSI",
+        description: "Pre-mixed concrete 3000 PSI strength",
+        category: "Cement & Concrete",
+        brand: "CemEx",
+        unit: "Cubic Yard",
+        cost: 85.00,
+        price: 115.00,
+        wholesale_price: 95.00,
+        tax_rate: 6.5,
+        reorder_point: 20,
+        stock: 150,
+    },
+    DemoProduct {
+        sku: "CMX-002",
+        barcode: "8901234004",
+        name: "Ready Mix Concrete 4000 PSI",
+        description: "High strength ready mix for commercial",
+        category: "Cement & Concrete",
+        brand: "CemEx",
+        unit: "Cubic Yard",
+        cost: 95.00,
+        price: 125.00,
+        wholesale_price: 105.00,
+        tax_rate: 6.5,
+        reorder_point: 15,
+        stock: 30,
+    },
+    DemoProduct {
+        sku: "GRT-001",
+        barcode: "8901234005",
+        name: "Cement Grout - Sanded",
+        description: "Sanded grout for tile joints 1/8\" to 1/2\"",
+        category: "Cement & Concrete",
+        brand: "Custom Building Products",
+        unit: "Bag",
+        cost: 16.00,
+        price: 22.99,
+        wholesale_price: 18.00,
+        tax_rate: 6.5,
+        reorder_point: 60,
+        stock: 100,
+    },
+    DemoProduct {
+        sku: "LBR-2X4-8",
+        barcode: "8901234101",
+        name: "2x4x8 Pressure Treated Lumber",
+        description: "Premium grade pressure treated lumber",
+        category: "Lumber",
+        brand: "Georgia-Pacific",
+        unit: "Each",
+        cost: 6.20,
+        price: 8.99,
+        wholesale_price: 7.00,
+        tax_rate: 6.5,
+        reorder_point: 200,
+        stock: 25,
+    },
+    DemoProduct {
+        sku: "LBR-2X4-10",
+        barcode: "8901234102",
+        name: "2x4x10 Pressure Treated Lumber",
+        description: "10 foot pressure treated for framing",
+        category: "Lumber",
+        brand: "Georgia-Pacific",
+        unit: "Each",
+        cost: 7.80,
+        price: 11.49,
+        wholesale_price: 8.90,
+        tax_rate: 6.5,
+        reorder_point: 180,
+        stock: 75,
+    },
+    DemoProduct {
+        sku: "LBR-2X6-8",
+        barcode: "8901234103",
+        name: "2x6x8 Pressure Treated Lumber",
+        description: "Heavy duty 2x6 for decking and framing",
+        category: "Lumber",
+        brand: "Georgia-Pacific",
+        unit: "Each",
+        cost: 9.50,
+        price: 13.99,
+        wholesale_price: 10.80,
+        tax_rate: 6.5,
+        reorder_point: 150,
+        stock: 15,
+    },
+    DemoProduct {
+        sku: "LBR-2X8-10",
+        barcode: "8901234104",
+        name: "2x8x10 Pressure Treated Lumber",
+        description: "Large dimensional lumber for structures",
+        category: "Lumber",
+        brand: "Georgia-Pacific",
+        unit: "Each",
+        cost: 17.00,
+        price: 24.99,
+        wholesale_price: 19.50,
+        tax_rate: 6.5,
+        reorder_point: 100,
+        stock: 50,
+    },
+    DemoProduct {
+        sku: "PLY-001",
+        barcode: "8901234105",
+        name: "Plywood 4x8 1/2\" CDX",
+        description: "Construction grade plywood sheet",
+        category: "Lumber",
+        brand: "Weyerhaeuser",
+        unit: "Sheet",
+        cost: 23.00,
+        price: 32.99,
+        wholesale_price: 26.50,
+        tax_rate: 6.5,
+        reorder_point: 80,
+        stock: 150,
+    },
+    DemoProduct {
+        sku: "PLY-002",
+        barcode: "8901234106",
+        name: "Plywood 4x8 3/4\" CDX",
+        description: "Heavy duty construction plywood",
+        category: "Lumber",
+        brand: "Weyerhaeuser",
+        unit: "Sheet",
+        cost: 32.00,
+        price: 45.99,
+        wholesale_price: 36.50,
+        tax_rate: 6.5,
+        reorder_point: 60,
+        stock: 30,
+    },
+    DemoProduct {
+        sku: "OSB-001",
+        barcode: "8901234107",
+        name: "OSB 4x8 7/16\" Sheathing",
+        description: "Oriented strand board for sheathing",
+        category: "Lumber",
+        brand: "LP Building",
+        unit: "Sheet",
+        cost: 13.50,
+        price: 18.99,
+        wholesale_price: 15.20,
+        tax_rate: 6.5,
+        reorder_point: 100,
+        stock: 100,
+    },
+    DemoProduct {
+        sku: "DRY-001",
+        barcode: "8901234201",
+        name: "Drywall 4x8 1/2\" Regular",
+        description: "Standard gypsum drywall sheet",
+        category: "Drywall & Insulation",
+        brand: "USG",
+        unit: "Sheet",
+        cost: 7.20,
+        price: 10.49,
+        wholesale_price: 8.30,
+        tax_rate: 6.5,
+        reorder_point: 150,
+        stock: 25,
+    },
+    DemoProduct {
+        sku: "DRY-002",
+        barcode: "8901234202",
+        name: "Drywall 4x8 5/8\" Type X Fire",
+        description: "Fire resistant Type X drywall",
+        category: "Drywall & Insulation",
+        brand: "USG",
+        unit: "Sheet",
+        cost: 10.50,
+        price: 14.99,
+        wholesale_price: 12.00,
+        tax_rate: 6.5,
+        reorder_point: 100,
+        stock: 75,
+    },
+    DemoProduct {
+        sku: "DRY-003",
+        barcode: "8901234203",
+        name: "Drywall 4x8 1/2\" Moisture Resistant",
+        description: "Green board for moisture areas",
+        category: "Drywall & Insulation",
+        brand: "USG",
+        unit: "Sheet",
+        cost: 9.80,
+        price: 13.99,
+        wholesale_price: 11.20,
+        tax_rate: 6.5,
+        reorder_point: 80,
+        stock: 15,
+    },
+    DemoProduct {
+        sku: "INS-001",
+        barcode: "8901234204",
+        name: "Fiberglass Insulation R-13 15\"",
+        description: "R-13 batt insulation for 2x4 walls",
+        category: "Drywall & Insulation",
+        brand: "Owens Corning",
+        unit: "Roll",
+        cost: 30.00,
+        price: 42.99,
+        wholesale_price: 34.50,
+        tax_rate: 6.5,
+        reorder_point: 60,
+        stock: 50,
+    },
+    DemoProduct {
+        sku: "INS-002",
+        barcode: "8901234205",
+        name: "Fiberglass Insulation R-19 23\"",
+        description: "R-19 batt for 2x6 walls",
+        category: "Drywall & Insulation",
+        brand: "Owens Corning",
+        unit: "Roll",
+        cost: 42.00,
+        price: 59.99,
+        wholesale_price: 48.00,
+        tax_rate: 6.5,
+        reorder_point: 50,
+        stock: 150,
+    },
+    DemoProduct {
+        sku: "INS-003",
+        barcode: "8901234206",
+        name: "Rigid Foam Insulation 4x8 1\"",
+        description: "XPS foam board insulation R-5",
+        category: "Drywall & Insulation",
+        brand: "Owens Corning",
+        unit: "Sheet",
+        cost: 17.50,
+        price: 24.99,
+        wholesale_price: 20.00,
+        tax_rate: 6.5,
+        reorder_point: 70,
+        stock: 30,
+    },
+    DemoProduct {
+        sku: "ROOF-001",
+        barcode: "8901234301",
+        name: "Asphalt Shingles 3-Tab Black",
+        description: "Standard 3-tab architectural shingles",
+        category: "Roofing",
+        brand: "GAF",
+        unit: "Bundle",
+        cost: 20.00,
+        price: 28.99,
+        wholesale_price: 23.00,
+        tax_rate: 6.5,
+        reorder_point: 100,
+        stock: 100,
+    },
+    DemoProduct {
+        sku: "ROOF-002",
+        barcode: "8901234302",
+        name: "Asphalt Shingles Architectural Gray",
+        description: "Premium architectural shingles",
+        category: "Roofing",
+        brand: "GAF",
+        unit: "Bundle",
+        cost: 27.00,
+        price: 38.99,
+        wholesale_price: 31.00,
+        tax_rate: 6.5,
+        reorder_point: 80,
+        stock: 25,
+    },
+    DemoProduct {
+        sku: "ROOF-003",
+        barcode: "8901234303",
+        name: "Roofing Felt 15lb Tar Paper",
+        description: "Underlayment felt paper roll",
+        category: "Roofing",
+        brand: "GAF",
+        unit: "Roll",
+        cost: 16.00,
+        price: 22.99,
+        wholesale_price: 18.50,
+        tax_rate: 6.5,
+        reorder_point: 50,
+        stock: 75,
+    },
+    DemoProduct {
+        sku: "ROOF-004",
+        barcode: "8901234304",
+        name: "Synthetic Roofing Underlayment",
+        description: "Advanced synthetic underlayment",
+        category: "Roofing",
+        brand: "GAF",
+        unit: "Roll",
+        cost: 85.00,
+        price: 119.99,
+        wholesale_price: 98.00,
+        tax_rate: 6.5,
+        reorder_point: 40,
+        stock: 15,
+    },
+    DemoProduct {
+        sku: "ROOF-005",
+        barcode: "8901234305",
+        name: "Metal Roofing Panel 3' - Galvanized",
+        description: "Corrugated metal roofing panel",
+        category: "Roofing",
+        brand: "Union Corrugating",
+        unit: "Panel",
+        cost: 24.50,
+        price: 34.99,
+        wholesale_price: 28.00,
+        tax_rate: 6.5,
+        reorder_point: 60,
+        stock: 50,
+    },
+    DemoProduct {
+        sku: "SID-001",
+        barcode: "8901234401",
+        name: "Vinyl Siding 4.5\" Dutch Lap White",
+        description: "Low maintenance vinyl siding",
+        category: "Siding & Exterior",
+        brand: "CertainTeed",
+        unit: "Box",
+        cost: 112.00,
+        price: 159.99,
+        wholesale_price: 128.00,
+        tax_rate: 6.5,
+        reorder_point: 40,
+        stock: 150,
+    },
+    DemoProduct {
+        sku: "SID-002",
+        barcode: "8901234402",
+        name: "Vinyl Siding 4.5\" Dutch Lap Tan",
+        description: "Tan vinyl siding for residential",
+        category: "Siding & Exterior",
+        brand: "CertainTeed",
+        unit: "Box",
+        cost: 112.00,
+        price: 159.99,
+        wholesale_price: 128.00,
+        tax_rate: 6.5,
+        reorder_point: 35,
+        stock: 30,
+    },
+    DemoProduct {
+        sku: "SID-003",
+        barcode: "8901234403",
+        name: "House Wrap Tyvek 9'x150'",
+        description: "Weather resistant house wrap",
+        category: "Siding & Exterior",
+        brand: "DuPont",
+        unit: "Roll",
+        cost: 120.00,
+        price: 169.99,
+        wholesale_price: 138.00,
+        tax_rate: 6.5,
+        reorder_point: 30,
+        stock: 100,
+    },
+    DemoProduct {
+        sku: "SID-004",
+        barcode: "8901234404",
+        name: "Hardie Board Fiber Cement 4x8",
+        description: "Durable fiber cement siding",
+        category: "Siding & Exterior",
+        brand: "James Hardie",
+        unit: "Sheet",
+        cost: 30.00,
+        price: 42.99,
+        wholesale_price: 34.50,
+        tax_rate: 6.5,
+        reorder_point: 50,
+        stock: 25,
+    },
+    DemoProduct {
+        sku: "WIN-001",
+        barcode: "8901234501",
+        name: "Vinyl Window Single Hung 24x36",
+        description: "Energy efficient vinyl window",
+        category: "Windows & Doors",
+        brand: "Pella",
+        unit: "Each",
+        cost: 135.00,
+        price: 189.99,
+        wholesale_price: 155.00,
+        tax_rate: 6.5,
+        reorder_point: 25,
+        stock: 75,
+    },
+    DemoProduct {
+        sku: "WIN-002",
+        barcode: "8901234502",
+        name: "Vinyl Window Single Hung 30x48",
+        description: "Large single hung window",
+        category: "Windows & Doors",
+        brand: "Pella",
+        unit: "Each",
+        cost: 178.00,
+        price: 249.99,
+        wholesale_price: 204.00,
+        tax_rate: 6.5,
+        reorder_point: 20,
+        stock: 15,
+    },
+    DemoProduct {
+        sku: "DOOR-001",
+        barcode: "8901234503",
+        name: "Steel Entry Door 36\" White",
+        description: "Insulated steel entry door",
+        category: "Windows & Doors",
+        brand: "Therma-Tru",
+        unit: "Each",
+        cost: 215.00,
+        price: 299.99,
+        wholesale_price: 245.00,
+        tax_rate: 6.5,
+        reorder_point: 15,
+        stock: 50,
+    },
+    DemoProduct {
+        sku: "DOOR-002",
+        barcode: "8901234504",
+        name: "Fiberglass Entry Door 36\" Oak",
+        description: "Premium fiberglass entry door",
+        category: "Windows & Doors",
+        brand: "Therma-Tru",
+        unit: "Each",
+        cost: 330.00,
+        price: 459.99,
+        wholesale_price: 378.00,
+        tax_rate: 6.5,
+        reorder_point: 12,
+        stock: 150,
+    },
+    DemoProduct {
+        sku: "NAIL-001",
+        barcode: "8901234601",
+        name: "Framing Nails 16d 5lb Box",
+        description: "Hot-dipped galvanized framing nails",
+        category: "Fasteners & Hardware",
+        brand: "Grip-Rite",
+        unit: "Box",
+        cost: 10.50,
+        price: 14.99,
+        wholesale_price: 12.00,
+        tax_rate: 6.5,
+        reorder_point: 100,
+        stock: 30,
+    },
+    DemoProduct {
+        sku: "NAIL-002",
+        barcode: "8901234602",
+        name: "Finish Nails 8d 1lb Box",
+        description: "Bright finish nails for trim",
+        category: "Fasteners & Hardware",
+        brand: "Grip-Rite",
+        unit: "Box",
+        cost: 4.20,
+        price: 5.99,
+        wholesale_price: 4.80,
+        tax_rate: 6.5,
+        reorder_point: 150,
+        stock: 100,
+    },
+    DemoProduct {
+        sku: "SCREW-001",
+        barcode: "8901234603",
+        name: "Deck Screws #8 x 2.5\" 5lb",
+        description: "Coated deck screws",
+        category: "Fasteners & Hardware",
+        brand: "GRK Fasteners",
+        unit: "Box",
+        cost: 23.00,
+        price: 32.99,
+        wholesale_price: 26.50,
+        tax_rate: 6.5,
+        reorder_point: 80,
+        stock: 25,
+    },
+    DemoProduct {
+        sku: "SCREW-002",
+        barcode: "8901234604",
+        name: "Drywall Screws #6 x 1-5/8\" 5lb",
+        description: "Coarse thread drywall screws",
+        category: "Fasteners & Hardware",
+        brand: "Grip-Rite",
+        unit: "Box",
+        cost: 13.50,
+        price: 18.99,
+        wholesale_price: 15.50,
+        tax_rate: 6.5,
+        reorder_point: 100,
+        stock: 75,
+    },
+    DemoProduct {
+        sku: "ELEC-001",
+        barcode: "8901234701",
+        name: "Romex 12/2 NM-B Cable 250ft",
+        description: "12 AWG 2-conductor cable",
+        category: "Electrical",
+        brand: "Southwire",
+        unit: "Roll",
+        cost: 92.00,
+        price: 129.99,
+        wholesale_price: 105.00,
+        tax_rate: 6.5,
+        reorder_point: 30,
+        stock: 15,
+    },
+    DemoProduct {
+        sku: "ELEC-002",
+        barcode: "8901234702",
+        name: "Romex 14/2 NM-B Cable 250ft",
+        description: "14 AWG 2-conductor cable",
+        category: "Electrical",
+        brand: "Southwire",
+        unit: "Roll",
+        cost: 64.00,
+        price: 89.99,
+        wholesale_price: 73.00,
+        tax_rate: 6.5,
+        reorder_point: 40,
+        stock: 50,
+    },
+    DemoProduct {
+        sku: "ELEC-003",
+        barcode: "8901234703",
+        name: "Electrical Box Single Gang",
+        description: "PVC electrical outlet box",
+        category: "Electrical",
+        brand: "Carlon",
+        unit: "Each",
+        cost: 0.90,
+        price: 1.29,
+        wholesale_price: 1.05,
+        tax_rate: 6.5,
+        reorder_point: 500,
+        stock: 150,
+    },
+    DemoProduct {
+        sku: "ELEC-004",
+        barcode: "8901234704",
+        name: "Electrical Box Double Gang",
+        description: "PVC double gang box",
+        category: "Electrical",
+        brand: "Carlon",
+        unit: "Each",
+        cost: 1.35,
+        price: 1.89,
+        wholesale_price: 1.55,
+        tax_rate: 6.5,
+        reorder_point: 400,
+        stock: 30,
+    },
+    DemoProduct {
+        sku: "PLUMB-001",
+        barcode: "8901234801",
+        name: "PVC Pipe 2\" x 10ft Schedule 40",
+        description: "White PVC drainage pipe",
+        category: "Plumbing",
+        brand: "Charlotte Pipe",
+        unit: "Each",
+        cost: 7.00,
+        price: 9.99,
+        wholesale_price: 8.00,
+        tax_rate: 6.5,
+        reorder_point: 150,
+        stock: 100,
+    },
+    DemoProduct {
+        sku: "PLUMB-002",
+        barcode: "8901234802",
+        name: "PVC Pipe 4\" x 10ft Schedule 40",
+        description: "Large diameter drainage pipe",
+        category: "Plumbing",
+        brand: "Charlotte Pipe",
+        unit: "Each",
+        cost: 17.50,
+        price: 24.99,
+        wholesale_price: 20.00,
+        tax_rate: 6.5,
+        reorder_point: 100,
+        stock: 25,
+    },
+    DemoProduct {
+        sku: "PLUMB-003",
+        barcode: "8901234803",
+        name: "Copper Pipe Type L 3/4\" x 10ft",
+        description: "Type L copper water pipe",
+        category: "Plumbing",
+        brand: "Mueller",
+        unit: "Each",
+        cost: 30.50,
+        price: 42.99,
+        wholesale_price: 35.00,
+        tax_rate: 6.5,
+        reorder_point: 60,
+        stock: 75,
+    },
+    DemoProduct {
+        sku: "PLUMB-004",
+        barcode: "8901234804",
+        name: "PEX Tubing 1/2\" x 100ft Red",
+        description: "Red PEX for hot water",
+        category: "Plumbing",
+        brand: "SharkBite",
+        unit: "Roll",
+        cost: 46.00,
+        price: 64.99,
+        wholesale_price: 52.50,
+        tax_rate: 6.5,
+        reorder_point: 50,
+        stock: 15,
+    },
+    DemoProduct {
+        sku: "BLOCK-001",
+        barcode: "8901234901",
+        name: "Concrete Block 8x8x16 Standard",
+        description: "Standard concrete masonry unit",
+        category: "Masonry",
+        brand: "Oldcastle",
+        unit: "Each",
+        cost: 1.35,
+        price: 1.89,
+        wholesale_price: 1.55,
+        tax_rate: 6.5,
+        reorder_point: 1000,
+        stock: 50,
+    },
+    DemoProduct {
+        sku: "BLOCK-002",
+        barcode: "8901234902",
+        name: "Concrete Block 8x8x16 Half",
+        description: "Half block for corners",
+        category: "Masonry",
+        brand: "Oldcastle",
+        unit: "Each",
+        cost: 1.05,
+        price: 1.49,
+        wholesale_price: 1.20,
+        tax_rate: 6.5,
+        reorder_point: 500,
+        stock: 150,
+    },
+    DemoProduct {
+        sku: "BRICK-001",
+        barcode: "8901234903",
+        name: "Clay Brick Standard Red",
+        description: "Standard modular clay brick",
+        category: "Masonry",
+        brand: "Boral",
+        unit: "Each",
+        cost: 0.48,
+        price: 0.69,
+        wholesale_price: 0.55,
+        tax_rate: 6.5,
+        reorder_point: 2000,
+        stock: 30,
+    },
+    DemoProduct {
+        sku: "BRICK-002",
+        barcode: "8901234904",
+        name: "Clay Brick Paver Red",
+        description: "Red clay paver for walkways",
+        category: "Masonry",
+        brand: "Boral",
+        unit: "Each",
+        cost: 0.62,
+        price: 0.89,
+        wholesale_price: 0.72,
+        tax_rate: 6.5,
+        reorder_point: 1500,
+        stock: 100,
+    },
+];
+
+const CUSTOMERS: &[DemoCustomer] = &[
+    DemoCustomer {
+        first_name: "John",
+        last_name: "Doe",
+        email: "john.doe@email.com",
+        phone: "+1-555-0101",
+        customer_type: "Retail",
+    },
+    DemoCustomer {
+        first_name: "Jane",
+        last_name: "Smith",
+        email: "jane.smith@email.com",
+        phone: "+1-555-0102",
+        customer_type: "Wholesale",
+    },
+    DemoCustomer {
+        first_name: "Bob",
+        last_name: "Johnson",
+        email: "bob.j@email.com",
+        phone: "+1-555-0103",
+        customer_type: "Corporate",
+    },
+    DemoCustomer {
+        first_name: "Alice",
+        last_name: "Williams",
+        email: "alice.w@email.com",
+        phone: "+1-555-0104",
+        customer_type: "VIP",
+    },
+    DemoCustomer {
+        first_name: "Charlie",
+        last_name: "Brown",
+        email: "charlie.b@email.com",
+        phone: "+1-555-0105",
+        customer_type: "Retail",
+    },
+];
+
+const SUPPLIERS: &[DemoSupplier] = &[
+    DemoSupplier {
+        company: "ABC Building Supply",
+        contact: "Tom Anderson",
+        email: "tom@abcsupply.com",
+        phone: "+1-555-0201",
+    },
+    DemoSupplier {
+        company: "XYZ Hardware Co",
+        contact: "Lisa Chen",
+        email: "lisa@xyzhardware.com",
+        phone: "+1-555-0202",
+    },
+    DemoSupplier {
+        company: "Quality Materials Inc",
+        contact: "Mark Davis",
+        email: "mark@quality.com",
+        phone: "+1-555-0203",
+    },
+];
@@ -0,0 +1,254 @@
+//! Neighbourhood grocery: dairy, bakery, produce and pantry staples, untaxed.
+
+use super::{DemoCustomer, DemoProduct, DemoSupplier, DemoTemplate, DemoUser};
+
+pub struct Grocery;
+
+impl DemoTemplate for Grocery {
+    fn code(&self) -> &'static str {
+        "grocery"
+    }
+
+    fn number_prefix(&self) -> &'static str {
+        "GRC"
+    }
+
+    fn users(&self) -> &'static [DemoUser] {
+        USERS
+    }
+
+    fn categories(&self) -> &'static [&'static str] {
+        CATEGORIES
+    }
+
+    fn products(&self) -> &'static [DemoProduct] {
+        PRODUCTS
+    }
+
+    fn customers(&self) -> &'static [DemoCustomer] {
+        CUSTOMERS
+    }
+
+    fn suppliers(&self) -> &'static [DemoSupplier] {
+        SUPPLIERS
+    }
+}
+
+const USERS: &[DemoUser] = &[
+    DemoUser {
+        username: "grocery.manager",
+        email: "manager@cornermarket.example.org",
+        password: "Manager123",
+        first_name: "Ines",
+        last_name: "Walker",
+        role: "Manager",
+    },
+    DemoUser {
+        username: "grocery.cashier",
+        email: "cashier@cornermarket.example.org",
+        password: "Cashier123",
+        first_name: "Sam",
+        last_name: "Ortiz",
+        role: "Cashier",
+    },
+];
+
+const CATEGORIES: &[&str] = &["Dairy", "Bakery", "Produce", "Pantry", "Beverages"];
+
+const PRODUCTS: &[DemoProduct] = &[
+    DemoProduct {
+        sku: "GRC-MLK-1L",
+        barcode: "5012345678901",
+        name: "Whole Milk 1L",
+        description: "",
+        category: "Dairy",
+        brand: "Meadow Farms",
+        unit: "each",
+        cost: 0.65,
+        price: 1.29,
+        wholesale_price: 1.29,
+        tax_rate: 0.0,
+        reorder_point: 30,
+        stock: 120,
+    },
+    DemoProduct {
+        sku: "GRC-EGG-12",
+        barcode: "5012345678902",
+        name: "Free Range Eggs 12",
+        description: "",
+        category: "Dairy",
+        brand: "Meadow Farms",
+        unit: "dozen",
+        cost: 1.90,
+        price: 3.49,
+        wholesale_price: 3.49,
+        tax_rate: 0.0,
+        reorder_point: 15,
+        stock: 60,
+    },
+    DemoProduct {
+        sku: "GRC-BRD-WHL",
+        barcode: "5012345678903",
+        name: "Wholemeal Bread Loaf",
+        description: "",
+        category: "Bakery",
+        brand: "Stone Oven",
+        unit: "each",
+        cost: 0.95,
+        price: 2.19,
+        wholesale_price: 2.19,
+        tax_rate: 0.0,
+        reorder_point: 10,
+        stock: 40,
+    },
+    DemoProduct {
+        sku: "GRC-BAN-KG",
+        barcode: "5012345678904",
+        name: "Bananas",
+        description: "",
+        category: "Produce",
+        brand: "Fresh Direct",
+        unit: "kg",
+        cost: 0.55,
+        price: 1.19,
+        wholesale_price: 1.19,
+        tax_rate: 0.0,
+        reorder_point: 22,
+        stock: 90,
+    },
+    DemoProduct {
+        sku: "GRC-APL-KG",
+        barcode: "5012345678905",
+        name: "Gala Apples",
+        description: "",
+        category: "Produce",
+        brand: "Fresh Direct",
+        unit: "kg",
+        cost: 0.90,
+        price: 2.49,
+        wholesale_price: 2.49,
+        tax_rate: 0.0,
+        reorder_point: 17,
+        stock: 70,
+    },
+    DemoProduct {
+        sku: "GRC-RCE-2KG",
+        barcode: "5012345678906",
+        name: "Long Grain Rice 2kg",
+        description: "",
+        category: "Pantry",
+        brand: "Golden Field",
+        unit: "each",
+        cost: 1.60,
+        price: 3.99,
+        wholesale_price: 3.99,
+        tax_rate: 0.0,
+        reorder_point: 12,
+        stock: 50,
+    },
+    DemoProduct {
+        sku: "GRC-PST-500",
+        barcode: "5012345678907",
+        name: "Penne Pasta 500g",
+        description: "",
+        category: "Pantry",
+        brand: "Golden Field",
+        unit: "each",
+        cost: 0.45,
+        price: 1.09,
+        wholesale_price: 1.09,
+        tax_rate: 0.0,
+        reorder_point: 20,
+        stock: 80,
+    },
+    DemoProduct {
+        sku: "GRC-OIL-1L",
+        barcode: "5012345678908",
+        name: "Sunflower Oil 1L",
+        description: "",
+        category: "Pantry",
+        brand: "Golden Field",
+        unit: "each",
+        cost: 1.30,
+        price: 2.79,
+        wholesale_price: 2.79,
+        tax_rate: 0.0,
+        reorder_point: 11,
+        stock: 45,
+    },
+    DemoProduct {
+        sku: "GRC-COF-250",
+        barcode: "5012345678909",
+        name: "Ground Coffee 250g",
+        description: "",
+        category: "Beverages",
+        brand: "Roastery",
+        unit: "each",
+        cost: 2.40,
+        price: 5.49,
+        wholesale_price: 5.49,
+        tax_rate: 0.0,
+        reorder_point: 7,
+        stock: 30,
+    },
+    DemoProduct {
+        sku: "GRC-WTR-6PK",
+        barcode: "5012345678910",
+        name: "Still Water 6 x 1.5L",
+        description: "",
+        category: "Beverages",
+        brand: "Clearspring",
+        unit: "pack",
+        cost: 1.10,
+        price: 2.99,
+        wholesale_price: 2.99,
+        tax_rate: 0.0,
+        reorder_point: 13,
+        stock: 55,
+    },
+];
+
+const CUSTOMERS: &[DemoCustomer] = &[
+    DemoCustomer {
+        first_name: "Ruth",
+        last_name: "Okafor",
+        email: "ruth.okafor@example.org",
+        phone: "+1-555-0501",
+        customer_type: "Retail",
+    },
+    DemoCustomer {
+        first_name: "Tom",
+        last_name: "Becker",
+        email: "tom.becker@example.org",
+        phone: "+1-555-0502",
+        customer_type: "Retail",
+    },
+    DemoCustomer {
+        first_name: "Greenleaf Cafe",
+        last_name: "Accounts",
+        email: "accounts@greenleaf.example.org",
+        phone: "+1-555-0503",
+        customer_type: "Wholesale",
+    },
+];
+
+const SUPPLIERS: &[DemoSupplier] = &[
+    DemoSupplier {
+        company: "Meadow Farms Dairy",
+        contact: "Ellen Price",
+        email: "orders@meadowfarms.example.org",
+        phone: "+1-555-0601",
+    },
+    DemoSupplier {
+        company: "Fresh Direct Produce",
+        contact: "Raj Singh",
+        email: "dispatch@freshdirect.example.org",
+        phone: "+1-555-0602",
+    },
+    DemoSupplier {
+        company: "Golden Field Foods",
+        contact: "Ana Costa",
+        email: "sales@goldenfield.example.org",
+        phone: "+1-555-0603",
+    },
+];
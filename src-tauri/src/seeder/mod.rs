@@ -0,0 +1,806 @@
+//! Demo data for trying the app out or training staff. Nothing is seeded at startup; a
+//! store seeds an industry template on purpose. Every row a template adds is tagged with
+//! the template's code in `demo_template`, so seeding the same template again adds
+//! nothing and `reset_demo_data` removes exactly what was seeded.
+
+mod building_materials;
+mod grocery;
+mod retail;
+mod salon;
+
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use bcrypt::{hash, DEFAULT_COST};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+
+pub use building_materials::BuildingMaterials;
+pub use grocery::Grocery;
+pub use retail::Retail;
+pub use salon::Salon;
+
+pub struct DemoUser {
+    pub username: &'static str,
+    pub email: &'static str,
+    pub password: &'static str,
+    pub first_name: &'static str,
+    pub last_name: &'static str,
+    pub role: &'static str,
+}
+
+pub struct DemoProduct {
+    pub sku: &'static str,
+    pub barcode: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub category: &'static str,
+    pub brand: &'static str,
+    pub unit: &'static str,
+    pub cost: f64,
+    pub price: f64,
+    pub wholesale_price: f64,
+    /// Sales tax percentage; 0 marks the product untaxed
+    pub tax_rate: f64,
+    pub reorder_point: i32,
+    /// Units on hand when seeded
+    pub stock: i32,
+}
+
+pub struct DemoService {
+    pub name: &'static str,
+    pub category: &'static str,
+    pub duration_minutes: i32,
+    pub price: f64,
+}
+
+pub struct DemoCustomer {
+    pub first_name: &'static str,
+    pub last_name: &'static str,
+    pub email: &'static str,
+    pub phone: &'static str,
+    pub customer_type: &'static str,
+}
+
+pub struct DemoSupplier {
+    pub company: &'static str,
+    pub contact: &'static str,
+    pub email: &'static str,
+    pub phone: &'static str,
+}
+
+/// One industry's demo store. The seeding engine inserts whatever a template provides,
+/// so a new industry is a new implementation plus a variant of `Industry`.
+pub trait DemoTemplate: Sync {
+    /// Stored in `demo_template` on every seeded row
+    fn code(&self) -> &'static str;
+    /// Prefix of the customer, supplier and sale numbers, so templates never collide
+    fn number_prefix(&self) -> &'static str;
+    fn users(&self) -> &'static [DemoUser];
+    fn categories(&self) -> &'static [&'static str];
+    fn products(&self) -> &'static [DemoProduct];
+    fn services(&self) -> &'static [DemoService] {
+        &[]
+    }
+    fn customers(&self) -> &'static [DemoCustomer];
+    fn suppliers(&self) -> &'static [DemoSupplier];
+    /// How many sales the demo shift rings up
+    fn sample_sales(&self) -> i32 {
+        12
+    }
+    /// Payment methods the sample sales take in turn
+    fn payment_methods(&self) -> &'static [&'static str] {
+        &["cash", "card"]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Industry {
+    BuildingMaterials,
+    Retail,
+    Grocery,
+    Salon,
+}
+
+impl Industry {
+    pub fn template(self) -> &'static dyn DemoTemplate {
+        match self {
+            Industry::BuildingMaterials => &BuildingMaterials,
+            Industry::Retail => &Retail,
+            Industry::Grocery => &Grocery,
+            Industry::Salon => &Salon,
+        }
+    }
+}
+
+/// Rows a seed added to, or a reset removed from, one table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableRowCount {
+    pub table: String,
+    pub rows: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeedSummary {
+    pub template: Industry,
+    /// Tables the seed added rows to
+    pub tables: Vec<TableRowCount>,
+}
+
+/// Tables a template may add to, reported in the summary
+const SEEDED_TABLES: [&str; 12] = [
+    "users",
+    "categories",
+    "products",
+    "inventory",
+    "services",
+    "customers",
+    "suppliers",
+    "shifts",
+    "cash_drawer_transactions",
+    "sales",
+    "sale_items",
+    "inventory_movements",
+];
+
+/// Opening float of the demo shift
+const DEMO_OPENING_AMOUNT: f64 = 200.0;
+
+async fn row_counts(pool: &SqlitePool) -> AppResult<Vec<i64>> {
+    let mut counts = Vec::with_capacity(SEEDED_TABLES.len());
+    for table in SEEDED_TABLES {
+        // Table names come from the constant above
+        let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(pool)
+            .await?;
+        counts.push(count);
+    }
+    Ok(counts)
+}
+
+async fn seed_users(conn: &mut SqliteConnection, template: &dyn DemoTemplate) -> AppResult<()> {
+    for user in template.users() {
+        let exists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE username = ?1")
+            .bind(user.username)
+            .fetch_one(&mut *conn)
+            .await?;
+        if exists > 0 {
+            continue;
+        }
+        let password_hash = hash(user.password, DEFAULT_COST).map_err(|e| AppError::Internal {
+            message: e.to_string(),
+        })?;
+        sqlx::query(
+            "INSERT OR IGNORE INTO users (username, email, password_hash, first_name, last_name, role,
+                                          is_active, demo_template)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7)",
+        )
+        .bind(user.username)
+        .bind(user.email)
+        .bind(&password_hash)
+        .bind(user.first_name)
+        .bind(user.last_name)
+        .bind(user.role)
+        .bind(template.code())
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Categories, products with their stock, and services. A category the store already
+/// has keeps its own (untagged) row, so a reset never removes it.
+async fn seed_catalog(conn: &mut SqliteConnection, template: &dyn DemoTemplate) -> AppResult<()> {
+    let code = template.code();
+    for category in template.categories() {
+        sqlx::query(
+            "INSERT OR IGNORE INTO categories (name, is_active, demo_template) VALUES (?1, 1, ?2)",
+        )
+        .bind(category)
+        .bind(code)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for product in template.products() {
+        let inserted = sqlx::query(
+            "INSERT OR IGNORE INTO products (sku, barcode, name, description, category, brand, unit_of_measure,
+                                             cost_price, selling_price, wholesale_price, tax_rate, is_taxable,
+                                             weight, reorder_point, is_active, demo_template)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, 0, ?13, 1, ?14)",
+        )
+        .bind(product.sku)
+        .bind(product.barcode)
+        .bind(product.name)
+        .bind(product.description)
+        .bind(product.category)
+        .bind(product.brand)
+        .bind(product.unit)
+        .bind(product.cost)
+        .bind(product.price)
+        .bind(product.wholesale_price)
+        .bind(product.tax_rate)
+        .bind(product.tax_rate > 0.0)
+        .bind(product.reorder_point)
+        .bind(code)
+        .execute(&mut *conn)
+        .await?;
+        if inserted.rows_affected() == 0 {
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO inventory (product_id, current_stock, minimum_stock, maximum_stock,
+                                    reserved_stock, available_stock)
+             VALUES (?1, ?2, ?3, ?4, 0, ?2)",
+        )
+        .bind(inserted.last_insert_rowid())
+        .bind(product.stock)
+        .bind(product.stock / 4)
+        .bind(product.stock * 3)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    // Services have no natural key, so the name within the template stands in for one
+    for service in template.services() {
+        sqlx::query(
+            "INSERT INTO services (name, duration_minutes, price, category, is_active, demo_template)
+             SELECT ?1, ?2, ?3, ?4, 1, ?5
+             WHERE NOT EXISTS (SELECT 1 FROM services WHERE name = ?1 AND demo_template = ?5)",
+        )
+        .bind(service.name)
+        .bind(service.duration_minutes)
+        .bind(service.price)
+        .bind(service.category)
+        .bind(code)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn seed_contacts(conn: &mut SqliteConnection, template: &dyn DemoTemplate) -> AppResult<()> {
+    let prefix = template.number_prefix();
+    for (i, customer) in template.customers().iter().enumerate() {
+        sqlx::query(
+            "INSERT OR IGNORE INTO customers (customer_number, first_name, last_name, email, phone, customer_type,
+                                              status, demo_template)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'Active', ?7)",
+        )
+        .bind(format!("{}-C{:04}", prefix, i + 1))
+        .bind(customer.first_name)
+        .bind(customer.last_name)
+        .bind(customer.email)
+        .bind(customer.phone)
+        .bind(customer.customer_type)
+        .bind(template.code())
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for (i, supplier) in template.suppliers().iter().enumerate() {
+        sqlx::query(
+            "INSERT OR IGNORE INTO suppliers (supplier_number, company_name, contact_name, email, phone, is_active,
+                                              demo_template)
+             VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6)",
+        )
+        .bind(format!("{}-S{:04}", prefix, i + 1))
+        .bind(supplier.company)
+        .bind(supplier.contact)
+        .bind(supplier.email)
+        .bind(supplier.phone)
+        .bind(template.code())
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Ring up the template's sample sales during one shift yesterday: each sale takes its
+/// items out of stock with a 'sale' movement, and the shift is opened and closed with its
+/// cash drawer transactions, so the movement history and shift reports have a day to
+/// show. Sale numbers are fixed, so a second run finds the first sale and adds nothing.
+async fn seed_sales(conn: &mut SqliteConnection, template: &dyn DemoTemplate) -> AppResult<()> {
+    let code = template.code();
+    let prefix = template.number_prefix();
+    let seeded: Option<i64> = sqlx::query_scalar("SELECT id FROM sales WHERE sale_number = ?1")
+        .bind(format!("SALE-{}-0001", prefix))
+        .fetch_optional(&mut *conn)
+        .await?;
+    if seeded.is_some() {
+        return Ok(());
+    }
+
+    // The template's first user works the shift, or whoever the store has if it was
+    // already taken by a real account
+    let cashier_id: i64 = sqlx::query_scalar(
+        "SELECT id FROM users ORDER BY (username = ?1) DESC, (role = 'Admin') DESC, id LIMIT 1",
+    )
+    .bind(template.users().first().map_or("", |user| user.username))
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::not_found("User"))?;
+
+    // Only the template's own catalog is sold, whatever else the database holds
+    let product_ids: Vec<i64> = sqlx::query_scalar(
+        "SELECT i.product_id FROM inventory i JOIN products p ON p.id = i.product_id
+         WHERE p.demo_template = ?1 ORDER BY i.product_id",
+    )
+    .bind(code)
+    .fetch_all(&mut *conn)
+    .await?;
+    if product_ids.is_empty() {
+        return Ok(());
+    }
+
+    // Yesterday from 08:00 to 18:00
+    let shift_id = sqlx::query(
+        "INSERT INTO shifts (user_id, location_id, start_time, opening_amount, status, notes, demo_template)
+         VALUES (?1, 1, datetime('now', 'start of day', '-1 day', '+8 hours'), ?2, 'open', 'Demo day', ?3)",
+    )
+    .bind(cashier_id)
+    .bind(DEMO_OPENING_AMOUNT)
+    .bind(code)
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    sqlx::query(
+        "INSERT INTO cash_drawer_transactions (shift_id, transaction_type, amount, reason, user_id, created_at)
+         SELECT id, 'opening', opening_amount, 'Opening float', user_id, start_time FROM shifts WHERE id = ?1",
+    )
+    .bind(shift_id)
+    .execute(&mut *conn)
+    .await?;
+
+    let payment_methods = template.payment_methods();
+    let customers = template.customers();
+    let mut total_sales = Money::ZERO;
+    let mut cash_sales = Money::ZERO;
+
+    // One sale every half hour from 08:15
+    for i in 0..template.sample_sales() {
+        let sale_number = format!("SALE-{}-{:04}", prefix, i + 1);
+        let sold_at = format!("+{} minutes", 8 * 60 + 15 + i * 30);
+        let payment_method = payment_methods[i as usize % payment_methods.len()];
+        let customer_name = customers
+            .get(i as usize % customers.len().max(1))
+            .map(|customer| format!("{} {}", customer.first_name, customer.last_name));
+
+        let sale_id = sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, tax_amount, discount_amount, total_amount,
+             payment_method, payment_status, cashier_id, customer_name, shift_id, demo_template, created_at)
+             VALUES (?1, 0, 0, 0, 0, ?2, 'completed', ?3, ?4, ?5, ?6, datetime('now', 'start of day', '-1 day', ?7))",
+        )
+        .bind(&sale_number)
+        .bind(payment_method)
+        .bind(cashier_id)
+        .bind(customer_name)
+        .bind(shift_id)
+        .bind(code)
+        .bind(&sold_at)
+        .execute(&mut *conn)
+        .await?
+        .last_insert_rowid();
+
+        let mut subtotal = Money::ZERO;
+        let mut tax_amount = Money::ZERO;
+
+        // 1-5 lines per sale
+        for j in 0..1 + i % 5 {
+            let product_id = product_ids[(i * 3 + j) as usize % product_ids.len()];
+            let product: (f64, f64, bool, f64, i32) = sqlx::query_as(
+                "SELECT p.cost_price, p.selling_price, p.is_taxable, p.tax_rate, i.current_stock
+                 FROM products p JOIN inventory i ON i.product_id = p.id
+                 WHERE p.id = ?1",
+            )
+            .bind(product_id)
+            .fetch_one(&mut *conn)
+            .await?;
+            let (cost_price, selling_price, is_taxable, tax_rate, previous_stock) = product;
+
+            // 1-5 units, never more than is on the shelf
+            let quantity = (1 + j).min(previous_stock);
+            if quantity <= 0 {
+                continue;
+            }
+            let new_stock = previous_stock - quantity;
+            // Amounts are stored to the cent, as a real sale stores them
+            let line_total = Money::from_major(selling_price).times(quantity);
+            let item_tax = if is_taxable {
+                line_total.percent(tax_rate)
+            } else {
+                Money::ZERO
+            };
+            subtotal += line_total;
+            tax_amount += item_tax;
+
+            sqlx::query(
+                "INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, discount_amount,
+                 line_total, tax_amount, cost_price, post_sale_stock)
+                 VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6, ?7, ?8)",
+            )
+            .bind(sale_id)
+            .bind(product_id)
+            .bind(quantity)
+            .bind(selling_price)
+            .bind(line_total)
+            .bind(item_tax)
+            .bind(cost_price)
+            .bind(new_stock)
+            .execute(&mut *conn)
+            .await?;
+
+            sqlx::query(
+                "UPDATE inventory SET current_stock = ?1, available_stock = available_stock - ?2,
+                 last_updated = CURRENT_TIMESTAMP
+                 WHERE product_id = ?3",
+            )
+            .bind(new_stock)
+            .bind(quantity)
+            .bind(product_id)
+            .execute(&mut *conn)
+            .await?;
+
+            sqlx::query(
+                "INSERT INTO inventory_movements (product_id, movement_type, quantity_change, previous_stock,
+                 new_stock, reference_id, reference_type, notes, user_id, unit_cost, created_at)
+                 VALUES (?1, 'sale', ?2, ?3, ?4, ?5, 'sale', 'Sale transaction', ?6, ?7,
+                         datetime('now', 'start of day', '-1 day', ?8))",
+            )
+            .bind(product_id)
+            .bind(-quantity)
+            .bind(previous_stock)
+            .bind(new_stock)
+            .bind(sale_id)
+            .bind(cashier_id)
+            .bind(cost_price)
+            .bind(&sold_at)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        let total_amount = subtotal + tax_amount;
+        sqlx::query(
+            "UPDATE sales SET subtotal = ?1, tax_amount = ?2, total_amount = ?3 WHERE id = ?4",
+        )
+        .bind(subtotal)
+        .bind(tax_amount)
+        .bind(total_amount)
+        .bind(sale_id)
+        .execute(&mut *conn)
+        .await?;
+
+        total_sales += total_amount;
+        if payment_method == "cash" {
+            cash_sales += total_amount;
+        }
+    }
+
+    // The drawer is counted at close and holds the float plus the cash taken
+    let closing_amount = Money::from_major(DEMO_OPENING_AMOUNT) + cash_sales;
+    sqlx::query(
+        "UPDATE shifts SET end_time = datetime('now', 'start of day', '-1 day', '+18 hours'),
+         closing_amount = ?1, total_sales = ?2, cash_sales = ?3, card_sales = ?4, status = 'closed'
+         WHERE id = ?5",
+    )
+    .bind(closing_amount)
+    .bind(total_sales)
+    .bind(cash_sales)
+    .bind(total_sales - cash_sales)
+    .bind(shift_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO cash_drawer_transactions (shift_id, transaction_type, amount, reason, user_id, created_at)
+         SELECT id, 'closing', closing_amount, 'End of day count', user_id, end_time FROM shifts WHERE id = ?1",
+    )
+    .bind(shift_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Seed `industry`'s template in one transaction and report what it added. Seeding a
+/// template twice adds nothing. Refused once the store has rung up a real sale, so demo
+/// stock and shifts never mix with the books.
+pub async fn seed_demo_data(pool: &SqlitePool, industry: Industry) -> AppResult<SeedSummary> {
+    let real_sales: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM sales WHERE demo_template IS NULL")
+            .fetch_one(pool)
+            .await?;
+    if real_sales > 0 {
+        return Err(AppError::Conflict {
+            message: "The store already has real sales; demo data can only be seeded before trading starts"
+                .to_string(),
+        });
+    }
+
+    let template = industry.template();
+    let before = row_counts(pool).await?;
+    let mut tx = pool.begin().await?;
+    seed_users(&mut tx, template).await?;
+    seed_catalog(&mut tx, template).await?;
+    seed_contacts(&mut tx, template).await?;
+    seed_sales(&mut tx, template).await?;
+    tx.commit().await?;
+    let after = row_counts(pool).await?;
+
+    let tables = SEEDED_TABLES
+        .iter()
+        .zip(before.iter().zip(after.iter()))
+        .filter(|(_, (before, after))| after > before)
+        .map(|(table, (before, after))| TableRowCount {
+            table: table.to_string(),
+            rows: after - before,
+        })
+        .collect();
+    Ok(SeedSummary {
+        template: industry,
+        tables,
+    })
+}
+
+/// Statements removing every seeded row, children before parents
+const RESET_STATEMENTS: [(&str, &str); 13] = [
+    (
+        "sale_items",
+        "DELETE FROM sale_items WHERE sale_id IN (SELECT id FROM sales WHERE demo_template IS NOT NULL)",
+    ),
+    (
+        "inventory_movements",
+        "DELETE FROM inventory_movements
+         WHERE product_id IN (SELECT id FROM products WHERE demo_template IS NOT NULL)",
+    ),
+    (
+        "cash_drawer_transactions",
+        "DELETE FROM cash_drawer_transactions
+         WHERE shift_id IN (SELECT id FROM shifts WHERE demo_template IS NOT NULL)",
+    ),
+    ("sales", "DELETE FROM sales WHERE demo_template IS NOT NULL"),
+    ("shifts", "DELETE FROM shifts WHERE demo_template IS NOT NULL"),
+    (
+        "inventory",
+        "DELETE FROM inventory WHERE product_id IN (SELECT id FROM products WHERE demo_template IS NOT NULL)",
+    ),
+    (
+        "reorder_policies",
+        "DELETE FROM reorder_policies
+         WHERE product_id IN (SELECT id FROM products WHERE demo_template IS NOT NULL)",
+    ),
+    ("products", "DELETE FROM products WHERE demo_template IS NOT NULL"),
+    ("categories", "DELETE FROM categories WHERE demo_template IS NOT NULL"),
+    ("services", "DELETE FROM services WHERE demo_template IS NOT NULL"),
+    ("customers", "DELETE FROM customers WHERE demo_template IS NOT NULL"),
+    ("suppliers", "DELETE FROM suppliers WHERE demo_template IS NOT NULL"),
+    ("users", "DELETE FROM users WHERE demo_template IS NOT NULL"),
+];
+
+/// Remove every row any template seeded, leaving the store's own data alone, and report
+/// what was removed. Refused while a real sale includes a demo product.
+pub async fn reset_demo_data(pool: &SqlitePool) -> AppResult<Vec<TableRowCount>> {
+    let mixed: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sale_items si
+         JOIN sales s ON s.id = si.sale_id
+         JOIN products p ON p.id = si.product_id
+         WHERE s.demo_template IS NULL AND p.demo_template IS NOT NULL",
+    )
+    .fetch_one(pool)
+    .await?;
+    if mixed > 0 {
+        return Err(AppError::Conflict {
+            message:
+                "Real sales include demo products; void or keep them before resetting demo data"
+                    .to_string(),
+        });
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut removed = Vec::new();
+    for (table, statement) in RESET_STATEMENTS {
+        let rows = sqlx::query(statement)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected() as i64;
+        if rows > 0 {
+            removed.push(TableRowCount {
+                table: table.to_string(),
+                rows,
+            });
+        }
+    }
+    tx.commit().await?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_product, insert_test_user, test_pool};
+
+    fn rows(tables: &[TableRowCount], table: &str) -> i64 {
+        tables
+            .iter()
+            .find(|count| count.table == table)
+            .map_or(0, |count| count.rows)
+    }
+
+    #[tokio::test]
+    async fn test_templates_seed_back_to_back_and_reset() {
+        let pool = test_pool().await;
+
+        let grocery = seed_demo_data(&pool, Industry::Grocery).await.unwrap();
+        assert_eq!(
+            rows(&grocery.tables, "products"),
+            Grocery.products().len() as i64
+        );
+        assert_eq!(
+            rows(&grocery.tables, "inventory"),
+            Grocery.products().len() as i64
+        );
+        assert_eq!(
+            rows(&grocery.tables, "sales"),
+            Grocery.sample_sales() as i64
+        );
+        assert_eq!(rows(&grocery.tables, "shifts"), 1);
+
+        let salon = seed_demo_data(&pool, Industry::Salon).await.unwrap();
+        assert_eq!(
+            rows(&salon.tables, "services"),
+            Salon.services().len() as i64
+        );
+        assert_eq!(rows(&salon.tables, "users"), Salon.users().len() as i64);
+        assert_eq!(rows(&salon.tables, "shifts"), 1);
+
+        // Each template only sold its own catalog
+        let crossed: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sale_items si
+             JOIN sales s ON s.id = si.sale_id JOIN products p ON p.id = si.product_id
+             WHERE s.demo_template != p.demo_template",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(crossed, 0);
+
+        // Seeding either again adds nothing
+        for industry in [Industry::Grocery, Industry::Salon] {
+            let again = seed_demo_data(&pool, industry).await.unwrap();
+            assert!(again.tables.is_empty(), "{:?}", again.tables);
+        }
+
+        // The store's own rows survive a reset
+        let own_user = insert_test_user(&pool, "owner").await;
+        let own_product = insert_test_product(&pool, "OWN-1", 5.0, 10).await;
+        let removed = reset_demo_data(&pool).await.unwrap();
+        assert_eq!(
+            rows(&removed, "products"),
+            (Grocery.products().len() + Salon.products().len()) as i64
+        );
+        assert_eq!(
+            rows(&removed, "sales"),
+            (Grocery.sample_sales() + Salon.sample_sales()) as i64
+        );
+        for table in [
+            "sales",
+            "shifts",
+            "services",
+            "customers",
+            "suppliers",
+            "cash_drawer_transactions",
+        ] {
+            let left: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            assert_eq!(left, 0, "{} left behind", table);
+        }
+        let products: Vec<i64> = sqlx::query_scalar("SELECT id FROM products")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(products, vec![own_product]);
+        let owner: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE id = ?1")
+            .bind(own_user)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(owner, 1);
+
+        // And the template can be seeded afresh
+        let reseeded = seed_demo_data(&pool, Industry::Grocery).await.unwrap();
+        assert_eq!(
+            rows(&reseeded.tables, "sales"),
+            Grocery.sample_sales() as i64
+        );
+    }
+
+    #[tokio::test]
+    async fn test_seeding_is_refused_once_the_store_has_real_sales() {
+        let pool = test_pool().await;
+        let cashier = insert_test_user(&pool, "cashier").await;
+        sqlx::query(
+            "INSERT INTO sales (sale_number, subtotal, tax_amount, discount_amount, total_amount,
+             payment_method, payment_status, cashier_id)
+             VALUES ('SALE-1', 10, 0, 0, 10, 'cash', 'completed', ?1)",
+        )
+        .bind(cashier)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let err = seed_demo_data(&pool, Industry::Retail).await.unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+        let products: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM products")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(products, 0);
+    }
+
+    #[tokio::test]
+    async fn test_building_materials_sales_leave_a_consistent_day() {
+        let pool = test_pool().await;
+        seed_demo_data(&pool, Industry::BuildingMaterials)
+            .await
+            .unwrap();
+
+        // Every line sold took its quantity out of stock with a movement
+        let (lines, sold): (i64, i64) =
+            sqlx::query_as("SELECT COUNT(*), SUM(quantity) FROM sale_items")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let (movements, moved): (i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*), -SUM(quantity_change) FROM inventory_movements WHERE movement_type = 'sale'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((movements, moved), (lines, sold));
+        let mismatched: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM inventory_movements m
+             JOIN inventory i ON i.product_id = m.product_id
+             WHERE m.id = (SELECT MAX(id) FROM inventory_movements WHERE product_id = m.product_id)
+               AND m.new_stock != i.current_stock",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(mismatched, 0);
+
+        // All sales fall in one closed shift whose totals and drawer add up
+        let (shifts, status, total_sales, closing): (i64, String, f64, f64) = sqlx::query_as(
+            "SELECT COUNT(*), MAX(status), SUM(total_sales), SUM(closing_amount) FROM shifts",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((shifts, status.as_str()), (1, "closed"));
+        let (unshifted, sales_total): (i64, f64) =
+            sqlx::query_as("SELECT SUM(shift_id IS NULL), SUM(total_amount) FROM sales")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(unshifted, 0);
+        assert!((sales_total - total_sales).abs() < 0.005);
+        let drawer: Vec<(String, f64)> = sqlx::query_as(
+            "SELECT transaction_type, amount FROM cash_drawer_transactions ORDER BY id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(drawer.len(), 2);
+        assert_eq!(drawer[0].0, "opening");
+        assert_eq!(drawer[1], ("closing".to_string(), closing));
+    }
+
+    #[test]
+    fn test_industry_names() {
+        let industry: Industry = serde_json::from_str("\"building_materials\"").unwrap();
+        assert_eq!(industry, Industry::BuildingMaterials);
+        assert_eq!(Industry::Salon.template().code(), "salon");
+        assert!(serde_json::from_str::<Industry>("\"pharmacy\"").is_err());
+    }
+}
@@ -0,0 +1,249 @@
+//! Clothing and gift shop: apparel, accessories, homeware and small electronics,
+//! taxed at 8%.
+
+use super::{DemoCustomer, DemoProduct, DemoSupplier, DemoTemplate, DemoUser};
+
+pub struct Retail;
+
+impl DemoTemplate for Retail {
+    fn code(&self) -> &'static str {
+        "retail"
+    }
+
+    fn number_prefix(&self) -> &'static str {
+        "RTL"
+    }
+
+    fn users(&self) -> &'static [DemoUser] {
+        USERS
+    }
+
+    fn categories(&self) -> &'static [&'static str] {
+        CATEGORIES
+    }
+
+    fn products(&self) -> &'static [DemoProduct] {
+        PRODUCTS
+    }
+
+    fn customers(&self) -> &'static [DemoCustomer] {
+        CUSTOMERS
+    }
+
+    fn suppliers(&self) -> &'static [DemoSupplier] {
+        SUPPLIERS
+    }
+}
+
+const USERS: &[DemoUser] = &[
+    DemoUser {
+        username: "retail.manager",
+        email: "manager@threadandco.example.com",
+        password: "Manager123",
+        first_name: "Nadia",
+        last_name: "Brooks",
+        role: "Manager",
+    },
+    DemoUser {
+        username: "retail.cashier",
+        email: "cashier@threadandco.example.com",
+        password: "Cashier123",
+        first_name: "Theo",
+        last_name: "Marsh",
+        role: "Cashier",
+    },
+];
+
+const CATEGORIES: &[&str] = &["Apparel", "Accessories", "Home", "Electronics"];
+
+const PRODUCTS: &[DemoProduct] = &[
+    DemoProduct {
+        sku: "RTL-TEE-BLK-M",
+        barcode: "4006381333901",
+        name: "Cotton T-Shirt Black M",
+        description: "",
+        category: "Apparel",
+        brand: "Basics Co",
+        unit: "each",
+        cost: 4.20,
+        price: 12.99,
+        wholesale_price: 12.99,
+        tax_rate: 8.0,
+        reorder_point: 15,
+        stock: 60,
+    },
+    DemoProduct {
+        sku: "RTL-TEE-WHT-L",
+        barcode: "4006381333902",
+        name: "Cotton T-Shirt White L",
+        description: "",
+        category: "Apparel",
+        brand: "Basics Co",
+        unit: "each",
+        cost: 4.20,
+        price: 12.99,
+        wholesale_price: 12.99,
+        tax_rate: 8.0,
+        reorder_point: 11,
+        stock: 45,
+    },
+    DemoProduct {
+        sku: "RTL-JNS-32",
+        barcode: "4006381333903",
+        name: "Slim Jeans 32",
+        description: "",
+        category: "Apparel",
+        brand: "Denimworks",
+        unit: "each",
+        cost: 14.50,
+        price: 39.99,
+        wholesale_price: 39.99,
+        tax_rate: 8.0,
+        reorder_point: 6,
+        stock: 25,
+    },
+    DemoProduct {
+        sku: "RTL-SCK-3PK",
+        barcode: "4006381333904",
+        name: "Ankle Socks 3-Pack",
+        description: "",
+        category: "Apparel",
+        brand: "Basics Co",
+        unit: "pack",
+        cost: 2.10,
+        price: 7.49,
+        wholesale_price: 7.49,
+        tax_rate: 8.0,
+        reorder_point: 20,
+        stock: 80,
+    },
+    DemoProduct {
+        sku: "RTL-CAP-NVY",
+        barcode: "4006381333905",
+        name: "Baseball Cap Navy",
+        description: "",
+        category: "Accessories",
+        brand: "Headline",
+        unit: "each",
+        cost: 3.80,
+        price: 14.99,
+        wholesale_price: 14.99,
+        tax_rate: 8.0,
+        reorder_point: 7,
+        stock: 30,
+    },
+    DemoProduct {
+        sku: "RTL-BAG-TOTE",
+        barcode: "4006381333906",
+        name: "Canvas Tote Bag",
+        description: "",
+        category: "Accessories",
+        brand: "Carryall",
+        unit: "each",
+        cost: 2.90,
+        price: 9.99,
+        wholesale_price: 9.99,
+        tax_rate: 8.0,
+        reorder_point: 10,
+        stock: 40,
+    },
+    DemoProduct {
+        sku: "RTL-MUG-CER",
+        barcode: "4006381333907",
+        name: "Ceramic Mug 350ml",
+        description: "",
+        category: "Home",
+        brand: "Kiln & Co",
+        unit: "each",
+        cost: 1.75,
+        price: 8.99,
+        wholesale_price: 8.99,
+        tax_rate: 8.0,
+        reorder_point: 12,
+        stock: 50,
+    },
+    DemoProduct {
+        sku: "RTL-CND-VAN",
+        barcode: "4006381333908",
+        name: "Vanilla Scented Candle",
+        description: "",
+        category: "Home",
+        brand: "Glow",
+        unit: "each",
+        cost: 3.10,
+        price: 11.99,
+        wholesale_price: 11.99,
+        tax_rate: 8.0,
+        reorder_point: 8,
+        stock: 35,
+    },
+    DemoProduct {
+        sku: "RTL-USB-C1M",
+        barcode: "4006381333909",
+        name: "USB-C Cable 1m",
+        description: "",
+        category: "Electronics",
+        brand: "Linkup",
+        unit: "each",
+        cost: 1.40,
+        price: 9.49,
+        wholesale_price: 9.49,
+        tax_rate: 8.0,
+        reorder_point: 25,
+        stock: 100,
+    },
+    DemoProduct {
+        sku: "RTL-EAR-BT",
+        barcode: "4006381333910",
+        name: "Bluetooth Earbuds",
+        description: "",
+        category: "Electronics",
+        brand: "Linkup",
+        unit: "each",
+        cost: 11.00,
+        price: 29.99,
+        wholesale_price: 29.99,
+        tax_rate: 8.0,
+        reorder_point: 5,
+        stock: 20,
+    },
+];
+
+const CUSTOMERS: &[DemoCustomer] = &[
+    DemoCustomer {
+        first_name: "Maya",
+        last_name: "Patel",
+        email: "maya.patel@example.com",
+        phone: "+1-555-0301",
+        customer_type: "Retail",
+    },
+    DemoCustomer {
+        first_name: "Liam",
+        last_name: "Nguyen",
+        email: "liam.nguyen@example.com",
+        phone: "+1-555-0302",
+        customer_type: "VIP",
+    },
+    DemoCustomer {
+        first_name: "Sofia",
+        last_name: "Garcia",
+        email: "sofia.garcia@example.com",
+        phone: "+1-555-0303",
+        customer_type: "Retail",
+    },
+];
+
+const SUPPLIERS: &[DemoSupplier] = &[
+    DemoSupplier {
+        company: "Basics Co Wholesale",
+        contact: "Dana Reed",
+        email: "orders@basicsco.example.com",
+        phone: "+1-555-0401",
+    },
+    DemoSupplier {
+        company: "Linkup Electronics",
+        contact: "Omar Haddad",
+        email: "sales@linkup.example.com",
+        phone: "+1-555-0402",
+    },
+];
@@ -0,0 +1,231 @@
+//! Hair and beauty salon: appointment services plus the hair care products sold at
+//! the desk, taxed at 7%.
+
+use super::{DemoCustomer, DemoProduct, DemoService, DemoSupplier, DemoTemplate, DemoUser};
+
+pub struct Salon;
+
+impl DemoTemplate for Salon {
+    fn code(&self) -> &'static str {
+        "salon"
+    }
+
+    fn number_prefix(&self) -> &'static str {
+        "SLN"
+    }
+
+    fn users(&self) -> &'static [DemoUser] {
+        USERS
+    }
+
+    fn categories(&self) -> &'static [&'static str] {
+        CATEGORIES
+    }
+
+    fn products(&self) -> &'static [DemoProduct] {
+        PRODUCTS
+    }
+
+    fn services(&self) -> &'static [DemoService] {
+        SERVICES
+    }
+
+    fn customers(&self) -> &'static [DemoCustomer] {
+        CUSTOMERS
+    }
+
+    fn suppliers(&self) -> &'static [DemoSupplier] {
+        SUPPLIERS
+    }
+}
+
+const USERS: &[DemoUser] = &[
+    DemoUser {
+        username: "salon.manager",
+        email: "manager@studiolumen.example.net",
+        password: "Manager123",
+        first_name: "Clara",
+        last_name: "Reyes",
+        role: "Manager",
+    },
+    DemoUser {
+        username: "salon.stylist",
+        email: "stylist@studiolumen.example.net",
+        password: "Stylist123",
+        first_name: "Jonah",
+        last_name: "Pike",
+        role: "Cashier",
+    },
+];
+
+const CATEGORIES: &[&str] = &[
+    "Hair Care",
+    "Styling",
+    "Tools",
+    "Hair Services",
+    "Colour",
+    "Nails",
+];
+
+const PRODUCTS: &[DemoProduct] = &[
+    DemoProduct {
+        sku: "SLN-SHP-300",
+        barcode: "7311250000011",
+        name: "Repair Shampoo 300ml",
+        description: "",
+        category: "Hair Care",
+        brand: "Lumen Pro",
+        unit: "each",
+        cost: 5.40,
+        price: 16.00,
+        wholesale_price: 16.00,
+        tax_rate: 7.0,
+        reorder_point: 6,
+        stock: 24,
+    },
+    DemoProduct {
+        sku: "SLN-CND-300",
+        barcode: "7311250000012",
+        name: "Repair Conditioner 300ml",
+        description: "",
+        category: "Hair Care",
+        brand: "Lumen Pro",
+        unit: "each",
+        cost: 5.60,
+        price: 17.00,
+        wholesale_price: 17.00,
+        tax_rate: 7.0,
+        reorder_point: 6,
+        stock: 24,
+    },
+    DemoProduct {
+        sku: "SLN-MSK-200",
+        barcode: "7311250000013",
+        name: "Hydrating Hair Mask 200ml",
+        description: "",
+        category: "Hair Care",
+        brand: "Lumen Pro",
+        unit: "each",
+        cost: 7.20,
+        price: 22.00,
+        wholesale_price: 22.00,
+        tax_rate: 7.0,
+        reorder_point: 3,
+        stock: 12,
+    },
+    DemoProduct {
+        sku: "SLN-OIL-50",
+        barcode: "7311250000014",
+        name: "Argan Hair Oil 50ml",
+        description: "",
+        category: "Styling",
+        brand: "Oro",
+        unit: "each",
+        cost: 6.10,
+        price: 19.50,
+        wholesale_price: 19.50,
+        tax_rate: 7.0,
+        reorder_point: 4,
+        stock: 16,
+    },
+    DemoProduct {
+        sku: "SLN-SPR-250",
+        barcode: "7311250000015",
+        name: "Flexible Hold Hairspray 250ml",
+        description: "",
+        category: "Styling",
+        brand: "Oro",
+        unit: "each",
+        cost: 3.90,
+        price: 12.50,
+        wholesale_price: 12.50,
+        tax_rate: 7.0,
+        reorder_point: 5,
+        stock: 20,
+    },
+    DemoProduct {
+        sku: "SLN-BRS-PAD",
+        barcode: "7311250000016",
+        name: "Paddle Brush",
+        description: "",
+        category: "Tools",
+        brand: "Kent & Vale",
+        unit: "each",
+        cost: 4.80,
+        price: 14.00,
+        wholesale_price: 14.00,
+        tax_rate: 7.0,
+        reorder_point: 2,
+        stock: 10,
+    },
+];
+
+const SERVICES: &[DemoService] = &[
+    DemoService {
+        name: "Women's Cut & Style",
+        category: "Hair Services",
+        duration_minutes: 60,
+        price: 55.00,
+    },
+    DemoService {
+        name: "Men's Cut",
+        category: "Hair Services",
+        duration_minutes: 30,
+        price: 28.00,
+    },
+    DemoService {
+        name: "Full Colour",
+        category: "Colour",
+        duration_minutes: 120,
+        price: 95.00,
+    },
+    DemoService {
+        name: "Highlights",
+        category: "Colour",
+        duration_minutes: 150,
+        price: 120.00,
+    },
+    DemoService {
+        name: "Blow Dry",
+        category: "Hair Services",
+        duration_minutes: 45,
+        price: 35.00,
+    },
+    DemoService {
+        name: "Manicure",
+        category: "Nails",
+        duration_minutes: 40,
+        price: 30.00,
+    },
+];
+
+const CUSTOMERS: &[DemoCustomer] = &[
+    DemoCustomer {
+        first_name: "Hannah",
+        last_name: "Lee",
+        email: "hannah.lee@example.net",
+        phone: "+1-555-0701",
+        customer_type: "Retail",
+    },
+    DemoCustomer {
+        first_name: "Marcus",
+        last_name: "Bell",
+        email: "marcus.bell@example.net",
+        phone: "+1-555-0702",
+        customer_type: "VIP",
+    },
+    DemoCustomer {
+        first_name: "Priya",
+        last_name: "Shah",
+        email: "priya.shah@example.net",
+        phone: "+1-555-0703",
+        customer_type: "Retail",
+    },
+];
+
+const SUPPLIERS: &[DemoSupplier] = &[DemoSupplier {
+    company: "Lumen Professional",
+    contact: "Elise Moreau",
+    email: "trade@lumenpro.example.net",
+    phone: "+1-555-0801",
+}];
@@ -1,4 +1,6 @@
 use crate::error::{AppError, AppResult};
+use crate::margins::DEFAULT_LOCATION_ID;
+use crate::tenancy::DEFAULT_ORGANIZATION_ID;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -15,32 +17,59 @@ pub struct Session {
     pub created_at: u64,
     pub expires_at: u64,
     pub last_activity: u64,
+    /// Created by PIN login; such sessions are short-lived and cannot run admin actions
+    #[serde(default)]
+    pub pin_session: bool,
+    /// Store or branch the user is working at, chosen at login
+    #[serde(default = "default_location")]
+    pub location_id: i64,
+    /// Organization whose data the session reads and writes
+    #[serde(default = "default_organization")]
+    pub organization_id: i64,
+}
+
+fn default_location() -> i64 {
+    DEFAULT_LOCATION_ID
+}
+
+fn default_organization() -> i64 {
+    DEFAULT_ORGANIZATION_ID
 }
 
 /// Session manager with in-memory storage
 pub struct SessionManager {
     sessions: Arc<Mutex<HashMap<String, Session>>>,
-    failed_attempts: Arc<Mutex<HashMap<String, (u32, u64)>>>, // username -> (count, last_attempt_time)
     session_timeout: Duration,
-    max_failed_attempts: u32,
-    lockout_duration: Duration,
+    pin_session_timeout: Duration,
 }
 
 impl SessionManager {
     pub fn new() -> Self {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
-            failed_attempts: Arc::new(Mutex::new(HashMap::new())),
             session_timeout: Duration::from_secs(24 * 60 * 60), // 24 hours
-            max_failed_attempts: 5,
-            lockout_duration: Duration::from_secs(15 * 60), // 15 minutes
+            pin_session_timeout: Duration::from_secs(30 * 60),  // 30 minutes
         }
     }
 
     /// Create a new session
     pub fn create_session(&self, user_id: i64, username: String, role: String) -> String {
+        self.insert_session(user_id, username, role, false)
+    }
+
+    /// Create a short-lived session for a user who signed in with their register PIN
+    pub fn create_pin_session(&self, user_id: i64, username: String, role: String) -> String {
+        self.insert_session(user_id, username, role, true)
+    }
+
+    fn insert_session(&self, user_id: i64, username: String, role: String, pin_session: bool) -> String {
         let token = Uuid::new_v4().to_string();
         let now = current_timestamp();
+        let timeout = if pin_session {
+            self.pin_session_timeout
+        } else {
+            self.session_timeout
+        };
 
         let session = Session {
             token: token.clone(),
@@ -48,8 +77,11 @@ impl SessionManager {
             username,
             role,
             created_at: now,
-            expires_at: now + self.session_timeout.as_secs(),
+            expires_at: now + timeout.as_secs(),
             last_activity: now,
+            pin_session,
+            location_id: DEFAULT_LOCATION_ID,
+            organization_id: DEFAULT_ORGANIZATION_ID,
         };
 
         let mut sessions = self.sessions.lock().unwrap();
@@ -58,11 +90,6 @@ impl SessionManager {
         token
     }
 
-    /// Check if a user is locked out due to failed attempts
-    pub fn is_locked(&self, username: &str) -> bool {
-        self.check_rate_limit(username).is_err()
-    }
-
     /// Remove a session (alias for invalidate_session for compatibility)
     pub fn remove_session(&self, token: &str) {
         let mut sessions = self.sessions.lock().unwrap();
@@ -113,60 +140,74 @@ impl SessionManager {
         Ok(session.clone())
     }
 
-    /// Invalidate session (logout)
-    pub fn invalidate_session(&self, token: &str) -> AppResult<()> {
-        let mut sessions = self.sessions.lock().unwrap();
-        sessions.remove(token);
-        Ok(())
+    /// Validate session and ensure the user holds one of the allowed roles
+    pub fn require_role(&self, token: &str, allowed_roles: &[&str]) -> AppResult<Session> {
+        let session = self.validate_session(token)?;
+
+        if allowed_roles.contains(&session.role.as_str()) {
+            Ok(session)
+        } else {
+            Err(AppError::permission_denied())
+        }
     }
 
-    /// Clean up expired sessions
-    pub fn cleanup_expired_sessions(&self) {
-        let mut sessions = self.sessions.lock().unwrap();
-        let now = current_timestamp();
+    /// Validate the session and reject PIN sessions, whatever the user's role
+    pub fn require_password_session(&self, token: &str) -> AppResult<Session> {
+        let session = self.validate_session(token)?;
 
-        sessions.retain(|_, session| now <= session.expires_at);
+        if session.pin_session {
+            Err(AppError::password_login_required())
+        } else {
+            Ok(session)
+        }
     }
 
-    /// Check if username is locked out due to failed attempts
-    pub fn check_rate_limit(&self, username: &str) -> AppResult<()> {
-        let mut failed_attempts = self.failed_attempts.lock().unwrap();
-        let now = current_timestamp();
+    /// Like `require_role`, but also rejects PIN sessions (for administrative actions)
+    pub fn require_full_session(&self, token: &str, allowed_roles: &[&str]) -> AppResult<Session> {
+        let session = self.require_role(token, allowed_roles)?;
 
-        if let Some((count, last_attempt)) = failed_attempts.get(username) {
-            let time_since_last = now.saturating_sub(*last_attempt);
+        if session.pin_session {
+            Err(AppError::password_login_required())
+        } else {
+            Ok(session)
+        }
+    }
 
-            // Reset if lockout duration has passed
-            if time_since_last > self.lockout_duration.as_secs() {
-                failed_attempts.remove(username);
-                return Ok(());
-            }
+    /// Switch the location a session works at
+    pub fn set_location(&self, token: &str, location_id: i64) -> AppResult<Session> {
+        self.validate_session(token)?;
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions
+            .get_mut(token)
+            .ok_or_else(AppError::session_invalid)?;
+        session.location_id = location_id;
+        Ok(session.clone())
+    }
 
-            // Check if locked out
-            if *count >= self.max_failed_attempts {
-                return Err(AppError::rate_limit_exceeded());
-            }
-        }
+    /// Switch the organization a session works in
+    pub fn set_organization(&self, token: &str, organization_id: i64) -> AppResult<Session> {
+        self.validate_session(token)?;
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions
+            .get_mut(token)
+            .ok_or_else(AppError::session_invalid)?;
+        session.organization_id = organization_id;
+        Ok(session.clone())
+    }
 
+    /// Invalidate session (logout)
+    pub fn invalidate_session(&self, token: &str) -> AppResult<()> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.remove(token);
         Ok(())
     }
 
-    /// Record failed login attempt
-    pub fn record_failed_attempt(&self, username: &str) {
-        let mut failed_attempts = self.failed_attempts.lock().unwrap();
+    /// Clean up expired sessions
+    pub fn cleanup_expired_sessions(&self) {
+        let mut sessions = self.sessions.lock().unwrap();
         let now = current_timestamp();
 
-        let entry = failed_attempts
-            .entry(username.to_string())
-            .or_insert((0, now));
-        entry.0 += 1;
-        entry.1 = now;
-    }
-
-    /// Clear failed attempts for username (on successful login)
-    pub fn clear_failed_attempts(&self, username: &str) {
-        let mut failed_attempts = self.failed_attempts.lock().unwrap();
-        failed_attempts.remove(username);
+        sessions.retain(|_, session| now <= session.expires_at);
     }
 
     /// Get all active sessions for a user
@@ -186,6 +227,17 @@ impl SessionManager {
         Ok(())
     }
 
+    /// All unexpired sessions
+    pub fn active_sessions(&self) -> Vec<Session> {
+        let sessions = self.sessions.lock().unwrap();
+        let now = current_timestamp();
+        sessions
+            .values()
+            .filter(|s| now <= s.expires_at)
+            .cloned()
+            .collect()
+    }
+
     /// Get session count
     pub fn session_count(&self) -> usize {
         let sessions = self.sessions.lock().unwrap();
@@ -224,13 +276,22 @@ mod tests {
     }
 
     #[test]
-    fn test_rate_limiting() {
+    fn test_require_role() {
         let manager = SessionManager::new();
+        let token = manager.create_session(1, "testuser".to_string(), "Cashier".to_string());
+        assert!(manager.require_role(&token, &["Admin", "Cashier"]).is_ok());
+        assert!(manager.require_role(&token, &["Admin"]).is_err());
+    }
 
-        for _ in 0..5 {
-            manager.record_failed_attempt("testuser");
-        }
+    #[test]
+    fn test_pin_session_restricted_from_admin_actions() {
+        let manager = SessionManager::new();
+        let pin_token = manager.create_pin_session(1, "admin".to_string(), "Admin".to_string());
+        let full_token = manager.create_session(1, "admin".to_string(), "Admin".to_string());
 
-        assert!(manager.check_rate_limit("testuser").is_err());
+        assert!(manager.require_role(&pin_token, &["Admin"]).is_ok());
+        let err = manager.require_full_session(&pin_token, &["Admin"]).unwrap_err();
+        assert_eq!(err.code(), "PERMISSION_DENIED");
+        assert!(manager.require_full_session(&full_token, &["Admin"]).is_ok());
     }
 }
@@ -0,0 +1,255 @@
+//! App settings: typed values stored as JSON in `app_settings`, each for an organization,
+//! a location or a user. Reads go through an in-memory cache per database that a write
+//! to the same key and scope clears.
+
+use crate::error::{AppError, AppResult};
+use crate::margins::DEFAULT_LOCATION_ID;
+use crate::promotions::PromotionPrecedence;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+/// What a setting applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "scope", content = "scope_id", rename_all = "lowercase")]
+pub enum SettingScope {
+    Organization(i64),
+    Location(i64),
+    User(i64),
+}
+
+impl SettingScope {
+    pub fn parse(scope: &str, scope_id: i64) -> AppResult<Self> {
+        match scope {
+            "organization" => Ok(SettingScope::Organization(scope_id)),
+            "location" => Ok(SettingScope::Location(scope_id)),
+            "user" => Ok(SettingScope::User(scope_id)),
+            other => Err(AppError::validation(
+                "scope",
+                &format!("Unknown scope '{}', expected organization, location or user", other),
+            )),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SettingScope::Organization(_) => "organization",
+            SettingScope::Location(_) => "location",
+            SettingScope::User(_) => "user",
+        }
+    }
+
+    fn id(self) -> i64 {
+        match self {
+            SettingScope::Organization(id) | SettingScope::Location(id) | SettingScope::User(id) => id,
+        }
+    }
+}
+
+/// Scope of the store's own settings, such as its currency and default tax rate
+pub const STORE_SETTINGS: SettingScope = SettingScope::Location(DEFAULT_LOCATION_ID);
+
+/// Settings the app knows about; `validate_setting` checks the values each accepts. The
+/// currency and cash rounding are organization settings, see `organization_settings`.
+pub const KNOWN_SETTINGS: &[&str] = &[
+    "backup_schedule",
+    "commission_basis",
+    "demo_mode",
+    "fraud_discount_percent",
+    "fraud_no_sale_limit",
+    "fraud_reprint_rate_percent",
+    "fraud_void_rate_percent",
+    "lockout_max_failed_attempts",
+    "lockout_minutes",
+    "min_margin_percent",
+    "promotion_precedence",
+    "tax_rate",
+];
+
+/// Fail unless `value` is acceptable for the known setting `key`
+pub fn validate_setting(key: &str, value: &Value) -> AppResult<()> {
+    if !KNOWN_SETTINGS.contains(&key) {
+        return Err(AppError::validation(key, &format!("Unknown setting '{}'", key)));
+    }
+    let percent = |value: &Value| value.as_f64().filter(|v| (0.0..=100.0).contains(v)).is_some();
+    let valid = match key {
+        "demo_mode" => value.is_boolean(),
+        "backup_schedule" => matches!(value.as_str(), Some("off" | "daily" | "weekly")),
+        "commission_basis" => matches!(value.as_str(), Some("revenue" | "profit")),
+        "fraud_no_sale_limit" => value.is_u64(),
+        "lockout_max_failed_attempts" | "lockout_minutes" => {
+            value.as_u64().and_then(|v| u32::try_from(v).ok()).is_some_and(|v| v > 0)
+        }
+        "promotion_precedence" => serde_json::from_value::<PromotionPrecedence>(value.clone()).is_ok(),
+        "fraud_discount_percent"
+        | "fraud_reprint_rate_percent"
+        | "fraud_void_rate_percent"
+        | "min_margin_percent"
+        | "tax_rate" => percent(value),
+        _ => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::validation(key, &format!("Invalid value for {}: {}", key, value)))
+    }
+}
+
+type CacheKey = (SettingScope, String);
+
+/// Cached values of one database. The pool's options are held weakly so a new pool that
+/// happens to reuse the address never sees another database's values.
+struct DatabaseCache {
+    options: Weak<SqliteConnectOptions>,
+    values: HashMap<CacheKey, Option<Value>>,
+}
+
+lazy_static::lazy_static! {
+    static ref SETTINGS_CACHE: Mutex<HashMap<usize, DatabaseCache>> = Mutex::new(HashMap::new());
+}
+
+fn with_cache<R>(pool: &SqlitePool, f: impl FnOnce(&mut HashMap<CacheKey, Option<Value>>) -> R) -> R {
+    let options = pool.connect_options();
+    let address = Arc::as_ptr(&options) as usize;
+    let mut caches = SETTINGS_CACHE.lock().unwrap();
+    caches.retain(|_, cache| cache.options.strong_count() > 0);
+    let cache = caches.entry(address).or_insert_with(|| DatabaseCache {
+        options: Arc::downgrade(&options),
+        values: HashMap::new(),
+    });
+    f(&mut cache.values)
+}
+
+/// Raw JSON of a setting, `None` when it is not set for the scope
+pub async fn get_setting_value(pool: &SqlitePool, key: &str, scope: SettingScope) -> AppResult<Option<Value>> {
+    let cache_key = (scope, key.to_string());
+    if let Some(value) = with_cache(pool, |values| values.get(&cache_key).cloned()) {
+        return Ok(value);
+    }
+
+    let stored: Option<String> = sqlx::query_scalar(
+        "SELECT value FROM app_settings WHERE key = ?1 AND scope = ?2 AND scope_id = ?3",
+    )
+    .bind(key)
+    .bind(scope.name())
+    .bind(scope.id())
+    .fetch_optional(pool)
+    .await?;
+    let value = stored
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|e| AppError::Internal {
+            message: format!("Setting {} is not valid JSON: {}", key, e),
+        })?;
+    with_cache(pool, |values| values.insert(cache_key, value.clone()));
+    Ok(value)
+}
+
+/// A setting as `T`, `None` when it is not set for the scope
+pub async fn get_setting<T: DeserializeOwned>(
+    pool: &SqlitePool,
+    key: &str,
+    scope: SettingScope,
+) -> AppResult<Option<T>> {
+    get_setting_value(pool, key, scope)
+        .await?
+        .map(|value| {
+            serde_json::from_value(value).map_err(|e| AppError::Internal {
+                message: format!("Setting {} has an unexpected type: {}", key, e),
+            })
+        })
+        .transpose()
+}
+
+/// Validate and store a known setting, replacing the cached value
+pub async fn set_setting<T: Serialize>(
+    pool: &SqlitePool,
+    key: &str,
+    scope: SettingScope,
+    value: &T,
+) -> AppResult<()> {
+    let value = serde_json::to_value(value).map_err(|e| AppError::Internal {
+        message: format!("Failed to serialize setting {}: {}", key, e),
+    })?;
+    validate_setting(key, &value)?;
+    sqlx::query(
+        "INSERT INTO app_settings (key, value, scope, scope_id) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(key, scope, scope_id) DO UPDATE SET
+            value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(key)
+    .bind(value.to_string())
+    .bind(scope.name())
+    .bind(scope.id())
+    .execute(pool)
+    .await?;
+    with_cache(pool, |values| values.remove(&(scope, key.to_string())));
+    Ok(())
+}
+
+/// Every setting stored for the scope
+pub async fn settings_for_scope(pool: &SqlitePool, scope: SettingScope) -> AppResult<HashMap<String, Value>> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT key, value FROM app_settings WHERE scope = ?1 AND scope_id = ?2 ORDER BY key",
+    )
+    .bind(scope.name())
+    .bind(scope.id())
+    .fetch_all(pool)
+    .await?;
+    rows.into_iter()
+        .map(|(key, json)| {
+            let value = serde_json::from_str(&json).map_err(|e| AppError::Internal {
+                message: format!("Setting {} is not valid JSON: {}", key, e),
+            })?;
+            Ok((key, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_pool;
+
+    #[tokio::test]
+    async fn test_settings_round_trip_with_their_types() {
+        let pool = test_pool().await;
+        let till = SettingScope::Location(1);
+        set_setting(&pool, "fraud_no_sale_limit", till, &3).await.unwrap();
+        set_setting(&pool, "min_margin_percent", till, &12.5).await.unwrap();
+
+        assert_eq!(get_setting::<u64>(&pool, "fraud_no_sale_limit", till).await.unwrap(), Some(3));
+        assert_eq!(get_setting::<f64>(&pool, "min_margin_percent", till).await.unwrap(), Some(12.5));
+        assert_eq!(get_setting::<u64>(&pool, "fraud_no_sale_limit", SettingScope::Location(2)).await.unwrap(), None);
+        assert!(get_setting::<String>(&pool, "fraud_no_sale_limit", till).await.is_err());
+        // Currency and cash rounding are organization settings now
+        assert!(set_setting(&pool, "currency", till, &"XAF").await.is_err());
+
+        let err = set_setting(&pool, "min_margin_percent", till, &"high").await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        assert!(set_setting(&pool, "made_up", till, &1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_writes_replace_cached_values() {
+        let pool = test_pool().await;
+        let store = SettingScope::Location(1);
+        set_setting(&pool, "commission_basis", store, &"revenue").await.unwrap();
+        assert_eq!(get_setting::<String>(&pool, "commission_basis", store).await.unwrap().as_deref(), Some("revenue"));
+
+        // A write behind the cache's back is not seen until the setting is written again
+        sqlx::query("UPDATE app_settings SET value = '\"profit\"' WHERE key = 'commission_basis'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(get_setting::<String>(&pool, "commission_basis", store).await.unwrap().as_deref(), Some("revenue"));
+
+        set_setting(&pool, "backup_schedule", store, &"weekly").await.unwrap();
+        set_setting(&pool, "commission_basis", store, &"profit").await.unwrap();
+        assert_eq!(get_setting::<String>(&pool, "commission_basis", store).await.unwrap().as_deref(), Some("profit"));
+    }
+}
@@ -0,0 +1,620 @@
+use crate::archive::{bind_json, insert_row, table_columns};
+use crate::error::{AppError, AppResult};
+use crate::sync_outbox::{enqueue_change, entity_type_for_table, has_pending_change, SyncOperation};
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashSet;
+
+/// Inventory stock columns are merged as deltas instead of being overwritten
+const INVENTORY_TABLE: &str = "inventory";
+const INVENTORY_STOCK_COLUMNS: &[&str] = &[
+    "id",
+    "product_id",
+    "current_stock",
+    "available_stock",
+    "synced_stock",
+    "last_updated",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictSide {
+    Local,
+    Remote,
+}
+
+impl ConflictSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictSide::Local => "local",
+            ConflictSide::Remote => "remote",
+        }
+    }
+}
+
+/// What applying one inbound record did to the local row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplyOutcome {
+    Inserted,
+    Updated,
+    Unchanged,
+    /// The local row was newer; a conflict was recorded
+    KeptLocal,
+    /// Both sides changed inventory stock; the deltas were added together
+    Merged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub id: i64,
+    pub entity_type: String,
+    pub entity_id: i64,
+    pub local_payload: Value,
+    pub remote_payload: Value,
+    /// Side that was applied: local, remote or merged
+    pub resolution: String,
+    pub status: String,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+}
+
+/// Accepts SQLite's `YYYY-MM-DD HH:MM:SS` as well as the RFC 3339 timestamps the cloud sends
+fn parse_timestamp(value: &Value) -> Option<NaiveDateTime> {
+    let text = value.as_str()?;
+    DateTime::parse_from_rfc3339(text)
+        .map(|dt| dt.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f"))
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f"))
+        .ok()
+}
+
+/// Compare values the way SQLite stores them: booleans are integers and REAL 10.0 equals 10
+fn same_value(local: &Value, remote: &Value) -> bool {
+    let as_number = |value: &Value| match value {
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        Value::Number(n) => n.as_f64(),
+        _ => None,
+    };
+    match (as_number(local), as_number(remote)) {
+        (Some(a), Some(b)) => (a - b).abs() < 1e-9,
+        _ => local == remote,
+    }
+}
+
+fn record_object<'a>(record: &'a Value, key: &str) -> AppResult<(&'a Map<String, Value>, i64)> {
+    let row = record
+        .as_object()
+        .ok_or_else(|| AppError::validation("record", "Record is not a valid object"))?;
+    let id = row
+        .get(key)
+        .and_then(Value::as_i64)
+        .ok_or_else(|| AppError::validation(key, &format!("Record has no {}", key)))?;
+    Ok((row, id))
+}
+
+async fn known_columns(conn: &mut SqliteConnection, table: &str) -> AppResult<HashSet<String>> {
+    let columns: HashSet<String> = table_columns(conn, table).await?.into_iter().collect();
+    if columns.is_empty() {
+        return Err(AppError::validation(
+            "table",
+            &format!("Unknown table '{}'", table),
+        ));
+    }
+    Ok(columns)
+}
+
+async fn fetch_local_row(
+    conn: &mut SqliteConnection,
+    table: &str,
+    key: &str,
+    id: i64,
+) -> AppResult<Option<Map<String, Value>>> {
+    let pairs: Vec<String> = table_columns(conn, table)
+        .await?
+        .iter()
+        .map(|column| format!("'{}', \"{}\"", column, column))
+        .collect();
+    let query = format!(
+        "SELECT json_object({}) FROM {} WHERE {} = ?1",
+        pairs.join(", "),
+        table,
+        key
+    );
+    let row: Option<String> = sqlx::query_scalar(&query)
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    row.map(|json| match serde_json::from_str(&json) {
+        Ok(Value::Object(map)) => Ok(map),
+        _ => Err(AppError::from(format!("Unreadable {} row {}", table, id))),
+    })
+    .transpose()
+}
+
+/// Overwrite the given columns of an existing row
+async fn update_row(
+    conn: &mut SqliteConnection,
+    table: &str,
+    key: &str,
+    id: i64,
+    row: &Map<String, Value>,
+    columns: &[&String],
+) -> AppResult<()> {
+    if columns.is_empty() {
+        return Ok(());
+    }
+    let assignments: Vec<String> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, name)| format!("\"{}\" = ?{}", name, i + 1))
+        .collect();
+    let sql = format!(
+        "UPDATE {} SET {} WHERE {} = ?{}",
+        table,
+        assignments.join(", "),
+        key,
+        columns.len() + 1
+    );
+
+    let mut query = sqlx::query(&sql);
+    for name in columns {
+        query = bind_json(query, &row[name.as_str()]);
+    }
+    query.bind(id).execute(&mut *conn).await?;
+    Ok(())
+}
+
+async fn record_conflict(
+    conn: &mut SqliteConnection,
+    table: &str,
+    id: i64,
+    local: &Map<String, Value>,
+    remote: &Map<String, Value>,
+    resolution: &str,
+) -> AppResult<()> {
+    sqlx::query(
+        "INSERT INTO sync_conflicts (entity_type, entity_id, local_payload, remote_payload, resolution)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(table)
+    .bind(id)
+    .bind(Value::Object(local.clone()).to_string())
+    .bind(Value::Object(remote.clone()).to_string())
+    .bind(resolution)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Last write wins on `updated_at`. A remote change that overwrites an unpushed local edit,
+/// or a remote change that loses to a newer local row, is recorded as a conflict.
+async fn apply_last_write_wins(
+    conn: &mut SqliteConnection,
+    table: &str,
+    record: &Value,
+) -> AppResult<ApplyOutcome> {
+    let columns = known_columns(conn, table).await?;
+    let (remote, id) = record_object(record, "id")?;
+
+    let Some(local) = fetch_local_row(conn, table, "id", id).await? else {
+        insert_row(conn, table, &columns, remote).await?;
+        return Ok(ApplyOutcome::Inserted);
+    };
+
+    let changed: Vec<&String> = remote
+        .keys()
+        .filter(|name| columns.contains(*name) && name.as_str() != "id")
+        .collect();
+    let differs = changed
+        .iter()
+        .filter(|name| name.as_str() != "updated_at")
+        .any(|name| !same_value(&local[name.as_str()], &remote[name.as_str()]));
+    if !differs {
+        return Ok(ApplyOutcome::Unchanged);
+    }
+
+    // Without timestamps on both sides the cloud copy wins, as it always has
+    let remote_is_newer = match (
+        local.get("updated_at").and_then(parse_timestamp),
+        remote.get("updated_at").and_then(parse_timestamp),
+    ) {
+        (Some(local_at), Some(remote_at)) => remote_at > local_at,
+        _ => true,
+    };
+
+    if !remote_is_newer {
+        record_conflict(conn, table, id, &local, remote, "local").await?;
+        return Ok(ApplyOutcome::KeptLocal);
+    }
+
+    let pending = match entity_type_for_table(table) {
+        Some(entity_type) => has_pending_change(conn, entity_type, id).await?,
+        None => false,
+    };
+    if pending {
+        record_conflict(conn, table, id, &local, remote, "remote").await?;
+    }
+    update_row(conn, table, "id", id, remote, &changed).await?;
+    Ok(ApplyOutcome::Updated)
+}
+
+/// Move a product's stock by `delta`, logging it as a sync adjustment
+async fn adjust_synced_stock(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    previous_stock: i64,
+    delta: i64,
+    notes: &str,
+) -> AppResult<()> {
+    sqlx::query(
+        "UPDATE inventory SET
+            current_stock = current_stock + ?1,
+            available_stock = available_stock + ?1,
+            last_updated = CURRENT_TIMESTAMP
+         WHERE product_id = ?2",
+    )
+    .bind(delta)
+    .bind(product_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO inventory_movements (product_id, movement_type, quantity_change, previous_stock,
+                                         new_stock, reference_type, notes)
+         VALUES (?1, 'adjustment', ?2, ?3, ?4, 'sync', ?5)",
+    )
+    .bind(product_id)
+    .bind(delta)
+    .bind(previous_stock)
+    .bind(previous_stock + delta)
+    .bind(notes)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Inventory rows are matched on product and their stock is merged rather than overwritten:
+/// the change the cloud made since its last count (`synced_stock`) is added to the local
+/// stock as a movement, so concurrent sales and adjustments on both sides all survive.
+/// Other inventory columns follow last write wins on `last_updated`.
+async fn merge_inventory(conn: &mut SqliteConnection, record: &Value) -> AppResult<ApplyOutcome> {
+    let columns = known_columns(conn, INVENTORY_TABLE).await?;
+    let (remote, product_id) = record_object(record, "product_id")?;
+    let remote_stock = remote
+        .get("current_stock")
+        .and_then(Value::as_i64)
+        .ok_or_else(|| AppError::validation("current_stock", "Record has no current_stock"))?;
+
+    let Some(local) = fetch_local_row(conn, INVENTORY_TABLE, "product_id", product_id).await? else {
+        let mut row = remote.clone();
+        row.remove("id");
+        row.insert("synced_stock".to_string(), Value::from(remote_stock));
+        insert_row(conn, INVENTORY_TABLE, &columns, &row).await?;
+        return Ok(ApplyOutcome::Inserted);
+    };
+
+    let local_stock = local
+        .get("current_stock")
+        .and_then(Value::as_i64)
+        .unwrap_or(0);
+    // Never synced: the cloud count is taken as is
+    let base = local
+        .get("synced_stock")
+        .and_then(Value::as_i64)
+        .unwrap_or(local_stock);
+    let local_delta = local_stock - base;
+    let remote_delta = remote_stock - base;
+
+    let mut outcome = ApplyOutcome::Unchanged;
+    if remote_delta != 0 {
+        adjust_synced_stock(conn, product_id, local_stock, remote_delta, "Stock change from cloud sync")
+            .await?;
+        outcome = if local_delta != 0 {
+            record_conflict(conn, INVENTORY_TABLE, product_id, &local, remote, "merged").await?;
+            ApplyOutcome::Merged
+        } else {
+            ApplyOutcome::Updated
+        };
+    }
+
+    let remote_is_newer = match (
+        local.get("last_updated").and_then(parse_timestamp),
+        remote.get("last_updated").and_then(parse_timestamp),
+    ) {
+        (Some(local_at), Some(remote_at)) => remote_at > local_at,
+        _ => true,
+    };
+    if remote_is_newer {
+        let settings: Vec<&String> = remote
+            .keys()
+            .filter(|name| columns.contains(*name) && !INVENTORY_STOCK_COLUMNS.contains(&name.as_str()))
+            .filter(|name| !same_value(&local[name.as_str()], &remote[name.as_str()]))
+            .collect();
+        if !settings.is_empty() {
+            update_row(conn, INVENTORY_TABLE, "product_id", product_id, remote, &settings).await?;
+            if outcome == ApplyOutcome::Unchanged {
+                outcome = ApplyOutcome::Updated;
+            }
+        }
+    }
+
+    sqlx::query("UPDATE inventory SET synced_stock = ?1 WHERE product_id = ?2")
+        .bind(remote_stock)
+        .bind(product_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(outcome)
+}
+
+/// Apply one record received from the cloud to the local table of the same name
+pub async fn apply_remote_record(
+    pool: &SqlitePool,
+    table: &str,
+    record: &Value,
+) -> AppResult<ApplyOutcome> {
+    let mut tx = pool.begin().await?;
+    let outcome = if table == INVENTORY_TABLE {
+        merge_inventory(&mut tx, record).await?
+    } else {
+        apply_last_write_wins(&mut tx, table, record).await?
+    };
+    tx.commit().await?;
+    Ok(outcome)
+}
+
+fn conflict_from_row(row: &sqlx::sqlite::SqliteRow) -> AppResult<SyncConflict> {
+    let parse = |column: &str| -> AppResult<Value> {
+        let text: String = row.try_get(column)?;
+        serde_json::from_str(&text).map_err(|e| AppError::from(e.to_string()))
+    };
+    Ok(SyncConflict {
+        id: row.try_get("id")?,
+        entity_type: row.try_get("entity_type")?,
+        entity_id: row.try_get("entity_id")?,
+        local_payload: parse("local_payload")?,
+        remote_payload: parse("remote_payload")?,
+        resolution: row.try_get("resolution")?,
+        status: row.try_get("status")?,
+        created_at: row.try_get("created_at")?,
+        resolved_at: row.try_get("resolved_at")?,
+    })
+}
+
+const CONFLICT_COLUMNS: &str = "id, entity_type, entity_id, local_payload, remote_payload, resolution,
+    status, COALESCE(created_at, '') as created_at, resolved_at";
+
+/// Conflicts still waiting for review, oldest first
+pub async fn get_open_conflicts(pool: &SqlitePool) -> AppResult<Vec<SyncConflict>> {
+    let query = format!(
+        "SELECT {} FROM sync_conflicts WHERE status = 'open' ORDER BY id",
+        CONFLICT_COLUMNS
+    );
+    let rows = sqlx::query(&query).fetch_all(pool).await?;
+    rows.iter().map(conflict_from_row).collect()
+}
+
+/// Settle a conflict by writing the chosen side back to the local row. Keeping the local
+/// side queues it for the cloud again so the remote copy is corrected too.
+pub async fn resolve_conflict(
+    pool: &SqlitePool,
+    conflict_id: i64,
+    keep: ConflictSide,
+) -> AppResult<SyncConflict> {
+    let mut tx = pool.begin().await?;
+
+    let query = format!("SELECT {} FROM sync_conflicts WHERE id = ?1", CONFLICT_COLUMNS);
+    let row = sqlx::query(&query)
+        .bind(conflict_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("Sync conflict"))?;
+    let conflict = conflict_from_row(&row)?;
+    if conflict.status == "resolved" {
+        return Err(AppError::Conflict {
+            message: "Sync conflict is already resolved".to_string(),
+        });
+    }
+
+    let chosen = match keep {
+        ConflictSide::Local => &conflict.local_payload,
+        ConflictSide::Remote => &conflict.remote_payload,
+    };
+    let chosen = chosen
+        .as_object()
+        .ok_or_else(|| AppError::from("Unreadable sync conflict payload".to_string()))?;
+
+    if conflict.entity_type == INVENTORY_TABLE {
+        let target = chosen.get("current_stock").and_then(Value::as_i64).unwrap_or(0);
+        let current: i64 = sqlx::query_scalar("SELECT current_stock FROM inventory WHERE product_id = ?1")
+            .bind(conflict.entity_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if target != current {
+            adjust_synced_stock(
+                &mut tx,
+                conflict.entity_id,
+                current,
+                target - current,
+                "Sync conflict resolved",
+            )
+            .await?;
+        }
+    } else {
+        let columns = known_columns(&mut tx, &conflict.entity_type).await?;
+        let names: Vec<&String> = chosen
+            .keys()
+            .filter(|name| columns.contains(*name) && name.as_str() != "id")
+            .collect();
+        update_row(&mut tx, &conflict.entity_type, "id", conflict.entity_id, chosen, &names).await?;
+
+        if keep == ConflictSide::Local {
+            if let Some(entity_type) = entity_type_for_table(&conflict.entity_type) {
+                enqueue_change(&mut tx, entity_type, conflict.entity_id, SyncOperation::Update).await?;
+            }
+        }
+    }
+
+    sqlx::query(
+        "UPDATE sync_conflicts
+         SET status = 'resolved', resolution = ?2, resolved_at = CURRENT_TIMESTAMP
+         WHERE id = ?1",
+    )
+    .bind(conflict_id)
+    .bind(keep.as_str())
+    .execute(&mut *tx)
+    .await?;
+
+    let row = sqlx::query(&query)
+        .bind(conflict_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let resolved = conflict_from_row(&row)?;
+    tx.commit().await?;
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{current_stock, insert_test_product, insert_test_user, test_pool};
+    use serde_json::json;
+
+    async fn insert_return(pool: &SqlitePool, user_id: i64, notes: &str, updated_at: &str) -> i64 {
+        sqlx::query(
+            "INSERT INTO comprehensive_returns (return_number, return_type, subtotal, total_amount,
+                                                processed_by, notes, updated_at)
+             VALUES ('SR-1', 'SalesReturn', 10.0, 10.0, ?1, ?2, ?3)",
+        )
+        .bind(user_id)
+        .bind(notes)
+        .bind(updated_at)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+    }
+
+    async fn return_notes(pool: &SqlitePool, id: i64) -> String {
+        sqlx::query_scalar("SELECT notes FROM comprehensive_returns WHERE id = ?1")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    fn remote_return(id: i64, user_id: i64, notes: &str, updated_at: &str) -> Value {
+        json!({
+            "id": id,
+            "return_number": "SR-1",
+            "return_type": "SalesReturn",
+            "subtotal": 10,
+            "total_amount": 10,
+            "processed_by": user_id,
+            "notes": notes,
+            "updated_at": updated_at,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_newer_local_return_is_kept_and_conflict_recorded() {
+        let pool = test_pool().await;
+        let user = insert_test_user(&pool, "clerk").await;
+        let id = insert_return(&pool, user, "edited locally", "2024-05-02 10:00:00").await;
+
+        let remote = remote_return(id, user, "edited in cloud", "2024-05-01T09:00:00Z");
+        let outcome = apply_remote_record(&pool, "comprehensive_returns", &remote)
+            .await
+            .unwrap();
+        assert_eq!(outcome, ApplyOutcome::KeptLocal);
+        assert_eq!(return_notes(&pool, id).await, "edited locally");
+
+        let conflicts = get_open_conflicts(&pool).await.unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].resolution, "local");
+        assert_eq!(conflicts[0].remote_payload["notes"], "edited in cloud");
+
+        let resolved = resolve_conflict(&pool, conflicts[0].id, ConflictSide::Remote)
+            .await
+            .unwrap();
+        assert_eq!(resolved.status, "resolved");
+        assert_eq!(resolved.resolution, "remote");
+        assert_eq!(return_notes(&pool, id).await, "edited in cloud");
+        assert!(get_open_conflicts(&pool).await.unwrap().is_empty());
+
+        let err = resolve_conflict(&pool, conflicts[0].id, ConflictSide::Local)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "CONFLICT");
+    }
+
+    #[tokio::test]
+    async fn test_newer_remote_return_wins() {
+        let pool = test_pool().await;
+        let user = insert_test_user(&pool, "clerk").await;
+        let id = insert_return(&pool, user, "original", "2024-05-01 09:00:00").await;
+
+        let remote = remote_return(id, user, "approved in cloud", "2024-05-02T10:00:00.123Z");
+        let outcome = apply_remote_record(&pool, "comprehensive_returns", &remote)
+            .await
+            .unwrap();
+        assert_eq!(outcome, ApplyOutcome::Updated);
+        assert_eq!(return_notes(&pool, id).await, "approved in cloud");
+        // Nothing local was waiting to be pushed, so this is not a conflict
+        assert!(get_open_conflicts(&pool).await.unwrap().is_empty());
+
+        let outcome = apply_remote_record(&pool, "comprehensive_returns", &remote)
+            .await
+            .unwrap();
+        assert_eq!(outcome, ApplyOutcome::Unchanged);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_stock_changes_are_merged() {
+        let pool = test_pool().await;
+        let product_id = insert_test_product(&pool, "WIDGET", 10.0, 20).await;
+
+        // First sync takes the cloud count as the shared base
+        let outcome = apply_remote_record(
+            &pool,
+            "inventory",
+            &json!({ "product_id": product_id, "current_stock": 20 }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome, ApplyOutcome::Unchanged);
+
+        // Locally 3 are sold while the cloud receives 10
+        sqlx::query("UPDATE inventory SET current_stock = 17 WHERE product_id = ?1")
+            .bind(product_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let remote = json!({ "product_id": product_id, "current_stock": 30 });
+        let outcome = apply_remote_record(&pool, "inventory", &remote).await.unwrap();
+        assert_eq!(outcome, ApplyOutcome::Merged);
+        assert_eq!(current_stock(&pool, product_id).await, 27);
+
+        let movement: (i64, i64, i64) = sqlx::query_as(
+            "SELECT quantity_change, previous_stock, new_stock FROM inventory_movements
+             WHERE product_id = ?1 AND reference_type = 'sync'",
+        )
+        .bind(product_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(movement, (10, 17, 27));
+
+        let conflicts = get_open_conflicts(&pool).await.unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].resolution, "merged");
+
+        // Receiving the same cloud count again changes nothing
+        let outcome = apply_remote_record(&pool, "inventory", &remote).await.unwrap();
+        assert_eq!(outcome, ApplyOutcome::Unchanged);
+        assert_eq!(current_stock(&pool, product_id).await, 27);
+    }
+}
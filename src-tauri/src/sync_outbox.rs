@@ -0,0 +1,337 @@
+use crate::archive::table_columns;
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{Row, SqlitePool};
+
+pub const DEFAULT_SYNC_BATCH_SIZE: i64 = 100;
+const MAX_SYNC_BATCH_SIZE: i64 = 500;
+/// Entries that failed this many times stay in the outbox but are no longer handed out
+pub const MAX_SYNC_ATTEMPTS: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncOperation {
+    Create,
+    Update,
+    Delete,
+}
+
+impl SyncOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncOperation::Create => "create",
+            SyncOperation::Update => "update",
+            SyncOperation::Delete => "delete",
+        }
+    }
+}
+
+/// A synced entity: its table and, for documents, the line items embedded in its payload
+struct SyncEntity {
+    entity_type: &'static str,
+    table: &'static str,
+    /// (child table, foreign key to the parent)
+    items: Option<(&'static str, &'static str)>,
+}
+
+const SYNC_ENTITIES: &[SyncEntity] = &[
+    SyncEntity {
+        entity_type: "sale",
+        table: "sales",
+        items: Some(("sale_items", "sale_id")),
+    },
+    SyncEntity {
+        entity_type: "product",
+        table: "products",
+        items: None,
+    },
+    SyncEntity {
+        entity_type: "customer",
+        table: "customers",
+        items: None,
+    },
+    SyncEntity {
+        entity_type: "return",
+        table: "comprehensive_returns",
+        items: Some(("comprehensive_return_items", "return_id")),
+    },
+];
+
+/// Outbox entity type for a table, if changes to it are queued
+pub fn entity_type_for_table(table: &str) -> Option<&'static str> {
+    SYNC_ENTITIES
+        .iter()
+        .find(|entity| entity.table == table)
+        .map(|entity| entity.entity_type)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncOutboxEntry {
+    pub id: i64,
+    pub entity_type: String,
+    pub entity_id: i64,
+    pub operation: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub created_at: String,
+}
+
+fn json_object_sql(columns: &[String], alias: &str) -> String {
+    let pairs: Vec<String> = columns
+        .iter()
+        .map(|column| format!("'{}', {}.\"{}\"", column, alias, column))
+        .collect();
+    format!("json_object({})", pairs.join(", "))
+}
+
+fn find_entity(entity_type: &str) -> AppResult<&'static SyncEntity> {
+    SYNC_ENTITIES
+        .iter()
+        .find(|entity| entity.entity_type == entity_type)
+        .ok_or_else(|| {
+            AppError::validation(
+                "entity_type",
+                &format!("Unknown sync entity '{}'", entity_type),
+            )
+        })
+}
+
+/// `json_object(...)` expression over the entity's row aliased `t`, items included
+async fn snapshot_sql(conn: &mut SqliteConnection, entity: &SyncEntity) -> AppResult<String> {
+    let mut payload = json_object_sql(&table_columns(conn, entity.table).await?, "t");
+    if let Some((child_table, foreign_key)) = entity.items {
+        let child_columns = table_columns(conn, child_table).await?;
+        // Strip the closing parenthesis to append the items array to the same object
+        payload.pop();
+        payload = format!(
+            "{}, 'items', json(COALESCE((SELECT json_group_array({}) FROM {} c WHERE c.{} = t.id), '[]')))",
+            payload,
+            json_object_sql(&child_columns, "c"),
+            child_table,
+            foreign_key
+        );
+    }
+    Ok(payload)
+}
+
+/// The record as JSON, in the same shape queued in the outbox
+pub async fn entity_snapshot(
+    conn: &mut SqliteConnection,
+    entity_type: &str,
+    entity_id: i64,
+) -> AppResult<Value> {
+    let entity = find_entity(entity_type)?;
+    let query = format!(
+        "SELECT {} FROM {} t WHERE t.id = ?1",
+        snapshot_sql(conn, entity).await?,
+        entity.table
+    );
+    let json: String = sqlx::query_scalar(&query)
+        .bind(entity_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::not_found(entity_type))?;
+    serde_json::from_str(&json).map_err(|e| AppError::from(e.to_string()))
+}
+
+/// Queue a change for the cloud, snapshotting the record as it is now.
+/// Call it inside the transaction that makes the change so both commit or neither does,
+/// and before the row is removed for a hard delete. Records made in demo mode never
+/// leave the machine; nothing is queued for them and 0 is returned.
+pub async fn enqueue_change(
+    conn: &mut SqliteConnection,
+    entity_type: &str,
+    entity_id: i64,
+    operation: SyncOperation,
+) -> AppResult<i64> {
+    let entity = find_entity(entity_type)?;
+    let demo_filter = if table_columns(conn, entity.table).await?.iter().any(|column| column == "is_demo") {
+        format!(" WHERE NOT EXISTS (SELECT 1 FROM {} WHERE id = ?2 AND is_demo = 1)", entity.table)
+    } else {
+        String::new()
+    };
+
+    // A record that is already gone still gets an entry carrying its id
+    let query = format!(
+        "INSERT INTO sync_outbox (entity_type, entity_id, operation, payload)
+         SELECT ?1, ?2, ?3, COALESCE((SELECT {} FROM {} t WHERE t.id = ?2), json_object('id', ?2)){}",
+        snapshot_sql(conn, entity).await?,
+        entity.table,
+        demo_filter
+    );
+    let inserted = sqlx::query(&query)
+        .bind(entity.entity_type)
+        .bind(entity_id)
+        .bind(operation.as_str())
+        .execute(&mut *conn)
+        .await?;
+    Ok(if inserted.rows_affected() == 0 {
+        0
+    } else {
+        inserted.last_insert_rowid()
+    })
+}
+
+/// Whether a local change to the record is still waiting to be pushed
+pub async fn has_pending_change(
+    conn: &mut SqliteConnection,
+    entity_type: &str,
+    entity_id: i64,
+) -> AppResult<bool> {
+    let pending: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sync_outbox
+         WHERE entity_type = ?1 AND entity_id = ?2 AND status != 'synced'",
+    )
+    .bind(entity_type)
+    .bind(entity_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(pending > 0)
+}
+
+/// Oldest unsynced entries, including failed ones that have not used up their retries
+pub async fn get_pending_batch(pool: &SqlitePool, limit: Option<i64>) -> AppResult<Vec<SyncOutboxEntry>> {
+    let limit = limit
+        .unwrap_or(DEFAULT_SYNC_BATCH_SIZE)
+        .clamp(1, MAX_SYNC_BATCH_SIZE);
+
+    let rows = sqlx::query(
+        "SELECT id, entity_type, entity_id, operation, payload, status, attempts, last_error,
+                COALESCE(created_at, '') as created_at
+         FROM sync_outbox
+         WHERE status IN ('pending', 'error') AND attempts < ?1
+         ORDER BY id
+         LIMIT ?2",
+    )
+    .bind(MAX_SYNC_ATTEMPTS)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    let mut entries = Vec::with_capacity(rows.len());
+    for row in rows {
+        let payload: String = row.try_get("payload")?;
+        entries.push(SyncOutboxEntry {
+            id: row.try_get("id")?,
+            entity_type: row.try_get("entity_type")?,
+            entity_id: row.try_get("entity_id")?,
+            operation: row.try_get("operation")?,
+            payload: serde_json::from_str(&payload).map_err(|e| AppError::from(e.to_string()))?,
+            status: row.try_get("status")?,
+            attempts: row.try_get("attempts")?,
+            last_error: row.try_get("last_error")?,
+            created_at: row.try_get("created_at")?,
+        });
+    }
+    Ok(entries)
+}
+
+/// Mark entries as pushed; returns how many were updated
+pub async fn mark_synced(pool: &SqlitePool, ids: &[i64]) -> AppResult<u64> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let query = format!(
+        "UPDATE sync_outbox
+         SET status = 'synced', last_error = NULL,
+             synced_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+         WHERE id IN ({})",
+        placeholders
+    );
+    let mut q = sqlx::query(&query);
+    for id in ids {
+        q = q.bind(id);
+    }
+    Ok(q.execute(pool).await?.rows_affected())
+}
+
+/// Record a failed push; the entry is retried until it reaches `MAX_SYNC_ATTEMPTS`
+pub async fn mark_error(pool: &SqlitePool, id: i64, error: &str) -> AppResult<()> {
+    let result = sqlx::query(
+        "UPDATE sync_outbox
+         SET status = 'error', attempts = attempts + 1, last_error = ?2,
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1",
+    )
+    .bind(id)
+    .bind(error)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Sync outbox entry"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_test_product, test_pool};
+
+    #[tokio::test]
+    async fn test_batch_skips_synced_and_exhausted_entries() {
+        let pool = test_pool().await;
+        let product_id = insert_test_product(&pool, "SYNC-1", 10.0, 5).await;
+
+        let mut conn = pool.acquire().await.unwrap();
+        let mut ids = Vec::new();
+        for operation in [SyncOperation::Create, SyncOperation::Update, SyncOperation::Update] {
+            ids.push(
+                enqueue_change(&mut conn, "product", product_id, operation)
+                    .await
+                    .unwrap(),
+            );
+        }
+        drop(conn);
+
+        let batch = get_pending_batch(&pool, None).await.unwrap();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch[0].operation, "create");
+        assert_eq!(batch[0].payload["sku"], "SYNC-1");
+
+        assert_eq!(mark_synced(&pool, &ids[..1]).await.unwrap(), 1);
+        mark_error(&pool, ids[1], "timeout").await.unwrap();
+        sqlx::query("UPDATE sync_outbox SET attempts = ?1 WHERE id = ?2")
+            .bind(MAX_SYNC_ATTEMPTS)
+            .bind(ids[2])
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let batch = get_pending_batch(&pool, Some(10)).await.unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].id, ids[1]);
+        assert_eq!(batch[0].status, "error");
+        assert_eq!(batch[0].attempts, 1);
+        assert_eq!(batch[0].last_error.as_deref(), Some("timeout"));
+
+        let err = mark_error(&pool, 9999, "timeout").await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_deleted_record_is_queued_with_its_id() {
+        let pool = test_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        enqueue_change(&mut conn, "customer", 4242, SyncOperation::Delete)
+            .await
+            .unwrap();
+        let err = enqueue_change(&mut conn, "invoice", 1, SyncOperation::Create)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        drop(conn);
+
+        let batch = get_pending_batch(&pool, None).await.unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].payload, serde_json::json!({ "id": 4242 }));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::currency::Currency;
+use crate::error::AppResult;
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnection;
+use sqlx::FromRow;
+
+pub const TAX_RULE_TARGETS: [&str; 3] = ["all", "category", "product"];
+
+/// Name recorded for tax from `products.tax_rate` when no tax rules are configured
+pub const LEGACY_TAX_NAME: &str = "Sales tax";
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TaxRule {
+    pub id: i64,
+    pub name: String,
+    /// Percentage, e.g. `8.25`
+    pub rate: f64,
+    /// `all`, `category` or `product`
+    pub applies_to: String,
+    pub category: Option<String>,
+    pub product_id: Option<i64>,
+    /// Charged on the line plus the taxes of the rules before it, rather than on the line alone
+    pub is_compound: bool,
+    /// Rules apply in ascending priority
+    pub priority: i32,
+    /// Comma-separated product categories this rule does not tax
+    pub exempt_categories: Option<String>,
+    pub is_active: bool,
+    pub created_at: String,
+}
+
+/// One tax charged on a sale line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineTax {
+    /// `None` for the legacy per-product rate
+    pub tax_rule_id: Option<i64>,
+    pub name: String,
+    pub rate: f64,
+    pub taxable_amount: Money,
+    pub tax_amount: Money,
+}
+
+/// What the tax engine needs to know about a line
+#[derive(Debug, Clone)]
+pub struct TaxableLine<'a> {
+    pub product_id: i64,
+    pub category: Option<&'a str>,
+    pub is_taxable: bool,
+    /// `products.tax_rate`, used only while no rules are configured
+    pub product_tax_rate: f64,
+    pub line_total: Money,
+}
+
+impl TaxRule {
+    pub fn applies_to_line(&self, line: &TaxableLine) -> bool {
+        let exempt = match (line.category, self.exempt_categories.as_deref()) {
+            (Some(category), Some(exempt)) => exempt
+                .split(',')
+                .any(|c| c.trim().eq_ignore_ascii_case(category.trim())),
+            _ => false,
+        };
+        if exempt {
+            return false;
+        }
+        match self.applies_to.as_str() {
+            "product" => self.product_id == Some(line.product_id),
+            "category" => match (self.category.as_deref(), line.category) {
+                (Some(rule), Some(product)) => rule.trim().eq_ignore_ascii_case(product.trim()),
+                _ => false,
+            },
+            _ => true,
+        }
+    }
+}
+
+pub const TAX_RULE_COLUMNS: &str = "id, name, rate, applies_to, category, product_id, is_compound,
+    priority, exempt_categories, is_active, COALESCE(created_at, '') as created_at";
+
+/// Active rules in the order they apply
+pub async fn active_tax_rules(conn: &mut SqliteConnection) -> AppResult<Vec<TaxRule>> {
+    let query = format!(
+        "SELECT {} FROM tax_rules WHERE is_active = 1 ORDER BY priority, id",
+        TAX_RULE_COLUMNS
+    );
+    Ok(sqlx::query_as::<_, TaxRule>(&query)
+        .fetch_all(&mut *conn)
+        .await?)
+}
+
+/// Taxes on one line. With rules configured, every matching rule applies in priority order:
+/// parallel rules tax the line, compound rules tax the line plus the taxes before them.
+/// A line no rule matches is untaxed. Without any rules the product's own rate applies.
+pub fn compute_line_taxes(rules: &[TaxRule], currency: &Currency, line: &TaxableLine) -> Vec<LineTax> {
+    if !line.is_taxable {
+        return Vec::new();
+    }
+    if rules.is_empty() {
+        if line.product_tax_rate <= 0.0 {
+            return Vec::new();
+        }
+        return vec![LineTax {
+            tax_rule_id: None,
+            name: LEGACY_TAX_NAME.to_string(),
+            rate: line.product_tax_rate,
+            taxable_amount: line.line_total,
+            tax_amount: currency.tax(line.line_total, line.product_tax_rate),
+        }];
+    }
+
+    let mut taxes: Vec<LineTax> = Vec::new();
+    for rule in rules.iter().filter(|rule| rule.applies_to_line(line)) {
+        let taxable_amount = if rule.is_compound {
+            line.line_total + taxes.iter().map(|tax| tax.tax_amount).sum::<Money>()
+        } else {
+            line.line_total
+        };
+        taxes.push(LineTax {
+            tax_rule_id: Some(rule.id),
+            name: rule.name.clone(),
+            rate: rule.rate,
+            taxable_amount,
+            tax_amount: currency.tax(taxable_amount, rule.rate),
+        });
+    }
+    taxes
+}
+
+/// Keep a line's tax breakdown for reporting
+pub async fn record_line_taxes(
+    conn: &mut SqliteConnection,
+    sale_item_id: i64,
+    taxes: &[LineTax],
+) -> AppResult<()> {
+    for tax in taxes {
+        sqlx::query(
+            "INSERT INTO sale_item_taxes (sale_item_id, tax_rule_id, name, rate, taxable_amount, tax_amount)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .bind(sale_item_id)
+        .bind(tax.tax_rule_id)
+        .bind(&tax.name)
+        .bind(tax.rate)
+        .bind(tax.taxable_amount)
+        .bind(tax.tax_amount)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: i64, rate: f64, priority: i32, is_compound: bool) -> TaxRule {
+        TaxRule {
+            id,
+            name: format!("Rule {}", id),
+            rate,
+            applies_to: "all".to_string(),
+            category: None,
+            product_id: None,
+            is_compound,
+            priority,
+            exempt_categories: None,
+            is_active: true,
+            created_at: String::new(),
+        }
+    }
+
+    fn line(category: Option<&str>) -> TaxableLine<'_> {
+        TaxableLine {
+            product_id: 1,
+            category,
+            is_taxable: true,
+            product_tax_rate: 19.25,
+            line_total: Money::from_minor(10000),
+        }
+    }
+
+    fn amounts(taxes: &[LineTax]) -> Vec<i64> {
+        taxes.iter().map(|tax| tax.tax_amount.minor()).collect()
+    }
+
+    #[test]
+    fn test_compound_parallel_and_exempt_rules() {
+        let usd = Currency::usd();
+        let state = rule(1, 6.0, 1, false);
+        let county = rule(2, 2.0, 2, false);
+
+        // Parallel: both on 100.00
+        let parallel = compute_line_taxes(&[state.clone(), county.clone()], &usd, &line(None));
+        assert_eq!(amounts(&parallel), vec![600, 200]);
+
+        // Compound county tax is charged on 106.00
+        let compound_county = TaxRule { is_compound: true, ..county.clone() };
+        let compound = compute_line_taxes(&[state.clone(), compound_county], &usd, &line(None));
+        assert_eq!(amounts(&compound), vec![600, 212]);
+        assert_eq!(compound[1].taxable_amount, Money::from_minor(10600));
+
+        // Groceries are exempt from the state tax only
+        let grocery_exempt = TaxRule {
+            exempt_categories: Some("Alcohol, Groceries".to_string()),
+            ..state.clone()
+        };
+        let groceries =
+            compute_line_taxes(&[grocery_exempt, county.clone()], &usd, &line(Some("groceries")));
+        assert_eq!(groceries.len(), 1);
+        assert_eq!(groceries[0].tax_rule_id, Some(2));
+
+        // Category and product rules only hit their own lines
+        let fuel = TaxRule {
+            applies_to: "category".to_string(),
+            category: Some("Fuel".to_string()),
+            ..rule(3, 10.0, 0, false)
+        };
+        let hardware = compute_line_taxes(std::slice::from_ref(&fuel), &usd, &line(Some("Hardware")));
+        assert!(hardware.is_empty());
+        assert_eq!(amounts(&compute_line_taxes(&[fuel], &usd, &line(Some("Fuel")))), vec![1000]);
+
+        // Untaxable products pay nothing; without rules the product rate applies
+        let untaxable = TaxableLine { is_taxable: false, ..line(None) };
+        assert!(compute_line_taxes(&[state], &usd, &untaxable).is_empty());
+        let legacy = compute_line_taxes(&[], &usd, &line(None));
+        assert_eq!(legacy[0].tax_rule_id, None);
+        assert_eq!(amounts(&legacy), vec![1925]);
+    }
+}
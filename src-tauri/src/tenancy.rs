@@ -0,0 +1,147 @@
+//! Organization scoping. Products, customers and sales carry the `organization_id` they
+//! belong to, and commands read and write only the rows of the session's organization.
+//! Org-scoped commands need a valid session; there is no fallback organization.
+
+use crate::error::{AppError, AppResult};
+use crate::session::SESSION_MANAGER;
+use sqlx::sqlite::SqliteConnection;
+
+/// Organization created with the database; existing rows belong to it
+pub const DEFAULT_ORGANIZATION_ID: i64 = 1;
+
+/// Organization of the session. A missing token is SESSION_INVALID rather than the
+/// default organization, so leaving it out never reaches another organization's rows.
+pub fn active_organization(session_token: &str) -> AppResult<i64> {
+    Ok(SESSION_MANAGER.validate_session(session_token)?.organization_id)
+}
+
+/// `AND` condition keeping a query to one organization's rows
+pub fn organization_scope(column: &str, organization_id: i64) -> String {
+    format!(" AND {} = {}", column, organization_id)
+}
+
+/// Fail with NOT_FOUND unless row `id` of `table` belongs to the organization, so rows of
+/// other organizations look the same as rows that do not exist
+pub async fn require_in_organization(
+    conn: &mut SqliteConnection,
+    table: &str,
+    id: i64,
+    organization_id: i64,
+    entity: &str,
+) -> AppResult<()> {
+    let owner: Option<i64> =
+        sqlx::query_scalar(&format!("SELECT organization_id FROM {} WHERE id = ?1", table))
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    match owner {
+        Some(owner) if owner == organization_id => Ok(()),
+        _ => Err(AppError::not_found(entity)),
+    }
+}
+
+/// The organization a user signs in to: the first one they are an active member of
+pub async fn home_organization(conn: &mut SqliteConnection, user_id: i64) -> AppResult<i64> {
+    let organization_id: Option<i64> = sqlx::query_scalar(
+        "SELECT ou.organization_id FROM organization_users ou
+         JOIN organizations o ON o.id = ou.organization_id
+         WHERE ou.user_id = ?1 AND ou.is_active = 1 AND o.is_active = 1
+         ORDER BY ou.joined_at, ou.id
+         LIMIT 1",
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(organization_id.unwrap_or(DEFAULT_ORGANIZATION_ID))
+}
+
+/// A user's active membership of an organization
+#[derive(Debug, Clone)]
+pub struct Membership {
+    pub role: String,
+    /// Permissions granted on top of the role, from the membership's JSON array
+    pub permissions: Vec<String>,
+}
+
+impl Membership {
+    /// Owners and admins hold every permission, other roles only those granted to them
+    pub fn allows(&self, permission: &str) -> bool {
+        matches!(self.role.as_str(), "Owner" | "Admin")
+            || self.permissions.iter().any(|granted| granted == permission)
+    }
+}
+
+/// Parse a membership's permissions column, a JSON array of permission names
+pub fn parse_permissions(permissions: Option<&str>) -> Vec<String> {
+    permissions
+        .filter(|permissions| !permissions.trim().is_empty())
+        .and_then(|permissions| serde_json::from_str(permissions).ok())
+        .unwrap_or_default()
+}
+
+/// The user's active membership of an active organization
+pub async fn membership(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    user_id: i64,
+) -> AppResult<Option<Membership>> {
+    let row: Option<(String, Option<String>)> = sqlx::query_as(
+        "SELECT ou.role, ou.permissions FROM organization_users ou
+         JOIN organizations o ON o.id = ou.organization_id
+         WHERE ou.organization_id = ?1 AND ou.user_id = ?2 AND ou.is_active = 1 AND o.is_active = 1",
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(row.map(|(role, permissions)| Membership {
+        role,
+        permissions: parse_permissions(permissions.as_deref()),
+    }))
+}
+
+/// Fail unless the user's membership of the organization allows `permission`
+pub async fn require_permission(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    user_id: i64,
+    permission: &str,
+) -> AppResult<Membership> {
+    match membership(conn, organization_id, user_id).await? {
+        Some(membership) if membership.allows(permission) => Ok(membership),
+        Some(_) => Err(AppError::PermissionDenied {
+            message: format!("The '{}' permission is required", permission),
+        }),
+        None => Err(AppError::not_found("Organization")),
+    }
+}
+
+/// Fail unless the user is an active Owner or Admin of the organization
+pub async fn require_admin(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    user_id: i64,
+) -> AppResult<Membership> {
+    match membership(conn, organization_id, user_id).await? {
+        Some(membership) if matches!(membership.role.as_str(), "Owner" | "Admin") => Ok(membership),
+        Some(_) => Err(AppError::PermissionDenied {
+            message: "Only an owner or admin of the organization can do this".to_string(),
+        }),
+        None => Err(AppError::not_found("Organization")),
+    }
+}
+
+/// Fail unless the user is an active Owner of the organization
+pub async fn require_owner(
+    conn: &mut SqliteConnection,
+    organization_id: i64,
+    user_id: i64,
+) -> AppResult<()> {
+    match membership(conn, organization_id, user_id).await? {
+        Some(membership) if membership.role == "Owner" => Ok(()),
+        Some(_) => Err(AppError::PermissionDenied {
+            message: "Only an owner of the organization can switch to it".to_string(),
+        }),
+        None => Err(AppError::not_found("Organization")),
+    }
+}
@@ -0,0 +1,75 @@
+use crate::database::apply_migrations;
+use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+
+/// Create an in-memory database with every migration from `get_migrations` applied.
+/// A single connection is used so all queries see the same in-memory database.
+pub async fn test_pool() -> SqlitePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .expect("failed to open in-memory database");
+
+    apply_migrations(&pool)
+        .await
+        .expect("failed to apply migrations");
+
+    pool
+}
+
+/// Insert an active user and return its id
+pub async fn insert_test_user(pool: &SqlitePool, username: &str) -> i64 {
+    sqlx::query(
+        "INSERT INTO users (username, email, password_hash, first_name, last_name, role)
+         VALUES (?1, ?1 || '@example.com', 'x', 'Test', 'User', 'Cashier')",
+    )
+    .bind(username)
+    .execute(pool)
+    .await
+    .expect("failed to insert test user")
+    .last_insert_rowid()
+}
+
+/// Insert an untaxed product with an inventory record holding `stock` units and return its id
+pub async fn insert_test_product(
+    pool: &SqlitePool,
+    sku: &str,
+    selling_price: f64,
+    stock: i32,
+) -> i64 {
+    let product_id = sqlx::query(
+        "INSERT INTO products (sku, name, unit_of_measure, cost_price, selling_price,
+                               wholesale_price, tax_rate, is_taxable, weight, reorder_point, is_active)
+         VALUES (?1, ?1, 'each', ?2 / 2, ?2, ?2, 0, 0, 0, 5, 1)",
+    )
+    .bind(sku)
+    .bind(selling_price)
+    .execute(pool)
+    .await
+    .expect("failed to insert test product")
+    .last_insert_rowid();
+
+    sqlx::query(
+        "INSERT INTO inventory (product_id, current_stock, minimum_stock, maximum_stock,
+                                reserved_stock, available_stock)
+         VALUES (?1, ?2, 0, 1000, 0, ?2)",
+    )
+    .bind(product_id)
+    .bind(stock)
+    .execute(pool)
+    .await
+    .expect("failed to insert test inventory");
+
+    product_id
+}
+
+/// Current on-hand stock for a product
+pub async fn current_stock(pool: &SqlitePool, product_id: i64) -> i32 {
+    sqlx::query_scalar("SELECT current_stock FROM inventory WHERE product_id = ?1")
+        .bind(product_id)
+        .fetch_one(pool)
+        .await
+        .expect("inventory record missing")
+}
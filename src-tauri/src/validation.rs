@@ -1,13 +1,19 @@
 use crate::error::{AppError, AppResult};
+use crate::models::{
+    CreateCustomerRequest, CreateExpenseRequest, CreateProductRequest, CreateSaleRequest,
+    CreateSupplierRequest, CreateUserRequest, SaleItemRequest, StockUpdateRequest,
+    UpdateCustomerRequest, UpdateExpenseRequest, UpdateProfileRequest, UpdateSupplierRequest,
+};
+use crate::money::Money;
 use regex::Regex;
 
 /// Validate that a required field is not empty
 pub fn validate_required(value: &str, field: &str) -> AppResult<()> {
     if value.trim().is_empty() {
-        Err(AppError::validation_error(&format!(
-            "{} is required",
-            field
-        )))
+        Err(AppError::validation(
+            field,
+            &format!("{} is required", field),
+        ))
     } else {
         Ok(())
     }
@@ -59,11 +65,20 @@ pub fn validate_password_strength(password: &str) -> AppResult<()> {
     Ok(())
 }
 
-/// Validate phone number format (US)
+/// Validate register PIN format (4-8 digits)
+pub fn validate_pin(pin: &str) -> AppResult<()> {
+    if pin.len() >= 4 && pin.len() <= 8 && pin.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(AppError::validation_error("PIN must be 4 to 8 digits"))
+    }
+}
+
+/// Validate phone number format (optional leading +, 7-15 digits, common separators)
 pub fn validate_phone(phone: &str) -> AppResult<()> {
-    let phone_regex =
-        Regex::new(r"^\+?1?[-.\s]?\(?([0-9]{3})\)?[-.\s]?([0-9]{3})[-.\s]?([0-9]{4})$").unwrap();
-    if phone_regex.is_match(phone) {
+    let phone_regex = Regex::new(r"^\+?[0-9\s().-]+$").unwrap();
+    let digits = phone.chars().filter(|c| c.is_ascii_digit()).count();
+    if phone_regex.is_match(phone.trim()) && (7..=15).contains(&digits) {
         Ok(())
     } else {
         Err(AppError::invalid_format("phone"))
@@ -82,10 +97,10 @@ pub fn validate_non_negative<T: PartialOrd + Default>(value: T, field: &str) ->
 /// Validate that a value is positive
 pub fn validate_positive(value: f64, field: &str) -> AppResult<()> {
     if value <= 0.0 {
-        Err(AppError::validation_error(&format!(
-            "{} must be positive",
-            field
-        )))
+        Err(AppError::validation(
+            field,
+            &format!("{} must be positive", field),
+        ))
     } else {
         Ok(())
     }
@@ -94,10 +109,10 @@ pub fn validate_positive(value: f64, field: &str) -> AppResult<()> {
 /// Validate string is not empty
 pub fn validate_not_empty(value: &str, field: &str) -> AppResult<()> {
     if value.trim().is_empty() {
-        Err(AppError::validation_error(&format!(
-            "{} cannot be empty",
-            field
-        )))
+        Err(AppError::validation(
+            field,
+            &format!("{} cannot be empty", field),
+        ))
     } else {
         Ok(())
     }
@@ -107,10 +122,10 @@ pub fn validate_not_empty(value: &str, field: &str) -> AppResult<()> {
 pub fn validate_length(value: &str, min: usize, max: usize, field: &str) -> AppResult<()> {
     let len = value.len();
     if len < min || len > max {
-        Err(AppError::validation_error(&format!(
-            "{} must be between {} and {} characters",
-            field, min, max
-        )))
+        Err(AppError::validation(
+            field,
+            &format!("{} must be between {} and {} characters", field, min, max),
+        ))
     } else {
         Ok(())
     }
@@ -146,15 +161,33 @@ pub fn validate_barcode(barcode: &str) -> AppResult<()> {
 /// Validate quantity (must be positive integer)
 pub fn validate_quantity(quantity: i32, field: &str) -> AppResult<()> {
     if quantity <= 0 {
-        Err(AppError::validation_error(&format!(
-            "{} must be greater than 0",
-            field
-        )))
+        Err(AppError::validation(
+            field,
+            &format!("{} must be greater than 0", field),
+        ))
     } else {
         Ok(())
     }
 }
 
+/// Validate a weighed or measured quantity: positive, finite and at most three decimals (grams)
+pub fn validate_measured_quantity(quantity: f64, field: &str) -> AppResult<()> {
+    if !quantity.is_finite() || quantity <= 0.0 {
+        return Err(AppError::validation(
+            field,
+            &format!("{} must be greater than 0", field),
+        ));
+    }
+    let thousandths = quantity * 1000.0;
+    if (thousandths - thousandths.round()).abs() > 1e-6 {
+        return Err(AppError::validation(
+            field,
+            &format!("{} has more than three decimal places", field),
+        ));
+    }
+    Ok(())
+}
+
 /// Validate price (must be non-negative)
 pub fn validate_price(price: f64, field: &str) -> AppResult<()> {
     if price < 0.0 {
@@ -164,6 +197,44 @@ pub fn validate_price(price: f64, field: &str) -> AppResult<()> {
     }
 }
 
+/// Validate a monetary amount (must be non-negative)
+pub fn validate_amount(amount: Money, field: &str) -> AppResult<()> {
+    if amount.is_negative() {
+        Err(AppError::negative_value(field))
+    } else {
+        Ok(())
+    }
+}
+
+/// Tag a line item validation error with the product it belongs to
+pub fn for_product<T>(product_id: i64, result: AppResult<T>) -> AppResult<T> {
+    result.map_err(|err| match err {
+        AppError::Validation { field, message } => AppError::Validation {
+            field,
+            message: format!("{} (product {})", message, product_id),
+        },
+        other => other,
+    })
+}
+
+/// Validate a sale or return line: quantity must be positive and the unit price non-negative
+pub fn validate_line_item(product_id: i64, quantity: i32, unit_price: Money) -> AppResult<()> {
+    for_product(product_id, validate_quantity(quantity, "quantity"))?;
+    for_product(product_id, validate_amount(unit_price, "unit_price"))
+}
+
+/// Validate that a client-supplied total matches the server-side recomputation within a cent
+pub fn validate_total(actual: Money, expected: Money, field: &str) -> AppResult<()> {
+    if (actual - expected).minor().abs() > 1 {
+        Err(AppError::validation(
+            field,
+            &format!("{} is {} but should be {}", field, actual, expected),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 /// Validate enum value
 pub fn validate_enum<T: PartialEq>(value: &T, allowed: &[T], field: &str) -> AppResult<()>
 where
@@ -172,10 +243,208 @@ where
     if allowed.contains(value) {
         Ok(())
     } else {
-        Err(AppError::validation_error(&format!(
-            "Invalid value for {}",
-            field
-        )))
+        Err(AppError::validation(
+            field,
+            &format!("Invalid value for {}", field),
+        ))
+    }
+}
+
+/// Field-level validation for incoming create/update requests.
+/// Called at the top of each command before anything is written.
+pub trait Validate {
+    fn validate(&self) -> AppResult<()>;
+}
+
+/// Validate tax rate is a percentage between 0 and 100
+pub fn validate_tax_rate(rate: f64, field: &str) -> AppResult<()> {
+    if (0.0..=100.0).contains(&rate) {
+        Ok(())
+    } else {
+        Err(AppError::validation(
+            field,
+            &format!("{} must be between 0 and 100", field),
+        ))
+    }
+}
+
+// Optional text fields arrive as empty strings from the forms; only check filled-in values
+fn filled(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn validate_optional_email(email: &Option<String>) -> AppResult<()> {
+    filled(email).map_or(Ok(()), validate_email)
+}
+
+fn validate_optional_phone(phone: &Option<String>) -> AppResult<()> {
+    filled(phone).map_or(Ok(()), validate_phone)
+}
+
+/// Fields that may be omitted on update but cannot be cleared
+fn validate_optional_required(value: &Option<String>, field: &str) -> AppResult<()> {
+    value
+        .as_deref()
+        .map_or(Ok(()), |v| validate_required(v, field))
+}
+
+impl Validate for CreateUserRequest {
+    fn validate(&self) -> AppResult<()> {
+        validate_required(&self.username, "username")?;
+        validate_email(self.email.trim())
+    }
+}
+
+impl Validate for UpdateProfileRequest {
+    fn validate(&self) -> AppResult<()> {
+        validate_required(&self.username, "username")?;
+        validate_email(self.email.trim())
+    }
+}
+
+impl Validate for CreateProductRequest {
+    fn validate(&self) -> AppResult<()> {
+        validate_required(&self.sku, "sku")?;
+        validate_required(&self.name, "name")?;
+        validate_required(&self.unit_of_measure, "unit_of_measure")?;
+        validate_price(self.cost_price, "cost_price")?;
+        validate_price(self.selling_price, "selling_price")?;
+        validate_price(self.wholesale_price, "wholesale_price")?;
+        validate_tax_rate(self.tax_rate, "tax_rate")?;
+        validate_non_negative(self.weight, "weight")?;
+        validate_non_negative(self.reorder_point, "reorder_point")
+    }
+}
+
+impl Validate for SaleItemRequest {
+    fn validate(&self) -> AppResult<()> {
+        if let Some(measured) = self.measured_quantity {
+            for_product(
+                self.product_id,
+                validate_measured_quantity(measured, "measured_quantity"),
+            )?;
+        }
+        validate_line_item(self.product_id, self.whole_quantity(), self.unit_price)?;
+        if let Some(price_override) = self.price_override {
+            for_product(self.product_id, validate_amount(price_override, "price_override"))?;
+            for_product(
+                self.product_id,
+                validate_required(self.override_reason.as_deref().unwrap_or(""), "override_reason"),
+            )?;
+        }
+        validate_amount(self.discount_amount, "discount_amount")?;
+        validate_amount(self.line_total, "line_total")?;
+        validate_total(
+            self.line_total,
+            self.extend(self.charged_price()) - self.discount_amount,
+            "line_total",
+        )
+    }
+}
+
+impl Validate for CreateSaleRequest {
+    fn validate(&self) -> AppResult<()> {
+        if self.items.is_empty() {
+            return Err(AppError::validation(
+                "items",
+                "Sale must contain at least one item",
+            ));
+        }
+        for item in &self.items {
+            item.validate()?;
+        }
+        validate_amount(self.subtotal, "subtotal")?;
+        validate_amount(self.tax_amount, "tax_amount")?;
+        validate_amount(self.discount_amount, "discount_amount")?;
+        validate_amount(self.total_amount, "total_amount")?;
+        validate_total(
+            self.subtotal,
+            self.items.iter().map(|item| item.line_total).sum(),
+            "subtotal",
+        )?;
+        validate_total(
+            self.total_amount,
+            self.subtotal + self.tax_amount - self.discount_amount,
+            "total_amount",
+        )?;
+        if let Some(cash) = self.cash_amount {
+            validate_amount(cash, "cash_amount")?;
+            if cash > self.total_amount {
+                return Err(AppError::validation(
+                    "cash_amount",
+                    "Cash paid cannot be more than the total",
+                ));
+            }
+        }
+        validate_required(&self.payment_method, "payment_method")?;
+        validate_optional_email(&self.customer_email)?;
+        validate_optional_phone(&self.customer_phone)
+    }
+}
+
+impl Validate for StockUpdateRequest {
+    fn validate(&self) -> AppResult<()> {
+        if self.quantity_change == 0 {
+            return Err(AppError::validation(
+                "quantity_change",
+                "quantity_change cannot be 0",
+            ));
+        }
+        validate_required(&self.movement_type, "movement_type")
+    }
+}
+
+impl Validate for CreateCustomerRequest {
+    fn validate(&self) -> AppResult<()> {
+        validate_required(&self.first_name, "first_name")?;
+        validate_required(&self.last_name, "last_name")?;
+        validate_optional_email(&self.email)?;
+        validate_optional_phone(&self.phone)
+    }
+}
+
+impl Validate for UpdateCustomerRequest {
+    fn validate(&self) -> AppResult<()> {
+        validate_optional_required(&self.first_name, "first_name")?;
+        validate_optional_required(&self.last_name, "last_name")?;
+        validate_optional_email(&self.email)?;
+        validate_optional_phone(&self.phone)
+    }
+}
+
+impl Validate for CreateSupplierRequest {
+    fn validate(&self) -> AppResult<()> {
+        validate_required(&self.company_name, "company_name")?;
+        validate_optional_email(&self.email)?;
+        validate_optional_phone(&self.phone)
+    }
+}
+
+impl Validate for UpdateSupplierRequest {
+    fn validate(&self) -> AppResult<()> {
+        validate_optional_required(&self.company_name, "company_name")?;
+        validate_optional_email(&self.email)?;
+        validate_optional_phone(&self.phone)
+    }
+}
+
+impl Validate for CreateExpenseRequest {
+    fn validate(&self) -> AppResult<()> {
+        validate_required(&self.description, "description")?;
+        validate_price(self.amount, "amount")?;
+        validate_required(&self.expense_date, "expense_date")?;
+        validate_required(&self.payment_method, "payment_method")
+    }
+}
+
+impl Validate for UpdateExpenseRequest {
+    fn validate(&self) -> AppResult<()> {
+        validate_optional_required(&self.description, "description")?;
+        if let Some(amount) = self.amount {
+            validate_price(amount, "amount")?;
+        }
+        validate_optional_required(&self.expense_date, "expense_date")?;
+        validate_optional_required(&self.payment_method, "payment_method")
     }
 }
 
@@ -196,10 +465,349 @@ mod tests {
         assert!(validate_password_strength("NoDigits!").is_err());
     }
 
+    #[test]
+    fn test_pin_validation() {
+        assert!(validate_pin("1234").is_ok());
+        assert!(validate_pin("12345678").is_ok());
+        assert!(validate_pin("123").is_err());
+        assert!(validate_pin("123456789").is_err());
+        assert!(validate_pin("12a4").is_err());
+    }
+
     #[test]
     fn test_sku_validation() {
         assert!(validate_sku("SKU-123").is_ok());
         assert!(validate_sku("A").is_err());
         assert!(validate_sku("invalid sku").is_err());
     }
+
+    const JUNK_TEXT: [&str; 3] = ["", "   ", "\t\n"];
+    const JUNK_PRICES: [f64; 3] = [-0.01, -1.0, -1_000_000.0];
+    const JUNK_EMAILS: [&str; 4] = ["not-an-email", "a@b", "@example.com", "user@@example.com"];
+    const JUNK_PHONES: [&str; 4] = ["abc", "12", "555-CALL-NOW", "+1234567890123456"];
+
+    type Mutation<R> = Box<dyn Fn(&mut R)>;
+
+    fn field_of(result: AppResult<()>) -> Option<String> {
+        let err = result.expect_err("junk value was accepted");
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        serde_json::to_value(&err).unwrap()["field"]
+            .as_str()
+            .map(str::to_string)
+    }
+
+    /// Apply each mutation to a fresh valid request and assert it is rejected on `field`
+    fn assert_rejects<R: Validate>(
+        valid: impl Fn() -> R,
+        field: &str,
+        mutations: Vec<Mutation<R>>,
+    ) {
+        assert!(valid().validate().is_ok());
+        for mutate in mutations {
+            let mut request = valid();
+            mutate(&mut request);
+            assert_eq!(field_of(request.validate()).as_deref(), Some(field));
+        }
+    }
+
+    fn text_mutations<R: 'static>(set: fn(&mut R, String)) -> Vec<Mutation<R>> {
+        JUNK_TEXT
+            .iter()
+            .map(|junk| Box::new(move |r: &mut R| set(r, junk.to_string())) as Mutation<R>)
+            .collect()
+    }
+
+    fn price_mutations<R: 'static>(set: fn(&mut R, f64)) -> Vec<Mutation<R>> {
+        JUNK_PRICES
+            .iter()
+            .map(|junk| Box::new(move |r: &mut R| set(r, *junk)) as Mutation<R>)
+            .collect()
+    }
+
+    fn optional_mutations<R: 'static>(
+        junk: &'static [&'static str],
+        set: fn(&mut R, Option<String>),
+    ) -> Vec<Mutation<R>> {
+        junk.iter()
+            .map(|junk| Box::new(move |r: &mut R| set(r, Some(junk.to_string()))) as Mutation<R>)
+            .collect()
+    }
+
+    fn valid_product() -> CreateProductRequest {
+        CreateProductRequest {
+            sku: "SKU-1".to_string(),
+            barcode: None,
+            name: "Widget".to_string(),
+            description: None,
+            category: None,
+            subcategory: None,
+            brand: None,
+            unit_of_measure: "each".to_string(),
+            cost_price: 5.0,
+            selling_price: 10.0,
+            wholesale_price: 8.0,
+            tax_rate: 19.25,
+            is_taxable: true,
+            weight: 0.0,
+            dimensions: None,
+            supplier_info: Some(String::new()),
+            reorder_point: 5,
+            category_id: None,
+        }
+    }
+
+    fn valid_sale() -> CreateSaleRequest {
+        CreateSaleRequest {
+            items: vec![SaleItemRequest {
+                product_id: 1,
+                quantity: 2,
+                unit_price: Money::from_minor(1000),
+                discount_amount: Money::ZERO,
+                line_total: Money::from_minor(2000),
+                measured_quantity: None,
+                price_override: None,
+                override_reason: None,
+            }],
+            subtotal: Money::from_minor(2000),
+            tax_amount: Money::ZERO,
+            discount_amount: Money::ZERO,
+            total_amount: Money::from_minor(2000),
+            payment_method: "cash".to_string(),
+            payment_status: None,
+            customer_name: None,
+            customer_phone: Some(String::new()),
+            customer_email: None,
+            notes: None,
+            location_id: None,
+            organization_id: None,
+            below_cost_approved_by: None,
+            tender: None,
+            override_approval: None,
+            cash_amount: None,
+            promotion_codes: Vec::new(),
+        }
+    }
+
+    fn valid_customer() -> CreateCustomerRequest {
+        CreateCustomerRequest {
+            first_name: "Ada".to_string(),
+            last_name: "Lovelace".to_string(),
+            email: Some("ada@example.com".to_string()),
+            phone: Some("+237 6 77 12 34 56".to_string()),
+            company: None,
+            address: None,
+            city: None,
+            state: None,
+            zip_code: None,
+            country: None,
+            date_of_birth: None,
+            customer_type: None,
+            notes: None,
+            tags: None,
+        }
+    }
+
+    #[test]
+    fn test_product_request_rejects_junk() {
+        assert_rejects(valid_product, "sku", text_mutations(|r, v| r.sku = v));
+        assert_rejects(valid_product, "name", text_mutations(|r, v| r.name = v));
+        assert_rejects(valid_product, "cost_price", price_mutations(|r, v| r.cost_price = v));
+        assert_rejects(
+            valid_product,
+            "selling_price",
+            price_mutations(|r, v| r.selling_price = v),
+        );
+        assert_rejects(
+            valid_product,
+            "tax_rate",
+            [-5.0, 100.01, 250.0]
+                .into_iter()
+                .map(|rate| Box::new(move |r: &mut CreateProductRequest| r.tax_rate = rate) as _)
+                .collect(),
+        );
+    }
+
+    #[test]
+    fn test_sale_request_rejects_junk() {
+        assert_rejects(valid_sale, "items", vec![Box::new(|r| r.items.clear())]);
+        assert_rejects(
+            valid_sale,
+            "quantity",
+            [0, -1, i32::MIN]
+                .into_iter()
+                .map(|qty| Box::new(move |r: &mut CreateSaleRequest| r.items[0].quantity = qty) as _)
+                .collect(),
+        );
+        assert_rejects(
+            valid_sale,
+            "unit_price",
+            price_mutations(|r, v| r.items[0].unit_price = Money::from_major(v)),
+        );
+        assert_rejects(valid_sale, "total_amount", price_mutations(|r, v| r.total_amount = Money::from_major(v)));
+        assert_rejects(
+            valid_sale,
+            "payment_method",
+            text_mutations(|r, v| r.payment_method = v),
+        );
+        assert_rejects(
+            valid_sale,
+            "email",
+            optional_mutations(&JUNK_EMAILS, |r, v| r.customer_email = v),
+        );
+    }
+
+    #[test]
+    fn test_sale_totals_accept_consistent_payload() {
+        let mut sale = valid_sale();
+        sale.items[0].discount_amount = Money::from_minor(150);
+        sale.items[0].line_total = Money::from_minor(1850);
+        sale.items.push(SaleItemRequest {
+            product_id: 2,
+            quantity: 3,
+            unit_price: Money::from_major(0.1),
+            discount_amount: Money::ZERO,
+            line_total: Money::from_major(0.30000000000000004),
+            measured_quantity: None,
+            price_override: None,
+            override_reason: None,
+        });
+        sale.subtotal = Money::from_minor(1880);
+        sale.tax_amount = Money::from_minor(160);
+        sale.discount_amount = Money::from_minor(40);
+        sale.total_amount = Money::from_minor(2000);
+        assert!(sale.validate().is_ok());
+
+        // One cent of float slack from the frontend is tolerated
+        sale.total_amount = Money::from_minor(2001);
+        assert!(sale.validate().is_ok());
+    }
+
+    #[test]
+    fn test_sale_totals_reject_tampered_payload() {
+        assert_rejects(
+            valid_sale,
+            "line_total",
+            vec![
+                Box::new(|r| r.items[0].line_total = Money::from_minor(20)),
+                Box::new(|r| r.items[0].discount_amount = Money::from_minor(500)),
+            ],
+        );
+        assert_rejects(
+            valid_sale,
+            "subtotal",
+            vec![
+                Box::new(|r| r.subtotal = Money::from_minor(1000)),
+                Box::new(|r| {
+                    r.items[0].quantity = 3;
+                    r.items[0].line_total = Money::from_minor(3000);
+                }),
+            ],
+        );
+        assert_rejects(
+            valid_sale,
+            "total_amount",
+            vec![
+                Box::new(|r| r.total_amount = Money::from_minor(1)),
+                Box::new(|r| r.tax_amount = Money::from_minor(300)),
+                Box::new(|r| r.discount_amount = Money::from_minor(2000)),
+            ],
+        );
+
+        let mut sale = valid_sale();
+        sale.total_amount = Money::from_minor(1);
+        let err = sale.validate().unwrap_err();
+        assert_eq!(err.message(), "total_amount is 0.01 but should be 20.00");
+    }
+
+    #[test]
+    fn test_line_items_reject_non_positive_quantity_and_negative_price() {
+        for quantity in [0, -5] {
+            let err = validate_line_item(42, quantity, Money::from_minor(100)).unwrap_err();
+            assert_eq!(field_of(Err(err.clone())).as_deref(), Some("quantity"));
+            assert!(err.message().contains("product 42"));
+        }
+
+        let err = validate_line_item(7, 1, Money::from_minor(-1)).unwrap_err();
+        assert_eq!(field_of(Err(err.clone())).as_deref(), Some("unit_price"));
+        assert!(err.message().contains("product 7"));
+
+        assert!(validate_line_item(7, 1, Money::ZERO).is_ok());
+    }
+
+    #[test]
+    fn test_measured_line_is_priced_by_decimal_quantity() {
+        let mut sale = valid_sale();
+        // 2.5 kg at 4.99/kg is 12.475, rounded half-up to 12.48
+        sale.items[0].measured_quantity = Some(2.5);
+        sale.items[0].unit_price = Money::from_minor(499);
+        sale.items[0].line_total = Money::from_minor(1248);
+        assert_eq!(sale.items[0].whole_quantity(), 3);
+        assert!(sale.items[0].validate().is_ok());
+
+        // Pricing the line by the whole units is rejected
+        sale.items[0].line_total = Money::from_minor(1497);
+        assert_eq!(field_of(sale.items[0].validate()).as_deref(), Some("line_total"));
+
+        for measured in [0.0, -1.5, f64::NAN, 1.2345] {
+            sale.items[0].measured_quantity = Some(measured);
+            assert_eq!(
+                field_of(sale.items[0].validate()).as_deref(),
+                Some("measured_quantity")
+            );
+        }
+    }
+
+    #[test]
+    fn test_customer_requests_reject_junk() {
+        assert_rejects(valid_customer, "first_name", text_mutations(|r, v| r.first_name = v));
+        assert_rejects(valid_customer, "last_name", text_mutations(|r, v| r.last_name = v));
+        assert_rejects(
+            valid_customer,
+            "email",
+            optional_mutations(&JUNK_EMAILS, |r, v| r.email = v),
+        );
+        assert_rejects(
+            valid_customer,
+            "phone",
+            optional_mutations(&JUNK_PHONES, |r, v| r.phone = v),
+        );
+
+        let update = UpdateCustomerRequest {
+            first_name: Some(" ".to_string()),
+            last_name: None,
+            email: None,
+            phone: None,
+            company: None,
+            address: None,
+            city: None,
+            state: None,
+            zip_code: None,
+            country: None,
+            date_of_birth: None,
+            customer_type: None,
+            status: None,
+            notes: None,
+            tags: None,
+        };
+        assert_eq!(field_of(update.validate()).as_deref(), Some("first_name"));
+    }
+
+    #[test]
+    fn test_expense_request_rejects_junk() {
+        let valid = || CreateExpenseRequest {
+            category_id: None,
+            vendor: None,
+            description: "Rent".to_string(),
+            amount: 100.0,
+            expense_date: "2024-01-01".to_string(),
+            payment_method: "cash".to_string(),
+            reference_number: None,
+            is_recurring: None,
+            recurring_frequency: None,
+            tags: None,
+            notes: None,
+        };
+        assert_rejects(valid, "description", text_mutations(|r, v| r.description = v));
+        assert_rejects(valid, "amount", price_mutations(|r, v| r.amount = v));
+    }
 }
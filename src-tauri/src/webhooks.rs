@@ -0,0 +1,641 @@
+use crate::error::{AppError, AppResult};
+use crate::sync_outbox::entity_snapshot;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+pub const EVENT_SALE_COMPLETED: &str = "sale.completed";
+pub const EVENT_SALE_VOIDED: &str = "sale.voided";
+pub const EVENT_RETURN_CREATED: &str = "return.created";
+pub const EVENT_PURCHASE_ORDER_RECEIVED: &str = "purchase_order.received";
+/// Sent only by `test_webhook`, whatever the subscription
+pub const EVENT_TEST: &str = "webhook.test";
+
+/// Events a webhook can subscribe to; `*` subscribes to all of them
+pub const WEBHOOK_EVENTS: &[&str] = &[
+    EVENT_SALE_COMPLETED,
+    EVENT_SALE_VOIDED,
+    EVENT_RETURN_CREATED,
+    EVENT_PURCHASE_ORDER_RECEIVED,
+];
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+/// A delivery is given up after this many failed attempts
+pub const MAX_DELIVERY_ATTEMPTS: i64 = 6;
+/// Wait before the first retry, doubled after every further failure
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often the worker looks for due retries when nothing wakes it earlier
+const DELIVERY_POLL_INTERVAL: Duration = Duration::from_secs(15);
+const DELIVERY_BATCH_SIZE: i64 = 50;
+
+lazy_static::lazy_static! {
+    /// Wakes the delivery worker as soon as an event is queued
+    static ref DELIVERY_WAKE: Notify = Notify::new();
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: i64,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<String>,
+    pub is_active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookRequest {
+    pub url: String,
+    /// Generated when left empty
+    pub secret: Option<String>,
+    pub events: Vec<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i64,
+    pub response_status: Option<i64>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<String>,
+    pub created_at: String,
+    pub delivered_at: Option<String>,
+}
+
+/// Hex HMAC-SHA256 of the request body, sent as `sha256=<hex>` in `X-Webhook-Signature`
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Delay before retrying a delivery that has failed `attempts` times
+pub fn retry_delay(attempts: i64) -> Duration {
+    let doublings = attempts.clamp(1, 16) as u32 - 1;
+    RETRY_BASE_DELAY * 2u32.pow(doublings)
+}
+
+fn validate_request(request: &WebhookRequest) -> AppResult<()> {
+    let url = request.url.trim();
+    if !(url.starts_with("http://") || url.starts_with("https://")) || url.len() <= "https://".len() {
+        return Err(AppError::validation(
+            "url",
+            "Webhook URL must start with http:// or https://",
+        ));
+    }
+    if request.events.is_empty() {
+        return Err(AppError::validation(
+            "events",
+            "Subscribe the webhook to at least one event",
+        ));
+    }
+    if let Some(unknown) = request
+        .events
+        .iter()
+        .find(|event| event.as_str() != "*" && !WEBHOOK_EVENTS.contains(&event.as_str()))
+    {
+        return Err(AppError::validation(
+            "events",
+            &format!("Unknown webhook event '{}'", unknown),
+        ));
+    }
+    Ok(())
+}
+
+fn webhook_from_row(row: &SqliteRow) -> AppResult<Webhook> {
+    let events: String = row.try_get("events")?;
+    Ok(Webhook {
+        id: row.try_get("id")?,
+        url: row.try_get("url")?,
+        secret: row.try_get("secret")?,
+        events: serde_json::from_str(&events).unwrap_or_default(),
+        is_active: row.try_get("is_active")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+fn delivery_from_row(row: &SqliteRow) -> AppResult<WebhookDelivery> {
+    let payload: String = row.try_get("payload")?;
+    Ok(WebhookDelivery {
+        id: row.try_get("id")?,
+        webhook_id: row.try_get("webhook_id")?,
+        event: row.try_get("event")?,
+        payload: serde_json::from_str(&payload).map_err(|e| AppError::from(e.to_string()))?,
+        status: row.try_get("status")?,
+        attempts: row.try_get("attempts")?,
+        response_status: row.try_get("response_status")?,
+        last_error: row.try_get("last_error")?,
+        next_attempt_at: row.try_get("next_attempt_at")?,
+        created_at: row.try_get("created_at")?,
+        delivered_at: row.try_get("delivered_at")?,
+    })
+}
+
+const WEBHOOK_COLUMNS: &str = "id, url, secret, events, is_active,
+    COALESCE(created_at, '') as created_at, COALESCE(updated_at, '') as updated_at";
+const DELIVERY_COLUMNS: &str = "id, webhook_id, event, payload, status, attempts, response_status,
+    last_error, next_attempt_at, COALESCE(created_at, '') as created_at, delivered_at";
+
+pub async fn get_webhooks(pool: &SqlitePool) -> AppResult<Vec<Webhook>> {
+    let query = format!("SELECT {} FROM webhooks ORDER BY id", WEBHOOK_COLUMNS);
+    let rows = sqlx::query(&query).fetch_all(pool).await?;
+    rows.iter().map(webhook_from_row).collect()
+}
+
+pub async fn get_webhook(pool: &SqlitePool, webhook_id: i64) -> AppResult<Webhook> {
+    let query = format!("SELECT {} FROM webhooks WHERE id = ?1", WEBHOOK_COLUMNS);
+    let row = sqlx::query(&query)
+        .bind(webhook_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("Webhook"))?;
+    webhook_from_row(&row)
+}
+
+fn secret_or_generated(secret: &Option<String>) -> String {
+    match secret.as_deref().map(str::trim) {
+        Some(secret) if !secret.is_empty() => secret.to_string(),
+        _ => uuid::Uuid::new_v4().simple().to_string(),
+    }
+}
+
+pub async fn create_webhook(pool: &SqlitePool, request: &WebhookRequest) -> AppResult<Webhook> {
+    validate_request(request)?;
+
+    let events = serde_json::to_string(&request.events).map_err(|e| AppError::from(e.to_string()))?;
+    let webhook_id = sqlx::query(
+        "INSERT INTO webhooks (url, secret, events, is_active) VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(request.url.trim())
+    .bind(secret_or_generated(&request.secret))
+    .bind(events)
+    .bind(request.is_active.unwrap_or(true))
+    .execute(pool)
+    .await?
+    .last_insert_rowid();
+
+    get_webhook(pool, webhook_id).await
+}
+
+/// Replace a webhook's settings; an empty secret keeps the current one
+pub async fn update_webhook(
+    pool: &SqlitePool,
+    webhook_id: i64,
+    request: &WebhookRequest,
+) -> AppResult<Webhook> {
+    validate_request(request)?;
+
+    let events = serde_json::to_string(&request.events).map_err(|e| AppError::from(e.to_string()))?;
+    let secret = request
+        .secret
+        .as_deref()
+        .map(str::trim)
+        .filter(|secret| !secret.is_empty());
+    let result = sqlx::query(
+        "UPDATE webhooks
+         SET url = ?2, secret = COALESCE(?3, secret), events = ?4,
+             is_active = COALESCE(?5, is_active), updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1",
+    )
+    .bind(webhook_id)
+    .bind(request.url.trim())
+    .bind(secret)
+    .bind(events)
+    .bind(request.is_active)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Webhook"));
+    }
+    get_webhook(pool, webhook_id).await
+}
+
+pub async fn delete_webhook(pool: &SqlitePool, webhook_id: i64) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM webhook_deliveries WHERE webhook_id = ?1")
+        .bind(webhook_id)
+        .execute(&mut *tx)
+        .await?;
+    let result = sqlx::query("DELETE FROM webhooks WHERE id = ?1")
+        .bind(webhook_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Webhook"));
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Most recent deliveries for one webhook, newest first
+pub async fn get_deliveries(
+    pool: &SqlitePool,
+    webhook_id: i64,
+    limit: i64,
+) -> AppResult<Vec<WebhookDelivery>> {
+    let query = format!(
+        "SELECT {} FROM webhook_deliveries WHERE webhook_id = ?1 ORDER BY id DESC LIMIT ?2",
+        DELIVERY_COLUMNS
+    );
+    let rows = sqlx::query(&query)
+        .bind(webhook_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    rows.iter().map(delivery_from_row).collect()
+}
+
+fn envelope(event: &str, data: Value) -> Value {
+    json!({
+        "event": event,
+        "occurred_at": chrono::Utc::now().to_rfc3339(),
+        "data": data,
+    })
+}
+
+/// Queue `event` for every active webhook subscribed to it; returns how many were queued
+pub async fn enqueue_event(pool: &SqlitePool, event: &str, data: Value) -> AppResult<u64> {
+    let result = sqlx::query(
+        "INSERT INTO webhook_deliveries (webhook_id, event, payload)
+         SELECT w.id, ?1, ?2 FROM webhooks w
+         WHERE w.is_active = 1
+           AND EXISTS (SELECT 1 FROM json_each(w.events) e WHERE e.value IN (?1, '*'))",
+    )
+    .bind(event)
+    .bind(envelope(event, data).to_string())
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() > 0 {
+        DELIVERY_WAKE.notify_one();
+    }
+    Ok(result.rows_affected())
+}
+
+/// Queue an event about a committed sale or return, carrying the record as JSON.
+/// Call it after the commit; failures are only logged so they never fail the command.
+/// Records made in demo mode raise no events.
+pub async fn emit_entity_event(pool: &SqlitePool, event: &str, entity_type: &str, entity_id: i64) {
+    let result = async {
+        let mut conn = pool.acquire().await?;
+        let data = entity_snapshot(&mut conn, entity_type, entity_id).await?;
+        drop(conn);
+        if data.get("is_demo").and_then(Value::as_i64) == Some(1) {
+            return Ok(());
+        }
+        enqueue_event(pool, event, data).await
+    }
+    .await;
+    if let Err(e) = result {
+        log::error!("Could not queue webhook event {}: {}", event, e.message());
+    }
+}
+
+/// Queue an event with a ready-made payload; failures are only logged
+pub async fn emit_event(pool: &SqlitePool, event: &str, data: Value) {
+    if let Err(e) = enqueue_event(pool, event, data).await {
+        log::error!("Could not queue webhook event {}: {}", event, e.message());
+    }
+}
+
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+/// POST one delivery; a 2xx response counts as delivered
+async fn send(
+    client: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    delivery: &WebhookDelivery,
+) -> Result<u16, (Option<u16>, String)> {
+    let body = delivery.payload.to_string();
+    let signature = format!("sha256={}", sign_payload(secret, body.as_bytes()));
+
+    let response = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, signature)
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| (None, e.to_string()))?;
+
+    let status = response.status();
+    if status.is_success() {
+        Ok(status.as_u16())
+    } else {
+        Err((Some(status.as_u16()), format!("HTTP {}", status)))
+    }
+}
+
+async fn record_attempt(
+    pool: &SqlitePool,
+    delivery: &WebhookDelivery,
+    result: Result<u16, (Option<u16>, String)>,
+) -> AppResult<()> {
+    match result {
+        Ok(status) => {
+            sqlx::query(
+                "UPDATE webhook_deliveries
+                 SET status = 'delivered', attempts = attempts + 1, response_status = ?2,
+                     last_error = NULL, delivered_at = CURRENT_TIMESTAMP
+                 WHERE id = ?1",
+            )
+            .bind(delivery.id)
+            .bind(status)
+            .execute(pool)
+            .await?;
+        }
+        Err((status, error)) => {
+            let attempts = delivery.attempts + 1;
+            let give_up = attempts >= MAX_DELIVERY_ATTEMPTS;
+            let delay = format!("+{} seconds", retry_delay(attempts).as_secs());
+            sqlx::query(
+                "UPDATE webhook_deliveries
+                 SET status = CASE WHEN ?2 THEN 'failed' ELSE 'pending' END,
+                     attempts = ?3, response_status = ?4, last_error = ?5,
+                     next_attempt_at = datetime('now', ?6)
+                 WHERE id = ?1",
+            )
+            .bind(delivery.id)
+            .bind(give_up)
+            .bind(attempts)
+            .bind(status)
+            .bind(error)
+            .bind(delay)
+            .execute(pool)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Send every delivery that is due; returns how many were attempted
+pub async fn deliver_due(pool: &SqlitePool, client: &reqwest::Client) -> AppResult<usize> {
+    let rows = sqlx::query(
+        "SELECT d.id, d.webhook_id, d.event, d.payload, d.status, d.attempts, d.response_status,
+                d.last_error, d.next_attempt_at, COALESCE(d.created_at, '') as created_at,
+                d.delivered_at, w.url as webhook_url, w.secret as webhook_secret
+         FROM webhook_deliveries d
+         JOIN webhooks w ON w.id = d.webhook_id
+         WHERE d.status = 'pending' AND w.is_active = 1
+           AND COALESCE(d.next_attempt_at, d.created_at) <= CURRENT_TIMESTAMP
+         ORDER BY d.id
+         LIMIT ?1",
+    )
+        .bind(DELIVERY_BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+
+    for row in &rows {
+        let delivery = delivery_from_row(row)?;
+        let url: String = row.try_get("webhook_url")?;
+        let secret: String = row.try_get("webhook_secret")?;
+        let result = send(client, &url, &secret, &delivery).await;
+        record_attempt(pool, &delivery, result).await?;
+    }
+    Ok(rows.len())
+}
+
+/// Send a test event to one webhook right away and return the recorded delivery
+pub async fn test_webhook(
+    pool: &SqlitePool,
+    client: &reqwest::Client,
+    webhook_id: i64,
+) -> AppResult<WebhookDelivery> {
+    let webhook = get_webhook(pool, webhook_id).await?;
+    let payload = envelope(EVENT_TEST, json!({ "webhook_id": webhook.id }));
+
+    let query = format!(
+        "INSERT INTO webhook_deliveries (webhook_id, event, payload)
+         VALUES (?1, ?2, ?3)
+         RETURNING {}",
+        DELIVERY_COLUMNS
+    );
+    let row = sqlx::query(&query)
+        .bind(webhook.id)
+        .bind(EVENT_TEST)
+        .bind(payload.to_string())
+        .fetch_one(pool)
+        .await?;
+    let delivery = delivery_from_row(&row)?;
+
+    let result = send(client, &webhook.url, &webhook.secret, &delivery).await;
+    record_attempt(pool, &delivery, result).await?;
+
+    let query = format!("SELECT {} FROM webhook_deliveries WHERE id = ?1", DELIVERY_COLUMNS);
+    let row = sqlx::query(&query).bind(delivery.id).fetch_one(pool).await?;
+    delivery_from_row(&row)
+}
+
+/// Spawn the task that posts queued deliveries, woken by new events and polling for retries.
+/// Failures are logged and retried with backoff; they never reach the command that queued them.
+pub fn spawn_delivery_worker(pool: SqlitePool) {
+    tauri::async_runtime::spawn(async move {
+        let client = http_client();
+        loop {
+            if pool.is_closed() {
+                break;
+            }
+            if let Err(e) = deliver_due(&pool, &client).await {
+                log::error!("Webhook delivery run failed: {}", e.message());
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(DELIVERY_POLL_INTERVAL) => {}
+                _ = DELIVERY_WAKE.notified() => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_pool;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    /// Minimal HTTP server answering each request with the next status in `statuses`,
+    /// returning the raw requests it received
+    async fn mock_server(statuses: Vec<u16>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&raw).to_string();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text
+                            .lines()
+                            .find_map(|line| {
+                                let lower = line.to_ascii_lowercase();
+                                lower
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if raw.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                    if n == 0 {
+                        break;
+                    }
+                }
+                requests.push(String::from_utf8_lossy(&raw).to_string());
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.ok();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn request(url: &str, events: &[&str]) -> WebhookRequest {
+        WebhookRequest {
+            url: url.to_string(),
+            secret: Some("s3cret".to_string()),
+            events: events.iter().map(|e| e.to_string()).collect(),
+            is_active: None,
+        }
+    }
+
+    fn header<'a>(raw: &'a str, name: &str) -> Option<&'a str> {
+        raw.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    #[tokio::test]
+    async fn test_subscribed_event_is_delivered_signed() {
+        let pool = test_pool().await;
+        let (url, server) = mock_server(vec![200]).await;
+        let webhook = create_webhook(&pool, &request(&url, &[EVENT_SALE_COMPLETED]))
+            .await
+            .unwrap();
+
+        let queued = enqueue_event(&pool, EVENT_SALE_COMPLETED, json!({ "sale_number": "S-1" }))
+            .await
+            .unwrap();
+        assert_eq!(queued, 1);
+        let queued = enqueue_event(&pool, EVENT_SALE_VOIDED, json!({})).await.unwrap();
+        assert_eq!(queued, 0);
+
+        let client = http_client();
+        assert_eq!(deliver_due(&pool, &client).await.unwrap(), 1);
+
+        let requests = server.await.unwrap();
+        let raw = &requests[0];
+        let body = &raw[raw.find("\r\n\r\n").unwrap() + 4..];
+        assert_eq!(
+            header(raw, SIGNATURE_HEADER).unwrap(),
+            format!("sha256={}", sign_payload("s3cret", body.as_bytes()))
+        );
+        assert_eq!(header(raw, EVENT_HEADER), Some(EVENT_SALE_COMPLETED));
+        let sent: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(sent["data"]["sale_number"], "S-1");
+
+        let deliveries = get_deliveries(&pool, webhook.id, 10).await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, "delivered");
+        assert_eq!(deliveries[0].response_status, Some(200));
+        assert_eq!(deliveries[0].attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_backs_off_then_gives_up() {
+        let pool = test_pool().await;
+        let (url, server) = mock_server(vec![500]).await;
+        let webhook = create_webhook(&pool, &request(&url, &["*"])).await.unwrap();
+        enqueue_event(&pool, EVENT_RETURN_CREATED, json!({})).await.unwrap();
+
+        let client = http_client();
+        assert_eq!(deliver_due(&pool, &client).await.unwrap(), 1);
+        server.await.unwrap();
+
+        let delivery = &get_deliveries(&pool, webhook.id, 10).await.unwrap()[0];
+        assert_eq!(delivery.status, "pending");
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.response_status, Some(500));
+        // Not due again until the backoff has passed
+        assert_eq!(deliver_due(&pool, &client).await.unwrap(), 0);
+
+        // Nothing listens on the port any more: the last attempt fails to connect
+        sqlx::query(
+            "UPDATE webhook_deliveries SET attempts = ?1, next_attempt_at = datetime('now', '-1 seconds')",
+        )
+        .bind(MAX_DELIVERY_ATTEMPTS - 1)
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(deliver_due(&pool, &client).await.unwrap(), 1);
+
+        let delivery = &get_deliveries(&pool, webhook.id, 10).await.unwrap()[0];
+        assert_eq!(delivery.status, "failed");
+        assert_eq!(delivery.attempts, MAX_DELIVERY_ATTEMPTS);
+        assert!(delivery.last_error.is_some());
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(3), Duration::from_secs(120));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_sends_immediately() {
+        let pool = test_pool().await;
+        let (url, server) = mock_server(vec![204]).await;
+        let webhook = create_webhook(&pool, &request(&url, &[EVENT_SALE_VOIDED]))
+            .await
+            .unwrap();
+
+        let delivery = test_webhook(&pool, &http_client(), webhook.id).await.unwrap();
+        assert_eq!(delivery.event, EVENT_TEST);
+        assert_eq!(delivery.status, "delivered");
+        assert_eq!(delivery.response_status, Some(204));
+        server.await.unwrap();
+
+        let err = create_webhook(&pool, &request("ftp://example", &[EVENT_SALE_VOIDED]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        let err = create_webhook(&pool, &request(&url, &["sale.deleted"]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+    }
+}
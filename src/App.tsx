@@ -29,7 +29,7 @@ import TimeTracking from "@/pages/TimeTracking";
 import Unauthorized from "@/pages/Unauthorized";
 import Users from "@/pages/Users";
 import { useAuthStore } from "@/store/authStore";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { useEffect, useState } from "react";
 import { Navigate, Route, Routes } from "react-router-dom";
 import { Toaster } from "sonner";
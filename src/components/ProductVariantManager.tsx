@@ -8,7 +8,7 @@ import { Label } from "@/components/ui/label";
 import { ScrollArea } from "@/components/ui/scroll-area";
 import { Separator } from "@/components/ui/separator";
 import { Tabs, TabsContent, TabsList, TabsTrigger } from "@/components/ui/tabs";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { ChevronRight, Layers, Trash2, Wand2, X } from "lucide-react";
 import { useEffect, useState } from "react";
 import { toast } from "sonner";
@@ -1,7 +1,7 @@
 // src/components/ReceiptTemplate.tsx
 import { format } from "date-fns";
 import { useEffect, useState } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { currencyFormatter } from "@/lib/currency";
 
 interface ReceiptProps {
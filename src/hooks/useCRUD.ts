@@ -3,7 +3,7 @@
  * Simplifies Create, Read, Update, Delete operations with Tauri backend
  */
 import { useState, useCallback, useEffect, useRef } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { toast } from "sonner";
 
 export interface UseCRUDOptions<T> {
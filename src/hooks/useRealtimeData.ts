@@ -1,4 +1,4 @@
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { useEffect, useState, useCallback, useMemo } from "react";
 
 export interface RealtimeDataConfig {
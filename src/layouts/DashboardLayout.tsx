@@ -27,7 +27,8 @@ import {
 import { cn } from "@/lib/utils";
 import { useAuthStore } from "@/store/authStore";
 import { useCartStore } from "@/store/cartStore";
-import { invoke, convertFileSrc } from "@tauri-apps/api/core";
+import { convertFileSrc } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { parseUTCDate } from "@/lib/date-utils";
 import { formatDistance } from "date-fns";
 import {
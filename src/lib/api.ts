@@ -3,12 +3,17 @@
  * Fixes all frontend API invocation issues identified in the risk analysis
  */
 
-import { invoke } from "@tauri-apps/api/core";
+import {
+    invoke as tauriInvoke,
+    type InvokeArgs,
+    type InvokeOptions,
+} from "@tauri-apps/api/core";
 
 // Typed error from backend
 export interface AppError {
     code: string;
     message: string;
+    field?: string;
     details?: string;
 }
 
@@ -19,11 +24,8 @@ const RETRY_DELAY_MS = 1000;
 
 // Retryable error codes
 const RETRYABLE_ERROR_CODES = [
-    "DB_001", // Database error
-    "DB_002", // Connection failed
-    "DB_003", // Query timeout
-    "TXN_003", // Concurrent modification
-    "SYS_002", // Operation timeout
+    "DATABASE_ERROR",
+    "CONCURRENT_MODIFICATION",
 ];
 
 /**
@@ -79,6 +81,35 @@ function getBackoffDelay(attempt: number, baseDelay: number = RETRY_DELAY_MS): n
     return baseDelay * Math.pow(2, attempt - 1);
 }
 
+/**
+ * Session token stored at login, if any
+ */
+export function getSessionToken(): string | null {
+    return localStorage.getItem("session_token");
+}
+
+/**
+ * Tauri invoke that passes the stored session token as `sessionToken`, which the
+ * organization-scoped commands require. Commands without the parameter ignore it, and
+ * a `sessionToken` given in `args` wins.
+ */
+export function invoke<T>(
+    command: string,
+    args?: InvokeArgs,
+    options?: InvokeOptions
+): Promise<T> {
+    const sessionToken = getSessionToken();
+    const isPlainArgs =
+        args === undefined ||
+        (typeof args === "object" && !Array.isArray(args) && !ArrayBuffer.isView(args) &&
+            !(args instanceof ArrayBuffer));
+    if (sessionToken && isPlainArgs) {
+        const withToken = { sessionToken, ...(args as Record<string, unknown>) };
+        return tauriInvoke<T>(command, withToken, options);
+    }
+    return tauriInvoke<T>(command, args, options);
+}
+
 /**
  * Execute API call with timeout
  */
@@ -166,36 +197,14 @@ export function isError(result: unknown): result is AppError {
 export function getErrorMessage(error: AppError): string {
     // Map error codes to user-friendly messages
     const errorMessages: Record<string, string> = {
-        AUTH_001: "Invalid username or password",
-        AUTH_002: "Your session has expired. Please log in again",
-        AUTH_003: "Invalid session. Please log in again",
-        AUTH_004: "Too many login attempts. Please wait before trying again",
-        AUTH_005: "Password must be at least 8 characters with uppercase, lowercase, and number",
-        AUTH_006: "Your account has been deactivated. Please contact support",
-        VAL_001: "Please check your input and try again",
-        VAL_002: "This entry already exists",
-        VAL_003: "The requested item was not found",
-        VAL_004: "Invalid format provided",
-        VAL_005: "Value cannot be negative",
-        INV_001: "Not enough stock available",
-        INV_002: "This product is currently inactive",
-        INV_003: "Inventory record not found",
-        TXN_001: "Transaction failed. Please try again",
-        TXN_002: "Failed to undo changes",
-        TXN_003: "This record was modified by another user. Please refresh and try again",
-        SALE_001: "This sale has already been voided",
-        SALE_002: "Sale not found",
-        SALE_003: "Invalid payment amount",
-        SHIFT_001: "You already have an open shift",
-        SHIFT_002: "Shift not found or already closed",
-        SHIFT_003: "Cash drawer has a discrepancy",
-        REF_001: "Cannot delete because it's being used elsewhere",
-        DB_001: "Database error occurred. Please try again",
-        DB_002: "Could not connect to database",
-        DB_003: "Operation took too long. Please try again",
-        SYS_001: "An unexpected error occurred. Please try again",
-        SYS_002: "Operation timed out. Please try again",
-        SYS_003: "You don't have permission to perform this action",
+        INVALID_CREDENTIALS: "Invalid username or password",
+        SESSION_EXPIRED: "Your session has expired. Please log in again",
+        SESSION_INVALID: "Invalid session. Please log in again",
+        RATE_LIMITED: "Too many login attempts. Please wait before trying again",
+        USER_INACTIVE: "Your account has been deactivated. Please contact support",
+        CONCURRENT_MODIFICATION: "This record was modified by another user. Please refresh and try again",
+        DATABASE_ERROR: "Database error occurred. Please try again",
+        INTERNAL_ERROR: "An unexpected error occurred. Please try again",
     };
 
     return errorMessages[error.code] || error.message;
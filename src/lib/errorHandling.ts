@@ -17,6 +17,21 @@ export interface AppError {
 export function parseError(error: unknown): AppError {
   // Handle Tauri errors
   if (typeof error === "string") {
+    // Commands still returning String errors serialize typed errors as JSON
+    try {
+      const parsed = JSON.parse(error);
+      if (parsed && parsed.code && parsed.message) {
+        return {
+          message: parsed.message,
+          code: parsed.code,
+          details: parsed,
+          isRetryable: isRetryableError(parsed.message),
+        };
+      }
+    } catch {
+      // Plain message
+    }
+
     return {
       message: error,
       isRetryable: isRetryableError(error),
@@ -3,7 +3,7 @@
  * Handles operations when offline and syncs when online
  */
 
-import { invoke } from '@tauri-apps/api/core';
+import { invoke } from '@/lib/api';
 
 export interface QueuedOperation {
   id: string;
@@ -1,5 +1,5 @@
 // Centralized Receipt Printer Utility
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { currencyFormatter } from "./currency";
 import { toast } from "sonner";
 import { formatLocalDateTime as formatLocalDateTimeUtil } from "./date-utils";
@@ -35,7 +35,7 @@ import { useCurrency } from "@/hooks/useCurrency";
 import { printReceipt } from "@/lib/receipt-printer";
 import { useAuthStore } from "@/store/authStore";
 import { useCartStore } from "@/store/cartStore";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import {
   Check,
   CheckCircle2,
@@ -1,7 +1,7 @@
 // src/pages/Customers.tsx - Customer Management with full CRUD
 import PageHeader from "@/components/PageHeader";
 import { useState, useEffect } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { Customer, CreateCustomerRequest, UpdateCustomerRequest } from "@/types";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
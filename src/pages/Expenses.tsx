@@ -1,7 +1,7 @@
 // src/pages/Expenses.tsx - Expense Management
 import PageHeader from "@/components/PageHeader";
 import { useState, useEffect } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { Expense, CreateExpenseRequest, UpdateExpenseRequest } from "@/types";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
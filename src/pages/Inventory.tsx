@@ -46,7 +46,7 @@ import { useCurrency } from "@/hooks/useCurrency";
 import { parseUTCDate } from "@/lib/date-utils";
 import { Product } from "@/types";
 import { useAuthStore } from "@/store/authStore";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { formatDistance } from "date-fns";
 import {
   AlertCircle,
@@ -6,7 +6,7 @@ import { Label } from "@/components/ui/label";
 import StoreLogo from "@/components/StoreLogo";
 import { useAuthStore } from "@/store/authStore";
 import type { User } from "@/types";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { Eye, EyeOff, Lock, User as UserIcon } from "lucide-react";
 import { useState } from "react";
 import { useNavigate } from "react-router-dom";
@@ -35,7 +35,7 @@ import {
   TabsTrigger,
 } from "@/components/ui/tabs";
 import { Textarea } from "@/components/ui/textarea";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { Edit, Layers, MoreHorizontal, Package, Palette, Plus, Ruler, Tag, Trash2 } from "lucide-react";
 import { useEffect, useState } from "react";
 import { ConfirmDialog } from '@/components/ConfirmDialog';
@@ -17,7 +17,7 @@ import {
 import { Skeleton } from "@/components/ui/skeleton";
 import { parseUTCDate } from "@/lib/date-utils";
 import { useAuthStore } from "@/store/authStore";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { formatDistance, format, isToday, isYesterday } from "date-fns";
 import {
   AlertTriangle,
@@ -50,6 +50,7 @@ interface Notification {
   message: string;
   severity: string;
   is_read: boolean;
+  is_archived: boolean;
   user_id?: number;
   reference_id?: number;
   reference_type?: string;
@@ -64,6 +65,11 @@ interface NotificationStats {
   system: number;
   invoice: number;
   debt: number;
+  error: number;
+  warning: number;
+  info: number;
+  success: number;
+  unread_error: number;
 }
 
 export default function Notifications() {
@@ -44,7 +44,7 @@ import {
   PaginationPrevious,
 } from "@/components/ui/pagination";
 import { useCurrency } from "@/hooks/useCurrency";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import {
   Package,
   Plus,
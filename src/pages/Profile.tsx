@@ -15,7 +15,7 @@ import { Badge } from "@/components/ui/badge";
 import { formatLocalDate } from "@/lib/date-utils";
 import { useAuthStore } from "@/store/authStore";
 import { User as UserType, UpdateProfileRequest } from "@/types";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { Camera, Info, Key, User, Upload, X, Save, Loader2 } from "lucide-react";
 import { useEffect, useState } from "react";
 import { useNavigate } from "react-router-dom";
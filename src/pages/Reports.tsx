@@ -49,7 +49,7 @@ import {
 } from "lucide-react";
 // import { useAuthStore } from "@/store/authStore";
 import { useCurrency } from "@/hooks/useCurrency";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { toast } from "sonner";
 import { format as formatDate, startOfWeek, startOfMonth, startOfQuarter, startOfYear } from "date-fns";
 import { AreaChart, Area, BarChart, Bar, PieChart, Pie, Cell, XAxis, YAxis, CartesianGrid, Tooltip, Legend, ResponsiveContainer } from "recharts";
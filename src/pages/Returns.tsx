@@ -15,7 +15,7 @@ import { Skeleton } from "@/components/ui/skeleton";
 import { Textarea } from "@/components/ui/textarea";
 import { useCurrency } from "@/hooks/useCurrency";
 import { useAuthStore } from "@/store/authStore";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { format as formatDate } from "date-fns";
 import { 
   ArrowRight, 
@@ -325,6 +325,11 @@ export default function Returns() {
       return;
     }
 
+    if (returnType === 'SalesReturn' && !referenceId) {
+      toast.error("Please select the original sale for sales returns");
+      return;
+    }
+
     if (returnType === 'SalesReturn' && !refundMethod) {
       toast.error("Please select a refund method");
       return;
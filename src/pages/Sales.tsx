@@ -39,7 +39,7 @@ import { SaleData, printReceipt } from "@/lib/receipt-printer";
 import { useAuthStore } from "@/store/authStore";
 import { useCartStore } from "@/store/cartStore";
 import { ProductWithStock, Sale } from "@/types";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import {
   Check,
   CheckCircle2,
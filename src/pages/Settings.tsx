@@ -24,7 +24,7 @@ import { useSettings } from "@/hooks/useSettings";
 import { useAuthStore } from "@/store/authStore";
 import { useStoreConfigStore } from "@/store/storeConfigStore";
 import { playSound, LANGUAGE_LABELS } from "@/store/settingsStore";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { convertFileSrc } from "@tauri-apps/api/core";
 import {
   Bell,
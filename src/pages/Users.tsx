@@ -65,7 +65,7 @@ import {
   Calendar,
   Users as UsersIcon,
 } from "lucide-react";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 import { toast } from "sonner";
 import { useAuthStore } from "@/store/authStore";
 
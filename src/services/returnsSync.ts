@@ -1,6 +1,6 @@
 // src/services/returnsSync.ts - Returns synchronization service
 
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/api";
 
 // Types for returns
 export interface ReturnPayload {
@@ -68,7 +68,6 @@ class ReturnsSyncService {
         reason: payload.reason || null,
         notes: payload.notes || null,
         attachments: payload.attachments || null,
-        user_id: payload.userId ? parseInt(payload.userId) : null,
         shift_id: payload.shiftId ? parseInt(payload.shiftId) : null,
       };
 
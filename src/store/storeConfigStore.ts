@@ -47,7 +47,7 @@ export const useStoreConfigStore = create<StoreConfigState>()(
         try {
           set({ loading: true });
           // Import invoke dynamically to avoid circular dependencies
-          const { invoke } = await import('@tauri-apps/api/core');
+          const { invoke } = await import('@/lib/api');
           const config = await invoke<StoreConfig>('get_store_config');
           set({ storeConfig: config });
         } catch (error) {
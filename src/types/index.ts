@@ -238,6 +238,7 @@ export interface Notification {
   message: string;
   severity: NotificationSeverity;
   is_read: boolean;
+  is_archived: boolean;
   user_id?: number;
   reference_id?: number;
   reference_type?: string;